@@ -1,7 +1,10 @@
+use super::agent_selection_policy::*;
 use super::*;
 use crate::error::*;
 use crate::mdns::*;
+use crate::network_monitor::*;
 use crate::network_type::*;
+use crate::proxy::ProxyDialer;
 use crate::udp_network::UDPNetwork;
 use crate::url::*;
 
@@ -16,6 +19,12 @@ pub(crate) const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_millis(200);
 /// The interval used to keep candidates alive.
 pub(crate) const DEFAULT_KEEPALIVE_INTERVAL: Duration = Duration::from_secs(2);
 
+/// The average interval at which consent to send is refreshed on the selected pair (RFC 7675).
+pub(crate) const DEFAULT_CONSENT_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The time after which consent to send expires when no consent check is answered (RFC 7675).
+pub(crate) const DEFAULT_CONSENT_TIMEOUT: Duration = Duration::from_secs(30);
+
 /// The default time till an Agent transitions disconnected.
 pub(crate) const DEFAULT_DISCONNECTED_TIMEOUT: Duration = Duration::from_secs(5);
 
@@ -34,6 +43,9 @@ pub(crate) const DEFAULT_PRFLX_ACCEPTANCE_MIN_WAIT: Duration = Duration::from_mi
 /// Wait time before nominating a relay candidate.
 pub(crate) const DEFAULT_RELAY_ACCEPTANCE_MIN_WAIT: Duration = Duration::from_millis(2000);
 
+/// The interval at which a continually gathering agent polls the network interfaces.
+pub(crate) const DEFAULT_NETWORK_MONITOR_INTERVAL: Duration = Duration::from_secs(2);
+
 /// Max binding request before considering a pair failed.
 pub(crate) const DEFAULT_MAX_BINDING_REQUESTS: u16 = 7;
 
@@ -95,6 +107,16 @@ pub struct AgentConfig {
     /// A keepalive interval of 0 means we never send keepalive packets
     pub keepalive_interval: Option<Duration>,
 
+    /// Determines how often consent to send on the selected pair is refreshed (RFC 7675). Each
+    /// request is randomized between 0.8 and 1.2 times the interval. Defaults to 5 seconds when
+    /// this property is nil.
+    pub consent_check_interval: Option<Duration>,
+
+    /// Determines how long the agent keeps sending on the selected pair after the last consent
+    /// check was answered. Once consent expires the pair and the agent go to failed and writes
+    /// on the connection fail. Defaults to 30 seconds when this property is nil.
+    pub consent_timeout: Option<Duration>,
+
     /// An optional configuration for disabling or enabling support for specific network types.
     pub network_types: Vec<NetworkType>,
 
@@ -152,6 +174,33 @@ pub struct AgentConfig {
     /// Controls if self-signed certificates are accepted when connecting to TURN servers via TLS or
     /// DTLS.
     pub insecure_skip_verify: bool,
+
+    /// Controls whether the agent keeps gathering host candidates when the network
+    /// interfaces change, for example when a mobile device switches from Wi-Fi to LTE.
+    /// Continual gathering is not supported with a muxed UDP network.
+    pub continual_gathering_policy: ContinualGatheringPolicy,
+
+    /// Reports the network interfaces when gathering continually. Defaults to the
+    /// interfaces of `net`.
+    pub network_monitor: Option<Arc<dyn NetworkMonitor + Send + Sync>>,
+
+    /// Determines how often the network interfaces are polled when gathering continually.
+    /// Defaults to 2 seconds when this property is nil.
+    pub network_monitor_interval: Option<Duration>,
+
+    /// Enables ICE renomination (`a=ice-options:renomination`). The controlling agent keeps
+    /// checking candidate pairs after one is selected and renominates a better one, and the
+    /// controlled agent follows the nomination with the highest NOMINATION value.
+    pub renomination: bool,
+
+    /// Decides when the controlling agent renominates another pair. Defaults to
+    /// `DefaultPairSelectionPolicy`.
+    pub pair_selection_policy: Option<Arc<dyn PairSelectionPolicy + Send + Sync>>,
+
+    /// Dials the TURN servers of `turn` and `turns` urls with `transport=tcp` through a
+    /// proxy, for example a `Socks5Dialer` or an `HttpConnectDialer`. They are dialed
+    /// directly when this property is nil.
+    pub proxy_dialer: Option<Arc<dyn ProxyDialer + Send + Sync>>,
 }
 
 impl AgentConfig {
@@ -205,6 +254,18 @@ impl AgentConfig {
             a.keepalive_interval = DEFAULT_KEEPALIVE_INTERVAL;
         }
 
+        if let Some(consent_check_interval) = self.consent_check_interval {
+            a.consent_check_interval = consent_check_interval;
+        } else {
+            a.consent_check_interval = DEFAULT_CONSENT_CHECK_INTERVAL;
+        }
+
+        if let Some(consent_timeout) = self.consent_timeout {
+            a.consent_timeout = consent_timeout;
+        } else {
+            a.consent_timeout = DEFAULT_CONSENT_TIMEOUT;
+        }
+
         if self.check_interval == Duration::from_secs(0) {
             a.check_interval = DEFAULT_CHECK_INTERVAL;
         } else {
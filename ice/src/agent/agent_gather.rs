@@ -1,6 +1,9 @@
 use super::*;
 use crate::error::*;
+use crate::network_monitor::*;
 use crate::network_type::*;
+use crate::proxy::stream_conn::StreamConn;
+use crate::proxy::{join_host_port, ProxyDialer};
 use crate::udp_network::UDPNetwork;
 use crate::url::{ProtoType, SchemeType, Url};
 use crate::util::*;
@@ -12,9 +15,11 @@ use crate::candidate::candidate_host::CandidateHostConfig;
 use crate::candidate::candidate_relay::CandidateRelayConfig;
 use crate::candidate::candidate_server_reflexive::CandidateServerReflexiveConfig;
 use crate::candidate::*;
-use std::net::{Ipv4Addr, Ipv6Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::str::FromStr;
 use std::sync::Arc;
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
 use waitgroup::WaitGroup;
 
 const STUN_GATHER_TIMEOUT: Duration = Duration::from_secs(5);
@@ -33,6 +38,11 @@ pub(crate) struct GatherCandidatesInternalParams {
     pub(crate) agent_internal: Arc<AgentInternal>,
     pub(crate) gathering_state: Arc<AtomicU8>,
     pub(crate) chan_candidate_tx: ChanCandidateTx,
+    pub(crate) continual_gathering_policy: ContinualGatheringPolicy,
+    pub(crate) network_monitor: Arc<dyn NetworkMonitor + Send + Sync>,
+    pub(crate) network_monitor_interval: Duration,
+    pub(crate) network_monitor_cancel_tx: Arc<SyncMutex<Option<mpsc::Sender<()>>>>,
+    pub(crate) proxy_dialer: Option<Arc<dyn ProxyDialer + Send + Sync>>,
 }
 
 struct GatherCandidatesLocalParams {
@@ -152,11 +162,13 @@ impl Agent {
                     let urls = params.urls.clone();
                     let net = Arc::clone(&params.net);
                     let agent_internal = Arc::clone(&params.agent_internal);
+                    let proxy_dialer = params.proxy_dialer.clone();
                     let w = wg.worker();
                     tokio::spawn(async move {
                         let _d = w;
 
-                        Self::gather_candidates_relay(urls, net, agent_internal).await;
+                        Self::gather_candidates_relay(urls, net, agent_internal, proxy_dialer)
+                            .await;
                     });
                 }
                 _ => {}
@@ -166,6 +178,19 @@ impl Agent {
         // Block until all STUN and TURN URLs have been gathered (or timed out)
         wg.wait().await;
 
+        if params.continual_gathering_policy == ContinualGatheringPolicy::GatherContinually
+            && params.candidate_types.contains(&CandidateType::Host)
+        {
+            if let UDPNetwork::Ephemeral(_) = params.udp_network {
+                Self::gather_candidates_continually(params).await;
+                return;
+            }
+            log::warn!(
+                "[{}]: continual gathering is not supported with a muxed UDP network",
+                params.agent_internal.get_name()
+            );
+        }
+
         Self::set_gathering_state(
             &params.chan_candidate_tx,
             &params.gathering_state,
@@ -174,6 +199,86 @@ impl Agent {
         .await;
     }
 
+    // Polls the network interfaces until continual gathering is stopped. Host
+    // candidates are gathered on new addresses, and the candidates of vanished
+    // addresses are removed.
+    async fn gather_candidates_continually(params: GatherCandidatesInternalParams) {
+        let (cancel_tx, mut cancel_rx) = mpsc::channel::<()>(1);
+        {
+            // The agent may have been closed while the initial candidates were
+            // gathered. Close stops continual gathering after it marked the agent
+            // done, so it takes any sender installed before this check.
+            let mut network_monitor_cancel_tx = params.network_monitor_cancel_tx.lock();
+            if params.agent_internal.agent_conn.done.load(Ordering::SeqCst) {
+                log::debug!(
+                    "[{}]: agent closed, continual gathering not started",
+                    params.agent_internal.get_name()
+                );
+                return;
+            }
+            *network_monitor_cancel_tx = Some(cancel_tx);
+        }
+
+        let mut known = local_interfaces(
+            &params.net,
+            &params.interface_filter,
+            &params.ip_filter,
+            &params.network_types,
+        )
+        .await;
+
+        loop {
+            // The interfaces are polled when the interval passes without a cancel.
+            if tokio::time::timeout(params.network_monitor_interval, cancel_rx.recv())
+                .await
+                .is_ok()
+            {
+                log::debug!(
+                    "[{}]: continual gathering stopped",
+                    params.agent_internal.get_name()
+                );
+                return;
+            }
+
+            let interfaces = params.network_monitor.interfaces().await;
+            let current = filter_local_ips(
+                &interfaces,
+                &params.interface_filter,
+                &params.ip_filter,
+                &params.network_types,
+            );
+
+            let (added, removed) = diff_ips(&known, &current);
+            for ip in removed {
+                log::info!(
+                    "[{}]: local address {} vanished, removing its candidates",
+                    params.agent_internal.get_name(),
+                    ip
+                );
+                params.agent_internal.remove_local_candidates(ip).await;
+            }
+            for ip in added {
+                log::info!(
+                    "[{}]: gathering candidates on new local address {}",
+                    params.agent_internal.get_name(),
+                    ip
+                );
+                Self::gather_candidate_local_ip(
+                    ip,
+                    &params.udp_network,
+                    params.mdns_mode,
+                    &params.mdns_name,
+                    &params.ext_ip_mapper,
+                    &params.net,
+                    &params.agent_internal,
+                )
+                .await;
+            }
+
+            known = current;
+        }
+    }
+
     async fn set_gathering_state(
         chan_candidate_tx: &ChanCandidateTx,
         gathering_state: &Arc<AtomicU8>,
@@ -227,142 +332,164 @@ impl Agent {
 
         let ips = local_interfaces(&net, &interface_filter, &ip_filter, &network_types).await;
         for ip in ips {
-            let mut mapped_ip = ip;
+            Self::gather_candidate_local_ip(
+                ip,
+                &udp_network,
+                mdns_mode,
+                &mdns_name,
+                &ext_ip_mapper,
+                &net,
+                &agent_internal,
+            )
+            .await;
+        }
+    }
 
-            if mdns_mode != MulticastDnsMode::QueryAndGather && ext_ip_mapper.is_some() {
-                if let Some(ext_ip_mapper2) = ext_ip_mapper.as_ref() {
-                    if ext_ip_mapper2.candidate_type == CandidateType::Host {
-                        if let Ok(mi) = ext_ip_mapper2.find_external_ip(&ip.to_string()) {
-                            mapped_ip = mi;
-                        } else {
-                            log::warn!(
-                                "[{}]: 1:1 NAT mapping is enabled but no external IP is found for {}",
-                                agent_internal.get_name(),
-                                ip
-                            );
-                        }
+    // Gathers a host candidate on the local address ip.
+    async fn gather_candidate_local_ip(
+        ip: IpAddr,
+        udp_network: &UDPNetwork,
+        mdns_mode: MulticastDnsMode,
+        mdns_name: &str,
+        ext_ip_mapper: &Arc<Option<ExternalIpMapper>>,
+        net: &Arc<Net>,
+        agent_internal: &Arc<AgentInternal>,
+    ) {
+        let mut mapped_ip = ip;
+
+        if mdns_mode != MulticastDnsMode::QueryAndGather && ext_ip_mapper.is_some() {
+            if let Some(ext_ip_mapper2) = ext_ip_mapper.as_ref() {
+                if ext_ip_mapper2.candidate_type == CandidateType::Host {
+                    if let Ok(mi) = ext_ip_mapper2.find_external_ip(&ip.to_string()) {
+                        mapped_ip = mi;
+                    } else {
+                        log::warn!(
+                            "[{}]: 1:1 NAT mapping is enabled but no external IP is found for {}",
+                            agent_internal.get_name(),
+                            ip
+                        );
                     }
                 }
             }
+        }
 
-            let address = if mdns_mode == MulticastDnsMode::QueryAndGather {
-                mdns_name.clone()
-            } else {
-                mapped_ip.to_string()
-            };
+        let address = if mdns_mode == MulticastDnsMode::QueryAndGather {
+            mdns_name.to_owned()
+        } else {
+            mapped_ip.to_string()
+        };
 
-            //TODO: for network in networks
-            let network = UDP.to_owned();
-            if let UDPNetwork::Ephemeral(ephemeral_config) = &udp_network {
-                /*TODO:switch network {
-                case tcp:
-                    // Handle ICE TCP passive mode
-
-                    a.log.Debugf("GetConn by ufrag: %s\n", a.localUfrag)
-                    conn, err = a.tcpMux.GetConnByUfrag(a.localUfrag)
-                    if err != nil {
-                        if !errors.Is(err, ErrTCPMuxNotInitialized) {
-                            a.log.Warnf("error getting tcp conn by ufrag: %s %s %s\n", network, ip, a.localUfrag)
-                        }
-                        continue
+        //TODO: for network in networks
+        let network = UDP.to_owned();
+        if let UDPNetwork::Ephemeral(ephemeral_config) = udp_network {
+            /*TODO:switch network {
+            case tcp:
+                // Handle ICE TCP passive mode
+
+                a.log.Debugf("GetConn by ufrag: %s\n", a.localUfrag)
+                conn, err = a.tcpMux.GetConnByUfrag(a.localUfrag)
+                if err != nil {
+                    if !errors.Is(err, ErrTCPMuxNotInitialized) {
+                        a.log.Warnf("error getting tcp conn by ufrag: %s %s %s\n", network, ip, a.localUfrag)
                     }
-                    port = conn.LocalAddr().(*net.TCPAddr).Port
-                    tcpType = TCPTypePassive
-                    // is there a way to verify that the listen address is even
-                    // accessible from the current interface.
-                case udp:*/
+                    continue
+                }
+                port = conn.LocalAddr().(*net.TCPAddr).Port
+                tcpType = TCPTypePassive
+                // is there a way to verify that the listen address is even
+                // accessible from the current interface.
+            case udp:*/
 
-                let conn: Arc<dyn Conn + Send + Sync> = match listen_udp_in_port_range(
-                    &net,
-                    ephemeral_config.port_max(),
-                    ephemeral_config.port_min(),
-                    SocketAddr::new(ip, 0),
-                )
-                .await
-                {
-                    Ok(conn) => conn,
-                    Err(err) => {
-                        log::warn!(
-                            "[{}]: could not listen {} {}: {}",
-                            agent_internal.get_name(),
-                            network,
-                            ip,
-                            err
-                        );
-                        continue;
-                    }
-                };
+            let conn: Arc<dyn Conn + Send + Sync> = match listen_udp_in_port_range(
+                net,
+                ephemeral_config.port_max(),
+                ephemeral_config.port_min(),
+                SocketAddr::new(ip, 0),
+            )
+            .await
+            {
+                Ok(conn) => conn,
+                Err(err) => {
+                    log::warn!(
+                        "[{}]: could not listen {} {}: {}",
+                        agent_internal.get_name(),
+                        network,
+                        ip,
+                        err
+                    );
+                    return;
+                }
+            };
 
-                let port = match conn.local_addr() {
-                    Ok(addr) => addr.port(),
-                    Err(err) => {
-                        log::warn!(
-                            "[{}]: could not get local addr: {}",
-                            agent_internal.get_name(),
-                            err
-                        );
-                        continue;
-                    }
-                };
+            let port = match conn.local_addr() {
+                Ok(addr) => addr.port(),
+                Err(err) => {
+                    log::warn!(
+                        "[{}]: could not get local addr: {}",
+                        agent_internal.get_name(),
+                        err
+                    );
+                    return;
+                }
+            };
 
-                let host_config = CandidateHostConfig {
-                    base_config: CandidateBaseConfig {
-                        network: network.clone(),
-                        address,
-                        port,
-                        component: COMPONENT_RTP,
-                        conn: Some(conn),
-                        ..CandidateBaseConfig::default()
-                    },
-                    ..CandidateHostConfig::default()
-                };
+            let host_config = CandidateHostConfig {
+                base_config: CandidateBaseConfig {
+                    network: network.clone(),
+                    address,
+                    port,
+                    component: COMPONENT_RTP,
+                    conn: Some(conn),
+                    ..CandidateBaseConfig::default()
+                },
+                ..CandidateHostConfig::default()
+            };
 
-                let candidate: Arc<dyn Candidate + Send + Sync> =
-                    match host_config.new_candidate_host() {
-                        Ok(candidate) => {
-                            if mdns_mode == MulticastDnsMode::QueryAndGather {
-                                if let Err(err) = candidate.set_ip(&ip) {
-                                    log::warn!(
-                                        "[{}]: Failed to create host candidate: {} {} {}: {:?}",
-                                        agent_internal.get_name(),
-                                        network,
-                                        mapped_ip,
-                                        port,
-                                        err
-                                    );
-                                    continue;
-                                }
-                            }
-                            Arc::new(candidate)
-                        }
-                        Err(err) => {
+            let candidate: Arc<dyn Candidate + Send + Sync> = match host_config.new_candidate_host()
+            {
+                Ok(candidate) => {
+                    if mdns_mode == MulticastDnsMode::QueryAndGather {
+                        if let Err(err) = candidate.set_ip(&ip) {
                             log::warn!(
-                                "[{}]: Failed to create host candidate: {} {} {}: {}",
+                                "[{}]: Failed to create host candidate: {} {} {}: {:?}",
                                 agent_internal.get_name(),
                                 network,
                                 mapped_ip,
                                 port,
                                 err
                             );
-                            continue;
+                            return;
                         }
-                    };
+                    }
+                    Arc::new(candidate)
+                }
+                Err(err) => {
+                    log::warn!(
+                        "[{}]: Failed to create host candidate: {} {} {}: {}",
+                        agent_internal.get_name(),
+                        network,
+                        mapped_ip,
+                        port,
+                        err
+                    );
+                    return;
+                }
+            };
 
-                {
-                    if let Err(err) = agent_internal.add_candidate(&candidate).await {
-                        if let Err(close_err) = candidate.close().await {
-                            log::warn!(
-                                "[{}]: Failed to close candidate: {}",
-                                agent_internal.get_name(),
-                                close_err
-                            );
-                        }
+            {
+                if let Err(err) = agent_internal.add_candidate(&candidate).await {
+                    if let Err(close_err) = candidate.close().await {
                         log::warn!(
-                            "[{}]: Failed to append to localCandidates and run onCandidateHdlr: {}",
+                            "[{}]: Failed to close candidate: {}",
                             agent_internal.get_name(),
-                            err
+                            close_err
                         );
                     }
+                    log::warn!(
+                        "[{}]: Failed to append to localCandidates and run onCandidateHdlr: {}",
+                        agent_internal.get_name(),
+                        err
+                    );
                 }
             }
         }
@@ -391,33 +518,27 @@ impl Agent {
         let local_ips =
             local_interfaces(&net, &interface_filter, &ip_filter, &relevant_network_types).await;
 
-        let candidate_ip = ext_ip_mapper
-            .as_ref() // Arc
-            .as_ref() // Option
-            .and_then(|mapper| {
-                if mapper.candidate_type != CandidateType::Host {
-                    return None;
-                }
+        let map_ip = |ip: IpAddr| {
+            ext_ip_mapper
+                .as_ref() // Arc
+                .as_ref() // Option
+                .and_then(|mapper| {
+                    if mapper.candidate_type != CandidateType::Host {
+                        return None;
+                    }
 
-                local_ips
-                    .iter()
-                    .find_map(|ip| match mapper.find_external_ip(&ip.to_string()) {
+                    match mapper.find_external_ip(&ip.to_string()) {
                         Ok(ip) => Some(ip),
                         Err(err) => {
                             log::warn!(
-                            "1:1 NAT mapping is enabled but not external IP is found for {}: {}",
-                            ip,
-                            err
-                        );
+                                "1:1 NAT mapping is enabled but not external IP is found for {}: {}",
+                                ip,
+                                err
+                            );
                             None
                         }
-                    })
-            })
-            .or_else(|| local_ips.iter().copied().next());
-
-        let candidate_ip = match candidate_ip {
-            None => return Err(Error::ErrCandidateIpNotFound),
-            Some(ip) => ip,
+                    }
+                })
         };
 
         let ufrag = {
@@ -426,25 +547,47 @@ impl Agent {
             ufrag_pwd.local_ufrag.clone()
         };
 
-        let conn = udp_mux.get_conn(&ufrag).await?;
-        let port = conn.local_addr()?.port();
-
-        let host_config = CandidateHostConfig {
-            base_config: CandidateBaseConfig {
-                network: UDP.to_owned(),
-                address: candidate_ip.to_string(),
-                port,
-                conn: Some(conn),
-                component: COMPONENT_RTP,
-                ..Default::default()
-            },
-            tcp_type: TcpType::Unspecified,
-        };
+        // A mux listens on the unspecified address, or on a socket per local address.
+        let conns = udp_mux.get_all_conns(&ufrag).await?;
+        for conn in conns {
+            let local_addr = conn.local_addr()?;
+
+            let candidate_ip = if local_addr.ip().is_unspecified() {
+                local_ips
+                    .iter()
+                    .find_map(|ip| map_ip(*ip))
+                    .or_else(|| local_ips.iter().copied().next())
+            } else if relevant_network_types
+                .iter()
+                .any(|n| n.is_ipv4() == local_addr.is_ipv4())
+            {
+                map_ip(local_addr.ip()).or(Some(local_addr.ip()))
+            } else {
+                continue;
+            };
+
+            let candidate_ip = match candidate_ip {
+                None => return Err(Error::ErrCandidateIpNotFound),
+                Some(ip) => ip,
+            };
+
+            let host_config = CandidateHostConfig {
+                base_config: CandidateBaseConfig {
+                    network: UDP.to_owned(),
+                    address: candidate_ip.to_string(),
+                    port: local_addr.port(),
+                    conn: Some(conn),
+                    component: COMPONENT_RTP,
+                    ..Default::default()
+                },
+                tcp_type: TcpType::Unspecified,
+            };
 
-        let candidate: Arc<dyn Candidate + Send + Sync> =
-            Arc::new(host_config.new_candidate_host()?);
+            let candidate: Arc<dyn Candidate + Send + Sync> =
+                Arc::new(host_config.new_candidate_host()?);
 
-        agent_internal.add_candidate(&candidate).await?;
+            agent_internal.add_candidate(&candidate).await?;
+        }
 
         Ok(())
     }
@@ -717,6 +860,7 @@ impl Agent {
         urls: Vec<Url>,
         net: Arc<Net>,
         agent_internal: Arc<AgentInternal>,
+        proxy_dialer: Option<Arc<dyn ProxyDialer + Send + Sync>>,
     ) {
         let wg = WaitGroup::new();
 
@@ -744,6 +888,7 @@ impl Agent {
             let network = NetworkType::Udp4.to_string();
             let net2 = Arc::clone(&net);
             let agent_internal2 = Arc::clone(&agent_internal);
+            let proxy_dialer2 = proxy_dialer.clone();
 
             let w = wg.worker();
             tokio::spawn(async move {
@@ -769,10 +914,31 @@ impl Agent {
                         let rel_addr = local_addr.ip().to_string();
                         let rel_port = local_addr.port();
                         (loc_conn, rel_addr, rel_port)
-                    /*TODO: case url.proto == ProtoType::UDP && url.scheme == SchemeType::TURNS{
-                    case a.proxyDialer != nil && url.Proto == ProtoTypeTCP && (url.Scheme == SchemeTypeTURN || url.Scheme == SchemeTypeTURNS):
-                    case url.Proto == ProtoTypeTCP && url.Scheme == SchemeTypeTURN:
-                    case url.Proto == ProtoTypeTCP && url.Scheme == SchemeTypeTURNS:*/
+                    /*TODO: case url.proto == ProtoType::UDP && url.scheme == SchemeType::TURNS{*/
+                    } else if url.proto == ProtoType::Tcp {
+                        let loc_conn = match Self::dial_turn_server_tcp(
+                            &url,
+                            &proxy_dialer2,
+                            agent_internal2.insecure_skip_verify,
+                        )
+                        .await
+                        {
+                            Ok(c) => c,
+                            Err(err) => {
+                                log::warn!(
+                                    "[{}]: Failed to dial TURN server {}: {}",
+                                    agent_internal2.get_name(),
+                                    turn_server_addr,
+                                    err
+                                );
+                                return Ok(());
+                            }
+                        };
+
+                        let local_addr = loc_conn.local_addr()?;
+                        let rel_addr = local_addr.ip().to_string();
+                        let rel_port = local_addr.port();
+                        (loc_conn, rel_addr, rel_port)
                     } else {
                         log::warn!(
                             "[{}]: Unable to handle URL in gather_candidates_relay {}",
@@ -788,6 +954,7 @@ impl Agent {
                     username: url.username,
                     password: url.password,
                     realm: String::new(),
+                    oauth_credential: url.oauth_credential,
                     software: String::new(),
                     rto_in_ms: 0,
                     conn: loc_conn,
@@ -884,4 +1051,61 @@ impl Agent {
 
         wg.wait().await;
     }
+
+    // Connects to the TURN server of a url with `transport=tcp`, through the proxy if
+    // there is one, and secures the connection with TLS for `turns` urls. The host of
+    // a `turns` url must be a name, which the certificate of the server is verified for.
+    pub(crate) async fn dial_turn_server_tcp(
+        url: &Url,
+        proxy_dialer: &Option<Arc<dyn ProxyDialer + Send + Sync>>,
+        insecure_skip_verify: bool,
+    ) -> Result<Arc<dyn Conn + Send + Sync>> {
+        if url.scheme == SchemeType::Turns && url.host.parse::<IpAddr>().is_ok() {
+            return Err(Error::ErrTurnsIpAddressHost);
+        }
+
+        let turn_server_addr = join_host_port(&url.host, url.port);
+        let stream = if let Some(proxy_dialer) = proxy_dialer {
+            proxy_dialer.dial(&turn_server_addr).await?
+        } else {
+            TcpStream::connect(&turn_server_addr).await?
+        };
+        let local_addr = stream.local_addr()?;
+        let remote_addr = stream.peer_addr()?;
+
+        if url.scheme == SchemeType::Turns {
+            let mut config = rustls::ClientConfig::new();
+            config
+                .root_store
+                .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+            if insecure_skip_verify {
+                config
+                    .dangerous()
+                    .set_certificate_verifier(Arc::new(InsecureServerCertVerifier));
+            }
+            let dns_name = webpki::DNSNameRef::try_from_ascii_str(&url.host)
+                .map_err(|err| Error::Other(err.to_string()))?;
+            let stream = TlsConnector::from(Arc::new(config))
+                .connect(dns_name, stream)
+                .await?;
+            Ok(Arc::new(StreamConn::new(stream, local_addr, remote_addr)))
+        } else {
+            Ok(Arc::new(StreamConn::new(stream, local_addr, remote_addr)))
+        }
+    }
+}
+
+// Accepts any certificate of a TURN server, when `insecure_skip_verify` is set.
+struct InsecureServerCertVerifier;
+
+impl rustls::ServerCertVerifier for InsecureServerCertVerifier {
+    fn verify_server_cert(
+        &self,
+        _roots: &rustls::RootCertStore,
+        _presented_certs: &[rustls::Certificate],
+        _dns_name: webpki::DNSNameRef<'_>,
+        _ocsp_response: &[u8],
+    ) -> std::result::Result<rustls::ServerCertVerified, rustls::TLSError> {
+        Ok(rustls::ServerCertVerified::assertion())
+    }
 }
@@ -1,6 +1,6 @@
 use super::agent_vnet_test::*;
 use super::*;
-use crate::udp_mux::{UDPMuxDefault, UDPMuxParams};
+use crate::udp_mux::{MultiUDPMuxDefault, UDPMuxDefault, UDPMuxParams};
 use crate::util::*;
 
 use ipnet::IpNet;
@@ -382,6 +382,7 @@ async fn test_vnet_gather_turn_connection_leak() -> Result<()> {
         port: VNET_STUN_SERVER_PORT,
         username: "user".to_owned(),
         password: "pass".to_owned(),
+        oauth_credential: None,
         proto: ProtoType::Udp,
     };
 
@@ -411,6 +412,7 @@ async fn test_vnet_gather_turn_connection_leak() -> Result<()> {
             vec![turn_server_url.clone()],
             Arc::clone(&v.net0),
             agent_internal,
+            None,
         )
         .await;
     }
@@ -487,3 +489,205 @@ async fn test_vnet_gather_muxed_udp() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_gather_multi_udp_mux() -> Result<()> {
+    let mut muxes = vec![];
+    for addr in ["127.0.0.1:0", "127.0.0.1:0", "[::1]:0"] {
+        let udp_socket = UdpSocket::bind(addr).await?;
+        muxes.push(UDPMuxDefault::new(UDPMuxParams::new(udp_socket)));
+    }
+    let udp_mux = MultiUDPMuxDefault::new(muxes);
+
+    let a = Agent::new(AgentConfig {
+        network_types: vec![NetworkType::Udp4, NetworkType::Udp6],
+        candidate_types: vec![CandidateType::Host],
+        multicast_dns_mode: MulticastDnsMode::Disabled,
+        udp_network: UDPNetwork::Muxed(Arc::clone(&udp_mux) as Arc<dyn UDPMux + Send + Sync>),
+        ..Default::default()
+    })
+    .await?;
+
+    let (done_tx, mut done_rx) = mpsc::channel::<()>(1);
+    let done_tx = Arc::new(Mutex::new(Some(done_tx)));
+    a.on_candidate(Box::new(
+        move |c: Option<Arc<dyn Candidate + Send + Sync>>| {
+            let done_tx_clone = Arc::clone(&done_tx);
+            Box::pin(async move {
+                if c.is_none() {
+                    let mut tx = done_tx_clone.lock().await;
+                    tx.take();
+                }
+            })
+        },
+    ));
+
+    a.gather_candidates()?;
+    let _ = done_rx.recv().await;
+
+    let mut addresses: Vec<String> = a
+        .get_local_candidates()
+        .await?
+        .iter()
+        .map(|c| {
+            assert_eq!(c.port(), c.get_conn().unwrap().local_addr().unwrap().port());
+            c.address()
+        })
+        .collect();
+    addresses.sort();
+    assert_eq!(
+        addresses,
+        vec!["127.0.0.1".to_owned(), "::1".to_owned()],
+        "There must be a candidate for each address of the mux"
+    );
+
+    a.close().await?;
+    udp_mux.close().await?;
+
+    Ok(())
+}
+
+// Reports the interfaces that a test sets, to simulate network changes.
+struct TestNetworkMonitor {
+    interfaces: std::sync::Mutex<Vec<util::vnet::interface::Interface>>,
+    polls: AtomicUsize,
+}
+
+#[async_trait::async_trait]
+impl NetworkMonitor for TestNetworkMonitor {
+    async fn interfaces(&self) -> Vec<util::vnet::interface::Interface> {
+        self.polls.fetch_add(1, Ordering::SeqCst);
+        self.interfaces.lock().unwrap().clone()
+    }
+}
+
+#[tokio::test]
+async fn test_vnet_gather_continually() -> Result<()> {
+    let r = Arc::new(Mutex::new(router::Router::new(router::RouterConfig {
+        cidr: "1.2.3.0/24".to_owned(),
+        ..Default::default()
+    })?));
+    let nw = Arc::new(net::Net::new(Some(net::NetConfig::default())));
+    connect_net2router(&nw, &r).await?;
+
+    let monitor = Arc::new(TestNetworkMonitor {
+        interfaces: std::sync::Mutex::new(nw.get_interfaces().await),
+        polls: AtomicUsize::new(0),
+    });
+    let a = Agent::new(AgentConfig {
+        net: Some(Arc::clone(&nw)),
+        network_types: vec![NetworkType::Udp4],
+        candidate_types: vec![CandidateType::Host],
+        continual_gathering_policy: ContinualGatheringPolicy::GatherContinually,
+        network_monitor: Some(Arc::clone(&monitor) as Arc<dyn NetworkMonitor + Send + Sync>),
+        network_monitor_interval: Some(Duration::from_millis(50)),
+        ..Default::default()
+    })
+    .await?;
+
+    let (cand_tx, mut cand_rx) = mpsc::channel(8);
+    a.on_candidate(Box::new(
+        move |c: Option<Arc<dyn Candidate + Send + Sync>>| {
+            let cand_tx = cand_tx.clone();
+            Box::pin(async move {
+                let _ = cand_tx.send(c).await;
+            })
+        },
+    ));
+
+    a.gather_candidates()?;
+
+    let first = tokio::time::timeout(Duration::from_secs(5), cand_rx.recv())
+        .await
+        .expect("should gather a host candidate")
+        .flatten()
+        .expect("should not complete gathering");
+    let ip = first.addr().ip();
+
+    // The interface goes away: its candidate is removed.
+    let interfaces = monitor
+        .interfaces
+        .lock()
+        .unwrap()
+        .drain(..)
+        .collect::<Vec<_>>();
+    let mut removed = false;
+    for _ in 0..40 {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        if a.get_local_candidates().await?.is_empty() {
+            removed = true;
+            break;
+        }
+    }
+    assert!(
+        removed,
+        "candidate of the vanished address should be removed"
+    );
+
+    // The interface comes back: a new candidate is gathered on it.
+    *monitor.interfaces.lock().unwrap() = interfaces;
+    let second = tokio::time::timeout(Duration::from_secs(5), cand_rx.recv())
+        .await
+        .expect("should gather a new host candidate")
+        .flatten()
+        .expect("should not complete gathering");
+    assert_eq!(second.addr().ip(), ip);
+    assert_eq!(a.get_local_candidates().await?.len(), 1);
+
+    assert!(
+        GatheringState::from(a.gathering_state.load(Ordering::SeqCst)) == GatheringState::Gathering,
+        "continual gathering should not complete"
+    );
+
+    // A restart stops continual gathering.
+    a.restart(String::new(), String::new()).await?;
+    assert!(GatheringState::from(a.gathering_state.load(Ordering::SeqCst)) == GatheringState::New);
+
+    a.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_vnet_close_while_gathering_continually() -> Result<()> {
+    let r = Arc::new(Mutex::new(router::Router::new(router::RouterConfig {
+        cidr: "1.2.3.0/24".to_owned(),
+        ..Default::default()
+    })?));
+    let nw = Arc::new(net::Net::new(Some(net::NetConfig::default())));
+    connect_net2router(&nw, &r).await?;
+
+    let monitor = Arc::new(TestNetworkMonitor {
+        interfaces: std::sync::Mutex::new(nw.get_interfaces().await),
+        polls: AtomicUsize::new(0),
+    });
+    let a = Agent::new(AgentConfig {
+        net: Some(Arc::clone(&nw)),
+        network_types: vec![NetworkType::Udp4],
+        candidate_types: vec![CandidateType::Host],
+        continual_gathering_policy: ContinualGatheringPolicy::GatherContinually,
+        network_monitor: Some(Arc::clone(&monitor) as Arc<dyn NetworkMonitor + Send + Sync>),
+        network_monitor_interval: Some(Duration::from_millis(20)),
+        ..Default::default()
+    })
+    .await?;
+    a.on_candidate(Box::new(|_: Option<Arc<dyn Candidate + Send + Sync>>| {
+        Box::pin(async {})
+    }));
+
+    // Closed before the initial candidates are gathered, so before continual
+    // gathering starts.
+    a.gather_candidates()?;
+    a.close().await?;
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let polls = monitor.polls.load(Ordering::SeqCst);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(
+        monitor.polls.load(Ordering::SeqCst),
+        polls,
+        "the network should not be monitored after close"
+    );
+
+    Ok(())
+}
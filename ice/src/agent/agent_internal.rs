@@ -1,10 +1,12 @@
+use super::agent_selection_policy::*;
 use super::agent_transport::*;
 use super::*;
 use crate::candidate::candidate_base::CandidateBaseConfig;
 use crate::candidate::candidate_peer_reflexive::CandidatePeerReflexiveConfig;
 use crate::util::*;
 use arc_swap::ArcSwapOption;
-use std::sync::atomic::{AtomicBool, AtomicU64};
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64};
 use util::sync::Mutex as SyncMutex;
 
 pub type ChanCandidateTx =
@@ -45,6 +47,9 @@ pub struct AgentInternal {
 
     pub(crate) start_time: SyncMutex<Instant>,
     pub(crate) nominated_pair: Mutex<Option<Arc<CandidatePair>>>,
+    // The NOMINATION value of our latest nomination when renominating, or of the remote's
+    // latest nomination when controlled
+    pub(crate) nomination: AtomicU32,
 
     pub(crate) connection_state: AtomicU8, //ConnectionState,
 
@@ -63,6 +68,8 @@ pub struct AgentInternal {
 
     // the following variables won't be changed after init_with_defaults()
     pub(crate) insecure_skip_verify: bool,
+    pub(crate) renomination: AtomicBool,
+    pub(crate) pair_selection_policy: Arc<dyn PairSelectionPolicy + Send + Sync>,
     pub(crate) max_binding_requests: u16,
     pub(crate) host_acceptance_min_wait: Duration,
     pub(crate) srflx_acceptance_min_wait: Duration,
@@ -77,6 +84,10 @@ pub struct AgentInternal {
     // How often should we send keepalive packets?
     // 0 means never
     pub(crate) keepalive_interval: Duration,
+    // How often consent to send on the selected pair is refreshed
+    pub(crate) consent_check_interval: Duration,
+    // How long consent to send lasts without being refreshed
+    pub(crate) consent_timeout: Duration,
     // How often should we run our internal taskLoop to check for state changes when connecting
     pub(crate) check_interval: Duration,
 }
@@ -116,10 +127,16 @@ impl AgentInternal {
 
             start_time: SyncMutex::new(Instant::now()),
             nominated_pair: Mutex::new(None),
+            nomination: AtomicU32::new(0),
 
             connection_state: AtomicU8::new(ConnectionState::New as u8),
 
             insecure_skip_verify: config.insecure_skip_verify,
+            renomination: AtomicBool::new(config.renomination),
+            pair_selection_policy: config
+                .pair_selection_policy
+                .clone()
+                .unwrap_or_else(|| Arc::new(DefaultPairSelectionPolicy::default())),
 
             started_ch_tx: Mutex::new(Some(started_ch_tx)),
 
@@ -142,6 +159,9 @@ impl AgentInternal {
             // 0 means never
             keepalive_interval: Duration::from_secs(0),
 
+            consent_check_interval: Duration::from_secs(0),
+            consent_timeout: Duration::from_secs(0),
+
             // How often should we run our internal taskLoop to check for state changes when connecting
             check_interval: Duration::from_secs(0),
 
@@ -240,9 +260,16 @@ impl AgentInternal {
         const ZERO_DURATION: Duration = Duration::from_secs(0);
         let mut last_connection_state = ConnectionState::Unspecified;
         let mut checking_duration = Instant::now();
-        let (check_interval, keepalive_interval, disconnected_timeout, failed_timeout) = (
+        let (
+            check_interval,
+            keepalive_interval,
+            consent_check_interval,
+            disconnected_timeout,
+            failed_timeout,
+        ) = (
             self.check_interval,
             self.keepalive_interval,
+            self.consent_check_interval,
             self.disconnected_timeout,
             self.failed_timeout,
         );
@@ -274,6 +301,7 @@ impl AgentInternal {
                         }
                         ConnectionState::Connected | ConnectionState::Disconnected => {
                             update_interval(keepalive_interval);
+                            update_interval(consent_check_interval);
                         }
                         _ => {}
                     };
@@ -334,7 +362,17 @@ impl AgentInternal {
         );
 
         if let Some(p) = p {
+            if let Some(previous) = self.agent_conn.get_selected_pair() {
+                if !Arc::ptr_eq(&previous, &p) {
+                    previous.nominated.store(false, Ordering::SeqCst);
+                }
+            }
             p.nominated.store(true, Ordering::SeqCst);
+            // The remote granted consent when the pair was validated or nominated
+            p.checks
+                .lock()
+                .consent_granted
+                .get_or_insert_with(Instant::now);
             self.agent_conn.selected_pair.store(Some(p));
 
             self.update_connection_state(ConnectionState::Connected)
@@ -474,39 +512,65 @@ impl AgentInternal {
         valid
     }
 
-    /// Sends STUN Binding Indications to the selected pair.
-    /// if no packet has been sent on that pair in the last keepaliveInterval.
+    /// Sends STUN Binding requests to the selected pair, to refresh the consent to send on it
+    /// (RFC 7675) and if no packet has been sent on that pair in the last keepaliveInterval.
+    /// Once consent expires the selected pair and the agent go to failed.
     /// Note: the caller should hold the agent lock.
     pub(crate) async fn check_keepalive(&self) {
-        let (local, remote) = {
-            let selected_pair = self.agent_conn.selected_pair.load();
-            (*selected_pair)
-                .as_ref()
-                .map_or((None, None), |selected_pair| {
-                    (
-                        Some(selected_pair.local.clone()),
-                        Some(selected_pair.remote.clone()),
-                    )
-                })
+        let selected_pair = match self.agent_conn.get_selected_pair() {
+            Some(selected_pair) => selected_pair,
+            None => return,
+        };
+        let (local, remote) = (&selected_pair.local, &selected_pair.remote);
+
+        let now = Instant::now();
+        if let Some(consent_expiry) = selected_pair.consent_expiry(self.consent_timeout) {
+            if !self.lite.load(Ordering::SeqCst) && now >= consent_expiry {
+                log::warn!(
+                    "[{}]: consent to send on {} expired",
+                    self.get_name(),
+                    selected_pair
+                );
+                selected_pair.checks.lock().consent_expired = Some(now);
+                selected_pair
+                    .state
+                    .store(CandidatePairState::Failed as u8, Ordering::SeqCst);
+                self.update_connection_state(ConnectionState::Failed).await;
+                return;
+            }
+        }
+
+        let consent_check_due = {
+            let mut checks = selected_pair.checks.lock();
+            let due = checks
+                .next_consent_request
+                .map_or(true, |next_consent_request| now >= next_consent_request);
+            if due {
+                // Randomize the interval to avoid synchronized checks, RFC 7675 Section 5.1
+                let jitter = rand::Rng::gen_range(&mut rand::thread_rng(), 0.8..1.2);
+                checks.next_consent_request =
+                    Some(now + self.consent_check_interval.mul_f64(jitter));
+                checks.consent_requests_sent += 1;
+            }
+            due
         };
 
-        if let (Some(local), Some(remote)) = (local, remote) {
-            let last_sent = SystemTime::now()
-                .duration_since(local.last_sent())
-                .unwrap_or_else(|_| Duration::from_secs(0));
+        let last_sent = SystemTime::now()
+            .duration_since(local.last_sent())
+            .unwrap_or_else(|_| Duration::from_secs(0));
 
-            let last_received = SystemTime::now()
-                .duration_since(remote.last_received())
-                .unwrap_or_else(|_| Duration::from_secs(0));
+        let last_received = SystemTime::now()
+            .duration_since(remote.last_received())
+            .unwrap_or_else(|_| Duration::from_secs(0));
 
-            if (self.keepalive_interval != Duration::from_secs(0))
+        if consent_check_due
+            || ((self.keepalive_interval != Duration::from_secs(0))
                 && ((last_sent > self.keepalive_interval)
-                    || (last_received > self.keepalive_interval))
-            {
-                // we use binding request instead of indication to support refresh consent schemas
-                // see https://tools.ietf.org/html/rfc7675
-                self.ping_candidate(&local, &remote).await;
-            }
+                    || (last_received > self.keepalive_interval)))
+        {
+            // we use binding request instead of indication to support refresh consent schemas
+            // see https://tools.ietf.org/html/rfc7675
+            self.ping_candidate(local, remote).await;
         }
     }
 
@@ -610,6 +674,76 @@ impl AgentInternal {
         Ok(())
     }
 
+    /// Removes the local candidates whose sockets are bound to ip, which vanished from the
+    /// host, with their candidate pairs. If the selected pair used one of them, the agent
+    /// goes back to checking to select another pair.
+    pub(crate) async fn remove_local_candidates(&self, ip: IpAddr) {
+        let mut removed = vec![];
+        {
+            let mut local_candidates = self.local_candidates.lock().await;
+            for cands in local_candidates.values_mut() {
+                cands.retain(|c| {
+                    let bound_ip = c
+                        .get_conn()
+                        .and_then(|conn| conn.local_addr().ok())
+                        .map(|addr| addr.ip());
+                    if bound_ip == Some(ip) {
+                        removed.push(c.clone());
+                        false
+                    } else {
+                        true
+                    }
+                });
+            }
+        }
+        if removed.is_empty() {
+            return;
+        }
+
+        let is_removed =
+            |c: &Arc<dyn Candidate + Send + Sync>| removed.iter().any(|r| r.equal(&**c));
+        {
+            let mut checklist = self.agent_conn.checklist.lock().await;
+            checklist.retain(|p| !is_removed(&p.local));
+        }
+        {
+            let mut nominated_pair = self.nominated_pair.lock().await;
+            if nominated_pair
+                .as_ref()
+                .map_or(false, |p| is_removed(&p.local))
+            {
+                *nominated_pair = None;
+            }
+        }
+        let selected_pair_removed = self
+            .agent_conn
+            .get_selected_pair()
+            .map_or(false, |p| is_removed(&p.local));
+
+        for c in &removed {
+            if let Err(err) = c.close().await {
+                log::warn!(
+                    "[{}]: Failed to close candidate {}: {}",
+                    self.get_name(),
+                    c,
+                    err
+                );
+            }
+        }
+
+        if selected_pair_removed {
+            log::info!(
+                "[{}]: selected candidate pair was removed, checking again",
+                self.get_name()
+            );
+            self.set_selected_pair(None).await;
+            self.update_connection_state(ConnectionState::Checking)
+                .await;
+        }
+
+        self.request_connectivity_check();
+    }
+
     pub(crate) async fn close(&self) -> Result<()> {
         {
             let mut done_tx = self.done_tx.lock().await;
@@ -728,6 +862,9 @@ impl AgentInternal {
                 is_use_candidate: m.contains(ATTR_USE_CANDIDATE),
             });
         }
+        if let Some(p) = self.find_pair(local, remote).await {
+            p.on_request_sent();
+        }
 
         self.send_stun(m, local, remote).await;
     }
@@ -0,0 +1,78 @@
+use crate::candidate::{CandidatePair, CandidateType};
+
+use std::time::Duration;
+
+/// The RTT improvement the default policy requires before it switches between direct pairs.
+pub const DEFAULT_MIN_RTT_IMPROVEMENT: Duration = Duration::from_millis(20);
+
+/// Decides whether a controlling agent with renomination enabled moves traffic off the selected
+/// candidate pair. It is consulted with every other valid pair while a pair is selected.
+pub trait PairSelectionPolicy {
+    /// Returns true if the agent should renominate `candidate`, a valid pair, in place of
+    /// `selected`.
+    fn should_switch(&self, selected: &CandidatePair, candidate: &CandidatePair) -> bool;
+}
+
+/// Switches from a relayed pair to a direct one, then between direct pairs to one whose round
+/// trip time is at least `min_rtt_improvement` lower. It never switches to a relayed pair.
+#[derive(Debug, Clone, Copy)]
+pub struct DefaultPairSelectionPolicy {
+    pub min_rtt_improvement: Duration,
+}
+
+impl Default for DefaultPairSelectionPolicy {
+    fn default() -> Self {
+        Self {
+            min_rtt_improvement: DEFAULT_MIN_RTT_IMPROVEMENT,
+        }
+    }
+}
+
+impl PairSelectionPolicy for DefaultPairSelectionPolicy {
+    fn should_switch(&self, selected: &CandidatePair, candidate: &CandidatePair) -> bool {
+        match (is_relayed(selected), is_relayed(candidate)) {
+            (true, false) => true,
+            (false, true) => false,
+            _ => LowerRttPolicy {
+                min_rtt_improvement: self.min_rtt_improvement,
+            }
+            .should_switch(selected, candidate),
+        }
+    }
+}
+
+/// Switches from a relayed pair to any direct pair.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NonRelayPolicy;
+
+impl PairSelectionPolicy for NonRelayPolicy {
+    fn should_switch(&self, selected: &CandidatePair, candidate: &CandidatePair) -> bool {
+        is_relayed(selected) && !is_relayed(candidate)
+    }
+}
+
+/// Switches to a pair whose round trip time is at least `min_rtt_improvement` lower than the
+/// one of the selected pair. Pairs without a measured round trip time are never switched to.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LowerRttPolicy {
+    pub min_rtt_improvement: Duration,
+}
+
+impl PairSelectionPolicy for LowerRttPolicy {
+    fn should_switch(&self, selected: &CandidatePair, candidate: &CandidatePair) -> bool {
+        match (
+            selected.current_round_trip_time(),
+            candidate.current_round_trip_time(),
+        ) {
+            (Some(selected_rtt), Some(candidate_rtt)) => {
+                candidate_rtt + self.min_rtt_improvement < selected_rtt
+            }
+            _ => false,
+        }
+    }
+}
+
+fn is_relayed(p: &CandidatePair) -> bool {
+    p.local.candidate_type() == CandidateType::Relay
+        || p.remote.candidate_type() == CandidateType::Relay
+}
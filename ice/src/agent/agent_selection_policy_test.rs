@@ -0,0 +1,61 @@
+use super::agent_selection_policy::*;
+use super::*;
+use crate::candidate::candidate_base::CandidateBase;
+
+fn pair(local_type: CandidateType, rtt: Option<Duration>) -> CandidatePair {
+    let p = CandidatePair::new(
+        Arc::new(CandidateBase {
+            candidate_type: local_type,
+            ..Default::default()
+        }),
+        Arc::new(CandidateBase {
+            candidate_type: CandidateType::Host,
+            ..Default::default()
+        }),
+        true,
+    );
+    if let Some(rtt) = rtt {
+        p.on_response_received(rtt);
+    }
+    p
+}
+
+#[test]
+fn test_non_relay_policy() {
+    let relayed = pair(CandidateType::Relay, None);
+    let direct = pair(CandidateType::ServerReflexive, None);
+
+    assert!(NonRelayPolicy.should_switch(&relayed, &direct));
+    assert!(!NonRelayPolicy.should_switch(&direct, &relayed));
+    assert!(!NonRelayPolicy.should_switch(&relayed, &relayed));
+}
+
+#[test]
+fn test_lower_rtt_policy() {
+    let policy = LowerRttPolicy {
+        min_rtt_improvement: Duration::from_millis(10),
+    };
+    let slow = pair(CandidateType::Host, Some(Duration::from_millis(50)));
+    let fast = pair(CandidateType::Host, Some(Duration::from_millis(30)));
+    let almost_as_slow = pair(CandidateType::Host, Some(Duration::from_millis(45)));
+    let unmeasured = pair(CandidateType::Host, None);
+
+    assert!(policy.should_switch(&slow, &fast));
+    assert!(!policy.should_switch(&fast, &slow));
+    assert!(!policy.should_switch(&slow, &almost_as_slow));
+    assert!(!policy.should_switch(&slow, &unmeasured));
+    assert!(!policy.should_switch(&unmeasured, &fast));
+}
+
+#[test]
+fn test_default_pair_selection_policy() {
+    let policy = DefaultPairSelectionPolicy::default();
+    let fast_relayed = pair(CandidateType::Relay, Some(Duration::from_millis(10)));
+    let slow_direct = pair(CandidateType::Host, Some(Duration::from_millis(200)));
+    let fast_direct = pair(CandidateType::Host, Some(Duration::from_millis(20)));
+
+    assert!(policy.should_switch(&fast_relayed, &slow_direct));
+    assert!(!policy.should_switch(&slow_direct, &fast_relayed));
+    assert!(policy.should_switch(&slow_direct, &fast_direct));
+    assert!(!policy.should_switch(&fast_direct, &slow_direct));
+}
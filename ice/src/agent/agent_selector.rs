@@ -1,6 +1,7 @@
 use crate::agent::agent_internal::*;
 use crate::candidate::*;
 use crate::control::*;
+use crate::nomination::*;
 use crate::priority::*;
 use crate::use_candidate::*;
 
@@ -115,19 +116,26 @@ impl AgentInternal {
                     let ufrag_pwd = self.ufrag_pwd.lock().await;
                     let username =
                         ufrag_pwd.remote_ufrag.clone() + ":" + ufrag_pwd.local_ufrag.as_str();
-                    let mut msg = Message::new();
-                    let result = msg.build(&[
+                    let mut setters: Vec<Box<dyn Setter>> = vec![
                         Box::new(BINDING_REQUEST),
                         Box::new(TransactionId::new()),
                         Box::new(Username::new(ATTR_USERNAME, username)),
                         Box::<UseCandidateAttr>::default(),
                         Box::new(AttrControlling(self.tie_breaker.load(Ordering::SeqCst))),
                         Box::new(PriorityAttr(pair.local.priority())),
-                        Box::new(MessageIntegrity::new_short_term_integrity(
-                            ufrag_pwd.remote_pwd.clone(),
-                        )),
-                        Box::new(FINGERPRINT),
-                    ]);
+                    ];
+                    if self.renomination.load(Ordering::SeqCst) {
+                        setters.push(Box::new(NominationAttr(
+                            self.nomination.load(Ordering::SeqCst),
+                        )));
+                    }
+                    setters.push(Box::new(MessageIntegrity::new_short_term_integrity(
+                        ufrag_pwd.remote_pwd.clone(),
+                    )));
+                    setters.push(Box::new(FINGERPRINT));
+
+                    let mut msg = Message::new();
+                    let result = msg.build(&setters);
                     (msg, result)
                 };
 
@@ -154,6 +162,93 @@ impl AgentInternal {
         }
     }
 
+    /// Keeps checking candidate pairs while one is selected, and renominates the valid pair the
+    /// selection policy prefers over the selected one.
+    async fn renominate(&self) {
+        let selected_pair = match self.agent_conn.get_selected_pair() {
+            Some(selected_pair) => selected_pair,
+            None => return,
+        };
+        if selected_pair.state.load(Ordering::SeqCst) == CandidatePairState::Failed as u8 {
+            return;
+        }
+
+        // A better pair may still succeed
+        self.ping_all_candidates().await;
+
+        let pending_pair = {
+            let nominated_pair = self.nominated_pair.lock().await;
+            nominated_pair
+                .clone()
+                .filter(|p| !Arc::ptr_eq(p, &selected_pair))
+        };
+        if let Some(p) = pending_pair {
+            if p.binding_request_count.fetch_add(1, Ordering::SeqCst) < self.max_binding_requests {
+                self.nominate_pair().await;
+            } else {
+                log::debug!(
+                    "[{}]: renomination of {} was not answered, marking it as failed",
+                    self.get_name(),
+                    p
+                );
+                p.state
+                    .store(CandidatePairState::Failed as u8, Ordering::SeqCst);
+                let mut nominated_pair = self.nominated_pair.lock().await;
+                *nominated_pair = Some(selected_pair);
+            }
+            return;
+        }
+
+        let mut best_pair = Arc::clone(&selected_pair);
+        {
+            let checklist = self.agent_conn.checklist.lock().await;
+            for p in &*checklist {
+                if p.state.load(Ordering::SeqCst) == CandidatePairState::Succeeded as u8
+                    && !Arc::ptr_eq(p, &best_pair)
+                    && self.pair_selection_policy.should_switch(&best_pair, p)
+                {
+                    best_pair = Arc::clone(p);
+                }
+            }
+        }
+
+        if !Arc::ptr_eq(&best_pair, &selected_pair) {
+            log::debug!(
+                "[{}]: renominating {} in place of {}",
+                self.get_name(),
+                best_pair,
+                selected_pair
+            );
+            best_pair.binding_request_count.store(0, Ordering::SeqCst);
+            self.nomination.fetch_add(1, Ordering::SeqCst);
+            {
+                let mut nominated_pair = self.nominated_pair.lock().await;
+                *nominated_pair = Some(best_pair);
+            }
+            self.nominate_pair().await;
+        }
+    }
+
+    /// Returns true if `p` is a renominated pair that is not selected yet.
+    async fn is_renominated(&self, p: &Arc<CandidatePair>) -> bool {
+        if !self.renomination.load(Ordering::SeqCst) {
+            return false;
+        }
+
+        let is_nominated = {
+            let nominated_pair = self.nominated_pair.lock().await;
+            nominated_pair
+                .as_ref()
+                .map_or(false, |nominated_pair| Arc::ptr_eq(nominated_pair, p))
+        };
+        let is_selected = self
+            .agent_conn
+            .get_selected_pair()
+            .map_or(false, |selected_pair| Arc::ptr_eq(&selected_pair, p));
+
+        is_nominated && !is_selected
+    }
+
     pub(crate) async fn start(&self) {
         if self.is_controlling.load(Ordering::SeqCst) {
             ControllingSelector::start(self).await;
@@ -217,6 +312,7 @@ impl ControllingSelector for AgentInternal {
             let mut nominated_pair = self.nominated_pair.lock().await;
             *nominated_pair = None;
         }
+        self.nomination.store(1, Ordering::SeqCst);
         *self.start_time.lock() = Instant::now();
     }
 
@@ -236,6 +332,9 @@ impl ControllingSelector for AgentInternal {
             if self.validate_selected_pair().await {
                 log::trace!("[{}]: checking keepalive", self.get_name());
                 self.check_keepalive().await;
+                if self.renomination.load(Ordering::SeqCst) {
+                    self.renominate().await;
+                }
             }
         } else if nominated_pair_is_some {
             self.nominate_pair().await;
@@ -323,6 +422,9 @@ impl ControllingSelector for AgentInternal {
             let selected_pair_is_none = self.agent_conn.get_selected_pair().is_none();
 
             if let Some(p) = self.find_pair(local, remote).await {
+                p.on_response_received(
+                    Instant::now().saturating_duration_since(pending_request.timestamp),
+                );
                 p.state
                     .store(CandidatePairState::Succeeded as u8, Ordering::SeqCst);
                 log::trace!(
@@ -332,7 +434,9 @@ impl ControllingSelector for AgentInternal {
                     pending_request.is_use_candidate,
                     selected_pair_is_none
                 );
-                if pending_request.is_use_candidate && selected_pair_is_none {
+                if pending_request.is_use_candidate
+                    && (selected_pair_is_none || self.is_renominated(&p).await)
+                {
                     self.set_selected_pair(Some(Arc::clone(&p))).await;
                 }
             } else {
@@ -404,7 +508,9 @@ impl ControllingSelector for AgentInternal {
 
 #[async_trait]
 impl ControlledSelector for AgentInternal {
-    async fn start(&self) {}
+    async fn start(&self) {
+        self.nomination.store(0, Ordering::SeqCst);
+    }
 
     async fn contact_candidates(&self) {
         // A lite selector should not contact candidates
@@ -480,6 +586,9 @@ impl ControlledSelector for AgentInternal {
             );
 
             if let Some(p) = self.find_pair(local, remote).await {
+                p.on_response_received(
+                    Instant::now().saturating_duration_since(pending_request.timestamp),
+                );
                 p.state
                     .store(CandidatePairState::Succeeded as u8, Ordering::SeqCst);
                 log::trace!("Found valid candidate pair: {}", p);
@@ -516,8 +625,21 @@ impl ControlledSelector for AgentInternal {
                     // previously sent by this pair produced a successful response and
                     // generated a valid pair (Section 7.2.5.3.2).  The agent sets the
                     // nominated flag value of the valid pair to true.
-                    if self.agent_conn.get_selected_pair().is_none() {
-                        self.set_selected_pair(Some(Arc::clone(&p))).await;
+                    //
+                    // With renomination, the nomination with the highest NOMINATION value wins.
+                    let mut nomination = NominationAttr::default();
+                    let renominated = self.renomination.load(Ordering::SeqCst)
+                        && nomination.get_from(m).is_ok()
+                        && nomination.0 > self.nomination.load(Ordering::SeqCst);
+                    if renominated {
+                        self.nomination.store(nomination.0, Ordering::SeqCst);
+                    }
+                    match self.agent_conn.get_selected_pair() {
+                        None => self.set_selected_pair(Some(Arc::clone(&p))).await,
+                        Some(selected_pair) if renominated && !Arc::ptr_eq(&selected_pair, &p) => {
+                            self.set_selected_pair(Some(Arc::clone(&p))).await;
+                        }
+                        _ => {}
                     }
                     self.send_binding_success(m, local, remote).await;
                 } else {
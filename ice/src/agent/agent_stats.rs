@@ -108,7 +108,8 @@ pub struct CandidatePairStats {
     /// The total number of consent requests sent.
     pub consent_requests_sent: u64,
 
-    /// The timestamp at which the latest valid STUN binding response expired.
+    /// The timestamp at which the latest valid STUN binding response expired, or will expire
+    /// unless consent is refreshed.
     pub consent_expired_timestamp: Instant,
 }
 
@@ -217,12 +218,27 @@ impl AgentInternal {
         let checklist = self.agent_conn.checklist.lock().await;
         let mut res = Vec::with_capacity(checklist.len());
         for cp in &*checklist {
+            let checks = *cp.checks.lock();
+            let now = Instant::now();
             let stat = CandidatePairStats {
-                timestamp: Instant::now(),
+                timestamp: now,
                 local_candidate_id: cp.local.id(),
                 remote_candidate_id: cp.remote.id(),
                 state: cp.state.load(Ordering::SeqCst).into(),
                 nominated: cp.nominated.load(Ordering::SeqCst),
+                first_request_timestamp: checks.first_request.unwrap_or(now),
+                last_request_timestamp: checks.last_request.unwrap_or(now),
+                last_response_timestamp: checks.last_response.unwrap_or(now),
+                total_round_trip_time: checks.total_round_trip_time.as_secs_f64(),
+                current_round_trip_time: checks.current_round_trip_time.as_secs_f64(),
+                requests_sent: checks.requests_sent,
+                responses_received: checks.responses_received,
+                consent_requests_sent: checks.consent_requests_sent,
+                consent_expired_timestamp: checks.consent_expired.unwrap_or_else(|| {
+                    checks
+                        .consent_granted
+                        .map_or(now, |granted| granted + self.consent_timeout)
+                }),
                 ..CandidatePairStats::default()
             };
             res.push(stat);
@@ -1685,6 +1685,76 @@ async fn test_connection_state_failed_delete_all_candidates() -> Result<()> {
     Ok(())
 }
 
+// Assert that consent expires once the remote stops answering consent checks, well before the
+// agent would go to disconnected
+#[tokio::test]
+async fn test_consent_expiry() -> Result<()> {
+    let cfg0 = AgentConfig {
+        network_types: supported_network_types(),
+        consent_check_interval: Some(Duration::from_millis(200)),
+        consent_timeout: Some(Duration::from_secs(1)),
+        ..Default::default()
+    };
+    let cfg1 = AgentConfig {
+        network_types: supported_network_types(),
+        ..Default::default()
+    };
+
+    let a_agent = Arc::new(Agent::new(cfg0).await?);
+    let b_agent = Arc::new(Agent::new(cfg1).await?);
+
+    let (is_failed_tx, mut is_failed_rx) = mpsc::channel::<()>(1);
+    let is_failed_tx = Arc::new(Mutex::new(Some(is_failed_tx)));
+    a_agent.on_connection_state_change(Box::new(move |c: ConnectionState| {
+        let is_failed_tx_clone = Arc::clone(&is_failed_tx);
+        Box::pin(async move {
+            if c == ConnectionState::Failed {
+                let mut tx = is_failed_tx_clone.lock().await;
+                tx.take();
+            }
+        })
+    }));
+
+    let (a_conn, _b_conn) = connect_with_vnet(&a_agent, &b_agent).await?;
+
+    // Let a few consent checks complete
+    tokio::time::sleep(Duration::from_millis(800)).await;
+    let stats = a_agent.get_candidate_pairs_stats().await;
+    let selected = stats
+        .iter()
+        .find(|s| s.nominated)
+        .expect("expected a nominated pair");
+    assert!(selected.consent_requests_sent >= 2);
+    assert!(selected.requests_sent >= selected.consent_requests_sent);
+    assert!(selected.responses_received > 0);
+    assert!(selected.last_response_timestamp >= selected.first_request_timestamp);
+    assert!(selected.consent_expired_timestamp > Instant::now());
+    assert!(a_conn.send(&[1, 2, 3]).await.is_ok());
+
+    b_agent.close().await?;
+    tokio::time::timeout(Duration::from_secs(3), is_failed_rx.recv())
+        .await
+        .expect("consent should expire");
+
+    let stats = a_agent.get_candidate_pairs_stats().await;
+    let selected = stats
+        .iter()
+        .find(|s| s.nominated)
+        .expect("expected a nominated pair");
+    assert_eq!(selected.state, CandidatePairState::Failed);
+    assert!(selected.consent_expired_timestamp <= Instant::now());
+
+    if let Err(err) = a_conn.send(&[1, 2, 3]).await {
+        assert_eq!(err.to_string(), Error::ErrConsentExpired.to_string());
+    } else {
+        panic!("expected error, but got ok");
+    }
+
+    a_agent.close().await?;
+
+    Ok(())
+}
+
 // Assert that the ICE Agent can go directly from Connecting -> Failed on both sides
 #[tokio::test]
 async fn test_connection_state_connecting_to_failed() -> Result<()> {
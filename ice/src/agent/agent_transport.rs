@@ -203,6 +203,10 @@ impl Conn for AgentConn {
         }
 
         let result = if let Some(pair) = self.get_selected_pair() {
+            // Stop sending as soon as the remote no longer consents, RFC 7675 Section 5.1
+            if pair.state.load(Ordering::SeqCst) == CandidatePairState::Failed as u8 {
+                return Err(util::Error::Other(Error::ErrConsentExpired.to_string()));
+            }
             pair.write(buf).await
         } else if let Some(pair) = self.get_best_available_candidate_pair().await {
             pair.write(buf).await
@@ -1,3 +1,4 @@
+use super::agent_selection_policy::PairSelectionPolicy;
 use super::*;
 
 use crate::candidate::candidate_base::unmarshal_candidate;
@@ -236,7 +237,7 @@ pub(crate) async fn add_vnet_stun(wan_net: Arc<net::Net>) -> Result<turn::server
         }],
         realm: "webrtc.rs".to_owned(),
         auth_handler: Arc::new(TestAuthHandler::new()),
-        channel_bind_timeout: Duration::from_secs(0),
+        ..Default::default()
     })
     .await?;
 
@@ -529,6 +530,7 @@ async fn test_connectivity_vnet_full_cone_nats_on_both_ends() -> Result<(), Erro
         port: VNET_STUN_SERVER_PORT,
         username: "user".to_owned(),
         password: "pass".to_owned(),
+        oauth_credential: None,
         proto: ProtoType::Udp,
     };
 
@@ -591,6 +593,7 @@ async fn test_connectivity_vnet_symmetric_nats_on_both_ends() -> Result<(), Erro
         port: VNET_STUN_SERVER_PORT,
         username: "user".to_owned(),
         password: "pass".to_owned(),
+        oauth_credential: None,
         proto: ProtoType::Udp,
     };
 
@@ -877,6 +880,135 @@ async fn test_disconnected_to_connected() -> Result<(), Error> {
     Ok(())
 }
 
+// Selects the pair between the given addresses once it is set
+struct SwitchToPolicy {
+    target: util::sync::Mutex<Option<(SocketAddr, SocketAddr)>>,
+}
+
+impl PairSelectionPolicy for SwitchToPolicy {
+    fn should_switch(&self, _selected: &CandidatePair, candidate: &CandidatePair) -> bool {
+        *self.target.lock() == Some((candidate.local.addr(), candidate.remote.addr()))
+    }
+}
+
+// Assert that the controlling agent renominates the pair preferred by its selection policy, and
+// that both agents switch to it
+#[tokio::test]
+async fn test_renomination() -> Result<(), Error> {
+    // Create a network with two addresses on each side
+    let wan = Arc::new(Mutex::new(router::Router::new(router::RouterConfig {
+        cidr: "0.0.0.0/0".to_owned(),
+        ..Default::default()
+    })?));
+
+    let net0 = Arc::new(net::Net::new(Some(net::NetConfig {
+        static_ips: vec!["192.168.0.1".to_owned(), "192.168.0.3".to_owned()],
+        ..Default::default()
+    })));
+    let net1 = Arc::new(net::Net::new(Some(net::NetConfig {
+        static_ips: vec!["192.168.0.2".to_owned(), "192.168.0.4".to_owned()],
+        ..Default::default()
+    })));
+
+    connect_net2router(&net0, &wan).await?;
+    connect_net2router(&net1, &wan).await?;
+    start_router(&wan).await?;
+
+    let interval = Duration::from_millis(50);
+    let policy = Arc::new(SwitchToPolicy {
+        target: util::sync::Mutex::new(None),
+    });
+
+    let controlling_agent = Arc::new(
+        Agent::new(AgentConfig {
+            network_types: supported_network_types(),
+            multicast_dns_mode: MulticastDnsMode::Disabled,
+            net: Some(Arc::clone(&net0)),
+            keepalive_interval: Some(interval),
+            check_interval: interval,
+            renomination: true,
+            pair_selection_policy: Some(Arc::clone(&policy) as _),
+            ..Default::default()
+        })
+        .await?,
+    );
+
+    let controlled_agent = Arc::new(
+        Agent::new(AgentConfig {
+            network_types: supported_network_types(),
+            multicast_dns_mode: MulticastDnsMode::Disabled,
+            net: Some(Arc::clone(&net1)),
+            keepalive_interval: Some(interval),
+            check_interval: interval,
+            renomination: true,
+            ..Default::default()
+        })
+        .await?,
+    );
+
+    let (selected_tx, mut selected_rx) = mpsc::channel::<SocketAddr>(10);
+    controlling_agent.on_selected_candidate_pair_change(Box::new(
+        move |local: &Arc<dyn Candidate + Send + Sync>, _: &Arc<dyn Candidate + Send + Sync>| {
+            let _ = selected_tx.try_send(local.addr());
+            Box::pin(async move {})
+        },
+    ));
+
+    // The controlled agent accepts and the controlling agent dials
+    connect_with_vnet(&controlled_agent, &controlling_agent).await?;
+
+    let initial = controlling_agent
+        .internal
+        .agent_conn
+        .get_selected_pair()
+        .expect("expected a selected pair");
+    let target = {
+        let checklist = controlling_agent.internal.agent_conn.checklist.lock().await;
+        checklist
+            .iter()
+            .find(|p| {
+                p.local.addr().ip() != initial.local.addr().ip()
+                    && p.remote.addr().ip() != initial.remote.addr().ip()
+            })
+            .map(|p| (p.local.addr(), p.remote.addr()))
+            .expect("expected a pair on the other addresses")
+    };
+    *policy.target.lock() = Some(target);
+
+    let is_selected = |agent: &Arc<Agent>, local: SocketAddr, remote: SocketAddr| {
+        agent
+            .internal
+            .agent_conn
+            .get_selected_pair()
+            .map_or(false, |p| {
+                p.local.addr() == local && p.remote.addr() == remote
+            })
+    };
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while !(is_selected(&controlling_agent, target.0, target.1)
+            && is_selected(&controlled_agent, target.1, target.0))
+        {
+            tokio::time::sleep(interval).await;
+        }
+    })
+    .await
+    .expect("both agents should switch to the renominated pair");
+
+    assert!(!initial.nominated.load(Ordering::SeqCst));
+    assert_eq!(selected_rx.recv().await, Some(initial.local.addr()));
+    assert_eq!(selected_rx.recv().await, Some(target.0));
+
+    {
+        let mut w = wan.lock().await;
+        w.stop().await?;
+    }
+
+    controlling_agent.close().await?;
+    controlled_agent.close().await?;
+
+    Ok(())
+}
+
 //use std::io::Write;
 
 // Agent.Write should use the best valid pair if a selected pair is not yet available
@@ -1,6 +1,8 @@
 #[cfg(test)]
 mod agent_gather_test;
 #[cfg(test)]
+mod agent_selection_policy_test;
+#[cfg(test)]
 mod agent_test;
 #[cfg(test)]
 mod agent_transport_test;
@@ -10,6 +12,7 @@ pub(crate) mod agent_vnet_test;
 pub mod agent_config;
 pub mod agent_gather;
 pub(crate) mod agent_internal;
+pub mod agent_selection_policy;
 pub mod agent_selector;
 pub mod agent_stats;
 pub mod agent_transport;
@@ -18,7 +21,9 @@ use crate::candidate::*;
 use crate::error::*;
 use crate::external_ip_mapper::*;
 use crate::mdns::*;
+use crate::network_monitor::*;
 use crate::network_type::*;
+use crate::proxy::ProxyDialer;
 use crate::state::*;
 use crate::udp_mux::UDPMux;
 use crate::udp_network::UDPNetwork;
@@ -31,7 +36,7 @@ use mdns::conn::*;
 use std::collections::HashMap;
 use std::net::{Ipv4Addr, SocketAddr};
 use stun::{agent::*, attributes::*, fingerprint::*, integrity::*, message::*, xoraddr::*};
-use util::{vnet::net::*, Buffer};
+use util::{sync::Mutex as SyncMutex, vnet::net::*, Buffer};
 
 use crate::agent::agent_gather::GatherCandidatesInternalParams;
 use crate::rand::*;
@@ -111,6 +116,14 @@ pub struct Agent {
     pub(crate) network_types: Vec<NetworkType>,
 
     pub(crate) gather_candidate_cancel: Option<GatherCandidateCancelFn>,
+
+    pub(crate) continual_gathering_policy: ContinualGatheringPolicy,
+    pub(crate) network_monitor: Arc<dyn NetworkMonitor + Send + Sync>,
+    pub(crate) network_monitor_interval: Duration,
+    // Continual gathering stops when this sender is dropped.
+    pub(crate) network_monitor_cancel_tx: Arc<SyncMutex<Option<mpsc::Sender<()>>>>,
+
+    pub(crate) proxy_dialer: Option<Arc<dyn ProxyDialer + Send + Sync>>,
 }
 
 impl Agent {
@@ -189,6 +202,11 @@ impl Agent {
             Arc::new(Net::new(None))
         };
 
+        let network_monitor = config
+            .network_monitor
+            .clone()
+            .unwrap_or_else(|| Arc::new(InterfaceMonitor::new(Arc::clone(&net))));
+
         let agent = Self {
             udp_network: config.udp_network,
             internal: Arc::new(ai),
@@ -205,6 +223,15 @@ impl Agent {
             network_types: config.network_types.clone(),
 
             gather_candidate_cancel: None, //TODO: add cancel
+
+            continual_gathering_policy: config.continual_gathering_policy,
+            network_monitor,
+            network_monitor_interval: config
+                .network_monitor_interval
+                .unwrap_or(DEFAULT_NETWORK_MONITOR_INTERVAL),
+            network_monitor_cancel_tx: Arc::new(SyncMutex::new(None)),
+
+            proxy_dialer: config.proxy_dialer.clone(),
         };
 
         agent.internal.start_on_connection_state_change_routine(
@@ -231,6 +258,17 @@ impl Agent {
         self.internal.agent_conn.bytes_sent()
     }
 
+    /// Enables or disables renomination, e.g. when the remote agent doesn't advertise
+    /// `a=ice-options:renomination`. It has to be set before `dial` or `accept`.
+    pub fn set_renomination(&self, enabled: bool) {
+        self.internal.renomination.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Returns true if renomination is enabled.
+    pub fn renomination(&self) -> bool {
+        self.internal.renomination.load(Ordering::SeqCst)
+    }
+
     /// Sets a handler that is fired when the connection state changes.
     pub fn on_connection_state_change(&self, f: OnConnectionStateChangeHdlrFn) {
         self.internal
@@ -238,7 +276,8 @@ impl Agent {
             .store(Some(Arc::new(Mutex::new(f))))
     }
 
-    /// Sets a handler that is fired when the final candidate pair is selected.
+    /// Sets a handler that is fired when the final candidate pair is selected, and again whenever
+    /// renomination switches it.
     pub fn on_selected_candidate_pair_change(&self, f: OnSelectedCandidatePairChangeHdlrFn) {
         self.internal
             .on_selected_candidate_pair_change_hdlr
@@ -334,6 +373,7 @@ impl Agent {
         if let Some(gather_candidate_cancel) = &self.gather_candidate_cancel {
             gather_candidate_cancel();
         }
+        self.stop_continual_gathering();
 
         if let UDPNetwork::Muxed(ref udp_mux) = self.udp_network {
             let (ufrag, _) = self.get_local_user_credentials().await;
@@ -341,7 +381,12 @@ impl Agent {
         }
 
         //FIXME: deadlock here
-        self.internal.close().await
+        let result = self.internal.close().await;
+
+        // Continual gathering that started while the agent was closed.
+        self.stop_continual_gathering();
+
+        result
     }
 
     /// Returns the selected pair or nil if there is none
@@ -380,8 +425,11 @@ impl Agent {
             return Err(Error::ErrLocalPwdInsufficientBits);
         }
 
-        if GatheringState::from(self.gathering_state.load(Ordering::SeqCst))
-            == GatheringState::Gathering
+        // Continual gathering stays in the gathering state after the initial gathering,
+        // until it is stopped by a restart.
+        if !self.stop_continual_gathering()
+            && GatheringState::from(self.gathering_state.load(Ordering::SeqCst))
+                == GatheringState::Gathering
         {
             return Err(Error::ErrRestartWhenGathering);
         }
@@ -458,6 +506,11 @@ impl Agent {
             agent_internal: Arc::clone(&self.internal),
             gathering_state: Arc::clone(&self.gathering_state),
             chan_candidate_tx: Arc::clone(&self.internal.chan_candidate_tx),
+            continual_gathering_policy: self.continual_gathering_policy,
+            network_monitor: Arc::clone(&self.network_monitor),
+            network_monitor_interval: self.network_monitor_interval,
+            network_monitor_cancel_tx: Arc::clone(&self.network_monitor_cancel_tx),
+            proxy_dialer: self.proxy_dialer.clone(),
         };
         tokio::spawn(async move {
             Self::gather_candidates_internal(params).await;
@@ -500,6 +553,12 @@ impl Agent {
         Ok(c)
     }
 
+    /// Stops continual gathering, and returns true if it was running.
+    fn stop_continual_gathering(&self) -> bool {
+        let mut network_monitor_cancel_tx = self.network_monitor_cancel_tx.lock();
+        network_monitor_cancel_tx.take().is_some()
+    }
+
     async fn close_multicast_conn(mdns_conn: &Option<Arc<DnsConn>>) {
         if let Some(conn) = mdns_conn {
             if let Err(err) = conn.close().await {
@@ -7,7 +7,6 @@ use crate::error::Error;
 use crate::url::{ProtoType, SchemeType, Url};
 
 use std::result::Result;
-use std::time::Duration;
 use tokio::net::UdpSocket;
 use turn::auth::AuthHandler;
 
@@ -60,7 +59,8 @@ async fn test_relay_only_connection() -> Result<(), Error> {
                 net: Arc::new(util::vnet::net::Net::new(None)),
             }),
         }],
-        channel_bind_timeout: Duration::from_secs(0),
+        peer_address_filter: turn::server::peer_filter::PeerAddressFilter::allow_all(),
+        ..Default::default()
     })
     .await?;
 
@@ -71,6 +71,7 @@ async fn test_relay_only_connection() -> Result<(), Error> {
             host: "127.0.0.1".to_owned(),
             username: "username".to_owned(),
             password: "password".to_owned(),
+            oauth_credential: None,
             port: server_port,
             proto: ProtoType::Udp,
         }],
@@ -89,6 +90,7 @@ async fn test_relay_only_connection() -> Result<(), Error> {
             host: "127.0.0.1".to_owned(),
             username: "username".to_owned(),
             password: "password".to_owned(),
+            oauth_credential: None,
             port: server_port,
             proto: ProtoType::Udp,
         }],
@@ -4,7 +4,6 @@ use crate::agent::agent_config::AgentConfig;
 use crate::agent::agent_vnet_test::{connect_with_vnet, on_connected};
 use crate::agent::Agent;
 use crate::url::{SchemeType, Url};
-use std::time::Duration;
 use tokio::net::UdpSocket;
 
 //use std::io::Write;
@@ -39,7 +38,7 @@ async fn test_server_reflexive_only_connection() -> Result<()> {
                 net: Arc::new(util::vnet::net::Net::new(None)),
             }),
         }],
-        channel_bind_timeout: Duration::from_secs(0),
+        ..Default::default()
     })
     .await?;
 
@@ -24,8 +24,10 @@ use std::fmt;
 use std::net::{IpAddr, SocketAddr};
 use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU8, Ordering};
 use std::sync::Arc;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 use tokio::sync::{broadcast, Mutex};
+use tokio::time::Instant;
+use util::sync::Mutex as SyncMutex;
 
 pub(crate) const RECEIVE_MTU: usize = 8192;
 pub(crate) const DEFAULT_LOCAL_PREFERENCE: u16 = 65535;
@@ -232,6 +234,26 @@ pub struct CandidatePair {
     pub(crate) binding_request_count: AtomicU16,
     pub(crate) state: AtomicU8, // convert it to CandidatePairState,
     pub(crate) nominated: AtomicBool,
+    pub(crate) checks: SyncMutex<CandidatePairChecks>,
+}
+
+/// Book-keeping of the STUN transactions sent on a candidate pair, used for consent freshness
+/// (RFC 7675) and the candidate pair stats.
+#[derive(Default, Debug, Clone, Copy)]
+pub(crate) struct CandidatePairChecks {
+    pub(crate) requests_sent: u64,
+    pub(crate) responses_received: u64,
+    pub(crate) consent_requests_sent: u64,
+    pub(crate) first_request: Option<Instant>,
+    pub(crate) last_request: Option<Instant>,
+    pub(crate) last_response: Option<Instant>,
+    pub(crate) total_round_trip_time: Duration,
+    pub(crate) current_round_trip_time: Duration,
+    // The last time the remote granted consent, either by answering a request or by nominating
+    // the pair.
+    pub(crate) consent_granted: Option<Instant>,
+    pub(crate) next_consent_request: Option<Instant>,
+    pub(crate) consent_expired: Option<Instant>,
 }
 
 impl Default for CandidatePair {
@@ -243,6 +265,7 @@ impl Default for CandidatePair {
             state: AtomicU8::new(CandidatePairState::Waiting as u8),
             binding_request_count: AtomicU16::new(0),
             nominated: AtomicBool::new(false),
+            checks: SyncMutex::new(CandidatePairChecks::default()),
         }
     }
 }
@@ -295,6 +318,7 @@ impl CandidatePair {
             state: AtomicU8::new(CandidatePairState::Waiting as u8),
             binding_request_count: AtomicU16::new(0),
             nominated: AtomicBool::new(false),
+            checks: SyncMutex::new(CandidatePairChecks::default()),
         }
     }
 
@@ -320,4 +344,37 @@ impl CandidatePair {
     pub async fn write(&self, b: &[u8]) -> Result<usize> {
         self.local.write_to(b, &*self.remote).await
     }
+
+    /// Returns the round trip time of the latest connectivity or consent check answered on this
+    /// pair.
+    pub fn current_round_trip_time(&self) -> Option<Duration> {
+        let checks = self.checks.lock();
+        checks.last_response.map(|_| checks.current_round_trip_time)
+    }
+
+    pub(crate) fn on_request_sent(&self) {
+        let now = Instant::now();
+        let mut checks = self.checks.lock();
+        checks.requests_sent += 1;
+        checks.first_request.get_or_insert(now);
+        checks.last_request = Some(now);
+    }
+
+    pub(crate) fn on_response_received(&self, round_trip_time: Duration) {
+        let now = Instant::now();
+        let mut checks = self.checks.lock();
+        checks.responses_received += 1;
+        checks.last_response = Some(now);
+        checks.consent_granted = Some(now);
+        checks.total_round_trip_time += round_trip_time;
+        checks.current_round_trip_time = round_trip_time;
+    }
+
+    /// Returns when consent to send on this pair expires, if it was ever granted.
+    pub(crate) fn consent_expiry(&self, consent_timeout: Duration) -> Option<Instant> {
+        self.checks
+            .lock()
+            .consent_granted
+            .map(|granted| granted + consent_timeout)
+    }
 }
@@ -193,6 +193,26 @@ pub enum Error {
     ErrUrlParse,
     #[error("Candidate IP could not be found")]
     ErrCandidateIpNotFound,
+    #[error("consent to send on the selected candidate pair has expired")]
+    ErrConsentExpired,
+    #[error("invalid proxy target address")]
+    ErrProxyInvalidAddress,
+    #[error("socks5 proxy: unsupported version")]
+    ErrSocks5UnsupportedVersion,
+    #[error("socks5 proxy: no acceptable authentication method")]
+    ErrSocks5NoAcceptableAuthMethod,
+    #[error("socks5 proxy: username or password is longer than 255 bytes")]
+    ErrSocks5CredentialsTooLong,
+    #[error("socks5 proxy: authentication failed")]
+    ErrSocks5AuthFailed,
+    #[error("socks5 proxy: connect failed with reply {0}")]
+    ErrSocks5ConnectFailed(u8),
+    #[error("http proxy: CONNECT failed: {0}")]
+    ErrHttpProxyConnectFailed(String),
+    #[error("unexpected frame on a TURN stream")]
+    ErrUnexpectedStreamFrame,
+    #[error("turns url: the host must be a name to verify the TURN server certificate")]
+    ErrTurnsIpAddressHost,
 
     #[error("parse int: {0}")]
     ParseInt(#[from] ParseIntError),
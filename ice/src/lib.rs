@@ -7,8 +7,11 @@ pub mod control;
 mod error;
 pub mod external_ip_mapper;
 pub mod mdns;
+pub mod network_monitor;
 pub mod network_type;
+pub mod nomination;
 pub mod priority;
+pub mod proxy;
 pub mod rand;
 pub mod state;
 pub mod stats;
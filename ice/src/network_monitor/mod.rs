@@ -0,0 +1,76 @@
+#[cfg(test)]
+mod network_monitor_test;
+
+use async_trait::async_trait;
+use std::collections::HashSet;
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::Arc;
+use util::vnet::interface::Interface;
+use util::vnet::net::Net;
+
+/// Defines whether the agent keeps gathering candidates after the initial gathering.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ContinualGatheringPolicy {
+    /// Gathers candidates once, then completes gathering.
+    #[default]
+    GatherOnce,
+    /// Keeps watching the network interfaces after the initial gathering. Host
+    /// candidates are gathered on new addresses and emitted via `on_candidate`, and
+    /// the candidates of vanished addresses are removed. The gathering state stays
+    /// `Gathering`, and the end of candidates is never signaled.
+    GatherContinually,
+}
+
+impl fmt::Display for ContinualGatheringPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match *self {
+            Self::GatherOnce => "gather_once",
+            Self::GatherContinually => "gather_continually",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// Reports the network interfaces of the host, which a continually gathering agent
+/// polls for changes. Implement it to drive network changes, for example in tests.
+#[async_trait]
+pub trait NetworkMonitor {
+    async fn interfaces(&self) -> Vec<Interface>;
+}
+
+/// The default `NetworkMonitor`. It reads the interfaces of a virtual network from
+/// the `Net`, and the interfaces of the host from the system on every call.
+pub struct InterfaceMonitor {
+    net: Arc<Net>,
+}
+
+impl InterfaceMonitor {
+    pub fn new(net: Arc<Net>) -> Self {
+        Self { net }
+    }
+}
+
+#[async_trait]
+impl NetworkMonitor for InterfaceMonitor {
+    async fn interfaces(&self) -> Vec<Interface> {
+        if self.net.is_virtual() {
+            self.net.get_interfaces().await
+        } else {
+            // Net of the host snapshots the interfaces when it is created.
+            Net::new(None).get_interfaces().await
+        }
+    }
+}
+
+/// Returns the addresses that were added to and removed from `known`.
+pub(crate) fn diff_ips(
+    known: &HashSet<IpAddr>,
+    current: &HashSet<IpAddr>,
+) -> (Vec<IpAddr>, Vec<IpAddr>) {
+    let mut added: Vec<IpAddr> = current.difference(known).copied().collect();
+    let mut removed: Vec<IpAddr> = known.difference(current).copied().collect();
+    added.sort();
+    removed.sort();
+    (added, removed)
+}
@@ -0,0 +1,45 @@
+use super::*;
+use crate::error::Result;
+
+use ipnet::IpNet;
+use std::str::FromStr;
+
+#[test]
+fn test_diff_ips() -> Result<()> {
+    let ip = |s: &str| IpAddr::from_str(s).unwrap();
+    let known: HashSet<IpAddr> = [ip("192.168.1.2"), ip("10.0.0.2")].into_iter().collect();
+    let current: HashSet<IpAddr> = [ip("10.0.0.2"), ip("172.16.0.2"), ip("fe80::1")]
+        .into_iter()
+        .collect();
+
+    let (added, removed) = diff_ips(&known, &current);
+    assert_eq!(added, vec![ip("172.16.0.2"), ip("fe80::1")]);
+    assert_eq!(removed, vec![ip("192.168.1.2")]);
+
+    let (added, removed) = diff_ips(&current, &current);
+    assert!(added.is_empty() && removed.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_interface_monitor_vnet() -> Result<()> {
+    let net = Arc::new(Net::new(Some(util::vnet::net::NetConfig::default())));
+    {
+        let nic = net.get_nic()?;
+        let mut nic = nic.lock().await;
+        let ipnet =
+            IpNet::from_str("1.2.3.4/24").map_err(|e| crate::Error::Other(e.to_string()))?;
+        nic.add_addrs_to_interface("eth0", &[ipnet]).await?;
+    }
+
+    let monitor = InterfaceMonitor::new(Arc::clone(&net));
+    let interfaces = monitor.interfaces().await;
+    let eth0 = interfaces
+        .iter()
+        .find(|i| i.name() == "eth0")
+        .expect("should report eth0");
+    assert_eq!(eth0.addrs()[0].addr(), IpAddr::from_str("1.2.3.4").unwrap());
+
+    Ok(())
+}
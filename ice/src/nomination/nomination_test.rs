@@ -0,0 +1,37 @@
+use super::*;
+use crate::error::Result;
+
+#[test]
+fn test_nomination_get_from() -> Result<()> {
+    let mut m = Message::new();
+    let mut n = NominationAttr::default();
+    let result = n.get_from(&m);
+    if let Err(err) = result {
+        assert_eq!(err, stun::Error::ErrAttributeNotFound, "unexpected error");
+    } else {
+        panic!("expected error, but got ok");
+    }
+
+    m.build(&[Box::new(BINDING_REQUEST), Box::new(NominationAttr(3))])?;
+
+    let mut m1 = Message::new();
+    m1.write(&m.raw)?;
+
+    n.get_from(&m1)?;
+    assert_eq!(n, NominationAttr(3), "not equal");
+
+    //"IncorrectSize"
+    {
+        let mut m3 = Message::new();
+        m3.add(ATTR_NOMINATION, &[0; 100]);
+        let mut n2 = NominationAttr::default();
+        let result = n2.get_from(&m3);
+        if let Err(err) = result {
+            assert!(is_attr_size_invalid(&err), "should error");
+        } else {
+            panic!("expected error, but got ok");
+        }
+    }
+
+    Ok(())
+}
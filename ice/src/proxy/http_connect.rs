@@ -0,0 +1,75 @@
+use super::*;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+// The longest response header a proxy may answer a CONNECT request with.
+const MAX_RESPONSE_HEADER_SIZE: usize = 8192;
+
+/// Dials through an HTTP proxy with the CONNECT method (RFC 9110 Section 9.3.6),
+/// authenticating with Basic credentials if `auth` is set.
+#[derive(Debug, Clone)]
+pub struct HttpConnectDialer {
+    pub proxy_addr: String,
+    pub auth: Option<ProxyAuth>,
+}
+
+impl HttpConnectDialer {
+    pub fn new(proxy_addr: String, auth: Option<ProxyAuth>) -> Self {
+        HttpConnectDialer { proxy_addr, auth }
+    }
+}
+
+#[async_trait]
+impl ProxyDialer for HttpConnectDialer {
+    async fn dial(&self, addr: &str) -> Result<TcpStream> {
+        let mut stream = TcpStream::connect(&self.proxy_addr).await?;
+        http_connect(&mut stream, addr, &self.auth).await?;
+        Ok(stream)
+    }
+}
+
+// Sends a CONNECT request for addr, and reads the response header up to the
+// tunneled stream.
+pub(crate) async fn http_connect<S>(
+    stream: &mut S,
+    addr: &str,
+    auth: &Option<ProxyAuth>,
+) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (host, port) = split_host_port(addr)?;
+    let target = join_host_port(host, port);
+
+    let mut request = format!("CONNECT {target} HTTP/1.1\r\nHost: {target}\r\n");
+    if let Some(auth) = auth {
+        let credentials = base64::encode(format!("{}:{}", auth.username, auth.password));
+        request += &format!("Proxy-Authorization: Basic {credentials}\r\n");
+    }
+    request += "\r\n";
+    stream.write_all(request.as_bytes()).await?;
+
+    // Read byte by byte, so that no data of the tunnel is consumed.
+    let mut header = vec![];
+    while !header.ends_with(b"\r\n\r\n") {
+        if header.len() >= MAX_RESPONSE_HEADER_SIZE {
+            return Err(Error::ErrHttpProxyConnectFailed(
+                "response header too long".to_owned(),
+            ));
+        }
+        header.push(stream.read_u8().await?);
+    }
+
+    let header = String::from_utf8_lossy(&header);
+    let status_line = header.lines().next().unwrap_or_default();
+    let mut parts = status_line.split_whitespace();
+    let is_success = matches!(
+        (parts.next(), parts.next()),
+        (Some(version), Some(code)) if version.starts_with("HTTP/1.") && code.starts_with('2')
+    );
+    if !is_success {
+        return Err(Error::ErrHttpProxyConnectFailed(status_line.to_owned()));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+#[cfg(test)]
+mod proxy_test;
+
+pub mod http_connect;
+pub mod socks5;
+pub(crate) mod stream_conn;
+
+use crate::error::*;
+
+use async_trait::async_trait;
+use tokio::net::TcpStream;
+
+/// Connects to TURN servers over TCP through a proxy, for clients behind networks that
+/// only allow traffic through one. The agent uses it for `turn` and `turns` urls with
+/// `transport=tcp`.
+#[async_trait]
+pub trait ProxyDialer {
+    /// Returns a stream to `addr`, a `host:port` address, tunneled through the proxy.
+    async fn dial(&self, addr: &str) -> Result<TcpStream>;
+}
+
+/// The username and password a proxy authenticates a client with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyAuth {
+    pub username: String,
+    pub password: String,
+}
+
+// Splits a `host:port` address, where an IPv6 host may be enclosed in brackets.
+pub(crate) fn split_host_port(addr: &str) -> Result<(&str, u16)> {
+    let (host, port) = addr.rsplit_once(':').ok_or(Error::ErrProxyInvalidAddress)?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let port = port
+        .parse::<u16>()
+        .map_err(|_| Error::ErrProxyInvalidAddress)?;
+    if host.is_empty() {
+        return Err(Error::ErrProxyInvalidAddress);
+    }
+    Ok((host, port))
+}
+
+// Joins a host and a port into a `host:port` address, enclosing an IPv6 host in
+// brackets.
+pub(crate) fn join_host_port(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("[{host}]:{port}")
+    } else {
+        format!("{host}:{port}")
+    }
+}
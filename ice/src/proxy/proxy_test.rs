@@ -0,0 +1,418 @@
+use super::http_connect::*;
+use super::socks5::*;
+use super::stream_conn::*;
+use super::*;
+use crate::agent::agent_config::AgentConfig;
+use crate::agent::agent_vnet_test::TestAuthHandler;
+use crate::agent::Agent;
+use crate::candidate::CandidateType;
+use crate::mdns::MulticastDnsMode;
+use crate::url::{ProtoType, SchemeType, Url};
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, UdpSocket};
+use util::vnet::net::Net;
+use util::Conn;
+
+fn test_auth() -> ProxyAuth {
+    ProxyAuth {
+        username: "user".to_owned(),
+        password: "pass".to_owned(),
+    }
+}
+
+// Echoes what it receives on every connection.
+async fn run_echo_server() -> Result<SocketAddr> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let (mut reader, mut writer) = stream.split();
+                let _ = tokio::io::copy(&mut reader, &mut writer).await;
+            });
+        }
+    });
+    Ok(addr)
+}
+
+// A SOCKS5 proxy stand-in, which only accepts the username/password method.
+async fn run_socks5_proxy(auth: ProxyAuth) -> Result<SocketAddr> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let auth = auth.clone();
+            tokio::spawn(async move {
+                let mut greeting = [0u8; 2];
+                stream.read_exact(&mut greeting).await?;
+                let mut methods = vec![0u8; greeting[1] as usize];
+                stream.read_exact(&mut methods).await?;
+                if !methods.contains(&0x02) {
+                    stream.write_all(&[0x05, 0xff]).await?;
+                    return Result::<()>::Ok(());
+                }
+                stream.write_all(&[0x05, 0x02]).await?;
+
+                let mut credentials = vec![];
+                for _ in 0..2 {
+                    let mut header = [0u8; 2];
+                    if credentials.is_empty() {
+                        stream.read_exact(&mut header).await?;
+                    } else {
+                        stream.read_exact(&mut header[1..]).await?;
+                    }
+                    let mut value = vec![0u8; header[1] as usize];
+                    stream.read_exact(&mut value).await?;
+                    credentials.push(String::from_utf8_lossy(&value).to_string());
+                }
+                if credentials != [auth.username, auth.password] {
+                    stream.write_all(&[0x01, 0x01]).await?;
+                    return Ok(());
+                }
+                stream.write_all(&[0x01, 0x00]).await?;
+
+                let mut request = [0u8; 4];
+                stream.read_exact(&mut request).await?;
+                let host = match request[3] {
+                    0x01 => {
+                        let mut ip = [0u8; 4];
+                        stream.read_exact(&mut ip).await?;
+                        Ipv4Addr::from(ip).to_string()
+                    }
+                    0x04 => {
+                        let mut ip = [0u8; 16];
+                        stream.read_exact(&mut ip).await?;
+                        format!("[{}]", Ipv6Addr::from(ip))
+                    }
+                    _ => {
+                        let len = stream.read_u8().await?;
+                        let mut name = vec![0u8; len as usize];
+                        stream.read_exact(&mut name).await?;
+                        String::from_utf8_lossy(&name).to_string()
+                    }
+                };
+                let port = stream.read_u16().await?;
+
+                let mut target = TcpStream::connect(format!("{host}:{port}")).await?;
+                stream
+                    .write_all(&[0x05, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+                    .await?;
+                tokio::io::copy_bidirectional(&mut stream, &mut target).await?;
+
+                Ok(())
+            });
+        }
+    });
+    Ok(addr)
+}
+
+// An HTTP proxy stand-in, which requires Basic credentials for CONNECT.
+async fn run_http_proxy(auth: ProxyAuth) -> Result<SocketAddr> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let auth = auth.clone();
+            tokio::spawn(async move {
+                let mut header = vec![];
+                while !header.ends_with(b"\r\n\r\n") {
+                    header.push(stream.read_u8().await?);
+                }
+                let header = String::from_utf8_lossy(&header).to_string();
+                let target = header
+                    .strip_prefix("CONNECT ")
+                    .and_then(|rest| rest.split_whitespace().next())
+                    .unwrap_or_default()
+                    .to_owned();
+
+                let credentials = base64::encode(format!("{}:{}", auth.username, auth.password));
+                if !header.contains(&format!("Proxy-Authorization: Basic {credentials}\r\n")) {
+                    stream
+                        .write_all(b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n")
+                        .await?;
+                    return Result::<()>::Ok(());
+                }
+
+                let mut target = TcpStream::connect(target).await?;
+                stream
+                    .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+                    .await?;
+                tokio::io::copy_bidirectional(&mut stream, &mut target).await?;
+
+                Ok(())
+            });
+        }
+    });
+    Ok(addr)
+}
+
+async fn assert_echo(mut stream: TcpStream) -> Result<()> {
+    stream.write_all(b"ping").await?;
+    let mut buf = [0u8; 4];
+    stream.read_exact(&mut buf).await?;
+    assert_eq!(&buf, b"ping");
+    Ok(())
+}
+
+#[test]
+fn test_split_host_port() -> Result<()> {
+    assert_eq!(split_host_port("example.com:3478")?, ("example.com", 3478));
+    assert_eq!(split_host_port("10.0.0.1:443")?, ("10.0.0.1", 443));
+    assert_eq!(split_host_port("[::1]:3478")?, ("::1", 3478));
+    assert_eq!(
+        split_host_port("example.com"),
+        Err(Error::ErrProxyInvalidAddress)
+    );
+    assert_eq!(split_host_port(":3478"), Err(Error::ErrProxyInvalidAddress));
+
+    Ok(())
+}
+
+#[test]
+fn test_join_host_port() {
+    assert_eq!(join_host_port("example.com", 3478), "example.com:3478");
+    assert_eq!(join_host_port("10.0.0.1", 443), "10.0.0.1:443");
+    assert_eq!(join_host_port("::1", 3478), "[::1]:3478");
+}
+
+#[tokio::test]
+async fn test_socks5_dialer() -> Result<()> {
+    let echo_addr = run_echo_server().await?;
+    let proxy_addr = run_socks5_proxy(test_auth()).await?;
+
+    let dialer = Socks5Dialer::new(proxy_addr.to_string(), Some(test_auth()));
+    assert_echo(dialer.dial(&echo_addr.to_string()).await?).await?;
+    assert_echo(
+        dialer
+            .dial(&format!("localhost:{}", echo_addr.port()))
+            .await?,
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_socks5_dialer_auth_failed() -> Result<()> {
+    let echo_addr = run_echo_server().await?;
+    let proxy_addr = run_socks5_proxy(test_auth()).await?;
+
+    let dialer = Socks5Dialer::new(
+        proxy_addr.to_string(),
+        Some(ProxyAuth {
+            username: "user".to_owned(),
+            password: "wrong".to_owned(),
+        }),
+    );
+    let result = dialer.dial(&echo_addr.to_string()).await;
+    assert_eq!(result.err(), Some(Error::ErrSocks5AuthFailed));
+
+    let dialer = Socks5Dialer::new(proxy_addr.to_string(), None);
+    let result = dialer.dial(&echo_addr.to_string()).await;
+    assert_eq!(result.err(), Some(Error::ErrSocks5NoAcceptableAuthMethod));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_connect_dialer() -> Result<()> {
+    let echo_addr = run_echo_server().await?;
+    let proxy_addr = run_http_proxy(test_auth()).await?;
+
+    let dialer = HttpConnectDialer::new(proxy_addr.to_string(), Some(test_auth()));
+    assert_echo(dialer.dial(&echo_addr.to_string()).await?).await?;
+
+    let dialer = HttpConnectDialer::new(proxy_addr.to_string(), None);
+    let result = dialer.dial(&echo_addr.to_string()).await;
+    assert_eq!(
+        result.err(),
+        Some(Error::ErrHttpProxyConnectFailed(
+            "HTTP/1.1 407 Proxy Authentication Required".to_owned()
+        ))
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_connect_ipv6_target() -> Result<()> {
+    let (mut a, mut b) = tokio::io::duplex(1024);
+    b.write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+        .await?;
+    http_connect(&mut a, "::1:3478", &None).await?;
+    drop(a);
+
+    let mut request = String::new();
+    b.read_to_string(&mut request).await?;
+    assert_eq!(
+        request,
+        "CONNECT [::1]:3478 HTTP/1.1\r\nHost: [::1]:3478\r\n\r\n"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stream_conn_framing() -> Result<()> {
+    let (a, mut b) = tokio::io::duplex(1024);
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 3478);
+    let conn = StreamConn::new(a, addr, addr);
+
+    // A STUN message with an 8 byte attribute, and ChannelData with 5 bytes of data,
+    // which are padded to 8, written in one go.
+    let mut stun = vec![0x00, 0x01, 0x00, 0x08];
+    stun.extend_from_slice(&[0x21, 0x12, 0xa4, 0x42]);
+    stun.extend_from_slice(&[0xaa; 12]);
+    stun.extend_from_slice(&[0xbb; 8]);
+    let mut channel_data = vec![0x40, 0x00, 0x00, 0x05];
+    channel_data.extend_from_slice(&[1, 2, 3, 4, 5, 0, 0, 0]);
+    b.write_all(&[stun.clone(), channel_data.clone()].concat())
+        .await?;
+
+    let mut buf = vec![0u8; 1500];
+    let (n, from) = conn.recv_from(&mut buf).await?;
+    assert_eq!(&buf[..n], &stun[..]);
+    assert_eq!(from, addr);
+    let n = conn.recv(&mut buf).await?;
+    assert_eq!(&buf[..n], &channel_data[..]);
+
+    conn.send(b"data").await?;
+    let mut sent = [0u8; 4];
+    b.read_exact(&mut sent).await?;
+    assert_eq!(&sent, b"data");
+
+    b.write_all(&[0xc0, 0x00, 0x00, 0x00]).await?;
+    assert!(conn.recv(&mut buf).await.is_err());
+
+    Ok(())
+}
+
+// Runs a TURN server on UDP, and a front that relays the messages of TURN over TCP
+// connections to it. Returns the address of the front.
+async fn run_turn_server_tcp_front() -> Result<(turn::server::Server, SocketAddr)> {
+    let udp_conn = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let turn_addr = udp_conn.local_addr()?;
+    let server = turn::server::Server::new(turn::server::config::ServerConfig {
+        conn_configs: vec![turn::server::config::ConnConfig {
+            conn: udp_conn,
+            relay_addr_generator: Box::new(
+                turn::relay::relay_static::RelayAddressGeneratorStatic {
+                    relay_address: IpAddr::V4(Ipv4Addr::LOCALHOST),
+                    address: "127.0.0.1".to_owned(),
+                    net: Arc::new(Net::new(None)),
+                },
+            ),
+        }],
+        realm: "webrtc.rs".to_owned(),
+        auth_handler: Arc::new(TestAuthHandler::new()),
+        peer_address_filter: turn::server::peer_filter::PeerAddressFilter::allow_all(),
+        ..Default::default()
+    })
+    .await?;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let front_addr = listener.local_addr()?;
+    tokio::spawn(async move {
+        while let Ok((stream, remote_addr)) = listener.accept().await {
+            let local_addr = stream.local_addr()?;
+            let stream_conn = Arc::new(StreamConn::new(stream, local_addr, remote_addr));
+            let udp_conn = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+            udp_conn.connect(turn_addr).await?;
+
+            let (stream_conn2, udp_conn2) = (Arc::clone(&stream_conn), Arc::clone(&udp_conn));
+            tokio::spawn(async move {
+                let mut buf = vec![0u8; 1500];
+                while let Ok(n) = stream_conn2.recv(&mut buf).await {
+                    let _ = udp_conn2.send(&buf[..n]).await;
+                }
+            });
+            tokio::spawn(async move {
+                let mut buf = vec![0u8; 1500];
+                while let Ok(n) = udp_conn.recv(&mut buf).await {
+                    if stream_conn.send(&buf[..n]).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        Result::<()>::Ok(())
+    });
+
+    Ok((server, front_addr))
+}
+
+#[tokio::test]
+async fn test_gather_relay_through_proxy() -> Result<()> {
+    let (server, front_addr) = run_turn_server_tcp_front().await?;
+
+    let dialers: Vec<Arc<dyn ProxyDialer + Send + Sync>> = vec![
+        Arc::new(Socks5Dialer::new(
+            run_socks5_proxy(test_auth()).await?.to_string(),
+            Some(test_auth()),
+        )),
+        Arc::new(HttpConnectDialer::new(
+            run_http_proxy(test_auth()).await?.to_string(),
+            Some(test_auth()),
+        )),
+    ];
+
+    for dialer in dialers {
+        let url = Url {
+            scheme: SchemeType::Turn,
+            host: front_addr.ip().to_string(),
+            port: front_addr.port(),
+            username: "user".to_owned(),
+            password: "pass".to_owned(),
+            oauth_credential: None,
+            proto: ProtoType::Tcp,
+        };
+
+        let a = Agent::new(AgentConfig {
+            urls: vec![url.clone()],
+            candidate_types: vec![CandidateType::Relay],
+            multicast_dns_mode: MulticastDnsMode::Disabled,
+            proxy_dialer: Some(Arc::clone(&dialer)),
+            ..Default::default()
+        })
+        .await?;
+
+        Agent::gather_candidates_relay(
+            vec![url],
+            Arc::clone(&a.net),
+            Arc::clone(&a.internal),
+            Some(dialer),
+        )
+        .await;
+
+        let candidates = a.get_local_candidates().await?;
+        assert_eq!(candidates.len(), 1, "expected one relay candidate");
+        assert_eq!(candidates[0].candidate_type(), CandidateType::Relay);
+        assert_eq!(candidates[0].address(), "127.0.0.1");
+
+        a.close().await?;
+    }
+
+    server.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dial_turns_ip_address() -> Result<()> {
+    let url = Url {
+        scheme: SchemeType::Turns,
+        host: "127.0.0.1".to_owned(),
+        port: 5349,
+        username: "user".to_owned(),
+        password: "pass".to_owned(),
+        oauth_credential: None,
+        proto: ProtoType::Tcp,
+    };
+    let result = Agent::dial_turn_server_tcp(&url, &None, false).await;
+    assert_eq!(result.err(), Some(Error::ErrTurnsIpAddressHost));
+
+    Ok(())
+}
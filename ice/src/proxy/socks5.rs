@@ -0,0 +1,142 @@
+use super::*;
+
+use std::net::IpAddr;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+const SOCKS5_VERSION: u8 = 0x05;
+const SOCKS5_AUTH_VERSION: u8 = 0x01;
+
+const METHOD_NO_AUTH: u8 = 0x00;
+const METHOD_USERNAME_PASSWORD: u8 = 0x02;
+
+const CMD_CONNECT: u8 = 0x01;
+
+const ATYP_IPV4: u8 = 0x01;
+const ATYP_DOMAIN_NAME: u8 = 0x03;
+const ATYP_IPV6: u8 = 0x04;
+
+const REPLY_SUCCEEDED: u8 = 0x00;
+
+/// Dials through a SOCKS5 proxy (RFC 1928), authenticating with a username and
+/// password (RFC 1929) if `auth` is set. Host names are resolved by the proxy.
+#[derive(Debug, Clone)]
+pub struct Socks5Dialer {
+    pub proxy_addr: String,
+    pub auth: Option<ProxyAuth>,
+}
+
+impl Socks5Dialer {
+    pub fn new(proxy_addr: String, auth: Option<ProxyAuth>) -> Self {
+        Socks5Dialer { proxy_addr, auth }
+    }
+}
+
+#[async_trait]
+impl ProxyDialer for Socks5Dialer {
+    async fn dial(&self, addr: &str) -> Result<TcpStream> {
+        let mut stream = TcpStream::connect(&self.proxy_addr).await?;
+        socks5_connect(&mut stream, addr, &self.auth).await?;
+        Ok(stream)
+    }
+}
+
+// Negotiates the authentication method, authenticates and asks the proxy to
+// connect to addr.
+pub(crate) async fn socks5_connect<S>(
+    stream: &mut S,
+    addr: &str,
+    auth: &Option<ProxyAuth>,
+) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (host, port) = split_host_port(addr)?;
+
+    let method = if auth.is_some() {
+        METHOD_USERNAME_PASSWORD
+    } else {
+        METHOD_NO_AUTH
+    };
+    stream.write_all(&[SOCKS5_VERSION, 1, method]).await?;
+
+    let mut reply = [0u8; 2];
+    stream.read_exact(&mut reply).await?;
+    if reply[0] != SOCKS5_VERSION {
+        return Err(Error::ErrSocks5UnsupportedVersion);
+    }
+    match (reply[1], auth) {
+        (METHOD_NO_AUTH, _) => {}
+        (METHOD_USERNAME_PASSWORD, Some(auth)) => authenticate(stream, auth).await?,
+        _ => return Err(Error::ErrSocks5NoAcceptableAuthMethod),
+    }
+
+    let mut request = vec![SOCKS5_VERSION, CMD_CONNECT, 0x00];
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            request.push(ATYP_IPV4);
+            request.extend_from_slice(&ip.octets());
+        }
+        Ok(IpAddr::V6(ip)) => {
+            request.push(ATYP_IPV6);
+            request.extend_from_slice(&ip.octets());
+        }
+        Err(_) => {
+            if host.len() > u8::MAX as usize {
+                return Err(Error::ErrProxyInvalidAddress);
+            }
+            request.push(ATYP_DOMAIN_NAME);
+            request.push(host.len() as u8);
+            request.extend_from_slice(host.as_bytes());
+        }
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await?;
+
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header).await?;
+    if header[0] != SOCKS5_VERSION {
+        return Err(Error::ErrSocks5UnsupportedVersion);
+    }
+    if header[1] != REPLY_SUCCEEDED {
+        return Err(Error::ErrSocks5ConnectFailed(header[1]));
+    }
+
+    // Skip the address the proxy bound, and its port.
+    let bound_addr_len = match header[3] {
+        ATYP_IPV4 => 4,
+        ATYP_IPV6 => 16,
+        ATYP_DOMAIN_NAME => {
+            let mut len = [0u8; 1];
+            stream.read_exact(&mut len).await?;
+            len[0] as usize
+        }
+        _ => return Err(Error::ErrProxyInvalidAddress),
+    };
+    let mut bound_addr = vec![0u8; bound_addr_len + 2];
+    stream.read_exact(&mut bound_addr).await?;
+
+    Ok(())
+}
+
+async fn authenticate<S>(stream: &mut S, auth: &ProxyAuth) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    if auth.username.len() > u8::MAX as usize || auth.password.len() > u8::MAX as usize {
+        return Err(Error::ErrSocks5CredentialsTooLong);
+    }
+
+    let mut request = vec![SOCKS5_AUTH_VERSION, auth.username.len() as u8];
+    request.extend_from_slice(auth.username.as_bytes());
+    request.push(auth.password.len() as u8);
+    request.extend_from_slice(auth.password.as_bytes());
+    stream.write_all(&request).await?;
+
+    let mut reply = [0u8; 2];
+    stream.read_exact(&mut reply).await?;
+    if reply[1] != 0x00 {
+        return Err(Error::ErrSocks5AuthFailed);
+    }
+
+    Ok(())
+}
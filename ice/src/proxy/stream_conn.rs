@@ -0,0 +1,107 @@
+use crate::error::*;
+
+use async_trait::async_trait;
+use std::io;
+use std::net::SocketAddr;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
+use tokio::sync::Mutex;
+use util::Conn;
+
+type ConnResult<T> = std::result::Result<T, util::Error>;
+
+const STUN_HEADER_SIZE: usize = 20;
+const CHANNEL_DATA_HEADER_SIZE: usize = 4;
+const CHANNEL_DATA_PADDING: usize = 4;
+
+/// StreamConn carries the messages of a TURN client over a TCP or TLS stream. STUN
+/// messages and ChannelData messages delimit themselves, so `recv` returns exactly
+/// one of them.
+///
+/// RFC 8656 Section 12.5
+pub(crate) struct StreamConn<S> {
+    reader: Mutex<ReadHalf<S>>,
+    writer: Mutex<WriteHalf<S>>,
+    local_addr: SocketAddr,
+    remote_addr: SocketAddr,
+}
+
+impl<S: AsyncRead + AsyncWrite> StreamConn<S> {
+    pub(crate) fn new(stream: S, local_addr: SocketAddr, remote_addr: SocketAddr) -> Self {
+        let (reader, writer) = tokio::io::split(stream);
+        StreamConn {
+            reader: Mutex::new(reader),
+            writer: Mutex::new(writer),
+            local_addr,
+            remote_addr,
+        }
+    }
+}
+
+#[async_trait]
+impl<S: AsyncRead + AsyncWrite + Send + 'static> Conn for StreamConn<S> {
+    async fn connect(&self, _addr: SocketAddr) -> ConnResult<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "Not applicable").into())
+    }
+
+    async fn recv(&self, buf: &mut [u8]) -> ConnResult<usize> {
+        let mut reader = self.reader.lock().await;
+
+        let mut header = [0u8; 4];
+        reader.read_exact(&mut header).await?;
+        let length = u16::from_be_bytes([header[2], header[3]]) as usize;
+
+        // The first two bits are 0b00 for STUN messages, and 0b01 for ChannelData
+        // messages, which are padded to a multiple of four bytes over streams.
+        let size = match header[0] >> 6 {
+            0b00 => STUN_HEADER_SIZE + length,
+            0b01 => {
+                CHANNEL_DATA_HEADER_SIZE
+                    + (length + CHANNEL_DATA_PADDING - 1) / CHANNEL_DATA_PADDING
+                        * CHANNEL_DATA_PADDING
+            }
+            _ => {
+                return Err(util::Error::Other(
+                    Error::ErrUnexpectedStreamFrame.to_string(),
+                ))
+            }
+        };
+        if buf.len() < size {
+            return Err(util::Error::ErrBufferShort);
+        }
+
+        buf[..header.len()].copy_from_slice(&header);
+        reader.read_exact(&mut buf[header.len()..size]).await?;
+
+        Ok(size)
+    }
+
+    async fn recv_from(&self, buf: &mut [u8]) -> ConnResult<(usize, SocketAddr)> {
+        let n = self.recv(buf).await?;
+        Ok((n, self.remote_addr))
+    }
+
+    async fn send(&self, buf: &[u8]) -> ConnResult<usize> {
+        let mut writer = self.writer.lock().await;
+        writer.write_all(buf).await?;
+        Ok(buf.len())
+    }
+
+    // The stream is connected to the TURN server, so target is ignored.
+    async fn send_to(&self, buf: &[u8], _target: SocketAddr) -> ConnResult<usize> {
+        self.send(buf).await
+    }
+
+    fn local_addr(&self) -> ConnResult<SocketAddr> {
+        Ok(self.local_addr)
+    }
+
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        Some(self.remote_addr)
+    }
+
+    async fn close(&self) -> ConnResult<()> {
+        let mut writer = self.writer.lock().await;
+        writer.shutdown().await?;
+        Ok(())
+    }
+}
@@ -9,6 +9,11 @@ use tokio::sync::{watch, Mutex};
 mod udp_mux_conn;
 pub use udp_mux_conn::{UDPMuxConn, UDPMuxConnParams, UDPMuxWriter};
 
+mod udp_mux_multi;
+pub use udp_mux_multi::{MultiUDPMuxDefault, MultiUDPMuxFromPortsParams};
+
+#[cfg(test)]
+mod udp_mux_multi_test;
 #[cfg(test)]
 mod udp_mux_test;
 
@@ -44,6 +49,15 @@ pub trait UDPMux {
     /// Get the underlying connection for a given ufrag.
     async fn get_conn(self: Arc<Self>, ufrag: &str) -> Result<Arc<dyn Conn + Send + Sync>, Error>;
 
+    /// Get the underlying connections for a given ufrag, one for each local address the
+    /// mux listens on. The agent gathers a host candidate for each of them.
+    async fn get_all_conns(
+        self: Arc<Self>,
+        ufrag: &str,
+    ) -> Result<Vec<Arc<dyn Conn + Send + Sync>>, Error> {
+        Ok(vec![self.get_conn(ufrag).await?])
+    }
+
     /// Remove the underlying connection for a given ufrag.
     async fn remove_conn_by_ufrag(&self, ufrag: &str);
 }
@@ -0,0 +1,155 @@
+use super::*;
+use crate::agent::agent_config::{InterfaceFilterFn, IpFilterFn};
+use crate::network_type::NetworkType;
+use crate::util::local_interfaces;
+
+use std::net::IpAddr;
+use tokio::net::UdpSocket;
+use util::vnet::net::Net;
+
+/// The parameters of [`MultiUDPMuxDefault::from_ports`].
+#[derive(Default)]
+pub struct MultiUDPMuxFromPortsParams {
+    /// Selects the interfaces whose addresses are listened on.
+    pub interface_filter: Arc<Option<InterfaceFilterFn>>,
+    /// Selects the addresses that are listened on.
+    pub ip_filter: Arc<Option<IpFilterFn>>,
+    /// The network types whose addresses are listened on. Defaults to UDP4 and UDP6
+    /// when empty.
+    pub network_types: Vec<NetworkType>,
+}
+
+/// MultiUDPMuxDefault muxes the connections of agents over UDP sockets on several
+/// local addresses, for example on every interface of a multi-homed, dual-stack
+/// host. The agent gathers a host candidate on each address, and every socket
+/// routes the packets it receives by ufrag.
+///
+/// An address can be listened on with a pool of ports, which spreads the receive
+/// load over several sockets. Each ufrag uses one of the ports of every address.
+pub struct MultiUDPMuxDefault {
+    /// The muxes of each local address, one for every port of its pool.
+    muxes: Vec<Vec<Arc<UDPMuxDefault>>>,
+}
+
+impl MultiUDPMuxDefault {
+    /// Creates a mux over the given muxes. Muxes of the same local address form
+    /// its pool of ports.
+    pub fn new(muxes: Vec<Arc<UDPMuxDefault>>) -> Arc<Self> {
+        let mut pools: Vec<(Option<IpAddr>, Vec<Arc<UDPMuxDefault>>)> = vec![];
+        for mux in muxes {
+            let ip = mux.params.conn.local_addr().ok().map(|addr| addr.ip());
+            match pools
+                .iter_mut()
+                .find(|(pool_ip, _)| ip.is_some() && *pool_ip == ip)
+            {
+                Some((_, pool)) => pool.push(mux),
+                None => pools.push((ip, vec![mux])),
+            }
+        }
+
+        Arc::new(Self {
+            muxes: pools.into_iter().map(|(_, pool)| pool).collect(),
+        })
+    }
+
+    /// Listens on each of the given ports of every local address. Loopback and IPv6
+    /// link-local addresses are skipped.
+    pub async fn from_ports(
+        ports: &[u16],
+        params: MultiUDPMuxFromPortsParams,
+    ) -> Result<Arc<Self>, Error> {
+        let network_types = if params.network_types.is_empty() {
+            vec![NetworkType::Udp4, NetworkType::Udp6]
+        } else {
+            params.network_types
+        };
+
+        let net = Arc::new(Net::new(None));
+        let mut ips: Vec<IpAddr> = local_interfaces(
+            &net,
+            &params.interface_filter,
+            &params.ip_filter,
+            &network_types,
+        )
+        .await
+        .into_iter()
+        .filter(|ip| match ip {
+            IpAddr::V4(_) => true,
+            IpAddr::V6(ip) => ip.segments()[0] & 0xffc0 != 0xfe80,
+        })
+        .collect();
+        ips.sort();
+
+        if ips.is_empty() || ports.is_empty() {
+            return Err(Error::ErrNoAddressAssigned);
+        }
+
+        let mut muxes = vec![];
+        for ip in ips {
+            for port in ports {
+                let socket = UdpSocket::bind(SocketAddr::new(ip, *port)).await?;
+                log::info!("Listening on {}", socket.local_addr()?);
+                muxes.push(UDPMuxDefault::new(UDPMuxParams::new(socket)));
+            }
+        }
+
+        Ok(Self::new(muxes))
+    }
+
+    /// Returns the local addresses the mux listens on.
+    pub fn local_addrs(&self) -> Vec<SocketAddr> {
+        self.muxes
+            .iter()
+            .flatten()
+            .filter_map(|mux| mux.params.conn.local_addr().ok())
+            .collect()
+    }
+
+    // Picks the mux of a pool that the connections of ufrag use.
+    fn mux_for_ufrag<'a>(pool: &'a [Arc<UDPMuxDefault>], ufrag: &str) -> &'a Arc<UDPMuxDefault> {
+        let hash = ufrag.bytes().fold(0usize, |hash, b| {
+            hash.wrapping_mul(31).wrapping_add(b as usize)
+        });
+        &pool[hash % pool.len()]
+    }
+}
+
+#[async_trait]
+impl UDPMux for MultiUDPMuxDefault {
+    async fn close(&self) -> Result<(), Error> {
+        let mut result = Ok(());
+        for mux in self.muxes.iter().flatten() {
+            if let Err(err) = mux.close().await {
+                result = Err(err);
+            }
+        }
+        result
+    }
+
+    async fn get_conn(self: Arc<Self>, ufrag: &str) -> Result<Arc<dyn Conn + Send + Sync>, Error> {
+        let pool = self.muxes.first().ok_or(Error::ErrNoAddressAssigned)?;
+        Arc::clone(Self::mux_for_ufrag(pool, ufrag))
+            .get_conn(ufrag)
+            .await
+    }
+
+    async fn get_all_conns(
+        self: Arc<Self>,
+        ufrag: &str,
+    ) -> Result<Vec<Arc<dyn Conn + Send + Sync>>, Error> {
+        let mut conns = vec![];
+        for pool in &self.muxes {
+            let conn = Arc::clone(Self::mux_for_ufrag(pool, ufrag))
+                .get_conn(ufrag)
+                .await?;
+            conns.push(conn);
+        }
+        Ok(conns)
+    }
+
+    async fn remove_conn_by_ufrag(&self, ufrag: &str) {
+        for mux in self.muxes.iter().flatten() {
+            mux.remove_conn_by_ufrag(ufrag).await;
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use super::*;
+use crate::error::Result;
+use stun::agent::TransactionId;
+use stun::message::{Message, Setter, BINDING_REQUEST};
+use stun::textattrs::Username;
+
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::time::timeout;
+
+async fn new_mux(addr: &str) -> Result<Arc<UDPMuxDefault>> {
+    let socket = UdpSocket::bind(addr).await?;
+    Ok(UDPMuxDefault::new(UDPMuxParams::new(socket)))
+}
+
+fn binding_request(ufrag: &str) -> Vec<u8> {
+    let mut m = Message::new();
+    let setters: Vec<Box<dyn Setter>> = vec![
+        Box::new(BINDING_REQUEST),
+        Box::new(TransactionId::new()),
+        Box::new(Username::new(ATTR_USERNAME, format!("{ufrag}:remote"))),
+    ];
+    m.build(&setters).unwrap();
+    m.raw
+}
+
+#[tokio::test]
+async fn test_multi_udp_mux_routes_by_ufrag() -> Result<()> {
+    let mux = MultiUDPMuxDefault::new(vec![
+        new_mux("127.0.0.1:0").await?,
+        new_mux("[::1]:0").await?,
+    ]);
+    assert_eq!(mux.local_addrs().len(), 2);
+
+    let conns1 = Arc::clone(&mux).get_all_conns("ufrag1").await?;
+    let conns2 = Arc::clone(&mux).get_all_conns("ufrag2").await?;
+    assert_eq!(conns1.len(), 2, "one conn for each local address");
+    assert_eq!(conns2.len(), 2, "one conn for each local address");
+
+    for (conn1, conn2) in conns1.iter().zip(conns2.iter()) {
+        let local_addr = conn1.local_addr()?;
+        assert_eq!(local_addr, conn2.local_addr()?);
+
+        let remote = if local_addr.is_ipv4() {
+            UdpSocket::bind("127.0.0.1:0").await?
+        } else {
+            UdpSocket::bind("[::1]:0").await?
+        };
+        let request = binding_request("ufrag2");
+        remote.send_to(&request, local_addr).await?;
+
+        let mut buf = vec![0u8; RECEIVE_MTU];
+        let (n, from) = timeout(Duration::from_secs(5), conn2.recv_from(&mut buf))
+            .await
+            .expect("ufrag2 should receive its binding request")?;
+        assert_eq!(&buf[..n], &request[..]);
+        assert_eq!(from, remote.local_addr()?);
+
+        // Once ufrag2 answered, packets of the remote address are routed to it.
+        conn2.send_to(b"reply", from).await?;
+        let n = timeout(Duration::from_secs(5), remote.recv(&mut buf))
+            .await
+            .expect("the remote should receive the reply")?;
+        assert_eq!(&buf[..n], b"reply");
+
+        remote.send_to(b"data", local_addr).await?;
+        let (n, _) = timeout(Duration::from_secs(5), conn2.recv_from(&mut buf))
+            .await
+            .expect("ufrag2 should receive data of its remote address")?;
+        assert_eq!(&buf[..n], b"data");
+
+        assert!(
+            timeout(Duration::from_millis(100), conn1.recv_from(&mut buf))
+                .await
+                .is_err(),
+            "ufrag1 should not receive packets of ufrag2"
+        );
+    }
+
+    mux.close().await?;
+    assert!(Arc::clone(&mux).get_all_conns("ufrag3").await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_multi_udp_mux_port_pool() -> Result<()> {
+    let mux = MultiUDPMuxDefault::new(vec![
+        new_mux("127.0.0.1:0").await?,
+        new_mux("127.0.0.1:0").await?,
+    ]);
+
+    let mut ports = HashSet::new();
+    for i in 0..16 {
+        let ufrag = format!("ufrag{i}");
+        let conns = Arc::clone(&mux).get_all_conns(&ufrag).await?;
+        assert_eq!(conns.len(), 1, "the ports of an address form one pool");
+
+        let port = conns[0].local_addr()?.port();
+        let conn = Arc::clone(&mux).get_conn(&ufrag).await?;
+        assert_eq!(
+            conn.local_addr()?.port(),
+            port,
+            "a ufrag sticks to its port"
+        );
+        ports.insert(port);
+    }
+    assert_eq!(ports.len(), 2, "ufrags should be spread over the pool");
+
+    mux.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_multi_udp_mux_from_ports() -> Result<()> {
+    let mux = MultiUDPMuxDefault::from_ports(
+        &[0, 0],
+        MultiUDPMuxFromPortsParams {
+            ip_filter: Arc::new(Some(Box::new(|ip: std::net::IpAddr| ip.is_ipv4()))),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    // A host without a non-loopback IPv4 address has nothing to listen on.
+    let mux = match mux {
+        Ok(mux) => mux,
+        Err(err) => {
+            assert_eq!(err, Error::ErrNoAddressAssigned);
+            return Ok(());
+        }
+    };
+
+    let local_addrs = mux.local_addrs();
+    assert_eq!(local_addrs.len() % 2, 0, "two ports for each address");
+    for addr in &local_addrs {
+        assert!(addr.is_ipv4() && !addr.ip().is_loopback() && addr.port() != 0);
+    }
+
+    let conns = Arc::clone(&mux).get_all_conns("ufrag").await?;
+    assert_eq!(conns.len(), local_addrs.len() / 2);
+
+    mux.close().await?;
+
+    Ok(())
+}
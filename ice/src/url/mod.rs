@@ -110,6 +110,10 @@ pub struct Url {
     pub port: u16,
     pub username: String,
     pub password: String,
+    /// The access token credential of a TURN server that authorizes clients with
+    /// a third party (RFC 7635). If set, `username` is the key id of the token and
+    /// `password` is unused.
+    pub oauth_credential: Option<turn::auth::oauth::OAuthCredential>,
     pub proto: ProtoType,
 }
 
@@ -232,6 +236,7 @@ impl Url {
             port,
             username: "".to_owned(),
             password: "".to_owned(),
+            oauth_credential: None,
             proto,
         })
     }
@@ -10,7 +10,10 @@ use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 use stun::{agent::*, attributes::*, integrity::*, message::*, textattrs::*, xoraddr::*};
 use tokio::time::Duration;
-use util::{vnet::net::*, Conn};
+use util::{
+    vnet::{interface::Interface, net::*},
+    Conn,
+};
 
 pub fn create_addr(_network: NetworkType, ip: IpAddr, port: u16) -> SocketAddr {
     /*if network.is_tcp(){
@@ -93,8 +96,18 @@ pub async fn local_interfaces(
     ip_filter: &Option<IpFilterFn>,
     network_types: &[NetworkType],
 ) -> HashSet<IpAddr> {
-    let mut ips = HashSet::new();
     let interfaces = vnet.get_interfaces().await;
+    filter_local_ips(&interfaces, interface_filter, ip_filter, network_types)
+}
+
+/// Returns the addresses of interfaces that candidates can be gathered on.
+pub(crate) fn filter_local_ips(
+    interfaces: &[Interface],
+    interface_filter: &Option<InterfaceFilterFn>,
+    ip_filter: &Option<IpFilterFn>,
+    network_types: &[NetworkType],
+) -> HashSet<IpAddr> {
+    let mut ips = HashSet::new();
 
     let (mut ipv4requested, mut ipv6requested) = (false, false);
     for typ in network_types {
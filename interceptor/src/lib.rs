@@ -20,6 +20,7 @@ pub mod report;
 pub mod stats;
 pub mod stream_info;
 pub mod stream_reader;
+pub mod sync;
 pub mod twcc;
 
 pub use error::Error;
@@ -0,0 +1,379 @@
+#[cfg(test)]
+mod sync_test;
+
+use crate::error::Result;
+use crate::stream_info::StreamInfo;
+use crate::{Attributes, Interceptor, RTCPReader, RTCPWriter, RTPReader, RTPWriter};
+
+use async_trait::async_trait;
+use rtcp::sender_report::SenderReport;
+use rtcp::source_description::{SdesType, SourceDescription};
+use rtp::extension::abs_send_time_extension::unix2ntp;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use util::sync::Mutex;
+use util::Unmarshal;
+
+/// the number of sender reports the clock rate of a stream is estimated from
+const MAX_SENDER_REPORTS: usize = 20;
+/// the sender reports of a clock rate estimate span at least this many seconds
+const MIN_ESTIMATE_SECONDS: f64 = 1.0;
+/// estimates that differ more from the negotiated clock rate are not used
+const MAX_CLOCK_RATE_DEVIATION: f64 = 0.05;
+/// a sender report that is further off the current mapping restarts the estimate
+const MAX_MAPPING_ERROR_SECONDS: f64 = 1.0;
+/// the weight of a new transit time in the running average
+const TRANSIT_SMOOTHING: f64 = 1.0 / 16.0;
+/// the number of CNAMEs that are kept for sources that aren't bound as streams
+const MAX_UNBOUND_CNAMES: usize = 64;
+
+const NTP_FRACTION: f64 = (1u64 << 32) as f64;
+/// the seconds between the NTP epoch and the unix epoch
+const NTP_UNIX_OFFSET: f64 = 2_208_988_800.0;
+
+/// SyncOffset is the extra playout delay of two streams of the same source, for example the
+/// audio and video of a camera, that brings them in sync. At most one of them is not zero.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SyncOffset {
+    /// the extra playout delay of the stream
+    pub delay: Duration,
+    /// the extra playout delay of the other stream
+    pub other_delay: Duration,
+}
+
+#[derive(Default)]
+struct StreamSync {
+    clock_rate: f64,
+    /// (NTP seconds, unwrapped RTP timestamp) of the last sender reports
+    reports: VecDeque<(f64, i64)>,
+    /// the estimated clock rate of the sender
+    rate: f64,
+    /// the running average of the arrival time minus the capture time, in seconds
+    transit: Option<f64>,
+}
+
+impl StreamSync {
+    fn new(clock_rate: u32) -> Self {
+        StreamSync {
+            clock_rate: clock_rate as f64,
+            rate: clock_rate as f64,
+            ..Default::default()
+        }
+    }
+
+    fn process_sender_report(&mut self, sr: &SenderReport) {
+        if self.clock_rate == 0.0 {
+            return;
+        }
+
+        let ntp = sr.ntp_time as f64 / NTP_FRACTION;
+        if let Some(&(last_ntp, _)) = self.reports.back() {
+            if ntp <= last_ntp {
+                // a duplicate, or reordered
+                return;
+            }
+            let expected = self.capture_ntp(sr.rtp_time).unwrap_or(ntp);
+            if (expected - ntp).abs() > MAX_MAPPING_ERROR_SECONDS {
+                // the sender restarted the stream
+                self.reports.clear();
+                self.rate = self.clock_rate;
+            }
+        }
+
+        let rtp = self.unwrap_rtp_time(sr.rtp_time);
+        if self.reports.len() == MAX_SENDER_REPORTS {
+            self.reports.pop_front();
+        }
+        self.reports.push_back((ntp, rtp));
+        self.estimate_rate();
+    }
+
+    /// estimate_rate fits a line through the sender reports, whose slope is the rate of the
+    /// RTP clock on the NTP clock of the sender
+    fn estimate_rate(&mut self) {
+        let (first, last) = match (self.reports.front(), self.reports.back()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return,
+        };
+        if last.0 - first.0 < MIN_ESTIMATE_SECONDS {
+            return;
+        }
+
+        let n = self.reports.len() as f64;
+        let mean_ntp = self.reports.iter().map(|r| r.0 - first.0).sum::<f64>() / n;
+        let mean_rtp = self
+            .reports
+            .iter()
+            .map(|r| (r.1 - first.1) as f64)
+            .sum::<f64>()
+            / n;
+        let (mut covariance, mut variance) = (0.0, 0.0);
+        for &(ntp, rtp) in &self.reports {
+            let dt = ntp - first.0 - mean_ntp;
+            covariance += dt * ((rtp - first.1) as f64 - mean_rtp);
+            variance += dt * dt;
+        }
+
+        let rate = covariance / variance;
+        self.rate = if (rate / self.clock_rate - 1.0).abs() <= MAX_CLOCK_RATE_DEVIATION {
+            rate
+        } else {
+            self.clock_rate
+        };
+    }
+
+    fn unwrap_rtp_time(&self, rtp_time: u32) -> i64 {
+        match self.reports.back() {
+            Some(&(_, last)) => last + rtp_time.wrapping_sub(last as u32) as i32 as i64,
+            None => rtp_time as i64,
+        }
+    }
+
+    /// capture_ntp returns the NTP time of an RTP timestamp, in seconds
+    fn capture_ntp(&self, rtp_time: u32) -> Option<f64> {
+        let &(ntp, rtp) = self.reports.back()?;
+        let elapsed = self.unwrap_rtp_time(rtp_time) - rtp;
+        Some(ntp + elapsed as f64 / self.rate)
+    }
+
+    fn process_rtp(&mut self, rtp_time: u32, arrival: SystemTime) {
+        if let Some(capture) = self.capture_ntp(rtp_time) {
+            let transit = unix2ntp(arrival) as f64 / NTP_FRACTION - capture;
+            self.transit = Some(match self.transit {
+                Some(average) => average + (transit - average) * TRANSIT_SMOOTHING,
+                None => transit,
+            });
+        }
+    }
+}
+
+#[derive(Default)]
+struct SyncInternal {
+    streams: Mutex<HashMap<u32, StreamSync>>,
+    /// CNAMEs from source descriptions, which may arrive before the streams are bound.
+    /// Lock streams first when locking both.
+    cnames: Mutex<HashMap<u32, String>>,
+}
+
+/// SyncInterceptor maps the RTP timestamps of the remote streams to the NTP time of the
+/// sender, with the mapping of the RTCP sender reports. It estimates the clock rate of the
+/// sender from the recent sender reports, so the mapping doesn't drift over time, and tracks
+/// how late the packets of every stream arrive after their capture, which gives the playout
+/// delay that synchronizes the streams of a source.
+///
+/// Sender reports are only seen when the RTCP of the receivers is read.
+pub struct SyncInterceptor {
+    internal: Arc<SyncInternal>,
+    now_gen: Arc<dyn Fn() -> SystemTime + Send + Sync>,
+}
+
+impl Default for SyncInterceptor {
+    fn default() -> Self {
+        SyncInterceptor::new()
+    }
+}
+
+impl SyncInterceptor {
+    pub fn new() -> Self {
+        SyncInterceptor {
+            internal: Default::default(),
+            now_gen: Arc::new(SystemTime::now),
+        }
+    }
+
+    fn with_time_gen<F>(now_gen: F) -> Self
+    where
+        F: Fn() -> SystemTime + Send + Sync + 'static,
+    {
+        SyncInterceptor {
+            internal: Default::default(),
+            now_gen: Arc::new(now_gen),
+        }
+    }
+
+    /// capture_time returns the time on the clock of the sender at which the media with the
+    /// RTP timestamp of a remote stream was captured, or None before its first sender report
+    pub fn capture_time(&self, ssrc: u32, rtp_timestamp: u32) -> Option<SystemTime> {
+        let streams = self.internal.streams.lock();
+        let capture = streams.get(&ssrc)?.capture_ntp(rtp_timestamp)? - NTP_UNIX_OFFSET;
+        if capture < 0.0 {
+            return None;
+        }
+        UNIX_EPOCH.checked_add(Duration::from_secs_f64(capture))
+    }
+
+    /// cname returns the canonical name of the source of a remote stream, which is the same
+    /// for all streams that are played in sync
+    pub fn cname(&self, ssrc: u32) -> Option<String> {
+        let cnames = self.internal.cnames.lock();
+        cnames.get(&ssrc).cloned()
+    }
+
+    /// sync_offset returns the extra playout delay of two remote streams that synchronizes
+    /// them, when both are played with the same jitter buffer delay. It returns None until
+    /// both streams have a sender report and a packet that arrived after it.
+    pub fn sync_offset(&self, ssrc: u32, other_ssrc: u32) -> Option<SyncOffset> {
+        let streams = self.internal.streams.lock();
+        let transit = streams.get(&ssrc)?.transit?;
+        let other_transit = streams.get(&other_ssrc)?.transit?;
+
+        // The stream that arrives later after its capture sets the pace
+        let difference = transit - other_transit;
+        Some(if difference > 0.0 {
+            SyncOffset {
+                delay: Duration::ZERO,
+                other_delay: Duration::from_secs_f64(difference),
+            }
+        } else {
+            SyncOffset {
+                delay: Duration::from_secs_f64(-difference),
+                other_delay: Duration::ZERO,
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl Interceptor for SyncInterceptor {
+    /// bind_rtcp_reader lets you modify any incoming RTCP packets. It is called once per sender/receiver, however this might
+    /// change in the future. The returned method will be called once per packet batch.
+    async fn bind_rtcp_reader(
+        &self,
+        reader: Arc<dyn RTCPReader + Send + Sync>,
+    ) -> Arc<dyn RTCPReader + Send + Sync> {
+        Arc::new(SyncRTCPReader {
+            internal: Arc::clone(&self.internal),
+            parent_rtcp_reader: reader,
+        })
+    }
+
+    /// bind_rtcp_writer lets you modify any outgoing RTCP packets. It is called once per PeerConnection. The returned method
+    /// will be called once per packet batch.
+    async fn bind_rtcp_writer(
+        &self,
+        writer: Arc<dyn RTCPWriter + Send + Sync>,
+    ) -> Arc<dyn RTCPWriter + Send + Sync> {
+        writer
+    }
+
+    /// bind_local_stream lets you modify any outgoing RTP packets. It is called once for per LocalStream. The returned method
+    /// will be called once per rtp packet.
+    async fn bind_local_stream(
+        &self,
+        _info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync> {
+        writer
+    }
+
+    /// unbind_local_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_local_stream(&self, _info: &StreamInfo) {}
+
+    /// bind_remote_stream lets you modify any incoming RTP packets. It is called once for per RemoteStream. The returned method
+    /// will be called once per rtp packet.
+    async fn bind_remote_stream(
+        &self,
+        info: &StreamInfo,
+        reader: Arc<dyn RTPReader + Send + Sync>,
+    ) -> Arc<dyn RTPReader + Send + Sync> {
+        {
+            let mut streams = self.internal.streams.lock();
+            streams.insert(info.ssrc, StreamSync::new(info.clock_rate));
+        }
+
+        Arc::new(SyncRTPReader {
+            internal: Arc::clone(&self.internal),
+            parent_rtp_reader: reader,
+            now_gen: Arc::clone(&self.now_gen),
+        })
+    }
+
+    /// unbind_remote_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_remote_stream(&self, info: &StreamInfo) {
+        {
+            let mut streams = self.internal.streams.lock();
+            streams.remove(&info.ssrc);
+        }
+        let mut cnames = self.internal.cnames.lock();
+        cnames.remove(&info.ssrc);
+    }
+
+    /// close closes the Interceptor, cleaning up any data if necessary.
+    async fn close(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+struct SyncRTPReader {
+    internal: Arc<SyncInternal>,
+    parent_rtp_reader: Arc<dyn RTPReader + Send + Sync>,
+    now_gen: Arc<dyn Fn() -> SystemTime + Send + Sync>,
+}
+
+#[async_trait]
+impl RTPReader for SyncRTPReader {
+    /// read a rtp packet
+    async fn read(&self, buf: &mut [u8], attributes: &Attributes) -> Result<(usize, Attributes)> {
+        let (n, attr) = self.parent_rtp_reader.read(buf, attributes).await?;
+
+        let mut b = &buf[..n];
+        let header = rtp::header::Header::unmarshal(&mut b)?;
+        {
+            let mut streams = self.internal.streams.lock();
+            if let Some(stream) = streams.get_mut(&header.ssrc) {
+                stream.process_rtp(header.timestamp, (self.now_gen)());
+            }
+        }
+
+        Ok((n, attr))
+    }
+}
+
+struct SyncRTCPReader {
+    internal: Arc<SyncInternal>,
+    parent_rtcp_reader: Arc<dyn RTCPReader + Send + Sync>,
+}
+
+#[async_trait]
+impl RTCPReader for SyncRTCPReader {
+    /// read a batch of rtcp packets
+    async fn read(&self, buf: &mut [u8], attributes: &Attributes) -> Result<(usize, Attributes)> {
+        let (n, attr) = self.parent_rtcp_reader.read(buf, attributes).await?;
+
+        let mut b = &buf[..n];
+        let pkts = rtcp::packet::unmarshal(&mut b)?;
+        for p in &pkts {
+            if let Some(sr) = p.as_any().downcast_ref::<SenderReport>() {
+                let mut streams = self.internal.streams.lock();
+                if let Some(stream) = streams.get_mut(&sr.ssrc) {
+                    stream.process_sender_report(sr);
+                }
+            } else if let Some(sdes) = p.as_any().downcast_ref::<SourceDescription>() {
+                let streams = self.internal.streams.lock();
+                let mut cnames = self.internal.cnames.lock();
+                for chunk in &sdes.chunks {
+                    // Only a limited number of unknown sources is remembered, the CNAME of a
+                    // stream that is bound later is taken from a following source description
+                    if !streams.contains_key(&chunk.source)
+                        && !cnames.contains_key(&chunk.source)
+                        && cnames.len() >= MAX_UNBOUND_CNAMES
+                    {
+                        continue;
+                    }
+
+                    for item in &chunk.items {
+                        if item.sdes_type == SdesType::SdesCname {
+                            cnames.insert(
+                                chunk.source,
+                                String::from_utf8_lossy(&item.text).into_owned(),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok((n, attr))
+    }
+}
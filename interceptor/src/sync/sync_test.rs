@@ -0,0 +1,264 @@
+use super::*;
+use crate::mock::mock_stream::MockStream;
+use crate::mock::mock_time::MockTime;
+
+use bytes::Bytes;
+use rtcp::source_description::{SourceDescriptionChunk, SourceDescriptionItem};
+
+fn sender_report(ssrc: u32, ntp: SystemTime, rtp_time: u32) -> SenderReport {
+    SenderReport {
+        ssrc,
+        ntp_time: unix2ntp(ntp),
+        rtp_time,
+        ..Default::default()
+    }
+}
+
+fn rtp_packet(ssrc: u32, timestamp: u32) -> rtp::packet::Packet {
+    rtp::packet::Packet {
+        header: rtp::header::Header {
+            version: 2,
+            ssrc,
+            timestamp,
+            ..Default::default()
+        },
+        payload: Bytes::from_static(&[0x01]),
+    }
+}
+
+fn assert_close(actual: SystemTime, expected: SystemTime, tolerance: Duration) {
+    let difference = actual
+        .duration_since(expected)
+        .unwrap_or_else(|err| err.duration());
+    assert!(
+        difference <= tolerance,
+        "{:?} is {:?} off {:?}",
+        actual,
+        difference,
+        expected
+    );
+}
+
+async fn receive_sender_report(stream: &MockStream, sr: SenderReport) {
+    stream.receive_rtcp(vec![Box::new(sr)]).await;
+    stream.read_rtcp().await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn test_sync_interceptor_capture_time() -> Result<()> {
+    let icpr = Arc::new(SyncInterceptor::new());
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 123456,
+            clock_rate: 90000,
+            ..Default::default()
+        },
+        Arc::clone(&icpr) as Arc<dyn Interceptor + Send + Sync>,
+    )
+    .await;
+
+    assert_eq!(icpr.capture_time(123456, 0), None);
+
+    let t0 = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let rtp_time = u32::MAX - 44_999;
+    receive_sender_report(&stream, sender_report(123456, t0, rtp_time)).await;
+
+    let tolerance = Duration::from_micros(1);
+    assert_close(icpr.capture_time(123456, rtp_time).unwrap(), t0, tolerance);
+    // one second later, after the RTP timestamp wrapped around
+    assert_close(
+        icpr.capture_time(123456, 45_000).unwrap(),
+        t0 + Duration::from_secs(1),
+        tolerance,
+    );
+    assert_close(
+        icpr.capture_time(123456, rtp_time - 9000).unwrap(),
+        t0 - Duration::from_millis(100),
+        tolerance,
+    );
+    assert_eq!(icpr.capture_time(654321, rtp_time), None);
+
+    stream.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sync_interceptor_clock_drift() -> Result<()> {
+    let icpr = Arc::new(SyncInterceptor::new());
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 123456,
+            clock_rate: 90000,
+            ..Default::default()
+        },
+        Arc::clone(&icpr) as Arc<dyn Interceptor + Send + Sync>,
+    )
+    .await;
+
+    // The RTP clock of the sender runs 0.1% fast
+    let t0 = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    for i in 0..4u32 {
+        let sr = sender_report(
+            123456,
+            t0 + Duration::from_secs(5 * i as u64),
+            i * 5 * 90090,
+        );
+        receive_sender_report(&stream, sr).await;
+    }
+
+    // After ten minutes, the nominal clock rate would be 600ms off
+    let capture = icpr.capture_time(123456, 15 * 90090 + 600 * 90090).unwrap();
+    assert_close(
+        capture,
+        t0 + Duration::from_secs(615),
+        Duration::from_millis(1),
+    );
+
+    // A restarted stream starts over with the nominal clock rate
+    let t1 = t0 + Duration::from_secs(20);
+    receive_sender_report(&stream, sender_report(123456, t1, 5000)).await;
+    assert_close(
+        icpr.capture_time(123456, 5000 + 90000).unwrap(),
+        t1 + Duration::from_secs(1),
+        Duration::from_micros(1),
+    );
+
+    stream.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sync_interceptor_sync_offset() -> Result<()> {
+    let mt = Arc::new(MockTime::default());
+    let icpr = {
+        let mt = Arc::clone(&mt);
+        Arc::new(SyncInterceptor::with_time_gen(move || mt.now()))
+    };
+
+    let audio = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            clock_rate: 48000,
+            ..Default::default()
+        },
+        Arc::clone(&icpr) as Arc<dyn Interceptor + Send + Sync>,
+    )
+    .await;
+    let video = MockStream::new(
+        &StreamInfo {
+            ssrc: 2,
+            clock_rate: 90000,
+            ..Default::default()
+        },
+        Arc::clone(&icpr) as Arc<dyn Interceptor + Send + Sync>,
+    )
+    .await;
+
+    audio
+        .receive_rtcp(vec![Box::new(SourceDescription {
+            chunks: [1, 2]
+                .iter()
+                .map(|&source| SourceDescriptionChunk {
+                    source,
+                    items: vec![SourceDescriptionItem {
+                        sdes_type: SdesType::SdesCname,
+                        text: Bytes::from_static(b"camera"),
+                    }],
+                })
+                .collect(),
+        })])
+        .await;
+    audio.read_rtcp().await.unwrap()?;
+    assert_eq!(icpr.cname(1), Some("camera".to_owned()));
+    assert_eq!(icpr.cname(2), Some("camera".to_owned()));
+    assert_eq!(icpr.cname(3), None);
+
+    // The streams have different RTP timestamps for the same capture time
+    let t0 = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    receive_sender_report(&audio, sender_report(1, t0, 1000)).await;
+    receive_sender_report(&video, sender_report(2, t0, 500_000)).await;
+    assert_eq!(icpr.sync_offset(1, 2), None);
+
+    // Media captured every 100ms, audio arrives 50ms after its capture and video 150ms
+    for i in 0..10u32 {
+        let capture = t0 + Duration::from_millis(100 * i as u64);
+
+        mt.set_now(capture + Duration::from_millis(50));
+        audio.receive_rtp(rtp_packet(1, 1000 + i * 4800)).await;
+        audio.read_rtp().await.unwrap()?;
+
+        mt.set_now(capture + Duration::from_millis(150));
+        video.receive_rtp(rtp_packet(2, 500_000 + i * 9000)).await;
+        video.read_rtp().await.unwrap()?;
+    }
+
+    let offset = icpr.sync_offset(1, 2).unwrap();
+    assert!(
+        (offset.delay.as_secs_f64() - 0.1).abs() < 1e-6 && offset.other_delay == Duration::ZERO,
+        "{:?}",
+        offset
+    );
+    let offset = icpr.sync_offset(2, 1).unwrap();
+    assert!(
+        (offset.other_delay.as_secs_f64() - 0.1).abs() < 1e-6 && offset.delay == Duration::ZERO,
+        "{:?}",
+        offset
+    );
+
+    audio.close().await?;
+    video.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sync_interceptor_limits_unbound_cnames() -> Result<()> {
+    let icpr = Arc::new(SyncInterceptor::new());
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            clock_rate: 90000,
+            ..Default::default()
+        },
+        Arc::clone(&icpr) as Arc<dyn Interceptor + Send + Sync>,
+    )
+    .await;
+
+    let sdes = |sources: Vec<u32>| -> Vec<Box<dyn rtcp::packet::Packet + Send + Sync>> {
+        vec![Box::new(SourceDescription {
+            chunks: sources
+                .into_iter()
+                .map(|source| SourceDescriptionChunk {
+                    source,
+                    items: vec![SourceDescriptionItem {
+                        sdes_type: SdesType::SdesCname,
+                        text: Bytes::from_static(b"camera"),
+                    }],
+                })
+                .collect(),
+        })]
+    };
+
+    // A peer sends the CNAMEs of many sources that are never bound
+    for i in 0..10u32 {
+        stream
+            .receive_rtcp(sdes((0..20).map(|j| 1000 + i * 20 + j).collect()))
+            .await;
+        stream.read_rtcp().await.unwrap()?;
+    }
+    assert_eq!(icpr.internal.cnames.lock().len(), MAX_UNBOUND_CNAMES);
+    assert_eq!(icpr.cname(1000), Some("camera".to_owned()));
+    assert_eq!(icpr.cname(1199), None);
+
+    // The CNAME of a bound stream is still recorded
+    stream.receive_rtcp(sdes(vec![1, 1199])).await;
+    stream.read_rtcp().await.unwrap()?;
+    assert_eq!(icpr.cname(1), Some("camera".to_owned()));
+    assert_eq!(icpr.cname(1199), None);
+
+    stream.close().await?;
+
+    Ok(())
+}
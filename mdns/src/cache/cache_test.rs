@@ -0,0 +1,159 @@
+use super::*;
+use crate::message::name::Name;
+use crate::message::resource::ResourceHeader;
+use crate::message::{DnsClass, DNSCLASS_INET};
+use crate::Error;
+
+type Result<T> = std::result::Result<T, Error>;
+
+fn new_record(name: &str, data: RecordData, ttl: u32) -> Result<Record> {
+    let typ = match &data {
+        RecordData::Addr(ip) if ip.is_ipv4() => DnsType::A,
+        RecordData::Addr(_) => DnsType::Aaaa,
+        RecordData::Ptr(_) => DnsType::Ptr,
+        RecordData::Srv { .. } => DnsType::Srv,
+        RecordData::Txt(_) => DnsType::Txt,
+    };
+    Ok(Record {
+        header: ResourceHeader {
+            name: Name::new(name)?,
+            typ,
+            class: DNSCLASS_INET,
+            ttl,
+            ..Default::default()
+        },
+        data,
+    })
+}
+
+fn new_src() -> SocketAddr {
+    "192.168.1.2:5353".parse().unwrap()
+}
+
+#[test]
+fn test_cache_expire() -> Result<()> {
+    let now = Instant::now();
+    let mut cache = Cache::default();
+    let addr = RecordData::Addr("192.168.1.2".parse().unwrap());
+    cache.insert(new_record("peer.local.", addr.clone(), 10)?, new_src(), now);
+
+    let cached = cache.address("PEER.local.", now + Duration::from_secs(4));
+    assert_eq!(cached.map(|r| r.record.header.ttl), Some(6));
+    assert!(cache.address("other.local.", now).is_none());
+
+    // A refresh extends the TTL.
+    cache.insert(
+        new_record("peer.local.", addr, 10)?,
+        new_src(),
+        now + Duration::from_secs(8),
+    );
+    assert!(cache
+        .address("peer.local.", now + Duration::from_secs(12))
+        .is_some());
+
+    cache.expire(now + Duration::from_secs(18));
+    assert!(cache.records.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_cache_goodbye() -> Result<()> {
+    let now = Instant::now();
+    let mut cache = Cache::default();
+    let addr = RecordData::Addr("192.168.1.2".parse().unwrap());
+    cache.insert(
+        new_record("peer.local.", addr.clone(), 120)?,
+        new_src(),
+        now,
+    );
+
+    cache.insert(new_record("peer.local.", addr.clone(), 0)?, new_src(), now);
+    assert!(cache.address("peer.local.", now).is_some());
+    assert!(cache.address("peer.local.", now + GOODBYE_DELAY).is_none());
+
+    // A goodbye for a record that isn't cached is ignored.
+    cache.insert(new_record("other.local.", addr, 0)?, new_src(), now);
+    assert!(cache.address("other.local.", now).is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_cache_flush() -> Result<()> {
+    let now = Instant::now();
+    let mut cache = Cache::default();
+    let old = RecordData::Addr("192.168.1.2".parse().unwrap());
+    let new = RecordData::Addr("192.168.1.3".parse().unwrap());
+    cache.insert(new_record("peer.local.", old, 120)?, new_src(), now);
+
+    let mut record = new_record("peer.local.", new.clone(), 120)?;
+    record.header.class = DnsClass(DNSCLASS_INET.0 | CACHE_FLUSH_BIT);
+    cache.insert(record, new_src(), now + Duration::from_secs(2));
+
+    assert_eq!(cache.records.len(), 1, "old address should be flushed");
+    assert_eq!(
+        cache
+            .address("peer.local.", now + Duration::from_secs(2))
+            .map(|r| r.record.data),
+        Some(new)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cache_known_answers() -> Result<()> {
+    let now = Instant::now();
+    let mut cache = Cache::default();
+    let ptr = RecordData::Ptr("Living Room._webrtc._udp.local.".to_owned());
+    cache.insert(new_record("_webrtc._udp.local.", ptr, 120)?, new_src(), now);
+
+    let known = cache.known_answers("_webrtc._udp.local.", DnsType::Ptr, now);
+    assert_eq!(known.len(), 1);
+    assert!(cache
+        .known_answers("_webrtc._udp.local.", DnsType::Srv, now)
+        .is_empty());
+    assert!(
+        cache
+            .known_answers(
+                "_webrtc._udp.local.",
+                DnsType::Ptr,
+                now + Duration::from_secs(61)
+            )
+            .is_empty(),
+        "records with less than half of their TTL left are not known answers"
+    );
+
+    let sources = cache.records_by_source(now + Duration::from_secs(20));
+    assert_eq!(sources.len(), 1);
+    assert_eq!(sources[0].0, new_src());
+    assert_eq!(sources[0].1[0].header.ttl, 100);
+
+    Ok(())
+}
+
+#[test]
+fn test_cache_eviction() -> Result<()> {
+    let now = Instant::now();
+    let mut cache = Cache::default();
+    for i in 0..MAX_CACHED_RECORDS + 1 {
+        let ptr = RecordData::Ptr(format!("{i}._webrtc._udp.local."));
+        cache.insert(
+            new_record("_webrtc._udp.local.", ptr, 10 + i as u32)?,
+            new_src(),
+            now,
+        );
+    }
+
+    assert_eq!(cache.records.len(), MAX_CACHED_RECORDS);
+    assert!(
+        !cache
+            .records
+            .iter()
+            .any(|r| r.record.data == RecordData::Ptr("0._webrtc._udp.local.".to_owned())),
+        "the record that expires first should be evicted"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,159 @@
+#[cfg(test)]
+mod cache_test;
+
+use crate::dnssd::{name_eq, Record, RecordData};
+use crate::message::DnsType;
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+// MAX_CACHED_RECORDS bounds the cache on busy networks. When it is full, the
+// records that expire first are evicted.
+const MAX_CACHED_RECORDS: usize = 1024;
+
+// A record with TTL zero is a goodbye. It expires after a second, so that the
+// record isn't flushed by a goodbye that is followed by a new answer.
+// RFC 6762 Section 10.1
+const GOODBYE_DELAY: Duration = Duration::from_secs(1);
+
+// The top bit of the class of a record in a response is the cache-flush bit.
+// Records of the same name and type received more than a second before it are
+// flushed. RFC 6762 Section 10.2
+const CACHE_FLUSH_BIT: u16 = 0x8000;
+const CACHE_FLUSH_DELAY: Duration = Duration::from_secs(1);
+
+// CachedRecord is a record of a response, with the address it came from.
+#[derive(Debug, Clone)]
+pub(crate) struct CachedRecord {
+    pub(crate) record: Record,
+    pub(crate) src: SocketAddr,
+    received: Instant,
+    expires: Instant,
+}
+
+impl CachedRecord {
+    // remaining_ttl returns the seconds until the record expires.
+    pub(crate) fn remaining_ttl(&self, now: Instant) -> u32 {
+        self.expires.saturating_duration_since(now).as_secs() as u32
+    }
+
+    // with_remaining_ttl returns the record with the remaining TTL.
+    fn with_remaining_ttl(&self, now: Instant) -> Record {
+        let mut record = self.record.clone();
+        record.header.ttl = self.remaining_ttl(now);
+        record
+    }
+}
+
+// Cache holds the records of received responses until their TTL expires.
+#[derive(Debug, Default)]
+pub(crate) struct Cache {
+    records: Vec<CachedRecord>,
+}
+
+impl Cache {
+    // insert adds a record of a response from src, refreshes it if it's cached,
+    // or expires it soon if its TTL is zero.
+    pub(crate) fn insert(&mut self, record: Record, src: SocketAddr, now: Instant) {
+        self.expire(now);
+
+        let ttl = record.header.ttl;
+        if record.header.class.0 & CACHE_FLUSH_BIT != 0 && ttl != 0 {
+            self.records.retain(|r| {
+                !(name_eq(&r.record.header.name.data, &record.header.name.data)
+                    && r.record.header.typ == record.header.typ
+                    && r.record.data != record.data
+                    && now.saturating_duration_since(r.received) > CACHE_FLUSH_DELAY)
+            });
+        }
+
+        let cached = self.records.iter_mut().find(|r| {
+            name_eq(&r.record.header.name.data, &record.header.name.data)
+                && r.record.data == record.data
+        });
+        if let Some(cached) = cached {
+            if ttl == 0 {
+                cached.expires = std::cmp::min(cached.expires, now + GOODBYE_DELAY);
+            } else {
+                cached.record.header.ttl = ttl;
+                cached.src = src;
+                cached.received = now;
+                cached.expires = now + Duration::from_secs(ttl as u64);
+            }
+            return;
+        }
+        if ttl == 0 {
+            return;
+        }
+
+        if self.records.len() >= MAX_CACHED_RECORDS {
+            if let Some(i) = self
+                .records
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, r)| r.expires)
+                .map(|(i, _)| i)
+            {
+                self.records.swap_remove(i);
+            }
+        }
+
+        self.records.push(CachedRecord {
+            record,
+            src,
+            received: now,
+            expires: now + Duration::from_secs(ttl as u64),
+        });
+    }
+
+    // expire removes the records whose TTL expired.
+    pub(crate) fn expire(&mut self, now: Instant) {
+        self.records.retain(|r| r.expires > now);
+    }
+
+    // address returns a cached A or AAAA record of name.
+    pub(crate) fn address(&self, name: &str, now: Instant) -> Option<CachedRecord> {
+        self.records
+            .iter()
+            .find(|r| {
+                r.expires > now
+                    && matches!(r.record.data, RecordData::Addr(_))
+                    && name_eq(&r.record.header.name.data, name)
+            })
+            .map(|r| {
+                let mut r = r.clone();
+                r.record.header.ttl = r.remaining_ttl(now);
+                r
+            })
+    }
+
+    // records_by_source returns the cached records, with their remaining TTL,
+    // grouped by the address they came from.
+    pub(crate) fn records_by_source(&self, now: Instant) -> Vec<(SocketAddr, Vec<Record>)> {
+        let mut sources: Vec<(SocketAddr, Vec<Record>)> = vec![];
+        for r in self.records.iter().filter(|r| r.expires > now) {
+            match sources.iter_mut().find(|(src, _)| *src == r.src) {
+                Some((_, records)) => records.push(r.with_remaining_ttl(now)),
+                None => sources.push((r.src, vec![r.with_remaining_ttl(now)])),
+            }
+        }
+        sources
+    }
+
+    // known_answers returns the cached records of name and typ with more than half
+    // of their TTL left, which a query includes so that responders don't repeat
+    // them. RFC 6762 Section 7.1
+    pub(crate) fn known_answers(&self, name: &str, typ: DnsType, now: Instant) -> Vec<Record> {
+        self.records
+            .iter()
+            .filter(|r| {
+                r.record.header.typ == typ
+                    && name_eq(&r.record.header.name.data, name)
+                    && r.remaining_ttl(now) > r.record.header.ttl / 2
+            })
+            .map(|r| r.with_remaining_ttl(now))
+            .collect()
+    }
+}
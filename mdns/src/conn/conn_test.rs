@@ -1,6 +1,6 @@
 #[cfg(test)]
 mod test {
-    use crate::{config::Config, conn::*};
+    use crate::{config::Config, conn::*, dnssd::ServiceInstance};
     use tokio::time::timeout;
 
     #[tokio::test]
@@ -42,4 +42,270 @@ mod test {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_register_and_browse_service() -> Result<()> {
+        let server_a = DnsConn::server(
+            SocketAddr::new(Ipv4Addr::new(0, 0, 0, 0).into(), 5353),
+            Config::default(),
+        )?;
+        let server_b = DnsConn::server(
+            SocketAddr::new(Ipv4Addr::new(0, 0, 0, 0).into(), 5353),
+            Config::default(),
+        )?;
+
+        server_a
+            .register_service(ServiceInstance {
+                instance: "peer-a".to_owned(),
+                service: "_webrtc-rs-test._udp".to_owned(),
+                host: "webrtc-rs-test-a.local".to_owned(),
+                port: 5000,
+                txt: vec!["id=a".to_owned()],
+            })
+            .await?;
+
+        let (entries_tx, mut entries_rx) = mpsc::channel(1);
+        let (close_tx, close_rx) = mpsc::channel(1);
+        let browse = tokio::spawn(async move {
+            let result = server_b
+                .browse("_webrtc-rs-test._udp", entries_tx, close_rx)
+                .await;
+            server_b.close().await?;
+            result
+        });
+
+        let entry = timeout(Duration::from_secs(10), entries_rx.recv())
+            .await
+            .expect("browse should find the service")
+            .expect("browse should not end");
+        assert_eq!(entry.instance_name, "peer-a._webrtc-rs-test._udp.local.");
+        assert_eq!(entry.host, "webrtc-rs-test-a.local.");
+        assert_eq!(entry.port, 5000);
+        assert_eq!(entry.txt, vec!["id=a".to_owned()]);
+        assert!(!entry.addrs.is_empty());
+
+        close_tx.send(()).await.unwrap();
+        browse.await.unwrap()?;
+        server_a.close().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_identical_queries_of_two_conns() -> Result<()> {
+        let server_a = Arc::new(DnsConn::server(
+            SocketAddr::new(Ipv4Addr::new(0, 0, 0, 0).into(), 5353),
+            Config::default(),
+        )?);
+
+        server_a
+            .register_service(ServiceInstance {
+                instance: "peer-a".to_owned(),
+                service: "_webrtc-rs-same._udp".to_owned(),
+                host: "webrtc-rs-test-same.local".to_owned(),
+                port: 5000,
+                txt: vec![],
+            })
+            .await?;
+        // Let the announcements pass, so that server_b doesn't know the service.
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+        let server_b = DnsConn::server(
+            SocketAddr::new(Ipv4Addr::new(0, 0, 0, 0).into(), 5353),
+            Config::default(),
+        )?;
+
+        // Both browse the same service, so their queries are the same packets
+        // from the same address. Each must still answer the other's.
+        let (a_entries_tx, _a_entries_rx) = mpsc::channel(1);
+        let (a_close_tx, a_close_rx) = mpsc::channel(1);
+        let browse_a = tokio::spawn({
+            let server_a = Arc::clone(&server_a);
+            async move {
+                server_a
+                    .browse("_webrtc-rs-same._udp", a_entries_tx, a_close_rx)
+                    .await
+            }
+        });
+        let (b_entries_tx, mut b_entries_rx) = mpsc::channel(1);
+        let (b_close_tx, b_close_rx) = mpsc::channel(1);
+        let browse_b = tokio::spawn(async move {
+            let result = server_b
+                .browse("_webrtc-rs-same._udp", b_entries_tx, b_close_rx)
+                .await;
+            server_b.close().await?;
+            result
+        });
+
+        let entry = timeout(Duration::from_secs(10), b_entries_rx.recv())
+            .await
+            .expect("browse should find the service")
+            .expect("browse should not end");
+        assert_eq!(entry.instance_name, "peer-a._webrtc-rs-same._udp.local.");
+
+        b_close_tx.send(()).await.unwrap();
+        browse_b.await.unwrap()?;
+        a_close_tx.send(()).await.unwrap();
+        browse_a.await.unwrap()?;
+        server_a.close().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_query_ipv6() -> Result<()> {
+        let server_a = DnsConn::server(
+            SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), 5353),
+            Config {
+                local_names: vec!["webrtc-rs-test-v6.local".to_owned()],
+                ..Default::default()
+            },
+        )?;
+        let server_b = DnsConn::server(
+            SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), 5353),
+            Config::default(),
+        )?;
+
+        let (close_tx, close_rx) = mpsc::channel(1);
+        let query = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            close_tx.send(()).await
+        });
+
+        let (answer, addr) = server_b.query("webrtc-rs-test-v6.local", close_rx).await?;
+        assert_eq!(answer.typ, DnsType::Aaaa);
+        assert!(addr.is_ipv6());
+
+        query.abort();
+        server_a.close().await?;
+        server_b.close().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_local_name_conflict() -> Result<()> {
+        // Another host answers for the name with its own address.
+        let other_host = UdpSocket::bind("0.0.0.0:0").await?;
+        let answer = pack_response(
+            vec![address_resource(
+                "webrtc-rs-test-conflict.local.",
+                Ipv4Addr::new(192, 0, 2, 99).into(),
+                RESPONSE_TTL,
+            )?],
+            vec![],
+        )?;
+        let answering = tokio::spawn(async move {
+            loop {
+                let _ = other_host.send_to(&answer, DEFAULT_DEST_ADDR).await;
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        });
+
+        let server_a = DnsConn::server(
+            SocketAddr::new(Ipv4Addr::new(0, 0, 0, 0).into(), 5353),
+            Config {
+                local_names: vec!["webrtc-rs-test-conflict.local".to_owned()],
+                ..Default::default()
+            },
+        )?;
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert_eq!(
+            server_a.local_names().await,
+            vec!["webrtc-rs-test-conflict-2.local".to_owned()],
+            "a taken name should be renamed"
+        );
+
+        answering.abort();
+        server_a.close().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_service_instance_conflict() -> Result<()> {
+        let server_a = DnsConn::server(
+            SocketAddr::new(Ipv4Addr::new(0, 0, 0, 0).into(), 5353),
+            Config::default(),
+        )?;
+        let server_b = DnsConn::server(
+            SocketAddr::new(Ipv4Addr::new(0, 0, 0, 0).into(), 5353),
+            Config::default(),
+        )?;
+        let service = |host: &str| ServiceInstance {
+            instance: "peer".to_owned(),
+            service: "_webrtc-rs-conflict._udp".to_owned(),
+            host: host.to_owned(),
+            port: 5000,
+            txt: vec![],
+        };
+
+        let registered = server_a
+            .register_service(service("webrtc-rs-test-host-a.local"))
+            .await?;
+        assert_eq!(registered.instance, "peer");
+
+        let registered = server_b
+            .register_service(service("webrtc-rs-test-host-b.local"))
+            .await?;
+        assert_eq!(registered.instance, "peer (2)");
+
+        server_a.close().await?;
+        server_b.close().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_goodbye_flushes_cache() -> Result<()> {
+        let server_a = DnsConn::server(
+            SocketAddr::new(Ipv4Addr::new(0, 0, 0, 0).into(), 5353),
+            Config {
+                local_names: vec!["webrtc-rs-test-goodbye.local".to_owned()],
+                ..Default::default()
+            },
+        )?;
+        let server_b = DnsConn::server(
+            SocketAddr::new(Ipv4Addr::new(0, 0, 0, 0).into(), 5353),
+            Config::default(),
+        )?;
+
+        let (_close_tx, close_rx) = mpsc::channel(1);
+        timeout(
+            Duration::from_secs(10),
+            server_b.query("webrtc-rs-test-goodbye.local", close_rx),
+        )
+        .await
+        .expect("query should be answered")?;
+        {
+            let cache = server_b.internal.cache.lock().await;
+            assert!(cache
+                .address("webrtc-rs-test-goodbye.local.", Instant::now())
+                .is_some());
+        }
+
+        server_a.close().await?;
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        {
+            let cache = server_b.internal.cache.lock().await;
+            assert!(
+                cache
+                    .address("webrtc-rs-test-goodbye.local.", Instant::now())
+                    .is_none(),
+                "goodbye should expire the cached address"
+            );
+        }
+
+        server_b.close().await?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rename() {
+        assert_eq!(next_host_name("peer.local."), "peer-2.local.");
+        assert_eq!(next_host_name("peer-2.local."), "peer-3.local.");
+        assert_eq!(next_host_name("peer-1.local."), "peer-1-2.local.");
+        assert_eq!(next_instance("Living Room"), "Living Room (2)");
+        assert_eq!(next_instance("Living Room (2)"), "Living Room (3)");
+    }
 }
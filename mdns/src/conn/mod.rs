@@ -1,39 +1,86 @@
+use crate::cache::*;
 use crate::config::*;
+use crate::dnssd::*;
 use crate::error::*;
 use crate::message::name::*;
-use crate::message::{header::*, parser::*, question::*, resource::a::*, resource::*, *};
+use crate::message::{header::*, parser::*, question::*, resource::*, *};
 
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::sync::Arc;
 use std::time::Duration;
 
 use core::sync::atomic;
 use socket2::SockAddr;
-use tokio::net::{ToSocketAddrs, UdpSocket};
+use tokio::net::UdpSocket;
 use tokio::sync::mpsc;
 use tokio::sync::Mutex;
+use tokio::time::Instant;
 
 use util::ifaces;
 
 mod conn_test;
 
 pub const DEFAULT_DEST_ADDR: &str = "224.0.0.251:5353";
+pub const DEFAULT_DEST_ADDR_V6: &str = "[ff02::fb]:5353";
 
-const INBOUND_BUFFER_SIZE: usize = 512;
+// DNS-SD responses carry several records, so accept the largest mDNS messages,
+// RFC 6762 Section 17.
+const INBOUND_BUFFER_SIZE: usize = 9000;
 const DEFAULT_QUERY_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_BROWSE_INTERVAL: Duration = Duration::from_secs(60);
 const MAX_MESSAGE_RECORDS: usize = 3;
-const RESPONSE_TTL: u32 = 120;
+pub(crate) const RESPONSE_TTL: u32 = 120;
+
+// Names are probed three times, 250ms apart, after a random delay of up to 250ms.
+// A probe that loses a simultaneous probe tiebreak is retried after a second.
+// RFC 6762 Section 8.1 and 8.2
+const PROBE_COUNT: usize = 3;
+const PROBE_INTERVAL: Duration = Duration::from_millis(250);
+const PROBE_DEFER: Duration = Duration::from_secs(1);
+
+// Names that passed probing are announced twice, a second apart.
+// RFC 6762 Section 8.3
+const ANNOUNCE_COUNT: usize = 2;
+const ANNOUNCE_INTERVAL: Duration = Duration::from_secs(1);
+
+// SENT_PACKETS is how many sent packets are remembered, to ignore them when the
+// multicast loopback returns them. The loopback returns them at once, so they
+// are forgotten after SENT_PACKET_LIFETIME.
+const SENT_PACKETS: usize = 32;
+const SENT_PACKET_LIFETIME: Duration = Duration::from_secs(1);
 
 // Conn represents a mDNS Server
 pub struct DnsConn {
-    socket: Arc<UdpSocket>,
+    internal: Arc<DnsConnInternal>,
+    query_interval: Duration,
+    close_server: mpsc::Sender<()>,
+}
+
+struct DnsConnInternal {
+    socket: UdpSocket,
     dst_addr: SocketAddr,
 
-    query_interval: Duration,
-    queries: Arc<Mutex<Vec<Query>>>,
+    queries: Mutex<Vec<Query>>,
+    browses: Mutex<Vec<Browse>>,
+    probes: Mutex<Vec<Probe>>,
 
-    is_server_closed: Arc<atomic::AtomicBool>,
-    close_server: mpsc::Sender<()>,
+    // local_names and services are the names that passed probing, which are
+    // answered.
+    local_names: Mutex<Vec<String>>,
+    services: Mutex<Vec<ServiceInstance>>,
+
+    cache: Mutex<Cache>,
+    // sent are the hashes of the packets that were sent and haven't been looped
+    // back yet, and when they were sent.
+    sent: Mutex<VecDeque<(u64, Instant)>>,
+    // local_ips are the addresses of the interfaces, which looped back packets
+    // come from.
+    local_ips: Vec<IpAddr>,
+
+    is_server_closed: atomic::AtomicBool,
 }
 
 struct Query {
@@ -46,11 +93,114 @@ struct QueryResult {
     addr: SocketAddr,
 }
 
+struct Browse {
+    service_name: String,
+    browse_result_chan: mpsc::Sender<BrowseResult>,
+}
+
+// Wakeup ends a wait of a query or a browse for its results.
+enum Wakeup<T> {
+    // Resend means that the interval passed, so the question is sent again.
+    Resend,
+    // Closed means that the caller sent the close signal.
+    Closed,
+    // Result is the next result, or None if there are no more.
+    Result(Option<T>),
+}
+
+// Probe is a name that is being probed, with the record it proposes.
+struct Probe {
+    name: String,
+    proposed: Option<RecordData>,
+    conflict_chan: mpsc::Sender<Conflict>,
+}
+
+impl Probe {
+    // conflicts returns true if another host answers the name with data that
+    // differs from the proposed record of the same type.
+    fn conflicts(&self, data: &RecordData) -> bool {
+        match &self.proposed {
+            Some(proposed) => proposed.canonical().0 == data.canonical().0 && proposed != data,
+            None => true,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Conflict {
+    // Taken means that another host uses the name, so it must be renamed.
+    Taken,
+    // LostTiebreak means that another host probes for the name at the same time
+    // with greater records, so probing must be retried.
+    LostTiebreak,
+}
+
+// Owner is something that owns a unique name: a host or a service instance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Owner {
+    Host(String),
+    Service(ServiceInstance),
+}
+
+impl Owner {
+    fn name(&self) -> String {
+        match self {
+            Owner::Host(name) => name.clone(),
+            Owner::Service(service) => service.instance_name(),
+        }
+    }
+
+    // rename picks the next name after a conflict, RFC 6762 Section 9.
+    fn rename(&mut self) {
+        match self {
+            Owner::Host(name) => *name = next_host_name(name),
+            Owner::Service(service) => service.instance = next_instance(&service.instance),
+        }
+    }
+}
+
+// next_host_name renames peer.local. to peer-2.local., and peer-2.local. to
+// peer-3.local.
+fn next_host_name(name: &str) -> String {
+    let (label, domain) = match name.find('.') {
+        Some(i) => name.split_at(i),
+        None => (name, ""),
+    };
+    if let Some((base, n)) = label.rsplit_once('-') {
+        if let Ok(n) = n.parse::<u32>() {
+            if n >= 2 {
+                return format!("{base}-{}{domain}", n + 1);
+            }
+        }
+    }
+    format!("{label}-2{domain}")
+}
+
+// next_instance renames the instance Living Room to Living Room (2), and Living
+// Room (2) to Living Room (3).
+fn next_instance(instance: &str) -> String {
+    if let Some((base, n)) = instance.strip_suffix(')').and_then(|s| s.rsplit_once(" (")) {
+        if let Ok(n) = n.parse::<u32>() {
+            return format!("{base} ({})", n + 1);
+        }
+    }
+    format!("{instance} (2)")
+}
+
 impl DnsConn {
-    /// server establishes a mDNS connection over an existing connection
+    /// server establishes a mDNS connection over an existing connection. If addr
+    /// is an IPv6 address, like `[::]:5353`, it uses the IPv6 group `ff02::fb`
+    /// and answers with AAAA records.
+    ///
+    /// The local names are answered once probing found that no other host uses
+    /// them. A name that is taken is renamed, see [`DnsConn::local_names`].
     pub fn server(addr: SocketAddr, config: Config) -> Result<Self> {
         let socket = socket2::Socket::new(
-            socket2::Domain::IPV4,
+            if addr.is_ipv6() {
+                socket2::Domain::IPV6
+            } else {
+                socket2::Domain::IPV4
+            },
             socket2::Type::DGRAM,
             Some(socket2::Protocol::UDP),
         )?;
@@ -60,19 +210,43 @@ impl DnsConn {
         socket.set_reuse_port(true)?;
 
         socket.set_reuse_address(true)?;
-        socket.set_broadcast(true)?;
         socket.set_nonblocking(true)?;
 
-        socket.bind(&SockAddr::from(addr))?;
-        {
+        // IPv4 joins the group on every interface, IPv6 on the default one.
+        let interfaces = match ifaces::ifaces() {
+            Ok(e) => e,
+            Err(e) if addr.is_ipv6() => {
+                log::warn!("Error getting interfaces: {:?}", e);
+                vec![]
+            }
+            Err(e) => {
+                log::error!("Error getting interfaces: {:?}", e);
+                return Err(Error::Other(e.to_string()));
+            }
+        };
+        let local_ips = interfaces
+            .iter()
+            .filter_map(|interface| interface.addr)
+            .map(|addr| addr.ip())
+            .filter(|ip| ip.is_ipv6() == addr.is_ipv6())
+            .collect();
+
+        if addr.is_ipv6() {
+            socket.set_only_v6(true)?;
+            socket.bind(&SockAddr::from(addr))?;
+
+            // Join on the default multicast interface of the system.
+            if let Err(e) =
+                socket.join_multicast_v6(&Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 0xfb), 0)
+            {
+                log::trace!("Error connecting multicast, error: {:?}", e);
+                return Err(Error::ErrJoiningMulticastGroup);
+            }
+        } else {
+            socket.set_broadcast(true)?;
+            socket.bind(&SockAddr::from(addr))?;
+
             let mut join_error_count = 0;
-            let interfaces = match ifaces::ifaces() {
-                Ok(e) => e,
-                Err(e) => {
-                    log::error!("Error getting interfaces: {:?}", e);
-                    return Err(Error::Other(e.to_string()));
-                }
-            };
 
             for interface in &interfaces {
                 if let Some(SocketAddr::V4(e)) = interface.addr {
@@ -94,57 +268,70 @@ impl DnsConn {
 
         let socket = UdpSocket::from_std(socket.into())?;
 
-        let local_names = config
-            .local_names
-            .iter()
-            .map(|l| l.to_string() + ".")
-            .collect();
-
-        let dst_addr: SocketAddr = DEFAULT_DEST_ADDR.parse()?;
-
-        let is_server_closed = Arc::new(atomic::AtomicBool::new(false));
+        let dst_addr: SocketAddr = if addr.is_ipv6() {
+            DEFAULT_DEST_ADDR_V6
+        } else {
+            DEFAULT_DEST_ADDR
+        }
+        .parse()?;
 
         let (close_server_send, close_server_rcv) = mpsc::channel(1);
 
+        let internal = Arc::new(DnsConnInternal {
+            socket,
+            dst_addr,
+            queries: Mutex::new(vec![]),
+            browses: Mutex::new(vec![]),
+            probes: Mutex::new(vec![]),
+            local_names: Mutex::new(vec![]),
+            services: Mutex::new(vec![]),
+            cache: Mutex::new(Cache::default()),
+            sent: Mutex::new(VecDeque::new()),
+            local_ips,
+            is_server_closed: atomic::AtomicBool::new(false),
+        });
+
         let c = DnsConn {
+            internal: Arc::clone(&internal),
             query_interval: if config.query_interval != Duration::from_secs(0) {
                 config.query_interval
             } else {
                 DEFAULT_QUERY_INTERVAL
             },
-
-            queries: Arc::new(Mutex::new(vec![])),
-            socket: Arc::new(socket),
-            dst_addr,
-            is_server_closed: Arc::clone(&is_server_closed),
             close_server: close_server_send,
         };
 
-        let queries = c.queries.clone();
-        let socket = Arc::clone(&c.socket);
-
-        tokio::spawn(async move {
-            DnsConn::start(
-                close_server_rcv,
-                is_server_closed,
-                socket,
-                local_names,
-                dst_addr,
-                queries,
-            )
-            .await
+        tokio::spawn({
+            let internal = Arc::clone(&internal);
+            async move { internal.start(close_server_rcv).await }
         });
 
+        for local_name in config.local_names {
+            let internal = Arc::clone(&internal);
+            tokio::spawn(async move {
+                internal
+                    .probe_and_announce(Owner::Host(local_name + "."))
+                    .await
+            });
+        }
+
         Ok(c)
     }
 
-    /// Close closes the mDNS Conn
+    /// Close closes the mDNS Conn. It sends goodbye packets for its local names
+    /// and services, so that other hosts flush them from their caches.
     pub async fn close(&self) -> Result<()> {
         log::info!("Closing connection");
-        if self.is_server_closed.load(atomic::Ordering::SeqCst) {
+        if self
+            .internal
+            .is_server_closed
+            .load(atomic::Ordering::SeqCst)
+        {
             return Err(Error::ErrConnectionClosed);
         }
 
+        self.internal.send_goodbyes().await;
+
         log::trace!("Sending close command to server");
         match self.close_server.send(()).await {
             Ok(_) => {
@@ -158,46 +345,73 @@ impl DnsConn {
         }
     }
 
+    /// local_names returns the local names that passed probing, as they were
+    /// renamed after conflicts.
+    pub async fn local_names(&self) -> Vec<String> {
+        let local_names = self.internal.local_names.lock().await;
+        local_names
+            .iter()
+            .map(|name| name.trim_end_matches('.').to_owned())
+            .collect()
+    }
+
     /// Query sends mDNS Queries for the following name until
-    /// either there's a close signal or we get a result
+    /// either there's a close signal or we get a result. Cached answers are
+    /// returned immediately.
     pub async fn query(
         &self,
         name: &str,
         mut close_query_signal: mpsc::Receiver<()>,
     ) -> Result<(ResourceHeader, SocketAddr)> {
-        if self.is_server_closed.load(atomic::Ordering::SeqCst) {
+        if self
+            .internal
+            .is_server_closed
+            .load(atomic::Ordering::SeqCst)
+        {
             return Err(Error::ErrConnectionClosed);
         }
 
         let name_with_suffix = name.to_owned() + ".";
 
+        {
+            let cache = self.internal.cache.lock().await;
+            if let Some(cached) = cache.address(&name_with_suffix, Instant::now()) {
+                log::trace!("Found {} in cache", name_with_suffix);
+                return Ok((cached.record.header, cached.src));
+            }
+        }
+
         let (query_tx, mut query_rx) = mpsc::channel(1);
         {
-            let mut queries = self.queries.lock().await;
+            let mut queries = self.internal.queries.lock().await;
             queries.push(Query {
                 name_with_suffix: name_with_suffix.clone(),
                 query_result_chan: query_tx,
             });
         }
 
+        let typ = self.internal.address_type();
         log::trace!("Sending query");
-        self.send_question(&name_with_suffix).await;
+        self.internal
+            .send_question(&name_with_suffix, typ, &[])
+            .await;
 
         loop {
-            tokio::select! {
-                _ = tokio::time::sleep(self.query_interval) => {
+            match wait_for_result(self.query_interval, &mut close_query_signal, &mut query_rx).await
+            {
+                Wakeup::Resend => {
                     log::trace!("Sending query");
-                    self.send_question(&name_with_suffix).await
-                },
-
-                _ = close_query_signal.recv() => {
+                    self.internal
+                        .send_question(&name_with_suffix, typ, &[])
+                        .await
+                }
+                Wakeup::Closed => {
                     log::info!("Query close signal received.");
-                    return Err(Error::ErrConnectionClosed)
-                },
-
-                res_opt = query_rx.recv() =>{
+                    return Err(Error::ErrConnectionClosed);
+                }
+                Wakeup::Result(res_opt) => {
                     log::info!("Received query result");
-                    if let Some(res) = res_opt{
+                    if let Some(res) = res_opt {
                         return Ok((res.answer, res.addr));
                     }
                 }
@@ -205,7 +419,205 @@ impl DnsConn {
         }
     }
 
-    async fn send_question(&self, name: &str) {
+    /// register_service advertises a DNS-SD service instance. The DnsConn answers
+    /// queries for the service type, the instance and its host, with the address
+    /// of the interface towards the querier. An instance with the same name is
+    /// replaced.
+    ///
+    /// The instance name is probed first, and renamed if another host uses it.
+    /// It returns the instance as it is advertised.
+    pub async fn register_service(&self, service: ServiceInstance) -> Result<ServiceInstance> {
+        if self
+            .internal
+            .is_server_closed
+            .load(atomic::Ordering::SeqCst)
+        {
+            return Err(Error::ErrConnectionClosed);
+        }
+        service.validate()?;
+
+        self.unregister_service(&service.instance_name()).await;
+
+        match Arc::clone(&self.internal)
+            .probe_and_announce(Owner::Service(service))
+            .await
+        {
+            Some(Owner::Service(service)) => Ok(service),
+            _ => Err(Error::ErrConnectionClosed),
+        }
+    }
+
+    /// unregister_service stops advertising the service instance with the fully
+    /// qualified instance_name, and sends a goodbye packet for it.
+    pub async fn unregister_service(&self, instance_name: &str) {
+        let removed: Vec<ServiceInstance> = {
+            let mut services = self.internal.services.lock().await;
+            let (removed, kept) = services
+                .drain(..)
+                .partition(|s| name_eq(&s.instance_name(), instance_name));
+            *services = kept;
+            removed
+        };
+
+        for service in removed {
+            self.internal
+                .send_records(&Owner::Service(service), 0)
+                .await;
+        }
+    }
+
+    /// browse sends DNS-SD queries for the instances of service, like
+    /// `_webrtc._udp`, until there's a close signal or entries is closed. It sends
+    /// the instances it discovers, and again when their records change, to entries.
+    pub async fn browse(
+        &self,
+        service: &str,
+        entries: mpsc::Sender<ServiceEntry>,
+        mut close_browse_signal: mpsc::Receiver<()>,
+    ) -> Result<()> {
+        if self
+            .internal
+            .is_server_closed
+            .load(atomic::Ordering::SeqCst)
+        {
+            return Err(Error::ErrConnectionClosed);
+        }
+        validate_service(service)?;
+
+        let service_name = service_name(service);
+
+        let (browse_tx, mut browse_rx) = mpsc::channel(16);
+        {
+            let mut browses = self.internal.browses.lock().await;
+            browses.push(Browse {
+                service_name: service_name.clone(),
+                browse_result_chan: browse_tx.clone(),
+            });
+        }
+
+        // Start with the instances in the cache.
+        {
+            let cache = self.internal.cache.lock().await;
+            for (src, records) in cache.records_by_source(Instant::now()) {
+                for result in browse_results(&records, &service_name, src) {
+                    let _ = browse_tx.try_send(result);
+                }
+            }
+        }
+
+        log::trace!("Sending browse query");
+        self.internal.send_browse_question(&service_name).await;
+
+        // Query again at increasing intervals, RFC 6762 Section 5.2.
+        let mut interval = self.query_interval;
+        let mut known: HashMap<String, ServiceEntry> = HashMap::new();
+        let result = loop {
+            match wait_for_result(interval, &mut close_browse_signal, &mut browse_rx).await {
+                Wakeup::Resend => {
+                    log::trace!("Sending browse query");
+                    self.internal.send_browse_question(&service_name).await;
+                    interval = std::cmp::min(interval * 2, MAX_BROWSE_INTERVAL);
+                }
+                Wakeup::Closed => {
+                    log::info!("Browse close signal received.");
+                    break Ok(());
+                }
+                Wakeup::Result(res_opt) => match res_opt {
+                    Some(BrowseResult::Resolved(entry)) => {
+                        if known.get(&entry.instance_name) == Some(&entry) {
+                            continue;
+                        }
+                        known.insert(entry.instance_name.clone(), entry.clone());
+                        if entries.send(entry).await.is_err() {
+                            break Ok(());
+                        }
+                    }
+                    Some(BrowseResult::Unresolved(instance_name)) => {
+                        if !known.contains_key(&instance_name) {
+                            log::trace!("Resolving {}", instance_name);
+                            self.internal
+                                .send_question(&instance_name, DnsType::All, &[])
+                                .await;
+                        }
+                    }
+                    None => break Err(Error::ErrConnectionClosed),
+                },
+            }
+        };
+
+        let mut browses = self.internal.browses.lock().await;
+        browses.retain(|b| !b.browse_result_chan.same_channel(&browse_tx));
+
+        result
+    }
+}
+
+impl DnsConnInternal {
+    // address_type returns the type of the address records of the connection.
+    fn address_type(&self) -> DnsType {
+        if self.dst_addr.is_ipv6() {
+            DnsType::Aaaa
+        } else {
+            DnsType::A
+        }
+    }
+
+    // send sends a packet to the multicast group, and remembers it to recognize
+    // it when it's looped back.
+    async fn send(&self, raw: &[u8]) {
+        {
+            let mut sent = self.sent.lock().await;
+            if sent.len() >= SENT_PACKETS {
+                sent.pop_front();
+            }
+            sent.push_back((packet_hash(raw), Instant::now()));
+        }
+
+        log::trace!("{:?} sending {:?}...", self.socket.local_addr(), raw);
+        if let Err(err) = self.socket.send_to(raw, self.dst_addr).await {
+            log::error!("Failed to send mDNS packet {}", err);
+        }
+    }
+
+    // is_sent returns true if the packet is the loopback of a packet sent by this
+    // connection. Other connections on the host send from the same address and
+    // may send the same packet, e.g. the same query, so every sent packet only
+    // matches one received packet.
+    async fn is_sent(&self, raw: &[u8], src: SocketAddr) -> bool {
+        let from_host = self.socket.local_addr().map(|addr| addr.port()).ok() == Some(src.port())
+            && (self.local_ips.is_empty() || self.local_ips.contains(&src.ip()));
+        if !from_host {
+            return false;
+        }
+
+        let mut sent = self.sent.lock().await;
+        let now = Instant::now();
+        sent.retain(|(_, at)| now.duration_since(*at) < SENT_PACKET_LIFETIME);
+
+        let hash = packet_hash(raw);
+        if let Some(index) = sent.iter().position(|(sent_hash, _)| *sent_hash == hash) {
+            sent.remove(index);
+            true
+        } else {
+            false
+        }
+    }
+
+    // local_addr returns the address of the interface towards the multicast group,
+    // which announcements and goodbyes carry.
+    async fn local_addr(&self) -> Option<IpAddr> {
+        match get_interface_addr_for_ip(self.dst_addr).await {
+            Ok(addr) => Some(addr.ip()),
+            Err(err) => {
+                log::warn!("Failed to get local interface for multicast: {:?}", err);
+                None
+            }
+        }
+    }
+
+    // send_question sends a query for name, with known_answers that responders
+    // don't repeat.
+    async fn send_question(&self, name: &str, typ: DnsType, known_answers: &[Record]) {
         let packed_name = match Name::new(name) {
             Ok(pn) => pn,
             Err(err) => {
@@ -218,10 +630,16 @@ impl DnsConn {
             let mut msg = Message {
                 header: Header::default(),
                 questions: vec![Question {
-                    typ: DnsType::A,
+                    typ,
                     class: DNSCLASS_INET,
                     name: packed_name,
                 }],
+                answers: known_answers
+                    .iter()
+                    .filter_map(|r| {
+                        record_resource(&r.header.name.data, &r.data, r.header.ttl).ok()
+                    })
+                    .collect(),
                 ..Default::default()
             };
 
@@ -234,21 +652,181 @@ impl DnsConn {
             }
         };
 
-        log::trace!("{:?} sending {:?}...", self.socket.local_addr(), raw_query);
-        if let Err(err) = self.socket.send_to(&raw_query, self.dst_addr).await {
-            log::error!("Failed to send mDNS packet {}", err);
+        self.send(&raw_query).await;
+    }
+
+    // send_browse_question queries the instances of service_name, with the cached
+    // instances as known answers.
+    async fn send_browse_question(&self, service_name: &str) {
+        let known_answers = {
+            let cache = self.cache.lock().await;
+            cache.known_answers(service_name, DnsType::Ptr, Instant::now())
+        };
+        self.send_question(service_name, DnsType::Ptr, &known_answers)
+            .await;
+    }
+
+    // owner_resources returns the records of owner with ttl: the address of a
+    // host, or the PTR, SRV, TXT and address records of a service.
+    async fn owner_resources(&self, owner: &Owner, ttl: u32) -> Result<Vec<Resource>> {
+        let local_addr = self.local_addr().await;
+        match owner {
+            Owner::Host(name) => match local_addr {
+                Some(addr) => Ok(vec![address_resource(name, addr, ttl)?]),
+                None => Ok(vec![]),
+            },
+            Owner::Service(service) => service_resources(service, local_addr, ttl),
         }
     }
 
-    async fn start(
-        mut closed_rx: mpsc::Receiver<()>,
-        close_server: Arc<atomic::AtomicBool>,
-        socket: Arc<UdpSocket>,
-        local_names: Vec<String>,
-        dst_addr: SocketAddr,
-        queries: Arc<Mutex<Vec<Query>>>,
-    ) -> Result<()> {
-        log::info!("Looping and listening {:?}", socket.local_addr());
+    // send_records sends an unsolicited response with the records of owner, to
+    // announce them, or with ttl zero to say goodbye.
+    async fn send_records(&self, owner: &Owner, ttl: u32) {
+        let raw = match self.owner_resources(owner, ttl).await {
+            Ok(resources) if resources.is_empty() => return,
+            Ok(resources) => pack_response(resources, vec![]),
+            Err(err) => Err(err),
+        };
+        match raw {
+            Ok(raw) => self.send(&raw).await,
+            Err(err) => log::error!("Failed to construct mDNS packet {}", err),
+        }
+    }
+
+    async fn send_goodbyes(&self) {
+        let local_names = self.local_names.lock().await.clone();
+        let services = self.services.lock().await.clone();
+
+        for name in local_names {
+            self.send_records(&Owner::Host(name), 0).await;
+        }
+        for service in services {
+            self.send_records(&Owner::Service(service), 0).await;
+        }
+    }
+
+    // send_probe sends a query for the name of owner, with the records it
+    // proposes in the authority section. RFC 6762 Section 8.1
+    async fn send_probe(&self, owner: &Owner, proposed: &Option<RecordData>) {
+        let name = owner.name();
+        let authorities = match (owner, proposed) {
+            (Owner::Service(service), _) => probe_resources(service, RESPONSE_TTL),
+            (Owner::Host(_), Some(data)) => {
+                record_resource(&name, data, RESPONSE_TTL).map(|r| vec![r])
+            }
+            (Owner::Host(_), None) => Ok(vec![]),
+        };
+
+        let raw_probe = authorities.and_then(|authorities| {
+            let mut msg = Message {
+                header: Header::default(),
+                questions: vec![Question {
+                    name: Name::new(&name)?,
+                    typ: DnsType::All,
+                    class: DNSCLASS_INET,
+                }],
+                authorities,
+                ..Default::default()
+            };
+            msg.pack()
+        });
+
+        match raw_probe {
+            Ok(raw_probe) => self.send(&raw_probe).await,
+            Err(err) => log::error!("Failed to construct mDNS probe {}", err),
+        }
+    }
+
+    // probe_and_announce probes the name of owner until no other host uses it,
+    // renaming it on conflicts, then answers and announces it. It returns the
+    // owner as it was established, or None if the connection was closed.
+    // RFC 6762 Section 8
+    async fn probe_and_announce(self: Arc<Self>, mut owner: Owner) -> Option<Owner> {
+        let (conflict_tx, mut conflict_rx) = mpsc::channel(4);
+
+        loop {
+            if self.is_server_closed.load(atomic::Ordering::SeqCst) {
+                return None;
+            }
+
+            let name = owner.name();
+            let proposed = match &owner {
+                Owner::Host(_) => self.local_addr().await.map(RecordData::Addr),
+                Owner::Service(service) => Some(srv_data(service)),
+            };
+            {
+                let mut probes = self.probes.lock().await;
+                probes.push(Probe {
+                    name: name.clone(),
+                    proposed: proposed.clone(),
+                    conflict_chan: conflict_tx.clone(),
+                });
+            }
+
+            let delay = rand::random::<u64>() % PROBE_INTERVAL.as_millis() as u64;
+            let mut conflict =
+                tokio::time::timeout(Duration::from_millis(delay), conflict_rx.recv())
+                    .await
+                    .unwrap_or(None);
+            for _ in 0..PROBE_COUNT {
+                if conflict.is_some() {
+                    break;
+                }
+                log::trace!("Probing {}", name);
+                self.send_probe(&owner, &proposed).await;
+                conflict = tokio::time::timeout(PROBE_INTERVAL, conflict_rx.recv())
+                    .await
+                    .unwrap_or(None);
+            }
+
+            {
+                let mut probes = self.probes.lock().await;
+                probes.retain(|p| p.name != name);
+            }
+            while let Ok(c) = conflict_rx.try_recv() {
+                conflict.get_or_insert(c);
+            }
+
+            match conflict {
+                Some(Conflict::Taken) => {
+                    owner.rename();
+                    log::warn!("mDNS name {} is taken, renamed to {}", name, owner.name());
+                }
+                Some(Conflict::LostTiebreak) => {
+                    log::debug!("Lost probe tiebreak for {}, probing again", name);
+                    tokio::time::sleep(PROBE_DEFER).await;
+                }
+                None => break,
+            }
+        }
+
+        match &owner {
+            Owner::Host(name) => self.local_names.lock().await.push(name.clone()),
+            Owner::Service(service) => self.services.lock().await.push(service.clone()),
+        }
+        log::trace!("Established {}", owner.name());
+
+        tokio::spawn({
+            let internal = Arc::clone(&self);
+            let owner = owner.clone();
+            async move {
+                for i in 0..ANNOUNCE_COUNT {
+                    if i > 0 {
+                        tokio::time::sleep(ANNOUNCE_INTERVAL).await;
+                    }
+                    if internal.is_server_closed.load(atomic::Ordering::SeqCst) {
+                        break;
+                    }
+                    internal.send_records(&owner, RESPONSE_TTL).await;
+                }
+            }
+        });
+
+        Some(owner)
+    }
+
+    async fn start(self: Arc<Self>, mut closed_rx: mpsc::Receiver<()>) -> Result<()> {
+        log::info!("Looping and listening {:?}", self.socket.local_addr());
 
         let mut b = vec![0u8; INBOUND_BUFFER_SIZE];
         let (mut n, mut src);
@@ -257,12 +835,12 @@ impl DnsConn {
             tokio::select! {
                 _ = closed_rx.recv() => {
                     log::info!("Closing server connection");
-                    close_server.store(true, atomic::Ordering::SeqCst);
+                    self.is_server_closed.store(true, atomic::Ordering::SeqCst);
 
                     return Ok(());
                 }
 
-                result = socket.recv_from(&mut b) => {
+                result = self.socket.recv_from(&mut b) => {
                     match result{
                         Ok((len, addr)) => {
                             n = len;
@@ -278,153 +856,344 @@ impl DnsConn {
                 }
             }
 
-            let mut p = Parser::default();
-            if let Err(err) = p.start(&b[..n]) {
-                log::error!("Failed to parse mDNS packet {}", err);
+            if self.is_sent(&b[..n], src).await {
+                log::trace!("Ignoring own packet");
                 continue;
             }
 
-            run(&mut p, &socket, &local_names, src, dst_addr, &queries).await
+            let mut p = Parser::default();
+            let header = match p.start(&b[..n]) {
+                Ok(header) => header,
+                Err(err) => {
+                    log::error!("Failed to parse mDNS packet {}", err);
+                    continue;
+                }
+            };
+
+            self.run(&mut p, header.response, src).await
         }
     }
-}
 
-async fn run(
-    p: &mut Parser<'_>,
-    socket: &Arc<UdpSocket>,
-    local_names: &[String],
-    src: SocketAddr,
-    dst_addr: SocketAddr,
-    queries: &Arc<Mutex<Vec<Query>>>,
-) {
-    let mut interface_addr = None;
-    for _ in 0..=MAX_MESSAGE_RECORDS {
-        let q = match p.question() {
-            Ok(q) => q,
-            Err(err) => {
-                if Error::ErrSectionDone == err {
-                    log::trace!("Parsing has completed");
-                    break;
-                } else {
-                    log::error!("Failed to parse mDNS packet {}", err);
-                    return;
+    async fn run(self: &Arc<Self>, p: &mut Parser<'_>, response: bool, src: SocketAddr) {
+        let mut questions = vec![];
+        for _ in 0..=MAX_MESSAGE_RECORDS {
+            match p.question() {
+                Ok(q) => questions.push(q),
+                Err(err) => {
+                    if Error::ErrSectionDone == err {
+                        log::trace!("Parsing has completed");
+                        break;
+                    } else {
+                        log::error!("Failed to parse mDNS packet {}", err);
+                        return;
+                    }
                 }
             }
+        }
+
+        if let Err(err) = p.skip_all_questions() {
+            log::warn!("Failed to parse mDNS packet {}", err);
+            return;
+        }
+        let (records, authorities) = match read_records(p) {
+            Ok(records) => records,
+            Err(err) => {
+                log::warn!("Failed to parse mDNS packet {}", err);
+                return;
+            }
         };
 
-        for local_name in local_names {
-            if *local_name == q.name.data {
-                let interface_addr = match interface_addr {
-                    Some(addr) => addr,
-                    None => match get_interface_addr_for_ip(src).await {
-                        Ok(addr) => {
-                            interface_addr.replace(addr);
-                            addr
-                        }
-                        Err(e) => {
-                            log::warn!(
-                                "Failed to get local interface to communicate with {}: {:?}",
-                                &src,
-                                e
-                            );
-                            continue;
-                        }
-                    },
-                };
-
-                log::trace!(
-                    "Found local name: {} to send answer, IP {}, interface addr {}",
-                    local_name,
-                    src.ip(),
-                    interface_addr
-                );
-                if let Err(e) =
-                    send_answer(socket, &interface_addr, &q.name.data, src.ip(), dst_addr).await
-                {
-                    log::error!("Error sending answer to client: {:?}", e);
-                    continue;
-                };
+        let mut interface_addr = None;
+        if response {
+            self.handle_response(&records, src, &mut interface_addr)
+                .await;
+        } else {
+            self.handle_probes(&questions, &authorities).await;
+            for q in &questions {
+                self.answer(q, &records, src, &mut interface_addr).await;
             }
         }
     }
 
-    for _ in 0..=MAX_MESSAGE_RECORDS {
-        let a = match p.answer_header() {
-            Ok(a) => a,
-            Err(err) => {
-                if Error::ErrSectionDone != err {
-                    log::warn!("Failed to parse mDNS packet {}", err);
-                }
+    // answer answers a question about a local name or a service, unless
+    // known_answers has the answer already.
+    async fn answer(
+        &self,
+        q: &Question,
+        known_answers: &[Record],
+        src: SocketAddr,
+        interface_addr: &mut Option<SocketAddr>,
+    ) {
+        let services = self.services.lock().await.clone();
+        let is_local_name = {
+            let local_names = self.local_names.lock().await;
+            local_names.iter().any(|l| name_eq(l, &q.name.data))
+        } || services
+            .iter()
+            .any(|s| name_eq(&s.host_name(), &q.name.data));
+        if !is_local_name && services.is_empty() {
+            return;
+        }
+
+        let interface_addr = match get_cached_interface_addr(interface_addr, src).await {
+            Some(addr) => addr,
+            None => return,
+        };
+
+        let raw_answer = if is_local_name {
+            if is_known_answer(
+                known_answers,
+                &q.name.data,
+                &RecordData::Addr(interface_addr.ip()),
+            ) {
+                log::trace!("Answer for {} is known", q.name.data);
                 return;
             }
+
+            log::trace!(
+                "Found local name: {} to send answer, IP {}, interface addr {}",
+                q.name.data,
+                src.ip(),
+                interface_addr
+            );
+            address_resource(&q.name.data, interface_addr.ip(), RESPONSE_TTL)
+                .and_then(|answer| pack_response(vec![answer], vec![]))
+        } else {
+            match answer_question(&services, q, interface_addr.ip(), known_answers) {
+                Ok((answers, _)) if answers.is_empty() => return,
+                Ok((answers, additionals)) => {
+                    log::trace!("Found services for {} to send answer", q.name.data);
+                    pack_response(answers, additionals)
+                }
+                Err(e) => Err(e),
+            }
         };
 
-        if a.typ != DnsType::A && a.typ != DnsType::Aaaa {
-            continue;
+        match raw_answer {
+            Ok(raw_answer) => {
+                self.send(&raw_answer).await;
+                log::trace!("Sent answer to IP {}", src.ip());
+            }
+            Err(e) => log::error!("Error sending answer to client: {:?}", e),
         }
+    }
 
-        let mut qs = queries.lock().await;
-        for j in (0..qs.len()).rev() {
-            if qs[j].name_with_suffix == a.name.data {
-                let _ = qs[j]
-                    .query_result_chan
-                    .send(QueryResult {
-                        answer: a.clone(),
-                        addr: src,
-                    })
-                    .await;
-                qs.remove(j);
+    // handle_probes compares the records that other hosts propose for names that
+    // are being probed, and defers probing if theirs are greater.
+    // RFC 6762 Section 8.2
+    async fn handle_probes(&self, questions: &[Question], authorities: &[Record]) {
+        if authorities.is_empty() {
+            return;
+        }
+
+        let probes = self.probes.lock().await;
+        for probe in probes.iter() {
+            if !questions.iter().any(|q| name_eq(&q.name.data, &probe.name)) {
+                continue;
+            }
+            let theirs = authorities
+                .iter()
+                .filter(|r| name_eq(&r.header.name.data, &probe.name))
+                .map(|r| r.data.canonical())
+                .max();
+            if let (Some(ours), Some(theirs)) = (&probe.proposed, theirs) {
+                if ours.canonical() < theirs {
+                    let _ = probe.conflict_chan.try_send(Conflict::LostTiebreak);
+                }
+            }
+        }
+    }
+
+    // handle_response detects conflicts with the records of a response, caches
+    // them, and passes them to queries and browses.
+    async fn handle_response(
+        self: &Arc<Self>,
+        records: &[Record],
+        src: SocketAddr,
+        interface_addr: &mut Option<SocketAddr>,
+    ) {
+        self.detect_conflicts(records, src, interface_addr).await;
+
+        {
+            let mut cache = self.cache.lock().await;
+            let now = Instant::now();
+            for r in records {
+                cache.insert(r.clone(), src, now);
+            }
+        }
+
+        for r in records {
+            if r.header.typ != DnsType::A && r.header.typ != DnsType::Aaaa {
+                continue;
+            }
+
+            let mut qs = self.queries.lock().await;
+            for j in (0..qs.len()).rev() {
+                if qs[j].name_with_suffix == r.header.name.data {
+                    let _ = qs[j]
+                        .query_result_chan
+                        .send(QueryResult {
+                            answer: r.header.clone(),
+                            addr: src,
+                        })
+                        .await;
+                    qs.remove(j);
+                }
+            }
+        }
+
+        let bs = self.browses.lock().await;
+        for b in bs.iter() {
+            for result in browse_results(records, &b.service_name, src) {
+                let _ = b.browse_result_chan.try_send(result);
             }
         }
     }
+
+    // detect_conflicts checks whether another host answers for a name that is
+    // being probed, which must be renamed, or answers differently for a name that
+    // was established, which must be probed again. RFC 6762 Section 9
+    async fn detect_conflicts(
+        self: &Arc<Self>,
+        records: &[Record],
+        src: SocketAddr,
+        interface_addr: &mut Option<SocketAddr>,
+    ) {
+        {
+            let probes = self.probes.lock().await;
+            for probe in probes.iter() {
+                // Records that are identical to the proposed ones, like those of
+                // another responder on the same host, are no conflict.
+                if records
+                    .iter()
+                    .any(|r| name_eq(&r.header.name.data, &probe.name) && probe.conflicts(&r.data))
+                {
+                    let _ = probe.conflict_chan.try_send(Conflict::Taken);
+                }
+            }
+        }
+
+        let mut conflicts = vec![];
+        let claimed: Vec<&Record> = {
+            let local_names = self.local_names.lock().await;
+            records
+                .iter()
+                .filter(|r| {
+                    matches!(r.data, RecordData::Addr(_))
+                        && local_names.iter().any(|l| name_eq(l, &r.header.name.data))
+                })
+                .collect()
+        };
+        if !claimed.is_empty() {
+            if let Some(interface_addr) = get_cached_interface_addr(interface_addr, src).await {
+                let ours = RecordData::Addr(interface_addr.ip());
+                let mut local_names = self.local_names.lock().await;
+                for r in claimed {
+                    if r.data != ours {
+                        local_names.retain(|l| !name_eq(l, &r.header.name.data));
+                        conflicts.push(Owner::Host(r.header.name.data.clone()));
+                    }
+                }
+            }
+        }
+
+        {
+            let mut services = self.services.lock().await;
+            services.retain(|service| {
+                let instance_name = service.instance_name();
+                let conflict = records.iter().any(|r| {
+                    matches!(r.data, RecordData::Srv { .. })
+                        && name_eq(&r.header.name.data, &instance_name)
+                        && r.data != srv_data(service)
+                });
+                if conflict {
+                    conflicts.push(Owner::Service(service.clone()));
+                }
+                !conflict
+            });
+        }
+
+        conflicts.dedup();
+        for owner in conflicts {
+            log::warn!(
+                "mDNS name {} conflicts with {}, probing again",
+                owner.name(),
+                src
+            );
+            tokio::spawn(Arc::clone(self).probe_and_announce(owner));
+        }
+    }
 }
 
-async fn send_answer(
-    socket: &Arc<UdpSocket>,
-    interface_addr: &SocketAddr,
-    name: &str,
-    dst: IpAddr,
-    dst_addr: SocketAddr,
-) -> Result<()> {
-    let raw_answer = {
-        let mut msg = Message {
-            header: Header {
-                response: true,
-                authoritative: true,
-                ..Default::default()
-            },
+// wait_for_result waits for the next result, the close signal or the end of
+// interval, whatever comes first.
+async fn wait_for_result<T>(
+    interval: Duration,
+    close_signal: &mut mpsc::Receiver<()>,
+    results: &mut mpsc::Receiver<T>,
+) -> Wakeup<T> {
+    tokio::select! {
+        _ = tokio::time::sleep(interval) => Wakeup::Resend,
+        _ = close_signal.recv() => Wakeup::Closed,
+        res_opt = results.recv() => Wakeup::Result(res_opt),
+    }
+}
 
-            answers: vec![Resource {
-                header: ResourceHeader {
-                    typ: DnsType::A,
-                    class: DNSCLASS_INET,
-                    name: Name::new(name)?,
-                    ttl: RESPONSE_TTL,
-                    ..Default::default()
-                },
-                body: Some(Box::new(AResource {
-                    a: match interface_addr.ip() {
-                        IpAddr::V4(ip) => ip.octets(),
-                        IpAddr::V6(_) => {
-                            return Err(Error::Other("Unexpected IpV6 addr".to_owned()))
-                        }
-                    },
-                })),
-            }],
+// packet_hash identifies a sent packet.
+fn packet_hash(raw: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    raw.hash(&mut hasher);
+    hasher.finish()
+}
+
+// get_cached_interface_addr returns the address of the local interface towards
+// src, which is looked up once per message.
+async fn get_cached_interface_addr(
+    interface_addr: &mut Option<SocketAddr>,
+    src: SocketAddr,
+) -> Option<SocketAddr> {
+    if let Some(addr) = interface_addr {
+        return Some(*addr);
+    }
+
+    match get_interface_addr_for_ip(src).await {
+        Ok(addr) => {
+            interface_addr.replace(addr);
+            Some(addr)
+        }
+        Err(e) => {
+            log::warn!(
+                "Failed to get local interface to communicate with {}: {:?}",
+                &src,
+                e
+            );
+            None
+        }
+    }
+}
+
+fn pack_response(answers: Vec<Resource>, additionals: Vec<Resource>) -> Result<Vec<u8>> {
+    let mut msg = Message {
+        header: Header {
+            response: true,
+            authoritative: true,
             ..Default::default()
-        };
+        },
 
-        msg.pack()?
+        answers,
+        additionals,
+        ..Default::default()
     };
 
-    socket.send_to(&raw_answer, dst_addr).await?;
-    log::trace!("Sent answer to IP {}", dst);
-
-    Ok(())
+    msg.pack()
 }
 
-async fn get_interface_addr_for_ip(addr: impl ToSocketAddrs) -> std::io::Result<SocketAddr> {
-    let socket = UdpSocket::bind("0.0.0.0:0").await?;
+async fn get_interface_addr_for_ip(addr: SocketAddr) -> std::io::Result<SocketAddr> {
+    let socket = if addr.is_ipv6() {
+        UdpSocket::bind("[::]:0").await?
+    } else {
+        UdpSocket::bind("0.0.0.0:0").await?
+    };
     socket.connect(addr).await?;
     socket.local_addr()
 }
@@ -0,0 +1,181 @@
+use super::*;
+
+fn new_service() -> ServiceInstance {
+    ServiceInstance {
+        instance: "Living Room".to_owned(),
+        service: "_webrtc._udp".to_owned(),
+        host: "peer-1.local".to_owned(),
+        port: 5000,
+        txt: vec!["id=1".to_owned()],
+    }
+}
+
+fn new_question(name: &str, typ: DnsType) -> Result<Question> {
+    Ok(Question {
+        name: Name::new(name)?,
+        typ,
+        class: DNSCLASS_INET,
+    })
+}
+
+// parse_response packs a response with answers and additionals and reads its
+// records back, as a browser receives them.
+fn parse_response(answers: Vec<Resource>, additionals: Vec<Resource>) -> Result<Vec<Record>> {
+    let mut msg = Message {
+        answers,
+        additionals,
+        ..Default::default()
+    };
+    let raw = msg.pack()?;
+
+    let mut p = Parser::default();
+    p.start(&raw)?;
+    p.skip_all_questions()?;
+    Ok(read_records(&mut p)?.0)
+}
+
+#[test]
+fn test_service_instance_validate() {
+    assert_eq!(new_service().validate(), Ok(()));
+    assert_eq!(
+        new_service().instance_name(),
+        "Living Room._webrtc._udp.local."
+    );
+    assert_eq!(new_service().host_name(), "peer-1.local.");
+
+    let mut service = new_service();
+    service.instance = "a.b".to_owned();
+    assert_eq!(service.validate(), Err(Error::ErrInvalidServiceInstance));
+
+    for service_type in ["webrtc._udp", "_webrtc", "_webrtc._sctp", "_._udp"] {
+        let mut service = new_service();
+        service.service = service_type.to_owned();
+        assert_eq!(
+            service.validate(),
+            Err(Error::ErrInvalidServiceType),
+            "{service_type}"
+        );
+    }
+}
+
+#[test]
+fn test_browse_service() -> Result<()> {
+    let services = vec![new_service()];
+    let host_addr: IpAddr = "192.168.1.2".parse()?;
+    let src: SocketAddr = "192.168.1.2:5353".parse()?;
+
+    let q = new_question("_webrtc._udp.local.", DnsType::Ptr)?;
+    let (answers, additionals) = answer_question(&services, &q, host_addr, &[])?;
+    assert_eq!(answers.len(), 1);
+    assert_eq!(additionals.len(), 3);
+
+    let records = parse_response(answers, additionals)?;
+    assert_eq!(
+        browse_results(&records, "_webrtc._udp.local.", src),
+        vec![BrowseResult::Resolved(ServiceEntry {
+            instance_name: "Living Room._webrtc._udp.local.".to_owned(),
+            host: "peer-1.local.".to_owned(),
+            port: 5000,
+            txt: vec!["id=1".to_owned()],
+            addrs: vec![host_addr],
+        })]
+    );
+    assert!(browse_results(&records, "_other._udp.local.", src).is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_resolve_service_instance() -> Result<()> {
+    let mut service = new_service();
+    service.txt = vec![];
+    let services = vec![service];
+    let host_addr: IpAddr = "fe80::1".parse()?;
+    let src: SocketAddr = "[fe80::1]:5353".parse()?;
+
+    // A PTR record alone must be resolved with another query.
+    let ptr = record_resource(
+        "_webrtc._udp.local.",
+        &RecordData::Ptr("Living Room._webrtc._udp.local.".to_owned()),
+        RESPONSE_TTL,
+    )?;
+    let records = parse_response(vec![ptr], vec![])?;
+    assert_eq!(
+        browse_results(&records, "_webrtc._udp.local.", src),
+        vec![BrowseResult::Unresolved(
+            "Living Room._webrtc._udp.local.".to_owned()
+        )]
+    );
+
+    let q = new_question("Living Room._webrtc._udp.local.", DnsType::All)?;
+    let (answers, additionals) = answer_question(&services, &q, host_addr, &[])?;
+    assert_eq!(answers.len(), 2, "should answer SRV and TXT");
+
+    let records = parse_response(answers, additionals)?;
+    assert_eq!(
+        browse_results(&records, "_webrtc._udp.local.", src),
+        vec![BrowseResult::Resolved(ServiceEntry {
+            instance_name: "Living Room._webrtc._udp.local.".to_owned(),
+            host: "peer-1.local.".to_owned(),
+            port: 5000,
+            txt: vec![],
+            addrs: vec![host_addr],
+        })]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_service_type_enumeration() -> Result<()> {
+    let mut other = new_service();
+    other.instance = "Kitchen".to_owned();
+    let services = vec![new_service(), other];
+
+    let q = new_question(SERVICE_TYPE_ENUMERATION, DnsType::Ptr)?;
+    let (answers, additionals) = answer_question(&services, &q, "10.0.0.1".parse()?, &[])?;
+    assert!(additionals.is_empty());
+
+    let records = parse_response(answers, additionals)?;
+    assert_eq!(records.len(), 1, "service types should be listed once");
+    assert_eq!(
+        records[0].data,
+        RecordData::Ptr("_webrtc._udp.local.".to_owned())
+    );
+
+    let q = new_question("_other._udp.local.", DnsType::Ptr)?;
+    let (answers, _) = answer_question(&services, &q, "10.0.0.1".parse()?, &[])?;
+    assert!(answers.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_known_answer_suppression() -> Result<()> {
+    let services = vec![new_service()];
+    let host_addr: IpAddr = "192.168.1.2".parse()?;
+    let q = new_question("_webrtc._udp.local.", DnsType::Ptr)?;
+    let ptr = RecordData::Ptr("Living Room._webrtc._udp.local.".to_owned());
+
+    let known = parse_response(
+        vec![record_resource("_webrtc._udp.local.", &ptr, RESPONSE_TTL)?],
+        vec![],
+    )?;
+    let (answers, additionals) = answer_question(&services, &q, host_addr, &known)?;
+    assert!(answers.is_empty(), "known answer should be suppressed");
+    assert!(additionals.is_empty());
+
+    // A known answer with less than half of its TTL left is answered again.
+    let stale = parse_response(
+        vec![record_resource(
+            "_webrtc._udp.local.",
+            &ptr,
+            RESPONSE_TTL / 2 - 1,
+        )?],
+        vec![],
+    )?;
+    let (answers, _) = answer_question(&services, &q, host_addr, &stale)?;
+    assert_eq!(answers.len(), 1);
+
+    Ok(())
+}
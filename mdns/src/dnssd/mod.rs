@@ -0,0 +1,507 @@
+#[cfg(test)]
+mod dnssd_test;
+
+use crate::conn::RESPONSE_TTL;
+use crate::error::*;
+use crate::message::name::*;
+use crate::message::parser::*;
+use crate::message::question::*;
+use crate::message::resource::{a::*, aaaa::*, ptr::*, srv::*, txt::*, *};
+use crate::message::*;
+
+use std::net::{IpAddr, SocketAddr};
+
+// SERVICE_TYPE_ENUMERATION is the name that lists the service types of a domain,
+// see RFC 6763 Section 9.
+pub const SERVICE_TYPE_ENUMERATION: &str = "_services._dns-sd._udp.local.";
+
+/// ServiceInstance is a DNS-SD service instance that a DnsConn advertises, like
+/// `Living Room._webrtc._udp.local.` of host `peer-1.local` at port 5000.
+///
+/// RFC 6763 Section 4.1
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct ServiceInstance {
+    /// instance is the user-visible name of the instance. It must not contain dots.
+    pub instance: String,
+    /// service is the service type and protocol, like `_webrtc._udp`.
+    pub service: String,
+    /// host is the name of the host that provides the service, like `peer-1.local`.
+    /// The DnsConn answers its address.
+    pub host: String,
+    pub port: u16,
+    /// txt are the `key=value` pairs of the TXT record.
+    pub txt: Vec<String>,
+}
+
+impl ServiceInstance {
+    /// service_name returns the fully qualified name of the service type.
+    pub fn service_name(&self) -> String {
+        service_name(&self.service)
+    }
+
+    /// instance_name returns the fully qualified name of the instance.
+    pub fn instance_name(&self) -> String {
+        format!("{}.{}", self.instance, self.service_name())
+    }
+
+    /// host_name returns the fully qualified name of the host.
+    pub fn host_name(&self) -> String {
+        if self.host.ends_with('.') {
+            self.host.clone()
+        } else {
+            self.host.clone() + "."
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<()> {
+        if self.instance.is_empty() || self.instance.contains('.') {
+            return Err(Error::ErrInvalidServiceInstance);
+        }
+        validate_service(&self.service)?;
+        if self.host.is_empty() {
+            return Err(Error::ErrInvalidServiceInstance);
+        }
+        Name::new(&self.instance_name())?;
+        Name::new(&self.host_name())?;
+        Ok(())
+    }
+}
+
+/// ServiceEntry is a service instance that was discovered by browsing.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct ServiceEntry {
+    /// instance_name is the fully qualified name of the instance.
+    pub instance_name: String,
+    /// host is the fully qualified name of the host that provides the service.
+    pub host: String,
+    pub port: u16,
+    pub txt: Vec<String>,
+    /// addrs are the addresses of the host. If the response had no address
+    /// records, it is the address the response came from.
+    pub addrs: Vec<IpAddr>,
+}
+
+// service_name returns the fully qualified name of a service type in the local
+// domain.
+pub(crate) fn service_name(service: &str) -> String {
+    format!("{service}.local.")
+}
+
+// validate_service checks that service is a service type like `_webrtc._udp`.
+pub(crate) fn validate_service(service: &str) -> Result<()> {
+    let labels: Vec<&str> = service.split('.').collect();
+    match labels.as_slice() {
+        [name, proto]
+            if name.len() > 1
+                && name.starts_with('_')
+                && (*proto == "_udp" || *proto == "_tcp") =>
+        {
+            Ok(())
+        }
+        _ => Err(Error::ErrInvalidServiceType),
+    }
+}
+
+pub(crate) fn name_eq(a: &str, b: &str) -> bool {
+    a.eq_ignore_ascii_case(b)
+}
+
+fn name_has_suffix(name: &str, suffix: &str) -> bool {
+    name.len() > suffix.len()
+        && name.is_char_boundary(name.len() - suffix.len())
+        && name_eq(&name[name.len() - suffix.len()..], suffix)
+}
+
+// record_resource returns the resource record of name with data.
+pub(crate) fn record_resource(name: &str, data: &RecordData, ttl: u32) -> Result<Resource> {
+    let body: Box<dyn ResourceBody> = match data {
+        RecordData::Addr(IpAddr::V4(ip)) => Box::new(AResource { a: ip.octets() }),
+        RecordData::Addr(IpAddr::V6(ip)) => Box::new(AaaaResource { aaaa: ip.octets() }),
+        RecordData::Ptr(ptr) => Box::new(PtrResource {
+            ptr: Name::new(ptr)?,
+        }),
+        RecordData::Srv { port, target } => Box::new(SrvResource {
+            priority: 0,
+            weight: 0,
+            port: *port,
+            target: Name::new(target)?,
+        }),
+        RecordData::Txt(txt) => Box::new(TxtResource { txt: txt.clone() }),
+    };
+
+    Ok(Resource {
+        header: ResourceHeader {
+            name: Name::new(name)?,
+            typ: body.real_type(),
+            class: DNSCLASS_INET,
+            ttl,
+            ..Default::default()
+        },
+        body: Some(body),
+    })
+}
+
+// address_resource returns the A or AAAA record of name.
+pub(crate) fn address_resource(name: &str, ip: IpAddr, ttl: u32) -> Result<Resource> {
+    record_resource(name, &RecordData::Addr(ip), ttl)
+}
+
+fn ptr_data(service: &ServiceInstance) -> RecordData {
+    RecordData::Ptr(service.instance_name())
+}
+
+pub(crate) fn srv_data(service: &ServiceInstance) -> RecordData {
+    RecordData::Srv {
+        port: service.port,
+        target: service.host_name(),
+    }
+}
+
+fn txt_data(service: &ServiceInstance) -> RecordData {
+    // A TXT record must contain at least one string, RFC 6763 Section 6.1.
+    if service.txt.is_empty() {
+        RecordData::Txt(vec![String::new()])
+    } else {
+        RecordData::Txt(service.txt.clone())
+    }
+}
+
+// service_resources returns the PTR, SRV and TXT records of service, and the
+// address record of its host if host_addr is known.
+pub(crate) fn service_resources(
+    service: &ServiceInstance,
+    host_addr: Option<IpAddr>,
+    ttl: u32,
+) -> Result<Vec<Resource>> {
+    let instance_name = service.instance_name();
+    let mut resources = vec![
+        record_resource(&service.service_name(), &ptr_data(service), ttl)?,
+        record_resource(&instance_name, &srv_data(service), ttl)?,
+        record_resource(&instance_name, &txt_data(service), ttl)?,
+    ];
+    if let Some(host_addr) = host_addr {
+        resources.push(address_resource(&service.host_name(), host_addr, ttl)?);
+    }
+    Ok(resources)
+}
+
+// probe_resources returns the records that a probe for service proposes.
+pub(crate) fn probe_resources(service: &ServiceInstance, ttl: u32) -> Result<Vec<Resource>> {
+    let instance_name = service.instance_name();
+    Ok(vec![
+        record_resource(&instance_name, &srv_data(service), ttl)?,
+        record_resource(&instance_name, &txt_data(service), ttl)?,
+    ])
+}
+
+// is_known_answer returns true if known_answers, the answers that a querier
+// already knows, contain the record of name with data with at least half of its
+// TTL left, so that it needs no answer. RFC 6762 Section 7.1
+pub(crate) fn is_known_answer(known_answers: &[Record], name: &str, data: &RecordData) -> bool {
+    known_answers.iter().any(|r| {
+        name_eq(&r.header.name.data, name) && r.data == *data && r.header.ttl >= RESPONSE_TTL / 2
+    })
+}
+
+// answer_question returns the answers and additional records of the response to
+// a DNS-SD question about services, with host_addr as the address of their hosts.
+// Answers in known_answers are left out. Both are empty if the question isn't
+// about services.
+//
+// RFC 6763 Section 12
+pub(crate) fn answer_question(
+    services: &[ServiceInstance],
+    q: &Question,
+    host_addr: IpAddr,
+    known_answers: &[Record],
+) -> Result<(Vec<Resource>, Vec<Resource>)> {
+    let (mut answers, mut additionals) = (vec![], vec![]);
+    let name = q.name.data.as_str();
+    let any = q.typ == DnsType::All;
+
+    if name_eq(name, SERVICE_TYPE_ENUMERATION) && (any || q.typ == DnsType::Ptr) {
+        let mut service_names: Vec<String> = services.iter().map(|s| s.service_name()).collect();
+        service_names.sort();
+        service_names.dedup();
+        for service_name in service_names {
+            let data = RecordData::Ptr(service_name);
+            if !is_known_answer(known_answers, name, &data) {
+                answers.push(record_resource(
+                    SERVICE_TYPE_ENUMERATION,
+                    &data,
+                    RESPONSE_TTL,
+                )?);
+            }
+        }
+        return Ok((answers, additionals));
+    }
+
+    for service in services {
+        let instance_name = service.instance_name();
+        if name_eq(name, &service.service_name()) && (any || q.typ == DnsType::Ptr) {
+            if is_known_answer(known_answers, name, &ptr_data(service)) {
+                continue;
+            }
+            answers.push(record_resource(name, &ptr_data(service), RESPONSE_TTL)?);
+            additionals.push(record_resource(
+                &instance_name,
+                &srv_data(service),
+                RESPONSE_TTL,
+            )?);
+            additionals.push(record_resource(
+                &instance_name,
+                &txt_data(service),
+                RESPONSE_TTL,
+            )?);
+            additionals.push(address_resource(
+                &service.host_name(),
+                host_addr,
+                RESPONSE_TTL,
+            )?);
+        } else if name_eq(name, &instance_name) {
+            if (any || q.typ == DnsType::Srv)
+                && !is_known_answer(known_answers, name, &srv_data(service))
+            {
+                answers.push(record_resource(
+                    &instance_name,
+                    &srv_data(service),
+                    RESPONSE_TTL,
+                )?);
+                additionals.push(address_resource(
+                    &service.host_name(),
+                    host_addr,
+                    RESPONSE_TTL,
+                )?);
+            }
+            if (any || q.typ == DnsType::Txt)
+                && !is_known_answer(known_answers, name, &txt_data(service))
+            {
+                answers.push(record_resource(
+                    &instance_name,
+                    &txt_data(service),
+                    RESPONSE_TTL,
+                )?);
+            }
+        }
+    }
+
+    Ok((answers, additionals))
+}
+
+// RecordData is the data of the resource records that mDNS and DNS-SD use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum RecordData {
+    Addr(IpAddr),
+    Ptr(String),
+    Srv { port: u16, target: String },
+    Txt(Vec<String>),
+}
+
+impl RecordData {
+    // canonical returns the type and the uncompressed data of the record, with
+    // names in lower case, to compare records lexicographically in simultaneous
+    // probe tiebreaking. RFC 6762 Section 8.2
+    pub(crate) fn canonical(&self) -> (u16, Vec<u8>) {
+        match self {
+            RecordData::Addr(IpAddr::V4(ip)) => (DnsType::A as u16, ip.octets().to_vec()),
+            RecordData::Addr(IpAddr::V6(ip)) => (DnsType::Aaaa as u16, ip.octets().to_vec()),
+            RecordData::Ptr(ptr) => (DnsType::Ptr as u16, ptr.to_ascii_lowercase().into_bytes()),
+            RecordData::Srv { port, target } => {
+                let mut data = vec![0, 0, 0, 0];
+                data.extend_from_slice(&port.to_be_bytes());
+                data.extend_from_slice(target.to_ascii_lowercase().as_bytes());
+                (DnsType::Srv as u16, data)
+            }
+            RecordData::Txt(txt) => {
+                let mut data = vec![];
+                for s in txt {
+                    data.push(s.len() as u8);
+                    data.extend_from_slice(s.as_bytes());
+                }
+                (DnsType::Txt as u16, data)
+            }
+        }
+    }
+}
+
+// Record is a parsed resource record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Record {
+    pub(crate) header: ResourceHeader,
+    pub(crate) data: RecordData,
+}
+
+// record_data parses the body of a resource record at off, or returns None if the
+// record is of another type or malformed. Unlike SrvResource, it accepts
+// compressed SRV targets as mDNS does, RFC 6762 Section 18.14.
+fn record_data(msg: &[u8], off: usize, header: &ResourceHeader) -> Option<RecordData> {
+    let body = msg.get(off..off + header.length as usize)?;
+    match header.typ {
+        DnsType::A => {
+            let mut a = [0u8; 4];
+            if body.len() != a.len() {
+                return None;
+            }
+            a.copy_from_slice(body);
+            Some(RecordData::Addr(IpAddr::from(a)))
+        }
+        DnsType::Aaaa => {
+            let mut aaaa = [0u8; 16];
+            if body.len() != aaaa.len() {
+                return None;
+            }
+            aaaa.copy_from_slice(body);
+            Some(RecordData::Addr(IpAddr::from(aaaa)))
+        }
+        DnsType::Ptr => {
+            let mut ptr = Name::default();
+            ptr.unpack(msg, off).ok()?;
+            Some(RecordData::Ptr(ptr.data))
+        }
+        DnsType::Srv => {
+            if body.len() < 7 {
+                return None;
+            }
+            let port = u16::from_be_bytes([body[4], body[5]]);
+            let mut target = Name::default();
+            target.unpack(msg, off + 6).ok()?;
+            Some(RecordData::Srv {
+                port,
+                target: target.data,
+            })
+        }
+        DnsType::Txt => {
+            let mut txt = TxtResource::default();
+            txt.unpack(msg, off, header.length as usize).ok()?;
+            Some(RecordData::Txt(txt.txt))
+        }
+        _ => None,
+    }
+}
+
+// read_records parses the A, AAAA, PTR, SRV and TXT records of a message whose
+// questions were parsed or skipped, and skips the other records. It returns the
+// records of the answers and additionals, and those of the authorities, which
+// only probes have.
+pub(crate) fn read_records(p: &mut Parser<'_>) -> Result<(Vec<Record>, Vec<Record>)> {
+    let mut records = vec![];
+    let mut authorities = vec![];
+
+    loop {
+        let header = match p.answer_header() {
+            Ok(header) => header,
+            Err(Error::ErrSectionDone) => break,
+            Err(err) => return Err(err),
+        };
+        if let Some(data) = record_data(p.msg, p.off, &header) {
+            records.push(Record { header, data });
+        }
+        p.skip_answer()?;
+    }
+
+    loop {
+        let header = match p.authority_header() {
+            Ok(header) => header,
+            Err(Error::ErrSectionDone) => break,
+            Err(err) => return Err(err),
+        };
+        if let Some(data) = record_data(p.msg, p.off, &header) {
+            authorities.push(Record { header, data });
+        }
+        p.skip_authority()?;
+    }
+
+    loop {
+        let header = match p.additional_header() {
+            Ok(header) => header,
+            Err(Error::ErrSectionDone) => break,
+            Err(err) => return Err(err),
+        };
+        if let Some(data) = record_data(p.msg, p.off, &header) {
+            records.push(Record { header, data });
+        }
+        p.skip_additional()?;
+    }
+
+    Ok((records, authorities))
+}
+
+// BrowseResult is what a response tells about an instance of a browsed service.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum BrowseResult {
+    // Resolved is an instance with its SRV record.
+    Resolved(ServiceEntry),
+    // Unresolved is the name of an instance without SRV record in the response,
+    // which must be queried.
+    Unresolved(String),
+}
+
+// browse_results returns the instances of service_name in records, received
+// from src.
+pub(crate) fn browse_results(
+    records: &[Record],
+    service_name: &str,
+    src: SocketAddr,
+) -> Vec<BrowseResult> {
+    let suffix = format!(".{service_name}");
+
+    let mut instances: Vec<&str> = vec![];
+    for r in records {
+        let instance = match &r.data {
+            RecordData::Ptr(ptr) if name_eq(&r.header.name.data, service_name) => ptr.as_str(),
+            RecordData::Srv { .. } => r.header.name.data.as_str(),
+            _ => continue,
+        };
+        if name_has_suffix(instance, &suffix) && !instances.iter().any(|i| name_eq(i, instance)) {
+            instances.push(instance);
+        }
+    }
+
+    instances
+        .into_iter()
+        .map(|instance| {
+            let srv = records.iter().find_map(|r| match &r.data {
+                RecordData::Srv { port, target } if name_eq(&r.header.name.data, instance) => {
+                    Some((*port, target.clone()))
+                }
+                _ => None,
+            });
+            let (port, host) = match srv {
+                Some(srv) => srv,
+                None => return BrowseResult::Unresolved(instance.to_owned()),
+            };
+
+            let txt = records
+                .iter()
+                .find_map(|r| match &r.data {
+                    RecordData::Txt(txt) if name_eq(&r.header.name.data, instance) => Some(
+                        txt.iter()
+                            .filter(|s| !s.is_empty())
+                            .cloned()
+                            .collect::<Vec<String>>(),
+                    ),
+                    _ => None,
+                })
+                .unwrap_or_default();
+
+            let mut addrs: Vec<IpAddr> = records
+                .iter()
+                .filter_map(|r| match &r.data {
+                    RecordData::Addr(addr) if name_eq(&r.header.name.data, &host) => Some(*addr),
+                    _ => None,
+                })
+                .collect();
+            if addrs.is_empty() {
+                addrs.push(src.ip());
+            }
+
+            BrowseResult::Resolved(ServiceEntry {
+                instance_name: instance.to_owned(),
+                host,
+                port,
+                txt,
+                addrs,
+            })
+        })
+        .collect()
+}
@@ -17,6 +17,10 @@ pub enum Error {
     ErrContextElapsed,
     #[error("mDNS: config must not be nil")]
     ErrNilConfig,
+    #[error("mDNS: service type must be like _service._udp or _service._tcp")]
+    ErrInvalidServiceType,
+    #[error("mDNS: service instance needs an instance name without dots and a host")]
+    ErrInvalidServiceInstance,
     #[error("parsing/packing of this type isn't available yet")]
     ErrNotStarted,
     #[error("parsing/packing of this section has completed")]
@@ -1,8 +1,10 @@
 #![warn(rust_2018_idioms)]
 #![allow(dead_code)]
 
+mod cache;
 pub mod config;
 pub mod conn;
+pub mod dnssd;
 mod error;
 pub mod message;
 
@@ -52,6 +52,16 @@ impl<'a, T, L> BufferRef<'a, T, L> {
         };
         Self { samples, info }
     }
+
+    /// Get a reference to the buffer's samples.
+    pub fn samples(&self) -> &'a [T] {
+        self.samples
+    }
+
+    /// Get the buffer's info.
+    pub fn info(&self) -> BufferInfo<L> {
+        self.info
+    }
 }
 
 /// Buffer multi-channel interlaced Audio.
@@ -89,6 +99,21 @@ impl<T, L> Buffer<T, L> {
         };
         BufferRef { samples, info }
     }
+
+    /// Get a reference to the buffer's samples.
+    pub fn samples(&self) -> &[T] {
+        &self.samples[..]
+    }
+
+    /// Get the buffer's info.
+    pub fn info(&self) -> BufferInfo<L> {
+        self.info
+    }
+
+    /// Consumes the buffer, returning its samples.
+    pub fn into_samples(self) -> Vec<T> {
+        self.samples
+    }
 }
 
 impl<T> From<Buffer<T, Deinterleaved>> for Buffer<T, Interleaved>
@@ -0,0 +1,114 @@
+use std::iter::StepBy;
+use std::ops::Range;
+
+use crate::audio::buffer::{Buffer, BufferInfo, BufferLayout, BufferRef};
+use crate::audio::AudioSample;
+
+/// Mixes the channels of a buffer to another number of channels, converting its layout
+/// on the way, e.g. from an interleaved stereo buffer to a deinterleaved mono one.
+///
+/// Down-mixing averages the input channels into the output channel `channel % channels`,
+/// so that mono is the average of all channels. Up-mixing repeats the input channels,
+/// so that mono is copied to every channel.
+///
+/// # Panics
+///
+/// Panics if `channels` is zero.
+pub fn mix_channels<T, I, O>(input: &BufferRef<'_, T, I>, channels: usize) -> Buffer<T, O>
+where
+    T: AudioSample,
+    I: BufferLayout,
+    O: BufferLayout,
+{
+    assert!(channels > 0, "a buffer needs at least one channel");
+
+    let input_info = input.info();
+    let frames = input_info.frames();
+    let output_info = BufferInfo::<O>::new(channels, frames);
+    let input_samples = input.samples();
+
+    let mut samples = vec![T::default(); output_info.samples()];
+    for channel in 0..channels {
+        let sources = source_channels(input_info.channels(), channels, channel);
+        let count = sources.len();
+        let first = channel % input_info.channels();
+        for frame in 0..frames {
+            let output_index = O::index_of(&output_info, channel, frame);
+            samples[output_index] = if count == 1 {
+                // Copied, so that the samples aren't changed by a round-trip through `f32`.
+                input_samples[I::index_of(&input_info, first, frame)]
+            } else {
+                let sum: f32 = sources
+                    .clone()
+                    .map(|source| input_samples[I::index_of(&input_info, source, frame)].to_f32())
+                    .sum();
+                T::from_f32(sum / count as f32)
+            };
+        }
+    }
+
+    Buffer::new(samples, channels)
+}
+
+/// Returns the input channels that are mixed into an output `channel`.
+pub(crate) fn source_channels(
+    input_channels: usize,
+    output_channels: usize,
+    channel: usize,
+) -> StepBy<Range<usize>> {
+    if output_channels < input_channels {
+        (channel..input_channels).step_by(output_channels)
+    } else {
+        let source = channel % input_channels;
+        (source..source + 1).step_by(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::buffer::layout::{Deinterleaved, Interleaved};
+
+    #[test]
+    fn mix_stereo_to_mono() {
+        let input: Buffer<i16, Interleaved> = Buffer::new(vec![100, 300, -100, -300, 7, 7], 2);
+
+        let output: Buffer<i16, Interleaved> = mix_channels(&input.as_ref(), 1);
+
+        assert_eq!(output.info().channels(), 1);
+        assert_eq!(output.samples(), &[200, -200, 7]);
+    }
+
+    #[test]
+    fn mix_mono_to_stereo() {
+        let input: Buffer<i16, Interleaved> = Buffer::new(vec![1, 2, 3], 1);
+
+        let interleaved: Buffer<i16, Interleaved> = mix_channels(&input.as_ref(), 2);
+        assert_eq!(interleaved.samples(), &[1, 1, 2, 2, 3, 3]);
+
+        let deinterleaved: Buffer<i16, Deinterleaved> = mix_channels(&input.as_ref(), 2);
+        assert_eq!(deinterleaved.samples(), &[1, 2, 3, 1, 2, 3]);
+    }
+
+    #[test]
+    fn mix_channels_between_layouts() {
+        let input: Buffer<f32, Deinterleaved> =
+            Buffer::new(vec![0.0, 0.25, 0.5, 0.5, 0.75, 1.0, -0.5, 0.25, 0.0], 3);
+
+        let same: Buffer<f32, Interleaved> = mix_channels(&input.as_ref(), 3);
+        assert_eq!(
+            same.samples(),
+            &[0.0, 0.5, -0.5, 0.25, 0.75, 0.25, 0.5, 1.0, 0.0]
+        );
+
+        // The first and the third channel are averaged into the first channel
+        let down: Buffer<f32, Interleaved> = mix_channels(&input.as_ref(), 2);
+        assert_eq!(down.samples(), &[-0.25, 0.5, 0.25, 0.75, 0.25, 1.0]);
+
+        let up: Buffer<f32, Deinterleaved> = mix_channels(&input.as_ref(), 4);
+        assert_eq!(
+            up.samples(),
+            &[0.0, 0.25, 0.5, 0.5, 0.75, 1.0, -0.5, 0.25, 0.0, 0.0, 0.25, 0.5]
+        );
+    }
+}
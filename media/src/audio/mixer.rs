@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use crate::audio::buffer::{Buffer, BufferInfo, BufferLayout, BufferRef};
+use crate::audio::channel_mixer::source_channels;
+use crate::audio::AudioSample;
+
+/// Per frame recovery of the limiter gain towards one, about 100ms at 48kHz.
+const LIMITER_RELEASE: f32 = 1.0 / 4800.0;
+
+/// Mixer mixes the audio of any number of inputs into one buffer, e.g. the participants
+/// of a conference, with a gain per input.
+///
+/// The inputs are identified by an id, e.g. their SSRC, and are mixed to the channels
+/// of the mixer as [`mix_channels`](crate::audio::channel_mixer::mix_channels) does.
+/// A limiter protects the mix from clipping: it lowers the gain of the mix as soon as it
+/// would clip and lets it recover over about 4800 frames. A mix for each participant
+/// without its own audio takes a mixer each, as the limiter keeps state.
+pub struct Mixer {
+    channels: usize,
+    gains: HashMap<u32, f32>,
+    limiter_gain: f32,
+}
+
+impl Mixer {
+    /// Creates a mixer with `channels` output channels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channels` is zero.
+    pub fn new(channels: usize) -> Self {
+        assert!(channels > 0, "a buffer needs at least one channel");
+
+        Mixer {
+            channels,
+            gains: HashMap::new(),
+            limiter_gain: 1.0,
+        }
+    }
+
+    /// Returns the number of output channels.
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// Returns the gain of an input, which is one unless it was set.
+    pub fn gain(&self, input: u32) -> f32 {
+        self.gains.get(&input).copied().unwrap_or(1.0)
+    }
+
+    /// Sets the linear gain of an input, e.g. zero to mute it or 0.5 for -6dB.
+    pub fn set_gain(&mut self, input: u32, gain: f32) {
+        self.gains.insert(input, gain);
+    }
+
+    /// Forgets the gain of an input that left.
+    pub fn remove_input(&mut self, input: u32) {
+        self.gains.remove(&input);
+    }
+
+    /// Mixes the buffers of the inputs into a buffer of the longest of them, in which the
+    /// shorter ones are followed by silence. The inputs may have different channels.
+    pub fn mix<T, L>(&mut self, inputs: &[(u32, BufferRef<'_, T, L>)]) -> Buffer<T, L>
+    where
+        T: AudioSample,
+        L: BufferLayout,
+    {
+        let frames = inputs
+            .iter()
+            .map(|(_, buffer)| buffer.info().frames())
+            .max()
+            .unwrap_or(0);
+        let info = BufferInfo::<L>::new(self.channels, frames);
+
+        let mut mix = vec![0.0f32; info.samples()];
+        for (input, buffer) in inputs {
+            let gain = self.gain(*input);
+            if gain == 0.0 {
+                continue;
+            }
+
+            let input_info = buffer.info();
+            let samples = buffer.samples();
+            for channel in 0..self.channels {
+                let sources = source_channels(input_info.channels(), self.channels, channel);
+                let gain = gain / sources.len() as f32;
+                for source in sources {
+                    for frame in 0..input_info.frames() {
+                        let sample = samples[L::index_of(&input_info, source, frame)].to_f32();
+                        mix[L::index_of(&info, channel, frame)] += gain * sample;
+                    }
+                }
+            }
+        }
+
+        let mut samples = vec![T::default(); info.samples()];
+        for frame in 0..frames {
+            let peak = (0..self.channels)
+                .map(|channel| mix[L::index_of(&info, channel, frame)].abs())
+                .fold(0.0f32, f32::max);
+
+            self.limiter_gain = (self.limiter_gain + LIMITER_RELEASE).min(1.0);
+            if peak * self.limiter_gain > 1.0 {
+                self.limiter_gain = 1.0 / peak;
+            }
+
+            for channel in 0..self.channels {
+                let index = L::index_of(&info, channel, frame);
+                samples[index] = T::from_f32(mix[index] * self.limiter_gain);
+            }
+        }
+
+        Buffer::new(samples, self.channels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::buffer::layout::{Deinterleaved, Interleaved};
+
+    #[test]
+    fn mix_inputs_with_gain() {
+        let mut mixer = Mixer::new(2);
+        mixer.set_gain(2, 0.5);
+        mixer.set_gain(3, 0.0);
+        assert_eq!(mixer.gain(1), 1.0);
+        assert_eq!(mixer.gain(2), 0.5);
+
+        let stereo: Buffer<f32, Interleaved> = Buffer::new(vec![0.25, -0.25, 0.125, -0.125], 2);
+        let mono: Buffer<f32, Interleaved> = Buffer::new(vec![0.5, 0.5, 0.5], 1);
+        let muted: Buffer<f32, Interleaved> = Buffer::new(vec![0.75; 6], 2);
+
+        let output = mixer.mix(&[
+            (1, stereo.as_ref()),
+            (2, mono.as_ref()),
+            (3, muted.as_ref()),
+        ]);
+
+        assert_eq!(output.info().channels(), 2);
+        assert_eq!(output.samples(), &[0.5, 0.0, 0.375, 0.125, 0.25, 0.25]);
+
+        mixer.remove_input(2);
+        assert_eq!(mixer.gain(2), 1.0);
+    }
+
+    #[test]
+    fn mix_without_clipping() {
+        let mut mixer = Mixer::new(1);
+        let loud: Buffer<i16, Deinterleaved> = Buffer::new(vec![i16::MAX / 2; 4800], 1);
+
+        let output = mixer.mix(&[(1, loud.as_ref()), (2, loud.as_ref()), (3, loud.as_ref())]);
+
+        // The sum would be 1.5 times full scale, the limiter keeps it at full scale
+        assert!(output
+            .samples()
+            .iter()
+            .all(|&sample| sample > i16::MAX - 100));
+
+        // The gain recovers once the mix is quiet again
+        let quiet: Buffer<i16, Deinterleaved> = Buffer::new(vec![1000; 4800], 1);
+        let output = mixer.mix(&[(1, quiet.as_ref())]);
+        assert!(output.samples()[0] < 1000);
+        assert_eq!(output.samples()[4799], 1000);
+
+        let output = mixer.mix::<i16, Deinterleaved>(&[]);
+        assert_eq!(output.info().frames(), 0);
+    }
+}
@@ -1,7 +1,10 @@
 pub mod buffer;
+pub mod channel_mixer;
+pub mod mixer;
+pub mod resampler;
 mod sample;
 
-pub use sample::Sample;
+pub use sample::{AudioSample, Sample};
 
 mod sealed {
     pub trait Sealed {}
@@ -0,0 +1,324 @@
+use std::f64::consts::PI;
+
+use crate::audio::buffer::{Buffer, BufferInfo, BufferLayout, BufferRef};
+use crate::audio::AudioSample;
+
+/// Zero crossings of the windowed sinc on either side of its center.
+const ZERO_CROSSINGS: f64 = 16.0;
+/// Cutoff of the anti-aliasing filter, relative to the lower Nyquist frequency.
+const CUTOFF: f64 = 0.95;
+
+/// Resampler converts audio between sample rates, e.g. from 48kHz to 16kHz or 44.1kHz,
+/// with a polyphase windowed sinc filter that band-limits the audio to the lower of both
+/// Nyquist frequencies.
+///
+/// It keeps the audio between calls to [`process`](Resampler::process), so that a stream
+/// can be resampled in chunks of any size. The frames at the end of the input are held
+/// back until the frames that follow them arrive, [`flush`](Resampler::flush) returns
+/// them at the end of the stream.
+pub struct Resampler {
+    channels: usize,
+    input_rate: u32,
+    output_rate: u32,
+    /// Output frames per `down` input frames.
+    up: usize,
+    down: usize,
+    /// Input frames on either side of an output frame the filter reaches.
+    half_taps: usize,
+    /// Coefficients of the filter for each of the `up` phases of an output frame.
+    filters: Vec<Vec<f32>>,
+    /// Input frames per channel, from `half_taps` frames before `position`.
+    history: Vec<Vec<f32>>,
+    /// Index into `history` of the input frame at or before the next output frame.
+    position: usize,
+    /// Offset of the next output frame from `position`, in `1 / up` input frames.
+    phase: usize,
+}
+
+impl Resampler {
+    /// Creates a resampler of `channels` channels from `input_rate` to `output_rate`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channels` or one of the rates is zero.
+    pub fn new(input_rate: u32, output_rate: u32, channels: usize) -> Self {
+        assert!(channels > 0, "a buffer needs at least one channel");
+        assert!(
+            input_rate > 0 && output_rate > 0,
+            "sample rates need to be positive"
+        );
+
+        let divisor = gcd(input_rate, output_rate);
+        let up = (output_rate / divisor) as usize;
+        let down = (input_rate / divisor) as usize;
+
+        let (half_taps, filters) = if up == down {
+            (0, vec![vec![1.0]])
+        } else {
+            // In cycles per input frame
+            let cutoff = 0.5 * CUTOFF * (up as f64 / down as f64).min(1.0);
+            let half_width = ZERO_CROSSINGS / (2.0 * cutoff);
+            let half_taps = half_width.ceil() as usize;
+            let filters = (0..up)
+                .map(|phase| {
+                    let offset = phase as f64 / up as f64;
+                    let taps: Vec<f64> = (0..=2 * half_taps)
+                        .map(|tap| {
+                            let t = offset + half_taps as f64 - tap as f64;
+                            windowed_sinc(t, cutoff, half_width)
+                        })
+                        .collect();
+                    // Normalized to a gain of one at DC
+                    let sum: f64 = taps.iter().sum();
+                    taps.iter().map(|tap| (tap / sum) as f32).collect()
+                })
+                .collect();
+            (half_taps, filters)
+        };
+
+        Resampler {
+            channels,
+            input_rate,
+            output_rate,
+            up,
+            down,
+            half_taps,
+            filters,
+            history: vec![vec![0.0; half_taps]; channels],
+            position: half_taps,
+            phase: 0,
+        }
+    }
+
+    /// Returns the sample rate of the input.
+    pub fn input_rate(&self) -> u32 {
+        self.input_rate
+    }
+
+    /// Returns the sample rate of the output.
+    pub fn output_rate(&self) -> u32 {
+        self.output_rate
+    }
+
+    /// Returns the number of channels.
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// Resamples the input and returns the output frames that are complete.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the input doesn't have the channels of the resampler.
+    pub fn process<T, L>(&mut self, input: &BufferRef<'_, T, L>) -> Buffer<T, L>
+    where
+        T: AudioSample,
+        L: BufferLayout,
+    {
+        let info = input.info();
+        assert_eq!(info.channels(), self.channels, "unexpected channels");
+
+        let samples = input.samples();
+        for (channel, history) in self.history.iter_mut().enumerate() {
+            history.extend(
+                (0..info.frames())
+                    .map(|frame| samples[L::index_of(&info, channel, frame)].to_f32()),
+            );
+        }
+
+        self.resample()
+    }
+
+    /// Returns the output frames that are held back at the end of a stream. The resampler
+    /// continues with silence before the next input.
+    pub fn flush<T, L>(&mut self) -> Buffer<T, L>
+    where
+        T: AudioSample,
+        L: BufferLayout,
+    {
+        for history in &mut self.history {
+            history.resize(history.len() + self.half_taps, 0.0);
+        }
+
+        self.resample()
+    }
+
+    /// Resets the resampler to the start of a stream.
+    pub fn reset(&mut self) {
+        for history in &mut self.history {
+            history.clear();
+            history.resize(self.half_taps, 0.0);
+        }
+        self.position = self.half_taps;
+        self.phase = 0;
+    }
+
+    fn resample<T, L>(&mut self) -> Buffer<T, L>
+    where
+        T: AudioSample,
+        L: BufferLayout,
+    {
+        let available = self.history[0].len();
+
+        let mut frames = vec![];
+        while self.position + self.half_taps < available {
+            frames.push((self.position, self.phase));
+            self.phase += self.down;
+            self.position += self.phase / self.up;
+            self.phase %= self.up;
+        }
+
+        let info = BufferInfo::<L>::new(self.channels, frames.len());
+        let mut samples = vec![T::default(); info.samples()];
+        for (channel, history) in self.history.iter().enumerate() {
+            for (frame, &(position, phase)) in frames.iter().enumerate() {
+                let window = &history[position - self.half_taps..=position + self.half_taps];
+                let value: f32 = window
+                    .iter()
+                    .zip(&self.filters[phase])
+                    .map(|(sample, tap)| sample * tap)
+                    .sum();
+                samples[L::index_of(&info, channel, frame)] = T::from_f32(value);
+            }
+        }
+
+        // Only the input frames the filter of the next output frame reaches are kept
+        let consumed = self.position.min(available) - self.half_taps;
+        for history in &mut self.history {
+            history.drain(..consumed);
+        }
+        self.position -= consumed;
+
+        Buffer::new(samples, self.channels)
+    }
+}
+
+fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        let remainder = a % b;
+        a = b;
+        b = remainder;
+    }
+    a
+}
+
+/// A sinc low-pass filter of `cutoff` cycles per frame, with a Blackman window that
+/// ends at `half_width` frames, at `t` frames from its center.
+fn windowed_sinc(t: f64, cutoff: f64, half_width: f64) -> f64 {
+    if t.abs() >= half_width {
+        return 0.0;
+    }
+
+    let x = 2.0 * cutoff * t;
+    let sinc = if x == 0.0 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    };
+    let w = t / half_width;
+    let window = 0.42 + 0.5 * (PI * w).cos() + 0.08 * (2.0 * PI * w).cos();
+
+    2.0 * cutoff * sinc * window
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::buffer::layout::{Deinterleaved, Interleaved};
+
+    fn sine(frequency: f64, rate: u32, frames: usize) -> Vec<f32> {
+        (0..frames)
+            .map(|frame| (0.5 * (2.0 * PI * frequency * frame as f64 / rate as f64).sin()) as f32)
+            .collect()
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|sample| sample * sample).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn resample_sine() {
+        for (input_rate, output_rate) in [
+            (48000, 16000),
+            (16000, 48000),
+            (48000, 44100),
+            (44100, 48000),
+        ] {
+            let mut resampler = Resampler::new(input_rate, output_rate, 1);
+            let input: Buffer<f32, Interleaved> =
+                Buffer::new(sine(1000.0, input_rate, input_rate as usize / 10), 1);
+
+            let mut output = resampler.process(&input.as_ref()).into_samples();
+            output.extend(resampler.flush::<f32, Interleaved>().into_samples());
+
+            // The output frames are at the time of the input frames, without a delay
+            assert_eq!(output.len(), output_rate as usize / 10);
+            let expected = sine(1000.0, output_rate, output.len());
+            let skip = output.len() / 5;
+            for (frame, (actual, expected)) in output
+                .iter()
+                .zip(&expected)
+                .enumerate()
+                .skip(skip)
+                .take(output.len() - 2 * skip)
+            {
+                assert!(
+                    (actual - expected).abs() < 0.001,
+                    "{} to {}: frame {} is {} instead of {}",
+                    input_rate,
+                    output_rate,
+                    frame,
+                    actual,
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn resample_filters_aliases() {
+        let mut resampler = Resampler::new(48000, 16000, 1);
+        // Above the Nyquist frequency of 8kHz of the output
+        let input: Buffer<f32, Interleaved> = Buffer::new(sine(12000.0, 48000, 4800), 1);
+
+        let output = resampler.process(&input.as_ref());
+
+        let samples = output.samples();
+        assert!(rms(&samples[200..samples.len() - 200]) < 0.001);
+    }
+
+    #[test]
+    fn resample_in_chunks() {
+        let input: Vec<i16> = sine(440.0, 44100, 4410)
+            .into_iter()
+            .flat_map(|sample| [i16::from_f32(sample), i16::from_f32(-sample)])
+            .collect();
+        let input: Buffer<i16, Interleaved> = Buffer::new(input, 2);
+
+        let mut resampler = Resampler::new(44100, 48000, 2);
+        let whole: Buffer<i16, Interleaved> = resampler.process(&input.as_ref());
+
+        resampler.reset();
+        let mut chunks = vec![];
+        let mut start = 0;
+        for chunk in [1, 440, 7, 1000, 2962] {
+            let chunk = input.sub_range(start * 2..(start + chunk) * 2);
+            let output: Buffer<i16, Interleaved> = resampler.process(&chunk);
+            chunks.extend_from_slice(output.samples());
+            start += chunk.info().frames();
+        }
+        assert_eq!(start, 4410);
+
+        assert_eq!(chunks, whole.samples());
+    }
+
+    #[test]
+    fn resample_same_rate() {
+        let mut resampler = Resampler::new(48000, 48000, 1);
+        let input: Buffer<i16, Deinterleaved> = Buffer::new(vec![1, -2, 3, i16::MIN, i16::MAX], 1);
+
+        let output: Buffer<i16, Deinterleaved> = resampler.process(&input.as_ref());
+
+        assert_eq!(output, input);
+    }
+}
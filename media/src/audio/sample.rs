@@ -2,6 +2,8 @@ use std::io::{Cursor, Read};
 
 use byteorder::{ByteOrder, ReadBytesExt};
 
+use crate::audio::sealed::Sealed;
+
 #[cfg(test)]
 use nearly_eq::NearlyEq;
 
@@ -83,6 +85,49 @@ impl From<Sample<f32>> for Sample<i16> {
     }
 }
 
+/// A raw sample type of the audio processing, which is done on `f32` samples in the
+/// range of `-1.0..=1.0`.
+pub trait AudioSample: Copy + Default + Sealed {
+    fn to_f32(self) -> f32;
+    fn from_f32(value: f32) -> Self;
+}
+
+impl Sealed for i16 {}
+
+impl AudioSample for i16 {
+    #[inline]
+    fn to_f32(self) -> f32 {
+        Sample::<f32>::from(Sample::from(self)).into()
+    }
+
+    #[inline]
+    fn from_f32(value: f32) -> Self {
+        // Rounded rather than truncated, so that a round-trip is lossless.
+        let value = value.clamp(-1.0, 1.0);
+        let multiplier = if value < 0.0 {
+            i16::MIN as f32
+        } else {
+            i16::MAX as f32
+        }
+        .abs();
+        (value * multiplier).round() as i16
+    }
+}
+
+impl Sealed for f32 {}
+
+impl AudioSample for f32 {
+    #[inline]
+    fn to_f32(self) -> f32 {
+        self
+    }
+
+    #[inline]
+    fn from_f32(value: f32) -> Self {
+        Sample::from(value).into()
+    }
+}
+
 trait FromBytes: Sized {
     fn from_reader<B: ByteOrder, R: Read>(reader: &mut R) -> Result<Self, std::io::Error>;
 
@@ -195,4 +240,15 @@ mod tests {
             Sample::from(1.0)
         );
     }
+
+    #[test]
+    fn audio_sample_i16_round_trip() {
+        for raw in [i16::MIN, i16::MIN / 2, -1, 0, 1, 12345, i16::MAX] {
+            assert_eq!(i16::from_f32(raw.to_f32()), raw);
+        }
+
+        // For any values outside of -1.0..=1.0 we expect clamping:
+        assert_eq!(i16::from_f32(-2.0), i16::MIN);
+        assert_eq!(i16::from_f32(2.0), i16::MAX);
+    }
 }
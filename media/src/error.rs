@@ -42,11 +42,47 @@ pub enum Error {
     #[error("Io EOF")]
     ErrIoEOF,
 
+    #[error("bad EBML header, not a WebM file")]
+    ErrWebmBadHeader,
+    #[error("invalid EBML variable size integer")]
+    ErrWebmInvalidVint,
+    #[error("no tracks before the first cluster")]
+    ErrWebmMissingTracks,
+    #[error("laced blocks are not supported")]
+    ErrWebmLacingUnsupported,
+    #[error("no track for the packet or sample")]
+    ErrWebmUnknownTrack,
+    #[error("timecode is out of range")]
+    ErrWebmTimecodeOverflow,
+
+    #[error("clock rate of the jitter buffer is zero")]
+    ErrJitterBufferZeroClockRate,
+
+    #[error("no tracks to write")]
+    ErrFmp4MissingTracks,
+    #[error("no track for the packet or sample")]
+    ErrFmp4UnknownTrack,
+    #[error("invalid or unsupported SPS")]
+    ErrFmp4InvalidSps,
+
+    #[error("bad rtpdump header, not an rtpdump file")]
+    ErrRtpDumpBadHeader,
+    #[error("rtpdump packet is shorter than its header")]
+    ErrRtpDumpInvalidPacket,
+    #[error("bad header, not a pcap or pcapng file")]
+    ErrPcapBadHeader,
+    #[error("unsupported pcap link type")]
+    ErrPcapUnsupportedLinkType,
+    #[error("invalid pcapng block length")]
+    ErrPcapInvalidBlock,
+
     #[allow(non_camel_case_types)]
     #[error("{0}")]
     Io(#[source] IoError),
     #[error("{0}")]
     Rtp(#[from] rtp::Error),
+    #[error("{0}")]
+    Util(#[from] util::Error),
 
     #[error("{0}")]
     Other(String),
@@ -0,0 +1,455 @@
+use super::*;
+
+use rtp::header::Header;
+use rtp::packet::Packet;
+
+const SPS: &[u8] = &[0x67, 0x42, 0xC0, 0x1F, 0x8C, 0x8D, 0x40];
+const PPS: &[u8] = &[0x68, 0xCE, 0x3C, 0x80];
+const IDR: &[u8] = &[0x65, 0x88, 0x84, 0x00];
+const NON_IDR: &[u8] = &[0x41, 0x9A, 0x02, 0x03];
+
+fn h264_packet(sequence_number: u16, timestamp: u32, marker: bool, nalu: &[u8]) -> Packet {
+    Packet {
+        header: Header {
+            payload_type: 96,
+            sequence_number,
+            timestamp,
+            marker,
+            ..Default::default()
+        },
+        payload: Bytes::copy_from_slice(nalu),
+    }
+}
+
+fn sample(data: &[u8], origin: SystemTime, packet_timestamp: u32, clock_rate: u32) -> Sample {
+    Sample {
+        data: Bytes::copy_from_slice(data),
+        timestamp: origin + Duration::from_secs_f64(packet_timestamp as f64 / clock_rate as f64),
+        packet_timestamp,
+        ..Default::default()
+    }
+}
+
+fn annexb(nalus: &[&[u8]]) -> Vec<u8> {
+    let mut data = vec![];
+    for nalu in nalus {
+        data.extend_from_slice(ANNEXB_START_CODE);
+        data.extend_from_slice(nalu);
+    }
+    data
+}
+
+/// A box of the file, with its position and content
+struct Mp4Box<'a> {
+    box_type: [u8; 4],
+    position: usize,
+    content: &'a [u8],
+}
+
+fn read_boxes(data: &[u8]) -> Vec<Mp4Box<'_>> {
+    let mut boxes = vec![];
+    let mut offset = 0;
+    while offset < data.len() {
+        let size = u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap()) as usize;
+        assert!(size >= 8 && offset + size <= data.len(), "invalid box size");
+        boxes.push(Mp4Box {
+            box_type: data[offset + 4..offset + 8].try_into().unwrap(),
+            position: offset,
+            content: &data[offset + 8..offset + size],
+        });
+        offset += size;
+    }
+    boxes
+}
+
+/// find_box returns the first box at the path of box types, where a sample entry is
+/// followed by its skip bytes
+fn find_box<'a>(data: &'a [u8], path: &[(&[u8; 4], usize)]) -> Option<&'a [u8]> {
+    let mut content = data;
+    for (box_type, skip) in path {
+        content = read_boxes(content)
+            .into_iter()
+            .find(|b| &b.box_type == *box_type)?
+            .content;
+        content = &content[*skip..];
+    }
+    Some(content)
+}
+
+/// sample_entry_path returns the path of a sample entry in a trak, skipping its fields
+fn sample_entry_path(entry: &[u8; 4], skip: usize) -> Vec<(&[u8; 4], usize)> {
+    vec![
+        (b"trak", 0),
+        (b"mdia", 0),
+        (b"minf", 0),
+        (b"stbl", 0),
+        (b"stsd", 8),
+        (entry, skip),
+    ]
+}
+
+/// the duration, size and flags of a sample of a trun box
+type TrunSample = (u32, u32, u32);
+
+struct Fragment {
+    sequence_number: u32,
+    /// the tracks of the fragment with their decode time and samples
+    tracks: Vec<(u32, u64, Vec<TrunSample>)>,
+    /// the data of the first sample of every track
+    first_samples: Vec<Vec<u8>>,
+}
+
+fn read_fragments(file: &[u8]) -> Vec<Fragment> {
+    let boxes = read_boxes(file);
+    let mut fragments = vec![];
+    for (i, b) in boxes.iter().enumerate() {
+        if &b.box_type != b"moof" {
+            continue;
+        }
+        let mdat = &boxes[i + 1];
+        assert_eq!(&mdat.box_type, b"mdat");
+
+        let mfhd = find_box(b.content, &[(b"mfhd", 4)]).unwrap();
+        let mut fragment = Fragment {
+            sequence_number: u32::from_be_bytes(mfhd[..4].try_into().unwrap()),
+            tracks: vec![],
+            first_samples: vec![],
+        };
+        for traf in read_boxes(b.content)
+            .into_iter()
+            .filter(|b| &b.box_type == b"traf")
+        {
+            let tfhd = find_box(traf.content, &[(b"tfhd", 0)]).unwrap();
+            assert_eq!(u32::from_be_bytes(tfhd[..4].try_into().unwrap()), 0x02_0000);
+            let track_id = u32::from_be_bytes(tfhd[4..8].try_into().unwrap());
+
+            let tfdt = find_box(traf.content, &[(b"tfdt", 0)]).unwrap();
+            assert_eq!(tfdt[0], 1);
+            let decode_time = u64::from_be_bytes(tfdt[4..12].try_into().unwrap());
+
+            let trun = find_box(traf.content, &[(b"trun", 0)]).unwrap();
+            assert_eq!(u32::from_be_bytes(trun[..4].try_into().unwrap()), 0x00_0701);
+            let count = u32::from_be_bytes(trun[4..8].try_into().unwrap()) as usize;
+            let data_offset = u32::from_be_bytes(trun[8..12].try_into().unwrap()) as usize;
+            let samples: Vec<TrunSample> = trun[12..]
+                .chunks(12)
+                .map(|s| {
+                    (
+                        u32::from_be_bytes(s[..4].try_into().unwrap()),
+                        u32::from_be_bytes(s[4..8].try_into().unwrap()),
+                        u32::from_be_bytes(s[8..12].try_into().unwrap()),
+                    )
+                })
+                .collect();
+            assert_eq!(samples.len(), count);
+
+            // The data offset is relative to the moof and points into the mdat
+            let start = b.position + data_offset;
+            assert!(start >= mdat.position + 8);
+            let end = start + samples.iter().map(|s| s.1 as usize).sum::<usize>();
+            assert!(end <= mdat.position + 8 + mdat.content.len());
+            fragment
+                .first_samples
+                .push(file[start..start + samples[0].1 as usize].to_vec());
+
+            fragment.tracks.push((track_id, decode_time, samples));
+        }
+        fragments.push(fragment);
+    }
+    fragments
+}
+
+#[test]
+fn test_fmp4_writer_h264_rtp() -> Result<()> {
+    let mut file = vec![];
+    let mut writer = Fmp4Writer::new(
+        &mut file,
+        vec![Fmp4Track::video(96, Fmp4Codec::H264, 640, 480)],
+    )?;
+
+    let mut sequence_number = 0;
+    let mut push =
+        |writer: &mut Fmp4Writer<&mut Vec<u8>>, timestamp: u32, nalus: &[&[u8]]| -> Result<()> {
+            for (i, nalu) in nalus.iter().enumerate() {
+                writer.write_rtp(&h264_packet(
+                    sequence_number,
+                    timestamp,
+                    i == nalus.len() - 1,
+                    nalu,
+                ))?;
+                sequence_number += 1;
+            }
+            Ok(())
+        };
+
+    // The frames before the first key frame are dropped
+    push(&mut writer, 0, &[NON_IDR])?;
+    push(&mut writer, 3000, &[SPS, PPS, IDR])?;
+    for i in 2..5 {
+        push(&mut writer, i * 3000, &[NON_IDR])?;
+    }
+    push(&mut writer, 15000, &[SPS, PPS, IDR])?;
+    push(&mut writer, 18000, &[NON_IDR])?;
+    // The last frame is incomplete until the next one arrives
+    push(&mut writer, 21000, &[NON_IDR])?;
+    writer.close()?;
+    writer.close()?;
+    assert_eq!(
+        writer.write_rtp(&h264_packet(100, 24000, true, NON_IDR)),
+        Err(Error::ErrFileNotOpened)
+    );
+
+    let boxes = read_boxes(&file);
+    let types: Vec<&[u8; 4]> = boxes.iter().map(|b| &b.box_type).collect();
+    assert_eq!(
+        types,
+        vec![b"ftyp", b"moov", b"moof", b"mdat", b"moof", b"mdat"]
+    );
+    assert_eq!(&boxes[0].content[..4], b"iso6");
+    assert!(boxes[0].content.windows(4).any(|brand| brand == b"cmfc"));
+
+    let moov = boxes[1].content;
+    let mdhd = find_box(moov, &[(b"trak", 0), (b"mdia", 0), (b"mdhd", 12)]).unwrap();
+    assert_eq!(u32::from_be_bytes(mdhd[..4].try_into().unwrap()), 90000);
+    let avcc = find_box(
+        moov,
+        &[&sample_entry_path(b"avc1", 78)[..], &[(b"avcC", 0)]].concat(),
+    )
+    .unwrap();
+    assert_eq!(&avcc[..6], &[1, 0x42, 0xC0, 0x1F, 0xFF, 0xE1]);
+    assert_eq!(&avcc[8..8 + SPS.len()], SPS);
+    assert_eq!(&avcc[8 + SPS.len() + 3..], PPS);
+    assert!(find_box(moov, &[(b"mvex", 0), (b"trex", 0)]).is_some());
+
+    let fragments = read_fragments(&file);
+    assert_eq!(fragments.len(), 2);
+    assert_eq!(fragments[0].sequence_number, 1);
+    assert_eq!(fragments[1].sequence_number, 2);
+
+    // A fragment starts at every key frame, and continues the decode time of the previous one
+    let (track_id, decode_time, samples) = &fragments[0].tracks[0];
+    assert_eq!((*track_id, *decode_time), (1, 0));
+    assert_eq!(samples.len(), 4);
+    assert!(samples.iter().all(|s| s.0 == 3000));
+    assert_eq!(samples[0].2, SAMPLE_FLAGS_SYNC);
+    assert!(samples[1..].iter().all(|s| s.2 == SAMPLE_FLAGS_NON_SYNC));
+
+    let (_, decode_time, samples) = &fragments[1].tracks[0];
+    assert_eq!(*decode_time, 12000);
+    assert_eq!(samples.len(), 2);
+    assert!(samples.iter().all(|s| s.0 == 3000));
+    assert_eq!(samples[0].2, SAMPLE_FLAGS_SYNC);
+
+    // The samples are length prefixed NAL units
+    let mut expected = vec![];
+    for nalu in [SPS, PPS, IDR] {
+        expected.extend_from_slice(&(nalu.len() as u32).to_be_bytes());
+        expected.extend_from_slice(nalu);
+    }
+    assert_eq!(fragments[0].first_samples[0], expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_fmp4_writer_h265_samples() -> Result<()> {
+    let vps: &[u8] = &[0x40, 0x01, 0x0C, 0x01, 0xFF, 0xFF];
+    // profile_tier_level with the Main profile at level 3.1, and an emulation prevention byte
+    let sps: &[u8] = &[
+        0x42, 0x01, 0x01, 0x01, 0x60, 0x00, 0x00, 0x03, 0x00, 0x90, 0x00, 0x00, 0x03, 0x00, 0x00,
+        0x03, 0x00, 0x5D, 0xA0, 0x02, 0x80, 0x80, 0x2D, 0x17,
+    ];
+    let pps: &[u8] = &[0x44, 0x01, 0xC1, 0x72];
+    let aud: &[u8] = &[0x46, 0x01, 0x50];
+    let idr: &[u8] = &[0x26, 0x01, 0xAF, 0x00];
+    let trail: &[u8] = &[0x02, 0x01, 0xD0, 0x00];
+
+    let mut file = vec![];
+    let mut writer = Fmp4Writer::new(
+        &mut file,
+        vec![Fmp4Track::video(97, Fmp4Codec::H265, 1280, 720)],
+    )?;
+    assert_eq!(
+        writer.write_sample(1, &Sample::default()),
+        Err(Error::ErrFmp4UnknownTrack)
+    );
+
+    let origin = SystemTime::now();
+    writer.write_sample(
+        0,
+        &sample(&annexb(&[aud, vps, sps, pps, idr]), origin, 0, 90000),
+    )?;
+    writer.write_sample(0, &sample(&annexb(&[aud, trail]), origin, 3000, 90000))?;
+    writer.write_sample(0, &sample(&annexb(&[trail]), origin, 6000, 90000))?;
+    writer.close()?;
+
+    let boxes = read_boxes(&file);
+    let hvcc = find_box(
+        boxes[1].content,
+        &[&sample_entry_path(b"hvc1", 78)[..], &[(b"hvcC", 0)]].concat(),
+    )
+    .unwrap();
+    assert_eq!(hvcc[0], 1);
+    assert_eq!(hvcc[1], 0x01); // general_profile_idc Main
+    assert_eq!(&hvcc[2..6], &[0x60, 0x00, 0x00, 0x00]);
+    assert_eq!(&hvcc[6..12], &[0x90, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    assert_eq!(hvcc[12], 0x5D); // general_level_idc
+                                // 4:2:0 with 8 bits
+    assert_eq!(&hvcc[16..19], &[0xFD, 0xF8, 0xF8]);
+    assert_eq!(hvcc[21] & 0x03, 3); // lengthSizeMinusOne
+    assert_eq!(hvcc[22], 3); // numOfArrays
+    assert_eq!(hvcc[23], 0x80 | H265_NALU_TYPE_VPS);
+    assert_eq!(&hvcc[28..28 + vps.len()], vps);
+
+    let fragments = read_fragments(&file);
+    assert_eq!(fragments.len(), 1);
+    let (_, decode_time, samples) = &fragments[0].tracks[0];
+    assert_eq!(*decode_time, 0);
+    assert_eq!(
+        samples.iter().map(|s| s.0).collect::<Vec<u32>>(),
+        vec![3000, 3000, 3000]
+    );
+    assert_eq!(samples[0].2, SAMPLE_FLAGS_SYNC);
+    // The access unit delimiters are dropped
+    assert_eq!(samples[1].1 as usize, 4 + trail.len());
+
+    Ok(())
+}
+
+#[test]
+fn test_fmp4_writer_sps_format() {
+    // High 4:2:2 profile with 10 bits
+    let sps = Bytes::from_static(&[0x67, 0x7A, 0x00, 0x1F, 0xB6, 0xE0]);
+    let avcc = avc_decoder_configuration(&sps, &Bytes::from_static(PPS)).unwrap();
+    assert_eq!(&avcc[avcc.len() - 4..], &[0xFE, 0xFA, 0xFA, 0x00]);
+
+    // A truncated SPS of the High profile
+    assert_eq!(
+        avc_decoder_configuration(&sps.slice(..4), &Bytes::from_static(PPS)),
+        Err(Error::ErrFmp4InvalidSps)
+    );
+
+    // A range extensions profile with 4:4:4 and 10 bits, two sub-layers and a conformance window
+    let vps = Bytes::from_static(&[0x40, 0x01, 0x0C, 0x01, 0xFF, 0xFF]);
+    let sps = Bytes::from_static(&[
+        0x42, 0x01, 0x03, 0x04, 0x08, 0x00, 0x00, 0x03, 0x00, 0x00, 0x03, 0x00, 0x00, 0x03, 0x00,
+        0x00, 0x03, 0x00, 0x5D, 0xC0, 0x00, 0x00, 0x03, 0x00, 0x00, 0x03, 0x00, 0x00, 0x03, 0x00,
+        0x00, 0x03, 0x00, 0x00, 0x03, 0x00, 0x00, 0x5A, 0x90, 0x00, 0x50, 0x10, 0x05, 0xA3, 0xE5,
+        0x6E,
+    ]);
+    let pps = Bytes::from_static(&[0x44, 0x01, 0xC1, 0x72]);
+    let hvcc = hevc_decoder_configuration(&vps, &sps, &pps).unwrap();
+    assert_eq!(hvcc[1], 0x04); // general_profile_idc
+    assert_eq!(&hvcc[16..19], &[0xFF, 0xFA, 0xFA]);
+
+    assert_eq!(
+        hevc_decoder_configuration(&vps, &sps.slice(..sps.len() - 2), &pps),
+        Err(Error::ErrFmp4InvalidSps)
+    );
+}
+
+#[test]
+fn test_fmp4_writer_h265_depacketizer() {
+    let mut depacketizer = H265Depacketizer::default();
+
+    // Aggregation packet
+    let ap = Bytes::from_static(&[
+        0x60, 0x01, 0x00, 0x03, 0x40, 0x01, 0x0C, 0x00, 0x02, 0x44, 0x01,
+    ]);
+    assert!(depacketizer.is_partition_head(&ap));
+    assert_eq!(
+        depacketizer.depacketize(&ap).unwrap(),
+        Bytes::from(annexb(&[&[0x40, 0x01, 0x0C], &[0x44, 0x01]]))
+    );
+
+    // Fragmentation unit of an IDR_W_RADL
+    let start = Bytes::from_static(&[0x62, 0x01, 0x93, 0xAF, 0x01]);
+    let end = Bytes::from_static(&[0x62, 0x01, 0x53, 0x02]);
+    assert!(depacketizer.is_partition_head(&start));
+    assert!(!depacketizer.is_partition_head(&end));
+    assert!(depacketizer.depacketize(&start).unwrap().is_empty());
+    assert_eq!(
+        depacketizer.depacketize(&end).unwrap(),
+        Bytes::from(annexb(&[&[0x26, 0x01, 0xAF, 0x01, 0x02]]))
+    );
+
+    // A fragment without its start is dropped
+    assert!(depacketizer.depacketize(&end).unwrap().is_empty());
+}
+
+#[test]
+fn test_fmp4_writer_h264_opus() -> Result<()> {
+    let mut file = vec![];
+    let mut writer = Fmp4Writer::new(
+        &mut file,
+        vec![
+            Fmp4Track::video(96, Fmp4Codec::H264, 640, 480),
+            Fmp4Track::opus(111, 2),
+        ],
+    )?;
+
+    let origin = SystemTime::now();
+    // The audio before the first video key frame is dropped
+    writer.write_sample(1, &sample(&[0xFC, 0x00], origin, 0, 48000))?;
+    writer.write_sample(0, &sample(&annexb(&[SPS, PPS, IDR]), origin, 0, 90000))?;
+    // The audio starts 40ms after the video
+    for i in 2..7 {
+        writer.write_sample(1, &sample(&[0xFC, i as u8], origin, i * 960, 48000))?;
+    }
+    writer.write_sample(0, &sample(&annexb(&[NON_IDR]), origin, 3000, 90000))?;
+    writer.write_sample(0, &sample(&annexb(&[NON_IDR]), origin, 6000, 90000))?;
+    writer.close()?;
+
+    let boxes = read_boxes(&file);
+    let moov = boxes[1].content;
+    let traks: Vec<Mp4Box<'_>> = read_boxes(moov)
+        .into_iter()
+        .filter(|b| &b.box_type == b"trak")
+        .collect();
+    assert_eq!(traks.len(), 2);
+    let hdlr = find_box(traks[1].content, &[(b"mdia", 0), (b"hdlr", 8)]).unwrap();
+    assert_eq!(&hdlr[..4], b"soun");
+    let mut path = sample_entry_path(b"Opus", 28);
+    path.remove(0);
+    path.push((b"dOps", 0));
+    let dops = find_box(traks[1].content, &path).unwrap();
+    assert_eq!(dops[0], 0);
+    assert_eq!(dops[1], 2);
+    assert_eq!(u32::from_be_bytes(dops[4..8].try_into().unwrap()), 48000);
+
+    let fragments = read_fragments(&file);
+    assert_eq!(fragments.len(), 1);
+    let fragment = &fragments[0];
+    assert_eq!(fragment.tracks.len(), 2);
+
+    let (track_id, decode_time, samples) = &fragment.tracks[0];
+    assert_eq!((*track_id, *decode_time), (1, 0));
+    assert_eq!(samples.len(), 3);
+
+    let (track_id, decode_time, samples) = &fragment.tracks[1];
+    assert_eq!((*track_id, *decode_time), (2, 1920));
+    assert_eq!(samples.len(), 5);
+    assert!(samples
+        .iter()
+        .all(|s| s.0 == 960 && s.2 == SAMPLE_FLAGS_SYNC));
+    assert_eq!(fragment.first_samples[1], vec![0xFC, 0x02]);
+
+    Ok(())
+}
+
+#[test]
+fn test_fmp4_writer_errors() {
+    let mut file = vec![];
+    assert_eq!(
+        Fmp4Writer::new(&mut file, vec![]).err(),
+        Some(Error::ErrFmp4MissingTracks)
+    );
+
+    let mut writer = Fmp4Writer::new(&mut file, vec![Fmp4Track::opus(111, 2)]).unwrap();
+    assert_eq!(
+        writer.write_rtp(&h264_packet(0, 0, true, IDR)),
+        Err(Error::ErrFmp4UnknownTrack)
+    );
+    writer.close().unwrap();
+    assert!(file.is_empty());
+}
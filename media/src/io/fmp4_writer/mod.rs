@@ -0,0 +1,1002 @@
+#[cfg(test)]
+mod fmp4_writer_test;
+
+use crate::error::{Error, Result};
+use crate::io::ogg_reader::DEFAULT_PRE_SKIP;
+use crate::io::sample_builder::SampleBuilder;
+use crate::io::{BitReader, Writer};
+use crate::Sample;
+
+use bytes::{BufMut, Bytes, BytesMut};
+use rtp::codecs::{h264::H264Packet, opus::OpusPacket};
+use rtp::packetizer::Depacketizer;
+use std::io::Write;
+use std::time::{Duration, SystemTime};
+
+/// how many packets the sample builder of a track waits for a missing packet
+const SAMPLE_BUILDER_MAX_LATE: u16 = 256;
+/// the longest fragment when no video key frame starts a new one
+const DEFAULT_FRAGMENT_DURATION: Duration = Duration::from_secs(2);
+/// the timescale of the movie header
+const MOVIE_TIMESCALE: u32 = 1000;
+
+const ANNEXB_START_CODE: &[u8] = &[0x00, 0x00, 0x00, 0x01];
+
+const H264_NALU_TYPE_IDR: u8 = 5;
+const H264_NALU_TYPE_SPS: u8 = 7;
+const H264_NALU_TYPE_PPS: u8 = 8;
+const H264_NALU_TYPE_AUD: u8 = 9;
+
+const H265_NALU_TYPE_IRAP_START: u8 = 16;
+const H265_NALU_TYPE_IRAP_END: u8 = 21;
+const H265_NALU_TYPE_VPS: u8 = 32;
+const H265_NALU_TYPE_SPS: u8 = 33;
+const H265_NALU_TYPE_PPS: u8 = 34;
+const H265_NALU_TYPE_AUD: u8 = 35;
+const H265_NALU_TYPE_AP: u8 = 48;
+const H265_NALU_TYPE_FU: u8 = 49;
+const H265_NALU_TYPE_PACI: u8 = 50;
+
+/// sample_depends_on 2, a sync sample
+const SAMPLE_FLAGS_SYNC: u32 = 0x0200_0000;
+/// sample_depends_on 1 and sample_is_non_sync_sample
+const SAMPLE_FLAGS_NON_SYNC: u32 = 0x0101_0000;
+
+/// Fmp4Codec is a codec that can be stored in a fragmented MP4 file
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Fmp4Codec {
+    H264,
+    H265,
+    Opus,
+}
+
+impl Fmp4Codec {
+    pub fn is_video(&self) -> bool {
+        *self != Fmp4Codec::Opus
+    }
+}
+
+/// Fmp4Track configures a track of a fragmented MP4 file written by [`Fmp4Writer`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fmp4Track {
+    /// the RTP payload type of the packets of the track
+    pub payload_type: u8,
+    pub codec: Fmp4Codec,
+    /// the RTP clock rate of the track, which is the timescale of its media
+    pub clock_rate: u32,
+    /// the width and height of a video track, in pixels
+    pub width: u16,
+    pub height: u16,
+    /// the channel count of an audio track
+    pub channels: u8,
+}
+
+impl Fmp4Track {
+    /// video returns a video track with a 90kHz clock
+    pub fn video(payload_type: u8, codec: Fmp4Codec, width: u16, height: u16) -> Self {
+        Fmp4Track {
+            payload_type,
+            codec,
+            clock_rate: 90000,
+            width,
+            height,
+            channels: 0,
+        }
+    }
+
+    /// opus returns an Opus track with a 48kHz clock
+    pub fn opus(payload_type: u8, channels: u8) -> Self {
+        Fmp4Track {
+            payload_type,
+            codec: Fmp4Codec::Opus,
+            clock_rate: 48000,
+            width: 0,
+            height: 0,
+            channels,
+        }
+    }
+}
+
+/// H265Depacketizer depacketizes H.265 RTP payloads (RFC 7798) into an Annex-B bitstream
+#[derive(Default)]
+struct H265Depacketizer {
+    fu_buffer: Option<BytesMut>,
+}
+
+impl Depacketizer for H265Depacketizer {
+    fn depacketize(&mut self, packet: &Bytes) -> std::result::Result<Bytes, rtp::Error> {
+        if packet.len() < 3 {
+            return Err(rtp::Error::ErrShortPacket);
+        }
+
+        let mut payload = BytesMut::new();
+        match (packet[0] >> 1) & 0x3f {
+            H265_NALU_TYPE_AP => {
+                let mut offset = 2;
+                while offset + 2 <= packet.len() {
+                    let size = ((packet[offset] as usize) << 8) | packet[offset + 1] as usize;
+                    offset += 2;
+                    if offset + size > packet.len() {
+                        return Err(rtp::Error::ErrH265CorruptedPacket);
+                    }
+                    payload.put(ANNEXB_START_CODE);
+                    payload.put(&packet[offset..offset + size]);
+                    offset += size;
+                }
+            }
+            H265_NALU_TYPE_FU => {
+                let fu_header = packet[2];
+                if fu_header & 0x80 != 0 {
+                    let nalu_type = fu_header & 0x3f;
+                    let mut fu_buffer = BytesMut::new();
+                    fu_buffer.put_u8((packet[0] & 0x81) | (nalu_type << 1));
+                    fu_buffer.put_u8(packet[1]);
+                    self.fu_buffer = Some(fu_buffer);
+                }
+
+                if let Some(fu_buffer) = &mut self.fu_buffer {
+                    fu_buffer.put(&packet[3..]);
+                    if fu_header & 0x40 != 0 {
+                        payload.put(ANNEXB_START_CODE);
+                        payload.put(fu_buffer.split());
+                        self.fu_buffer = None;
+                    }
+                }
+            }
+            H265_NALU_TYPE_PACI => {}
+            _ => {
+                payload.put(ANNEXB_START_CODE);
+                payload.put(&packet[..]);
+            }
+        }
+
+        Ok(payload.freeze())
+    }
+
+    fn is_partition_head(&self, payload: &Bytes) -> bool {
+        if payload.len() < 3 {
+            false
+        } else if (payload[0] >> 1) & 0x3f == H265_NALU_TYPE_FU {
+            payload[2] & 0x80 != 0
+        } else {
+            true
+        }
+    }
+
+    fn is_partition_tail(&self, marker: bool, _payload: &Bytes) -> bool {
+        marker
+    }
+}
+
+enum TrackDepacketizer {
+    H264(H264Packet),
+    H265(H265Depacketizer),
+    Opus(OpusPacket),
+}
+
+impl TrackDepacketizer {
+    fn depacketizer(&self) -> &dyn Depacketizer {
+        match self {
+            TrackDepacketizer::H264(d) => d,
+            TrackDepacketizer::H265(d) => d,
+            TrackDepacketizer::Opus(d) => d,
+        }
+    }
+}
+
+impl Depacketizer for TrackDepacketizer {
+    fn depacketize(&mut self, b: &Bytes) -> std::result::Result<Bytes, rtp::Error> {
+        match self {
+            TrackDepacketizer::H264(d) => d.depacketize(b),
+            TrackDepacketizer::H265(d) => d.depacketize(b),
+            TrackDepacketizer::Opus(d) => d.depacketize(b),
+        }
+    }
+
+    fn is_partition_head(&self, payload: &Bytes) -> bool {
+        self.depacketizer().is_partition_head(payload)
+    }
+
+    fn is_partition_tail(&self, marker: bool, payload: &Bytes) -> bool {
+        self.depacketizer().is_partition_tail(marker, payload)
+    }
+}
+
+struct Mp4Sample {
+    /// decode time in the timescale of the track
+    decode_time: u64,
+    duration: u32,
+    is_sync: bool,
+    data: Bytes,
+}
+
+struct TrackState {
+    track: Fmp4Track,
+    track_id: u32,
+    builder: SampleBuilder<TrackDepacketizer>,
+    vps: Option<Bytes>,
+    sps: Option<Bytes>,
+    pps: Option<Bytes>,
+    /// the decode time of the first sample of the track
+    start: Option<u64>,
+    last_rtp_timestamp: u32,
+    /// the RTP timestamp since the first sample, unwrapped
+    rtp_elapsed: i64,
+    /// the last sample, which waits for the next one to know its duration
+    last_sample: Option<Mp4Sample>,
+    /// the samples of the current fragment
+    samples: Vec<Mp4Sample>,
+}
+
+impl TrackState {
+    fn has_parameter_sets(&self) -> bool {
+        match self.track.codec {
+            Fmp4Codec::H264 => self.sps.is_some() && self.pps.is_some(),
+            Fmp4Codec::H265 => self.vps.is_some() && self.sps.is_some() && self.pps.is_some(),
+            Fmp4Codec::Opus => true,
+        }
+    }
+
+    fn default_duration(&self) -> u32 {
+        match self.track.codec {
+            Fmp4Codec::Opus => self.track.clock_rate / 50,
+            _ => self.track.clock_rate / 30,
+        }
+    }
+}
+
+/// Fmp4Writer is used to take RTP packets or samples of H.264 or H.265 video and Opus audio
+/// and write them to a fragmented MP4 (CMAF) stream.
+///
+/// The stream starts with the `ftyp` and `moov` boxes, the init segment, which is written from
+/// the parameter sets of the first video key frame, and continues with `moof`/`mdat` fragments.
+/// A fragment starts at every key frame of the first video track, or once the fragment duration
+/// is reached. Video samples are converted from Annex-B to length prefixed NAL units, and the
+/// decode times of the samples are derived from their RTP timestamps. The tracks are aligned by
+/// the wallclock time of their first sample.
+pub struct Fmp4Writer<W: Write> {
+    writer: W,
+    tracks: Vec<TrackState>,
+    fragment_duration: Duration,
+    init_written: bool,
+    /// the wallclock time of the first sample of the stream
+    origin: Option<SystemTime>,
+    sequence_number: u32,
+    closed: bool,
+}
+
+impl<W: Write> Fmp4Writer<W> {
+    /// new initialize a new fragmented MP4 writer with an io.Writer output and the tracks of the stream
+    pub fn new(writer: W, tracks: Vec<Fmp4Track>) -> Result<Self> {
+        if tracks.is_empty() {
+            return Err(Error::ErrFmp4MissingTracks);
+        }
+
+        Ok(Fmp4Writer {
+            writer,
+            tracks: tracks
+                .into_iter()
+                .enumerate()
+                .map(|(i, track)| {
+                    let depacketizer = match track.codec {
+                        Fmp4Codec::H264 => TrackDepacketizer::H264(H264Packet::default()),
+                        Fmp4Codec::H265 => TrackDepacketizer::H265(H265Depacketizer::default()),
+                        Fmp4Codec::Opus => TrackDepacketizer::Opus(OpusPacket),
+                    };
+                    TrackState {
+                        builder: SampleBuilder::new(
+                            SAMPLE_BUILDER_MAX_LATE,
+                            depacketizer,
+                            track.clock_rate,
+                        ),
+                        track_id: i as u32 + 1,
+                        vps: None,
+                        sps: None,
+                        pps: None,
+                        start: None,
+                        last_rtp_timestamp: 0,
+                        rtp_elapsed: 0,
+                        last_sample: None,
+                        samples: vec![],
+                        track,
+                    }
+                })
+                .collect(),
+            fragment_duration: DEFAULT_FRAGMENT_DURATION,
+            init_written: false,
+            origin: None,
+            sequence_number: 0,
+            closed: false,
+        })
+    }
+
+    /// with_fragment_duration sets the longest fragment, which is used when the key frames are
+    /// further apart, for example to produce the partial segments of LL-HLS
+    pub fn with_fragment_duration(mut self, fragment_duration: Duration) -> Self {
+        self.fragment_duration = fragment_duration;
+        self
+    }
+
+    /// write_sample adds a sample of the track at track_index, in the order of the tracks given
+    /// to new. Video samples are in Annex-B format. The sample is timed by its timestamp and
+    /// packet_timestamp.
+    pub fn write_sample(&mut self, track_index: usize, sample: &Sample) -> Result<()> {
+        if self.closed {
+            return Err(Error::ErrFileNotOpened);
+        }
+        if track_index >= self.tracks.len() {
+            return Err(Error::ErrFmp4UnknownTrack);
+        }
+
+        self.push_sample(
+            track_index,
+            &sample.data,
+            sample.timestamp,
+            sample.packet_timestamp,
+        )
+    }
+
+    fn push_sample(
+        &mut self,
+        track_index: usize,
+        data: &Bytes,
+        timestamp: SystemTime,
+        packet_timestamp: u32,
+    ) -> Result<()> {
+        let state = &mut self.tracks[track_index];
+        let (data, is_sync) = match state.track.codec {
+            Fmp4Codec::Opus => (data.clone(), true),
+            codec => {
+                let mut avcc = BytesMut::new();
+                let mut is_sync = false;
+                for nalu in annexb_nalus(data) {
+                    let (is_key, is_aud) = if codec == Fmp4Codec::H264 {
+                        let nalu_type = nalu[0] & 0x1f;
+                        match nalu_type {
+                            H264_NALU_TYPE_SPS => state.sps = Some(nalu.clone()),
+                            H264_NALU_TYPE_PPS => state.pps = Some(nalu.clone()),
+                            _ => {}
+                        }
+                        (
+                            nalu_type == H264_NALU_TYPE_IDR,
+                            nalu_type == H264_NALU_TYPE_AUD,
+                        )
+                    } else {
+                        let nalu_type = (nalu[0] >> 1) & 0x3f;
+                        match nalu_type {
+                            H265_NALU_TYPE_VPS => state.vps = Some(nalu.clone()),
+                            H265_NALU_TYPE_SPS => state.sps = Some(nalu.clone()),
+                            H265_NALU_TYPE_PPS => state.pps = Some(nalu.clone()),
+                            _ => {}
+                        }
+                        (
+                            (H265_NALU_TYPE_IRAP_START..=H265_NALU_TYPE_IRAP_END)
+                                .contains(&nalu_type),
+                            nalu_type == H265_NALU_TYPE_AUD,
+                        )
+                    };
+                    is_sync |= is_key;
+                    if !is_aud {
+                        avcc.put_u32(nalu.len() as u32);
+                        avcc.put(nalu);
+                    }
+                }
+                (avcc.freeze(), is_sync)
+            }
+        };
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        if !self.init_written {
+            // The stream starts at a key frame of every video track
+            let state = &self.tracks[track_index];
+            if state.track.codec.is_video() && !(is_sync && state.has_parameter_sets()) {
+                return Ok(());
+            }
+            if self
+                .tracks
+                .iter()
+                .any(|t| t.track.codec.is_video() && !t.has_parameter_sets())
+            {
+                return Ok(());
+            }
+            self.write_init()?;
+        }
+
+        let origin = *self.origin.get_or_insert(timestamp);
+        let state = &mut self.tracks[track_index];
+        if state.start.is_none() && state.track.codec.is_video() && !is_sync {
+            return Ok(());
+        }
+
+        let start = match state.start {
+            Some(start) => {
+                state.rtp_elapsed +=
+                    packet_timestamp.wrapping_sub(state.last_rtp_timestamp) as i32 as i64;
+                start
+            }
+            None => {
+                let offset = timestamp.duration_since(origin).unwrap_or_default();
+                let start = (offset.as_secs_f64() * state.track.clock_rate as f64) as u64;
+                state.start = Some(start);
+                start
+            }
+        };
+        state.last_rtp_timestamp = packet_timestamp;
+
+        let decode_time = (start as i64 + state.rtp_elapsed).max(0) as u64;
+        let sample = Mp4Sample {
+            decode_time,
+            duration: 0,
+            is_sync,
+            data,
+        };
+        if let Some(mut last) = state.last_sample.replace(sample) {
+            last.duration = decode_time.saturating_sub(last.decode_time).max(1) as u32;
+            self.push_finished_sample(track_index, last)?;
+        }
+
+        Ok(())
+    }
+
+    /// push_finished_sample adds a sample whose duration is known to the current fragment,
+    /// and writes the fragment when the sample starts a new one
+    fn push_finished_sample(&mut self, track_index: usize, sample: Mp4Sample) -> Result<()> {
+        // The first video track, or the audio track, decides the fragments
+        let primary = self
+            .tracks
+            .iter()
+            .position(|t| t.track.codec.is_video())
+            .unwrap_or(0);
+        if track_index == primary {
+            let state = &self.tracks[track_index];
+            let fragment_duration = state
+                .samples
+                .first()
+                .map(|first| sample.decode_time.saturating_sub(first.decode_time))
+                .unwrap_or(0);
+            let max_duration =
+                (self.fragment_duration.as_secs_f64() * state.track.clock_rate as f64) as u64;
+            if !state.samples.is_empty()
+                && ((sample.is_sync && state.track.codec.is_video())
+                    || fragment_duration >= max_duration)
+            {
+                self.write_fragment()?;
+            }
+        }
+
+        self.tracks[track_index].samples.push(sample);
+        Ok(())
+    }
+
+    fn write_init(&mut self) -> Result<()> {
+        let mut ftyp = vec![];
+        ftyp.extend_from_slice(b"iso6"); // major_brand
+        ftyp.extend_from_slice(&0u32.to_be_bytes()); // minor_version
+        for brand in [b"iso6", b"cmfc", b"isom", b"mp41"] {
+            ftyp.extend_from_slice(brand); // compatible_brands
+        }
+
+        let mut moov = vec![];
+        let mut mvhd = vec![];
+        mvhd.extend_from_slice(&0u32.to_be_bytes()); // creation_time
+        mvhd.extend_from_slice(&0u32.to_be_bytes()); // modification_time
+        mvhd.extend_from_slice(&MOVIE_TIMESCALE.to_be_bytes());
+        mvhd.extend_from_slice(&0u32.to_be_bytes()); // duration
+        mvhd.extend_from_slice(&0x0001_0000u32.to_be_bytes()); // rate 1.0
+        mvhd.extend_from_slice(&0x0100u16.to_be_bytes()); // volume 1.0
+        mvhd.extend_from_slice(&[0; 10]); // reserved
+        mvhd.extend_from_slice(&MATRIX);
+        mvhd.extend_from_slice(&[0; 24]); // pre_defined
+        mvhd.extend_from_slice(&(self.tracks.len() as u32 + 1).to_be_bytes()); // next_track_ID
+        moov.extend(full_box(b"mvhd", 0, 0, &mvhd));
+
+        let mut mvex = vec![];
+        for state in &self.tracks {
+            moov.extend(mp4_box(b"trak", &trak(state)?));
+
+            let mut trex = vec![];
+            trex.extend_from_slice(&state.track_id.to_be_bytes());
+            trex.extend_from_slice(&1u32.to_be_bytes()); // default_sample_description_index
+            trex.extend_from_slice(&0u32.to_be_bytes()); // default_sample_duration
+            trex.extend_from_slice(&0u32.to_be_bytes()); // default_sample_size
+            trex.extend_from_slice(&0u32.to_be_bytes()); // default_sample_flags
+            mvex.extend(full_box(b"trex", 0, 0, &trex));
+        }
+        moov.extend(mp4_box(b"mvex", &mvex));
+
+        let mut init = mp4_box(b"ftyp", &ftyp);
+        init.extend(mp4_box(b"moov", &moov));
+        self.writer.write_all(&init)?;
+        self.init_written = true;
+
+        Ok(())
+    }
+
+    /// write_fragment writes the samples of the current fragment in a moof and an mdat box
+    fn write_fragment(&mut self) -> Result<()> {
+        if self.tracks.iter().all(|t| t.samples.is_empty()) {
+            return Ok(());
+        }
+        self.sequence_number += 1;
+
+        let mut moof = vec![];
+        moof.extend(full_box(b"mfhd", 0, 0, &self.sequence_number.to_be_bytes()));
+
+        // the positions of the data_offset fields in the moof, and the offsets of the
+        // data of their tracks in the mdat
+        let mut data_offsets = vec![];
+        let mut mdat = vec![];
+        for state in &mut self.tracks {
+            if state.samples.is_empty() {
+                continue;
+            }
+
+            let samples = std::mem::take(&mut state.samples);
+            // the durations are the differences of the decode times, so the decode time of
+            // a fragment continues from the previous one
+            let decode_time = samples[0].decode_time;
+
+            let mut traf = vec![];
+            // default-base-is-moof
+            traf.extend(full_box(
+                b"tfhd",
+                0,
+                0x02_0000,
+                &state.track_id.to_be_bytes(),
+            ));
+            traf.extend(full_box(b"tfdt", 1, 0, &decode_time.to_be_bytes()));
+
+            let mut trun = vec![];
+            trun.extend_from_slice(&(samples.len() as u32).to_be_bytes());
+            let data_offset_position = trun.len();
+            trun.extend_from_slice(&0u32.to_be_bytes()); // data_offset
+            for sample in &samples {
+                trun.extend_from_slice(&sample.duration.to_be_bytes());
+                trun.extend_from_slice(&(sample.data.len() as u32).to_be_bytes());
+                let flags = if sample.is_sync {
+                    SAMPLE_FLAGS_SYNC
+                } else {
+                    SAMPLE_FLAGS_NON_SYNC
+                };
+                trun.extend_from_slice(&flags.to_be_bytes());
+            }
+            // data-offset, sample-duration, sample-size and sample-flags present
+            let trun = full_box(b"trun", 0, 0x00_0701, &trun);
+
+            // moof + traf header + tfhd + tfdt + trun header
+            let position = moof.len() + 8 + traf.len() + 12 + data_offset_position;
+            traf.extend(trun);
+            moof.extend(mp4_box(b"traf", &traf));
+
+            data_offsets.push((8 + position, mdat.len()));
+            for sample in &samples {
+                mdat.extend_from_slice(&sample.data);
+            }
+        }
+
+        let mut moof = mp4_box(b"moof", &moof);
+        let moof_size = moof.len();
+        for (position, offset) in data_offsets {
+            let data_offset = (moof_size + 8 + offset) as u32;
+            moof[position..position + 4].copy_from_slice(&data_offset.to_be_bytes());
+        }
+
+        moof.extend(mp4_box(b"mdat", &mdat));
+        self.writer.write_all(&moof)?;
+
+        Ok(())
+    }
+}
+
+impl<W: Write> Writer for Fmp4Writer<W> {
+    /// write_rtp adds a new packet to the track of its payload type
+    fn write_rtp(&mut self, packet: &rtp::packet::Packet) -> Result<()> {
+        if self.closed {
+            return Err(Error::ErrFileNotOpened);
+        }
+
+        let track_index = self
+            .tracks
+            .iter()
+            .position(|t| t.track.payload_type == packet.header.payload_type)
+            .ok_or(Error::ErrFmp4UnknownTrack)?;
+
+        self.tracks[track_index].builder.push(packet.clone());
+        while let Some(sample) = self.tracks[track_index].builder.pop() {
+            self.push_sample(
+                track_index,
+                &sample.data,
+                sample.timestamp,
+                sample.packet_timestamp,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// close writes the last fragment and stops the recording
+    fn close(&mut self) -> Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+        self.closed = true;
+
+        for track_index in 0..self.tracks.len() {
+            let state = &mut self.tracks[track_index];
+            if let Some(mut last) = state.last_sample.take() {
+                last.duration = state
+                    .samples
+                    .last()
+                    .map(|s| s.duration)
+                    .unwrap_or_else(|| state.default_duration());
+                self.push_finished_sample(track_index, last)?;
+            }
+        }
+        self.write_fragment()?;
+
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// the unity matrix of mvhd and tkhd
+const MATRIX: [u8; 36] = [
+    0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, //
+];
+
+fn trak(state: &TrackState) -> Result<Vec<u8>> {
+    let track = &state.track;
+    let is_video = track.codec.is_video();
+
+    let mut tkhd = vec![];
+    tkhd.extend_from_slice(&0u32.to_be_bytes()); // creation_time
+    tkhd.extend_from_slice(&0u32.to_be_bytes()); // modification_time
+    tkhd.extend_from_slice(&state.track_id.to_be_bytes());
+    tkhd.extend_from_slice(&0u32.to_be_bytes()); // reserved
+    tkhd.extend_from_slice(&0u32.to_be_bytes()); // duration
+    tkhd.extend_from_slice(&[0; 8]); // reserved
+    tkhd.extend_from_slice(&0u16.to_be_bytes()); // layer
+    tkhd.extend_from_slice(&0u16.to_be_bytes()); // alternate_group
+    tkhd.extend_from_slice(&(if is_video { 0u16 } else { 0x0100 }).to_be_bytes()); // volume
+    tkhd.extend_from_slice(&0u16.to_be_bytes()); // reserved
+    tkhd.extend_from_slice(&MATRIX);
+    tkhd.extend_from_slice(&((track.width as u32) << 16).to_be_bytes());
+    tkhd.extend_from_slice(&((track.height as u32) << 16).to_be_bytes());
+    // track_enabled, track_in_movie
+    let mut trak = full_box(b"tkhd", 0, 0x03, &tkhd);
+
+    let mut mdhd = vec![];
+    mdhd.extend_from_slice(&0u32.to_be_bytes()); // creation_time
+    mdhd.extend_from_slice(&0u32.to_be_bytes()); // modification_time
+    mdhd.extend_from_slice(&track.clock_rate.to_be_bytes()); // timescale
+    mdhd.extend_from_slice(&0u32.to_be_bytes()); // duration
+    mdhd.extend_from_slice(&0x55C4u16.to_be_bytes()); // language 'und'
+    mdhd.extend_from_slice(&0u16.to_be_bytes()); // pre_defined
+    let mut mdia = full_box(b"mdhd", 0, 0, &mdhd);
+
+    let mut hdlr = vec![];
+    hdlr.extend_from_slice(&0u32.to_be_bytes()); // pre_defined
+    hdlr.extend_from_slice(if is_video { b"vide" } else { b"soun" });
+    hdlr.extend_from_slice(&[0; 12]); // reserved
+    hdlr.extend_from_slice(if is_video {
+        b"VideoHandler\0"
+    } else {
+        b"SoundHandler\0"
+    });
+    mdia.extend(full_box(b"hdlr", 0, 0, &hdlr));
+
+    let mut minf = if is_video {
+        full_box(b"vmhd", 0, 0x01, &[0; 8]) // graphicsmode, opcolor
+    } else {
+        full_box(b"smhd", 0, 0, &[0; 4]) // balance, reserved
+    };
+    let mut dref = 1u32.to_be_bytes().to_vec(); // entry_count
+    dref.extend(full_box(b"url ", 0, 0x01, &[])); // media data is in this file
+    minf.extend(mp4_box(b"dinf", &full_box(b"dref", 0, 0, &dref)));
+
+    let mut stsd = 1u32.to_be_bytes().to_vec(); // entry_count
+    stsd.extend(sample_entry(state)?);
+    let mut stbl = full_box(b"stsd", 0, 0, &stsd);
+    stbl.extend(full_box(b"stts", 0, 0, &0u32.to_be_bytes()));
+    stbl.extend(full_box(b"stsc", 0, 0, &0u32.to_be_bytes()));
+    stbl.extend(full_box(b"stsz", 0, 0, &[0; 8])); // sample_size, sample_count
+    stbl.extend(full_box(b"stco", 0, 0, &0u32.to_be_bytes()));
+    minf.extend(mp4_box(b"stbl", &stbl));
+    mdia.extend(mp4_box(b"minf", &minf));
+
+    trak.extend(mp4_box(b"mdia", &mdia));
+    Ok(trak)
+}
+
+fn sample_entry(state: &TrackState) -> Result<Vec<u8>> {
+    let track = &state.track;
+
+    let mut entry = vec![0; 6]; // reserved
+    entry.extend_from_slice(&1u16.to_be_bytes()); // data_reference_index
+
+    if track.codec == Fmp4Codec::Opus {
+        entry.extend_from_slice(&[0; 8]); // reserved
+        entry.extend_from_slice(&(track.channels as u16).to_be_bytes());
+        entry.extend_from_slice(&16u16.to_be_bytes()); // samplesize
+        entry.extend_from_slice(&0u16.to_be_bytes()); // pre_defined
+        entry.extend_from_slice(&0u16.to_be_bytes()); // reserved
+        entry.extend_from_slice(&(48000u32 << 16).to_be_bytes()); // samplerate
+
+        // https://opus-codec.org/docs/opus_in_isobmff.html
+        let mut dops = vec![0]; // Version
+        dops.push(track.channels); // OutputChannelCount
+        dops.extend_from_slice(&DEFAULT_PRE_SKIP.to_be_bytes()); // PreSkip
+        dops.extend_from_slice(&48000u32.to_be_bytes()); // InputSampleRate
+        dops.extend_from_slice(&0i16.to_be_bytes()); // OutputGain
+        dops.push(0); // ChannelMappingFamily
+        entry.extend(mp4_box(b"dOps", &dops));
+        return Ok(mp4_box(b"Opus", &entry));
+    }
+
+    entry.extend_from_slice(&[0; 16]); // pre_defined, reserved
+    entry.extend_from_slice(&track.width.to_be_bytes());
+    entry.extend_from_slice(&track.height.to_be_bytes());
+    entry.extend_from_slice(&0x0048_0000u32.to_be_bytes()); // horizresolution 72 dpi
+    entry.extend_from_slice(&0x0048_0000u32.to_be_bytes()); // vertresolution 72 dpi
+    entry.extend_from_slice(&0u32.to_be_bytes()); // reserved
+    entry.extend_from_slice(&1u16.to_be_bytes()); // frame_count
+    entry.extend_from_slice(&[0; 32]); // compressorname
+    entry.extend_from_slice(&0x0018u16.to_be_bytes()); // depth
+    entry.extend_from_slice(&(-1i16).to_be_bytes()); // pre_defined
+
+    let empty = Bytes::new();
+    let vps = state.vps.as_ref().unwrap_or(&empty);
+    let sps = state.sps.as_ref().unwrap_or(&empty);
+    let pps = state.pps.as_ref().unwrap_or(&empty);
+
+    if track.codec == Fmp4Codec::H264 {
+        entry.extend(mp4_box(b"avcC", &avc_decoder_configuration(sps, pps)?));
+        Ok(mp4_box(b"avc1", &entry))
+    } else {
+        entry.extend(mp4_box(
+            b"hvcC",
+            &hevc_decoder_configuration(vps, sps, pps)?,
+        ));
+        Ok(mp4_box(b"hvc1", &entry))
+    }
+}
+
+/// avc_decoder_configuration returns the AVCDecoderConfigurationRecord of ISO/IEC 14496-15
+fn avc_decoder_configuration(sps: &Bytes, pps: &Bytes) -> Result<Vec<u8>> {
+    let profile = sps.get(1).copied().unwrap_or(0);
+    let mut record = vec![
+        1, // configurationVersion
+        profile,
+        sps.get(2).copied().unwrap_or(0), // profile_compatibility
+        sps.get(3).copied().unwrap_or(0), // AVCLevelIndication
+        0xFF,                             // lengthSizeMinusOne 3
+        0xE1,                             // numOfSequenceParameterSets 1
+    ];
+    record.extend_from_slice(&(sps.len() as u16).to_be_bytes());
+    record.extend_from_slice(sps);
+    record.push(1); // numOfPictureParameterSets
+    record.extend_from_slice(&(pps.len() as u16).to_be_bytes());
+    record.extend_from_slice(pps);
+
+    if matches!(profile, 100 | 110 | 122 | 144) {
+        h264_sps_format(sps)
+            .ok_or(Error::ErrFmp4InvalidSps)?
+            .push_to(&mut record);
+        record.push(0); // numOfSequenceParameterSetExt
+    }
+
+    Ok(record)
+}
+
+/// hevc_decoder_configuration returns the HEVCDecoderConfigurationRecord of ISO/IEC 14496-15,
+/// with the general profile, tier and level of the SPS
+fn hevc_decoder_configuration(vps: &Bytes, sps: &Bytes, pps: &Bytes) -> Result<Vec<u8>> {
+    // NAL unit header (2), sps_video_parameter_set_id, sps_max_sub_layers_minus1,
+    // sps_temporal_id_nesting_flag (1) and profile_tier_level (12)
+    let rbsp = remove_emulation_prevention(sps, 15);
+    let mut profile_tier_level = [0u8; 12];
+    if rbsp.len() >= 15 {
+        profile_tier_level.copy_from_slice(&rbsp[3..15]);
+    }
+
+    let mut record = vec![1]; // configurationVersion
+                              // general_profile_space, general_tier_flag, general_profile_idc,
+                              // general_profile_compatibility_flags, general_constraint_indicator_flags
+    record.extend_from_slice(&profile_tier_level[..11]);
+    record.push(profile_tier_level[11]); // general_level_idc
+    record.extend_from_slice(&0xF000u16.to_be_bytes()); // min_spatial_segmentation_idc
+    record.push(0xFC); // parallelismType
+    h265_sps_format(sps)
+        .ok_or(Error::ErrFmp4InvalidSps)?
+        .push_to(&mut record);
+    record.extend_from_slice(&0u16.to_be_bytes()); // avgFrameRate
+                                                   // constantFrameRate 0, numTemporalLayers 1, temporalIdNested 1, lengthSizeMinusOne 3
+    record.push(0x0F);
+
+    record.push(3); // numOfArrays
+    for (nalu_type, nalu) in [
+        (H265_NALU_TYPE_VPS, vps),
+        (H265_NALU_TYPE_SPS, sps),
+        (H265_NALU_TYPE_PPS, pps),
+    ] {
+        record.push(0x80 | nalu_type); // array_completeness, NAL_unit_type
+        record.extend_from_slice(&1u16.to_be_bytes()); // numNalus
+        record.extend_from_slice(&(nalu.len() as u16).to_be_bytes());
+        record.extend_from_slice(nalu);
+    }
+
+    Ok(record)
+}
+
+/// SpsFormat is the chroma format and the bit depths of a video stream, as signalled in its SPS
+struct SpsFormat {
+    chroma_format_idc: u8,
+    bit_depth_luma_minus8: u8,
+    bit_depth_chroma_minus8: u8,
+}
+
+impl SpsFormat {
+    fn new(
+        chroma_format_idc: u64,
+        bit_depth_luma_minus8: u64,
+        bit_depth_chroma_minus8: u64,
+    ) -> Option<Self> {
+        // the fields of the decoder configuration records are 2 and 3 bits wide
+        if chroma_format_idc > 3 || bit_depth_luma_minus8 > 7 || bit_depth_chroma_minus8 > 7 {
+            return None;
+        }
+
+        Some(SpsFormat {
+            chroma_format_idc: chroma_format_idc as u8,
+            bit_depth_luma_minus8: bit_depth_luma_minus8 as u8,
+            bit_depth_chroma_minus8: bit_depth_chroma_minus8 as u8,
+        })
+    }
+
+    /// push_to appends the chroma_format_idc, bit_depth_luma_minus8 and bit_depth_chroma_minus8
+    /// fields of a decoder configuration record
+    fn push_to(&self, record: &mut Vec<u8>) {
+        record.push(0xFC | self.chroma_format_idc);
+        record.push(0xF8 | self.bit_depth_luma_minus8);
+        record.push(0xF8 | self.bit_depth_chroma_minus8);
+    }
+}
+
+/// h264_sps_format parses the SpsFormat of an H.264 SPS, see 7.3.2.1.1 of ITU-T H.264
+fn h264_sps_format(sps: &[u8]) -> Option<SpsFormat> {
+    let rbsp = remove_emulation_prevention(sps, sps.len());
+    let mut r = BitReader::new(rbsp.get(1..)?);
+    let profile_idc = r.read(8)?;
+    r.read(16)?; // constraint_set_flags, level_idc
+    r.read_uvlc()?; // seq_parameter_set_id
+
+    // the other profiles are 4:2:0 with 8 bits
+    if !matches!(
+        profile_idc,
+        100 | 110 | 122 | 244 | 44 | 83 | 86 | 118 | 128 | 138 | 139 | 134 | 135
+    ) {
+        return SpsFormat::new(1, 0, 0);
+    }
+
+    let chroma_format_idc = r.read_uvlc()?;
+    if chroma_format_idc == 3 {
+        r.read(1)?; // separate_colour_plane_flag
+    }
+    SpsFormat::new(chroma_format_idc, r.read_uvlc()?, r.read_uvlc()?)
+}
+
+/// h265_sps_format parses the SpsFormat of an H.265 SPS, see 7.3.2.2.1 of ITU-T H.265
+fn h265_sps_format(sps: &[u8]) -> Option<SpsFormat> {
+    let rbsp = remove_emulation_prevention(sps, sps.len());
+    let mut r = BitReader::new(rbsp.get(2..)?);
+    r.read(4)?; // sps_video_parameter_set_id
+    let max_sub_layers_minus1 = r.read(3)? as usize;
+    r.read(1)?; // sps_temporal_id_nesting_flag
+
+    // profile_tier_level, general_profile_space .. general_level_idc
+    r.read(48)?;
+    r.read(48)?;
+    let mut sub_layers = vec![];
+    for _ in 0..max_sub_layers_minus1 {
+        // sub_layer_profile_present_flag, sub_layer_level_present_flag
+        sub_layers.push((r.read(1)? == 1, r.read(1)? == 1));
+    }
+    if max_sub_layers_minus1 > 0 {
+        for _ in max_sub_layers_minus1..8 {
+            r.read(2)?; // reserved_zero_2bits
+        }
+    }
+    for (profile_present, level_present) in sub_layers {
+        if profile_present {
+            r.read(44)?;
+            r.read(44)?; // sub_layer_profile_space .. sub_layer_inbld_flag
+        }
+        if level_present {
+            r.read(8)?; // sub_layer_level_idc
+        }
+    }
+
+    r.read_uvlc()?; // sps_seq_parameter_set_id
+    let chroma_format_idc = r.read_uvlc()?;
+    if chroma_format_idc == 3 {
+        r.read(1)?; // separate_colour_plane_flag
+    }
+    r.read_uvlc()?; // pic_width_in_luma_samples
+    r.read_uvlc()?; // pic_height_in_luma_samples
+    if r.read(1)? == 1 {
+        // conf_win_left_offset, conf_win_right_offset, conf_win_top_offset, conf_win_bottom_offset
+        for _ in 0..4 {
+            r.read_uvlc()?;
+        }
+    }
+    SpsFormat::new(chroma_format_idc, r.read_uvlc()?, r.read_uvlc()?)
+}
+
+/// remove_emulation_prevention returns the first len bytes of the RBSP of a NAL unit
+fn remove_emulation_prevention(nalu: &[u8], len: usize) -> Vec<u8> {
+    let mut rbsp = Vec::with_capacity(len);
+    let mut zeros = 0;
+    for b in nalu {
+        if rbsp.len() == len {
+            break;
+        }
+        if zeros >= 2 && *b == 0x03 {
+            zeros = 0;
+            continue;
+        }
+        zeros = if *b == 0 { zeros + 1 } else { 0 };
+        rbsp.push(*b);
+    }
+    rbsp
+}
+
+/// annexb_nalus returns the NAL units of an Annex-B bitstream
+fn annexb_nalus(data: &Bytes) -> Vec<Bytes> {
+    let mut nalus = vec![];
+    let mut start: Option<usize> = None;
+    let mut i = 0;
+    while i + 3 <= data.len() {
+        if data[i] == 0 && data[i + 1] == 0 && data[i + 2] == 1 {
+            if let Some(start) = start {
+                let mut end = i;
+                while end > start && data[end - 1] == 0 {
+                    end -= 1;
+                }
+                nalus.push(data.slice(start..end));
+            }
+            i += 3;
+            start = Some(i);
+        } else {
+            i += 1;
+        }
+    }
+
+    match start {
+        Some(start) if start < data.len() => nalus.push(data.slice(start..)),
+        // a sample without start code is a single NAL unit
+        None if !data.is_empty() => nalus.push(data.clone()),
+        _ => {}
+    }
+    nalus.retain(|nalu| !nalu.is_empty());
+
+    nalus
+}
+
+fn mp4_box(box_type: &[u8; 4], content: &[u8]) -> Vec<u8> {
+    let mut b = Vec::with_capacity(8 + content.len());
+    b.extend_from_slice(&(8 + content.len() as u32).to_be_bytes());
+    b.extend_from_slice(box_type);
+    b.extend_from_slice(content);
+    b
+}
+
+fn full_box(box_type: &[u8; 4], version: u8, flags: u32, content: &[u8]) -> Vec<u8> {
+    let mut full = Vec::with_capacity(4 + content.len());
+    full.extend_from_slice(&((version as u32) << 24 | flags).to_be_bytes());
+    full.extend_from_slice(content);
+    mp4_box(box_type, &full)
+}
@@ -0,0 +1,266 @@
+use super::*;
+
+use bytes::Bytes;
+use rtp::codecs::opus::OpusPacket;
+use rtp::codecs::vp8::Vp8Packet;
+
+/// Opus frames of 20ms at 48kHz
+const OPUS_FRAME: u32 = 960;
+
+fn packet(sequence_number: u16, timestamp: u32, marker: bool, payload: &'static [u8]) -> Packet {
+    Packet {
+        header: rtp::header::Header {
+            version: 2,
+            payload_type: 96,
+            sequence_number,
+            timestamp,
+            marker,
+            ..Default::default()
+        },
+        payload: Bytes::from_static(payload),
+    }
+}
+
+fn opus_packet(sequence_number: u16) -> Packet {
+    packet(
+        sequence_number,
+        sequence_number as u32 * OPUS_FRAME,
+        false,
+        &[0x01],
+    )
+}
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+#[test]
+fn test_jitter_buffer_reorders_at_playout_time() {
+    let start = Instant::now();
+    let mut jb = JitterBuffer::new(OpusPacket, 48000)
+        .unwrap()
+        .with_min_delay(ms(50));
+
+    jb.push(opus_packet(0), start);
+    jb.push(opus_packet(1), start + ms(20));
+    jb.push(opus_packet(3), start + ms(60));
+    jb.push(opus_packet(2), start + ms(65));
+    assert_eq!(jb.next_playout(), Some(start + ms(50)));
+
+    assert!(jb.pop(start + ms(49)).is_none());
+    let sample = jb.pop(start + ms(50)).unwrap();
+    assert_eq!(sample.packet_timestamp, 0);
+    assert_eq!(sample.duration, ms(20));
+    assert!(jb.pop(start + ms(50)).is_none());
+
+    let timestamps: Vec<u32> = std::iter::from_fn(|| jb.pop(start + ms(90)))
+        .map(|sample| sample.packet_timestamp)
+        .collect();
+    assert_eq!(timestamps, vec![OPUS_FRAME, 2 * OPUS_FRAME]);
+    assert_eq!(jb.next_playout(), Some(start + ms(110)));
+
+    let stats = jb.stats();
+    assert_eq!(stats.jitter, Duration::ZERO);
+    assert_eq!(stats.jitter_buffer_target_delay, ms(50));
+    assert_eq!(stats.jitter_buffer_emitted_count, 3);
+    assert_eq!(stats.jitter_buffer_delay, ms(50 + 70 + 25));
+    assert_eq!(stats.recovered_packets, 1);
+    assert_eq!(stats.concealed_frames, 0);
+}
+
+#[test]
+fn test_jitter_buffer_adapts_delay_to_jitter() {
+    let start = Instant::now();
+    let mut jb = JitterBuffer::new(OpusPacket, 48000)
+        .unwrap()
+        .with_max_delay(ms(500));
+
+    // Every other packet is 10ms late
+    for i in 0..200u16 {
+        let late = if i % 2 == 1 { ms(10) } else { ms(0) };
+        jb.push(opus_packet(i), start + ms(20 * i as u64) + late);
+        while jb.pop(start + ms(20 * i as u64) + late).is_some() {}
+    }
+
+    let stats = jb.stats();
+    assert!(
+        stats.jitter > ms(9) && stats.jitter <= ms(10),
+        "jitter {:?}",
+        stats.jitter
+    );
+    let target_delay = stats.jitter.as_secs_f64() * JITTER_DELAY_FACTOR;
+    assert!((stats.jitter_buffer_target_delay.as_secs_f64() - target_delay).abs() < 1e-6);
+    assert_eq!(stats.concealed_frames, 0);
+    assert_eq!(stats.discarded_packets, 0);
+
+    let mut jb = JitterBuffer::new(OpusPacket, 48000)
+        .unwrap()
+        .with_max_delay(ms(20));
+    for i in 0..200u16 {
+        let late = if i % 2 == 1 { ms(10) } else { ms(0) };
+        jb.push(opus_packet(i), start + ms(20 * i as u64) + late);
+    }
+    assert_eq!(jb.stats().jitter_buffer_target_delay, ms(20));
+}
+
+#[test]
+fn test_jitter_buffer_follows_step_increase_in_delay() {
+    let start = Instant::now();
+    let mut jb = JitterBuffer::new(OpusPacket, 48000)
+        .unwrap()
+        .with_min_delay(ms(50));
+
+    // The network delay rises by 200ms after 300 packets
+    let arrival = |i: u16| start + ms(20 * i as u64) + if i >= 300 { ms(200) } else { ms(0) };
+    for i in 0..600u16 {
+        jb.push(opus_packet(i), arrival(i));
+        while jb.pop(arrival(i) + ms(50)).is_some() {}
+    }
+    assert_eq!(jb.stats().concealed_frames, 0);
+
+    // Once the lower transit times left the window, the frames are played the playout
+    // delay after their arrival again
+    jb.push(opus_packet(600), arrival(600));
+    let playout = jb.next_playout().unwrap();
+    assert!(
+        playout > arrival(600) + ms(49) && playout < arrival(600) + ms(51),
+        "playout {:?} after arrival",
+        playout.saturating_duration_since(arrival(600))
+    );
+    assert!(jb.pop(arrival(600) + ms(10)).is_none());
+    assert!(jb.pop(playout).is_some());
+}
+
+#[test]
+fn test_jitter_buffer_zero_clock_rate() {
+    assert_eq!(
+        JitterBuffer::new(OpusPacket, 0).err(),
+        Some(Error::ErrJitterBufferZeroClockRate)
+    );
+}
+
+#[test]
+fn test_jitter_buffer_waits_for_retransmission() {
+    let start = Instant::now();
+
+    // Packet 1 is lost, and retransmitted after 80ms
+    let mut jb = JitterBuffer::new(OpusPacket, 48000)
+        .unwrap()
+        .with_nack(ms(100));
+    jb.push(opus_packet(0), start);
+    jb.push(opus_packet(2), start + ms(40));
+    assert_eq!(jb.missing(), vec![1]);
+    jb.push(opus_packet(1), start + ms(100));
+    assert!(jb.missing().is_empty());
+
+    let timestamps: Vec<u32> = std::iter::from_fn(|| jb.pop(start + ms(140)))
+        .map(|sample| sample.packet_timestamp)
+        .collect();
+    assert_eq!(timestamps, vec![0, OPUS_FRAME, 2 * OPUS_FRAME]);
+    assert_eq!(jb.stats().recovered_packets, 1);
+
+    // Without NACK the frame is played without waiting, and the retransmission is too late
+    let mut jb = JitterBuffer::new(OpusPacket, 48000).unwrap();
+    jb.push(opus_packet(0), start);
+    assert_eq!(jb.pop(start).unwrap().packet_timestamp, 0);
+    jb.push(opus_packet(2), start + ms(40));
+    let sample = jb.pop(start + ms(40)).unwrap();
+    assert_eq!(sample.packet_timestamp, 2 * OPUS_FRAME);
+    assert_eq!(sample.prev_dropped_packets, 1);
+    jb.push(opus_packet(1), start + ms(100));
+    assert!(jb.pop(start + ms(100)).is_none());
+    assert_eq!(jb.stats().discarded_packets, 1);
+}
+
+#[test]
+fn test_jitter_buffer_conceals_incomplete_frame() {
+    let start = Instant::now();
+    let mut jb = JitterBuffer::new(Vp8Packet::default(), 90000)
+        .unwrap()
+        .with_min_delay(ms(100));
+
+    jb.push(packet(0, 0, true, &[0x10, 0x01, 0x01, 0x01]), start);
+    // Packet 2 of the second frame is lost
+    jb.push(
+        packet(1, 3000, false, &[0x10, 0x02, 0x02, 0x02]),
+        start + ms(33),
+    );
+    jb.push(
+        packet(3, 3000, true, &[0x00, 0x04, 0x04, 0x04]),
+        start + ms(34),
+    );
+    jb.push(
+        packet(4, 6000, false, &[0x10, 0x05, 0x05, 0x05]),
+        start + ms(66),
+    );
+    jb.push(
+        packet(5, 6000, true, &[0x00, 0x06, 0x06, 0x06]),
+        start + ms(67),
+    );
+    assert_eq!(jb.missing(), vec![2]);
+
+    let sample = jb.pop(start + ms(100)).unwrap();
+    assert_eq!(sample.data, Bytes::from_static(&[0x01, 0x01, 0x01]));
+    assert_eq!(sample.duration, ms(33) + Duration::from_nanos(333_333));
+
+    let sample = jb.pop(start + ms(170)).unwrap();
+    assert_eq!(sample.packet_timestamp, 6000);
+    assert_eq!(
+        sample.data,
+        Bytes::from_static(&[0x05, 0x05, 0x05, 0x06, 0x06, 0x06])
+    );
+    assert_eq!(sample.prev_dropped_packets, 3);
+
+    let stats = jb.stats();
+    assert_eq!(stats.concealed_frames, 1);
+    assert_eq!(stats.jitter_buffer_emitted_count, 2);
+
+    // The packet of the skipped frame is too late
+    jb.push(
+        packet(2, 3000, false, &[0x00, 0x03, 0x03, 0x03]),
+        start + ms(180),
+    );
+    assert_eq!(jb.stats().discarded_packets, 1);
+    assert!(jb.missing().is_empty());
+}
+
+#[test]
+fn test_jitter_buffer_padding_and_duplicates() {
+    let start = Instant::now();
+    let mut jb = JitterBuffer::new(OpusPacket, 48000).unwrap();
+
+    jb.push(opus_packet(0), start);
+    jb.push(opus_packet(0), start);
+    jb.push(packet(1, 0, false, &[]), start);
+    jb.push(packet(2, 0, false, &[]), start);
+    jb.push(opus_packet(3), start + ms(60));
+    assert!(jb.missing().is_empty());
+
+    assert_eq!(jb.pop(start).unwrap().packet_timestamp, 0);
+    let sample = jb.pop(start + ms(60)).unwrap();
+    assert_eq!(sample.prev_dropped_packets, 2);
+    assert_eq!(sample.prev_padding_packets, 2);
+    assert_eq!(jb.stats().discarded_packets, 1);
+}
+
+#[test]
+fn test_jitter_buffer_sequence_number_wraparound() {
+    let start = Instant::now();
+    let mut jb = JitterBuffer::new(OpusPacket, 48000).unwrap();
+
+    let base = u32::MAX - OPUS_FRAME + 1;
+    jb.push(packet(65535, base, false, &[0x01]), start);
+    jb.push(
+        packet(1, base.wrapping_add(2 * OPUS_FRAME), false, &[0x01]),
+        start + ms(40),
+    );
+    jb.push(
+        packet(0, base.wrapping_add(OPUS_FRAME), false, &[0x01]),
+        start + ms(41),
+    );
+
+    let sequence: Vec<u32> = std::iter::from_fn(|| jb.pop(start + ms(41)))
+        .map(|sample| sample.packet_timestamp)
+        .collect();
+    assert_eq!(sequence, vec![base, 0, OPUS_FRAME]);
+}
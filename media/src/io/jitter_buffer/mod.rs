@@ -0,0 +1,423 @@
+#[cfg(test)]
+mod jitter_buffer_test;
+
+use crate::error::{Error, Result};
+use crate::Sample;
+
+use bytes::BytesMut;
+use rtp::packet::Packet;
+use rtp::packetizer::Depacketizer;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::time::{Duration, Instant, SystemTime};
+
+/// the playout delay is this multiple of the interarrival jitter
+const JITTER_DELAY_FACTOR: f64 = 3.0;
+/// the number of packets over which the minimum transit time is tracked
+const TRANSIT_WINDOW: usize = 256;
+/// a timestamp jump of more seconds than this restarts the stream
+const RESET_THRESHOLD_SECONDS: i64 = 10;
+/// the maximum number of buffered frames
+const MAX_FRAMES: usize = 512;
+
+const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(1);
+
+/// JitterBufferStats are the statistics of a JitterBuffer, named like the jitter buffer
+/// members of RTCInboundRtpStreamStats
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct JitterBufferStats {
+    /// the interarrival jitter, estimated as in RFC 3550
+    pub jitter: Duration,
+    /// the current playout delay
+    pub jitter_buffer_target_delay: Duration,
+    /// the sum of the time every emitted frame spent in the buffer, from the arrival of its
+    /// first packet until it was emitted
+    pub jitter_buffer_delay: Duration,
+    /// the number of emitted frames
+    pub jitter_buffer_emitted_count: u64,
+    /// the number of frames that were incomplete at their playout time and skipped, which
+    /// the decoder has to conceal
+    pub concealed_frames: u64,
+    /// the number of packets that arrived after the playout of their frame, or twice
+    pub discarded_packets: u64,
+    /// the number of packets that arrived after a packet with a higher sequence number in
+    /// time for playout, for example retransmissions
+    pub recovered_packets: u64,
+}
+
+struct Frame {
+    packets: BTreeMap<i64, Packet>,
+    /// arrival of the first packet of the frame
+    arrival: Instant,
+}
+
+/// JitterBuffer reorders the RTP packets of a stream, assembles them into frames and hands
+/// out every frame at its playout time. The playout delay follows the interarrival jitter,
+/// so the latency is low on a steady network and rises when packets arrive irregularly.
+///
+/// Unlike [`SampleBuilder`](crate::io::sample_builder::SampleBuilder), which waits for a
+/// number of packets, frames are held for a time: a frame that is incomplete at its playout
+/// time is skipped and counted as concealed. With [`JitterBuffer::with_nack`] the delay
+/// includes a round trip time, so lost packets can be retransmitted before the playout.
+pub struct JitterBuffer<T: Depacketizer> {
+    depacketizer: T,
+    clock_rate: u32,
+    min_delay: Duration,
+    max_delay: Duration,
+    rtt: Option<Duration>,
+
+    frames: BTreeMap<i64, Frame>,
+    /// sequence numbers of the padding packets after the last emitted frame
+    padding: BTreeSet<i64>,
+
+    /// arrival and timestamp of the first packet, the playout times are relative to it
+    reference: Option<(Instant, i64)>,
+    highest_sequence_number: Option<i64>,
+    highest_timestamp: Option<i64>,
+    /// timestamp of the last emitted or skipped frame
+    played_timestamp: Option<i64>,
+    /// last sequence number of the last emitted frame
+    played_sequence_number: Option<i64>,
+    last_playout: Option<Instant>,
+    last_duration: Duration,
+
+    /// transit times in seconds, relative to the reference
+    transits: VecDeque<f64>,
+    last_transit: Option<f64>,
+    /// jitter in seconds
+    jitter: f64,
+
+    stats: JitterBufferStats,
+}
+
+impl<T: Depacketizer> JitterBuffer<T> {
+    /// new creates a jitter buffer of a stream with the given clock rate. The depacketizer
+    /// extracts the media of the frames from the RTP packets.
+    pub fn new(depacketizer: T, clock_rate: u32) -> Result<Self> {
+        if clock_rate == 0 {
+            return Err(Error::ErrJitterBufferZeroClockRate);
+        }
+
+        Ok(JitterBuffer {
+            depacketizer,
+            clock_rate,
+            min_delay: Duration::ZERO,
+            max_delay: DEFAULT_MAX_DELAY,
+            rtt: None,
+
+            frames: BTreeMap::new(),
+            padding: BTreeSet::new(),
+
+            reference: None,
+            highest_sequence_number: None,
+            highest_timestamp: None,
+            played_timestamp: None,
+            played_sequence_number: None,
+            last_playout: None,
+            last_duration: Duration::ZERO,
+
+            transits: VecDeque::with_capacity(TRANSIT_WINDOW),
+            last_transit: None,
+            jitter: 0.0,
+
+            stats: JitterBufferStats::default(),
+        })
+    }
+
+    /// with_min_delay sets the lowest playout delay, 0 by default
+    pub fn with_min_delay(mut self, min_delay: Duration) -> Self {
+        self.min_delay = min_delay;
+        self.update_target_delay();
+        self
+    }
+
+    /// with_max_delay sets the highest playout delay, 1s by default
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self.update_target_delay();
+        self
+    }
+
+    /// with_nack makes the jitter buffer wait for retransmissions of lost packets, which are
+    /// requested by the NACK generator interceptor, by adding the round trip time to the
+    /// playout delay
+    pub fn with_nack(mut self, rtt: Duration) -> Self {
+        self.set_rtt(rtt);
+        self
+    }
+
+    /// set_rtt updates the round trip time that is waited for retransmissions, and enables
+    /// waiting like with_nack
+    pub fn set_rtt(&mut self, rtt: Duration) {
+        self.rtt = Some(rtt);
+        self.update_target_delay();
+    }
+
+    /// stats returns the statistics of the jitter buffer
+    pub fn stats(&self) -> JitterBufferStats {
+        JitterBufferStats {
+            jitter: Duration::from_secs_f64(self.jitter),
+            ..self.stats
+        }
+    }
+
+    /// push adds a packet, which arrived at the given time
+    pub fn push(&mut self, packet: Packet, arrival: Instant) {
+        let mut sequence_number = self.unwrap_sequence_number(packet.header.sequence_number);
+
+        // libWebRTC sends padding packets without media to smooth out the send rate
+        if packet.payload.is_empty() {
+            if self
+                .played_sequence_number
+                .map_or(true, |played| sequence_number > played)
+            {
+                self.padding.insert(sequence_number);
+            }
+            if self
+                .highest_sequence_number
+                .map_or(true, |highest| sequence_number > highest)
+            {
+                self.highest_sequence_number = Some(sequence_number);
+            }
+            return;
+        }
+
+        let mut timestamp = self.unwrap_timestamp(packet.header.timestamp);
+        if let Some(highest) = self.highest_timestamp {
+            if (timestamp - highest).abs() > RESET_THRESHOLD_SECONDS * self.clock_rate as i64 {
+                self.reset();
+                sequence_number = packet.header.sequence_number as i64;
+                timestamp = packet.header.timestamp as i64;
+            }
+        }
+
+        let late = self
+            .played_timestamp
+            .map_or(false, |played| timestamp <= played);
+        let duplicate = self
+            .frames
+            .get(&timestamp)
+            .map_or(false, |frame| frame.packets.contains_key(&sequence_number));
+        if late || duplicate {
+            self.stats.discarded_packets += 1;
+            return;
+        }
+
+        let (reference_arrival, reference_timestamp) =
+            *self.reference.get_or_insert((arrival, timestamp));
+        let transit = arrival
+            .saturating_duration_since(reference_arrival)
+            .as_secs_f64()
+            - (timestamp - reference_timestamp) as f64 / self.clock_rate as f64;
+
+        if self
+            .highest_sequence_number
+            .map_or(true, |highest| sequence_number > highest)
+        {
+            // RFC 3550, 6.4.1: J(i) = J(i-1) + (|D(i-1,i)| - J(i-1))/16
+            if let Some(last_transit) = self.last_transit {
+                let d = (transit - last_transit).abs();
+                self.jitter += (d - self.jitter) / 16.0;
+            }
+            self.last_transit = Some(transit);
+
+            if self.transits.len() == TRANSIT_WINDOW {
+                self.transits.pop_front();
+            }
+            self.transits.push_back(transit);
+
+            self.highest_sequence_number = Some(sequence_number);
+            self.update_target_delay();
+        } else {
+            self.stats.recovered_packets += 1;
+        }
+
+        if self
+            .highest_timestamp
+            .map_or(true, |highest| timestamp > highest)
+        {
+            self.highest_timestamp = Some(timestamp);
+        }
+
+        self.frames
+            .entry(timestamp)
+            .or_insert_with(|| Frame {
+                packets: BTreeMap::new(),
+                arrival,
+            })
+            .packets
+            .insert(sequence_number, packet);
+
+        while self.frames.len() > MAX_FRAMES {
+            if let Some((&timestamp, _)) = self.frames.iter().next() {
+                self.frames.remove(&timestamp);
+                self.played_timestamp = Some(timestamp);
+                self.stats.concealed_frames += 1;
+            }
+        }
+    }
+
+    /// pop returns the next frame whose playout time is not after now, or None if there is
+    /// none. Incomplete frames whose playout time has passed are skipped.
+    pub fn pop(&mut self, now: Instant) -> Option<Sample> {
+        loop {
+            let timestamp = *self.frames.keys().next()?;
+            let playout = self.playout_time(timestamp)?;
+            if now < playout {
+                return None;
+            }
+
+            let frame = self.frames.remove(&timestamp)?;
+            self.played_timestamp = Some(timestamp);
+            self.last_playout = Some(playout);
+
+            if let Some(sample) = self.build_sample(timestamp, frame, now) {
+                return Some(sample);
+            }
+            self.stats.concealed_frames += 1;
+        }
+    }
+
+    /// next_playout returns the playout time of the next frame, or None if no frame is
+    /// buffered
+    pub fn next_playout(&self) -> Option<Instant> {
+        let timestamp = *self.frames.keys().next()?;
+        self.playout_time(timestamp)
+    }
+
+    /// missing returns the sequence numbers of the packets that are still awaited, which can
+    /// be requested with a NACK
+    pub fn missing(&self) -> Vec<u16> {
+        let highest = match self.highest_sequence_number {
+            Some(highest) => highest,
+            None => return vec![],
+        };
+        let received: BTreeSet<i64> = self
+            .frames
+            .values()
+            .flat_map(|frame| frame.packets.keys().copied())
+            .chain(self.padding.iter().copied())
+            .collect();
+        let first = match (self.played_sequence_number, received.iter().next()) {
+            (Some(played), _) => played + 1,
+            (None, Some(&first)) => first,
+            (None, None) => return vec![],
+        };
+
+        (first..highest)
+            .filter(|sequence_number| !received.contains(sequence_number))
+            .map(|sequence_number| sequence_number as u16)
+            .collect()
+    }
+
+    fn build_sample(&mut self, timestamp: i64, frame: Frame, now: Instant) -> Option<Sample> {
+        let (&first_sequence_number, first) = frame.packets.iter().next()?;
+        let (&last_sequence_number, last) = frame.packets.iter().next_back()?;
+        if (last_sequence_number - first_sequence_number + 1) as usize != frame.packets.len()
+            || !self.depacketizer.is_partition_head(&first.payload)
+            || !self
+                .depacketizer
+                .is_partition_tail(last.header.marker, &last.payload)
+        {
+            return None;
+        }
+
+        let mut data = BytesMut::new();
+        for packet in frame.packets.values() {
+            data.extend_from_slice(&self.depacketizer.depacketize(&packet.payload).ok()?);
+        }
+
+        let duration = match self.frames.keys().next() {
+            Some(&next) => {
+                Duration::from_secs_f64((next - timestamp) as f64 / self.clock_rate as f64)
+            }
+            None => self.last_duration,
+        };
+        self.last_duration = duration;
+
+        let prev_dropped_packets = self.played_sequence_number.map_or(0, |played| {
+            (first_sequence_number - played - 1).clamp(0, u16::MAX as i64) as u16
+        });
+        self.played_sequence_number = Some(last_sequence_number);
+        let padding = self.padding.split_off(&first_sequence_number);
+        let prev_padding_packets = std::mem::replace(&mut self.padding, padding).len();
+        self.padding = self.padding.split_off(&last_sequence_number);
+
+        self.stats.jitter_buffer_delay += now.saturating_duration_since(frame.arrival);
+        self.stats.jitter_buffer_emitted_count += 1;
+
+        Some(Sample {
+            data: data.freeze(),
+            timestamp: SystemTime::now(),
+            duration,
+            packet_timestamp: first.header.timestamp,
+            prev_dropped_packets,
+            prev_padding_packets: prev_padding_packets.min(u16::MAX as usize) as u16,
+        })
+    }
+
+    /// playout_time returns the time a frame is played: its send time on the clock of the
+    /// first packet, plus the lowest transit time in the window and the playout delay
+    fn playout_time(&self, timestamp: i64) -> Option<Instant> {
+        let (reference_arrival, reference_timestamp) = self.reference?;
+        let base_transit = self.transits.iter().copied().fold(f64::INFINITY, f64::min);
+        let base_transit = if base_transit.is_finite() {
+            base_transit
+        } else {
+            0.0
+        };
+        let offset = (timestamp - reference_timestamp) as f64 / self.clock_rate as f64
+            + base_transit
+            + self.stats.jitter_buffer_target_delay.as_secs_f64();
+
+        let playout = if offset >= 0.0 {
+            reference_arrival + Duration::from_secs_f64(offset)
+        } else {
+            reference_arrival
+                .checked_sub(Duration::from_secs_f64(-offset))
+                .unwrap_or(reference_arrival)
+        };
+
+        // Frames are played in order, even when the delay shrinks
+        Some(match self.last_playout {
+            Some(last_playout) if last_playout > playout => last_playout,
+            _ => playout,
+        })
+    }
+
+    fn update_target_delay(&mut self) {
+        let mut delay = Duration::from_secs_f64(self.jitter * JITTER_DELAY_FACTOR);
+        if let Some(rtt) = self.rtt {
+            delay += rtt;
+        }
+        self.stats.jitter_buffer_target_delay = delay.max(self.min_delay).min(self.max_delay);
+    }
+
+    fn unwrap_sequence_number(&self, sequence_number: u16) -> i64 {
+        match self.highest_sequence_number {
+            Some(highest) => highest + sequence_number.wrapping_sub(highest as u16) as i16 as i64,
+            None => sequence_number as i64,
+        }
+    }
+
+    fn unwrap_timestamp(&self, timestamp: u32) -> i64 {
+        match self.highest_timestamp {
+            Some(highest) => highest + timestamp.wrapping_sub(highest as u32) as i32 as i64,
+            None => timestamp as i64,
+        }
+    }
+
+    /// reset forgets the timing of the stream, after the sender restarted it
+    fn reset(&mut self) {
+        self.stats.concealed_frames += self.frames.len() as u64;
+        self.frames.clear();
+        self.padding.clear();
+        self.reference = None;
+        self.highest_sequence_number = None;
+        self.highest_timestamp = None;
+        self.played_timestamp = None;
+        self.played_sequence_number = None;
+        self.last_playout = None;
+        self.transits.clear();
+        self.last_transit = None;
+    }
+}
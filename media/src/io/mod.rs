@@ -1,12 +1,20 @@
+pub mod fmp4_writer;
 pub mod h264_reader;
 pub mod h264_writer;
 use crate::error::Result;
 
 pub mod ivf_reader;
 pub mod ivf_writer;
+pub mod jitter_buffer;
 pub mod ogg_reader;
 pub mod ogg_writer;
+pub mod pcap_reader;
+pub mod pcap_writer;
+pub mod rtpdump_reader;
+pub mod rtpdump_writer;
 pub mod sample_builder;
+pub mod webm_reader;
+pub mod webm_writer;
 
 pub type ResetFn<R> = Box<dyn FnMut(usize) -> R>;
 
@@ -19,3 +27,38 @@ pub trait Writer {
     // Note: close implementation must be idempotent
     fn close(&mut self) -> Result<()>;
 }
+
+/// BitReader reads the MSB-first bit fields of a bitstream
+pub(crate) struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        BitReader { data, position: 0 }
+    }
+
+    pub(crate) fn read(&mut self, bits: usize) -> Option<u64> {
+        let mut value = 0u64;
+        for _ in 0..bits {
+            let byte = *self.data.get(self.position / 8)?;
+            let bit = (byte >> (7 - self.position % 8)) & 0x01;
+            value = (value << 1) | bit as u64;
+            self.position += 1;
+        }
+        Some(value)
+    }
+
+    /// read_uvlc reads an unsigned Exp-Golomb code, which is the ue(v) of H.264 and H.265
+    pub(crate) fn read_uvlc(&mut self) -> Option<u64> {
+        let mut leading_zeros = 0;
+        while self.read(1)? == 0 {
+            leading_zeros += 1;
+            if leading_zeros >= 32 {
+                return Some(u32::MAX as u64);
+            }
+        }
+        Some(self.read(leading_zeros)? + (1u64 << leading_zeros) - 1)
+    }
+}
@@ -0,0 +1,407 @@
+#[cfg(test)]
+mod pcap_reader_test;
+
+use crate::error::{Error, Result};
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use bytes::Bytes;
+use std::io::Read;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const PCAP_MAGIC_MICROSECONDS: u32 = 0xA1B2_C3D4;
+pub const PCAP_MAGIC_NANOSECONDS: u32 = 0xA1B2_3C4D;
+pub const PCAP_FILE_HEADER_SIZE: usize = 24;
+pub const PCAP_PACKET_HEADER_SIZE: usize = 16;
+
+pub const PCAPNG_BLOCK_SECTION_HEADER: u32 = 0x0A0D_0D0A;
+pub const PCAPNG_BLOCK_INTERFACE_DESCRIPTION: u32 = 0x0000_0001;
+pub const PCAPNG_BLOCK_ENHANCED_PACKET: u32 = 0x0000_0006;
+pub const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+pub const PCAPNG_OPTION_END: u16 = 0;
+pub const PCAPNG_OPTION_IF_TSRESOL: u16 = 9;
+
+/// https://www.tcpdump.org/linktypes.html
+pub const LINKTYPE_NULL: u16 = 0;
+pub const LINKTYPE_ETHERNET: u16 = 1;
+pub const LINKTYPE_RAW: u16 = 101;
+pub const LINKTYPE_LINUX_SLL: u16 = 113;
+pub const LINKTYPE_IPV4: u16 = 228;
+pub const LINKTYPE_IPV6: u16 = 229;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86DD;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+const IP_PROTOCOL_UDP: u8 = 17;
+const UDP_HEADER_SIZE: usize = 8;
+
+/// the largest block that is read, to not allocate for a corrupted length
+const PCAPNG_MAX_BLOCK_SIZE: usize = 16 * 1024 * 1024;
+
+/// PcapFormat is the file format of a capture
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PcapFormat {
+    /// the classic libpcap format
+    /// https://www.ietf.org/archive/id/draft-gharris-opsawg-pcap-01.html
+    Pcap,
+    /// the pcapng format, which is the default of Wireshark
+    /// https://www.ietf.org/archive/id/draft-tuexen-opsawg-pcapng-05.html
+    PcapNg,
+}
+
+/// PcapPacket is a UDP datagram of a capture, which carries an RTP or RTCP packet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PcapPacket {
+    /// the wallclock time when the packet was captured
+    pub timestamp: SystemTime,
+    pub source: SocketAddr,
+    pub destination: SocketAddr,
+    /// the UDP payload
+    pub data: Bytes,
+}
+
+/// is_rtcp tells RTCP packets apart from RTP packets multiplexed on one port,
+/// by the RTCP packet types 192-223 (RFC 5761 section 4)
+pub fn is_rtcp(data: &[u8]) -> bool {
+    data.len() >= 2 && (192..=223).contains(&data[1])
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Interface {
+    link_type: u16,
+    /// the units of the timestamps in a second
+    units_per_second: u64,
+}
+
+impl Interface {
+    /// timestamp fails with ErrPcapInvalidBlock for a time the system can't represent
+    fn timestamp(&self, seconds: u64, units: u64) -> Result<SystemTime> {
+        let nanos = units as u128 * 1_000_000_000 / self.units_per_second as u128;
+        Duration::from_secs(seconds)
+            .checked_add(Duration::from_nanos(nanos as u64))
+            .and_then(|since_epoch| UNIX_EPOCH.checked_add(since_epoch))
+            .ok_or(Error::ErrPcapInvalidBlock)
+    }
+}
+
+/// PcapReader is used to read the UDP datagrams of a pcap or pcapng capture,
+/// skipping the packets of other protocols
+pub struct PcapReader<R: Read> {
+    reader: R,
+    format: PcapFormat,
+    big_endian: bool,
+    /// the interfaces of the current pcapng section, or the link of a pcap file
+    interfaces: Vec<Interface>,
+}
+
+impl<R: Read> PcapReader<R> {
+    /// new returns a new pcap reader with an io.Reader input, for a file in either format
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut magic = [0u8; 4];
+        reader
+            .read_exact(&mut magic)
+            .map_err(|_| Error::ErrPcapBadHeader)?;
+
+        if BigEndian::read_u32(&magic) == PCAPNG_BLOCK_SECTION_HEADER {
+            let mut r = PcapReader {
+                reader,
+                format: PcapFormat::PcapNg,
+                big_endian: false,
+                interfaces: vec![],
+            };
+            r.read_section_header()?;
+            return Ok(r);
+        }
+
+        let (big_endian, units_per_second) =
+            match (BigEndian::read_u32(&magic), LittleEndian::read_u32(&magic)) {
+                (PCAP_MAGIC_MICROSECONDS, _) => (true, 1_000_000),
+                (PCAP_MAGIC_NANOSECONDS, _) => (true, 1_000_000_000),
+                (_, PCAP_MAGIC_MICROSECONDS) => (false, 1_000_000),
+                (_, PCAP_MAGIC_NANOSECONDS) => (false, 1_000_000_000),
+                _ => return Err(Error::ErrPcapBadHeader),
+            };
+
+        let mut header = [0u8; PCAP_FILE_HEADER_SIZE - 4];
+        reader
+            .read_exact(&mut header)
+            .map_err(|_| Error::ErrPcapBadHeader)?;
+        let mut r = PcapReader {
+            reader,
+            format: PcapFormat::Pcap,
+            big_endian,
+            interfaces: vec![],
+        };
+        // version (4), thiszone (4), sigfigs (4), snaplen (4) and the link type
+        let link_type = r.read_u32(&header[16..20]) as u16;
+        if !is_supported_link_type(link_type) {
+            return Err(Error::ErrPcapUnsupportedLinkType);
+        }
+        r.interfaces.push(Interface {
+            link_type,
+            units_per_second,
+        });
+
+        Ok(r)
+    }
+
+    /// format returns the file format of the capture
+    pub fn format(&self) -> PcapFormat {
+        self.format
+    }
+
+    /// parse_next_packet reads from stream and returns the next UDP datagram,
+    /// or ErrIoEOF when no more packets are available.
+    pub fn parse_next_packet(&mut self) -> Result<PcapPacket> {
+        loop {
+            let packet = match self.format {
+                PcapFormat::Pcap => self.read_pcap_record()?,
+                PcapFormat::PcapNg => self.read_pcapng_block()?,
+            };
+            if let Some((interface, timestamp, frame)) = packet {
+                if let Some(packet) = parse_frame(interface.link_type, &frame) {
+                    return Ok(PcapPacket {
+                        timestamp,
+                        ..packet
+                    });
+                }
+            }
+        }
+    }
+
+    fn read_u16(&self, b: &[u8]) -> u16 {
+        if self.big_endian {
+            BigEndian::read_u16(b)
+        } else {
+            LittleEndian::read_u16(b)
+        }
+    }
+
+    fn read_u32(&self, b: &[u8]) -> u32 {
+        if self.big_endian {
+            BigEndian::read_u32(b)
+        } else {
+            LittleEndian::read_u32(b)
+        }
+    }
+
+    /// read_header reads the header of a record or block, or returns ErrIoEOF at the end of the file
+    fn read_header(&mut self, header: &mut [u8]) -> Result<()> {
+        let n = self.reader.read(header)?;
+        if n == 0 {
+            return Err(Error::ErrIoEOF);
+        }
+        self.reader.read_exact(&mut header[n..])?;
+        Ok(())
+    }
+
+    fn read_pcap_record(&mut self) -> Result<Option<(Interface, SystemTime, Vec<u8>)>> {
+        let mut header = [0u8; PCAP_PACKET_HEADER_SIZE];
+        self.read_header(&mut header)?;
+
+        let interface = self.interfaces[0];
+        let seconds = self.read_u32(&header[0..4]) as u64;
+        let units = self.read_u32(&header[4..8]) as u64;
+        let captured_length = self.read_u32(&header[8..12]) as usize;
+        if captured_length > PCAPNG_MAX_BLOCK_SIZE {
+            return Err(Error::ErrPcapInvalidBlock);
+        }
+
+        let mut frame = vec![0u8; captured_length];
+        self.reader.read_exact(&mut frame)?;
+
+        Ok(Some((
+            interface,
+            interface.timestamp(seconds, units)?,
+            frame,
+        )))
+    }
+
+    /// read_section_header reads a section header block, after its block type
+    fn read_section_header(&mut self) -> Result<()> {
+        let mut header = [0u8; 8];
+        self.reader
+            .read_exact(&mut header)
+            .map_err(|_| Error::ErrPcapBadHeader)?;
+        self.big_endian = match BigEndian::read_u32(&header[4..8]) {
+            PCAPNG_BYTE_ORDER_MAGIC => true,
+            magic if magic.swap_bytes() == PCAPNG_BYTE_ORDER_MAGIC => false,
+            _ => return Err(Error::ErrPcapBadHeader),
+        };
+        let block_length = self.read_u32(&header[0..4]) as usize;
+        if block_length < 28 || block_length % 4 != 0 || block_length > PCAPNG_MAX_BLOCK_SIZE {
+            return Err(Error::ErrPcapInvalidBlock);
+        }
+
+        // version, section length and options
+        let mut body = vec![0u8; block_length - 12];
+        self.reader.read_exact(&mut body)?;
+        self.interfaces.clear();
+
+        Ok(())
+    }
+
+    fn read_pcapng_block(&mut self) -> Result<Option<(Interface, SystemTime, Vec<u8>)>> {
+        let mut header = [0u8; 4];
+        self.read_header(&mut header)?;
+        if BigEndian::read_u32(&header) == PCAPNG_BLOCK_SECTION_HEADER {
+            self.read_section_header()?;
+            return Ok(None);
+        }
+        let block_type = self.read_u32(&header);
+
+        let mut length = [0u8; 4];
+        self.reader.read_exact(&mut length)?;
+        let block_length = self.read_u32(&length) as usize;
+        if block_length < 12 || block_length % 4 != 0 || block_length > PCAPNG_MAX_BLOCK_SIZE {
+            return Err(Error::ErrPcapInvalidBlock);
+        }
+        // the body and the trailing block length
+        let mut body = vec![0u8; block_length - 8];
+        self.reader.read_exact(&mut body)?;
+        body.truncate(block_length - 12);
+
+        match block_type {
+            PCAPNG_BLOCK_INTERFACE_DESCRIPTION if body.len() >= 8 => {
+                let link_type = self.read_u16(&body[0..2]);
+                let mut units_per_second = 1_000_000;
+
+                let mut options = &body[8..];
+                while options.len() >= 4 {
+                    let code = self.read_u16(&options[0..2]);
+                    let length = self.read_u16(&options[2..4]) as usize;
+                    let padded_length = (length + 3) & !3;
+                    if code == PCAPNG_OPTION_END || options.len() < 4 + padded_length {
+                        break;
+                    }
+                    if code == PCAPNG_OPTION_IF_TSRESOL && length == 1 {
+                        // a power of 10, or a power of 2 when the high bit is set
+                        let exponent = options[4];
+                        units_per_second = if exponent & 0x80 != 0 {
+                            1u64 << (exponent & 0x7F).min(63)
+                        } else {
+                            10u64.pow(exponent.min(19) as u32)
+                        };
+                    }
+                    options = &options[4 + padded_length..];
+                }
+
+                self.interfaces.push(Interface {
+                    link_type,
+                    units_per_second,
+                });
+                Ok(None)
+            }
+            PCAPNG_BLOCK_ENHANCED_PACKET if body.len() >= 20 => {
+                let interface_id = self.read_u32(&body[0..4]) as usize;
+                let timestamp = ((self.read_u32(&body[4..8]) as u64) << 32)
+                    | self.read_u32(&body[8..12]) as u64;
+                let captured_length = self.read_u32(&body[12..16]) as usize;
+                let interface = match self.interfaces.get(interface_id) {
+                    Some(interface) if 20 + captured_length <= body.len() => *interface,
+                    _ => return Ok(None),
+                };
+
+                Ok(Some((
+                    interface,
+                    interface.timestamp(
+                        timestamp / interface.units_per_second,
+                        timestamp % interface.units_per_second,
+                    )?,
+                    body[20..20 + captured_length].to_vec(),
+                )))
+            }
+            // Other blocks, like statistics or name resolution, are skipped
+            _ => Ok(None),
+        }
+    }
+}
+
+fn is_supported_link_type(link_type: u16) -> bool {
+    matches!(
+        link_type,
+        LINKTYPE_NULL
+            | LINKTYPE_ETHERNET
+            | LINKTYPE_RAW
+            | LINKTYPE_LINUX_SLL
+            | LINKTYPE_IPV4
+            | LINKTYPE_IPV6
+    )
+}
+
+/// parse_frame returns the UDP datagram of a captured frame, or None for other packets.
+/// The timestamp of the returned packet is not set.
+fn parse_frame(link_type: u16, frame: &[u8]) -> Option<PcapPacket> {
+    let ip = match link_type {
+        LINKTYPE_ETHERNET => {
+            let mut offset = 12;
+            let mut ether_type = BigEndian::read_u16(frame.get(offset..offset + 2)?);
+            while ether_type == ETHERTYPE_VLAN {
+                offset += 4;
+                ether_type = BigEndian::read_u16(frame.get(offset..offset + 2)?);
+            }
+            if ether_type != ETHERTYPE_IPV4 && ether_type != ETHERTYPE_IPV6 {
+                return None;
+            }
+            &frame[offset + 2..]
+        }
+        // The IP version tells the address family, which is in the byte order of the
+        // capturing host
+        LINKTYPE_NULL => frame.get(4..)?,
+        LINKTYPE_LINUX_SLL => {
+            let protocol = BigEndian::read_u16(frame.get(14..16)?);
+            if protocol != ETHERTYPE_IPV4 && protocol != ETHERTYPE_IPV6 {
+                return None;
+            }
+            &frame[16..]
+        }
+        LINKTYPE_RAW | LINKTYPE_IPV4 | LINKTYPE_IPV6 => frame,
+        _ => return None,
+    };
+
+    let (source, destination, udp) = match ip.first()? >> 4 {
+        4 => {
+            let header_length = ((ip[0] & 0x0F) as usize) * 4;
+            let total_length = BigEndian::read_u16(ip.get(2..4)?) as usize;
+            let fragment = BigEndian::read_u16(ip.get(6..8)?);
+            // Fragments are not reassembled
+            if *ip.get(9)? != IP_PROTOCOL_UDP || fragment & 0x3FFF != 0 {
+                return None;
+            }
+            let source: [u8; 4] = ip.get(12..16)?.try_into().ok()?;
+            let destination: [u8; 4] = ip.get(16..20)?.try_into().ok()?;
+            (
+                IpAddr::V4(Ipv4Addr::from(source)),
+                IpAddr::V4(Ipv4Addr::from(destination)),
+                ip.get(header_length..total_length.min(ip.len()))?,
+            )
+        }
+        6 => {
+            let payload_length = BigEndian::read_u16(ip.get(4..6)?) as usize;
+            // Extension headers are not supported
+            if *ip.get(6)? != IP_PROTOCOL_UDP {
+                return None;
+            }
+            let source: [u8; 16] = ip.get(8..24)?.try_into().ok()?;
+            let destination: [u8; 16] = ip.get(24..40)?.try_into().ok()?;
+            (
+                IpAddr::V6(Ipv6Addr::from(source)),
+                IpAddr::V6(Ipv6Addr::from(destination)),
+                ip.get(40..(40 + payload_length).min(ip.len()))?,
+            )
+        }
+        _ => return None,
+    };
+
+    let source_port = BigEndian::read_u16(udp.get(0..2)?);
+    let destination_port = BigEndian::read_u16(udp.get(2..4)?);
+    let length = BigEndian::read_u16(udp.get(4..6)?) as usize;
+    let data = udp.get(UDP_HEADER_SIZE..length.max(UDP_HEADER_SIZE).min(udp.len()))?;
+
+    Some(PcapPacket {
+        timestamp: UNIX_EPOCH,
+        source: SocketAddr::new(source, source_port),
+        destination: SocketAddr::new(destination, destination_port),
+        data: Bytes::copy_from_slice(data),
+    })
+}
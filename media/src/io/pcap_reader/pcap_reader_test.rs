@@ -0,0 +1,271 @@
+use super::*;
+use std::io::Cursor;
+
+fn udp(source_port: u16, destination_port: u16, data: &[u8]) -> Vec<u8> {
+    let mut udp = source_port.to_be_bytes().to_vec();
+    udp.extend_from_slice(&destination_port.to_be_bytes());
+    udp.extend_from_slice(&(8 + data.len() as u16).to_be_bytes());
+    udp.extend_from_slice(&[0, 0]);
+    udp.extend_from_slice(data);
+    udp
+}
+
+fn ipv4(protocol: u8, source: [u8; 4], destination: [u8; 4], payload: &[u8]) -> Vec<u8> {
+    let mut ip = vec![0x45, 0];
+    ip.extend_from_slice(&(20 + payload.len() as u16).to_be_bytes());
+    ip.extend_from_slice(&[0, 0, 0x40, 0, 64, protocol, 0, 0]);
+    ip.extend_from_slice(&source);
+    ip.extend_from_slice(&destination);
+    ip.extend_from_slice(payload);
+    ip
+}
+
+fn ipv6(source: Ipv6Addr, destination: Ipv6Addr, payload: &[u8]) -> Vec<u8> {
+    let mut ip = vec![0x60, 0, 0, 0];
+    ip.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+    ip.extend_from_slice(&[IP_PROTOCOL_UDP, 64]);
+    ip.extend_from_slice(&source.octets());
+    ip.extend_from_slice(&destination.octets());
+    ip.extend_from_slice(payload);
+    ip
+}
+
+fn ethernet(ether_type: &[u8], ip: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0; 12];
+    frame.extend_from_slice(ether_type);
+    frame.extend_from_slice(ip);
+    frame
+}
+
+fn pcapng_block(block_type: u32, body: &[u8]) -> Vec<u8> {
+    let padding = (4 - body.len() % 4) % 4;
+    let length = (12 + body.len() + padding) as u32;
+    let mut block = block_type.to_le_bytes().to_vec();
+    block.extend_from_slice(&length.to_le_bytes());
+    block.extend_from_slice(body);
+    block.extend_from_slice(&vec![0; padding]);
+    block.extend_from_slice(&length.to_le_bytes());
+    block
+}
+
+#[test]
+fn test_pcap_reader_parse_pcap() -> Result<()> {
+    // A big endian capture with nanosecond timestamps
+    let mut file = PCAP_MAGIC_NANOSECONDS.to_be_bytes().to_vec();
+    file.extend_from_slice(&[0, 2, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0]);
+    file.extend_from_slice(&65535u32.to_be_bytes());
+    file.extend_from_slice(&(LINKTYPE_ETHERNET as u32).to_be_bytes());
+
+    let rtp: &[u8] = &[0x80, 0x60, 0x00, 0x01, 0, 0, 0, 0, 0, 0, 0, 1, 0xAA];
+    let rtcp: &[u8] = &[0x80, 0xC9, 0x00, 0x01, 0, 0, 0, 1];
+    let source = Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1);
+    let destination = Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 2);
+
+    // A VLAN tagged IPv4 datagram with the padding of a short Ethernet frame
+    let mut vlan = vec![0x81, 0x00, 0x00, 0x01, 0x08, 0x00];
+    vlan.extend(ipv4(
+        IP_PROTOCOL_UDP,
+        [10, 0, 0, 1],
+        [10, 0, 0, 2],
+        &udp(5000, 6000, rtcp),
+    ));
+    vlan.extend_from_slice(&[0; 10]);
+    let frames = [
+        (1, 500, ethernet(&[], &vlan)),
+        // TCP is skipped
+        (
+            2,
+            0,
+            ethernet(
+                &[0x08, 0x00],
+                &ipv4(6, [10, 0, 0, 1], [10, 0, 0, 2], &[0; 20]),
+            ),
+        ),
+        (
+            3,
+            999_999_999,
+            ethernet(
+                &[0x86, 0xDD],
+                &ipv6(source, destination, &udp(7000, 8000, rtp)),
+            ),
+        ),
+    ];
+    for (seconds, nanos, frame) in &frames {
+        file.extend_from_slice(&(*seconds as u32).to_be_bytes());
+        file.extend_from_slice(&(*nanos as u32).to_be_bytes());
+        file.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+        file.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+        file.extend_from_slice(frame);
+    }
+
+    let mut reader = PcapReader::new(Cursor::new(file))?;
+    assert_eq!(reader.format(), PcapFormat::Pcap);
+
+    let packet = reader.parse_next_packet()?;
+    assert_eq!(
+        packet.timestamp,
+        UNIX_EPOCH + Duration::from_secs(1) + Duration::from_nanos(500)
+    );
+    assert_eq!(packet.source, "10.0.0.1:5000".parse().unwrap());
+    assert_eq!(packet.destination, "10.0.0.2:6000".parse().unwrap());
+    assert_eq!(packet.data, Bytes::from_static(rtcp));
+    assert!(is_rtcp(&packet.data));
+
+    let packet = reader.parse_next_packet()?;
+    assert_eq!(
+        packet.timestamp,
+        UNIX_EPOCH + Duration::from_secs(3) + Duration::from_nanos(999_999_999)
+    );
+    assert_eq!(packet.source, SocketAddr::new(source.into(), 7000));
+    assert_eq!(
+        packet.destination,
+        SocketAddr::new(destination.into(), 8000)
+    );
+    assert_eq!(packet.data, Bytes::from_static(rtp));
+    assert!(!is_rtcp(&packet.data));
+
+    assert_eq!(reader.parse_next_packet(), Err(Error::ErrIoEOF));
+
+    Ok(())
+}
+
+#[test]
+fn test_pcap_reader_parse_pcapng() -> Result<()> {
+    let mut body = PCAPNG_BYTE_ORDER_MAGIC.to_le_bytes().to_vec();
+    body.extend_from_slice(&[1, 0, 0, 0]);
+    body.extend_from_slice(&(-1i64).to_le_bytes());
+    let mut file = pcapng_block(PCAPNG_BLOCK_SECTION_HEADER, &body);
+
+    // A raw IP interface with nanosecond timestamps
+    let mut idb = LINKTYPE_RAW.to_le_bytes().to_vec();
+    idb.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+    idb.extend_from_slice(&PCAPNG_OPTION_IF_TSRESOL.to_le_bytes());
+    idb.extend_from_slice(&[1, 0, 9, 0, 0, 0]);
+    idb.extend_from_slice(&[0, 0, 0, 0]);
+    file.extend(pcapng_block(PCAPNG_BLOCK_INTERFACE_DESCRIPTION, &idb));
+    // A Linux cooked capture interface with microsecond timestamps
+    let mut idb = LINKTYPE_LINUX_SLL.to_le_bytes().to_vec();
+    idb.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+    file.extend(pcapng_block(PCAPNG_BLOCK_INTERFACE_DESCRIPTION, &idb));
+    // A Name Resolution Block is skipped
+    file.extend(pcapng_block(4, &[0, 0, 0, 0]));
+
+    let data: &[u8] = &[0x80, 0x60, 0x00, 0x02, 0, 0, 0, 0, 0, 0, 0, 1];
+    let packets = [
+        (
+            0u32,
+            1_500_000_000u64,
+            ipv4(
+                IP_PROTOCOL_UDP,
+                [127, 0, 0, 1],
+                [127, 0, 0, 1],
+                &udp(1, 2, data),
+            ),
+        ),
+        (1, 2_000_001, {
+            let mut sll = vec![0; 14];
+            sll.extend_from_slice(&[0x08, 0x00]);
+            sll.extend(ipv4(
+                IP_PROTOCOL_UDP,
+                [127, 0, 0, 1],
+                [127, 0, 0, 2],
+                &udp(3, 4, data),
+            ));
+            sll
+        }),
+        // An unknown interface is skipped
+        (
+            2,
+            0,
+            ipv4(
+                IP_PROTOCOL_UDP,
+                [127, 0, 0, 1],
+                [127, 0, 0, 1],
+                &udp(5, 6, data),
+            ),
+        ),
+    ];
+    for (interface_id, timestamp, frame) in &packets {
+        let mut epb = interface_id.to_le_bytes().to_vec();
+        epb.extend_from_slice(&((timestamp >> 32) as u32).to_le_bytes());
+        epb.extend_from_slice(&(*timestamp as u32).to_le_bytes());
+        epb.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        epb.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        epb.extend_from_slice(frame);
+        file.extend(pcapng_block(PCAPNG_BLOCK_ENHANCED_PACKET, &epb));
+    }
+
+    let mut reader = PcapReader::new(Cursor::new(file))?;
+    assert_eq!(reader.format(), PcapFormat::PcapNg);
+
+    let packet = reader.parse_next_packet()?;
+    assert_eq!(packet.timestamp, UNIX_EPOCH + Duration::from_millis(1500));
+    assert_eq!(packet.source, "127.0.0.1:1".parse().unwrap());
+    assert_eq!(packet.data, Bytes::from_static(data));
+
+    let packet = reader.parse_next_packet()?;
+    assert_eq!(
+        packet.timestamp,
+        UNIX_EPOCH + Duration::from_micros(2_000_001)
+    );
+    assert_eq!(packet.destination, "127.0.0.2:4".parse().unwrap());
+    assert_eq!(packet.data, Bytes::from_static(data));
+
+    assert_eq!(reader.parse_next_packet(), Err(Error::ErrIoEOF));
+
+    Ok(())
+}
+
+#[test]
+fn test_pcap_reader_parse_invalid_file() {
+    let result = PcapReader::new(Cursor::new(b"DKIF\x00\x00\x20\x00VP80".to_vec()));
+    assert_eq!(result.err(), Some(Error::ErrPcapBadHeader));
+
+    let mut file = PCAP_MAGIC_MICROSECONDS.to_le_bytes().to_vec();
+    file.extend_from_slice(&[2, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0]);
+    file.extend_from_slice(&147u32.to_le_bytes());
+    let result = PcapReader::new(Cursor::new(file));
+    assert_eq!(result.err(), Some(Error::ErrPcapUnsupportedLinkType));
+
+    let mut body = PCAPNG_BYTE_ORDER_MAGIC.to_le_bytes().to_vec();
+    body.extend_from_slice(&[1, 0, 0, 0]);
+    body.extend_from_slice(&(-1i64).to_le_bytes());
+    let mut file = pcapng_block(PCAPNG_BLOCK_SECTION_HEADER, &body);
+    file.extend_from_slice(&PCAPNG_BLOCK_ENHANCED_PACKET.to_le_bytes());
+    file.extend_from_slice(&7u32.to_le_bytes());
+    let mut reader = PcapReader::new(Cursor::new(file)).unwrap();
+    assert_eq!(reader.parse_next_packet(), Err(Error::ErrPcapInvalidBlock));
+}
+
+#[test]
+fn test_pcap_reader_parse_timestamp_overflow() {
+    let mut body = PCAPNG_BYTE_ORDER_MAGIC.to_le_bytes().to_vec();
+    body.extend_from_slice(&[1, 0, 0, 0]);
+    body.extend_from_slice(&(-1i64).to_le_bytes());
+    let mut file = pcapng_block(PCAPNG_BLOCK_SECTION_HEADER, &body);
+
+    // A raw IP interface with timestamps in seconds
+    let mut idb = LINKTYPE_RAW.to_le_bytes().to_vec();
+    idb.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+    idb.extend_from_slice(&PCAPNG_OPTION_IF_TSRESOL.to_le_bytes());
+    idb.extend_from_slice(&[1, 0, 0, 0, 0, 0]);
+    idb.extend_from_slice(&[0, 0, 0, 0]);
+    file.extend(pcapng_block(PCAPNG_BLOCK_INTERFACE_DESCRIPTION, &idb));
+
+    let frame = ipv4(
+        IP_PROTOCOL_UDP,
+        [127, 0, 0, 1],
+        [127, 0, 0, 1],
+        &udp(1, 2, &[0x80, 0x60, 0x00, 0x02]),
+    );
+    let mut epb = 0u32.to_le_bytes().to_vec();
+    epb.extend_from_slice(&u32::MAX.to_le_bytes());
+    epb.extend_from_slice(&u32::MAX.to_le_bytes());
+    epb.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+    epb.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+    epb.extend_from_slice(&frame);
+    file.extend(pcapng_block(PCAPNG_BLOCK_ENHANCED_PACKET, &epb));
+
+    let mut reader = PcapReader::new(Cursor::new(file)).unwrap();
+    assert_eq!(reader.parse_next_packet(), Err(Error::ErrPcapInvalidBlock));
+}
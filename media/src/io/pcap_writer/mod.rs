@@ -0,0 +1,240 @@
+#[cfg(test)]
+mod pcap_writer_test;
+
+use crate::error::{Error, Result};
+use crate::io::pcap_reader::{
+    PcapFormat, PcapPacket, LINKTYPE_RAW, PCAPNG_BLOCK_ENHANCED_PACKET,
+    PCAPNG_BLOCK_INTERFACE_DESCRIPTION, PCAPNG_BLOCK_SECTION_HEADER, PCAPNG_BYTE_ORDER_MAGIC,
+    PCAP_MAGIC_MICROSECONDS,
+};
+
+use byteorder::{LittleEndian, WriteBytesExt};
+use std::io::Write;
+use std::net::{IpAddr, Ipv6Addr};
+use std::time::UNIX_EPOCH;
+
+const IPV4_HEADER_SIZE: usize = 20;
+const IPV6_HEADER_SIZE: usize = 40;
+const UDP_HEADER_SIZE: usize = 8;
+const IP_PROTOCOL_UDP: u8 = 17;
+const IP_TTL: u8 = 64;
+
+/// PcapWriter is used to write UDP datagrams of RTP and RTCP packets to a pcap or pcapng
+/// capture, which can be opened in Wireshark. The datagrams are written as raw IP packets,
+/// with the addresses of the packets and microsecond timestamps.
+pub struct PcapWriter<W: Write> {
+    writer: W,
+    format: PcapFormat,
+    closed: bool,
+}
+
+impl<W: Write> PcapWriter<W> {
+    /// new initialize a new pcap writer with an io.Writer output and the format of the capture
+    pub fn new(writer: W, format: PcapFormat) -> Result<Self> {
+        let mut w = PcapWriter {
+            writer,
+            format,
+            closed: false,
+        };
+
+        w.write_header()?;
+
+        Ok(w)
+    }
+
+    fn write_header(&mut self) -> Result<()> {
+        match self.format {
+            PcapFormat::Pcap => {
+                self.writer
+                    .write_u32::<LittleEndian>(PCAP_MAGIC_MICROSECONDS)?; // magic
+                self.writer.write_u16::<LittleEndian>(2)?; // major version
+                self.writer.write_u16::<LittleEndian>(4)?; // minor version
+                self.writer.write_i32::<LittleEndian>(0)?; // thiszone
+                self.writer.write_u32::<LittleEndian>(0)?; // sigfigs
+                self.writer.write_u32::<LittleEndian>(u16::MAX as u32)?; // snaplen
+                self.writer.write_u32::<LittleEndian>(LINKTYPE_RAW as u32)?; // network
+            }
+            PcapFormat::PcapNg => {
+                // Section Header Block
+                self.writer
+                    .write_u32::<LittleEndian>(PCAPNG_BLOCK_SECTION_HEADER)?;
+                self.writer.write_u32::<LittleEndian>(28)?; // block total length
+                self.writer
+                    .write_u32::<LittleEndian>(PCAPNG_BYTE_ORDER_MAGIC)?;
+                self.writer.write_u16::<LittleEndian>(1)?; // major version
+                self.writer.write_u16::<LittleEndian>(0)?; // minor version
+                self.writer.write_i64::<LittleEndian>(-1)?; // section length, unknown
+                self.writer.write_u32::<LittleEndian>(28)?; // block total length
+
+                // Interface Description Block, with microsecond timestamps
+                self.writer
+                    .write_u32::<LittleEndian>(PCAPNG_BLOCK_INTERFACE_DESCRIPTION)?;
+                self.writer.write_u32::<LittleEndian>(20)?; // block total length
+                self.writer.write_u16::<LittleEndian>(LINKTYPE_RAW)?; // link type
+                self.writer.write_u16::<LittleEndian>(0)?; // reserved
+                self.writer.write_u32::<LittleEndian>(0)?; // snaplen, no limit
+                self.writer.write_u32::<LittleEndian>(20)?; // block total length
+            }
+        }
+
+        Ok(())
+    }
+
+    /// write_packet writes a UDP datagram with its addresses and capture time
+    pub fn write_packet(&mut self, packet: &PcapPacket) -> Result<()> {
+        if self.closed {
+            return Err(Error::ErrFileNotOpened);
+        }
+
+        let frame = ip_packet(packet);
+        let timestamp = packet
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        match self.format {
+            PcapFormat::Pcap => {
+                self.writer
+                    .write_u32::<LittleEndian>(timestamp.as_secs() as u32)?; // ts_sec
+                self.writer
+                    .write_u32::<LittleEndian>(timestamp.subsec_micros())?; // ts_usec
+                self.writer.write_u32::<LittleEndian>(frame.len() as u32)?; // incl_len
+                self.writer.write_u32::<LittleEndian>(frame.len() as u32)?; // orig_len
+                self.writer.write_all(&frame)?;
+            }
+            PcapFormat::PcapNg => {
+                let padding = (4 - frame.len() % 4) % 4;
+                let block_length = (32 + frame.len() + padding) as u32;
+                let micros = timestamp.as_micros() as u64;
+
+                // Enhanced Packet Block
+                self.writer
+                    .write_u32::<LittleEndian>(PCAPNG_BLOCK_ENHANCED_PACKET)?;
+                self.writer.write_u32::<LittleEndian>(block_length)?;
+                self.writer.write_u32::<LittleEndian>(0)?; // interface id
+                self.writer
+                    .write_u32::<LittleEndian>((micros >> 32) as u32)?; // timestamp high
+                self.writer.write_u32::<LittleEndian>(micros as u32)?; // timestamp low
+                self.writer.write_u32::<LittleEndian>(frame.len() as u32)?; // captured length
+                self.writer.write_u32::<LittleEndian>(frame.len() as u32)?; // original length
+                self.writer.write_all(&frame)?;
+                self.writer.write_all(&[0u8; 3][..padding])?;
+                self.writer.write_u32::<LittleEndian>(block_length)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// close stops the capture. It is idempotent.
+    pub fn close(&mut self) -> Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+        self.closed = true;
+
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// ip_packet returns the raw IPv4 or IPv6 packet of a UDP datagram. IPv4 addresses are
+/// mapped to IPv6 when the other address of the packet is IPv6.
+fn ip_packet(packet: &PcapPacket) -> Vec<u8> {
+    let (source, destination) = match (packet.source.ip(), packet.destination.ip()) {
+        (IpAddr::V4(source), IpAddr::V6(destination)) => {
+            (IpAddr::V6(source.to_ipv6_mapped()), IpAddr::V6(destination))
+        }
+        (IpAddr::V6(source), IpAddr::V4(destination)) => {
+            (IpAddr::V6(source), IpAddr::V6(destination.to_ipv6_mapped()))
+        }
+        addresses => addresses,
+    };
+
+    let udp_length = UDP_HEADER_SIZE + packet.data.len();
+    let mut udp = Vec::with_capacity(udp_length);
+    udp.extend_from_slice(&packet.source.port().to_be_bytes());
+    udp.extend_from_slice(&packet.destination.port().to_be_bytes());
+    udp.extend_from_slice(&(udp_length as u16).to_be_bytes());
+    udp.extend_from_slice(&[0, 0]); // checksum
+    udp.extend_from_slice(&packet.data);
+
+    // The pseudo header of the UDP checksum
+    let mut pseudo_header = vec![];
+    let mut ip = match (source, destination) {
+        (IpAddr::V4(source), IpAddr::V4(destination)) => {
+            pseudo_header.extend_from_slice(&source.octets());
+            pseudo_header.extend_from_slice(&destination.octets());
+            pseudo_header.extend_from_slice(&[0, IP_PROTOCOL_UDP]);
+            pseudo_header.extend_from_slice(&(udp_length as u16).to_be_bytes());
+
+            let mut ip = Vec::with_capacity(IPV4_HEADER_SIZE + udp_length);
+            ip.push(0x45); // version, IHL
+            ip.push(0); // DSCP, ECN
+            ip.extend_from_slice(&((IPV4_HEADER_SIZE + udp_length) as u16).to_be_bytes());
+            ip.extend_from_slice(&[0, 0]); // identification
+            ip.extend_from_slice(&[0x40, 0]); // don't fragment
+            ip.push(IP_TTL);
+            ip.push(IP_PROTOCOL_UDP);
+            ip.extend_from_slice(&[0, 0]); // header checksum
+            ip.extend_from_slice(&source.octets());
+            ip.extend_from_slice(&destination.octets());
+            let checksum = internet_checksum(&[&ip]);
+            ip[10..12].copy_from_slice(&checksum.to_be_bytes());
+            ip
+        }
+        (source, destination) => {
+            let source = to_ipv6(source);
+            let destination = to_ipv6(destination);
+            pseudo_header.extend_from_slice(&source.octets());
+            pseudo_header.extend_from_slice(&destination.octets());
+            pseudo_header.extend_from_slice(&(udp_length as u32).to_be_bytes());
+            pseudo_header.extend_from_slice(&[0, 0, 0, IP_PROTOCOL_UDP]);
+
+            let mut ip = Vec::with_capacity(IPV6_HEADER_SIZE + udp_length);
+            ip.extend_from_slice(&[0x60, 0, 0, 0]); // version, traffic class, flow label
+            ip.extend_from_slice(&(udp_length as u16).to_be_bytes());
+            ip.push(IP_PROTOCOL_UDP);
+            ip.push(IP_TTL);
+            ip.extend_from_slice(&source.octets());
+            ip.extend_from_slice(&destination.octets());
+            ip
+        }
+    };
+
+    let checksum = match internet_checksum(&[&pseudo_header, &udp]) {
+        // A zero checksum is sent as all ones, zero means no checksum
+        0 => 0xFFFF,
+        checksum => checksum,
+    };
+    udp[6..8].copy_from_slice(&checksum.to_be_bytes());
+    ip.extend(udp);
+
+    ip
+}
+
+fn to_ipv6(ip: IpAddr) -> Ipv6Addr {
+    match ip {
+        IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+        IpAddr::V6(ip) => ip,
+    }
+}
+
+/// internet_checksum returns the checksum of RFC 1071 over the concatenated data
+fn internet_checksum(data: &[&[u8]]) -> u16 {
+    let mut sum = 0u32;
+    let mut odd: Option<u8> = None;
+    for b in data.iter().flat_map(|d| d.iter()) {
+        match odd.take() {
+            Some(high) => sum += u16::from_be_bytes([high, *b]) as u32,
+            None => odd = Some(*b),
+        }
+    }
+    if let Some(high) = odd {
+        sum += u16::from_be_bytes([high, 0]) as u32;
+    }
+    while sum > 0xFFFF {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    !(sum as u16)
+}
@@ -0,0 +1,85 @@
+use super::*;
+use crate::io::pcap_reader::PcapReader;
+
+use bytes::Bytes;
+use std::io::Cursor;
+use std::net::SocketAddr;
+use std::time::{Duration, SystemTime};
+
+fn packets() -> Vec<PcapPacket> {
+    let start = UNIX_EPOCH + Duration::from_micros(1_650_000_000_123_456);
+    vec![
+        PcapPacket {
+            timestamp: start,
+            source: "192.168.1.2:5000".parse().unwrap(),
+            destination: "192.168.1.3:5002".parse().unwrap(),
+            data: Bytes::from_static(&[0x80, 0x60, 0x00, 0x01, 0, 0, 0, 0, 0, 0, 0, 1, 0xAA]),
+        },
+        PcapPacket {
+            timestamp: start + Duration::from_millis(20),
+            source: "[2001:db8::2]:5002".parse().unwrap(),
+            destination: "[2001:db8::1]:5000".parse().unwrap(),
+            data: Bytes::from_static(&[0x80, 0xC9, 0x00, 0x01, 0, 0, 0, 1]),
+        },
+    ]
+}
+
+#[test]
+fn test_pcap_writer_roundtrip() -> Result<()> {
+    for format in [PcapFormat::Pcap, PcapFormat::PcapNg] {
+        let mut file = vec![];
+        let mut writer = PcapWriter::new(&mut file, format)?;
+        for packet in packets() {
+            writer.write_packet(&packet)?;
+        }
+        writer.close()?;
+        writer.close()?;
+        assert_eq!(
+            writer.write_packet(&packets()[0]),
+            Err(Error::ErrFileNotOpened)
+        );
+
+        let mut reader = PcapReader::new(Cursor::new(file))?;
+        assert_eq!(reader.format(), format);
+        for packet in packets() {
+            assert_eq!(reader.parse_next_packet()?, packet);
+        }
+        assert_eq!(reader.parse_next_packet(), Err(Error::ErrIoEOF));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_pcap_writer_ip_packet() {
+    let packet = &packets()[0];
+    let ip = ip_packet(packet);
+    assert_eq!(ip.len(), 20 + 8 + packet.data.len());
+    assert_eq!(internet_checksum(&[&ip[..20]]), 0);
+
+    let mut pseudo_header = ip[12..20].to_vec();
+    pseudo_header.extend_from_slice(&[0, IP_PROTOCOL_UDP]);
+    pseudo_header.extend_from_slice(&ip[24..26]);
+    assert_eq!(internet_checksum(&[&pseudo_header, &ip[20..]]), 0);
+
+    // An IPv4 address is mapped when the other one is IPv6
+    let packet = PcapPacket {
+        timestamp: SystemTime::now(),
+        source: "10.0.0.1:1".parse().unwrap(),
+        destination: "[::1]:2".parse().unwrap(),
+        data: Bytes::from_static(&[1, 2, 3]),
+    };
+    let ip = ip_packet(&packet);
+    assert_eq!(ip[0] >> 4, 6);
+    assert_eq!(ip.len(), 40 + 8 + 3);
+    let source: [u8; 16] = ip[8..24].try_into().unwrap();
+    assert_eq!(
+        SocketAddr::new(Ipv6Addr::from(source).into(), 1),
+        "[::ffff:10.0.0.1]:1".parse().unwrap()
+    );
+
+    let mut pseudo_header = ip[8..40].to_vec();
+    pseudo_header.extend_from_slice(&(8 + 3u32).to_be_bytes());
+    pseudo_header.extend_from_slice(&[0, 0, 0, IP_PROTOCOL_UDP]);
+    assert_eq!(internet_checksum(&[&pseudo_header, &ip[40..]]), 0);
+}
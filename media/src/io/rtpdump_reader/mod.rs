@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod rtpdump_reader_test;
+
+use crate::error::{Error, Result};
+
+use byteorder::{BigEndian, ReadBytesExt};
+use bytes::Bytes;
+use std::io::Read;
+use std::net::{Ipv4Addr, SocketAddrV4};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const RTPDUMP_FILE_SIGNATURE: &[u8] = b"#!rtpplay1.0 ";
+pub const RTPDUMP_FILE_HEADER_SIZE: usize = 16;
+pub const RTPDUMP_PACKET_HEADER_SIZE: usize = 8;
+
+/// the longest text line of the file header, "#!rtpplay1.0 255.255.255.255/65535\n"
+const RTPDUMP_MAX_LINE_SIZE: usize = 64;
+
+/// RtpDumpHeader is the header of an rtpdump file, as written by rtpdump and read by rtpplay
+/// https://github.com/irtlab/rtptools/blob/master/rtpdump.h
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RtpDumpHeader {
+    /// the wallclock time when the recording started
+    pub start: SystemTime,
+    /// the address the packets were recorded from
+    pub source: SocketAddrV4,
+}
+
+impl Default for RtpDumpHeader {
+    fn default() -> Self {
+        RtpDumpHeader {
+            start: UNIX_EPOCH,
+            source: SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0),
+        }
+    }
+}
+
+/// RtpDumpPacket is an RTP or RTCP packet of an rtpdump file
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct RtpDumpPacket {
+    /// the time of the packet since the start of the recording, in milliseconds
+    pub offset: Duration,
+    pub is_rtcp: bool,
+    /// the marshaled packet
+    pub data: Bytes,
+}
+
+/// RtpDumpReader is used to read the packets of an rtpdump file
+pub struct RtpDumpReader<R: Read> {
+    reader: R,
+}
+
+impl<R: Read> RtpDumpReader<R> {
+    /// new returns a new rtpdump reader and the rtpdump file header
+    /// with an io.Reader input
+    pub fn new(mut reader: R) -> Result<(RtpDumpReader<R>, RtpDumpHeader)> {
+        let mut signature = [0u8; RTPDUMP_FILE_SIGNATURE.len()];
+        reader
+            .read_exact(&mut signature)
+            .map_err(|_| Error::ErrRtpDumpBadHeader)?;
+        if signature != RTPDUMP_FILE_SIGNATURE {
+            return Err(Error::ErrRtpDumpBadHeader);
+        }
+
+        // The rest of the text line is the address/port, which is also in the binary header
+        let mut line_size = signature.len();
+        loop {
+            let b = reader.read_u8().map_err(|_| Error::ErrRtpDumpBadHeader)?;
+            line_size += 1;
+            if b == b'\n' {
+                break;
+            } else if line_size >= RTPDUMP_MAX_LINE_SIZE {
+                return Err(Error::ErrRtpDumpBadHeader);
+            }
+        }
+
+        let start_sec = reader.read_u32::<BigEndian>()?;
+        let start_usec = reader.read_u32::<BigEndian>()?;
+        let source = reader.read_u32::<BigEndian>()?;
+        let port = reader.read_u16::<BigEndian>()?;
+        let _padding = reader.read_u16::<BigEndian>()?;
+
+        let header = RtpDumpHeader {
+            start: UNIX_EPOCH
+                + Duration::from_secs(start_sec as u64)
+                + Duration::from_micros(start_usec as u64),
+            source: SocketAddrV4::new(Ipv4Addr::from(source), port),
+        };
+
+        Ok((RtpDumpReader { reader }, header))
+    }
+
+    /// parse_next_packet reads from stream and returns the next packet,
+    /// or ErrIoEOF when no more packets are available.
+    pub fn parse_next_packet(&mut self) -> Result<RtpDumpPacket> {
+        let mut first = [0u8; 1];
+        if self.reader.read(&mut first)? == 0 {
+            return Err(Error::ErrIoEOF);
+        }
+        let length = ((first[0] as usize) << 8) | self.reader.read_u8()? as usize;
+        // the length of the RTP packet, or 0 for RTCP
+        let packet_length = self.reader.read_u16::<BigEndian>()?;
+        let offset = self.reader.read_u32::<BigEndian>()?;
+
+        if length < RTPDUMP_PACKET_HEADER_SIZE {
+            return Err(Error::ErrRtpDumpInvalidPacket);
+        }
+        let mut data = vec![0u8; length - RTPDUMP_PACKET_HEADER_SIZE];
+        self.reader.read_exact(&mut data)?;
+
+        Ok(RtpDumpPacket {
+            offset: Duration::from_millis(offset as u64),
+            is_rtcp: packet_length == 0,
+            data: Bytes::from(data),
+        })
+    }
+}
@@ -0,0 +1,70 @@
+use super::*;
+use std::io::Cursor;
+
+fn rtpdump_file(packets: &[(u16, u32, &[u8])]) -> Vec<u8> {
+    let mut file = b"#!rtpplay1.0 192.168.1.2/5004\n".to_vec();
+    file.extend_from_slice(&1_600_000_000u32.to_be_bytes()); // start sec
+    file.extend_from_slice(&250_000u32.to_be_bytes()); // start usec
+    file.extend_from_slice(&[192, 168, 1, 2]); // source
+    file.extend_from_slice(&5004u16.to_be_bytes()); // port
+    file.extend_from_slice(&[0, 0]); // padding
+    for (plen, offset, data) in packets {
+        file.extend_from_slice(&(8 + data.len() as u16).to_be_bytes());
+        file.extend_from_slice(&plen.to_be_bytes());
+        file.extend_from_slice(&offset.to_be_bytes());
+        file.extend_from_slice(data);
+    }
+    file
+}
+
+#[test]
+fn test_rtpdump_reader_parse_valid_file() -> Result<()> {
+    let rtp: &[u8] = &[
+        0x80, 0x60, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,
+    ];
+    let rtcp: &[u8] = &[0x80, 0xC9, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01];
+    let file = rtpdump_file(&[(12, 0, rtp), (0, 20, rtcp), (12, 1500, rtp)]);
+
+    let (mut reader, header) = RtpDumpReader::new(Cursor::new(file))?;
+    assert_eq!(
+        header.start,
+        UNIX_EPOCH + Duration::from_secs(1_600_000_000) + Duration::from_millis(250)
+    );
+    assert_eq!(header.source, "192.168.1.2:5004".parse().unwrap());
+
+    let expected = [(0, false, rtp), (20, true, rtcp), (1500, false, rtp)];
+    for (offset, is_rtcp, data) in expected {
+        let packet = reader.parse_next_packet()?;
+        assert_eq!(packet.offset, Duration::from_millis(offset));
+        assert_eq!(packet.is_rtcp, is_rtcp);
+        assert_eq!(packet.data, Bytes::from_static(data));
+    }
+    assert_eq!(reader.parse_next_packet(), Err(Error::ErrIoEOF));
+
+    Ok(())
+}
+
+#[test]
+fn test_rtpdump_reader_parse_invalid_file() {
+    let result = RtpDumpReader::new(Cursor::new(b"DKIF\x00\x00\x20\x00VP80".to_vec()));
+    assert_eq!(result.err(), Some(Error::ErrRtpDumpBadHeader));
+
+    let mut line = b"#!rtpplay1.0 ".to_vec();
+    line.extend_from_slice(&[b'1'; RTPDUMP_MAX_LINE_SIZE]);
+    let result = RtpDumpReader::new(Cursor::new(line));
+    assert_eq!(result.err(), Some(Error::ErrRtpDumpBadHeader));
+
+    let mut file = rtpdump_file(&[]);
+    file.extend_from_slice(&[0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    let (mut reader, _) = RtpDumpReader::new(Cursor::new(file)).unwrap();
+    assert_eq!(
+        reader.parse_next_packet(),
+        Err(Error::ErrRtpDumpInvalidPacket)
+    );
+
+    // A truncated packet
+    let mut file = rtpdump_file(&[(12, 0, &[0x80, 0x60])]);
+    file[RTPDUMP_FILE_HEADER_SIZE + 30 + 1] = 20;
+    let (mut reader, _) = RtpDumpReader::new(Cursor::new(file)).unwrap();
+    assert!(matches!(reader.parse_next_packet(), Err(Error::Io(_))));
+}
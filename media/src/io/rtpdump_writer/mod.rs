@@ -0,0 +1,101 @@
+#[cfg(test)]
+mod rtpdump_writer_test;
+
+use crate::error::{Error, Result};
+use crate::io::rtpdump_reader::{
+    RtpDumpHeader, RtpDumpPacket, RTPDUMP_FILE_SIGNATURE, RTPDUMP_PACKET_HEADER_SIZE,
+};
+use crate::io::Writer;
+
+use byteorder::{BigEndian, WriteBytesExt};
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+use util::Marshal;
+
+/// RtpDumpWriter is used to take RTP and RTCP packets and write them to an rtpdump file,
+/// which can be replayed with rtpplay or opened in Wireshark
+pub struct RtpDumpWriter<W: Write> {
+    writer: W,
+    start: SystemTime,
+    closed: bool,
+}
+
+impl<W: Write> RtpDumpWriter<W> {
+    /// new initialize a new rtpdump writer with an io.Writer output
+    pub fn new(writer: W, header: &RtpDumpHeader) -> Result<Self> {
+        let mut w = RtpDumpWriter {
+            writer,
+            start: header.start,
+            closed: false,
+        };
+
+        w.write_header(header)?;
+
+        Ok(w)
+    }
+
+    fn write_header(&mut self, header: &RtpDumpHeader) -> Result<()> {
+        self.writer.write_all(RTPDUMP_FILE_SIGNATURE)?;
+        writeln!(
+            self.writer,
+            "{}/{}",
+            header.source.ip(),
+            header.source.port()
+        )?;
+
+        let start = header.start.duration_since(UNIX_EPOCH).unwrap_or_default();
+        self.writer.write_u32::<BigEndian>(start.as_secs() as u32)?; // start sec
+        self.writer.write_u32::<BigEndian>(start.subsec_micros())?; // start usec
+        self.writer
+            .write_u32::<BigEndian>(u32::from(*header.source.ip()))?; // source
+        self.writer.write_u16::<BigEndian>(header.source.port())?; // port
+        self.writer.write_u16::<BigEndian>(0)?; // padding
+
+        Ok(())
+    }
+
+    /// write_packet writes a packet with its offset from the start of the recording
+    pub fn write_packet(&mut self, packet: &RtpDumpPacket) -> Result<()> {
+        if self.closed {
+            return Err(Error::ErrFileNotOpened);
+        }
+
+        let length = RTPDUMP_PACKET_HEADER_SIZE + packet.data.len();
+        self.writer.write_u16::<BigEndian>(length as u16)?; // length
+        self.writer.write_u16::<BigEndian>(if packet.is_rtcp {
+            0
+        } else {
+            packet.data.len() as u16
+        })?; // plen
+        self.writer
+            .write_u32::<BigEndian>(packet.offset.as_millis() as u32)?; // offset
+        self.writer.write_all(&packet.data)?;
+
+        Ok(())
+    }
+}
+
+impl<W: Write> Writer for RtpDumpWriter<W> {
+    /// write_rtp adds a new packet, with the time since the start of the recording as its offset
+    fn write_rtp(&mut self, packet: &rtp::packet::Packet) -> Result<()> {
+        let data = packet.marshal()?;
+        self.write_packet(&RtpDumpPacket {
+            offset: SystemTime::now()
+                .duration_since(self.start)
+                .unwrap_or_default(),
+            is_rtcp: false,
+            data,
+        })
+    }
+
+    /// close stops the recording
+    fn close(&mut self) -> Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+        self.closed = true;
+
+        self.writer.flush()?;
+        Ok(())
+    }
+}
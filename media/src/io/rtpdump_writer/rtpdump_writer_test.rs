@@ -0,0 +1,71 @@
+use super::*;
+use crate::error::Error;
+use crate::io::rtpdump_reader::RtpDumpReader;
+
+use bytes::Bytes;
+use std::io::Cursor;
+use std::net::SocketAddrV4;
+use std::time::Duration;
+
+#[test]
+fn test_rtpdump_writer_roundtrip() -> Result<()> {
+    let header = RtpDumpHeader {
+        start: SystemTime::now() - Duration::from_secs(1),
+        source: SocketAddrV4::new([10, 0, 0, 1].into(), 40000),
+    };
+    let packets = vec![
+        RtpDumpPacket {
+            offset: Duration::from_millis(0),
+            is_rtcp: false,
+            data: Bytes::from_static(&[0x80, 0x60, 0x00, 0x01, 0, 0, 0, 0, 0, 0, 0, 1, 0xAA]),
+        },
+        RtpDumpPacket {
+            offset: Duration::from_millis(40),
+            is_rtcp: true,
+            data: Bytes::from_static(&[0x80, 0xC9, 0x00, 0x01, 0, 0, 0, 1]),
+        },
+    ];
+
+    let mut file = vec![];
+    let mut writer = RtpDumpWriter::new(&mut file, &header)?;
+    for packet in &packets {
+        writer.write_packet(packet)?;
+    }
+    let rtp_packet = rtp::packet::Packet {
+        header: rtp::header::Header {
+            version: 2,
+            payload_type: 96,
+            sequence_number: 2,
+            ssrc: 1,
+            ..Default::default()
+        },
+        payload: Bytes::from_static(&[0xBB]),
+    };
+    writer.write_rtp(&rtp_packet)?;
+    writer.close()?;
+    writer.close()?;
+    assert_eq!(writer.write_rtp(&rtp_packet), Err(Error::ErrFileNotOpened));
+
+    assert!(file.starts_with(b"#!rtpplay1.0 10.0.0.1/40000\n"));
+    let (mut reader, read_header) = RtpDumpReader::new(Cursor::new(file))?;
+    // The start time is stored in microseconds
+    assert_eq!(read_header.source, header.source);
+    let start_error = header
+        .start
+        .duration_since(read_header.start)
+        .unwrap_or_default();
+    assert!(start_error < Duration::from_micros(1));
+
+    for packet in &packets {
+        assert_eq!(&reader.parse_next_packet()?, packet);
+    }
+
+    // write_rtp writes the time since the start of the recording
+    let packet = reader.parse_next_packet()?;
+    assert!(!packet.is_rtcp);
+    assert!(packet.offset >= Duration::from_secs(1));
+    assert_eq!(packet.data, rtp_packet.marshal()?);
+    assert_eq!(reader.parse_next_packet(), Err(Error::ErrIoEOF));
+
+    Ok(())
+}
@@ -0,0 +1,549 @@
+#[cfg(test)]
+mod webm_reader_test;
+
+use crate::error::{Error, Result};
+use crate::Sample;
+
+use byteorder::{BigEndian, ReadBytesExt};
+use bytes::Bytes;
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Cursor, Read};
+use std::time::Duration;
+
+pub const EBML_ID_HEADER: u32 = 0x1A45DFA3;
+pub const EBML_ID_VERSION: u32 = 0x4286;
+pub const EBML_ID_READ_VERSION: u32 = 0x42F7;
+pub const EBML_ID_MAX_ID_LENGTH: u32 = 0x42F2;
+pub const EBML_ID_MAX_SIZE_LENGTH: u32 = 0x42F3;
+pub const EBML_ID_DOC_TYPE: u32 = 0x4282;
+pub const EBML_ID_DOC_TYPE_VERSION: u32 = 0x4287;
+pub const EBML_ID_DOC_TYPE_READ_VERSION: u32 = 0x4285;
+pub const EBML_ID_VOID: u32 = 0xEC;
+
+pub const WEBM_ID_SEGMENT: u32 = 0x18538067;
+pub const WEBM_ID_SEEK_HEAD: u32 = 0x114D9B74;
+pub const WEBM_ID_SEEK: u32 = 0x4DBB;
+pub const WEBM_ID_SEEK_ID: u32 = 0x53AB;
+pub const WEBM_ID_SEEK_POSITION: u32 = 0x53AC;
+pub const WEBM_ID_INFO: u32 = 0x1549A966;
+pub const WEBM_ID_TIMECODE_SCALE: u32 = 0x2AD7B1;
+pub const WEBM_ID_DURATION: u32 = 0x4489;
+pub const WEBM_ID_MUXING_APP: u32 = 0x4D80;
+pub const WEBM_ID_WRITING_APP: u32 = 0x5741;
+pub const WEBM_ID_TRACKS: u32 = 0x1654AE6B;
+pub const WEBM_ID_TRACK_ENTRY: u32 = 0xAE;
+pub const WEBM_ID_TRACK_NUMBER: u32 = 0xD7;
+pub const WEBM_ID_TRACK_UID: u32 = 0x73C5;
+pub const WEBM_ID_TRACK_TYPE: u32 = 0x83;
+pub const WEBM_ID_FLAG_LACING: u32 = 0x9C;
+pub const WEBM_ID_CODEC_ID: u32 = 0x86;
+pub const WEBM_ID_CODEC_PRIVATE: u32 = 0x63A2;
+pub const WEBM_ID_CODEC_DELAY: u32 = 0x56AA;
+pub const WEBM_ID_SEEK_PRE_ROLL: u32 = 0x56BB;
+pub const WEBM_ID_VIDEO: u32 = 0xE0;
+pub const WEBM_ID_PIXEL_WIDTH: u32 = 0xB0;
+pub const WEBM_ID_PIXEL_HEIGHT: u32 = 0xBA;
+pub const WEBM_ID_AUDIO: u32 = 0xE1;
+pub const WEBM_ID_SAMPLING_FREQUENCY: u32 = 0xB5;
+pub const WEBM_ID_CHANNELS: u32 = 0x9F;
+pub const WEBM_ID_CLUSTER: u32 = 0x1F43B675;
+pub const WEBM_ID_TIMECODE: u32 = 0xE7;
+pub const WEBM_ID_SIMPLE_BLOCK: u32 = 0xA3;
+pub const WEBM_ID_BLOCK_GROUP: u32 = 0xA0;
+pub const WEBM_ID_BLOCK: u32 = 0xA1;
+pub const WEBM_ID_BLOCK_DURATION: u32 = 0x9B;
+pub const WEBM_ID_REFERENCE_BLOCK: u32 = 0xFB;
+pub const WEBM_ID_CUES: u32 = 0x1C53BB6B;
+pub const WEBM_ID_CUE_POINT: u32 = 0xBB;
+pub const WEBM_ID_CUE_TIME: u32 = 0xB3;
+pub const WEBM_ID_CUE_TRACK_POSITIONS: u32 = 0xB7;
+pub const WEBM_ID_CUE_TRACK: u32 = 0xF7;
+pub const WEBM_ID_CUE_CLUSTER_POSITION: u32 = 0xF1;
+
+pub const WEBM_TRACK_TYPE_VIDEO: u8 = 1;
+pub const WEBM_TRACK_TYPE_AUDIO: u8 = 2;
+pub const WEBM_DEFAULT_TIMECODE_SCALE: u64 = 1_000_000;
+pub const WEBM_BLOCK_FLAG_KEY_FRAME: u8 = 0x80;
+pub const WEBM_BLOCK_LACING_MASK: u8 = 0x06;
+
+/// WebmCodec is a codec that can be stored in a WebM file
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WebmCodec {
+    Vp8,
+    Vp9,
+    Av1,
+    Opus,
+}
+
+impl WebmCodec {
+    /// codec_id returns the Matroska codec ID of the codec
+    pub fn codec_id(&self) -> &'static str {
+        match self {
+            WebmCodec::Vp8 => "V_VP8",
+            WebmCodec::Vp9 => "V_VP9",
+            WebmCodec::Av1 => "V_AV1",
+            WebmCodec::Opus => "A_OPUS",
+        }
+    }
+
+    /// from_codec_id returns the codec of a Matroska codec ID
+    pub fn from_codec_id(codec_id: &str) -> Option<Self> {
+        match codec_id {
+            "V_VP8" => Some(WebmCodec::Vp8),
+            "V_VP9" => Some(WebmCodec::Vp9),
+            "V_AV1" => Some(WebmCodec::Av1),
+            "A_OPUS" => Some(WebmCodec::Opus),
+            _ => None,
+        }
+    }
+
+    pub fn is_video(&self) -> bool {
+        *self != WebmCodec::Opus
+    }
+}
+
+/// WebmTrackHeader is the TrackEntry of a track in a WebM file
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct WebmTrackHeader {
+    pub track_number: u64,
+    pub track_type: u8,
+    pub codec_id: String,
+    pub codec_private: Bytes,
+    pub width: u64,
+    pub height: u64,
+    pub sample_rate: f64,
+    pub channels: u64,
+}
+
+impl WebmTrackHeader {
+    /// codec returns the codec of the track, if it is one of [`WebmCodec`]
+    pub fn codec(&self) -> Option<WebmCodec> {
+        WebmCodec::from_codec_id(&self.codec_id)
+    }
+}
+
+/// WebmHeader is the metadata of a WebM file, read from
+/// its EBML header, Info and Tracks elements
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct WebmHeader {
+    pub doc_type: String,
+    /// nanoseconds of a timecode tick
+    pub timecode_scale: u64,
+    /// the duration of the file, if the writer recorded it
+    pub duration: Option<Duration>,
+    pub tracks: Vec<WebmTrackHeader>,
+}
+
+/// WebmFrame is a frame of a track of a WebM file
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct WebmFrame {
+    pub track_number: u64,
+    /// presentation time of the frame, relative to the start of the file
+    pub timestamp: Duration,
+    /// time until the next frame of the track
+    pub duration: Duration,
+    pub is_key_frame: bool,
+    pub data: Bytes,
+}
+
+impl WebmFrame {
+    /// into_sample converts the frame to a [`Sample`], as written by
+    /// `TrackLocalStaticSample::write_sample`
+    pub fn into_sample(self) -> Sample {
+        Sample {
+            data: self.data,
+            duration: self.duration,
+            ..Default::default()
+        }
+    }
+}
+
+/// WebmReader is used to read WebM files and return the frames of their tracks.
+/// The frames of a track are returned in order, and each frame is returned once
+/// the next frame of its track is read, so that its duration is known.
+pub struct WebmReader<R: Read> {
+    reader: R,
+    timecode_scale: u64,
+    cluster_timecode: i64,
+    pending: HashMap<u64, WebmFrame>,
+    last_durations: HashMap<u64, Duration>,
+    ready: VecDeque<WebmFrame>,
+    eof: bool,
+}
+
+impl<R: Read> WebmReader<R> {
+    /// new returns a new WebM reader and WebM header
+    /// with an io.Reader input
+    pub fn new(reader: R) -> Result<(WebmReader<R>, WebmHeader)> {
+        let mut r = WebmReader {
+            reader,
+            timecode_scale: WEBM_DEFAULT_TIMECODE_SCALE,
+            cluster_timecode: 0,
+            pending: HashMap::new(),
+            last_durations: HashMap::new(),
+            ready: VecDeque::new(),
+            eof: false,
+        };
+
+        let header = r.parse_header()?;
+
+        Ok((r, header))
+    }
+
+    /// parse_next_frame reads from stream and returns the next WebM frame.
+    /// Returns ErrIoEOF when no more frames are available.
+    pub fn parse_next_frame(&mut self) -> Result<WebmFrame> {
+        loop {
+            if let Some(frame) = self.ready.pop_front() {
+                return Ok(frame);
+            }
+
+            if self.eof {
+                if self.pending.is_empty() {
+                    return Err(Error::ErrIoEOF);
+                }
+
+                let mut frames: Vec<WebmFrame> = self.pending.drain().map(|(_, f)| f).collect();
+                frames.sort_by_key(|f| f.timestamp);
+                for mut frame in frames {
+                    if frame.duration.is_zero() {
+                        if let Some(duration) = self.last_durations.get(&frame.track_number) {
+                            frame.duration = *duration;
+                        }
+                    }
+                    self.ready.push_back(frame);
+                }
+                continue;
+            }
+
+            match self.read_block()? {
+                Some(frame) => self.push_frame(frame),
+                None => self.eof = true,
+            }
+        }
+    }
+
+    fn push_frame(&mut self, frame: WebmFrame) {
+        if let Some(mut previous) = self.pending.remove(&frame.track_number) {
+            if previous.duration.is_zero() {
+                previous.duration = frame.timestamp.saturating_sub(previous.timestamp);
+            }
+            self.last_durations
+                .insert(previous.track_number, previous.duration);
+            self.ready.push_back(previous);
+        }
+        self.pending.insert(frame.track_number, frame);
+    }
+
+    /// parse_header reads the EBML header and the elements of the segment
+    /// up to its first cluster
+    fn parse_header(&mut self) -> Result<WebmHeader> {
+        let mut header = WebmHeader {
+            timecode_scale: WEBM_DEFAULT_TIMECODE_SCALE,
+            ..Default::default()
+        };
+
+        match read_element_header(&mut self.reader)? {
+            Some((EBML_ID_HEADER, Some(size))) => {
+                let data = read_data(&mut self.reader, size)?;
+                let mut children = Cursor::new(data);
+                while let Some((id, size)) = read_element_header(&mut children)? {
+                    let size = size.ok_or(Error::ErrWebmInvalidVint)?;
+                    let data = read_data(&mut children, size)?;
+                    if id == EBML_ID_DOC_TYPE {
+                        header.doc_type = read_string(&data);
+                    }
+                }
+            }
+            _ => return Err(Error::ErrWebmBadHeader),
+        }
+        if header.doc_type != "webm" && header.doc_type != "matroska" {
+            return Err(Error::ErrWebmBadHeader);
+        }
+
+        match read_element_header(&mut self.reader)? {
+            Some((WEBM_ID_SEGMENT, _)) => {}
+            _ => return Err(Error::ErrWebmBadHeader),
+        }
+
+        let mut duration = None;
+        loop {
+            let (id, size) = match read_element_header(&mut self.reader)? {
+                Some(element) => element,
+                None => break,
+            };
+            if id == WEBM_ID_CLUSTER {
+                break;
+            }
+
+            let size = size.ok_or(Error::ErrWebmInvalidVint)?;
+            match id {
+                WEBM_ID_INFO => {
+                    let data = read_data(&mut self.reader, size)?;
+                    let mut children = Cursor::new(data);
+                    while let Some((id, size)) = read_element_header(&mut children)? {
+                        let size = size.ok_or(Error::ErrWebmInvalidVint)?;
+                        let data = read_data(&mut children, size)?;
+                        match id {
+                            WEBM_ID_TIMECODE_SCALE => header.timecode_scale = read_uint(&data),
+                            WEBM_ID_DURATION => duration = Some(read_float(&data)),
+                            _ => {}
+                        }
+                    }
+                }
+                WEBM_ID_TRACKS => {
+                    let data = read_data(&mut self.reader, size)?;
+                    let mut children = Cursor::new(data);
+                    while let Some((id, size)) = read_element_header(&mut children)? {
+                        let size = size.ok_or(Error::ErrWebmInvalidVint)?;
+                        let data = read_data(&mut children, size)?;
+                        if id == WEBM_ID_TRACK_ENTRY {
+                            header.tracks.push(parse_track_entry(data)?);
+                        }
+                    }
+                }
+                _ => skip_data(&mut self.reader, size)?,
+            }
+        }
+
+        if header.tracks.is_empty() {
+            return Err(Error::ErrWebmMissingTracks);
+        }
+
+        if header.timecode_scale == 0 {
+            header.timecode_scale = WEBM_DEFAULT_TIMECODE_SCALE;
+        }
+        self.timecode_scale = header.timecode_scale;
+        header.duration = duration.and_then(|d| {
+            if d.is_finite() && d > 0.0 {
+                Some(Duration::from_nanos(
+                    (d * header.timecode_scale as f64) as u64,
+                ))
+            } else {
+                None
+            }
+        });
+
+        Ok(header)
+    }
+
+    /// read_block reads the elements of the clusters up to the next block.
+    /// Returns None at the end of the stream.
+    fn read_block(&mut self) -> Result<Option<WebmFrame>> {
+        loop {
+            let (id, size) = match read_element_header(&mut self.reader)? {
+                Some(element) => element,
+                None => return Ok(None),
+            };
+
+            // The children of segments and clusters are read in place, which supports
+            // the unknown sizes of live streams.
+            if id == WEBM_ID_SEGMENT || id == WEBM_ID_CLUSTER {
+                continue;
+            }
+
+            let size = size.ok_or(Error::ErrWebmInvalidVint)?;
+            match id {
+                WEBM_ID_TIMECODE => {
+                    let data = read_data(&mut self.reader, size)?;
+                    self.cluster_timecode = i64::try_from(read_uint(&data))
+                        .map_err(|_| Error::ErrWebmTimecodeOverflow)?;
+                }
+                WEBM_ID_SIMPLE_BLOCK => {
+                    let data = read_data(&mut self.reader, size)?;
+                    let (mut frame, flags) = self.parse_block(data)?;
+                    frame.is_key_frame = flags & WEBM_BLOCK_FLAG_KEY_FRAME != 0;
+                    return Ok(Some(frame));
+                }
+                WEBM_ID_BLOCK_GROUP => {
+                    let data = read_data(&mut self.reader, size)?;
+                    let mut children = Cursor::new(data);
+                    let mut block = None;
+                    let mut duration = None;
+                    let mut is_key_frame = true;
+                    while let Some((id, size)) = read_element_header(&mut children)? {
+                        let size = size.ok_or(Error::ErrWebmInvalidVint)?;
+                        let data = read_data(&mut children, size)?;
+                        match id {
+                            WEBM_ID_BLOCK => block = Some(self.parse_block(data)?.0),
+                            WEBM_ID_BLOCK_DURATION => duration = Some(read_uint(&data)),
+                            WEBM_ID_REFERENCE_BLOCK => is_key_frame = false,
+                            _ => {}
+                        }
+                    }
+
+                    if let Some(mut frame) = block {
+                        frame.is_key_frame = is_key_frame;
+                        if let Some(duration) = duration {
+                            frame.duration = Duration::from_nanos(
+                                duration
+                                    .checked_mul(self.timecode_scale)
+                                    .ok_or(Error::ErrWebmTimecodeOverflow)?,
+                            );
+                        }
+                        return Ok(Some(frame));
+                    }
+                }
+                _ => skip_data(&mut self.reader, size)?,
+            }
+        }
+    }
+
+    /// parse_block parses the track number, timecode and flags of a (Simple)Block
+    fn parse_block(&self, data: Vec<u8>) -> Result<(WebmFrame, u8)> {
+        let mut reader = Cursor::new(data);
+        let (track_number, _) = read_vint(&mut reader)?.ok_or(Error::ErrIncompleteFrameHeader)?;
+        let timecode = reader
+            .read_i16::<BigEndian>()
+            .map_err(|_| Error::ErrIncompleteFrameHeader)?;
+        let flags = reader
+            .read_u8()
+            .map_err(|_| Error::ErrIncompleteFrameHeader)?;
+        if flags & WEBM_BLOCK_LACING_MASK != 0 {
+            return Err(Error::ErrWebmLacingUnsupported);
+        }
+
+        let ticks = self
+            .cluster_timecode
+            .checked_add(timecode as i64)
+            .ok_or(Error::ErrWebmTimecodeOverflow)?
+            .max(0) as u64;
+        let timestamp = ticks
+            .checked_mul(self.timecode_scale)
+            .ok_or(Error::ErrWebmTimecodeOverflow)?;
+        let offset = reader.position() as usize;
+        let mut data = Bytes::from(reader.into_inner());
+
+        Ok((
+            WebmFrame {
+                track_number,
+                timestamp: Duration::from_nanos(timestamp),
+                data: data.split_off(offset),
+                ..Default::default()
+            },
+            flags,
+        ))
+    }
+}
+
+fn parse_track_entry(data: Vec<u8>) -> Result<WebmTrackHeader> {
+    let mut track = WebmTrackHeader::default();
+
+    let mut children = Cursor::new(data);
+    while let Some((id, size)) = read_element_header(&mut children)? {
+        let size = size.ok_or(Error::ErrWebmInvalidVint)?;
+        let data = read_data(&mut children, size)?;
+        match id {
+            WEBM_ID_TRACK_NUMBER => track.track_number = read_uint(&data),
+            WEBM_ID_TRACK_TYPE => track.track_type = read_uint(&data) as u8,
+            WEBM_ID_CODEC_ID => track.codec_id = read_string(&data),
+            WEBM_ID_CODEC_PRIVATE => track.codec_private = Bytes::from(data),
+            WEBM_ID_VIDEO | WEBM_ID_AUDIO => {
+                let mut settings = Cursor::new(data);
+                while let Some((id, size)) = read_element_header(&mut settings)? {
+                    let size = size.ok_or(Error::ErrWebmInvalidVint)?;
+                    let data = read_data(&mut settings, size)?;
+                    match id {
+                        WEBM_ID_PIXEL_WIDTH => track.width = read_uint(&data),
+                        WEBM_ID_PIXEL_HEIGHT => track.height = read_uint(&data),
+                        WEBM_ID_SAMPLING_FREQUENCY => track.sample_rate = read_float(&data),
+                        WEBM_ID_CHANNELS => track.channels = read_uint(&data),
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(track)
+}
+
+/// read_vint reads an EBML variable size integer and returns its value with the
+/// VINT_MARKER cleared, and whether all its value bits are set.
+/// Returns None at the end of the stream.
+fn read_vint<R: Read>(reader: &mut R) -> Result<Option<(u64, bool)>> {
+    let mut first = [0u8; 1];
+    if reader.read(&mut first)? == 0 {
+        return Ok(None);
+    }
+
+    let length = first[0].leading_zeros() as usize + 1;
+    if length > 8 {
+        return Err(Error::ErrWebmInvalidVint);
+    }
+
+    let mut rest = [0u8; 7];
+    reader
+        .read_exact(&mut rest[..length - 1])
+        .map_err(|_| Error::ErrWebmInvalidVint)?;
+
+    let mut value = (first[0] as u64) & (0xff >> length);
+    for b in &rest[..length - 1] {
+        value = (value << 8) | *b as u64;
+    }
+    let all_ones = value == (1u64 << (7 * length)) - 1;
+
+    Ok(Some((value, all_ones)))
+}
+
+/// read_element_header reads the ID and size of an EBML element. The size is None
+/// if it is unknown. Returns None at the end of the stream.
+pub(crate) fn read_element_header<R: Read>(reader: &mut R) -> Result<Option<(u32, Option<u64>)>> {
+    let mut first = [0u8; 1];
+    if reader.read(&mut first)? == 0 {
+        return Ok(None);
+    }
+
+    // IDs keep their VINT_MARKER and are at most 4 bytes long
+    let length = first[0].leading_zeros() as usize + 1;
+    if length > 4 {
+        return Err(Error::ErrWebmInvalidVint);
+    }
+    let mut id = first[0] as u32;
+    for _ in 1..length {
+        let b = reader.read_u8().map_err(|_| Error::ErrWebmInvalidVint)?;
+        id = (id << 8) | b as u32;
+    }
+
+    let (size, all_ones) = read_vint(reader)?.ok_or(Error::ErrWebmInvalidVint)?;
+    Ok(Some((id, if all_ones { None } else { Some(size) })))
+}
+
+fn read_data<R: Read>(reader: &mut R, size: u64) -> Result<Vec<u8>> {
+    let mut data = vec![];
+    reader.by_ref().take(size).read_to_end(&mut data)?;
+    if (data.len() as u64) < size {
+        return Err(Error::ErrIncompleteFrameData);
+    }
+
+    Ok(data)
+}
+
+fn skip_data<R: Read>(reader: &mut R, size: u64) -> Result<()> {
+    if io::copy(&mut reader.by_ref().take(size), &mut io::sink())? < size {
+        return Err(Error::ErrIncompleteFrameData);
+    }
+
+    Ok(())
+}
+
+fn read_uint(data: &[u8]) -> u64 {
+    data.iter().fold(0, |value, b| (value << 8) | *b as u64)
+}
+
+fn read_float(data: &[u8]) -> f64 {
+    match data.len() {
+        4 => f32::from_be_bytes([data[0], data[1], data[2], data[3]]) as f64,
+        8 => f64::from_be_bytes([
+            data[0], data[1], data[2], data[3], data[4], data[5], data[6], data[7],
+        ]),
+        _ => 0.0,
+    }
+}
+
+fn read_string(data: &[u8]) -> String {
+    String::from_utf8_lossy(data)
+        .trim_end_matches('\0')
+        .to_owned()
+}
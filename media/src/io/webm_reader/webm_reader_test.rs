@@ -0,0 +1,208 @@
+use super::*;
+
+fn element(id: &[u8], data: &[u8]) -> Vec<u8> {
+    assert!(data.len() < 0x7f);
+    let mut e = id.to_vec();
+    e.push(0x80 | data.len() as u8);
+    e.extend_from_slice(data);
+    e
+}
+
+fn ebml_header(doc_type: &str) -> Vec<u8> {
+    element(
+        &[0x1A, 0x45, 0xDF, 0xA3],
+        &element(&[0x42, 0x82], doc_type.as_bytes()),
+    )
+}
+
+fn tracks() -> Vec<u8> {
+    let mut video = element(&[0xD7], &[1]);
+    video.extend(element(&[0x83], &[WEBM_TRACK_TYPE_VIDEO]));
+    video.extend(element(&[0x86], b"V_VP8"));
+    let mut settings = element(&[0xB0], &[0x02, 0x80]);
+    settings.extend(element(&[0xBA], &[0x01, 0xE0]));
+    video.extend(element(&[0xE0], &settings));
+
+    let mut audio = element(&[0xD7], &[2]);
+    audio.extend(element(&[0x83], &[WEBM_TRACK_TYPE_AUDIO]));
+    audio.extend(element(&[0x86], b"A_OPUS"));
+    audio.extend(element(&[0x63, 0xA2], b"OpusHead"));
+    let mut settings = element(&[0xB5], &48000f64.to_be_bytes());
+    settings.extend(element(&[0x9F], &[2]));
+    audio.extend(element(&[0xE1], &settings));
+
+    let mut entries = element(&[0xAE], &video);
+    entries.extend(element(&[0xAE], &audio));
+    element(&[0x16, 0x54, 0xAE, 0x6B], &entries)
+}
+
+fn simple_block(track_number: u8, timecode: i16, flags: u8, data: &[u8]) -> Vec<u8> {
+    let mut block = vec![0x80 | track_number];
+    block.extend_from_slice(&timecode.to_be_bytes());
+    block.push(flags);
+    block.extend_from_slice(data);
+    element(&[0xA3], &block)
+}
+
+#[test]
+fn test_webm_reader_parse_valid_file() -> Result<()> {
+    // A live stream, whose segment and cluster sizes are unknown
+    let mut file = ebml_header("webm");
+    file.extend_from_slice(&[0x18, 0x53, 0x80, 0x67, 0xFF]);
+    file.extend(element(&[0xEC], &[0, 0, 0]));
+    file.extend(element(
+        &[0x15, 0x49, 0xA9, 0x66],
+        &element(&[0x44, 0x89], &250f64.to_be_bytes()),
+    ));
+    file.extend(tracks());
+
+    file.extend_from_slice(&[0x1F, 0x43, 0xB6, 0x75, 0xFF]);
+    file.extend(element(&[0xE7], &[100]));
+    file.extend(simple_block(1, 0, 0x80, &[0x00, 0x01]));
+    file.extend(simple_block(2, 0, 0x80, &[0xA0]));
+    file.extend(simple_block(2, 20, 0x80, &[0xA1]));
+    file.extend(simple_block(1, 33, 0x00, &[0x01, 0x02]));
+
+    file.extend_from_slice(&[0x1F, 0x43, 0xB6, 0x75, 0xFF]);
+    file.extend(element(&[0xE7], &[0x00, 0xA0]));
+    let mut group = element(&[0xA1], &[0x81, 0x00, 0x06, 0x00, 0x01, 0x03]);
+    group.extend(element(&[0xFB], &[0xDF]));
+    group.extend(element(&[0x9B], &[40]));
+    file.extend(element(&[0xA0], &group));
+    file.extend(simple_block(2, 0, 0x80, &[0xA2]));
+
+    let (mut reader, header) = WebmReader::new(Cursor::new(file))?;
+    assert_eq!(header.doc_type, "webm");
+    assert_eq!(header.timecode_scale, WEBM_DEFAULT_TIMECODE_SCALE);
+    assert_eq!(header.duration, Some(Duration::from_millis(250)));
+    assert_eq!(header.tracks.len(), 2);
+    assert_eq!(header.tracks[0].codec(), Some(WebmCodec::Vp8));
+    assert_eq!(
+        (header.tracks[0].width, header.tracks[0].height),
+        (640, 480)
+    );
+    assert_eq!(header.tracks[1].codec(), Some(WebmCodec::Opus));
+    assert_eq!(
+        header.tracks[1].codec_private,
+        Bytes::from_static(b"OpusHead")
+    );
+    assert_eq!(header.tracks[1].sample_rate, 48000.0);
+    assert_eq!(header.tracks[1].channels, 2);
+
+    let mut frames = vec![];
+    loop {
+        match reader.parse_next_frame() {
+            Ok(frame) => frames.push(frame),
+            Err(err) => {
+                assert_eq!(err, Error::ErrIoEOF);
+                break;
+            }
+        }
+    }
+
+    let expected = [
+        (1, 100, 33, true, vec![0x00, 0x01]),
+        (2, 100, 20, true, vec![0xA0]),
+        (2, 120, 40, true, vec![0xA1]),
+        (1, 133, 33, false, vec![0x01, 0x02]),
+        (1, 166, 40, false, vec![0x01, 0x03]),
+        (2, 160, 40, true, vec![0xA2]),
+    ];
+    assert_eq!(frames.len(), expected.len());
+    for (track_number, timestamp, duration, is_key_frame, data) in expected {
+        let frame = frames
+            .iter()
+            .find(|f| {
+                f.track_number == track_number && f.timestamp == Duration::from_millis(timestamp)
+            })
+            .expect("frame should be read");
+        assert_eq!(frame.duration, Duration::from_millis(duration));
+        assert_eq!(frame.is_key_frame, is_key_frame);
+        assert_eq!(frame.data, Bytes::from(data));
+    }
+
+    // The frames of a track are read in order
+    for track_number in [1, 2] {
+        let timestamps: Vec<Duration> = frames
+            .iter()
+            .filter(|f| f.track_number == track_number)
+            .map(|f| f.timestamp)
+            .collect();
+        let mut sorted = timestamps.clone();
+        sorted.sort();
+        assert_eq!(timestamps, sorted);
+    }
+
+    let sample = frames
+        .into_iter()
+        .find(|f| f.track_number == 1)
+        .unwrap()
+        .into_sample();
+    assert_eq!(sample.data, Bytes::from_static(&[0x00, 0x01]));
+    assert_eq!(sample.duration, Duration::from_millis(33));
+
+    Ok(())
+}
+
+#[test]
+fn test_webm_reader_parse_invalid_file() {
+    let result = WebmReader::new(Cursor::new(b"DKIF\x00\x00\x20\x00VP80".to_vec()));
+    assert_eq!(result.err(), Some(Error::ErrWebmBadHeader));
+
+    let result = WebmReader::new(Cursor::new(ebml_header("mp4")));
+    assert_eq!(result.err(), Some(Error::ErrWebmBadHeader));
+
+    let mut file = ebml_header("webm");
+    file.extend_from_slice(&[0x18, 0x53, 0x80, 0x67, 0xFF]);
+    file.extend_from_slice(&[0x1F, 0x43, 0xB6, 0x75, 0xFF]);
+    let result = WebmReader::new(Cursor::new(file));
+    assert_eq!(result.err(), Some(Error::ErrWebmMissingTracks));
+
+    let mut file = ebml_header("matroska");
+    file.extend_from_slice(&[0x18, 0x53, 0x80, 0x67, 0xFF]);
+    file.extend(tracks());
+    file.extend_from_slice(&[0x1F, 0x43, 0xB6, 0x75, 0xFF]);
+    file.extend(simple_block(2, 0, 0x82, &[0x01, 0xA0, 0xA1]));
+    let (mut reader, _) = WebmReader::new(Cursor::new(file)).unwrap();
+    assert_eq!(
+        reader.parse_next_frame(),
+        Err(Error::ErrWebmLacingUnsupported)
+    );
+}
+
+#[test]
+fn test_webm_reader_huge_timecodes() {
+    let block_group = |duration: &[u8]| {
+        let mut group = element(&[0xA1], &[0x81, 0x00, 0x00, 0x00, 0x01]);
+        group.extend(element(&[0x9B], duration));
+        element(&[0xA0], &group)
+    };
+
+    let tests = vec![
+        (u64::MAX.to_be_bytes(), simple_block(1, 0, 0x80, &[0x01])),
+        (
+            (i64::MAX as u64).to_be_bytes(),
+            simple_block(1, 1, 0x80, &[0x01]),
+        ),
+        (
+            (u64::MAX / WEBM_DEFAULT_TIMECODE_SCALE + 1).to_be_bytes(),
+            simple_block(1, 0, 0x80, &[0x01]),
+        ),
+        (0u64.to_be_bytes(), block_group(&u64::MAX.to_be_bytes())),
+    ];
+
+    for (cluster_timecode, block) in tests {
+        let mut file = ebml_header("webm");
+        file.extend_from_slice(&[0x18, 0x53, 0x80, 0x67, 0xFF]);
+        file.extend(tracks());
+        file.extend_from_slice(&[0x1F, 0x43, 0xB6, 0x75, 0xFF]);
+        file.extend(element(&[0xE7], &cluster_timecode));
+        file.extend(block);
+
+        let (mut reader, _) = WebmReader::new(Cursor::new(file)).unwrap();
+        assert_eq!(
+            reader.parse_next_frame(),
+            Err(Error::ErrWebmTimecodeOverflow)
+        );
+    }
+}
@@ -0,0 +1,952 @@
+#[cfg(test)]
+mod webm_writer_test;
+
+use crate::error::{Error, Result};
+use crate::io::ogg_reader::ID_PAGE_SIGNATURE;
+use crate::io::sample_builder::SampleBuilder;
+use crate::io::webm_reader::*;
+use crate::io::{BitReader, Writer};
+use crate::Sample;
+
+use bytes::Bytes;
+use rtp::codecs::{av1::Av1Packet, opus::OpusPacket, vp8::Vp8Packet, vp9::Vp9Packet};
+use rtp::packetizer::Depacketizer;
+use std::collections::VecDeque;
+use std::io::{Seek, SeekFrom, Write};
+use std::time::{Duration, SystemTime};
+
+/// how many packets the sample builder of a track waits for a missing packet
+const SAMPLE_BUILDER_MAX_LATE: u16 = 128;
+/// how long the frames of a track wait for the frames of the other tracks to be interleaved
+const MAX_INTERLEAVE_DELAY_MS: i64 = 1000;
+/// the longest cluster, which keeps the block timecodes relative to the cluster in an i16
+const MAX_CLUSTER_DURATION_MS: i64 = 5000;
+/// the space kept after the Tracks element for codec private data known later
+const TRACKS_RESERVED_SIZE: usize = 1024;
+/// the size of a Seek entry with an 8-byte position
+const SEEK_ENTRY_SIZE: usize = 21;
+/// the unknown size, used until the size of an element is known
+const UNKNOWN_SIZE: u64 = 0x00FF_FFFF_FFFF_FFFF;
+/// 80ms, the seek pre-roll required by WebM for Opus
+const OPUS_SEEK_PRE_ROLL_NS: u64 = 80_000_000;
+
+/// WebmTrack configures a track of a WebM file written by [`WebmWriter`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebmTrack {
+    /// the RTP payload type of the packets of the track
+    pub payload_type: u8,
+    pub codec: WebmCodec,
+    /// the RTP clock rate of the track
+    pub clock_rate: u32,
+    /// the width and height of a video track, in pixels
+    pub width: u16,
+    pub height: u16,
+    /// the channel count of an audio track
+    pub channels: u8,
+}
+
+impl WebmTrack {
+    /// video returns a video track with a 90kHz clock
+    pub fn video(payload_type: u8, codec: WebmCodec, width: u16, height: u16) -> Self {
+        WebmTrack {
+            payload_type,
+            codec,
+            clock_rate: 90000,
+            width,
+            height,
+            channels: 0,
+        }
+    }
+
+    /// opus returns an Opus track with a 48kHz clock
+    pub fn opus(payload_type: u8, channels: u8) -> Self {
+        WebmTrack {
+            payload_type,
+            codec: WebmCodec::Opus,
+            clock_rate: 48000,
+            width: 0,
+            height: 0,
+            channels,
+        }
+    }
+}
+
+enum TrackDepacketizer {
+    Vp8(Vp8Packet),
+    Vp9(Vp9Packet),
+    Av1(Av1Packet),
+    Opus(OpusPacket),
+}
+
+impl TrackDepacketizer {
+    fn new(codec: WebmCodec) -> Self {
+        match codec {
+            WebmCodec::Vp8 => TrackDepacketizer::Vp8(Vp8Packet::default()),
+            WebmCodec::Vp9 => TrackDepacketizer::Vp9(Vp9Packet::default()),
+            WebmCodec::Av1 => TrackDepacketizer::Av1(Av1Packet::default()),
+            WebmCodec::Opus => TrackDepacketizer::Opus(OpusPacket),
+        }
+    }
+
+    fn depacketizer(&self) -> &dyn Depacketizer {
+        match self {
+            TrackDepacketizer::Vp8(d) => d,
+            TrackDepacketizer::Vp9(d) => d,
+            TrackDepacketizer::Av1(d) => d,
+            TrackDepacketizer::Opus(d) => d,
+        }
+    }
+}
+
+impl Depacketizer for TrackDepacketizer {
+    fn depacketize(&mut self, b: &Bytes) -> std::result::Result<Bytes, rtp::Error> {
+        match self {
+            TrackDepacketizer::Vp8(d) => d.depacketize(b),
+            TrackDepacketizer::Vp9(d) => d.depacketize(b),
+            TrackDepacketizer::Av1(d) => d.depacketize(b),
+            TrackDepacketizer::Opus(d) => d.depacketize(b),
+        }
+    }
+
+    fn is_partition_head(&self, payload: &Bytes) -> bool {
+        self.depacketizer().is_partition_head(payload)
+    }
+
+    fn is_partition_tail(&self, marker: bool, payload: &Bytes) -> bool {
+        self.depacketizer().is_partition_tail(marker, payload)
+    }
+}
+
+struct Frame {
+    /// milliseconds since the start of the file
+    time: i64,
+    duration: i64,
+    is_key_frame: bool,
+    data: Bytes,
+}
+
+struct TrackState {
+    track: WebmTrack,
+    track_number: u64,
+    track_uid: u64,
+    builder: SampleBuilder<TrackDepacketizer>,
+    codec_private: Option<Vec<u8>>,
+    seen_key_frame: bool,
+    /// milliseconds between the start of the file and the first frame of the track
+    start: Option<i64>,
+    last_rtp_timestamp: u32,
+    /// the RTP timestamp since the first frame, unwrapped
+    rtp_elapsed: i64,
+    last_time: i64,
+    pending: VecDeque<Frame>,
+}
+
+struct Cluster {
+    size_position: u64,
+    timecode: i64,
+    is_empty: bool,
+}
+
+struct CuePoint {
+    time: i64,
+    track_number: u64,
+    cluster_position: u64,
+}
+
+/// WebmWriter is used to take RTP packets or samples of VP8, VP9 or AV1 video and Opus audio
+/// and write them to one WebM file on disk.
+///
+/// The packets of each track are assembled to frames by a [`SampleBuilder`], which releases a
+/// frame once a packet of the next one arrives. The tracks are aligned by the wallclock time of
+/// their first frame, and their frames are interleaved by time. A cluster starts at every video
+/// key frame, and the cue index of those clusters is written on close.
+pub struct WebmWriter<W: Write + Seek> {
+    writer: W,
+    tracks: Vec<TrackState>,
+    has_video: bool,
+    /// the wallclock time of the first frame of the file
+    origin: Option<SystemTime>,
+    segment_size_position: u64,
+    segment_data_position: u64,
+    cues_seek_position: u64,
+    duration_position: u64,
+    tracks_position: u64,
+    tracks_space: usize,
+    cluster: Option<Cluster>,
+    cue_points: Vec<CuePoint>,
+    duration: i64,
+    closed: bool,
+}
+
+impl<W: Write + Seek> WebmWriter<W> {
+    /// new initialize a new WebM writer with an io.Writer output and the tracks of the file
+    pub fn new(writer: W, tracks: Vec<WebmTrack>) -> Result<Self> {
+        if tracks.is_empty() {
+            return Err(Error::ErrWebmMissingTracks);
+        }
+
+        let mut w = WebmWriter {
+            writer,
+            has_video: tracks.iter().any(|t| t.codec.is_video()),
+            tracks: tracks
+                .into_iter()
+                .enumerate()
+                .map(|(i, track)| TrackState {
+                    builder: SampleBuilder::new(
+                        SAMPLE_BUILDER_MAX_LATE,
+                        TrackDepacketizer::new(track.codec),
+                        track.clock_rate,
+                    ),
+                    track_number: i as u64 + 1,
+                    track_uid: rand::random::<u64>() | 1,
+                    codec_private: None,
+                    seen_key_frame: false,
+                    start: None,
+                    last_rtp_timestamp: 0,
+                    rtp_elapsed: 0,
+                    last_time: 0,
+                    pending: VecDeque::new(),
+                    track,
+                })
+                .collect(),
+            origin: None,
+            segment_size_position: 0,
+            segment_data_position: 0,
+            cues_seek_position: 0,
+            duration_position: 0,
+            tracks_position: 0,
+            tracks_space: 0,
+            cluster: None,
+            cue_points: vec![],
+            duration: 0,
+            closed: false,
+        };
+
+        w.write_header()?;
+
+        Ok(w)
+    }
+
+    /*
+        ref: https://www.matroska.org/technical/elements.html
+        https://www.webmproject.org/docs/container/
+
+        EBML
+        Segment
+        ├── SeekHead (Info, Tracks and Cues, written on close)
+        ├── Info (Duration, written on close)
+        ├── Tracks
+        ├── Void (space for codec private data)
+        ├── Cluster
+        │   ├── Timecode
+        │   └── SimpleBlock ...
+        ├── Cluster ...
+        └── Cues (written on close)
+    */
+
+    fn write_header(&mut self) -> Result<()> {
+        let mut ebml = vec![];
+        ebml_uint(&mut ebml, EBML_ID_VERSION, 1);
+        ebml_uint(&mut ebml, EBML_ID_READ_VERSION, 1);
+        ebml_uint(&mut ebml, EBML_ID_MAX_ID_LENGTH, 4);
+        ebml_uint(&mut ebml, EBML_ID_MAX_SIZE_LENGTH, 8);
+        ebml_string(&mut ebml, EBML_ID_DOC_TYPE, "webm");
+        ebml_uint(&mut ebml, EBML_ID_DOC_TYPE_VERSION, 4);
+        ebml_uint(&mut ebml, EBML_ID_DOC_TYPE_READ_VERSION, 2);
+        let mut header = vec![];
+        ebml_element(&mut header, EBML_ID_HEADER, &ebml);
+
+        ebml_id(&mut header, WEBM_ID_SEGMENT);
+        let start = self.writer.stream_position()?;
+        self.segment_size_position = start + header.len() as u64;
+        ebml_size_8(&mut header, UNKNOWN_SIZE);
+        self.segment_data_position = start + header.len() as u64;
+
+        let mut info = vec![];
+        ebml_uint(
+            &mut info,
+            WEBM_ID_TIMECODE_SCALE,
+            WEBM_DEFAULT_TIMECODE_SCALE,
+        );
+        ebml_string(&mut info, WEBM_ID_MUXING_APP, "webrtc-rs");
+        ebml_string(&mut info, WEBM_ID_WRITING_APP, "webrtc-rs");
+        let duration_offset = info.len() + 3;
+        ebml_float(&mut info, WEBM_ID_DURATION, 0.0);
+        let mut info_element = vec![];
+        ebml_element(&mut info_element, WEBM_ID_INFO, &info);
+
+        let tracks = self.tracks_element();
+
+        // The SeekHead has the positions of Info and Tracks, and a Void where the
+        // position of Cues is written on close.
+        let mut seek_head = vec![];
+        let seek_head_size = 4 + 1 + 3 * SEEK_ENTRY_SIZE;
+        let info_position = seek_head_size as u64;
+        let tracks_position = info_position + info_element.len() as u64;
+        ebml_seek_entry(&mut seek_head, WEBM_ID_INFO, info_position);
+        ebml_seek_entry(&mut seek_head, WEBM_ID_TRACKS, tracks_position);
+        let cues_seek_offset = header.len() + 5 + seek_head.len();
+        ebml_void(&mut seek_head, SEEK_ENTRY_SIZE);
+        ebml_element(&mut header, WEBM_ID_SEEK_HEAD, &seek_head);
+
+        self.cues_seek_position = start + cues_seek_offset as u64;
+        self.duration_position = start
+            + (header.len() + info_element.len() - info.len()) as u64
+            + duration_offset as u64;
+        header.extend_from_slice(&info_element);
+
+        self.tracks_position = self.segment_data_position + tracks_position;
+        self.tracks_space = tracks.len() + TRACKS_RESERVED_SIZE;
+        header.extend_from_slice(&tracks);
+        ebml_void(&mut header, TRACKS_RESERVED_SIZE);
+
+        self.writer.write_all(&header)?;
+
+        Ok(())
+    }
+
+    fn tracks_element(&self) -> Vec<u8> {
+        let mut tracks = vec![];
+        for state in &self.tracks {
+            let track = &state.track;
+            let mut entry = vec![];
+            ebml_uint(&mut entry, WEBM_ID_TRACK_NUMBER, state.track_number);
+            ebml_uint(&mut entry, WEBM_ID_TRACK_UID, state.track_uid);
+            ebml_uint(&mut entry, WEBM_ID_FLAG_LACING, 0);
+            ebml_string(&mut entry, WEBM_ID_CODEC_ID, track.codec.codec_id());
+
+            if track.codec.is_video() {
+                ebml_uint(&mut entry, WEBM_ID_TRACK_TYPE, WEBM_TRACK_TYPE_VIDEO as u64);
+                if let Some(codec_private) = &state.codec_private {
+                    ebml_element(&mut entry, WEBM_ID_CODEC_PRIVATE, codec_private);
+                }
+
+                let mut video = vec![];
+                ebml_uint(&mut video, WEBM_ID_PIXEL_WIDTH, track.width as u64);
+                ebml_uint(&mut video, WEBM_ID_PIXEL_HEIGHT, track.height as u64);
+                ebml_element(&mut entry, WEBM_ID_VIDEO, &video);
+            } else {
+                ebml_uint(&mut entry, WEBM_ID_TRACK_TYPE, WEBM_TRACK_TYPE_AUDIO as u64);
+
+                // OpusHead, with no pre-skip as the stream is recorded from its middle
+                let mut opus_head = vec![];
+                opus_head.extend_from_slice(ID_PAGE_SIGNATURE);
+                opus_head.push(1); // version
+                opus_head.push(track.channels);
+                opus_head.extend_from_slice(&0u16.to_le_bytes()); // pre-skip
+                opus_head.extend_from_slice(&track.clock_rate.to_le_bytes());
+                opus_head.extend_from_slice(&0u16.to_le_bytes()); // output gain
+                opus_head.push(0); // channel map 0 = one stream: mono or stereo
+                ebml_element(&mut entry, WEBM_ID_CODEC_PRIVATE, &opus_head);
+                ebml_uint(&mut entry, WEBM_ID_CODEC_DELAY, 0);
+                ebml_uint(&mut entry, WEBM_ID_SEEK_PRE_ROLL, OPUS_SEEK_PRE_ROLL_NS);
+
+                let mut audio = vec![];
+                ebml_float(
+                    &mut audio,
+                    WEBM_ID_SAMPLING_FREQUENCY,
+                    track.clock_rate as f64,
+                );
+                ebml_uint(&mut audio, WEBM_ID_CHANNELS, track.channels as u64);
+                ebml_element(&mut entry, WEBM_ID_AUDIO, &audio);
+            }
+
+            ebml_element(&mut tracks, WEBM_ID_TRACK_ENTRY, &entry);
+        }
+
+        let mut element = vec![];
+        ebml_element(&mut element, WEBM_ID_TRACKS, &tracks);
+        element
+    }
+
+    /// rewrite_tracks rewrites the Tracks element into its reserved space
+    fn rewrite_tracks(&mut self) -> Result<()> {
+        let tracks = self.tracks_element();
+        let void_size = self.tracks_space.wrapping_sub(tracks.len());
+        if tracks.len() > self.tracks_space || void_size == 1 {
+            return Ok(());
+        }
+
+        let mut element = tracks;
+        if void_size > 0 {
+            ebml_void(&mut element, void_size);
+        }
+
+        let position = self.writer.stream_position()?;
+        self.writer.seek(SeekFrom::Start(self.tracks_position))?;
+        self.writer.write_all(&element)?;
+        self.writer.seek(SeekFrom::Start(position))?;
+
+        Ok(())
+    }
+
+    /// write_sample adds a sample of the track at track_index, in the order of the
+    /// tracks given to new. The sample is timed by its timestamp and packet_timestamp.
+    pub fn write_sample(&mut self, track_index: usize, sample: &Sample) -> Result<()> {
+        if self.closed {
+            return Err(Error::ErrFileNotOpened);
+        }
+        if track_index >= self.tracks.len() {
+            return Err(Error::ErrWebmUnknownTrack);
+        }
+
+        self.push_sample(
+            track_index,
+            sample.data.clone(),
+            sample.timestamp,
+            sample.packet_timestamp,
+            sample.duration,
+        )
+    }
+
+    fn push_sample(
+        &mut self,
+        track_index: usize,
+        data: Bytes,
+        timestamp: SystemTime,
+        packet_timestamp: u32,
+        duration: Duration,
+    ) -> Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        let state = &mut self.tracks[track_index];
+        let codec = state.track.codec;
+        let is_key_frame = is_key_frame(codec, &data);
+        if !state.seen_key_frame {
+            if !is_key_frame {
+                return Ok(());
+            }
+            state.seen_key_frame = true;
+        }
+        let origin = *self.origin.get_or_insert(timestamp);
+
+        let start = match state.start {
+            Some(start) => {
+                state.rtp_elapsed +=
+                    packet_timestamp.wrapping_sub(state.last_rtp_timestamp) as i32 as i64;
+                start
+            }
+            None => {
+                let start = timestamp
+                    .duration_since(origin)
+                    .unwrap_or_default()
+                    .as_millis() as i64;
+                state.start = Some(start);
+                start
+            }
+        };
+        state.last_rtp_timestamp = packet_timestamp;
+
+        let time = start + state.rtp_elapsed * 1000 / state.track.clock_rate.max(1) as i64;
+        state.pending.push_back(Frame {
+            time,
+            duration: duration.as_millis() as i64,
+            is_key_frame,
+            data,
+        });
+
+        if codec == WebmCodec::Av1 && is_key_frame && state.codec_private.is_none() {
+            if let Some(codec_private) =
+                av1_codec_private(&state.pending[state.pending.len() - 1].data)
+            {
+                state.codec_private = Some(codec_private);
+                self.rewrite_tracks()?;
+            }
+        }
+
+        self.interleave(false)
+    }
+
+    /// interleave writes the pending frames in time order. A frame is written once every
+    /// track has a pending frame, or once it waited too long for the other tracks.
+    fn interleave(&mut self, flush: bool) -> Result<()> {
+        loop {
+            let mut next: Option<(usize, i64)> = None;
+            let mut all_pending = true;
+            let mut latest = i64::MIN;
+            for (i, state) in self.tracks.iter().enumerate() {
+                match (state.pending.front(), state.pending.back()) {
+                    (Some(front), Some(back)) => {
+                        if next.map_or(true, |(_, time)| front.time < time) {
+                            next = Some((i, front.time));
+                        }
+                        latest = latest.max(back.time);
+                    }
+                    _ => all_pending = false,
+                }
+            }
+
+            let (track_index, time) = match next {
+                Some(next) => next,
+                None => return Ok(()),
+            };
+            if !flush && !all_pending && latest - time <= MAX_INTERLEAVE_DELAY_MS {
+                return Ok(());
+            }
+
+            if let Some(frame) = self.tracks[track_index].pending.pop_front() {
+                self.write_block(track_index, frame)?;
+            }
+        }
+    }
+
+    fn write_block(&mut self, track_index: usize, frame: Frame) -> Result<()> {
+        let state = &mut self.tracks[track_index];
+        let mut time = frame.time.max(state.last_time);
+        let track_number = state.track_number;
+        let is_video = state.track.codec.is_video();
+
+        let new_cluster = match &self.cluster {
+            Some(cluster) => {
+                (is_video && frame.is_key_frame && !cluster.is_empty)
+                    || time - cluster.timecode > MAX_CLUSTER_DURATION_MS
+            }
+            None => true,
+        };
+        if new_cluster {
+            self.close_cluster()?;
+
+            let position = self.writer.stream_position()?;
+            let mut cluster = vec![];
+            ebml_id(&mut cluster, WEBM_ID_CLUSTER);
+            ebml_size_8(&mut cluster, UNKNOWN_SIZE);
+            ebml_uint(&mut cluster, WEBM_ID_TIMECODE, time as u64);
+            self.writer.write_all(&cluster)?;
+
+            if (is_video && frame.is_key_frame) || !self.has_video {
+                self.cue_points.push(CuePoint {
+                    time,
+                    track_number,
+                    cluster_position: position - self.segment_data_position,
+                });
+            }
+            self.cluster = Some(Cluster {
+                size_position: position + 4,
+                timecode: time,
+                is_empty: true,
+            });
+        }
+
+        let cluster = match &mut self.cluster {
+            Some(cluster) => cluster,
+            None => return Ok(()),
+        };
+        // a frame of a track may be older than the cluster when it waited too long
+        time = time.max(cluster.timecode);
+        cluster.is_empty = false;
+        self.tracks[track_index].last_time = time;
+
+        let mut block = vec![];
+        ebml_size(&mut block, track_number);
+        block.extend_from_slice(&((time - cluster.timecode) as i16).to_be_bytes());
+        block.push(if frame.is_key_frame {
+            WEBM_BLOCK_FLAG_KEY_FRAME
+        } else {
+            0
+        });
+
+        let mut element = vec![];
+        ebml_id(&mut element, WEBM_ID_SIMPLE_BLOCK);
+        ebml_size(&mut element, (block.len() + frame.data.len()) as u64);
+        element.extend_from_slice(&block);
+        self.writer.write_all(&element)?;
+        self.writer.write_all(&frame.data)?;
+
+        self.duration = self.duration.max(time + frame.duration);
+
+        Ok(())
+    }
+
+    /// close_cluster writes the size of the current cluster
+    fn close_cluster(&mut self) -> Result<()> {
+        if let Some(cluster) = self.cluster.take() {
+            let position = self.writer.stream_position()?;
+            let mut size = vec![];
+            ebml_size_8(&mut size, position - cluster.size_position - 8);
+            self.writer.seek(SeekFrom::Start(cluster.size_position))?;
+            self.writer.write_all(&size)?;
+            self.writer.seek(SeekFrom::Start(position))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> Writer for WebmWriter<W> {
+    /// write_rtp adds a new packet to the track of its payload type
+    fn write_rtp(&mut self, packet: &rtp::packet::Packet) -> Result<()> {
+        if self.closed {
+            return Err(Error::ErrFileNotOpened);
+        }
+
+        let track_index = self
+            .tracks
+            .iter()
+            .position(|t| t.track.payload_type == packet.header.payload_type)
+            .ok_or(Error::ErrWebmUnknownTrack)?;
+
+        self.tracks[track_index].builder.push(packet.clone());
+        while let Some(sample) = self.tracks[track_index].builder.pop() {
+            self.push_sample(
+                track_index,
+                sample.data,
+                sample.timestamp,
+                sample.packet_timestamp,
+                sample.duration,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// close writes the pending frames and the cue index, and stops the recording
+    fn close(&mut self) -> Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+        self.closed = true;
+
+        self.interleave(true)?;
+        self.close_cluster()?;
+
+        if !self.cue_points.is_empty() {
+            let cues_position = self.writer.stream_position()?;
+            let mut cues = vec![];
+            for cue_point in &self.cue_points {
+                let mut positions = vec![];
+                ebml_uint(&mut positions, WEBM_ID_CUE_TRACK, cue_point.track_number);
+                ebml_uint(
+                    &mut positions,
+                    WEBM_ID_CUE_CLUSTER_POSITION,
+                    cue_point.cluster_position,
+                );
+                let mut point = vec![];
+                ebml_uint(&mut point, WEBM_ID_CUE_TIME, cue_point.time as u64);
+                ebml_element(&mut point, WEBM_ID_CUE_TRACK_POSITIONS, &positions);
+                ebml_element(&mut cues, WEBM_ID_CUE_POINT, &point);
+            }
+            let mut element = vec![];
+            ebml_element(&mut element, WEBM_ID_CUES, &cues);
+            self.writer.write_all(&element)?;
+
+            let mut seek = vec![];
+            ebml_seek_entry(
+                &mut seek,
+                WEBM_ID_CUES,
+                cues_position - self.segment_data_position,
+            );
+            self.writer.seek(SeekFrom::Start(self.cues_seek_position))?;
+            self.writer.write_all(&seek)?;
+        }
+
+        let end = self.writer.seek(SeekFrom::End(0))?;
+
+        self.writer.seek(SeekFrom::Start(self.duration_position))?;
+        self.writer
+            .write_all(&(self.duration as f64).to_be_bytes())?;
+
+        let mut size = vec![];
+        ebml_size_8(&mut size, end - self.segment_data_position);
+        self.writer
+            .seek(SeekFrom::Start(self.segment_size_position))?;
+        self.writer.write_all(&size)?;
+
+        self.writer.seek(SeekFrom::Start(end))?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// is_key_frame checks whether a frame of the codec can be decoded on its own
+fn is_key_frame(codec: WebmCodec, data: &[u8]) -> bool {
+    match codec {
+        // the P bit of the frame tag is 0
+        WebmCodec::Vp8 => !data.is_empty() && data[0] & 0x01 == 0,
+        WebmCodec::Vp9 => {
+            let mut reader = BitReader::new(data);
+            let mut parse = || -> Option<bool> {
+                if reader.read(2)? != 2 {
+                    return Some(false); // frame_marker
+                }
+                let profile = reader.read(1)? | (reader.read(1)? << 1);
+                if profile == 3 {
+                    reader.read(1)?; // reserved_zero
+                }
+                if reader.read(1)? == 1 {
+                    return Some(false); // show_existing_frame
+                }
+                Some(reader.read(1)? == 0) // frame_type
+            };
+            parse().unwrap_or(false)
+        }
+        // WebRTC sends the sequence header with every key frame
+        WebmCodec::Av1 => av1_obus(data).any(|(obu_type, _)| obu_type == AV1_OBU_SEQUENCE_HEADER),
+        WebmCodec::Opus => true,
+    }
+}
+
+const AV1_OBU_SEQUENCE_HEADER: u8 = 1;
+
+/// av1_obus returns the type and the bytes of each OBU of an AV1 frame
+fn av1_obus(data: &[u8]) -> impl Iterator<Item = (u8, &[u8])> {
+    let mut rest = data;
+    std::iter::from_fn(move || {
+        let header = *rest.first()?;
+        let header_size = if header & 0x04 != 0 { 2 } else { 1 };
+        let size = if header & 0x02 != 0 {
+            let mut size = 0usize;
+            let mut length = 0;
+            loop {
+                let b = *rest.get(header_size + length)?;
+                size |= ((b & 0x7f) as usize) << (7 * length);
+                length += 1;
+                if b & 0x80 == 0 || length == 8 {
+                    break;
+                }
+            }
+            header_size + length + size
+        } else {
+            rest.len()
+        };
+
+        if size > rest.len() {
+            return None;
+        }
+        let (obu, remaining) = rest.split_at(size);
+        rest = remaining;
+        Some(((header >> 3) & 0x0f, obu))
+    })
+}
+
+/// av1_codec_private returns the AV1CodecConfigurationRecord of a key frame, which is
+/// built from its sequence header OBU.
+/// <https://aomediacodec.github.io/av1-isobmff/#av1codecconfigurationbox-section>
+fn av1_codec_private(data: &[u8]) -> Option<Vec<u8>> {
+    let (_, obu) = av1_obus(data).find(|(obu_type, _)| *obu_type == AV1_OBU_SEQUENCE_HEADER)?;
+
+    // skip the OBU header and size
+    let header_size = if obu[0] & 0x04 != 0 { 2 } else { 1 };
+    let mut payload = &obu[header_size..];
+    while let Some((b, rest)) = payload.split_first() {
+        payload = rest;
+        if b & 0x80 == 0 {
+            break;
+        }
+    }
+
+    let mut r = BitReader::new(payload);
+    let seq_profile = r.read(3)? as u8;
+    let _still_picture = r.read(1)?;
+    let reduced_still_picture_header = r.read(1)? == 1;
+
+    let seq_level_idx_0;
+    let mut seq_tier_0 = 0;
+    if reduced_still_picture_header {
+        seq_level_idx_0 = r.read(5)? as u8;
+    } else {
+        let mut decoder_model_info_present = false;
+        let mut buffer_delay_length = 0;
+        if r.read(1)? == 1 {
+            // timing_info
+            r.read(32)?; // num_units_in_display_tick
+            r.read(32)?; // time_scale
+            if r.read(1)? == 1 {
+                r.read_uvlc()?; // num_ticks_per_picture_minus_1
+            }
+            decoder_model_info_present = r.read(1)? == 1;
+            if decoder_model_info_present {
+                buffer_delay_length = r.read(5)? as usize + 1;
+                r.read(32)?; // num_units_in_decoding_tick
+                r.read(5)?; // buffer_removal_time_length_minus_1
+                r.read(5)?; // frame_presentation_time_length_minus_1
+            }
+        }
+        let initial_display_delay_present = r.read(1)? == 1;
+        let operating_points_cnt = r.read(5)? + 1;
+        let mut levels = vec![];
+        for _ in 0..operating_points_cnt {
+            r.read(12)?; // operating_point_idc
+            let seq_level_idx = r.read(5)? as u8;
+            let seq_tier = if seq_level_idx > 7 {
+                r.read(1)? as u8
+            } else {
+                0
+            };
+            levels.push((seq_level_idx, seq_tier));
+            if decoder_model_info_present && r.read(1)? == 1 {
+                r.read(buffer_delay_length)?; // decoder_buffer_delay
+                r.read(buffer_delay_length)?; // encoder_buffer_delay
+                r.read(1)?; // low_delay_mode_flag
+            }
+            if initial_display_delay_present && r.read(1)? == 1 {
+                r.read(4)?; // initial_display_delay_minus_1
+            }
+        }
+        seq_level_idx_0 = levels[0].0;
+        seq_tier_0 = levels[0].1;
+    }
+
+    let frame_width_bits = r.read(4)? as usize + 1;
+    let frame_height_bits = r.read(4)? as usize + 1;
+    r.read(frame_width_bits)?; // max_frame_width_minus_1
+    r.read(frame_height_bits)?; // max_frame_height_minus_1
+    if !reduced_still_picture_header && r.read(1)? == 1 {
+        // frame_id_numbers_present_flag
+        r.read(4)?; // delta_frame_id_length_minus_2
+        r.read(3)?; // additional_frame_id_length_minus_1
+    }
+    r.read(1)?; // use_128x128_superblock
+    r.read(1)?; // enable_filter_intra
+    r.read(1)?; // enable_intra_edge_filter
+    if !reduced_still_picture_header {
+        r.read(1)?; // enable_interintra_compound
+        r.read(1)?; // enable_masked_compound
+        r.read(1)?; // enable_warped_motion
+        r.read(1)?; // enable_dual_filter
+        let enable_order_hint = r.read(1)? == 1;
+        if enable_order_hint {
+            r.read(1)?; // enable_jnt_comp
+            r.read(1)?; // enable_ref_frame_mvs
+        }
+        let seq_force_screen_content_tools = if r.read(1)? == 1 {
+            2 // seq_choose_screen_content_tools
+        } else {
+            r.read(1)?
+        };
+        if seq_force_screen_content_tools > 0 && r.read(1)? == 0 {
+            r.read(1)?; // seq_force_integer_mv
+        }
+        if enable_order_hint {
+            r.read(3)?; // order_hint_bits_minus_1
+        }
+    }
+    r.read(1)?; // enable_superres
+    r.read(1)?; // enable_cdef
+    r.read(1)?; // enable_restoration
+
+    // color_config
+    let high_bitdepth = r.read(1)? as u8;
+    let twelve_bit = if seq_profile == 2 && high_bitdepth == 1 {
+        r.read(1)? as u8
+    } else {
+        0
+    };
+    let mono_chrome = if seq_profile == 1 {
+        0
+    } else {
+        r.read(1)? as u8
+    };
+    let (color_primaries, transfer_characteristics, matrix_coefficients) = if r.read(1)? == 1 {
+        (r.read(8)?, r.read(8)?, r.read(8)?)
+    } else {
+        (2, 2, 2)
+    };
+    let (subsampling_x, subsampling_y, chroma_sample_position) = if mono_chrome == 1 {
+        (1, 1, 0)
+    } else if color_primaries == 1 && transfer_characteristics == 13 && matrix_coefficients == 0 {
+        (0, 0, 0)
+    } else {
+        r.read(1)?; // color_range
+        let (subsampling_x, subsampling_y) = match seq_profile {
+            0 => (1, 1),
+            1 => (0, 0),
+            _ if twelve_bit == 1 => {
+                let subsampling_x = r.read(1)? as u8;
+                let subsampling_y = if subsampling_x == 1 {
+                    r.read(1)? as u8
+                } else {
+                    0
+                };
+                (subsampling_x, subsampling_y)
+            }
+            _ => (1, 0),
+        };
+        let chroma_sample_position = if subsampling_x == 1 && subsampling_y == 1 {
+            r.read(2)? as u8
+        } else {
+            0
+        };
+        (subsampling_x, subsampling_y, chroma_sample_position)
+    };
+
+    let mut record = vec![
+        0x81, // marker, version
+        (seq_profile << 5) | seq_level_idx_0,
+        (seq_tier_0 << 7)
+            | (high_bitdepth << 6)
+            | (twelve_bit << 5)
+            | (mono_chrome << 4)
+            | (subsampling_x << 3)
+            | (subsampling_y << 2)
+            | chroma_sample_position,
+        0, // initial_presentation_delay_present
+    ];
+    record.extend_from_slice(obu);
+
+    Some(record)
+}
+
+fn ebml_id(buf: &mut Vec<u8>, id: u32) {
+    let bytes = id.to_be_bytes();
+    let skip = bytes.iter().take_while(|b| **b == 0).count().min(3);
+    buf.extend_from_slice(&bytes[skip..]);
+}
+
+/// ebml_size writes the shortest variable size integer of size
+fn ebml_size(buf: &mut Vec<u8>, size: u64) {
+    let mut length = 1;
+    while length < 8 && size >= (1u64 << (7 * length)) - 1 {
+        length += 1;
+    }
+    let value = size | (1u64 << (7 * length));
+    buf.extend_from_slice(&value.to_be_bytes()[8 - length..]);
+}
+
+/// ebml_size_8 writes an 8-byte variable size integer of size, which can be overwritten later
+fn ebml_size_8(buf: &mut Vec<u8>, size: u64) {
+    buf.extend_from_slice(&(size | (1u64 << 56)).to_be_bytes());
+}
+
+fn ebml_element(buf: &mut Vec<u8>, id: u32, data: &[u8]) {
+    ebml_id(buf, id);
+    ebml_size(buf, data.len() as u64);
+    buf.extend_from_slice(data);
+}
+
+fn ebml_uint(buf: &mut Vec<u8>, id: u32, value: u64) {
+    let bytes = value.to_be_bytes();
+    let skip = bytes.iter().take_while(|b| **b == 0).count().min(7);
+    ebml_element(buf, id, &bytes[skip..]);
+}
+
+fn ebml_float(buf: &mut Vec<u8>, id: u32, value: f64) {
+    ebml_element(buf, id, &value.to_be_bytes());
+}
+
+fn ebml_string(buf: &mut Vec<u8>, id: u32, value: &str) {
+    ebml_element(buf, id, value.as_bytes());
+}
+
+/// ebml_seek_entry writes a Seek entry of SEEK_ENTRY_SIZE bytes
+fn ebml_seek_entry(buf: &mut Vec<u8>, id: u32, position: u64) {
+    let mut seek_id = vec![];
+    ebml_id(&mut seek_id, id);
+    let mut seek = vec![];
+    ebml_element(&mut seek, WEBM_ID_SEEK_ID, &seek_id);
+    ebml_element(&mut seek, WEBM_ID_SEEK_POSITION, &position.to_be_bytes());
+    ebml_element(buf, WEBM_ID_SEEK, &seek);
+}
+
+/// ebml_void writes a Void element of size bytes, which must be at least 2
+fn ebml_void(buf: &mut Vec<u8>, size: usize) {
+    ebml_id(buf, EBML_ID_VOID);
+    if size - 2 < 0x7f {
+        ebml_size(buf, (size - 2) as u64);
+        buf.resize(buf.len() + size - 2, 0);
+    } else {
+        ebml_size_8(buf, (size - 9) as u64);
+        buf.resize(buf.len() + size - 9, 0);
+    }
+}
@@ -0,0 +1,375 @@
+use super::*;
+use crate::io::webm_reader::read_element_header;
+
+use rtp::header::Header;
+use rtp::packet::Packet;
+use std::io::Cursor;
+
+fn vp8_packet(sequence_number: u16, timestamp: u32, frame: &[u8]) -> Packet {
+    let mut payload = vec![0x10]; // start of partition 0
+    payload.extend_from_slice(frame);
+    Packet {
+        header: Header {
+            payload_type: 96,
+            sequence_number,
+            timestamp,
+            marker: true,
+            ..Default::default()
+        },
+        payload: Bytes::from(payload),
+    }
+}
+
+fn opus_packet(sequence_number: u16, timestamp: u32, frame: &[u8]) -> Packet {
+    Packet {
+        header: Header {
+            payload_type: 111,
+            sequence_number,
+            timestamp,
+            ..Default::default()
+        },
+        payload: Bytes::copy_from_slice(frame),
+    }
+}
+
+fn sample(data: &[u8], origin: SystemTime, packet_timestamp: u32, clock_rate: u32) -> Sample {
+    Sample {
+        data: Bytes::copy_from_slice(data),
+        timestamp: origin + Duration::from_secs_f64(packet_timestamp as f64 / clock_rate as f64),
+        packet_timestamp,
+        ..Default::default()
+    }
+}
+
+/// An element of the segment of a file, with the blocks of a cluster
+struct SegmentElement {
+    id: u32,
+    position: u64,
+    data: Vec<u8>,
+}
+
+/// read_segment returns the top level elements of the segment of a closed file
+fn read_segment(file: &[u8]) -> Vec<SegmentElement> {
+    let mut reader = Cursor::new(file);
+    let (id, size) = read_element_header(&mut reader).unwrap().unwrap();
+    assert_eq!(id, EBML_ID_HEADER);
+    reader.set_position(reader.position() + size.unwrap());
+
+    let (id, size) = read_element_header(&mut reader).unwrap().unwrap();
+    assert_eq!(id, WEBM_ID_SEGMENT);
+    let segment_position = reader.position();
+    assert_eq!(
+        segment_position + size.expect("segment size should be written on close"),
+        file.len() as u64
+    );
+
+    let mut elements = vec![];
+    while (reader.position() as usize) < file.len() {
+        let position = reader.position() - segment_position;
+        let (id, size) = read_element_header(&mut reader).unwrap().unwrap();
+        let size = size.expect("element size should be written on close") as usize;
+        let start = reader.position() as usize;
+        elements.push(SegmentElement {
+            id,
+            position,
+            data: file[start..start + size].to_vec(),
+        });
+        reader.set_position((start + size) as u64);
+    }
+
+    elements
+}
+
+fn children(data: &[u8]) -> Vec<(u32, Vec<u8>)> {
+    let mut reader = Cursor::new(data);
+    let mut children = vec![];
+    while let Some((id, size)) = read_element_header(&mut reader).unwrap() {
+        let start = reader.position() as usize;
+        let end = start + size.unwrap() as usize;
+        children.push((id, data[start..end].to_vec()));
+        reader.set_position(end as u64);
+    }
+    children
+}
+
+fn uint(data: &[u8]) -> u64 {
+    data.iter().fold(0, |value, b| (value << 8) | *b as u64)
+}
+
+#[test]
+fn test_webm_writer_rtp() -> Result<()> {
+    let mut writer = WebmWriter::new(
+        Cursor::new(vec![]),
+        vec![
+            WebmTrack::video(96, WebmCodec::Vp8, 640, 480),
+            WebmTrack::opus(111, 2),
+        ],
+    )?;
+
+    // A frame before the first key frame can't be decoded
+    writer.write_rtp(&vp8_packet(99, 0, &[0x01, 0xff, 0x9d, 0x01]))?;
+    for i in 0..30u16 {
+        let frame = [(i % 10 != 0) as u8, i as u8, 0x9d, 0x01];
+        writer.write_rtp(&vp8_packet(100 + i, 3000 * (i as u32 + 1), &frame))?;
+        writer.write_rtp(&opus_packet(
+            500 + 2 * i,
+            960 * 2 * i as u32,
+            &[0xA0, i as u8],
+        ))?;
+        writer.write_rtp(&opus_packet(
+            501 + 2 * i,
+            960 * (2 * i as u32 + 1),
+            &[0xA1, i as u8],
+        ))?;
+    }
+    writer.close()?;
+    // close is idempotent
+    writer.close()?;
+
+    assert_eq!(
+        writer.write_rtp(&opus_packet(600, 0, &[0xA0])),
+        Err(Error::ErrFileNotOpened)
+    );
+
+    let file = writer.writer.into_inner();
+    let (mut reader, header) = WebmReader::new(Cursor::new(file))?;
+    assert_eq!(header.doc_type, "webm");
+    assert_eq!(header.tracks.len(), 2);
+    assert_eq!(header.tracks[0].codec(), Some(WebmCodec::Vp8));
+    assert_eq!(
+        (header.tracks[0].width, header.tracks[0].height),
+        (640, 480)
+    );
+    assert_eq!(header.tracks[1].codec(), Some(WebmCodec::Opus));
+    assert_eq!(header.tracks[1].channels, 2);
+    assert_eq!(&header.tracks[1].codec_private[..8], ID_PAGE_SIGNATURE);
+    assert!(header.duration.is_some());
+
+    let mut video = vec![];
+    let mut audio = vec![];
+    while let Ok(frame) = reader.parse_next_frame() {
+        if frame.track_number == 1 {
+            video.push(frame);
+        } else {
+            audio.push(frame);
+        }
+    }
+
+    // The last frame of each track waits for a packet of the next one
+    assert_eq!(video.len(), 29);
+    assert_eq!(audio.len(), 59);
+    for (i, frame) in video.iter().enumerate() {
+        assert_eq!(
+            frame.data,
+            Bytes::copy_from_slice(&[(i % 10 != 0) as u8, i as u8, 0x9d, 0x01])
+        );
+        assert_eq!(frame.is_key_frame, i % 10 == 0);
+        assert_eq!(
+            frame.timestamp.as_millis() as i64 - video[0].timestamp.as_millis() as i64,
+            i as i64 * 1000 / 30
+        );
+    }
+    for (i, frame) in audio.iter().enumerate() {
+        assert_eq!(frame.data[1], i as u8 / 2);
+        assert!(frame.is_key_frame);
+        assert_eq!(
+            frame.timestamp.as_millis() - audio[0].timestamp.as_millis(),
+            i as u128 * 20
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_webm_writer_cues_and_interleaving() -> Result<()> {
+    let mut writer = WebmWriter::new(
+        Cursor::new(vec![]),
+        vec![
+            WebmTrack::video(96, WebmCodec::Vp8, 320, 240),
+            WebmTrack::opus(111, 1),
+        ],
+    )?;
+
+    // The audio arrives 500ms late, and the tracks start 100ms apart
+    let origin = SystemTime::now();
+    let audio_origin = origin + Duration::from_millis(100);
+    let mut audio_timestamp = 0;
+    for i in 0..60u32 {
+        let frame = if i % 20 == 0 { [0x00] } else { [0x01] };
+        writer.write_sample(0, &sample(&frame, origin, 3000 * i, 90000))?;
+        while i >= 15 && audio_timestamp < 48 * (i * 100 / 3 - 500) {
+            writer.write_sample(1, &sample(&[0xA0], audio_origin, audio_timestamp, 48000))?;
+            audio_timestamp += 960;
+        }
+    }
+    assert_eq!(
+        writer.write_sample(2, &sample(&[0xA0], origin, 0, 48000)),
+        Err(Error::ErrWebmUnknownTrack)
+    );
+    writer.close()?;
+
+    let file = writer.writer.into_inner();
+    let elements = read_segment(&file);
+
+    let clusters: Vec<&SegmentElement> = elements
+        .iter()
+        .filter(|e| e.id == WEBM_ID_CLUSTER)
+        .collect();
+    assert_eq!(clusters.len(), 3, "a cluster starts at each key frame");
+
+    // The blocks are interleaved in time order
+    let mut last_time = 0;
+    let mut blocks = 0;
+    for cluster in &clusters {
+        let children = children(&cluster.data);
+        assert_eq!(children[0].0, WEBM_ID_TIMECODE);
+        let timecode = uint(&children[0].1);
+        for (id, data) in &children[1..] {
+            assert_eq!(*id, WEBM_ID_SIMPLE_BLOCK);
+            let time = timecode + i16::from_be_bytes([data[1], data[2]]) as u64;
+            assert!(time >= last_time, "blocks must be in time order");
+            last_time = time;
+            blocks += 1;
+        }
+    }
+    assert_eq!(blocks, 60 + audio_timestamp as usize / 960);
+
+    // The SeekHead has the position of the cues, which index the clusters
+    let seek_head = elements.iter().find(|e| e.id == WEBM_ID_SEEK_HEAD).unwrap();
+    let cues_position = children(&seek_head.data)
+        .into_iter()
+        .filter(|(id, _)| *id == WEBM_ID_SEEK)
+        .map(|(_, seek)| children(&seek))
+        .find(|seek| seek[0].1 == [0x1C, 0x53, 0xBB, 0x6B])
+        .map(|seek| uint(&seek[1].1))
+        .expect("SeekHead should have the position of the cues");
+    let cues = elements
+        .iter()
+        .find(|e| e.position == cues_position)
+        .expect("cues should be at their position");
+    assert_eq!(cues.id, WEBM_ID_CUES);
+
+    let cue_points = children(&cues.data);
+    assert_eq!(cue_points.len(), clusters.len());
+    for ((id, cue_point), cluster) in cue_points.iter().zip(clusters.iter()) {
+        assert_eq!(*id, WEBM_ID_CUE_POINT);
+        let cue_point = children(cue_point);
+        assert_eq!(cue_point[0].0, WEBM_ID_CUE_TIME);
+        assert_eq!(
+            uint(&cue_point[0].1),
+            uint(&children(&cluster.data)[0].1),
+            "a cue has the time of its cluster"
+        );
+        let positions = children(&cue_point[1].1);
+        assert_eq!(uint(&positions[0].1), 1);
+        assert_eq!(uint(&positions[1].1), cluster.position);
+    }
+
+    let (mut reader, header) = WebmReader::new(Cursor::new(file))?;
+    assert!(header.duration.unwrap() >= Duration::from_millis(last_time));
+    let mut audio_start = None;
+    while let Ok(frame) = reader.parse_next_frame() {
+        if frame.track_number == 2 && audio_start.is_none() {
+            audio_start = Some(frame.timestamp);
+        }
+    }
+    let audio_start = audio_start.unwrap().as_millis();
+    assert!(
+        (99..=101).contains(&audio_start),
+        "audio should start 100ms after video, started at {audio_start}ms"
+    );
+
+    Ok(())
+}
+
+struct BitWriter {
+    data: Vec<u8>,
+    bits: usize,
+}
+
+impl BitWriter {
+    fn write(&mut self, bits: usize, value: u64) {
+        for i in (0..bits).rev() {
+            if self.bits % 8 == 0 {
+                self.data.push(0);
+            }
+            let bit = ((value >> i) & 1) as u8;
+            *self.data.last_mut().unwrap() |= bit << (7 - self.bits % 8);
+            self.bits += 1;
+        }
+    }
+}
+
+#[test]
+fn test_webm_writer_av1_codec_private() -> Result<()> {
+    let mut w = BitWriter {
+        data: vec![],
+        bits: 0,
+    };
+    w.write(3, 0); // seq_profile
+    w.write(1, 0); // still_picture
+    w.write(1, 0); // reduced_still_picture_header
+    w.write(1, 0); // timing_info_present_flag
+    w.write(1, 0); // initial_display_delay_present_flag
+    w.write(5, 0); // operating_points_cnt_minus_1
+    w.write(12, 0); // operating_point_idc
+    w.write(5, 8); // seq_level_idx
+    w.write(1, 1); // seq_tier
+    w.write(4, 10); // frame_width_bits_minus_1
+    w.write(4, 10); // frame_height_bits_minus_1
+    w.write(11, 639); // max_frame_width_minus_1
+    w.write(11, 479); // max_frame_height_minus_1
+    w.write(1, 0); // frame_id_numbers_present_flag
+    w.write(3, 0); // use_128x128_superblock, enable_filter_intra, enable_intra_edge_filter
+    w.write(4, 0); // enable_interintra_compound .. enable_dual_filter
+    w.write(1, 1); // enable_order_hint
+    w.write(2, 0); // enable_jnt_comp, enable_ref_frame_mvs
+    w.write(1, 1); // seq_choose_screen_content_tools
+    w.write(1, 1); // seq_choose_integer_mv
+    w.write(3, 6); // order_hint_bits_minus_1
+    w.write(3, 0b011); // enable_superres, enable_cdef, enable_restoration
+    w.write(1, 0); // high_bitdepth
+    w.write(1, 0); // mono_chrome
+    w.write(1, 0); // color_description_present_flag
+    w.write(1, 0); // color_range
+    w.write(2, 1); // chroma_sample_position
+    w.write(1, 0); // film_grain_params_present
+    w.write(1, 1); // trailing_one_bit
+    let mut sequence_header = vec![0x0A, w.data.len() as u8];
+    sequence_header.extend_from_slice(&w.data);
+    let mut key_frame = sequence_header.clone();
+    key_frame.extend_from_slice(&[0x32, 0x01, 0xAA]);
+
+    let mut writer = WebmWriter::new(
+        Cursor::new(vec![]),
+        vec![WebmTrack::video(45, WebmCodec::Av1, 640, 480)],
+    )?;
+    let origin = SystemTime::now();
+    writer.write_sample(0, &sample(&[0x32, 0x01, 0xBB], origin, 0, 90000))?;
+    writer.write_sample(0, &sample(&key_frame, origin, 3000, 90000))?;
+    writer.write_sample(0, &sample(&[0x32, 0x01, 0xCC], origin, 6000, 90000))?;
+    writer.close()?;
+
+    let file = writer.writer.into_inner();
+    let (mut reader, header) = WebmReader::new(Cursor::new(file))?;
+    assert_eq!(header.tracks[0].codec(), Some(WebmCodec::Av1));
+    let mut codec_private = vec![0x81, 0x08, 0x8d, 0x00];
+    codec_private.extend_from_slice(&sequence_header);
+    assert_eq!(header.tracks[0].codec_private, Bytes::from(codec_private));
+
+    let frame = reader.parse_next_frame()?;
+    assert!(frame.is_key_frame);
+    assert_eq!(frame.data, Bytes::from(key_frame));
+    let frame = reader.parse_next_frame()?;
+    assert!(!frame.is_key_frame);
+    assert_eq!(reader.parse_next_frame(), Err(Error::ErrIoEOF));
+
+    Ok(())
+}
+
+#[test]
+fn test_webm_writer_no_tracks() {
+    let result = WebmWriter::new(Cursor::new(vec![]), vec![]);
+    assert!(matches!(result, Err(Error::ErrWebmMissingTracks)));
+}
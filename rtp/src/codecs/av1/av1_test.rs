@@ -0,0 +1,95 @@
+use super::*;
+
+#[test]
+fn test_av1_unmarshal() -> Result<()> {
+    let mut pck = Av1Packet::default();
+
+    // Empty packet
+    let result = pck.depacketize(&Bytes::from_static(&[]));
+    assert_eq!(result, Err(Error::ErrShortPacket));
+
+    // Aggregation header only
+    let result = pck.depacketize(&Bytes::from_static(&[0x00]));
+    assert_eq!(result, Err(Error::ErrShortPacket));
+
+    // Two OBUs with length fields: a sequence header and a frame
+    let raw_bytes = Bytes::from_static(&[0x08, 0x03, 0x08, 0xaa, 0xbb, 0x02, 0x30, 0xcc]);
+    let payload = pck.depacketize(&raw_bytes)?;
+    assert!(pck.n);
+    assert_eq!(
+        payload,
+        Bytes::from_static(&[0x0a, 0x02, 0xaa, 0xbb, 0x32, 0x01, 0xcc]),
+        "OBUs must get their size field"
+    );
+
+    // Two OBUs, the last one without length field (W=2)
+    let raw_bytes = Bytes::from_static(&[0x20, 0x02, 0x30, 0xcc, 0x30, 0xdd, 0xee]);
+    let payload = pck.depacketize(&raw_bytes)?;
+    assert_eq!(pck.w, 2);
+    assert_eq!(
+        payload,
+        Bytes::from_static(&[0x32, 0x01, 0xcc, 0x32, 0x02, 0xdd, 0xee])
+    );
+
+    // OBU that already has its size field and an extension header
+    let raw_bytes = Bytes::from_static(&[0x10, 0x36, 0x28, 0x01, 0xcc]);
+    let payload = pck.depacketize(&raw_bytes)?;
+    assert_eq!(payload, Bytes::from_static(&[0x36, 0x28, 0x01, 0xcc]));
+
+    // Temporal delimiters are dropped
+    let raw_bytes = Bytes::from_static(&[0x00, 0x01, 0x10, 0x02, 0x30, 0xcc]);
+    let payload = pck.depacketize(&raw_bytes)?;
+    assert_eq!(payload, Bytes::from_static(&[0x32, 0x01, 0xcc]));
+
+    // Element longer than the packet
+    let raw_bytes = Bytes::from_static(&[0x00, 0x05, 0x30, 0xcc]);
+    let result = pck.depacketize(&raw_bytes);
+    assert_eq!(result, Err(Error::ErrAv1CorruptedPacket));
+
+    Ok(())
+}
+
+#[test]
+fn test_av1_fragmented_obu() -> Result<()> {
+    let mut pck = Av1Packet::default();
+
+    // The frame OBU starts in the first packet (Y=1)
+    let payload = pck.depacketize(&Bytes::from_static(&[0x50, 0x30, 0x01, 0x02]))?;
+    assert!(payload.is_empty(), "a fragment must be buffered");
+
+    // and continues in the middle of the second one (Z=1, Y=1)
+    let payload = pck.depacketize(&Bytes::from_static(&[0xd0, 0x03, 0x04]))?;
+    assert!(payload.is_empty(), "a fragment must be buffered");
+
+    // and ends in the third one (Z=1), which carries another OBU
+    let payload = pck.depacketize(&Bytes::from_static(&[0xa0, 0x01, 0x05, 0x30, 0x06]))?;
+    assert_eq!(
+        payload,
+        Bytes::from_static(&[0x32, 0x05, 0x01, 0x02, 0x03, 0x04, 0x05, 0x32, 0x01, 0x06])
+    );
+
+    // A continuation whose start was lost is dropped
+    let mut pck = Av1Packet::default();
+    let payload = pck.depacketize(&Bytes::from_static(&[0xa0, 0x01, 0x05, 0x30, 0x06]))?;
+    assert_eq!(payload, Bytes::from_static(&[0x32, 0x01, 0x06]));
+
+    Ok(())
+}
+
+#[test]
+fn test_av1_partition_head_checker() {
+    let pck = Av1Packet::default();
+
+    assert!(
+        !pck.is_partition_head(&Bytes::new()),
+        "empty payload must not be a partition head"
+    );
+    assert!(
+        pck.is_partition_head(&Bytes::from_static(&[0x18, 0x30])),
+        "packet without Z must be a partition head"
+    );
+    assert!(
+        !pck.is_partition_head(&Bytes::from_static(&[0x90, 0x30])),
+        "packet with Z must not be a partition head"
+    );
+}
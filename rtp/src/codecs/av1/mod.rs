@@ -0,0 +1,177 @@
+#[cfg(test)]
+mod av1_test;
+
+use crate::{
+    error::{Error, Result},
+    packetizer::Depacketizer,
+};
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+const AV1_Z_MASK: u8 = 0x80;
+const AV1_Y_MASK: u8 = 0x40;
+const AV1_W_MASK: u8 = 0x30;
+const AV1_N_MASK: u8 = 0x08;
+
+const OBU_HAS_EXTENSION_MASK: u8 = 0x04;
+const OBU_HAS_SIZE_MASK: u8 = 0x02;
+
+const OBU_TYPE_TEMPORAL_DELIMITER: u8 = 2;
+const OBU_TYPE_TILE_LIST: u8 = 8;
+const OBU_TYPE_PADDING: u8 = 15;
+
+/// Av1Packet represents the AV1 aggregation header that is stored in the payload of an RTP Packet.
+/// It depacketizes the OBU elements of a payload into the low overhead bitstream format, in which
+/// every OBU has its size field, joining the OBUs that are fragmented over several packets.
+/// <https://aomediacodec.github.io/av1-rtp-spec/>
+#[derive(PartialEq, Eq, Debug, Default, Clone)]
+pub struct Av1Packet {
+    /// the first OBU element is the continuation of an OBU of the previous packet
+    pub z: bool,
+    /// the last OBU element continues in the next packet
+    pub y: bool,
+    /// the number of OBU elements, 0 if each element has its length field
+    pub w: u8,
+    /// the packet is the first of a coded video sequence
+    pub n: bool,
+
+    /// the start of an OBU whose last fragment has not arrived yet
+    fragment: BytesMut,
+}
+
+impl Depacketizer for Av1Packet {
+    /// depacketize parses the passed byte slice and stores the result in the Av1Packet this method is called upon
+    fn depacketize(&mut self, packet: &Bytes) -> Result<Bytes> {
+        if packet.len() < 2 {
+            return Err(Error::ErrShortPacket);
+        }
+
+        let b = packet[0];
+        self.z = (b & AV1_Z_MASK) != 0;
+        self.y = (b & AV1_Y_MASK) != 0;
+        self.w = (b & AV1_W_MASK) >> 4;
+        self.n = (b & AV1_N_MASK) != 0;
+
+        // The start of the OBU that this packet continues was lost, or the end of the
+        // buffered one was.
+        let lost_start = self.z && self.fragment.is_empty();
+        if !self.z {
+            self.fragment.clear();
+        }
+
+        let mut payload = BytesMut::new();
+        let mut index = 1;
+        let mut count = 0;
+        while index < packet.len() {
+            count += 1;
+            let length = if self.w != 0 && count == self.w {
+                packet.len() - index
+            } else {
+                let (length, n) = read_leb128(&packet[index..])?;
+                index += n;
+                length as usize
+            };
+            if length > packet.len() - index {
+                return Err(Error::ErrAv1CorruptedPacket);
+            }
+
+            let element = packet.slice(index..index + length);
+            index += length;
+            let is_first = count == 1;
+            let is_last = index == packet.len();
+
+            if is_first && self.z {
+                if lost_start {
+                    continue;
+                }
+                self.fragment.extend_from_slice(&element);
+                if is_last && self.y {
+                    continue;
+                }
+                let obu = self.fragment.split().freeze();
+                write_obu(&mut payload, &obu)?;
+            } else if is_last && self.y {
+                self.fragment.extend_from_slice(&element);
+            } else {
+                write_obu(&mut payload, &element)?;
+            }
+        }
+
+        Ok(payload.freeze())
+    }
+
+    /// is_partition_head checks whether if this is a head of the AV1 partition
+    fn is_partition_head(&self, payload: &Bytes) -> bool {
+        if payload.is_empty() {
+            false
+        } else {
+            (payload[0] & AV1_Z_MASK) == 0
+        }
+    }
+
+    fn is_partition_tail(&self, marker: bool, _payload: &Bytes) -> bool {
+        marker
+    }
+}
+
+/// Appends an OBU to the payload with its size field. Temporal delimiters, tile lists and
+/// padding are dropped, as they are not transmitted in the bitstream.
+fn write_obu(payload: &mut BytesMut, obu: &Bytes) -> Result<()> {
+    if obu.is_empty() {
+        return Ok(());
+    }
+
+    let header = obu[0];
+    let obu_type = (header >> 3) & 0x0f;
+    if obu_type == OBU_TYPE_TEMPORAL_DELIMITER
+        || obu_type == OBU_TYPE_TILE_LIST
+        || obu_type == OBU_TYPE_PADDING
+    {
+        return Ok(());
+    }
+
+    let header_size = if header & OBU_HAS_EXTENSION_MASK != 0 {
+        2
+    } else {
+        1
+    };
+    if obu.len() < header_size {
+        return Err(Error::ErrAv1CorruptedPacket);
+    }
+
+    if header & OBU_HAS_SIZE_MASK != 0 {
+        payload.extend_from_slice(obu);
+    } else {
+        payload.put_u8(header | OBU_HAS_SIZE_MASK);
+        payload.extend_from_slice(&obu[1..header_size]);
+        write_leb128(payload, (obu.len() - header_size) as u64);
+        payload.extend_from_slice(&obu[header_size..]);
+    }
+
+    Ok(())
+}
+
+/// Reads an unsigned LEB128 value and returns it with the number of bytes it takes.
+fn read_leb128(b: &[u8]) -> Result<(u64, usize)> {
+    let mut value = 0u64;
+    for (i, byte) in b.iter().take(8).enumerate() {
+        value |= ((byte & 0x7f) as u64) << (i * 7);
+        if byte & 0x80 == 0 {
+            return Ok((value, i + 1));
+        }
+    }
+
+    Err(Error::ErrAv1CorruptedPacket)
+}
+
+fn write_leb128(payload: &mut BytesMut, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            payload.put_u8(byte);
+            return;
+        }
+        payload.put_u8(byte | 0x80);
+    }
+}
@@ -45,6 +45,9 @@ pub enum Error {
     #[error("invalid h265 packet type")]
     ErrInvalidH265PacketType,
 
+    #[error("corrupted av1 packet")]
+    ErrAv1CorruptedPacket,
+
     #[error("extension_payload must be in 32-bit words")]
     HeaderExtensionPayloadNot32BitWords,
     #[error("audio level overflow")]
@@ -0,0 +1,209 @@
+use super::*;
+use crate::description::media::MediaDescription;
+use crate::description::session::SessionDescription;
+
+use std::io::Cursor;
+
+const CANONICAL_ATTRIBUTES: &[&str] = &[
+    "rtpmap:96 VP8/90000",
+    "rtpmap:111 opus/48000/2",
+    "fmtp:111 minptime=10;useinbandfec=1",
+    "rtcp-fb:96 nack",
+    "rtcp-fb:96 nack pli",
+    "rtcp-fb:* transport-cc",
+    "ssrc:2231627014 cname:4TOk42mSjXCkVIa6",
+    "ssrc:2231627014 msid:stream track",
+    "ssrc-group:FID 2231627014 632943048",
+    "rid:hi send pt=96,97;max-width=1280;max-height=720",
+    "rid:lo send",
+    "simulcast:send hi;mid,~mid2;lo recv r0",
+    "candidate:1 1 udp 2130706431 192.168.1.2 54321 typ host generation 0",
+    "candidate:2 1 udp 1694498815 203.0.113.1 51000 typ srflx raddr 192.168.1.2 rport 54321",
+    "fingerprint:sha-256 0F:74:31:25:CB:A2:13:EC:28:6F:6D:2C:61:FF:5D:C2:BC:B9:DB:3D:98:14:8D:1A:BB:EA:33:0C:A4:60:A8:8E",
+    "group:BUNDLE 0 1",
+    "extmap:1 http://www.webrtc.org/experiments/rtp-hdrext/abs-send-time",
+    "sendrecv",
+    "mid:0",
+    "x-custom-attribute:some value",
+];
+
+fn attribute_from_str(s: &str) -> Attribute {
+    match s.split_once(':') {
+        Some((key, value)) => Attribute::new(key.to_owned(), Some(value.to_owned())),
+        None => Attribute::new(s.to_owned(), None),
+    }
+}
+
+#[test]
+fn test_typed_attribute_round_trip() -> Result<()> {
+    for s in CANONICAL_ATTRIBUTES {
+        let attr = attribute_from_str(s);
+        let typed = TypedAttribute::unmarshal(&attr)?;
+        assert_eq!(typed.to_string(), *s, "round trip of {s}");
+        assert_eq!(typed.key(), attr.key, "key of {s}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_typed_attribute_kind() -> Result<()> {
+    let typed = TypedAttribute::unmarshal(&attribute_from_str("rtpmap:111 opus/48000/2"))?;
+    if let TypedAttribute::RtpMap(rtpmap) = typed {
+        assert_eq!(
+            rtpmap,
+            RtpMap {
+                payload_type: 111,
+                encoding_name: "opus".to_owned(),
+                clock_rate: 48000,
+                encoding_parameters: Some("2".to_owned()),
+            }
+        );
+    } else {
+        panic!("expected rtpmap, got {typed:?}");
+    }
+
+    let typed = TypedAttribute::unmarshal(&attribute_from_str("mid:0"))?;
+    assert!(matches!(typed, TypedAttribute::Unknown(_)), "{typed:?}");
+
+    let typed = TypedAttribute::unmarshal(&attribute_from_str("recvonly"))?;
+    assert!(
+        matches!(typed, TypedAttribute::Direction(Direction::RecvOnly)),
+        "{typed:?}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_typed_attribute_malformed() {
+    let malformed = vec![
+        "rtpmap:96",
+        "rtpmap:abc VP8/90000",
+        "rtpmap:96 VP8",
+        "fmtp:96",
+        "rtcp-fb:96",
+        "ssrc:notanumber cname:foo",
+        "ssrc-group:FID 1 x",
+        "rid:hi sideways",
+        "rid:hi send pt=x",
+        "simulcast:send",
+        "simulcast:send a send b",
+        "candidate:1 1 udp 2130706431 192.168.1.2 54321 host",
+        "candidate:1 1 udp 2130706431 192.168.1.2 54321 typ host generation",
+        "fingerprint:sha-256",
+        "group:",
+        "extmap:300 http://example.com",
+    ];
+
+    for s in malformed {
+        let attr = attribute_from_str(s);
+        assert!(
+            TypedAttribute::unmarshal(&attr).is_err(),
+            "{s} should fail to parse"
+        );
+    }
+}
+
+#[test]
+fn test_typed_attribute_helpers() -> Result<()> {
+    let fmtp = Fmtp::unmarshal("102 level-asymmetry-allowed=1;packetization-mode=1")?;
+    assert_eq!(fmtp.parameter("packetization-mode"), Some("1"));
+    assert_eq!(fmtp.parameter("profile-level-id"), None);
+
+    let rid = Rid::unmarshal("hi send pt=96;max-width=1280")?;
+    assert_eq!(rid.payload_types, vec![96]);
+    assert_eq!(rid.restriction("max-width"), Some("1280"));
+
+    let candidate = Candidate::unmarshal(
+        "1 1 tcp 1518280447 192.168.1.2 9 typ host tcptype active generation 0",
+    )?;
+    assert_eq!(candidate.extension("tcptype"), Some("active"));
+    assert_eq!(candidate.related_address, None);
+
+    let simulcast = Simulcast::unmarshal("recv a;~b")?;
+    assert!(simulcast.send.is_empty());
+    assert_eq!(
+        simulcast.recv,
+        vec![
+            vec![SimulcastRid {
+                id: "a".to_owned(),
+                paused: false
+            }],
+            vec![SimulcastRid {
+                id: "b".to_owned(),
+                paused: true
+            }],
+        ]
+    );
+
+    Ok(())
+}
+
+const TYPED_SESSION: &str = "v=0\r\n\
+o=- 4596489990601351948 2 IN IP4 127.0.0.1\r\n\
+s=-\r\n\
+t=0 0\r\n\
+a=group:BUNDLE 0\r\n\
+a=fingerprint:sha-256 AB:CD\r\n\
+m=video 9 UDP/TLS/RTP/SAVPF 96 97\r\n\
+c=IN IP4 0.0.0.0\r\n\
+a=mid:0\r\n\
+a=rtpmap:96 VP8/90000\r\n\
+a=rtcp-fb:96 nack\r\n\
+a=rtpmap:97 rtx/90000\r\n\
+a=fmtp:97 apt=96\r\n\
+a=ssrc-group:FID 1 2\r\n\
+a=ssrc:1 cname:foo\r\n\
+a=ssrc:2 cname:foo\r\n\
+a=rid:hi send\r\n\
+a=rid:lo send\r\n\
+a=simulcast:send hi;lo\r\n\
+a=candidate:1 1 udp 2130706431 192.168.1.2 54321 typ host\r\n";
+
+#[test]
+fn test_typed_accessors() -> Result<()> {
+    let mut reader = Cursor::new(TYPED_SESSION.as_bytes());
+    let sd = SessionDescription::unmarshal(&mut reader)?;
+
+    let bundle = sd.bundle_group()?.expect("bundle group");
+    assert_eq!(bundle.identification_tags, vec!["0".to_owned()]);
+    assert_eq!(
+        sd.fingerprint()?,
+        Some(Fingerprint {
+            algorithm: "sha-256".to_owned(),
+            value: "AB:CD".to_owned(),
+        })
+    );
+
+    let m = &sd.media_descriptions[0];
+    assert_eq!(m.rtp_maps()?.len(), 2);
+    assert_eq!(m.fmtps()?[0].parameter("apt"), Some("96"));
+    assert_eq!(m.rtcp_fbs()?[0].typ, "nack");
+    assert_eq!(m.ssrc_groups()?[0].ssrcs, vec![1, 2]);
+    assert_eq!(m.ssrcs()?.len(), 2);
+    let rids: Vec<String> = m.rids()?.into_iter().map(|r| r.id).collect();
+    assert_eq!(rids, vec!["hi".to_owned(), "lo".to_owned()]);
+    assert_eq!(m.simulcast()?.expect("simulcast").send.len(), 2);
+    assert_eq!(m.candidates()?[0].port, 54321);
+    assert_eq!(m.fingerprint()?, None);
+    assert_eq!(m.typed_attributes()?.len(), m.attributes.len());
+
+    let md = MediaDescription::default()
+        .with_typed_attribute(TypedAttribute::Rid(Rid {
+            id: "f".to_owned(),
+            direction: RidDirection::Send,
+            ..Default::default()
+        }))
+        .with_typed_attribute(TypedAttribute::Simulcast(Simulcast {
+            send: vec![vec![SimulcastRid {
+                id: "f".to_owned(),
+                paused: false,
+            }]],
+            recv: vec![],
+        }));
+    assert_eq!(md.attribute("rid"), Some(Some("f send")));
+    assert_eq!(md.attribute("simulcast"), Some(Some("send f")));
+
+    Ok(())
+}
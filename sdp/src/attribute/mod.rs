@@ -0,0 +1,796 @@
+#[cfg(test)]
+mod attribute_test;
+
+use super::direction::*;
+use super::error::{Error, Result};
+use super::extmap::*;
+use crate::description::common::*;
+use crate::description::session::*;
+
+use std::fmt;
+use std::io;
+
+/// RtpMap describes the "a=rtpmap" attribute, which maps an RTP payload type
+/// to an encoding name and clock rate.
+///
+/// `a=rtpmap:<payload type> <encoding name>/<clock rate>[/<encoding parameters>]`
+///
+/// <https://tools.ietf.org/html/rfc4566#section-6>
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RtpMap {
+    pub payload_type: u8,
+    pub encoding_name: String,
+    pub clock_rate: u32,
+    pub encoding_parameters: Option<String>,
+}
+
+impl fmt::Display for RtpMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}/{}",
+            self.payload_type, self.encoding_name, self.clock_rate
+        )?;
+        if let Some(encoding_parameters) = &self.encoding_parameters {
+            write!(f, "/{encoding_parameters}")?;
+        }
+        Ok(())
+    }
+}
+
+impl RtpMap {
+    /// unmarshal parses the value of an "a=rtpmap" attribute.
+    pub fn unmarshal(value: &str) -> Result<Self> {
+        let (payload_type, encoding) = split_first_token(ATTR_KEY_RTPMAP, value)?;
+        let payload_type = parse_payload_type(ATTR_KEY_RTPMAP, payload_type)?;
+
+        let mut split = encoding.splitn(3, '/');
+        let encoding_name = match split.next() {
+            Some(name) if !name.is_empty() => name.to_owned(),
+            _ => return Err(invalid_attribute(ATTR_KEY_RTPMAP, value)),
+        };
+        let clock_rate = match split.next() {
+            Some(clock_rate) => clock_rate
+                .parse::<u32>()
+                .map_err(|_| invalid_attribute(ATTR_KEY_RTPMAP, value))?,
+            None => return Err(invalid_attribute(ATTR_KEY_RTPMAP, value)),
+        };
+        let encoding_parameters = split.next().map(|s| s.to_owned());
+
+        Ok(RtpMap {
+            payload_type,
+            encoding_name,
+            clock_rate,
+            encoding_parameters,
+        })
+    }
+}
+
+/// Fmtp describes the "a=fmtp" attribute, which carries format specific
+/// parameters of a payload type.
+///
+/// `a=fmtp:<format> <format specific parameters>`
+///
+/// <https://tools.ietf.org/html/rfc4566#section-6>
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Fmtp {
+    pub payload_type: u8,
+    pub parameters: String,
+}
+
+impl fmt::Display for Fmtp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.payload_type, self.parameters)
+    }
+}
+
+impl Fmtp {
+    /// unmarshal parses the value of an "a=fmtp" attribute.
+    pub fn unmarshal(value: &str) -> Result<Self> {
+        let (payload_type, parameters) = split_first_token(ATTR_KEY_FMTP, value)?;
+
+        Ok(Fmtp {
+            payload_type: parse_payload_type(ATTR_KEY_FMTP, payload_type)?,
+            parameters: parameters.to_owned(),
+        })
+    }
+
+    /// parameter returns the value of a `key=value` pair of the format
+    /// specific parameters, if it exists.
+    pub fn parameter(&self, key: &str) -> Option<&str> {
+        self.parameters.split(';').find_map(|p| {
+            let mut kv = p.trim().splitn(2, '=');
+            match (kv.next(), kv.next()) {
+                (Some(k), Some(v)) if k.eq_ignore_ascii_case(key) => Some(v),
+                _ => None,
+            }
+        })
+    }
+}
+
+/// RtcpFb describes the "a=rtcp-fb" attribute, which enables an RTCP feedback
+/// message for a payload type.
+///
+/// `a=rtcp-fb:<payload type> <RTCP feedback type> [<RTCP feedback parameter>]`
+///
+/// <https://tools.ietf.org/html/rfc4585#section-4.2>
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RtcpFb {
+    /// payload_type is None for the wildcard payload type "*".
+    pub payload_type: Option<u8>,
+    pub typ: String,
+    pub parameter: Option<String>,
+}
+
+impl fmt::Display for RtcpFb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(payload_type) = self.payload_type {
+            write!(f, "{} {}", payload_type, self.typ)?;
+        } else {
+            write!(f, "* {}", self.typ)?;
+        }
+        if let Some(parameter) = &self.parameter {
+            write!(f, " {parameter}")?;
+        }
+        Ok(())
+    }
+}
+
+impl RtcpFb {
+    /// unmarshal parses the value of an "a=rtcp-fb" attribute.
+    pub fn unmarshal(value: &str) -> Result<Self> {
+        let (payload_type, feedback) = split_first_token(ATTR_KEY_RTCP_FB, value)?;
+        let payload_type = if payload_type == "*" {
+            None
+        } else {
+            Some(parse_payload_type(ATTR_KEY_RTCP_FB, payload_type)?)
+        };
+
+        let mut split = feedback.splitn(2, ' ');
+        let typ = match split.next() {
+            Some(typ) if !typ.is_empty() => typ.to_owned(),
+            _ => return Err(invalid_attribute(ATTR_KEY_RTCP_FB, value)),
+        };
+        let parameter = split.next().map(|s| s.to_owned());
+
+        Ok(RtcpFb {
+            payload_type,
+            typ,
+            parameter,
+        })
+    }
+}
+
+/// Ssrc describes the "a=ssrc" attribute, which conveys a source-level
+/// attribute of a media source.
+///
+/// `a=ssrc:<ssrc-id> <attribute>[:<value>]`
+///
+/// <https://tools.ietf.org/html/rfc5576#section-4.1>
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Ssrc {
+    pub ssrc: u32,
+    pub attribute: String,
+    pub value: Option<String>,
+}
+
+impl fmt::Display for Ssrc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(value) = &self.value {
+            write!(f, "{} {}:{}", self.ssrc, self.attribute, value)
+        } else {
+            write!(f, "{} {}", self.ssrc, self.attribute)
+        }
+    }
+}
+
+impl Ssrc {
+    /// unmarshal parses the value of an "a=ssrc" attribute.
+    pub fn unmarshal(value: &str) -> Result<Self> {
+        let (ssrc, source_attribute) = split_first_token(ATTR_KEY_SSRC, value)?;
+        let ssrc = ssrc
+            .parse::<u32>()
+            .map_err(|_| invalid_attribute(ATTR_KEY_SSRC, value))?;
+
+        let mut split = source_attribute.splitn(2, ':');
+        let attribute = match split.next() {
+            Some(attribute) if !attribute.is_empty() => attribute.to_owned(),
+            _ => return Err(invalid_attribute(ATTR_KEY_SSRC, value)),
+        };
+
+        Ok(Ssrc {
+            ssrc,
+            attribute,
+            value: split.next().map(|s| s.to_owned()),
+        })
+    }
+}
+
+/// SsrcGroup describes the "a=ssrc-group" attribute, which expresses a
+/// relationship among several media sources.
+///
+/// `a=ssrc-group:<semantics> <ssrc-id> ...`
+///
+/// <https://tools.ietf.org/html/rfc5576#section-4.2>
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SsrcGroup {
+    pub semantics: String,
+    pub ssrcs: Vec<u32>,
+}
+
+impl fmt::Display for SsrcGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.semantics)?;
+        for ssrc in &self.ssrcs {
+            write!(f, " {ssrc}")?;
+        }
+        Ok(())
+    }
+}
+
+impl SsrcGroup {
+    /// unmarshal parses the value of an "a=ssrc-group" attribute.
+    pub fn unmarshal(value: &str) -> Result<Self> {
+        let mut split = value.split_whitespace();
+        let semantics = match split.next() {
+            Some(semantics) => semantics.to_owned(),
+            None => return Err(invalid_attribute(ATTR_KEY_SSRCGROUP, value)),
+        };
+        let ssrcs = split
+            .map(|ssrc| ssrc.parse::<u32>())
+            .collect::<std::result::Result<Vec<u32>, _>>()
+            .map_err(|_| invalid_attribute(ATTR_KEY_SSRCGROUP, value))?;
+
+        Ok(SsrcGroup { semantics, ssrcs })
+    }
+}
+
+/// RidDirection is the direction of an RTP stream identified by an "a=rid" attribute.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RidDirection {
+    #[default]
+    Send,
+    Recv,
+}
+
+const RID_DIRECTION_SEND_STR: &str = "send";
+const RID_DIRECTION_RECV_STR: &str = "recv";
+
+impl fmt::Display for RidDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            RidDirection::Send => RID_DIRECTION_SEND_STR,
+            RidDirection::Recv => RID_DIRECTION_RECV_STR,
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// Rid describes the "a=rid" attribute, which identifies an RTP stream and
+/// its restrictions.
+///
+/// `a=rid:<rid-id> <direction> [pt=<fmt-list>;]<restriction>=<value>...`
+///
+/// <https://tools.ietf.org/html/rfc8851#section-4>
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Rid {
+    pub id: String,
+    pub direction: RidDirection,
+    pub payload_types: Vec<u8>,
+    pub restrictions: Vec<(String, String)>,
+}
+
+impl fmt::Display for Rid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.id, self.direction)?;
+
+        let mut params = vec![];
+        if !self.payload_types.is_empty() {
+            let pts: Vec<String> = self.payload_types.iter().map(|pt| pt.to_string()).collect();
+            params.push(format!("pt={}", pts.join(",")));
+        }
+        for (key, value) in &self.restrictions {
+            if value.is_empty() {
+                params.push(key.clone());
+            } else {
+                params.push(format!("{key}={value}"));
+            }
+        }
+        if !params.is_empty() {
+            write!(f, " {}", params.join(";"))?;
+        }
+        Ok(())
+    }
+}
+
+impl Rid {
+    /// unmarshal parses the value of an "a=rid" attribute.
+    pub fn unmarshal(value: &str) -> Result<Self> {
+        let mut split = value.split_whitespace();
+        let (id, direction) = match (split.next(), split.next()) {
+            (Some(id), Some(direction)) => (id.to_owned(), direction),
+            _ => return Err(invalid_attribute(ATTR_KEY_RID, value)),
+        };
+        let direction = match direction {
+            RID_DIRECTION_SEND_STR => RidDirection::Send,
+            RID_DIRECTION_RECV_STR => RidDirection::Recv,
+            _ => return Err(invalid_attribute(ATTR_KEY_RID, value)),
+        };
+
+        let mut payload_types = vec![];
+        let mut restrictions = vec![];
+        if let Some(params) = split.next() {
+            for param in params.split(';').filter(|p| !p.is_empty()) {
+                let mut kv = param.splitn(2, '=');
+                let key = kv.next().unwrap_or_default();
+                let val = kv.next().unwrap_or_default();
+                if key == "pt" {
+                    for pt in val.split(',') {
+                        payload_types.push(parse_payload_type(ATTR_KEY_RID, pt)?);
+                    }
+                } else {
+                    restrictions.push((key.to_owned(), val.to_owned()));
+                }
+            }
+        }
+        if split.next().is_some() {
+            return Err(invalid_attribute(ATTR_KEY_RID, value));
+        }
+
+        Ok(Rid {
+            id,
+            direction,
+            payload_types,
+            restrictions,
+        })
+    }
+
+    /// restriction returns the value of a restriction, e.g. "max-width", if it exists.
+    pub fn restriction(&self, key: &str) -> Option<&str> {
+        self.restrictions
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// SimulcastRid is a single RTP stream identifier in a simulcast stream list.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SimulcastRid {
+    pub id: String,
+    pub paused: bool,
+}
+
+impl fmt::Display for SimulcastRid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.paused {
+            write!(f, "~{}", self.id)
+        } else {
+            write!(f, "{}", self.id)
+        }
+    }
+}
+
+/// Simulcast describes the "a=simulcast" attribute. Each direction holds a
+/// list of simulcast streams, and each stream a list of alternative RIDs.
+///
+/// `a=simulcast:send <rid-list> recv <rid-list>`
+///
+/// <https://tools.ietf.org/html/rfc8853#section-5.1>
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Simulcast {
+    pub send: Vec<Vec<SimulcastRid>>,
+    pub recv: Vec<Vec<SimulcastRid>>,
+}
+
+fn fmt_simulcast_streams(streams: &[Vec<SimulcastRid>]) -> String {
+    streams
+        .iter()
+        .map(|alternatives| {
+            alternatives
+                .iter()
+                .map(|rid| rid.to_string())
+                .collect::<Vec<String>>()
+                .join(",")
+        })
+        .collect::<Vec<String>>()
+        .join(";")
+}
+
+impl fmt::Display for Simulcast {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = vec![];
+        if !self.send.is_empty() {
+            parts.push(format!(
+                "{} {}",
+                RID_DIRECTION_SEND_STR,
+                fmt_simulcast_streams(&self.send)
+            ));
+        }
+        if !self.recv.is_empty() {
+            parts.push(format!(
+                "{} {}",
+                RID_DIRECTION_RECV_STR,
+                fmt_simulcast_streams(&self.recv)
+            ));
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+impl Simulcast {
+    /// unmarshal parses the value of an "a=simulcast" attribute.
+    pub fn unmarshal(value: &str) -> Result<Self> {
+        let mut simulcast = Simulcast::default();
+
+        let fields: Vec<&str> = value.split_whitespace().collect();
+        if fields.is_empty() || fields.len() % 2 != 0 {
+            return Err(invalid_attribute(ATTR_KEY_SIMULCAST, value));
+        }
+
+        for pair in fields.chunks(2) {
+            let streams = match pair[0] {
+                RID_DIRECTION_SEND_STR if simulcast.send.is_empty() => &mut simulcast.send,
+                RID_DIRECTION_RECV_STR if simulcast.recv.is_empty() => &mut simulcast.recv,
+                _ => return Err(invalid_attribute(ATTR_KEY_SIMULCAST, value)),
+            };
+
+            for stream in pair[1].split(';') {
+                let mut alternatives = vec![];
+                for rid in stream.split(',') {
+                    let (id, paused) = match rid.strip_prefix('~') {
+                        Some(id) => (id, true),
+                        None => (rid, false),
+                    };
+                    if id.is_empty() {
+                        return Err(invalid_attribute(ATTR_KEY_SIMULCAST, value));
+                    }
+                    alternatives.push(SimulcastRid {
+                        id: id.to_owned(),
+                        paused,
+                    });
+                }
+                streams.push(alternatives);
+            }
+        }
+
+        Ok(simulcast)
+    }
+}
+
+/// Candidate describes the "a=candidate" attribute, which conveys an ICE candidate.
+///
+/// `a=candidate:<foundation> <component-id> <transport> <priority> <connection-address> <port>
+/// typ <cand-type> [raddr <rel-addr>] [rport <rel-port>] *(<extension-att-name> <extension-att-value>)`
+///
+/// <https://tools.ietf.org/html/rfc8839#section-5.1>
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub foundation: String,
+    pub component: u16,
+    pub transport: String,
+    pub priority: u32,
+    pub address: String,
+    pub port: u16,
+    pub typ: String,
+    pub related_address: Option<String>,
+    pub related_port: Option<u16>,
+    pub extensions: Vec<(String, String)>,
+}
+
+impl fmt::Display for Candidate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {} typ {}",
+            self.foundation,
+            self.component,
+            self.transport,
+            self.priority,
+            self.address,
+            self.port,
+            self.typ
+        )?;
+        if let Some(related_address) = &self.related_address {
+            write!(f, " raddr {related_address}")?;
+        }
+        if let Some(related_port) = &self.related_port {
+            write!(f, " rport {related_port}")?;
+        }
+        for (name, value) in &self.extensions {
+            write!(f, " {name} {value}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Candidate {
+    /// unmarshal parses the value of an "a=candidate" attribute.
+    pub fn unmarshal(value: &str) -> Result<Self> {
+        let fields: Vec<&str> = value.split_whitespace().collect();
+        if fields.len() < 8 || fields[6] != "typ" {
+            return Err(invalid_attribute(ATTR_KEY_CANDIDATE, value));
+        }
+
+        let component = fields[1]
+            .parse::<u16>()
+            .map_err(|_| invalid_attribute(ATTR_KEY_CANDIDATE, value))?;
+        let priority = fields[3]
+            .parse::<u32>()
+            .map_err(|_| invalid_attribute(ATTR_KEY_CANDIDATE, value))?;
+        let port = fields[5]
+            .parse::<u16>()
+            .map_err(|_| invalid_attribute(ATTR_KEY_CANDIDATE, value))?;
+
+        let mut candidate = Candidate {
+            foundation: fields[0].to_owned(),
+            component,
+            transport: fields[2].to_owned(),
+            priority,
+            address: fields[4].to_owned(),
+            port,
+            typ: fields[7].to_owned(),
+            ..Default::default()
+        };
+
+        let rest = &fields[8..];
+        if rest.len() % 2 != 0 {
+            return Err(invalid_attribute(ATTR_KEY_CANDIDATE, value));
+        }
+        for pair in rest.chunks(2) {
+            match pair[0] {
+                "raddr" => candidate.related_address = Some(pair[1].to_owned()),
+                "rport" => {
+                    candidate.related_port = Some(
+                        pair[1]
+                            .parse::<u16>()
+                            .map_err(|_| invalid_attribute(ATTR_KEY_CANDIDATE, value))?,
+                    )
+                }
+                name => candidate
+                    .extensions
+                    .push((name.to_owned(), pair[1].to_owned())),
+            }
+        }
+
+        Ok(candidate)
+    }
+
+    /// extension returns the value of a candidate extension, e.g. "tcptype", if it exists.
+    pub fn extension(&self, name: &str) -> Option<&str> {
+        self.extensions
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Fingerprint describes the "a=fingerprint" attribute, which carries the
+/// hash of the DTLS certificate.
+///
+/// `a=fingerprint:<hash-func> <fingerprint>`
+///
+/// <https://tools.ietf.org/html/rfc8122#section-5>
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Fingerprint {
+    pub algorithm: String,
+    pub value: String,
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.algorithm, self.value)
+    }
+}
+
+impl Fingerprint {
+    /// unmarshal parses the value of an "a=fingerprint" attribute.
+    pub fn unmarshal(value: &str) -> Result<Self> {
+        let fields: Vec<&str> = value.split_whitespace().collect();
+        if fields.len() != 2 {
+            return Err(invalid_attribute(ATTR_KEY_FINGERPRINT, value));
+        }
+
+        Ok(Fingerprint {
+            algorithm: fields[0].to_owned(),
+            value: fields[1].to_owned(),
+        })
+    }
+}
+
+/// Group describes the "a=group" attribute, e.g. `a=group:BUNDLE 0 1`.
+///
+/// `a=group:<semantics> <identification-tag> ...`
+///
+/// <https://tools.ietf.org/html/rfc5888#section-5>
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Group {
+    pub semantics: String,
+    pub identification_tags: Vec<String>,
+}
+
+impl fmt::Display for Group {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.semantics)?;
+        for tag in &self.identification_tags {
+            write!(f, " {tag}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Group {
+    /// unmarshal parses the value of an "a=group" attribute.
+    pub fn unmarshal(value: &str) -> Result<Self> {
+        let mut split = value.split_whitespace();
+        let semantics = match split.next() {
+            Some(semantics) => semantics.to_owned(),
+            None => return Err(invalid_attribute(ATTR_KEY_GROUP, value)),
+        };
+
+        Ok(Group {
+            semantics,
+            identification_tags: split.map(|s| s.to_owned()).collect(),
+        })
+    }
+
+    /// is_bundle returns true if this is a BUNDLE group.
+    pub fn is_bundle(&self) -> bool {
+        self.semantics == SEMANTIC_TOKEN_BUNDLE
+    }
+}
+
+/// TypedAttribute is the structured form of an "a=" field. Attributes
+/// without a structured form, or with a key this crate does not know,
+/// are passed through unchanged as TypedAttribute::Unknown.
+#[derive(Debug, Clone)]
+pub enum TypedAttribute {
+    RtpMap(RtpMap),
+    Fmtp(Fmtp),
+    RtcpFb(RtcpFb),
+    Ssrc(Ssrc),
+    SsrcGroup(SsrcGroup),
+    Rid(Rid),
+    Simulcast(Simulcast),
+    Candidate(Candidate),
+    Fingerprint(Fingerprint),
+    Group(Group),
+    ExtMap(ExtMap),
+    Direction(Direction),
+    Unknown(Attribute),
+}
+
+impl fmt::Display for TypedAttribute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_attribute())
+    }
+}
+
+impl TypedAttribute {
+    /// unmarshal converts an Attribute into its structured form.
+    pub fn unmarshal(attr: &Attribute) -> Result<Self> {
+        let value = attr.value.as_deref();
+        let typed = match (attr.key.as_str(), value) {
+            (ATTR_KEY_RTPMAP, Some(value)) => TypedAttribute::RtpMap(RtpMap::unmarshal(value)?),
+            (ATTR_KEY_FMTP, Some(value)) => TypedAttribute::Fmtp(Fmtp::unmarshal(value)?),
+            (ATTR_KEY_RTCP_FB, Some(value)) => TypedAttribute::RtcpFb(RtcpFb::unmarshal(value)?),
+            (ATTR_KEY_SSRC, Some(value)) => TypedAttribute::Ssrc(Ssrc::unmarshal(value)?),
+            (ATTR_KEY_SSRCGROUP, Some(value)) => {
+                TypedAttribute::SsrcGroup(SsrcGroup::unmarshal(value)?)
+            }
+            (ATTR_KEY_RID, Some(value)) => TypedAttribute::Rid(Rid::unmarshal(value)?),
+            (ATTR_KEY_SIMULCAST, Some(value)) => {
+                TypedAttribute::Simulcast(Simulcast::unmarshal(value)?)
+            }
+            (ATTR_KEY_CANDIDATE, Some(value)) => {
+                TypedAttribute::Candidate(Candidate::unmarshal(value)?)
+            }
+            (ATTR_KEY_FINGERPRINT, Some(value)) => {
+                TypedAttribute::Fingerprint(Fingerprint::unmarshal(value)?)
+            }
+            (ATTR_KEY_GROUP, Some(value)) => TypedAttribute::Group(Group::unmarshal(value)?),
+            (ATTR_KEY_EXT_MAP, Some(_)) => {
+                let mut reader = io::Cursor::new(attr.to_string());
+                TypedAttribute::ExtMap(ExtMap::unmarshal(&mut reader)?)
+            }
+            (
+                ATTR_KEY_SEND_RECV | ATTR_KEY_SEND_ONLY | ATTR_KEY_RECV_ONLY | ATTR_KEY_INACTIVE,
+                None,
+            ) => TypedAttribute::Direction(Direction::new(&attr.key)),
+            _ => TypedAttribute::Unknown(attr.clone()),
+        };
+
+        Ok(typed)
+    }
+
+    /// key returns the attribute key, e.g. "rtpmap".
+    pub fn key(&self) -> String {
+        match self {
+            TypedAttribute::RtpMap(_) => ATTR_KEY_RTPMAP.to_owned(),
+            TypedAttribute::Fmtp(_) => ATTR_KEY_FMTP.to_owned(),
+            TypedAttribute::RtcpFb(_) => ATTR_KEY_RTCP_FB.to_owned(),
+            TypedAttribute::Ssrc(_) => ATTR_KEY_SSRC.to_owned(),
+            TypedAttribute::SsrcGroup(_) => ATTR_KEY_SSRCGROUP.to_owned(),
+            TypedAttribute::Rid(_) => ATTR_KEY_RID.to_owned(),
+            TypedAttribute::Simulcast(_) => ATTR_KEY_SIMULCAST.to_owned(),
+            TypedAttribute::Candidate(_) => ATTR_KEY_CANDIDATE.to_owned(),
+            TypedAttribute::Fingerprint(_) => ATTR_KEY_FINGERPRINT.to_owned(),
+            TypedAttribute::Group(_) => ATTR_KEY_GROUP.to_owned(),
+            TypedAttribute::ExtMap(_) => ATTR_KEY_EXT_MAP.to_owned(),
+            TypedAttribute::Direction(direction) => direction.to_string(),
+            TypedAttribute::Unknown(attr) => attr.key.clone(),
+        }
+    }
+
+    /// to_attribute converts this object back to an Attribute
+    pub fn to_attribute(&self) -> Attribute {
+        let value = match self {
+            TypedAttribute::RtpMap(a) => Some(a.to_string()),
+            TypedAttribute::Fmtp(a) => Some(a.to_string()),
+            TypedAttribute::RtcpFb(a) => Some(a.to_string()),
+            TypedAttribute::Ssrc(a) => Some(a.to_string()),
+            TypedAttribute::SsrcGroup(a) => Some(a.to_string()),
+            TypedAttribute::Rid(a) => Some(a.to_string()),
+            TypedAttribute::Simulcast(a) => Some(a.to_string()),
+            TypedAttribute::Candidate(a) => Some(a.to_string()),
+            TypedAttribute::Fingerprint(a) => Some(a.to_string()),
+            TypedAttribute::Group(a) => Some(a.to_string()),
+            TypedAttribute::ExtMap(a) => return a.convert(),
+            TypedAttribute::Direction(_) => None,
+            TypedAttribute::Unknown(attr) => return attr.clone(),
+        };
+
+        Attribute::new(self.key(), value)
+    }
+}
+
+impl TryFrom<&Attribute> for TypedAttribute {
+    type Error = Error;
+
+    fn try_from(attr: &Attribute) -> Result<Self> {
+        TypedAttribute::unmarshal(attr)
+    }
+}
+
+impl From<TypedAttribute> for Attribute {
+    fn from(typed: TypedAttribute) -> Self {
+        typed.to_attribute()
+    }
+}
+
+/// unmarshal_all parses the values of all attributes with the given key.
+pub(crate) fn unmarshal_all<T>(
+    attributes: &[Attribute],
+    key: &str,
+    unmarshal: fn(&str) -> Result<T>,
+) -> Result<Vec<T>> {
+    attributes
+        .iter()
+        .filter(|a| a.key == key)
+        .map(|a| match &a.value {
+            Some(value) => unmarshal(value),
+            None => Err(invalid_attribute(key, "")),
+        })
+        .collect()
+}
+
+/// split_first_token splits "<token> <rest>" on the first space.
+fn split_first_token<'a>(key: &str, value: &'a str) -> Result<(&'a str, &'a str)> {
+    let mut split = value.trim().splitn(2, ' ');
+    match (split.next(), split.next()) {
+        (Some(first), Some(rest)) if !first.is_empty() && !rest.trim().is_empty() => {
+            Ok((first, rest.trim()))
+        }
+        _ => Err(invalid_attribute(key, value)),
+    }
+}
+
+fn parse_payload_type(key: &str, payload_type: &str) -> Result<u8> {
+    payload_type
+        .parse::<u8>()
+        .map_err(|_| Error::ParseAttribute(format!("{key}: invalid payload type {payload_type}")))
+}
+
+fn invalid_attribute(key: &str, value: &str) -> Error {
+    Error::ParseAttribute(format!("{key}:{value}"))
+}
@@ -560,11 +560,131 @@ fn test_unmarshal_repeat_times() -> Result<()> {
 fn test_unmarshal_repeat_times_overflow() -> Result<()> {
     let mut reader = Cursor::new(REPEAT_TIMES_OVERFLOW_SDP.as_bytes());
     let result = SessionDescription::unmarshal(&mut reader);
-    assert!(result.is_err());
-    assert_eq!(
-        Error::SdpInvalidValue("106751991167301d".to_owned()),
-        result.unwrap_err()
-    );
+    if let Err(Error::SdpInvalidLine { source, .. }) = result {
+        assert_eq!(
+            Error::SdpInvalidValue("106751991167301d".to_owned()),
+            *source
+        );
+    } else {
+        panic!("expected SdpInvalidLine, got {result:?}");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_unmarshal_error_position() {
+    let tests = vec![
+        ("missing version", "o=- 0 0 IN IP4 0.0.0.0\r\n", 1, 1, "v="),
+        (
+            "invalid origin session id",
+            "v=0\r\no=- abc 0 IN IP4 0.0.0.0\r\n",
+            2,
+            5,
+            "o=<username> <sess-id> <sess-version> <nettype> <addrtype> <unicast-address>",
+        ),
+        (
+            "invalid media port",
+            "v=0\r\no=- 0 0 IN IP4 0.0.0.0\r\ns=-\r\nt=0 0\r\nm=audio 9x UDP 0\r\n",
+            5,
+            9,
+            "m=<media> <port>[/<number of ports>] <proto> <fmt> ...",
+        ),
+        (
+            "misplaced attribute",
+            "v=0\r\no=- 0 0 IN IP4 0.0.0.0\r\ns=-\r\na=sendrecv\r\n",
+            4,
+            1,
+            "i=, u=, e=, p=, c=, b= or t=",
+        ),
+        (
+            "line without type",
+            "v=0\r\no=- 0 0 IN IP4 0.0.0.0\r\ns=-\r\nt=0 0\r\n\r\nbogus\r\n",
+            6,
+            1,
+            "<type>=<value>",
+        ),
+    ];
+
+    for (name, sdp_str, expected_line, expected_column, expected_grammar) in tests {
+        let mut reader = Cursor::new(sdp_str.as_bytes());
+        match SessionDescription::unmarshal(&mut reader) {
+            Err(Error::SdpInvalidLine {
+                line,
+                column,
+                expected,
+                ..
+            }) => {
+                assert_eq!(line, expected_line, "{name}");
+                assert_eq!(column, expected_column, "{name}");
+                assert_eq!(expected, expected_grammar, "{name}");
+            }
+            result => panic!("{name}: expected SdpInvalidLine, got {result:?}"),
+        }
+    }
+}
+
+const LENIENT_SDP: &str = "v=0\r\n\
+     o=- 0 0 IN IP4 0.0.0.0\r\n\
+     s=-\r\n\
+     b=AS:lots\r\n\
+     t=0 0\r\n\
+     a=:broken\r\n\
+     a=group:BUNDLE 0\r\n\
+     m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n\
+     a=mid:0\r\n\
+     garbage\r\n\
+     a=rtpmap:111 opus/48000/2\r\n\
+     e=late@example.com\r\n";
+
+#[test]
+fn test_unmarshal_lenient() -> Result<()> {
+    let mut reader = Cursor::new(LENIENT_SDP.as_bytes());
+    assert!(SessionDescription::unmarshal(&mut reader).is_err());
+
+    let mut reader = Cursor::new(LENIENT_SDP.as_bytes());
+    let (sdp, warnings) = SessionDescription::unmarshal_lenient(&mut reader)?;
+
+    let lines: Vec<usize> = warnings
+        .iter()
+        .map(|w| match w {
+            Error::SdpInvalidLine { line, .. } => *line,
+            _ => panic!("unexpected warning {w:?}"),
+        })
+        .collect();
+    assert_eq!(lines, vec![4, 6, 10, 12]);
+
+    assert!(sdp.bandwidth.is_empty());
+    assert_eq!(sdp.attribute("group"), Some(&"BUNDLE 0".to_owned()));
+    assert_eq!(sdp.media_descriptions.len(), 1);
+    let m = &sdp.media_descriptions[0];
+    assert_eq!(m.attribute("rtpmap"), Some(Some("111 opus/48000/2")));
+
+    // Malformed attribute names are accepted by unmarshal, as they always were,
+    // and kept by unmarshal_lenient, which reports them as warnings.
+    let input =
+        "v=0\r\no=- 0 0 IN IP4 0.0.0.0\r\ns=-\r\nt=0 0\r\na=\r\na=:broken\r\na=foo bar:1\r\n";
+    let mut reader = Cursor::new(input.as_bytes());
+    let sdp = SessionDescription::unmarshal(&mut reader)?;
+    let keys: Vec<&str> = sdp.attributes.iter().map(|a| a.key.as_str()).collect();
+    assert_eq!(keys, vec!["", "", "foo bar"]);
+
+    let mut reader = Cursor::new(input.as_bytes());
+    let (sdp, warnings) = SessionDescription::unmarshal_lenient(&mut reader)?;
+    let keys: Vec<&str> = sdp.attributes.iter().map(|a| a.key.as_str()).collect();
+    assert_eq!(keys, vec!["", "", "foo bar"]);
+    let lines: Vec<usize> = warnings
+        .iter()
+        .map(|w| match w {
+            Error::SdpInvalidLine { line, .. } => *line,
+            _ => panic!("unexpected warning {w:?}"),
+        })
+        .collect();
+    assert_eq!(lines, vec![5, 6, 7]);
+
+    // Structural lines are never skipped.
+    let mut reader = Cursor::new("v=0\r\no=- 0 0 IN IP4 0.0.0.0\r\ns=-\r\nt=0 x\r\n".as_bytes());
+    assert!(SessionDescription::unmarshal_lenient(&mut reader).is_err());
+
     Ok(())
 }
 
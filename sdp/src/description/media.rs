@@ -2,7 +2,10 @@ use std::collections::HashMap;
 use std::fmt;
 use url::Url;
 
+use crate::attribute::*;
 use crate::description::common::*;
+use crate::description::session::*;
+use crate::error::Result;
 use crate::extmap::*;
 
 /// Constants for extmap key
@@ -69,6 +72,69 @@ impl MediaDescription {
         None
     }
 
+    /// typed_attributes returns the structured form of all attributes
+    pub fn typed_attributes(&self) -> Result<Vec<TypedAttribute>> {
+        self.attributes
+            .iter()
+            .map(TypedAttribute::unmarshal)
+            .collect()
+    }
+
+    /// rtp_maps returns all "a=rtpmap" attributes
+    pub fn rtp_maps(&self) -> Result<Vec<RtpMap>> {
+        unmarshal_all(&self.attributes, ATTR_KEY_RTPMAP, RtpMap::unmarshal)
+    }
+
+    /// fmtps returns all "a=fmtp" attributes
+    pub fn fmtps(&self) -> Result<Vec<Fmtp>> {
+        unmarshal_all(&self.attributes, ATTR_KEY_FMTP, Fmtp::unmarshal)
+    }
+
+    /// rtcp_fbs returns all "a=rtcp-fb" attributes
+    pub fn rtcp_fbs(&self) -> Result<Vec<RtcpFb>> {
+        unmarshal_all(&self.attributes, ATTR_KEY_RTCP_FB, RtcpFb::unmarshal)
+    }
+
+    /// ssrcs returns all "a=ssrc" attributes
+    pub fn ssrcs(&self) -> Result<Vec<Ssrc>> {
+        unmarshal_all(&self.attributes, ATTR_KEY_SSRC, Ssrc::unmarshal)
+    }
+
+    /// ssrc_groups returns all "a=ssrc-group" attributes
+    pub fn ssrc_groups(&self) -> Result<Vec<SsrcGroup>> {
+        unmarshal_all(&self.attributes, ATTR_KEY_SSRCGROUP, SsrcGroup::unmarshal)
+    }
+
+    /// rids returns all "a=rid" attributes
+    pub fn rids(&self) -> Result<Vec<Rid>> {
+        unmarshal_all(&self.attributes, ATTR_KEY_RID, Rid::unmarshal)
+    }
+
+    /// simulcast returns the "a=simulcast" attribute if it exists
+    pub fn simulcast(&self) -> Result<Option<Simulcast>> {
+        Ok(
+            unmarshal_all(&self.attributes, ATTR_KEY_SIMULCAST, Simulcast::unmarshal)?
+                .into_iter()
+                .next(),
+        )
+    }
+
+    /// candidates returns all "a=candidate" attributes
+    pub fn candidates(&self) -> Result<Vec<Candidate>> {
+        unmarshal_all(&self.attributes, ATTR_KEY_CANDIDATE, Candidate::unmarshal)
+    }
+
+    /// fingerprint returns the "a=fingerprint" attribute if it exists
+    pub fn fingerprint(&self) -> Result<Option<Fingerprint>> {
+        Ok(unmarshal_all(
+            &self.attributes,
+            ATTR_KEY_FINGERPRINT,
+            Fingerprint::unmarshal,
+        )?
+        .into_iter()
+        .next())
+    }
+
     /// new_jsep_media_description creates a new MediaName with
     /// some settings that are required by the JSEP spec.
     pub fn new_jsep_media_description(codec_type: String, _codec_prefs: Vec<&str>) -> Self {
@@ -115,6 +181,12 @@ impl MediaDescription {
         self
     }
 
+    /// with_typed_attribute adds a structured attribute to the media description
+    pub fn with_typed_attribute(mut self, attr: TypedAttribute) -> Self {
+        self.attributes.push(attr.to_attribute());
+        self
+    }
+
     /// with_fingerprint adds a fingerprint to the media description
     pub fn with_fingerprint(self, algorithm: String, value: String) -> Self {
         self.with_value_attribute("fingerprint".to_owned(), algorithm + " " + &value)
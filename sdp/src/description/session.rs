@@ -1,9 +1,11 @@
 use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::str::FromStr;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::{fmt, io};
 use url::Url;
 
+use crate::attribute::*;
 use crate::error::{Error, Result};
 use crate::lexer::*;
 use crate::util::*;
@@ -30,12 +32,19 @@ pub const ATTR_KEY_RECV_ONLY: &str = "recvonly";
 pub const ATTR_KEY_SEND_ONLY: &str = "sendonly";
 pub const ATTR_KEY_SEND_RECV: &str = "sendrecv";
 pub const ATTR_KEY_EXT_MAP: &str = "extmap";
+pub const ATTR_KEY_RTPMAP: &str = "rtpmap";
+pub const ATTR_KEY_FMTP: &str = "fmtp";
+pub const ATTR_KEY_RTCP_FB: &str = "rtcp-fb";
+pub const ATTR_KEY_RID: &str = "rid";
+pub const ATTR_KEY_SIMULCAST: &str = "simulcast";
+pub const ATTR_KEY_FINGERPRINT: &str = "fingerprint";
 
 /// Constants for semantic tokens used in JSEP
 pub const SEMANTIC_TOKEN_LIP_SYNCHRONIZATION: &str = "LS";
 pub const SEMANTIC_TOKEN_FLOW_IDENTIFICATION: &str = "FID";
 pub const SEMANTIC_TOKEN_FORWARD_ERROR_CORRECTION: &str = "FEC";
 pub const SEMANTIC_TOKEN_WEBRTC_MEDIA_STREAMS: &str = "WMS";
+pub const SEMANTIC_TOKEN_BUNDLE: &str = "BUNDLE";
 
 /// Version describes the value provided by the "v=" field which gives
 /// the version of the Session Description Protocol.
@@ -294,6 +303,12 @@ impl SessionDescription {
         self
     }
 
+    /// WithTypedAttribute adds a structured attribute to the session description
+    pub fn with_typed_attribute(mut self, attr: TypedAttribute) -> Self {
+        self.attributes.push(attr.to_attribute());
+        self
+    }
+
     /// WithFingerprint adds a fingerprint to the session description
     pub fn with_fingerprint(self, algorithm: String, value: String) -> Self {
         self.with_value_attribute("fingerprint".to_string(), algorithm + " " + value.as_str())
@@ -365,6 +380,35 @@ impl SessionDescription {
         None
     }
 
+    /// typed_attributes returns the structured form of all session attributes
+    pub fn typed_attributes(&self) -> Result<Vec<TypedAttribute>> {
+        self.attributes
+            .iter()
+            .map(TypedAttribute::unmarshal)
+            .collect()
+    }
+
+    /// groups returns all session level "a=group" attributes
+    pub fn groups(&self) -> Result<Vec<Group>> {
+        unmarshal_all(&self.attributes, ATTR_KEY_GROUP, Group::unmarshal)
+    }
+
+    /// bundle_group returns the "a=group:BUNDLE" attribute if it exists
+    pub fn bundle_group(&self) -> Result<Option<Group>> {
+        Ok(self.groups()?.into_iter().find(|g| g.is_bundle()))
+    }
+
+    /// fingerprint returns the session level "a=fingerprint" attribute if it exists
+    pub fn fingerprint(&self) -> Result<Option<Fingerprint>> {
+        Ok(unmarshal_all(
+            &self.attributes,
+            ATTR_KEY_FINGERPRINT,
+            Fingerprint::unmarshal,
+        )?
+        .into_iter()
+        .next())
+    }
+
     /// Marshal takes a SDP struct to text
     ///
     /// <https://tools.ietf.org/html/rfc4566#section-5>
@@ -534,33 +578,77 @@ impl SessionDescription {
     /// |   s16  |    |    14 |    |     |    |  15 |   |    | 12 |   |   |     |   |   |    |   |    |
     /// +--------+----+-------+----+-----+----+-----+---+----+----+---+---+-----+---+---+----+---+----+
     /// ```
+    ///
+    /// Errors are reported as [`Error::SdpInvalidLine`], which carries the line
+    /// number, the column and the grammar element the parser expected.
     pub fn unmarshal<R: io::BufRead + io::Seek>(reader: &mut R) -> Result<Self> {
-        let mut lexer = Lexer {
-            desc: SessionDescription {
-                version: 0,
-                origin: Origin::new(),
-                session_name: "".to_owned(),
-                session_information: None,
-                uri: None,
-                email_address: None,
-                phone_number: None,
-                connection_information: None,
-                bandwidth: vec![],
-                time_descriptions: vec![],
-                time_zones: vec![],
-                encryption_key: None,
-                attributes: vec![],
-                media_descriptions: vec![],
-            },
-            reader,
+        let (desc, _) = Self::unmarshal_with(reader, false)?;
+        Ok(desc)
+    }
+
+    /// unmarshal_lenient is like unmarshal, but skips malformed or misplaced lines
+    /// other than `v=`, `o=`, `s=`, `t=` and `m=` instead of failing. The skipped
+    /// lines are returned as warnings, each an [`Error::SdpInvalidLine`]. Attribute
+    /// lines with an empty or whitespace-containing name are accepted by unmarshal,
+    /// so they are kept and only reported as warnings.
+    pub fn unmarshal_lenient<R: io::BufRead + io::Seek>(
+        reader: &mut R,
+    ) -> Result<(Self, Vec<Error>)> {
+        Self::unmarshal_with(reader, true)
+    }
+
+    fn unmarshal_with<R: io::BufRead + io::Seek>(
+        reader: &mut R,
+        lenient: bool,
+    ) -> Result<(Self, Vec<Error>)> {
+        let desc = SessionDescription {
+            version: 0,
+            origin: Origin::new(),
+            session_name: "".to_owned(),
+            session_information: None,
+            uri: None,
+            email_address: None,
+            phone_number: None,
+            connection_information: None,
+            bandwidth: vec![],
+            time_descriptions: vec![],
+            time_zones: vec![],
+            encryption_key: None,
+            attributes: vec![],
+            media_descriptions: vec![],
         };
+        let mut lexer = Lexer::new(desc, reader, lenient);
+
+        // The states alternate between reading the type of a line and parsing its
+        // value. A skipped line resumes at the last state that read a type.
+        let mut state = StateFn { f: s1 };
+        let mut resume = state.f;
+        let mut reads_type = true;
+        loop {
+            if reads_type {
+                resume = state.f;
+            }
 
-        let mut state = Some(StateFn { f: s1 });
-        while let Some(s) = state {
-            state = (s.f)(&mut lexer)?;
+            match (state.f)(&mut lexer) {
+                Ok(Some(next)) => {
+                    state = next;
+                    reads_type = !reads_type;
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    let skippable = lexer.skippable(&err);
+                    let err = lexer.locate(err);
+                    if !skippable {
+                        return Err(err);
+                    }
+                    lexer.warnings.push(err);
+                    state = StateFn { f: resume };
+                    reads_type = true;
+                }
+            }
         }
 
-        Ok(lexer.desc)
+        Ok((lexer.desc, lexer.warnings))
     }
 }
 
@@ -580,40 +668,40 @@ impl TryFrom<String> for SessionDescription {
 }
 
 fn s1<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option<StateFn<'a, R>>> {
-    let (key, _) = read_type(lexer.reader)?;
+    let (key, _) = read_type(lexer)?;
     if &key == b"v=" {
         return Ok(Some(StateFn {
             f: unmarshal_protocol_version,
         }));
     }
 
-    Err(Error::SdpInvalidSyntax(String::from_utf8(key)?))
+    Err(lexer.unexpected_type(&key, "v="))
 }
 
 fn s2<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option<StateFn<'a, R>>> {
-    let (key, _) = read_type(lexer.reader)?;
+    let (key, _) = read_type(lexer)?;
     if &key == b"o=" {
         return Ok(Some(StateFn {
             f: unmarshal_origin,
         }));
     }
 
-    Err(Error::SdpInvalidSyntax(String::from_utf8(key)?))
+    Err(lexer.unexpected_type(&key, "o="))
 }
 
 fn s3<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option<StateFn<'a, R>>> {
-    let (key, _) = read_type(lexer.reader)?;
+    let (key, _) = read_type(lexer)?;
     if &key == b"s=" {
         return Ok(Some(StateFn {
             f: unmarshal_session_name,
         }));
     }
 
-    Err(Error::SdpInvalidSyntax(String::from_utf8(key)?))
+    Err(lexer.unexpected_type(&key, "s="))
 }
 
 fn s4<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option<StateFn<'a, R>>> {
-    let (key, _) = read_type(lexer.reader)?;
+    let (key, _) = read_type(lexer)?;
     match key.as_slice() {
         b"i=" => Ok(Some(StateFn {
             f: unmarshal_session_information,
@@ -630,12 +718,12 @@ fn s4<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option<
         b"t=" => Ok(Some(StateFn {
             f: unmarshal_timing,
         })),
-        _ => Err(Error::SdpInvalidSyntax(String::from_utf8(key)?)),
+        _ => Err(lexer.unexpected_type(&key, "i=, u=, e=, p=, c=, b= or t=")),
     }
 }
 
 fn s5<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option<StateFn<'a, R>>> {
-    let (key, _) = read_type(lexer.reader)?;
+    let (key, _) = read_type(lexer)?;
     match key.as_slice() {
         b"b=" => Ok(Some(StateFn {
             f: unmarshal_session_bandwidth,
@@ -643,12 +731,12 @@ fn s5<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option<
         b"t=" => Ok(Some(StateFn {
             f: unmarshal_timing,
         })),
-        _ => Err(Error::SdpInvalidSyntax(String::from_utf8(key)?)),
+        _ => Err(lexer.unexpected_type(&key, "b= or t=")),
     }
 }
 
 fn s6<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option<StateFn<'a, R>>> {
-    let (key, _) = read_type(lexer.reader)?;
+    let (key, _) = read_type(lexer)?;
     match key.as_slice() {
         b"p=" => Ok(Some(StateFn { f: unmarshal_phone })),
         b"c=" => Ok(Some(StateFn {
@@ -660,12 +748,12 @@ fn s6<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option<
         b"t=" => Ok(Some(StateFn {
             f: unmarshal_timing,
         })),
-        _ => Err(Error::SdpInvalidSyntax(String::from_utf8(key)?)),
+        _ => Err(lexer.unexpected_type(&key, "p=, c=, b= or t=")),
     }
 }
 
 fn s7<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option<StateFn<'a, R>>> {
-    let (key, _) = read_type(lexer.reader)?;
+    let (key, _) = read_type(lexer)?;
     match key.as_slice() {
         b"u=" => Ok(Some(StateFn { f: unmarshal_uri })),
         b"e=" => Ok(Some(StateFn { f: unmarshal_email })),
@@ -679,12 +767,12 @@ fn s7<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option<
         b"t=" => Ok(Some(StateFn {
             f: unmarshal_timing,
         })),
-        _ => Err(Error::SdpInvalidSyntax(String::from_utf8(key)?)),
+        _ => Err(lexer.unexpected_type(&key, "u=, e=, p=, c=, b= or t=")),
     }
 }
 
 fn s8<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option<StateFn<'a, R>>> {
-    let (key, _) = read_type(lexer.reader)?;
+    let (key, _) = read_type(lexer)?;
     match key.as_slice() {
         b"c=" => Ok(Some(StateFn {
             f: unmarshal_session_connection_information,
@@ -695,12 +783,12 @@ fn s8<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option<
         b"t=" => Ok(Some(StateFn {
             f: unmarshal_timing,
         })),
-        _ => Err(Error::SdpInvalidSyntax(String::from_utf8(key)?)),
+        _ => Err(lexer.unexpected_type(&key, "c=, b= or t=")),
     }
 }
 
 fn s9<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option<StateFn<'a, R>>> {
-    let (key, num_bytes) = read_type(lexer.reader)?;
+    let (key, num_bytes) = read_type(lexer)?;
     if key.is_empty() && num_bytes == 0 {
         return Ok(None);
     }
@@ -724,12 +812,12 @@ fn s9<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option<
         b"m=" => Ok(Some(StateFn {
             f: unmarshal_media_description,
         })),
-        _ => Err(Error::SdpInvalidSyntax(String::from_utf8(key)?)),
+        _ => Err(lexer.unexpected_type(&key, "z=, k=, a=, r=, t=, m= or end of input")),
     }
 }
 
 fn s10<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option<StateFn<'a, R>>> {
-    let (key, _) = read_type(lexer.reader)?;
+    let (key, _) = read_type(lexer)?;
     match key.as_slice() {
         b"e=" => Ok(Some(StateFn { f: unmarshal_email })),
         b"p=" => Ok(Some(StateFn { f: unmarshal_phone })),
@@ -742,12 +830,12 @@ fn s10<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option
         b"t=" => Ok(Some(StateFn {
             f: unmarshal_timing,
         })),
-        _ => Err(Error::SdpInvalidSyntax(String::from_utf8(key)?)),
+        _ => Err(lexer.unexpected_type(&key, "e=, p=, c=, b= or t=")),
     }
 }
 
 fn s11<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option<StateFn<'a, R>>> {
-    let (key, num_bytes) = read_type(lexer.reader)?;
+    let (key, num_bytes) = read_type(lexer)?;
     if key.is_empty() && num_bytes == 0 {
         return Ok(None);
     }
@@ -759,12 +847,12 @@ fn s11<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option
         b"m=" => Ok(Some(StateFn {
             f: unmarshal_media_description,
         })),
-        _ => Err(Error::SdpInvalidSyntax(String::from_utf8(key)?)),
+        _ => Err(lexer.unexpected_type(&key, "a=, m= or end of input")),
     }
 }
 
 fn s12<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option<StateFn<'a, R>>> {
-    let (key, num_bytes) = read_type(lexer.reader)?;
+    let (key, num_bytes) = read_type(lexer)?;
     if key.is_empty() && num_bytes == 0 {
         return Ok(None);
     }
@@ -788,12 +876,12 @@ fn s12<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option
         b"m=" => Ok(Some(StateFn {
             f: unmarshal_media_description,
         })),
-        _ => Err(Error::SdpInvalidSyntax(String::from_utf8(key)?)),
+        _ => Err(lexer.unexpected_type(&key, "a=, k=, b=, c=, i=, m= or end of input")),
     }
 }
 
 fn s13<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option<StateFn<'a, R>>> {
-    let (key, num_bytes) = read_type(lexer.reader)?;
+    let (key, num_bytes) = read_type(lexer)?;
     if key.is_empty() && num_bytes == 0 {
         return Ok(None);
     }
@@ -808,12 +896,12 @@ fn s13<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option
         b"m=" => Ok(Some(StateFn {
             f: unmarshal_media_description,
         })),
-        _ => Err(Error::SdpInvalidSyntax(String::from_utf8(key)?)),
+        _ => Err(lexer.unexpected_type(&key, "a=, k=, m= or end of input")),
     }
 }
 
 fn s14<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option<StateFn<'a, R>>> {
-    let (key, num_bytes) = read_type(lexer.reader)?;
+    let (key, num_bytes) = read_type(lexer)?;
     if key.is_empty() && num_bytes == 0 {
         return Ok(None);
     }
@@ -841,12 +929,12 @@ fn s14<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option
         b"m=" => Ok(Some(StateFn {
             f: unmarshal_media_description,
         })),
-        _ => Err(Error::SdpInvalidSyntax(String::from_utf8(key)?)),
+        _ => Err(lexer.unexpected_type(&key, "a=, k=, b=, c=, i=, m= or end of input")),
     }
 }
 
 fn s15<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option<StateFn<'a, R>>> {
-    let (key, num_bytes) = read_type(lexer.reader)?;
+    let (key, num_bytes) = read_type(lexer)?;
     if key.is_empty() && num_bytes == 0 {
         return Ok(None);
     }
@@ -871,12 +959,12 @@ fn s15<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option
         b"m=" => Ok(Some(StateFn {
             f: unmarshal_media_description,
         })),
-        _ => Err(Error::SdpInvalidSyntax(String::from_utf8(key)?)),
+        _ => Err(lexer.unexpected_type(&key, "a=, k=, b=, c=, i=, m= or end of input")),
     }
 }
 
 fn s16<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option<StateFn<'a, R>>> {
-    let (key, num_bytes) = read_type(lexer.reader)?;
+    let (key, num_bytes) = read_type(lexer)?;
     if key.is_empty() && num_bytes == 0 {
         return Ok(None);
     }
@@ -901,16 +989,16 @@ fn s16<'a, R: io::BufRead + io::Seek>(lexer: &mut Lexer<'a, R>) -> Result<Option
         b"m=" => Ok(Some(StateFn {
             f: unmarshal_media_description,
         })),
-        _ => Err(Error::SdpInvalidSyntax(String::from_utf8(key)?)),
+        _ => Err(lexer.unexpected_type(&key, "a=, k=, c=, b=, i=, m= or end of input")),
     }
 }
 
 fn unmarshal_protocol_version<'a, R: io::BufRead + io::Seek>(
     lexer: &mut Lexer<'a, R>,
 ) -> Result<Option<StateFn<'a, R>>> {
-    let (value, _) = read_value(lexer.reader)?;
+    let (value, _) = read_value(lexer)?;
 
-    let version = value.parse::<u32>()?;
+    let version = parse_field::<u32>(&value)?;
 
     // As off the latest draft of the rfc this value is required to be 0.
     // https://tools.ietf.org/html/draft-ietf-rtcweb-jsep-24#section-5.8.1
@@ -924,15 +1012,15 @@ fn unmarshal_protocol_version<'a, R: io::BufRead + io::Seek>(
 fn unmarshal_origin<'a, R: io::BufRead + io::Seek>(
     lexer: &mut Lexer<'a, R>,
 ) -> Result<Option<StateFn<'a, R>>> {
-    let (value, _) = read_value(lexer.reader)?;
+    let (value, _) = read_value(lexer)?;
 
     let fields: Vec<&str> = value.split_whitespace().collect();
     if fields.len() != 6 {
         return Err(Error::SdpInvalidSyntax(format!("`o={value}`")));
     }
 
-    let session_id = fields[1].parse::<u64>()?;
-    let session_version = fields[2].parse::<u64>()?;
+    let session_id = parse_field::<u64>(fields[1])?;
+    let session_version = parse_field::<u64>(fields[2])?;
 
     // Set according to currently registered with IANA
     // https://tools.ietf.org/html/rfc4566#section-8.2.6
@@ -965,7 +1053,7 @@ fn unmarshal_origin<'a, R: io::BufRead + io::Seek>(
 fn unmarshal_session_name<'a, R: io::BufRead + io::Seek>(
     lexer: &mut Lexer<'a, R>,
 ) -> Result<Option<StateFn<'a, R>>> {
-    let (value, _) = read_value(lexer.reader)?;
+    let (value, _) = read_value(lexer)?;
     lexer.desc.session_name = value;
     Ok(Some(StateFn { f: s4 }))
 }
@@ -973,7 +1061,7 @@ fn unmarshal_session_name<'a, R: io::BufRead + io::Seek>(
 fn unmarshal_session_information<'a, R: io::BufRead + io::Seek>(
     lexer: &mut Lexer<'a, R>,
 ) -> Result<Option<StateFn<'a, R>>> {
-    let (value, _) = read_value(lexer.reader)?;
+    let (value, _) = read_value(lexer)?;
     lexer.desc.session_information = Some(value);
     Ok(Some(StateFn { f: s7 }))
 }
@@ -981,7 +1069,7 @@ fn unmarshal_session_information<'a, R: io::BufRead + io::Seek>(
 fn unmarshal_uri<'a, R: io::BufRead + io::Seek>(
     lexer: &mut Lexer<'a, R>,
 ) -> Result<Option<StateFn<'a, R>>> {
-    let (value, _) = read_value(lexer.reader)?;
+    let (value, _) = read_value(lexer)?;
     lexer.desc.uri = Some(Url::parse(&value)?);
     Ok(Some(StateFn { f: s10 }))
 }
@@ -989,7 +1077,7 @@ fn unmarshal_uri<'a, R: io::BufRead + io::Seek>(
 fn unmarshal_email<'a, R: io::BufRead + io::Seek>(
     lexer: &mut Lexer<'a, R>,
 ) -> Result<Option<StateFn<'a, R>>> {
-    let (value, _) = read_value(lexer.reader)?;
+    let (value, _) = read_value(lexer)?;
     lexer.desc.email_address = Some(value);
     Ok(Some(StateFn { f: s6 }))
 }
@@ -997,7 +1085,7 @@ fn unmarshal_email<'a, R: io::BufRead + io::Seek>(
 fn unmarshal_phone<'a, R: io::BufRead + io::Seek>(
     lexer: &mut Lexer<'a, R>,
 ) -> Result<Option<StateFn<'a, R>>> {
-    let (value, _) = read_value(lexer.reader)?;
+    let (value, _) = read_value(lexer)?;
     lexer.desc.phone_number = Some(value);
     Ok(Some(StateFn { f: s8 }))
 }
@@ -1005,7 +1093,7 @@ fn unmarshal_phone<'a, R: io::BufRead + io::Seek>(
 fn unmarshal_session_connection_information<'a, R: io::BufRead + io::Seek>(
     lexer: &mut Lexer<'a, R>,
 ) -> Result<Option<StateFn<'a, R>>> {
-    let (value, _) = read_value(lexer.reader)?;
+    let (value, _) = read_value(lexer)?;
     lexer.desc.connection_information = unmarshal_connection_information(&value)?;
     Ok(Some(StateFn { f: s5 }))
 }
@@ -1050,7 +1138,7 @@ fn unmarshal_connection_information(value: &str) -> Result<Option<ConnectionInfo
 fn unmarshal_session_bandwidth<'a, R: io::BufRead + io::Seek>(
     lexer: &mut Lexer<'a, R>,
 ) -> Result<Option<StateFn<'a, R>>> {
-    let (value, _) = read_value(lexer.reader)?;
+    let (value, _) = read_value(lexer)?;
     lexer.desc.bandwidth.push(unmarshal_bandwidth(&value)?);
     Ok(Some(StateFn { f: s5 }))
 }
@@ -1073,7 +1161,7 @@ fn unmarshal_bandwidth(value: &str) -> Result<Bandwidth> {
         }
     }
 
-    let bandwidth = parts[1].parse::<u64>()?;
+    let bandwidth = parse_field::<u64>(parts[1])?;
 
     Ok(Bandwidth {
         experimental,
@@ -1085,15 +1173,15 @@ fn unmarshal_bandwidth(value: &str) -> Result<Bandwidth> {
 fn unmarshal_timing<'a, R: io::BufRead + io::Seek>(
     lexer: &mut Lexer<'a, R>,
 ) -> Result<Option<StateFn<'a, R>>> {
-    let (value, _) = read_value(lexer.reader)?;
+    let (value, _) = read_value(lexer)?;
 
     let fields: Vec<&str> = value.split_whitespace().collect();
     if fields.len() < 2 {
         return Err(Error::SdpInvalidSyntax(format!("`t={value}`")));
     }
 
-    let start_time = fields[0].parse::<u64>()?;
-    let stop_time = fields[1].parse::<u64>()?;
+    let start_time = parse_field::<u64>(fields[0])?;
+    let stop_time = parse_field::<u64>(fields[1])?;
 
     lexer.desc.time_descriptions.push(TimeDescription {
         timing: Timing {
@@ -1109,7 +1197,7 @@ fn unmarshal_timing<'a, R: io::BufRead + io::Seek>(
 fn unmarshal_repeat_times<'a, R: io::BufRead + io::Seek>(
     lexer: &mut Lexer<'a, R>,
 ) -> Result<Option<StateFn<'a, R>>> {
-    let (value, _) = read_value(lexer.reader)?;
+    let (value, _) = read_value(lexer)?;
 
     let fields: Vec<&str> = value.split_whitespace().collect();
     if fields.len() < 3 {
@@ -1139,7 +1227,7 @@ fn unmarshal_repeat_times<'a, R: io::BufRead + io::Seek>(
 fn unmarshal_time_zones<'a, R: io::BufRead + io::Seek>(
     lexer: &mut Lexer<'a, R>,
 ) -> Result<Option<StateFn<'a, R>>> {
-    let (value, _) = read_value(lexer.reader)?;
+    let (value, _) = read_value(lexer)?;
 
     // These fields are transimitted in pairs
     // z=<adjustment time> <offset> <adjustment time> <offset> ....
@@ -1150,7 +1238,7 @@ fn unmarshal_time_zones<'a, R: io::BufRead + io::Seek>(
     }
 
     for i in (0..fields.len()).step_by(2) {
-        let adjustment_time = fields[i].parse::<u64>()?;
+        let adjustment_time = parse_field::<u64>(fields[i])?;
         let offset = parse_time_units(fields[i + 1])?;
 
         lexer.desc.time_zones.push(TimeZone {
@@ -1165,7 +1253,7 @@ fn unmarshal_time_zones<'a, R: io::BufRead + io::Seek>(
 fn unmarshal_session_encryption_key<'a, R: io::BufRead + io::Seek>(
     lexer: &mut Lexer<'a, R>,
 ) -> Result<Option<StateFn<'a, R>>> {
-    let (value, _) = read_value(lexer.reader)?;
+    let (value, _) = read_value(lexer)?;
     lexer.desc.encryption_key = Some(value);
     Ok(Some(StateFn { f: s11 }))
 }
@@ -1173,20 +1261,9 @@ fn unmarshal_session_encryption_key<'a, R: io::BufRead + io::Seek>(
 fn unmarshal_session_attribute<'a, R: io::BufRead + io::Seek>(
     lexer: &mut Lexer<'a, R>,
 ) -> Result<Option<StateFn<'a, R>>> {
-    let (value, _) = read_value(lexer.reader)?;
+    let (value, _) = read_value(lexer)?;
 
-    let fields: Vec<&str> = value.splitn(2, ':').collect();
-    let attribute = if fields.len() == 2 {
-        Attribute {
-            key: fields[0].to_owned(),
-            value: Some(fields[1].to_owned()),
-        }
-    } else {
-        Attribute {
-            key: fields[0].to_owned(),
-            value: None,
-        }
-    };
+    let attribute = unmarshal_attribute(lexer, &value);
     lexer.desc.attributes.push(attribute);
 
     Ok(Some(StateFn { f: s11 }))
@@ -1195,7 +1272,7 @@ fn unmarshal_session_attribute<'a, R: io::BufRead + io::Seek>(
 fn unmarshal_media_description<'a, R: io::BufRead + io::Seek>(
     lexer: &mut Lexer<'a, R>,
 ) -> Result<Option<StateFn<'a, R>>> {
-    let (value, _) = read_value(lexer.reader)?;
+    let (value, _) = read_value(lexer)?;
 
     let fields: Vec<&str> = value.split_whitespace().collect();
     if fields.len() < 4 {
@@ -1215,9 +1292,9 @@ fn unmarshal_media_description<'a, R: io::BufRead + io::Seek>(
 
     // <port>
     let parts: Vec<&str> = fields[1].split('/').collect();
-    let port_value = parts[0].parse::<u16>()? as isize;
+    let port_value = parse_field::<u16>(parts[0])? as isize;
     let port_range = if parts.len() > 1 {
-        Some(parts[1].parse::<i32>()? as isize)
+        Some(parse_field::<i32>(parts[1])? as isize)
     } else {
         None
     };
@@ -1268,7 +1345,7 @@ fn unmarshal_media_description<'a, R: io::BufRead + io::Seek>(
 fn unmarshal_media_title<'a, R: io::BufRead + io::Seek>(
     lexer: &mut Lexer<'a, R>,
 ) -> Result<Option<StateFn<'a, R>>> {
-    let (value, _) = read_value(lexer.reader)?;
+    let (value, _) = read_value(lexer)?;
 
     if let Some(latest_media_desc) = lexer.desc.media_descriptions.last_mut() {
         latest_media_desc.media_title = Some(value);
@@ -1281,7 +1358,7 @@ fn unmarshal_media_title<'a, R: io::BufRead + io::Seek>(
 fn unmarshal_media_connection_information<'a, R: io::BufRead + io::Seek>(
     lexer: &mut Lexer<'a, R>,
 ) -> Result<Option<StateFn<'a, R>>> {
-    let (value, _) = read_value(lexer.reader)?;
+    let (value, _) = read_value(lexer)?;
 
     if let Some(latest_media_desc) = lexer.desc.media_descriptions.last_mut() {
         latest_media_desc.connection_information = unmarshal_connection_information(&value)?;
@@ -1294,7 +1371,7 @@ fn unmarshal_media_connection_information<'a, R: io::BufRead + io::Seek>(
 fn unmarshal_media_bandwidth<'a, R: io::BufRead + io::Seek>(
     lexer: &mut Lexer<'a, R>,
 ) -> Result<Option<StateFn<'a, R>>> {
-    let (value, _) = read_value(lexer.reader)?;
+    let (value, _) = read_value(lexer)?;
 
     if let Some(latest_media_desc) = lexer.desc.media_descriptions.last_mut() {
         let bandwidth = unmarshal_bandwidth(&value)?;
@@ -1308,7 +1385,7 @@ fn unmarshal_media_bandwidth<'a, R: io::BufRead + io::Seek>(
 fn unmarshal_media_encryption_key<'a, R: io::BufRead + io::Seek>(
     lexer: &mut Lexer<'a, R>,
 ) -> Result<Option<StateFn<'a, R>>> {
-    let (value, _) = read_value(lexer.reader)?;
+    let (value, _) = read_value(lexer)?;
 
     if let Some(latest_media_desc) = lexer.desc.media_descriptions.last_mut() {
         latest_media_desc.encryption_key = Some(value);
@@ -1321,10 +1398,34 @@ fn unmarshal_media_encryption_key<'a, R: io::BufRead + io::Seek>(
 fn unmarshal_media_attribute<'a, R: io::BufRead + io::Seek>(
     lexer: &mut Lexer<'a, R>,
 ) -> Result<Option<StateFn<'a, R>>> {
-    let (value, _) = read_value(lexer.reader)?;
+    let (value, _) = read_value(lexer)?;
+
+    let attribute = unmarshal_attribute(lexer, &value);
+
+    if let Some(latest_media_desc) = lexer.desc.media_descriptions.last_mut() {
+        latest_media_desc.attributes.push(attribute);
+        Ok(Some(StateFn { f: s14 }))
+    } else {
+        Err(Error::SdpEmptyTimeDescription)
+    }
+}
 
+fn unmarshal_attribute<R: io::BufRead + io::Seek>(
+    lexer: &mut Lexer<'_, R>,
+    value: &str,
+) -> Attribute {
     let fields: Vec<&str> = value.splitn(2, ':').collect();
-    let attribute = if fields.len() == 2 {
+
+    // https://tools.ietf.org/html/rfc4566#section-9
+    // att-field is a token, which can be neither empty nor contain whitespace.
+    // Such attributes have always been accepted by unmarshal, so unmarshal_lenient
+    // keeps them too and only reports them as warnings.
+    if lexer.lenient && (fields[0].is_empty() || fields[0].contains(char::is_whitespace)) {
+        let warning = lexer.locate(Error::SdpInvalidSyntax(format!("`a={value}`")));
+        lexer.warnings.push(warning);
+    }
+
+    if fields.len() == 2 {
         Attribute {
             key: fields[0].to_owned(),
             value: Some(fields[1].to_owned()),
@@ -1334,16 +1435,15 @@ fn unmarshal_media_attribute<'a, R: io::BufRead + io::Seek>(
             key: fields[0].to_owned(),
             value: None,
         }
-    };
-
-    if let Some(latest_media_desc) = lexer.desc.media_descriptions.last_mut() {
-        latest_media_desc.attributes.push(attribute);
-        Ok(Some(StateFn { f: s14 }))
-    } else {
-        Err(Error::SdpEmptyTimeDescription)
     }
 }
 
+fn parse_field<T: FromStr>(field: &str) -> Result<T> {
+    field
+        .parse::<T>()
+        .map_err(|_| Error::SdpInvalidValue(field.to_owned()))
+}
+
 fn parse_time_units(value: &str) -> Result<i64> {
     // Some time offsets in the protocol can be provided with a shorthand
     // notation. This code ensures to convert it to NTP timestamp format.
@@ -1356,7 +1456,7 @@ fn parse_time_units(value: &str) -> Result<i64> {
         Some(b's') => (&value[..len - 1], 1),     // seconds (allowed for completeness)
         _ => (value, 1),
     };
-    num.parse::<i64>()?
+    parse_field::<i64>(num)?
         .checked_mul(factor)
         .ok_or_else(|| Error::SdpInvalidValue(value.to_owned()))
 }
@@ -26,6 +26,14 @@ pub enum Error {
     SdpInvalidSyntax(String),
     #[error("SdpInvalidValue: {0}")]
     SdpInvalidValue(String),
+    #[error("sdp: line {line}, column {column}: expected `{expected}`, found `{found}`: {source}")]
+    SdpInvalidLine {
+        line: usize,
+        column: usize,
+        expected: String,
+        found: String,
+        source: Box<Error>,
+    },
     #[error("sdp: empty time_descriptions")]
     SdpEmptyTimeDescription,
     #[error("parse int: {0}")]
@@ -34,6 +42,8 @@ pub enum Error {
     ParseUrl(#[from] url::ParseError),
     #[error("parse extmap: {0}")]
     ParseExtMap(String),
+    #[error("parse attribute: {0}")]
+    ParseAttribute(String),
     #[error("{} --> {} <-- {}", .s.substring(0,*.p), .s.substring(*.p, *.p+1), .s.substring(*.p+1, .s.len()))]
     SyntaxError { s: String, p: usize },
 }
@@ -2,13 +2,28 @@ use super::description::session::SessionDescription;
 use super::error::{Error, Result};
 
 use std::io;
-use std::io::SeekFrom;
 
 pub(crate) const END_LINE: &str = "\r\n";
 
+/// Column of the first character of a value, e.g. of `0` in `v=0`.
+const VALUE_COLUMN: usize = 3;
+
 pub struct Lexer<'a, R: io::BufRead + io::Seek> {
     pub desc: SessionDescription,
     pub reader: &'a mut R,
+
+    /// line is the 1-based number of the line being parsed.
+    pub line: usize,
+    /// content is the text of the line being parsed, without line ending.
+    pub content: String,
+    /// expected describes the grammar element the parser expects next.
+    pub expected: &'static str,
+    /// column is the 1-based column of an error found before the value is parsed.
+    pub column: Option<usize>,
+    /// lenient makes the parser skip malformed lines instead of failing.
+    pub lenient: bool,
+    /// warnings collects the malformed lines found in lenient mode.
+    pub warnings: Vec<Error>,
 }
 
 pub type StateFnType<'a, R> = fn(&mut Lexer<'a, R>) -> Result<Option<StateFn<'a, R>>>;
@@ -17,35 +32,123 @@ pub struct StateFn<'a, R: io::BufRead + io::Seek> {
     pub f: StateFnType<'a, R>,
 }
 
-pub fn read_type<R: io::BufRead + io::Seek>(reader: &mut R) -> Result<(Vec<u8>, usize)> {
-    let mut b = [0; 1];
+impl<'a, R: io::BufRead + io::Seek> Lexer<'a, R> {
+    pub fn new(desc: SessionDescription, reader: &'a mut R, lenient: bool) -> Self {
+        Lexer {
+            desc,
+            reader,
+            line: 0,
+            content: String::new(),
+            expected: "v=",
+            column: None,
+            lenient,
+            warnings: vec![],
+        }
+    }
+
+    /// unexpected_type reports a line whose type is not allowed at this point.
+    pub fn unexpected_type(&mut self, key: &[u8], expected: &'static str) -> Error {
+        self.expected = expected;
+        self.column = Some(1);
+        Error::SdpInvalidSyntax(String::from_utf8_lossy(key).into_owned())
+    }
 
+    /// skippable reports whether the line being parsed may be dropped in lenient
+    /// mode. Lines that carry the structure of the description (`v=`, `o=`, `s=`,
+    /// `t=` and `m=`) and the end of input are never skipped.
+    pub fn skippable(&self, err: &Error) -> bool {
+        self.lenient
+            && !matches!(err, Error::Io(_))
+            && !matches!(
+                self.content.as_bytes().first(),
+                None | Some(b'v' | b'o' | b's' | b't' | b'm')
+            )
+    }
+
+    /// locate wraps err with the position of the line being parsed.
+    pub fn locate(&self, err: Error) -> Error {
+        let value = self.content.get(VALUE_COLUMN - 1..).unwrap_or_default();
+        let column = match (&err, self.column) {
+            (_, Some(column)) => column,
+            (Error::SdpInvalidValue(token), None) if !token.is_empty() => {
+                value.find(token.as_str()).map_or(VALUE_COLUMN, |offset| {
+                    VALUE_COLUMN + value[..offset].chars().count()
+                })
+            }
+            _ => VALUE_COLUMN,
+        };
+
+        Error::SdpInvalidLine {
+            line: self.line,
+            column,
+            expected: self.expected.to_owned(),
+            found: self.content.clone(),
+            source: Box::new(err),
+        }
+    }
+}
+
+/// read_type reads the next non-empty line and returns its type, e.g. `v=`.
+/// The value of the line is returned by the following read_value call.
+pub fn read_type<R: io::BufRead + io::Seek>(lexer: &mut Lexer<'_, R>) -> Result<(Vec<u8>, usize)> {
     loop {
-        if reader.read_exact(&mut b).is_err() {
+        let mut line = String::new();
+        let num_bytes = lexer.reader.read_line(&mut line)?;
+        lexer.line += 1;
+        lexer.column = None;
+        if num_bytes == 0 {
+            lexer.content.clear();
             return Ok((b"".to_vec(), 0));
         }
 
-        if b[0] == b'\n' || b[0] == b'\r' {
+        let content = line.trim_end_matches(['\r', '\n']);
+        if content.is_empty() {
             continue;
         }
-        reader.seek(SeekFrom::Current(-1))?;
+        lexer.content = content.to_owned();
 
-        let mut buf = Vec::with_capacity(2);
-        let num_bytes = reader.read_until(b'=', &mut buf)?;
-        if num_bytes == 0 {
-            return Ok((b"".to_vec(), num_bytes));
-        }
-        match buf.len() {
-            2 => return Ok((buf, num_bytes)),
-            _ => return Err(Error::SdpInvalidSyntax(String::from_utf8(buf)?)),
+        let bytes = content.as_bytes();
+        if bytes.len() < 2 || bytes[1] != b'=' || bytes[0].is_ascii_whitespace() {
+            lexer.expected = "<type>=<value>";
+            lexer.column = Some(1);
+            return Err(Error::SdpInvalidSyntax(content.to_owned()));
         }
+
+        return Ok((bytes[..2].to_vec(), num_bytes));
     }
 }
 
-pub fn read_value<R: io::BufRead + io::Seek>(reader: &mut R) -> Result<(String, usize)> {
-    let mut value = String::new();
-    let num_bytes = reader.read_line(&mut value)?;
-    Ok((value.trim().to_string(), num_bytes))
+/// read_value returns the value of the line whose type was read last.
+pub fn read_value<R: io::BufRead + io::Seek>(lexer: &mut Lexer<'_, R>) -> Result<(String, usize)> {
+    let key = lexer.content.get(..2).unwrap_or_default();
+    lexer.expected = grammar_of(key);
+
+    let value = lexer.content.get(2..).unwrap_or_default();
+    Ok((value.trim().to_string(), value.len()))
+}
+
+/// grammar_of returns the grammar of a line type.
+///
+/// <https://tools.ietf.org/html/rfc4566#section-5>
+fn grammar_of(key: &str) -> &'static str {
+    match key {
+        "v=" => "v=0",
+        "o=" => "o=<username> <sess-id> <sess-version> <nettype> <addrtype> <unicast-address>",
+        "s=" => "s=<session name>",
+        "i=" => "i=<session description>",
+        "u=" => "u=<uri>",
+        "e=" => "e=<email-address>",
+        "p=" => "p=<phone-number>",
+        "c=" => "c=<nettype> <addrtype> <connection-address>",
+        "b=" => "b=<bwtype>:<bandwidth>",
+        "t=" => "t=<start-time> <stop-time>",
+        "r=" => "r=<repeat interval> <active duration> <offsets from start-time>",
+        "z=" => "z=<adjustment time> <offset> <adjustment time> <offset> ...",
+        "k=" => "k=<method>[:<encryption key>]",
+        "a=" => "a=<attribute>[:<value>]",
+        "m=" => "m=<media> <port>[/<number of ports>] <proto> <fmt> ...",
+        _ => "<type>=<value>",
+    }
 }
 
 pub fn index_of(element: &str, data: &[&str]) -> i32 {
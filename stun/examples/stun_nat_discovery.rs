@@ -0,0 +1,58 @@
+use stun::nat::*;
+use stun::Error;
+
+use clap::{App, Arg};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use util::Conn;
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    let mut app = App::new("STUN NAT Discovery")
+        .version("0.1.0")
+        .author("Rain Liu <yliu@webrtc.rs>")
+        .about("An example of RFC 5780 NAT behavior discovery")
+        .arg(
+            Arg::with_name("FULLHELP")
+                .help("Prints more detailed help information")
+                .long("fullhelp"),
+        )
+        .arg(
+            Arg::with_name("server")
+                .required_unless("FULLHELP")
+                .takes_value(true)
+                .long("server")
+                .help("STUN Server supporting RFC 5780, e.g. 192.0.2.1:3478"),
+        )
+        .arg(
+            Arg::with_name("local")
+                .takes_value(true)
+                .default_value("0.0.0.0:0")
+                .long("local")
+                .help("Local address to bind to"),
+        );
+
+    let matches = app.clone().get_matches();
+
+    if matches.is_present("FULLHELP") {
+        app.print_long_help().unwrap();
+        std::process::exit(0);
+    }
+
+    let server: SocketAddr = matches
+        .value_of("server")
+        .unwrap()
+        .parse()
+        .map_err(|_| Error::Other("invalid server address".to_owned()))?;
+
+    let conn: Arc<dyn Conn + Send + Sync> =
+        Arc::new(UdpSocket::bind(matches.value_of("local").unwrap()).await?);
+    println!("Local address: {}", conn.local_addr()?);
+
+    let behavior = discover_nat_behavior(&conn, server, Duration::from_secs(3)).await?;
+    println!("{behavior}");
+
+    Ok(())
+}
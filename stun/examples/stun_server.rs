@@ -0,0 +1,97 @@
+use stun::server::*;
+use stun::Error;
+
+use clap::{App, Arg};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use tokio::net::UdpSocket;
+use util::Conn;
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    let mut app = App::new("STUN Server")
+        .version("0.1.0")
+        .author("Rain Liu <yliu@webrtc.rs>")
+        .about("An example of STUN Server")
+        .arg(
+            Arg::with_name("FULLHELP")
+                .help("Prints more detailed help information")
+                .long("fullhelp"),
+        )
+        .arg(
+            Arg::with_name("host")
+                .required_unless("FULLHELP")
+                .takes_value(true)
+                .long("host")
+                .help("IP address to listen on"),
+        )
+        .arg(
+            Arg::with_name("port")
+                .takes_value(true)
+                .default_value("3478")
+                .long("port")
+                .help("Port to listen on"),
+        )
+        .arg(
+            Arg::with_name("alt-host")
+                .takes_value(true)
+                .long("alt-host")
+                .help("Alternate IP address, enables RFC 5780 NAT behavior discovery"),
+        )
+        .arg(
+            Arg::with_name("alt-port")
+                .takes_value(true)
+                .default_value("3479")
+                .long("alt-port")
+                .help("Alternate port, used with --alt-host"),
+        );
+
+    let matches = app.clone().get_matches();
+
+    if matches.is_present("FULLHELP") {
+        app.print_long_help().unwrap();
+        std::process::exit(0);
+    }
+
+    let parse_ip = |name| -> Result<IpAddr, Error> {
+        matches
+            .value_of(name)
+            .unwrap()
+            .parse()
+            .map_err(|_| Error::Other(format!("invalid {name}")))
+    };
+    let parse_port = |name| -> Result<u16, Error> {
+        matches
+            .value_of(name)
+            .unwrap()
+            .parse()
+            .map_err(|_| Error::Other(format!("invalid {name}")))
+    };
+
+    let mut addrs = vec![SocketAddr::new(parse_ip("host")?, parse_port("port")?)];
+    if matches.is_present("alt-host") {
+        let (ip, port) = (parse_ip("host")?, parse_port("port")?);
+        let (alt_ip, alt_port) = (parse_ip("alt-host")?, parse_port("alt-port")?);
+        addrs.push(SocketAddr::new(ip, alt_port));
+        addrs.push(SocketAddr::new(alt_ip, port));
+        addrs.push(SocketAddr::new(alt_ip, alt_port));
+    }
+
+    let mut conns: Vec<Arc<dyn Conn + Send + Sync>> = vec![];
+    for addr in addrs {
+        conns.push(Arc::new(UdpSocket::bind(addr).await?));
+        println!("Listening on {addr}");
+    }
+
+    let server = Server::new(ServerConfig {
+        conns,
+        software: "webrtc-rs stun server".to_owned(),
+    })
+    .await?;
+
+    println!("Waiting for Ctrl-C...");
+    let _ = tokio::signal::ctrl_c().await;
+    server.close().await?;
+
+    Ok(())
+}
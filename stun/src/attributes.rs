@@ -50,6 +50,7 @@ impl fmt::Display for AttrType {
             ATTR_USE_CANDIDATE => "USE-CANDIDATE",
             ATTR_ICE_CONTROLLED => "ICE-CONTROLLED",
             ATTR_ICE_CONTROLLING => "ICE-CONTROLLING",
+            ATTR_NOMINATION => "NOMINATION",
             ATTR_CHANNEL_NUMBER => "CHANNEL-NUMBER",
             ATTR_LIFETIME => "LIFETIME",
             ATTR_XOR_PEER_ADDRESS => "XOR-PEER-ADDRESS",
@@ -61,11 +62,15 @@ impl fmt::Display for AttrType {
             ATTR_RESERVATION_TOKEN => "RESERVATION-TOKEN",
             ATTR_CONNECTION_ID => "CONNECTION-ID",
             ATTR_REQUESTED_ADDRESS_FAMILY => "REQUESTED-ADDRESS-FAMILY",
+            ATTR_ADDITIONAL_ADDRESS_FAMILY => "ADDITIONAL-ADDRESS-FAMILY",
+            ATTR_ADDRESS_ERROR_CODE => "ADDRESS-ERROR-CODE",
             ATTR_MESSAGE_INTEGRITY_SHA256 => "MESSAGE-INTEGRITY-SHA256",
             ATTR_PASSWORD_ALGORITHM => "PASSWORD-ALGORITHM",
             ATTR_USER_HASH => "USERHASH",
             ATTR_PASSWORD_ALGORITHMS => "PASSWORD-ALGORITHMS",
             ATTR_ALTERNATE_DOMAIN => "ALTERNATE-DOMAIN",
+            ATTR_ACCESS_TOKEN => "ACCESS-TOKEN",
+            ATTR_THIRD_PARTY_AUTHORIZATION => "THIRD-PARTY-AUTHORIZATION",
             _ => other.as_str(),
         };
 
@@ -111,6 +116,9 @@ pub const ATTR_USE_CANDIDATE: AttrType = AttrType(0x0025); // USE-CANDIDATE
 pub const ATTR_ICE_CONTROLLED: AttrType = AttrType(0x8029); // ICE-CONTROLLED
 pub const ATTR_ICE_CONTROLLING: AttrType = AttrType(0x802A); // ICE-CONTROLLING
 
+/// Attributes from draft-thatcher-ice-renomination.
+pub const ATTR_NOMINATION: AttrType = AttrType(0xC001); // NOMINATION
+
 /// Attributes from RFC 5766 TURN.
 pub const ATTR_CHANNEL_NUMBER: AttrType = AttrType(0x000C); // CHANNEL-NUMBER
 pub const ATTR_LIFETIME: AttrType = AttrType(0x000D); // LIFETIME
@@ -141,6 +149,10 @@ pub const ATTR_CONNECTION_ID: AttrType = AttrType(0x002a); // CONNECTION-ID
 /// Attributes from RFC 6156 TURN IPv6.
 pub const ATTR_REQUESTED_ADDRESS_FAMILY: AttrType = AttrType(0x0017); // REQUESTED-ADDRESS-FAMILY
 
+/// Attributes from RFC 8656 TURN.
+pub const ATTR_ADDITIONAL_ADDRESS_FAMILY: AttrType = AttrType(0x8000); // ADDITIONAL-ADDRESS-FAMILY
+pub const ATTR_ADDRESS_ERROR_CODE: AttrType = AttrType(0x8001); // ADDRESS-ERROR-CODE
+
 /// Attributes from An Origin Attribute for the STUN Protocol.
 pub const ATTR_ORIGIN: AttrType = AttrType(0x802F);
 
@@ -151,6 +163,10 @@ pub const ATTR_USER_HASH: AttrType = AttrType(0x001E); // USER-HASH
 pub const ATTR_PASSWORD_ALGORITHMS: AttrType = AttrType(0x8002); // PASSWORD-ALGORITHMS
 pub const ATTR_ALTERNATE_DOMAIN: AttrType = AttrType(0x8003); // ALTERNATE-DOMAIN
 
+/// Attributes from RFC 7635 STUN Extension for Third-Party Authorization.
+pub const ATTR_ACCESS_TOKEN: AttrType = AttrType(0x001B); // ACCESS-TOKEN
+pub const ATTR_THIRD_PARTY_AUTHORIZATION: AttrType = AttrType(0x802E); // THIRD-PARTY-AUTHORIZATION
+
 /// RawAttribute is a Type-Length-Value (TLV) object that
 /// can be added to a STUN message. Attributes are divided into two
 /// types: comprehension-required and comprehension-optional.  STUN
@@ -0,0 +1,61 @@
+#[cfg(test)]
+mod change_request_test;
+
+use crate::attributes::*;
+use crate::checks::*;
+use crate::error::*;
+use crate::message::*;
+
+use std::fmt;
+
+const CHANGE_IP: u32 = 0x04;
+const CHANGE_PORT: u32 = 0x02;
+const CHANGE_REQUEST_SIZE: usize = 4; // 32 bit
+
+// ChangeRequest represents CHANGE-REQUEST attribute. It asks the server to send
+// the response from its alternate IP address and/or port.
+//
+// RFC 5780 Section 7.2
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ChangeRequest {
+    pub change_ip: bool,
+    pub change_port: bool,
+}
+
+impl fmt::Display for ChangeRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "change ip: {}, change port: {}",
+            self.change_ip, self.change_port
+        )
+    }
+}
+
+impl Setter for ChangeRequest {
+    // add_to adds CHANGE-REQUEST to message.
+    fn add_to(&self, m: &mut Message) -> Result<()> {
+        let mut flags = 0u32;
+        if self.change_ip {
+            flags |= CHANGE_IP;
+        }
+        if self.change_port {
+            flags |= CHANGE_PORT;
+        }
+        m.add(ATTR_CHANGE_REQUEST, &flags.to_be_bytes());
+        Ok(())
+    }
+}
+
+impl Getter for ChangeRequest {
+    // get_from decodes CHANGE-REQUEST from message.
+    fn get_from(&mut self, m: &Message) -> Result<()> {
+        let v = m.get(ATTR_CHANGE_REQUEST)?;
+        check_size(ATTR_CHANGE_REQUEST, v.len(), CHANGE_REQUEST_SIZE)?;
+
+        let flags = u32::from_be_bytes([v[0], v[1], v[2], v[3]]);
+        self.change_ip = flags & CHANGE_IP != 0;
+        self.change_port = flags & CHANGE_PORT != 0;
+        Ok(())
+    }
+}
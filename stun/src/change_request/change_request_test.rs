@@ -0,0 +1,34 @@
+use super::*;
+
+#[test]
+fn test_change_request() -> Result<()> {
+    for (change_ip, change_port, value) in [
+        (false, false, [0, 0, 0, 0]),
+        (false, true, [0, 0, 0, 2]),
+        (true, false, [0, 0, 0, 4]),
+        (true, true, [0, 0, 0, 6]),
+    ] {
+        let mut m = Message::new();
+        let r = ChangeRequest {
+            change_ip,
+            change_port,
+        };
+        r.add_to(&mut m)?;
+        assert_eq!(m.get(ATTR_CHANGE_REQUEST)?, value.to_vec());
+
+        let mut got = ChangeRequest::default();
+        got.get_from(&m)?;
+        assert_eq!(got, r);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_change_request_bad_size() {
+    let mut m = Message::new();
+    m.add(ATTR_CHANGE_REQUEST, &[0, 6]);
+
+    let mut r = ChangeRequest::default();
+    assert_eq!(r.get_from(&m), Err(Error::ErrAttributeSizeInvalid));
+}
@@ -39,6 +39,10 @@ pub enum Error {
     ErrFingerprintMismatch,
     #[error("FINGERPRINT before MESSAGE-INTEGRITY attribute")]
     ErrFingerprintBeforeIntegrity,
+    #[error("MESSAGE-INTEGRITY-SHA256 before MESSAGE-INTEGRITY attribute")]
+    ErrIntegritySha256BeforeIntegrity,
+    #[error("unsupported password algorithm")]
+    ErrUnsupportedPasswordAlgorithm,
     #[error("bad UNKNOWN-ATTRIBUTES size")]
     ErrBadUnknownAttrsSize,
     #[error("invalid length of IP value")]
@@ -47,6 +51,10 @@ pub enum Error {
     ErrNoConnection,
     #[error("client is closed")]
     ErrClientClosed,
+    #[error("server is closed")]
+    ErrServerClosed,
+    #[error("server doesn't support NAT behavior discovery: no OTHER-ADDRESS")]
+    ErrNoOtherAddress,
     #[error("no agent is set")]
     ErrNoAgent,
     #[error("collector is closed")]
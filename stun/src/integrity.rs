@@ -5,6 +5,7 @@ use crate::attributes::*;
 use crate::checks::*;
 use crate::error::*;
 use crate::message::*;
+use crate::password_algorithm::*;
 
 use md5::{Digest, Md5};
 use ring::hmac;
@@ -27,6 +28,34 @@ fn new_hmac(key: &[u8], message: &[u8]) -> Vec<u8> {
     hmac::sign(&mac, message).as_ref().to_vec()
 }
 
+fn new_hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mac = hmac::Key::new(hmac::HMAC_SHA256, key);
+    hmac::sign(&mac, message).as_ref().to_vec()
+}
+
+// hmac_input adjusts the length in the header of m to cover the attributes up to
+// and including the integrity attribute t and returns the bytes preceding t, which
+// is the text used as input to HMAC. The length must be restored by the caller.
+fn hmac_input(m: &mut Message, t: AttrType) -> Result<&[u8]> {
+    let mut offset = MESSAGE_HEADER_SIZE;
+    let mut found = false;
+    for a in &m.attributes.0 {
+        let size = ATTRIBUTE_HEADER_SIZE + nearest_padded_value_length(a.length as usize);
+        if a.typ == t {
+            found = true;
+            m.length = (offset + size - MESSAGE_HEADER_SIZE) as u32;
+            break;
+        }
+        offset += size;
+    }
+    if !found {
+        return Err(Error::ErrAttributeNotFound);
+    }
+    m.write_length();
+
+    Ok(&m.raw[..offset])
+}
+
 impl fmt::Display for MessageIntegrity {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "KEY: 0x{:x?}", self.0)
@@ -44,6 +73,10 @@ impl Setter for MessageIntegrity {
             if a.typ == ATTR_FINGERPRINT {
                 return Err(Error::ErrFingerprintBeforeIntegrity);
             }
+            // MESSAGE-INTEGRITY-SHA256 must follow MESSAGE-INTEGRITY.
+            if a.typ == ATTR_MESSAGE_INTEGRITY_SHA256 {
+                return Err(Error::ErrIntegritySha256BeforeIntegrity);
+            }
         }
         // The text used as input to HMAC is the STUN message,
         // including the header, up to and including the attribute preceding the
@@ -115,3 +148,84 @@ impl MessageIntegrity {
         check_hmac(&v, &expected)
     }
 }
+
+// MessageIntegritySha256 represents MESSAGE-INTEGRITY-SHA256 attribute. It is
+// computed like MESSAGE-INTEGRITY, but with HMAC-SHA256, and may be truncated by
+// the sender to no less than 16 bytes.
+//
+// RFC 8489 Section 14.6
+#[derive(Default, Clone)]
+pub struct MessageIntegritySha256(pub Vec<u8>);
+
+pub(crate) const MESSAGE_INTEGRITY_SHA256_SIZE: usize = 32;
+const MESSAGE_INTEGRITY_SHA256_MIN_SIZE: usize = 16;
+
+impl fmt::Display for MessageIntegritySha256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "KEY: 0x{:x?}", self.0)
+    }
+}
+
+impl Setter for MessageIntegritySha256 {
+    // add_to adds MESSAGE-INTEGRITY-SHA256 attribute to message, with the
+    // untruncated HMAC.
+    fn add_to(&self, m: &mut Message) -> Result<()> {
+        for a in &m.attributes.0 {
+            // Message should not contain FINGERPRINT attribute
+            // before MESSAGE-INTEGRITY-SHA256.
+            if a.typ == ATTR_FINGERPRINT {
+                return Err(Error::ErrFingerprintBeforeIntegrity);
+            }
+        }
+        let length = m.length;
+        // Adjusting m.Length to contain MESSAGE-INTEGRITY-SHA256 TLV.
+        m.length += (MESSAGE_INTEGRITY_SHA256_SIZE + ATTRIBUTE_HEADER_SIZE) as u32;
+        m.write_length();
+        let v = new_hmac_sha256(&self.0, &m.raw);
+        m.length = length;
+
+        m.add(ATTR_MESSAGE_INTEGRITY_SHA256, &v);
+
+        Ok(())
+    }
+}
+
+impl MessageIntegritySha256 {
+    // new_long_term_integrity returns new MessageIntegritySha256 with key for
+    // long-term credentials, derived with the given password algorithm. Password,
+    // username, and realm must be SASL-prepared.
+    pub fn new_long_term_integrity(
+        username: String,
+        realm: String,
+        password: String,
+        algorithm: PasswordAlgorithm,
+    ) -> Result<Self> {
+        Ok(MessageIntegritySha256(
+            algorithm.long_term_key(&username, &realm, &password)?,
+        ))
+    }
+
+    // new_short_term_integrity returns new MessageIntegritySha256 with key for
+    // short-term credentials. Password must be SASL-prepared.
+    pub fn new_short_term_integrity(password: String) -> Self {
+        MessageIntegritySha256(password.as_bytes().to_vec())
+    }
+
+    // check checks MESSAGE-INTEGRITY-SHA256 attribute, which may be truncated.
+    pub fn check(&self, m: &mut Message) -> Result<()> {
+        let v = m.get(ATTR_MESSAGE_INTEGRITY_SHA256)?;
+        if v.len() < MESSAGE_INTEGRITY_SHA256_MIN_SIZE
+            || v.len() > MESSAGE_INTEGRITY_SHA256_SIZE
+            || v.len() % 4 != 0
+        {
+            return Err(Error::ErrAttributeSizeInvalid);
+        }
+
+        let length = m.length;
+        let expected =
+            hmac_input(m, ATTR_MESSAGE_INTEGRITY_SHA256).map(|b| new_hmac_sha256(&self.0, b));
+        m.length = length;
+        m.write_length(); // writing length back
+        check_hmac(&v, &expected?[..v.len()])
+    }
+}
@@ -91,3 +91,105 @@ fn test_message_integrity_before_fingerprint() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_message_integrity_sha256() -> Result<()> {
+    let i = MessageIntegritySha256::new_long_term_integrity(
+        "user".to_owned(),
+        "realm".to_owned(),
+        "pass".to_owned(),
+        PASSWORD_ALGORITHM_SHA256,
+    )?;
+
+    let mut m = Message::new();
+    m.write_header();
+    let a = TextAttribute {
+        attr: ATTR_SOFTWARE,
+        text: "software".to_owned(),
+    };
+    a.add_to(&mut m)?;
+    i.add_to(&mut m)?;
+    FINGERPRINT.add_to(&mut m)?;
+    m.write_header();
+    assert_eq!(
+        m.get(ATTR_MESSAGE_INTEGRITY_SHA256)?.len(),
+        MESSAGE_INTEGRITY_SHA256_SIZE
+    );
+
+    let mut d_m = Message::new();
+    d_m.raw = m.raw.clone();
+    d_m.decode()?;
+    i.check(&mut d_m)?;
+    assert_eq!(d_m.raw, m.raw, "length should be restored");
+
+    let other = MessageIntegritySha256::new_long_term_integrity(
+        "user".to_owned(),
+        "realm".to_owned(),
+        "pass".to_owned(),
+        PASSWORD_ALGORITHM_MD5,
+    )?;
+    assert_eq!(other.check(&mut d_m), Err(Error::ErrIntegrityMismatch));
+
+    Ok(())
+}
+
+#[test]
+fn test_message_integrity_sha256_truncated() -> Result<()> {
+    let i = MessageIntegritySha256::new_short_term_integrity("pwd".to_owned());
+
+    // The sender computes the HMAC with the length of the truncated attribute.
+    let truncated = |size: usize| -> Message {
+        let mut t = Message::new();
+        t.write_header();
+        t.length = (ATTRIBUTE_HEADER_SIZE + size) as u32;
+        t.write_length();
+        let v = new_hmac_sha256(&i.0, &t.raw);
+        t.length = 0;
+        t.add(ATTR_MESSAGE_INTEGRITY_SHA256, &v[..size]);
+        t.write_header();
+        t
+    };
+
+    for size in [16, 20, 28, 32] {
+        i.check(&mut truncated(size))?;
+    }
+    for size in [12, 18] {
+        assert_eq!(
+            i.check(&mut truncated(size)),
+            Err(Error::ErrAttributeSizeInvalid)
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_message_integrity_with_sha256() -> Result<()> {
+    let mut m = Message::new();
+    m.write_header();
+    let i = MessageIntegrity::new_short_term_integrity("pwd".to_owned());
+    let i256 = MessageIntegritySha256::new_short_term_integrity("pwd".to_owned());
+    i.add_to(&mut m)?;
+    i256.add_to(&mut m)?;
+    FINGERPRINT.add_to(&mut m)?;
+    i.check(&mut m)?;
+    i256.check(&mut m)?;
+
+    let mut m = Message::new();
+    m.write_header();
+    i256.add_to(&mut m)?;
+    assert_eq!(
+        i.add_to(&mut m),
+        Err(Error::ErrIntegritySha256BeforeIntegrity)
+    );
+
+    let mut m = Message::new();
+    m.write_header();
+    FINGERPRINT.add_to(&mut m)?;
+    assert_eq!(
+        i256.add_to(&mut m),
+        Err(Error::ErrFingerprintBeforeIntegrity)
+    );
+
+    Ok(())
+}
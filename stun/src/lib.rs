@@ -7,6 +7,7 @@ extern crate lazy_static;
 pub mod addr;
 pub mod agent;
 pub mod attributes;
+pub mod change_request;
 pub mod checks;
 pub mod client;
 mod error;
@@ -14,9 +15,14 @@ pub mod error_code;
 pub mod fingerprint;
 pub mod integrity;
 pub mod message;
+pub mod nat;
+pub mod password_algorithm;
+pub mod security_features;
+pub mod server;
 pub mod textattrs;
 pub mod uattrs;
 pub mod uri;
+pub mod userhash;
 pub mod xoraddr;
 
 // IANA assigned ports for "stun" protocol.
@@ -0,0 +1,225 @@
+#[cfg(test)]
+mod nat_test;
+
+use crate::addr::*;
+use crate::agent::*;
+use crate::attributes::*;
+use crate::change_request::*;
+use crate::error::*;
+use crate::error_code::*;
+use crate::fingerprint::*;
+use crate::message::*;
+use crate::xoraddr::*;
+
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::time::{Duration, Instant};
+use util::Conn;
+
+const MAX_ATTEMPTS: u32 = 3;
+const RECEIVE_MTU: usize = 1500;
+
+/// EndpointDependency describes on what a NAT bases its mapping or filtering
+/// decisions for packets exchanged with a remote endpoint.
+///
+/// RFC 4787 Section 4.1 and 5
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EndpointDependency {
+    /// EndpointIndependent means the behavior doesn't depend on the remote endpoint.
+    EndpointIndependent,
+    /// AddressDependent means the behavior depends on the IP address of the remote
+    /// endpoint.
+    AddressDependent,
+    /// AddressAndPortDependent means the behavior depends on the IP address and the
+    /// port of the remote endpoint.
+    AddressAndPortDependent,
+}
+
+impl fmt::Display for EndpointDependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match *self {
+            EndpointDependency::EndpointIndependent => "endpoint-independent",
+            EndpointDependency::AddressDependent => "address-dependent",
+            EndpointDependency::AddressAndPortDependent => "address and port-dependent",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// NatBehavior is the result of NAT behavior discovery.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NatBehavior {
+    /// mapped_address is the reflexive transport address the server saw first.
+    pub mapped_address: SocketAddr,
+    /// behind_nat is false if the mapped address is the local address of the
+    /// connection, in which case mapping is always endpoint-independent.
+    pub behind_nat: bool,
+    /// mapping describes when the NAT reuses a mapping for a new remote endpoint.
+    pub mapping: EndpointDependency,
+    /// filtering describes which remote endpoints may send packets to a mapping.
+    pub filtering: EndpointDependency,
+}
+
+impl fmt::Display for NatBehavior {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "mapped address: {}, mapping: {}, filtering: {}",
+            self.mapped_address, self.mapping, self.filtering
+        )
+    }
+}
+
+/// discover_nat_behavior classifies the mapping and filtering behavior of the NAT
+/// between conn and a STUN server that supports RFC 5780, like
+/// [`crate::server::Server`] with two IP addresses and two ports.
+///
+/// conn must not be connected, as requests are sent to several addresses of the
+/// server, and should be bound to a specific local address so that the absence of
+/// a NAT can be detected. timeout bounds every test; tests that expect no response
+/// from a filtering NAT take that long.
+///
+/// RFC 5780 Section 4.3 and 4.4
+pub async fn discover_nat_behavior(
+    conn: &Arc<dyn Conn + Send + Sync>,
+    server: SocketAddr,
+    timeout: Duration,
+) -> Result<NatBehavior> {
+    let prober = Prober { conn, timeout };
+
+    // Test I: the mapped address and the alternate address of the server.
+    let response = prober
+        .binding(server, None)
+        .await?
+        .ok_or(Error::ErrTransactionTimeOut)?;
+    let mapped_address = get_mapped_address(&response)?;
+    let mut other = OtherAddress::default();
+    if other.get_from_as(&response, ATTR_OTHER_ADDRESS).is_err() {
+        return Err(Error::ErrNoOtherAddress);
+    }
+    let other = SocketAddr::new(other.ip, other.port);
+    let behind_nat = conn.local_addr()? != mapped_address;
+
+    // The filtering tests run first: the mapping tests send to the alternate
+    // address, which would open the filter of an address-dependent NAT for it.
+    let filtering = if prober
+        .binding(
+            server,
+            Some(ChangeRequest {
+                change_ip: true,
+                change_port: true,
+            }),
+        )
+        .await?
+        .is_some()
+    {
+        EndpointDependency::EndpointIndependent
+    } else if prober
+        .binding(
+            server,
+            Some(ChangeRequest {
+                change_ip: false,
+                change_port: true,
+            }),
+        )
+        .await?
+        .is_some()
+    {
+        EndpointDependency::AddressDependent
+    } else {
+        EndpointDependency::AddressAndPortDependent
+    };
+
+    let mapping = if !behind_nat {
+        EndpointDependency::EndpointIndependent
+    } else {
+        // Test II: the alternate IP address with the primary port.
+        let response = prober
+            .binding(SocketAddr::new(other.ip(), server.port()), None)
+            .await?
+            .ok_or(Error::ErrTransactionTimeOut)?;
+        let mapped_address2 = get_mapped_address(&response)?;
+        if mapped_address2 == mapped_address {
+            EndpointDependency::EndpointIndependent
+        } else {
+            // Test III: the alternate IP address and port.
+            let response = prober
+                .binding(other, None)
+                .await?
+                .ok_or(Error::ErrTransactionTimeOut)?;
+            if get_mapped_address(&response)? == mapped_address2 {
+                EndpointDependency::AddressDependent
+            } else {
+                EndpointDependency::AddressAndPortDependent
+            }
+        }
+    };
+
+    Ok(NatBehavior {
+        mapped_address,
+        behind_nat,
+        mapping,
+        filtering,
+    })
+}
+
+fn get_mapped_address(m: &Message) -> Result<SocketAddr> {
+    let mut addr = XorMappedAddress::default();
+    addr.get_from(m)?;
+    Ok(SocketAddr::new(addr.ip, addr.port))
+}
+
+struct Prober<'a> {
+    conn: &'a Arc<dyn Conn + Send + Sync>,
+    timeout: Duration,
+}
+
+impl<'a> Prober<'a> {
+    /// binding sends a Binding request to dst, retransmitting it until timeout, and
+    /// returns the response. None is returned if no response arrived in time.
+    async fn binding(
+        &self,
+        dst: SocketAddr,
+        change: Option<ChangeRequest>,
+    ) -> Result<Option<Message>> {
+        let mut request = Message::new();
+        request.build(&[Box::<TransactionId>::default(), Box::new(BINDING_REQUEST)])?;
+        if let Some(change) = change {
+            change.add_to(&mut request)?;
+        }
+        FINGERPRINT.add_to(&mut request)?;
+
+        let mut buf = vec![0u8; RECEIVE_MTU];
+        for _ in 0..MAX_ATTEMPTS {
+            self.conn.send_to(&request.raw, dst).await?;
+
+            let deadline = Instant::now() + self.timeout / MAX_ATTEMPTS;
+            while let Ok(result) =
+                tokio::time::timeout_at(deadline, self.conn.recv_from(&mut buf)).await
+            {
+                let (n, _) = result?;
+                if !is_message(&buf[..n]) {
+                    continue;
+                }
+
+                let mut response = Message::new();
+                if response.unmarshal_binary(&buf[..n]).is_err()
+                    || response.transaction_id != request.transaction_id
+                {
+                    // A late response to an earlier test, or not STUN at all.
+                    continue;
+                }
+
+                if response.typ.class == CLASS_ERROR_RESPONSE {
+                    let mut code = ErrorCodeAttribute::default();
+                    code.get_from(&response)?;
+                    return Err(Error::Other(format!("binding failed: {code}")));
+                }
+                return Ok(Some(response));
+            }
+        }
+
+        Ok(None)
+    }
+}
@@ -0,0 +1,186 @@
+use super::*;
+use crate::server::{Server, ServerConfig};
+
+use std::net::IpAddr;
+use std::str::FromStr;
+use tokio::sync::Mutex;
+use util::vnet::nat::{EndpointDependencyType, NatType};
+use util::vnet::net::{Net, NetConfig};
+use util::vnet::router::{Nic, Router, RouterConfig};
+
+const SERVER_IPS: [&str; 2] = ["1.2.3.4", "1.2.3.5"];
+const SERVER_PORTS: [u16; 2] = [3478, 3479];
+const GLOBAL_IP: &str = "27.1.1.1";
+const LOCAL_IP: &str = "192.168.0.2";
+
+async fn connect_net2router(net: &Arc<Net>, router: &Arc<Mutex<Router>>) -> Result<()> {
+    let nic = net.get_nic()?;
+    router.lock().await.add_net(Arc::clone(&nic)).await?;
+    nic.lock().await.set_router(Arc::clone(router)).await?;
+    Ok(())
+}
+
+/// build_vnet builds a WAN with an RFC 5780 STUN server and a client net behind a
+/// NAT of nat_type, and returns the router, the server and the client connection.
+async fn build_vnet(
+    nat_type: Option<NatType>,
+) -> Result<(Arc<Mutex<Router>>, Server, Arc<dyn Conn + Send + Sync>)> {
+    let wan = Arc::new(Mutex::new(Router::new(RouterConfig {
+        cidr: "0.0.0.0/0".to_owned(),
+        ..Default::default()
+    })?));
+
+    let server_net = Arc::new(Net::new(Some(NetConfig {
+        static_ips: SERVER_IPS.iter().map(|ip| ip.to_string()).collect(),
+        ..Default::default()
+    })));
+    connect_net2router(&server_net, &wan).await?;
+
+    let client_net = if let Some(nat_type) = nat_type {
+        let lan = Arc::new(Mutex::new(Router::new(RouterConfig {
+            static_ips: vec![GLOBAL_IP.to_owned()],
+            cidr: "192.168.0.0/24".to_owned(),
+            nat_type: Some(nat_type),
+            ..Default::default()
+        })?));
+        let client_net = Arc::new(Net::new(Some(NetConfig {
+            static_ips: vec![LOCAL_IP.to_owned()],
+            ..Default::default()
+        })));
+        connect_net2router(&client_net, &lan).await?;
+
+        wan.lock().await.add_router(Arc::clone(&lan)).await?;
+        lan.lock().await.set_router(Arc::clone(&wan)).await?;
+        client_net
+    } else {
+        let client_net = Arc::new(Net::new(Some(NetConfig {
+            static_ips: vec![GLOBAL_IP.to_owned()],
+            ..Default::default()
+        })));
+        connect_net2router(&client_net, &wan).await?;
+        client_net
+    };
+
+    wan.lock().await.start().await?;
+
+    let mut conns = vec![];
+    for ip in SERVER_IPS {
+        for port in SERVER_PORTS {
+            conns.push(
+                server_net
+                    .bind(SocketAddr::new(IpAddr::from_str(ip).unwrap(), port))
+                    .await?,
+            );
+        }
+    }
+    let server = Server::new(ServerConfig {
+        conns,
+        software: String::new(),
+    })
+    .await?;
+    assert!(server.supports_change_request());
+
+    let local_ip = if nat_type.is_some() {
+        LOCAL_IP
+    } else {
+        GLOBAL_IP
+    };
+    let conn = client_net
+        .bind(SocketAddr::new(IpAddr::from_str(local_ip).unwrap(), 0))
+        .await?;
+
+    Ok((wan, server, conn))
+}
+
+#[tokio::test]
+async fn test_discover_nat_behavior() -> Result<()> {
+    use EndpointDependency::{AddressAndPortDependent, AddressDependent, EndpointIndependent};
+    use EndpointDependencyType::{
+        EndpointAddrDependent, EndpointAddrPortDependent, EndpointIndependent as Independent,
+    };
+
+    let tests = vec![
+        (
+            Independent,
+            Independent,
+            EndpointIndependent,
+            EndpointIndependent,
+        ),
+        (
+            Independent,
+            EndpointAddrDependent,
+            EndpointIndependent,
+            AddressDependent,
+        ),
+        (
+            Independent,
+            EndpointAddrPortDependent,
+            EndpointIndependent,
+            AddressAndPortDependent,
+        ),
+        (
+            EndpointAddrDependent,
+            EndpointAddrDependent,
+            AddressDependent,
+            AddressDependent,
+        ),
+        (
+            EndpointAddrPortDependent,
+            EndpointAddrPortDependent,
+            AddressAndPortDependent,
+            AddressAndPortDependent,
+        ),
+    ];
+
+    for (mapping_behavior, filtering_behavior, mapping, filtering) in tests {
+        let (wan, server, conn) = build_vnet(Some(NatType {
+            mapping_behavior,
+            filtering_behavior,
+            ..Default::default()
+        }))
+        .await?;
+
+        let behavior = discover_nat_behavior(
+            &conn,
+            SocketAddr::new(IpAddr::from_str(SERVER_IPS[0]).unwrap(), SERVER_PORTS[0]),
+            Duration::from_millis(300),
+        )
+        .await?;
+        assert!(behavior.behind_nat);
+        assert_eq!(behavior.mapped_address.ip().to_string(), GLOBAL_IP);
+        assert_eq!(
+            behavior.mapping, mapping,
+            "{mapping_behavior:?}/{filtering_behavior:?}"
+        );
+        assert_eq!(
+            behavior.filtering, filtering,
+            "{mapping_behavior:?}/{filtering_behavior:?}"
+        );
+
+        server.close().await?;
+        wan.lock().await.stop().await?;
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_discover_nat_behavior_no_nat() -> Result<()> {
+    let (wan, server, conn) = build_vnet(None).await?;
+
+    let behavior = discover_nat_behavior(
+        &conn,
+        SocketAddr::new(IpAddr::from_str(SERVER_IPS[0]).unwrap(), SERVER_PORTS[0]),
+        Duration::from_millis(300),
+    )
+    .await?;
+    assert!(!behavior.behind_nat);
+    assert_eq!(behavior.mapped_address, conn.local_addr()?);
+    assert_eq!(behavior.mapping, EndpointDependency::EndpointIndependent);
+    assert_eq!(behavior.filtering, EndpointDependency::EndpointIndependent);
+
+    server.close().await?;
+    wan.lock().await.stop().await?;
+
+    Ok(())
+}
@@ -0,0 +1,155 @@
+#[cfg(test)]
+mod password_algorithm_test;
+
+use crate::attributes::*;
+use crate::error::*;
+use crate::integrity::CREDENTIALS_SEP;
+use crate::message::*;
+
+use md5::{Digest, Md5};
+use ring::digest;
+use std::fmt;
+
+// algorithm number and parameters length are 16 bit each.
+const PASSWORD_ALGORITHM_HEADER_SIZE: usize = 4;
+
+// PasswordAlgorithm represents PASSWORD-ALGORITHM attribute and the entries of
+// PASSWORD-ALGORITHMS. It selects the hash that derives the long-term credential
+// key from username, realm and password.
+//
+// RFC 8489 Section 14.12 and 18.5
+#[derive(Debug, Default, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct PasswordAlgorithm(pub u16);
+
+// Password algorithms as registered in RFC 8489 Section 18.5.
+pub const PASSWORD_ALGORITHM_MD5: PasswordAlgorithm = PasswordAlgorithm(0x0001);
+pub const PASSWORD_ALGORITHM_SHA256: PasswordAlgorithm = PasswordAlgorithm(0x0002);
+
+impl fmt::Display for PasswordAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            PASSWORD_ALGORITHM_MD5 => write!(f, "MD5"),
+            PASSWORD_ALGORITHM_SHA256 => write!(f, "SHA-256"),
+            PasswordAlgorithm(v) => write!(f, "0x{v:x}"),
+        }
+    }
+}
+
+impl PasswordAlgorithm {
+    // is_supported returns true if long_term_key can derive keys with the algorithm.
+    pub fn is_supported(&self) -> bool {
+        *self == PASSWORD_ALGORITHM_MD5 || *self == PASSWORD_ALGORITHM_SHA256
+    }
+
+    // long_term_key returns the key for long-term credentials, which is the hash
+    // of "username:realm:password". Password, username, and realm must be
+    // SASL-prepared.
+    pub fn long_term_key(&self, username: &str, realm: &str, password: &str) -> Result<Vec<u8>> {
+        let s = [username, realm, password].join(CREDENTIALS_SEP);
+
+        match *self {
+            PASSWORD_ALGORITHM_MD5 => {
+                let mut h = Md5::new();
+                h.update(s.as_bytes());
+                Ok(h.finalize().as_slice().to_vec())
+            }
+            PASSWORD_ALGORITHM_SHA256 => Ok(digest::digest(&digest::SHA256, s.as_bytes())
+                .as_ref()
+                .to_vec()),
+            _ => Err(Error::ErrUnsupportedPasswordAlgorithm),
+        }
+    }
+
+    fn encode(&self, v: &mut Vec<u8>) {
+        // None of the registered algorithms have parameters.
+        v.extend_from_slice(&self.0.to_be_bytes());
+        v.extend_from_slice(&0u16.to_be_bytes());
+    }
+
+    // decode reads the algorithm at the start of b and returns the number of
+    // bytes it takes, including the padded parameters.
+    fn decode(b: &[u8]) -> Result<(Self, usize)> {
+        if b.len() < PASSWORD_ALGORITHM_HEADER_SIZE {
+            return Err(Error::ErrUnexpectedEof);
+        }
+        let algorithm = u16::from_be_bytes([b[0], b[1]]);
+        let params_length = u16::from_be_bytes([b[2], b[3]]) as usize;
+        let size = PASSWORD_ALGORITHM_HEADER_SIZE + nearest_padded_value_length(params_length);
+        if b.len() < PASSWORD_ALGORITHM_HEADER_SIZE + params_length {
+            return Err(Error::ErrUnexpectedEof);
+        }
+
+        Ok((PasswordAlgorithm(algorithm), size.min(b.len())))
+    }
+}
+
+impl Setter for PasswordAlgorithm {
+    // add_to adds PASSWORD-ALGORITHM to message.
+    fn add_to(&self, m: &mut Message) -> Result<()> {
+        let mut v = Vec::with_capacity(PASSWORD_ALGORITHM_HEADER_SIZE);
+        self.encode(&mut v);
+        m.add(ATTR_PASSWORD_ALGORITHM, &v);
+        Ok(())
+    }
+}
+
+impl Getter for PasswordAlgorithm {
+    // get_from decodes PASSWORD-ALGORITHM from message.
+    fn get_from(&mut self, m: &Message) -> Result<()> {
+        let v = m.get(ATTR_PASSWORD_ALGORITHM)?;
+        let (algorithm, size) = PasswordAlgorithm::decode(&v)?;
+        if size != v.len() {
+            return Err(Error::ErrAttributeSizeInvalid);
+        }
+        *self = algorithm;
+        Ok(())
+    }
+}
+
+// PasswordAlgorithms represents PASSWORD-ALGORITHMS attribute, the list of
+// password algorithms the server supports, in order of preference.
+//
+// RFC 8489 Section 14.11
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct PasswordAlgorithms(pub Vec<PasswordAlgorithm>);
+
+impl fmt::Display for PasswordAlgorithms {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s: Vec<String> = self.0.iter().map(|a| a.to_string()).collect();
+        write!(f, "{}", s.join(", "))
+    }
+}
+
+impl PasswordAlgorithms {
+    // preferred returns the first algorithm of the list that is supported.
+    pub fn preferred(&self) -> Option<PasswordAlgorithm> {
+        self.0.iter().find(|a| a.is_supported()).copied()
+    }
+}
+
+impl Setter for PasswordAlgorithms {
+    // add_to adds PASSWORD-ALGORITHMS to message.
+    fn add_to(&self, m: &mut Message) -> Result<()> {
+        let mut v = Vec::with_capacity(PASSWORD_ALGORITHM_HEADER_SIZE * self.0.len());
+        for a in &self.0 {
+            a.encode(&mut v);
+        }
+        m.add(ATTR_PASSWORD_ALGORITHMS, &v);
+        Ok(())
+    }
+}
+
+impl Getter for PasswordAlgorithms {
+    // get_from decodes PASSWORD-ALGORITHMS from message.
+    fn get_from(&mut self, m: &Message) -> Result<()> {
+        let v = m.get(ATTR_PASSWORD_ALGORITHMS)?;
+        self.0.clear();
+        let mut first = 0usize;
+        while first < v.len() {
+            let (algorithm, size) = PasswordAlgorithm::decode(&v[first..])?;
+            self.0.push(algorithm);
+            first += size;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use super::*;
+
+#[test]
+fn test_password_algorithm_long_term_key() -> Result<()> {
+    let key = PASSWORD_ALGORITHM_MD5.long_term_key("user", "realm", "pass")?;
+    assert_eq!(
+        key,
+        vec![
+            0x84, 0x93, 0xfb, 0xc5, 0x3b, 0xa5, 0x82, 0xfb, 0x4c, 0x04, 0x4c, 0x45, 0x6b, 0xdc,
+            0x40, 0xeb,
+        ]
+    );
+
+    let key = PASSWORD_ALGORITHM_SHA256.long_term_key("user", "realm", "pass")?;
+    assert_eq!(
+        key,
+        vec![
+            0x07, 0xe9, 0x34, 0x11, 0x7a, 0xbd, 0x40, 0x83, 0x6e, 0x7c, 0x63, 0x29, 0xb5, 0x47,
+            0x31, 0xb2, 0xb2, 0xd2, 0xa5, 0xf9, 0xa7, 0x1f, 0x54, 0x49, 0x22, 0xd7, 0x5e, 0x07,
+            0x30, 0xd8, 0x25, 0x1b,
+        ]
+    );
+
+    let result = PasswordAlgorithm(0x0003).long_term_key("user", "realm", "pass");
+    assert_eq!(result, Err(Error::ErrUnsupportedPasswordAlgorithm));
+
+    Ok(())
+}
+
+#[test]
+fn test_password_algorithm() -> Result<()> {
+    let mut m = Message::new();
+    m.write_header();
+    PASSWORD_ALGORITHM_SHA256.add_to(&mut m)?;
+    m.write_header();
+
+    let mut decoded = Message::new();
+    decoded.raw = m.raw.clone();
+    decoded.decode()?;
+    let mut a = PasswordAlgorithm::default();
+    a.get_from(&decoded)?;
+    assert_eq!(a, PASSWORD_ALGORITHM_SHA256);
+    assert_eq!(a.to_string(), "SHA-256");
+
+    //"Bad length"
+    {
+        let mut m = Message::new();
+        m.add(ATTR_PASSWORD_ALGORITHM, &[0, 1]);
+        let mut a = PasswordAlgorithm::default();
+        assert_eq!(a.get_from(&m), Err(Error::ErrUnexpectedEof));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_password_algorithms() -> Result<()> {
+    let algorithms = PasswordAlgorithms(vec![
+        PasswordAlgorithm(0x0042),
+        PASSWORD_ALGORITHM_SHA256,
+        PASSWORD_ALGORITHM_MD5,
+    ]);
+    assert_eq!(algorithms.to_string(), "0x42, SHA-256, MD5");
+    assert_eq!(algorithms.preferred(), Some(PASSWORD_ALGORITHM_SHA256));
+
+    let mut m = Message::new();
+    m.write_header();
+    algorithms.add_to(&mut m)?;
+    m.write_header();
+
+    let mut decoded = Message::new();
+    decoded.raw = m.raw.clone();
+    decoded.decode()?;
+    let mut got = PasswordAlgorithms::default();
+    got.get_from(&decoded)?;
+    assert_eq!(got, algorithms);
+
+    //"With parameters"
+    {
+        let mut m = Message::new();
+        m.add(
+            ATTR_PASSWORD_ALGORITHMS,
+            &[0, 0x42, 0, 3, 1, 2, 3, 0, 0, 1, 0, 0],
+        );
+        let mut got = PasswordAlgorithms::default();
+        got.get_from(&m)?;
+        assert_eq!(
+            got,
+            PasswordAlgorithms(vec![PasswordAlgorithm(0x0042), PASSWORD_ALGORITHM_MD5])
+        );
+        assert_eq!(got.preferred(), Some(PASSWORD_ALGORITHM_MD5));
+    }
+
+    assert_eq!(PasswordAlgorithms(vec![]).preferred(), None);
+
+    Ok(())
+}
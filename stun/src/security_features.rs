@@ -0,0 +1,59 @@
+#[cfg(test)]
+mod security_features_test;
+
+// NONCE_COOKIE_PREFIX starts the NONCE of a server that supports the security
+// features of RFC 8489. It is followed by the base64 encoded security feature set.
+pub const NONCE_COOKIE_PREFIX: &str = "obMatJos2";
+
+// the feature set is 24 bit, encoded as 4 base64 characters.
+const SECURITY_FEATURE_SET_SIZE: usize = 3;
+const SECURITY_FEATURE_SET_ENCODED_SIZE: usize = 4;
+
+// bit 0 (the least significant) and bit 1 of the feature set.
+const FEATURE_PASSWORD_ALGORITHMS: u8 = 0x01;
+const FEATURE_USERNAME_ANONYMITY: u8 = 0x02;
+
+// SecurityFeatures are the features a server announces in the nonce cookie. The
+// cookie protects their negotiation against bid-down attacks, as the NONCE is
+// covered by MESSAGE-INTEGRITY.
+//
+// RFC 8489 Section 9.2 and 18.1
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub struct SecurityFeatures {
+    // password_algorithms means the server sends PASSWORD-ALGORITHMS and the
+    // client must echo it, along with the PASSWORD-ALGORITHM it chose.
+    pub password_algorithms: bool,
+    // username_anonymity means the client may send USERHASH instead of USERNAME.
+    pub username_anonymity: bool,
+}
+
+impl SecurityFeatures {
+    // nonce_cookie returns the prefix of a NONCE announcing the features.
+    pub fn nonce_cookie(&self) -> String {
+        let mut set = [0u8; SECURITY_FEATURE_SET_SIZE];
+        if self.password_algorithms {
+            set[2] |= FEATURE_PASSWORD_ALGORITHMS;
+        }
+        if self.username_anonymity {
+            set[2] |= FEATURE_USERNAME_ANONYMITY;
+        }
+        format!("{}{}", NONCE_COOKIE_PREFIX, base64::encode(set))
+    }
+
+    // from_nonce parses the features announced by nonce. None is returned if
+    // nonce has no cookie, i.e. the server doesn't support RFC 8489.
+    pub fn from_nonce(nonce: &str) -> Option<Self> {
+        let encoded = nonce
+            .strip_prefix(NONCE_COOKIE_PREFIX)?
+            .get(..SECURITY_FEATURE_SET_ENCODED_SIZE)?;
+        let set = base64::decode(encoded).ok()?;
+        if set.len() != SECURITY_FEATURE_SET_SIZE {
+            return None;
+        }
+
+        Some(SecurityFeatures {
+            password_algorithms: set[2] & FEATURE_PASSWORD_ALGORITHMS != 0,
+            username_anonymity: set[2] & FEATURE_USERNAME_ANONYMITY != 0,
+        })
+    }
+}
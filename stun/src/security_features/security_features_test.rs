@@ -0,0 +1,47 @@
+use super::*;
+
+#[test]
+fn test_security_features_nonce_cookie() {
+    let tests = vec![
+        (SecurityFeatures::default(), "obMatJos2AAAA"),
+        (
+            SecurityFeatures {
+                password_algorithms: true,
+                username_anonymity: false,
+            },
+            "obMatJos2AAAB",
+        ),
+        (
+            SecurityFeatures {
+                password_algorithms: false,
+                username_anonymity: true,
+            },
+            "obMatJos2AAAC",
+        ),
+        (
+            SecurityFeatures {
+                password_algorithms: true,
+                username_anonymity: true,
+            },
+            "obMatJos2AAAD",
+        ),
+    ];
+
+    for (features, cookie) in tests {
+        assert_eq!(features.nonce_cookie(), cookie);
+        let nonce = format!("{cookie}f//499k954d6OL34oL9FSTvy64sA");
+        assert_eq!(SecurityFeatures::from_nonce(&nonce), Some(features));
+    }
+}
+
+#[test]
+fn test_security_features_from_nonce_without_cookie() {
+    for nonce in [
+        "",
+        "f//499k954d6OL34oL9FSTvy64sA",
+        "obMatJos2",
+        "obMatJos2A",
+    ] {
+        assert_eq!(SecurityFeatures::from_nonce(nonce), None, "{nonce}");
+    }
+}
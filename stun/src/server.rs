@@ -0,0 +1,330 @@
+#[cfg(test)]
+mod server_test;
+
+use crate::addr::*;
+use crate::attributes::*;
+use crate::change_request::*;
+use crate::error::*;
+use crate::error_code::*;
+use crate::fingerprint::*;
+use crate::message::*;
+use crate::textattrs::*;
+use crate::uattrs::*;
+use crate::xoraddr::*;
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use util::Conn;
+
+const INBOUND_MTU: usize = 1500;
+
+/// ServerConfig configures a STUN Server.
+pub struct ServerConfig {
+    /// conns are the connections the server answers Binding requests on. To answer
+    /// RFC 5780 CHANGE-REQUEST, they must be bound to all four combinations of two
+    /// IP addresses and two ports, e.g. 192.0.2.1:3478, 192.0.2.1:3479,
+    /// 192.0.2.2:3478 and 192.0.2.2:3479.
+    pub conns: Vec<Arc<dyn Conn + Send + Sync>>,
+
+    /// software is sent in the SOFTWARE attribute of responses if not empty.
+    pub software: String,
+}
+
+/// Server is a STUN server that answers Binding requests with the reflexive
+/// transport address of the client.
+///
+/// When configured with two IP addresses and two ports, it also supports NAT
+/// behavior discovery: responses carry OTHER-ADDRESS and the server honors
+/// CHANGE-REQUEST by answering from its alternate address or port.
+///
+/// RFC 5389 and RFC 5780 Section 6
+pub struct Server {
+    /// read_loops serve the connections until the server is closed. It is None once
+    /// the server is closed.
+    read_loops: Mutex<Option<Vec<JoinHandle<()>>>>,
+    supports_change_request: bool,
+}
+
+impl Server {
+    /// new creates the STUN server and starts serving on every connection of config.
+    pub async fn new(config: ServerConfig) -> Result<Self> {
+        if config.conns.is_empty() {
+            return Err(Error::ErrNoConnection);
+        }
+
+        let listeners = Arc::new(Listeners::new(config.conns, config.software)?);
+        let supports_change_request = listeners.alternate.is_some();
+
+        let mut read_loops = vec![];
+        for (local, conn) in &listeners.conns {
+            read_loops.push(tokio::spawn(Server::read_loop(
+                *local,
+                Arc::clone(conn),
+                Arc::clone(&listeners),
+            )));
+        }
+
+        Ok(Server {
+            read_loops: Mutex::new(Some(read_loops)),
+            supports_change_request,
+        })
+    }
+
+    /// supports_change_request returns true if the connections of the server cover two
+    /// IP addresses and two ports, so that it can answer RFC 5780 CHANGE-REQUEST.
+    pub fn supports_change_request(&self) -> bool {
+        self.supports_change_request
+    }
+
+    /// close stops serving and waits for the read loops to exit. The connections are
+    /// not closed.
+    pub async fn close(&self) -> Result<()> {
+        let read_loops = match self.read_loops.lock().await.take() {
+            Some(read_loops) => read_loops,
+            None => return Err(Error::ErrServerClosed),
+        };
+
+        for read_loop in read_loops {
+            read_loop.abort();
+            let _ = read_loop.await;
+        }
+
+        Ok(())
+    }
+
+    async fn read_loop(
+        local: SocketAddr,
+        conn: Arc<dyn Conn + Send + Sync>,
+        listeners: Arc<Listeners>,
+    ) {
+        let mut buf = vec![0u8; INBOUND_MTU];
+
+        loop {
+            let (n, src) = match conn.recv_from(&mut buf).await {
+                Ok(v) => v,
+                Err(err) => {
+                    log::debug!("exit read loop on error: {}", err);
+                    break;
+                }
+            };
+
+            if let Err(err) = listeners.handle(local, &conn, &buf[..n], src).await {
+                log::debug!("failed to handle STUN message from {}: {}", src, err);
+            }
+        }
+    }
+}
+
+/// Listeners are the connections of a Server, keyed by their local address.
+struct Listeners {
+    conns: Vec<(SocketAddr, Arc<dyn Conn + Send + Sync>)>,
+    /// alternate holds the two IP addresses and the two ports of the server, if the
+    /// connections cover all four combinations of them.
+    alternate: Option<([IpAddr; 2], [u16; 2])>,
+    software: String,
+}
+
+impl Listeners {
+    fn new(conns: Vec<Arc<dyn Conn + Send + Sync>>, software: String) -> Result<Self> {
+        let mut listeners = vec![];
+        for conn in conns {
+            listeners.push((conn.local_addr()?, conn));
+        }
+
+        let mut ips: Vec<IpAddr> = vec![];
+        let mut ports: Vec<u16> = vec![];
+        for (addr, _) in &listeners {
+            if !ips.contains(&addr.ip()) {
+                ips.push(addr.ip());
+            }
+            if !ports.contains(&addr.port()) {
+                ports.push(addr.port());
+            }
+        }
+
+        let alternate = if ips.len() == 2 && ports.len() == 2 {
+            let covered = ips.iter().all(|ip| {
+                ports.iter().all(|port| {
+                    listeners
+                        .iter()
+                        .any(|(addr, _)| *addr == SocketAddr::new(*ip, *port))
+                })
+            });
+            if covered {
+                Some(([ips[0], ips[1]], [ports[0], ports[1]]))
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+
+        Ok(Listeners {
+            conns: listeners,
+            alternate,
+            software,
+        })
+    }
+
+    /// other_address returns the address that differs from local in both IP address
+    /// and port.
+    fn other_address(&self, local: SocketAddr) -> Option<SocketAddr> {
+        self.changed_address(
+            local,
+            ChangeRequest {
+                change_ip: true,
+                change_port: true,
+            },
+        )
+    }
+
+    fn changed_address(&self, local: SocketAddr, change: ChangeRequest) -> Option<SocketAddr> {
+        let (ips, ports) = self.alternate?;
+
+        let mut ip = local.ip();
+        if change.change_ip {
+            ip = if ips[0] == ip { ips[1] } else { ips[0] };
+        }
+        let mut port = local.port();
+        if change.change_port {
+            port = if ports[0] == port { ports[1] } else { ports[0] };
+        }
+
+        Some(SocketAddr::new(ip, port))
+    }
+
+    fn conn(&self, addr: SocketAddr) -> Option<&Arc<dyn Conn + Send + Sync>> {
+        self.conns
+            .iter()
+            .find(|(local, _)| *local == addr)
+            .map(|(_, conn)| conn)
+    }
+
+    /// understands returns true if the server processes attributes of type t.
+    fn understands(&self, t: AttrType) -> bool {
+        t == ATTR_CHANGE_REQUEST && self.alternate.is_some()
+    }
+
+    async fn handle(
+        &self,
+        local: SocketAddr,
+        conn: &Arc<dyn Conn + Send + Sync>,
+        buf: &[u8],
+        src: SocketAddr,
+    ) -> Result<()> {
+        if !is_message(buf) {
+            return Ok(());
+        }
+
+        let mut m = Message::new();
+        m.unmarshal_binary(buf)?;
+        if m.typ.class != CLASS_REQUEST {
+            // Indications and responses are never answered.
+            return Ok(());
+        }
+        if m.contains(ATTR_FINGERPRINT) {
+            FINGERPRINT.check(&m)?;
+        }
+
+        if m.typ.method != METHOD_BINDING {
+            return self
+                .send_error(conn, src, &m, CODE_BAD_REQUEST, vec![])
+                .await;
+        }
+
+        let unknown: Vec<AttrType> = m
+            .attributes
+            .0
+            .iter()
+            .map(|a| a.typ)
+            .filter(|t| t.required() && !self.understands(*t))
+            .collect();
+        if !unknown.is_empty() {
+            return self
+                .send_error(conn, src, &m, CODE_UNKNOWN_ATTRIBUTE, unknown)
+                .await;
+        }
+
+        let mut change = ChangeRequest::default();
+        if m.contains(ATTR_CHANGE_REQUEST) && change.get_from(&m).is_err() {
+            return self
+                .send_error(conn, src, &m, CODE_BAD_REQUEST, vec![])
+                .await;
+        }
+
+        let (origin, conn) = if change.change_ip || change.change_port {
+            match self
+                .changed_address(local, change)
+                .and_then(|addr| self.conn(addr).map(|conn| (addr, conn)))
+            {
+                Some(v) => v,
+                None => return Err(Error::ErrNoConnection),
+            }
+        } else {
+            (local, conn)
+        };
+
+        let mut msg = Message::new();
+        msg.build(&[
+            Box::new(m.transaction_id),
+            Box::new(BINDING_SUCCESS),
+            Box::new(XorMappedAddress {
+                ip: src.ip(),
+                port: src.port(),
+            }),
+        ])?;
+        ResponseOrigin {
+            ip: origin.ip(),
+            port: origin.port(),
+        }
+        .add_to_as(&mut msg, ATTR_RESPONSE_ORIGIN)?;
+        if let Some(other) = self.other_address(local) {
+            OtherAddress {
+                ip: other.ip(),
+                port: other.port(),
+            }
+            .add_to_as(&mut msg, ATTR_OTHER_ADDRESS)?;
+        }
+
+        self.send(conn, src, msg).await
+    }
+
+    async fn send_error(
+        &self,
+        conn: &Arc<dyn Conn + Send + Sync>,
+        dst: SocketAddr,
+        request: &Message,
+        code: ErrorCode,
+        unknown: Vec<AttrType>,
+    ) -> Result<()> {
+        let mut msg = Message::new();
+        msg.build(&[
+            Box::new(request.transaction_id),
+            Box::new(MessageType::new(request.typ.method, CLASS_ERROR_RESPONSE)),
+            Box::new(code),
+        ])?;
+        if !unknown.is_empty() {
+            UnknownAttributes(unknown).add_to(&mut msg)?;
+        }
+
+        self.send(conn, dst, msg).await
+    }
+
+    /// send adds SOFTWARE and FINGERPRINT to msg and sends it to dst.
+    async fn send(
+        &self,
+        conn: &Arc<dyn Conn + Send + Sync>,
+        dst: SocketAddr,
+        mut msg: Message,
+    ) -> Result<()> {
+        if !self.software.is_empty() {
+            Software::new(ATTR_SOFTWARE, self.software.clone()).add_to(&mut msg)?;
+        }
+        FINGERPRINT.add_to(&mut msg)?;
+
+        conn.send_to(&msg.raw, dst).await?;
+        Ok(())
+    }
+}
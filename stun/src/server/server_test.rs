@@ -0,0 +1,133 @@
+use super::*;
+use crate::agent::TransactionId;
+
+use tokio::net::UdpSocket;
+use tokio::time::Duration;
+
+async fn new_server() -> Result<(Server, SocketAddr)> {
+    let conn = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let addr = conn.local_addr()?;
+    let server = Server::new(ServerConfig {
+        conns: vec![conn],
+        software: "webrtc-rs".to_owned(),
+    })
+    .await?;
+
+    Ok((server, addr))
+}
+
+async fn round_trip(
+    client: &UdpSocket,
+    server: SocketAddr,
+    setters: &[Box<dyn Setter>],
+) -> Result<Message> {
+    let mut request = Message::new();
+    request.build(setters)?;
+    client.send_to(&request.raw, server).await?;
+
+    let mut buf = vec![0u8; 1500];
+    let (n, from) = tokio::time::timeout(Duration::from_secs(5), client.recv_from(&mut buf))
+        .await
+        .expect("no response from server")?;
+    assert_eq!(from, server);
+
+    let mut response = Message::new();
+    response.unmarshal_binary(&buf[..n])?;
+    assert_eq!(response.transaction_id, request.transaction_id);
+    FINGERPRINT.check(&response)?;
+
+    Ok(response)
+}
+
+#[tokio::test]
+async fn test_server_binding() -> Result<()> {
+    let (server, server_addr) = new_server().await?;
+    assert!(!server.supports_change_request());
+
+    let client = UdpSocket::bind("127.0.0.1:0").await?;
+    let response = round_trip(
+        &client,
+        server_addr,
+        &[Box::<TransactionId>::default(), Box::new(BINDING_REQUEST)],
+    )
+    .await?;
+    assert_eq!(response.typ, BINDING_SUCCESS);
+
+    let mut mapped = XorMappedAddress::default();
+    mapped.get_from(&response)?;
+    assert_eq!(
+        SocketAddr::new(mapped.ip, mapped.port),
+        client.local_addr()?
+    );
+
+    let mut origin = ResponseOrigin::default();
+    origin.get_from_as(&response, ATTR_RESPONSE_ORIGIN)?;
+    assert_eq!(SocketAddr::new(origin.ip, origin.port), server_addr);
+    assert!(!response.contains(ATTR_OTHER_ADDRESS));
+
+    let software = TextAttribute::get_from_as(&response, ATTR_SOFTWARE)?;
+    assert_eq!(software.text, "webrtc-rs");
+
+    server.close().await?;
+    assert_eq!(server.close().await, Err(Error::ErrServerClosed));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_errors() -> Result<()> {
+    let (server, server_addr) = new_server().await?;
+    let client = UdpSocket::bind("127.0.0.1:0").await?;
+
+    // Without an alternate address, CHANGE-REQUEST is not understood.
+    let response = round_trip(
+        &client,
+        server_addr,
+        &[
+            Box::<TransactionId>::default(),
+            Box::new(BINDING_REQUEST),
+            Box::new(ChangeRequest {
+                change_ip: true,
+                change_port: true,
+            }),
+        ],
+    )
+    .await?;
+    assert_eq!(response.typ, BINDING_ERROR);
+    let mut code = ErrorCodeAttribute::default();
+    code.get_from(&response)?;
+    assert!(code.code == CODE_UNKNOWN_ATTRIBUTE);
+    let mut unknown = UnknownAttributes(vec![]);
+    unknown.get_from(&response)?;
+    assert_eq!(unknown.0, vec![ATTR_CHANGE_REQUEST]);
+
+    let response = round_trip(
+        &client,
+        server_addr,
+        &[
+            Box::<TransactionId>::default(),
+            Box::new(MessageType::new(METHOD_ALLOCATE, CLASS_REQUEST)),
+        ],
+    )
+    .await?;
+    assert_eq!(
+        response.typ,
+        MessageType::new(METHOD_ALLOCATE, CLASS_ERROR_RESPONSE)
+    );
+    code.get_from(&response)?;
+    assert!(code.code == CODE_BAD_REQUEST);
+
+    server.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_no_conns() {
+    let result = Server::new(ServerConfig {
+        conns: vec![],
+        software: String::new(),
+    })
+    .await;
+    assert!(matches!(result, Err(Error::ErrNoConnection)));
+}
@@ -0,0 +1,37 @@
+use super::*;
+
+#[test]
+fn test_userhash() -> Result<()> {
+    let u = UserHash::new("user", "realm");
+    assert_eq!(
+        u.to_string(),
+        "6a3029116b47aa98bcaa325399733dc1a23cd57e26b81bef3ff6531ce624e2da"
+    );
+
+    let mut m = Message::new();
+    m.write_header();
+    u.add_to(&mut m)?;
+    m.write_header();
+
+    let mut decoded = Message::new();
+    decoded.raw = m.raw.clone();
+    decoded.decode()?;
+    let mut got = UserHash::default();
+    got.get_from(&decoded)?;
+    assert_eq!(got, u);
+
+    Ok(())
+}
+
+#[test]
+fn test_userhash_bad_size() -> Result<()> {
+    let mut m = Message::new();
+    let result = UserHash(vec![1, 2, 3]).add_to(&mut m);
+    assert_eq!(result, Err(Error::ErrAttributeSizeInvalid));
+
+    m.add(ATTR_USER_HASH, &[1, 2, 3, 4]);
+    let mut u = UserHash::default();
+    assert_eq!(u.get_from(&m), Err(Error::ErrAttributeSizeInvalid));
+
+    Ok(())
+}
@@ -84,6 +84,7 @@ async fn main() -> Result<(), Error> {
         username: cred[0].to_string(),
         password: cred[1].to_string(),
         realm: realm.to_string(),
+        oauth_credential: None,
         software: String::new(),
         rto_in_ms: 0,
         conn: Arc::new(conn),
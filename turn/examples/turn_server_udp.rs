@@ -10,7 +10,6 @@ use std::str::FromStr;
 use std::sync::Arc;
 use tokio::net::UdpSocket;
 use tokio::signal;
-use tokio::time::Duration;
 use util::vnet::net::*;
 
 struct MyAuthHandler {
@@ -124,7 +123,7 @@ async fn main() -> Result<(), Error> {
         }],
         realm: realm.to_owned(),
         auth_handler: Arc::new(MyAuthHandler::new(cred_map)),
-        channel_bind_timeout: Duration::from_secs(0),
+        ..Default::default()
     })
     .await?;
 
@@ -3,7 +3,9 @@ mod allocation_manager_test;
 
 use super::*;
 use crate::error::*;
+use crate::proto::reqfamily::*;
 use crate::relay::*;
+use crate::server::events::{Event, EventHandler};
 
 use futures::future;
 use std::collections::HashMap;
@@ -13,6 +15,25 @@ use util::Conn;
 // ManagerConfig a bag of config params for Manager.
 pub struct ManagerConfig {
     pub relay_addr_generator: Box<dyn RelayAddressGenerator + Send + Sync>,
+
+    // allocation_quota limits the allocations of each username. It may be shared
+    // with other managers. None means unlimited.
+    pub allocation_quota: Option<Arc<AllocationQuota>>,
+
+    // allocation_bandwidth_limit limits the bytes per second relayed by each
+    // allocation, in both directions together. 0 means unlimited.
+    pub allocation_bandwidth_limit: usize,
+
+    // total_bandwidth_limiter limits the bytes per second relayed by all
+    // allocations. It may be shared with other managers.
+    pub total_bandwidth_limiter: Option<Arc<BandwidthLimiter>>,
+
+    // user_counters count the datagrams relayed by the allocations of each user.
+    // They may be shared with other managers.
+    pub user_counters: Option<Arc<UserCounters>>,
+
+    // event_handler receives the events of the allocations.
+    pub event_handler: Option<Arc<dyn EventHandler + Send + Sync>>,
 }
 
 // Manager is used to hold active allocations
@@ -20,6 +41,11 @@ pub struct Manager {
     allocations: AllocationMap,
     reservations: Arc<Mutex<HashMap<String, u16>>>,
     relay_addr_generator: Box<dyn RelayAddressGenerator + Send + Sync>,
+    allocation_quota: Option<Arc<AllocationQuota>>,
+    allocation_bandwidth_limit: usize,
+    total_bandwidth_limiter: Option<Arc<BandwidthLimiter>>,
+    user_counters: Option<Arc<UserCounters>>,
+    event_handler: Option<Arc<dyn EventHandler + Send + Sync>>,
 }
 
 impl Manager {
@@ -29,6 +55,11 @@ impl Manager {
             allocations: Arc::new(Mutex::new(HashMap::new())),
             reservations: Arc::new(Mutex::new(HashMap::new())),
             relay_addr_generator: config.relay_addr_generator,
+            allocation_quota: config.allocation_quota,
+            allocation_bandwidth_limit: config.allocation_bandwidth_limit,
+            total_bandwidth_limiter: config.total_bandwidth_limiter,
+            user_counters: config.user_counters,
+            event_handler: config.event_handler,
         }
     }
 
@@ -41,6 +72,21 @@ impl Manager {
         Ok(())
     }
 
+    // allocation_count returns the number of allocations.
+    pub async fn allocation_count(&self) -> usize {
+        self.allocations.lock().await.len()
+    }
+
+    // allocation_count_by_username returns the number of allocations of a username.
+    pub async fn allocation_count_by_username(&self, name: &str) -> usize {
+        self.allocations
+            .lock()
+            .await
+            .values()
+            .filter(|a| a.username.text == name)
+            .count()
+    }
+
     // Returns the information about the all [`Allocation`]s associated with
     // the specified [`FiveTuple`]s.
     pub async fn get_allocations_info(
@@ -60,6 +106,7 @@ impl Manager {
                         alloc.username.text.clone(),
                         #[cfg(feature = "metrics")]
                         alloc.relayed_bytes.load(Ordering::Acquire),
+                        alloc.stats(),
                     ),
                 );
             }
@@ -68,13 +115,22 @@ impl Manager {
         infos
     }
 
+    // supports_family returns true if relayed addresses of family can be allocated.
+    pub fn supports_family(&self, family: RequestedAddressFamily) -> bool {
+        self.relay_addr_generator.supports_family(family.is_ipv4())
+    }
+
     // get_allocation fetches the allocation matching the passed FiveTuple
     pub async fn get_allocation(&self, five_tuple: &FiveTuple) -> Option<Arc<Allocation>> {
         let allocations = self.allocations.lock().await;
         allocations.get(five_tuple).map(Arc::clone)
     }
 
-    // create_allocation creates a new allocation and starts relaying
+    // create_allocation creates a new allocation with a relayed address of
+    // requested_family and starts relaying. If additional_family is set, the
+    // allocation also gets a relayed address of that family if possible; the
+    // caller can tell with Allocation::relay_addrs.
+    #[allow(clippy::too_many_arguments)]
     pub async fn create_allocation(
         &self,
         five_tuple: FiveTuple,
@@ -82,6 +138,8 @@ impl Manager {
         requested_port: u16,
         lifetime: Duration,
         username: Username,
+        requested_family: RequestedAddressFamily,
+        additional_family: Option<RequestedAddressFamily>,
     ) -> Result<Arc<Allocation>> {
         if lifetime == Duration::from_secs(0) {
             return Err(Error::ErrLifetimeZero);
@@ -91,14 +149,50 @@ impl Manager {
             return Err(Error::ErrDupeFiveTuple);
         }
 
+        // Taken before relaying starts, so that concurrent requests can't exceed the
+        // quota. It is given back if the allocation fails, or when it is closed.
+        let quota_permit = match &self.allocation_quota {
+            Some(quota) => Some(
+                quota
+                    .acquire(&username.text)
+                    .ok_or(Error::ErrAllocationQuotaReached)?,
+            ),
+            None => None,
+        };
+
         let (relay_socket, relay_addr) = self
             .relay_addr_generator
-            .allocate_conn(true, requested_port)
+            .allocate_conn(requested_family.is_ipv4(), requested_port)
             .await?;
         let mut a = Allocation::new(turn_socket, relay_socket, relay_addr, five_tuple, username);
         a.allocations = Some(Arc::clone(&self.allocations));
+        if let Some(family) = additional_family {
+            if self.supports_family(family) {
+                match self
+                    .relay_addr_generator
+                    .allocate_conn(family.is_ipv4(), 0)
+                    .await
+                {
+                    Ok((socket, addr)) => a.additional_relay = Some((addr, socket)),
+                    Err(err) => log::warn!("failed to allocate {} relay: {}", family, err),
+                }
+            }
+        }
+        if self.allocation_bandwidth_limit != 0 {
+            a.bandwidth_limiters.push(Arc::new(BandwidthLimiter::new(
+                self.allocation_bandwidth_limit,
+            )));
+        }
+        if let Some(limiter) = &self.total_bandwidth_limiter {
+            a.bandwidth_limiters.push(Arc::clone(limiter));
+        }
+        if let Some(user_counters) = &self.user_counters {
+            a.user_counters = Some(user_counters.counters(&a.username.text));
+        }
+        a.event_handler = self.event_handler.clone();
+        *a.quota_permit.lock() = quota_permit;
 
-        log::debug!("listening on relay addr: {:?}", a.relay_addr);
+        log::debug!("listening on relay addrs: {:?}", a.relay_addrs());
         a.start(lifetime).await;
         a.packet_handler().await;
 
@@ -108,6 +202,13 @@ impl Manager {
             allocations.insert(five_tuple, Arc::clone(&a));
         }
 
+        a.emit(Event::AllocationCreated {
+            five_tuple,
+            username: a.username.text.clone(),
+            relay_addrs: a.relay_addrs(),
+            lifetime,
+        });
+
         Ok(a)
     }
 
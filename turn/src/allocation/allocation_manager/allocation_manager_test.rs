@@ -8,6 +8,7 @@ use crate::{
     relay::{relay_none::*, relay_static::RelayAddressGeneratorStatic},
     server::{
         config::{ConnConfig, ServerConfig},
+        peer_filter::PeerAddressFilter,
         Server,
     },
 };
@@ -26,6 +27,11 @@ fn new_test_manager() -> Manager {
             address: "0.0.0.0".to_owned(),
             net: Arc::new(Net::new(None)),
         }),
+        allocation_quota: None,
+        allocation_bandwidth_limit: 0,
+        total_bandwidth_limiter: None,
+        user_counters: None,
+        event_handler: None,
     };
     Manager::new(config)
 }
@@ -75,6 +81,8 @@ async fn test_packet_handler() -> Result<()> {
             0,
             DEFAULT_LIFETIME,
             TextAttribute::new(ATTR_USERNAME, "user".into()),
+            REQUESTED_FAMILY_IPV4,
+            None,
         )
         .await?;
 
@@ -180,6 +188,8 @@ async fn test_create_allocation_duplicate_five_tuple() -> Result<()> {
             0,
             DEFAULT_LIFETIME,
             TextAttribute::new(ATTR_USERNAME, "user".into()),
+            REQUESTED_FAMILY_IPV4,
+            None,
         )
         .await?;
 
@@ -190,6 +200,8 @@ async fn test_create_allocation_duplicate_five_tuple() -> Result<()> {
             0,
             DEFAULT_LIFETIME,
             TextAttribute::new(ATTR_USERNAME, "user".into()),
+            REQUESTED_FAMILY_IPV4,
+            None,
         )
         .await;
     assert!(result.is_err(), "expected error, but got ok");
@@ -215,6 +227,8 @@ async fn test_delete_allocation() -> Result<()> {
             0,
             DEFAULT_LIFETIME,
             TextAttribute::new(ATTR_USERNAME, "user".into()),
+            REQUESTED_FAMILY_IPV4,
+            None,
         )
         .await?;
 
@@ -255,6 +269,8 @@ async fn test_allocation_timeout() -> Result<()> {
                 0,
                 lifetime,
                 TextAttribute::new(ATTR_USERNAME, "user".into()),
+                REQUESTED_FAMILY_IPV4,
+                None,
             )
             .await?;
 
@@ -304,6 +320,8 @@ async fn test_manager_close() -> Result<()> {
             0,
             Duration::from_millis(100),
             TextAttribute::new(ATTR_USERNAME, "user".into()),
+            REQUESTED_FAMILY_IPV4,
+            None,
         )
         .await?;
     allocations.push(a1);
@@ -315,6 +333,8 @@ async fn test_manager_close() -> Result<()> {
             0,
             Duration::from_millis(200),
             TextAttribute::new(ATTR_USERNAME, "user".into()),
+            REQUESTED_FAMILY_IPV4,
+            None,
         )
         .await?;
     allocations.push(a2);
@@ -352,6 +372,8 @@ async fn test_delete_allocation_by_username() -> Result<()> {
             0,
             DEFAULT_LIFETIME,
             TextAttribute::new(ATTR_USERNAME, "user".into()),
+            REQUESTED_FAMILY_IPV4,
+            None,
         )
         .await?;
     let _ = m
@@ -361,6 +383,8 @@ async fn test_delete_allocation_by_username() -> Result<()> {
             0,
             DEFAULT_LIFETIME,
             TextAttribute::new(ATTR_USERNAME, "user".into()),
+            REQUESTED_FAMILY_IPV4,
+            None,
         )
         .await?;
     let _ = m
@@ -370,6 +394,8 @@ async fn test_delete_allocation_by_username() -> Result<()> {
             0,
             DEFAULT_LIFETIME,
             TextAttribute::new(ATTR_USERNAME, "user2".into()),
+            REQUESTED_FAMILY_IPV4,
+            None,
         )
         .await?;
 
@@ -410,7 +436,8 @@ async fn create_server() -> Result<(Server, u16)> {
         }],
         realm: "webrtc.rs".to_owned(),
         auth_handler: Arc::new(TestAuthHandler {}),
-        channel_bind_timeout: Duration::from_secs(0),
+        peer_address_filter: PeerAddressFilter::allow_all(),
+        ..Default::default()
     })
     .await?;
 
@@ -426,6 +453,7 @@ async fn create_client(username: String, server_port: u16) -> Result<Client> {
         username,
         password: "pass".to_owned(),
         realm: String::new(),
+        oauth_credential: None,
         software: String::new(),
         rto_in_ms: 0,
         conn,
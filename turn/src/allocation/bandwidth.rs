@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod bandwidth_test;
+
+use util::sync::Mutex as SyncMutex;
+
+use std::sync::Arc;
+
+use tokio::time::{Duration, Instant};
+
+// BURST is how long a limiter may save up unused bandwidth, so that short bursts
+// above the rate, like a video key frame, are relayed.
+const BURST: Duration = Duration::from_secs(1);
+
+// BandwidthLimiter is a token bucket that limits the rate of relayed bytes.
+pub struct BandwidthLimiter {
+    bytes_per_second: usize,
+    bucket: SyncMutex<Bucket>,
+}
+
+struct Bucket {
+    tokens: f64,
+    last: Instant,
+}
+
+impl BandwidthLimiter {
+    // new creates a limiter that allows bytes_per_second, with a full bucket.
+    pub fn new(bytes_per_second: usize) -> Self {
+        BandwidthLimiter {
+            bytes_per_second,
+            bucket: SyncMutex::new(Bucket {
+                tokens: bytes_per_second as f64 * BURST.as_secs_f64(),
+                last: Instant::now(),
+            }),
+        }
+    }
+
+    // bytes_per_second returns the rate of the limiter.
+    pub fn bytes_per_second(&self) -> usize {
+        self.bytes_per_second
+    }
+
+    // allow takes n bytes from the bucket and returns true, or returns false if
+    // relaying n bytes now would exceed the rate.
+    pub fn allow(&self, n: usize) -> bool {
+        let mut bucket = self.bucket.lock();
+        self.refill(&mut bucket, Instant::now());
+
+        if bucket.tokens < n as f64 {
+            return false;
+        }
+        bucket.tokens -= n as f64;
+        true
+    }
+
+    fn refill(&self, bucket: &mut Bucket, now: Instant) {
+        let rate = self.bytes_per_second as f64;
+        let elapsed = now.duration_since(bucket.last).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(rate * BURST.as_secs_f64());
+        bucket.last = now;
+    }
+}
+
+// allow_all takes n bytes from every limiter and returns true, or returns false
+// without taking any bytes if one of them would exceed its rate. The buckets are
+// locked in the order of limiters, so callers pass shared limiters last.
+pub fn allow_all(limiters: &[Arc<BandwidthLimiter>], n: usize) -> bool {
+    let now = Instant::now();
+    let mut buckets: Vec<_> = limiters
+        .iter()
+        .map(|l| {
+            let mut bucket = l.bucket.lock();
+            l.refill(&mut bucket, now);
+            bucket
+        })
+        .collect();
+
+    if buckets.iter().any(|bucket| bucket.tokens < n as f64) {
+        return false;
+    }
+    for bucket in &mut buckets {
+        bucket.tokens -= n as f64;
+    }
+    true
+}
@@ -0,0 +1,46 @@
+use super::*;
+
+#[test]
+fn test_bandwidth_limiter_burst() {
+    let limiter = BandwidthLimiter::new(1000);
+
+    assert!(limiter.allow(600), "should allow a burst within the rate");
+    assert!(limiter.allow(400), "should allow the rest of the burst");
+    assert!(!limiter.allow(100), "should drop once the bucket is empty");
+}
+
+#[tokio::test]
+async fn test_bandwidth_limiter_refill() {
+    let limiter = BandwidthLimiter::new(10_000);
+
+    assert!(limiter.allow(10_000));
+    assert!(!limiter.allow(200));
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(limiter.allow(200), "should refill with the rate");
+
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert!(
+        !limiter.allow(10_001),
+        "should not save up more than the burst"
+    );
+    assert!(limiter.allow(10_000));
+}
+
+#[test]
+fn test_bandwidth_limiter_allow_all() {
+    let allocation = Arc::new(BandwidthLimiter::new(1000));
+    let total = Arc::new(BandwidthLimiter::new(500));
+    let limiters = vec![Arc::clone(&allocation), Arc::clone(&total)];
+
+    assert!(allow_all(&limiters, 400));
+    assert!(
+        !allow_all(&limiters, 400),
+        "should drop once one bucket is empty"
+    );
+    assert!(
+        allocation.allow(600),
+        "a dropped packet should not take bytes from the other limiters"
+    );
+    assert!(total.allow(100));
+}
@@ -2,15 +2,22 @@
 mod allocation_test;
 
 pub mod allocation_manager;
+pub mod bandwidth;
 pub mod channel_bind;
 pub mod five_tuple;
 pub mod permission;
+pub mod quota;
+pub mod stats;
 
 use crate::error::*;
 use crate::proto::{chandata::*, channum::*, data::*, peeraddr::*, *};
+use crate::server::events::{Event, EventHandler};
+use bandwidth::*;
 use channel_bind::*;
 use five_tuple::*;
 use permission::*;
+use quota::*;
+use stats::*;
 use stun::{agent::*, message::*, textattrs::Username};
 use util::sync::Mutex as SyncMutex;
 
@@ -48,6 +55,9 @@ pub struct AllocationInfo {
     /// Relayed bytes with this [`Allocation`].
     #[cfg(feature = "metrics")]
     pub relayed_bytes: usize,
+
+    /// Datagrams relayed by this [`Allocation`] in both directions.
+    pub stats: RelayStats,
 }
 
 impl AllocationInfo {
@@ -56,12 +66,14 @@ impl AllocationInfo {
         five_tuple: FiveTuple,
         username: String,
         #[cfg(feature = "metrics")] relayed_bytes: usize,
+        stats: RelayStats,
     ) -> Self {
         Self {
             five_tuple,
             username,
             #[cfg(feature = "metrics")]
             relayed_bytes,
+            stats,
         }
     }
 }
@@ -73,6 +85,7 @@ pub struct Allocation {
     turn_socket: Arc<dyn Conn + Send + Sync>,
     pub(crate) relay_addr: SocketAddr,
     pub(crate) relay_socket: Arc<dyn Conn + Send + Sync>,
+    pub(crate) additional_relay: Option<(SocketAddr, Arc<dyn Conn + Send + Sync>)>,
     five_tuple: FiveTuple,
     username: Username,
     permissions: Arc<Mutex<HashMap<String, Permission>>>,
@@ -82,13 +95,37 @@ pub struct Allocation {
     timer_expired: Arc<AtomicBool>,
     closed: AtomicBool, // Option<mpsc::Receiver<()>>,
     pub(crate) relayed_bytes: AtomicUsize,
-    drop_tx: Option<Sender<u32>>,
+    pub(crate) bandwidth_limiters: Vec<Arc<BandwidthLimiter>>,
+    pub(crate) counters: Arc<RelayCounters>,
+    pub(crate) user_counters: Option<Arc<RelayCounters>>,
+    pub(crate) quota_permit: SyncMutex<Option<QuotaPermit>>,
+    pub(crate) event_handler: Option<Arc<dyn EventHandler + Send + Sync>>,
+    drop_tx: Vec<Sender<u32>>,
 }
 
 fn addr2ipfingerprint(addr: &SocketAddr) -> String {
     addr.ip().to_string()
 }
 
+// record_from_peer counts a datagram of n bytes relayed from a peer to the client,
+// for an allocation and its user.
+fn record_from_peer(
+    counters: &RelayCounters,
+    user_counters: &Option<Arc<RelayCounters>>,
+    n: usize,
+) {
+    counters.record_from_peer(n);
+    if let Some(user_counters) = user_counters {
+        user_counters.record_from_peer(n);
+    }
+}
+
+// allow_relay returns true if every limiter allows relaying n bytes. A packet
+// dropped by one limiter doesn't count against the others.
+fn allow_relay(limiters: &[Arc<BandwidthLimiter>], n: usize) -> bool {
+    allow_all(limiters, n)
+}
+
 impl Allocation {
     // creates a new instance of NewAllocation.
     pub fn new(
@@ -103,6 +140,7 @@ impl Allocation {
             turn_socket,
             relay_addr,
             relay_socket,
+            additional_relay: None,
             five_tuple,
             username,
             permissions: Arc::new(Mutex::new(HashMap::new())),
@@ -112,10 +150,68 @@ impl Allocation {
             timer_expired: Arc::new(AtomicBool::new(false)),
             closed: AtomicBool::new(false),
             relayed_bytes: Default::default(),
-            drop_tx: None,
+            bandwidth_limiters: vec![],
+            counters: Arc::new(RelayCounters::default()),
+            user_counters: None,
+            quota_permit: SyncMutex::new(None),
+            event_handler: None,
+            drop_tx: vec![],
+        }
+    }
+
+    // stats returns the datagrams relayed by the allocation.
+    pub fn stats(&self) -> RelayStats {
+        self.counters.stats()
+    }
+
+    // record_to_peer counts a datagram of n bytes relayed from the client to a
+    // peer, for the allocation and its user.
+    pub(crate) fn record_to_peer(&self, n: usize) {
+        self.counters.record_to_peer(n);
+        if let Some(counters) = &self.user_counters {
+            counters.record_to_peer(n);
         }
     }
 
+    // emit passes event to the event handler of the server, if any.
+    pub(crate) fn emit(&self, event: Event) {
+        if let Some(handler) = &self.event_handler {
+            handler.on_event(event);
+        }
+    }
+
+    // relay_addrs returns the relayed transport addresses of the allocation. A
+    // dual-stack allocation has an IPv4 and an IPv6 address.
+    pub fn relay_addrs(&self) -> Vec<SocketAddr> {
+        let mut addrs = vec![self.relay_addr];
+        if let Some((addr, _)) = &self.additional_relay {
+            addrs.push(*addr);
+        }
+        addrs
+    }
+
+    // relay_socket_for returns the relay socket of the address family of peer, or
+    // None if the allocation has no relayed address of that family.
+    pub(crate) fn relay_socket_for(
+        &self,
+        peer: &SocketAddr,
+    ) -> Option<&Arc<dyn Conn + Send + Sync>> {
+        if self.relay_addr.is_ipv4() == peer.is_ipv4() {
+            return Some(&self.relay_socket);
+        }
+        match &self.additional_relay {
+            Some((addr, socket)) if addr.is_ipv4() == peer.is_ipv4() => Some(socket),
+            _ => None,
+        }
+    }
+
+    // allow_relay returns true if relaying n bytes stays within the bandwidth
+    // limits of the allocation and of the server. Packets that exceed them are
+    // dropped.
+    pub(crate) fn allow_relay(&self, n: usize) -> bool {
+        allow_relay(&self.bandwidth_limiters, n)
+    }
+
     // has_permission gets the Permission from the allocation
     pub async fn has_permission(&self, addr: &SocketAddr) -> bool {
         let permissions = self.permissions.lock().await;
@@ -134,6 +230,7 @@ impl Allocation {
             }
         }
 
+        let peer = p.addr.ip();
         p.permissions = Some(Arc::clone(&self.permissions));
         p.start(PERMISSION_TIMEOUT).await;
 
@@ -141,6 +238,12 @@ impl Allocation {
             let mut permissions = self.permissions.lock().await;
             permissions.insert(fingerprint, p);
         }
+
+        self.emit(Event::PermissionAdded {
+            five_tuple: self.five_tuple,
+            username: self.username.text.clone(),
+            peer,
+        });
     }
 
     // remove_permission removes the net.Addr's fingerprint from the allocation's permissions
@@ -178,7 +281,7 @@ impl Allocation {
             }
         }
 
-        let peer = c.peer;
+        let (peer, number) = (c.peer, c.number);
 
         // Add or refresh this channel.
         c.channel_bindings = Some(Arc::clone(&self.channel_bindings));
@@ -189,6 +292,13 @@ impl Allocation {
             channel_bindings.insert(c.number, c);
         }
 
+        self.emit(Event::ChannelBound {
+            five_tuple: self.five_tuple,
+            username: self.username.text.clone(),
+            number: number.0,
+            peer,
+        });
+
         // Channel binds also refresh permissions.
         self.add_permission(Permission::new(peer)).await;
 
@@ -226,6 +336,7 @@ impl Allocation {
 
         self.closed.store(true, Ordering::Release);
         self.stop();
+        self.quota_permit.lock().take();
 
         {
             let mut permissions = self.permissions.lock().await;
@@ -242,9 +353,17 @@ impl Allocation {
         }
 
         log::trace!("allocation with {} closed!", self.five_tuple);
+        self.emit(Event::AllocationDeleted {
+            five_tuple: self.five_tuple,
+            username: self.username.text.clone(),
+            stats: self.stats(),
+        });
 
         let _ = self.turn_socket.close().await;
         let _ = self.relay_socket.close().await;
+        if let Some((_, socket)) = &self.additional_relay {
+            let _ = socket.close().await;
+        }
 
         Ok(())
     }
@@ -298,6 +417,12 @@ impl Allocation {
         if let Some(tx) = reset_tx {
             let _ = tx.send(lifetime).await;
         }
+
+        self.emit(Event::AllocationRefreshed {
+            five_tuple: self.five_tuple,
+            username: self.username.text.clone(),
+            lifetime,
+        });
     }
 
     //  https://tools.ietf.org/html/rfc5766#section-10.3
@@ -320,15 +445,28 @@ impl Allocation {
     //  transport address of the received UDP datagram.  The Data indication
     //  is then sent on the 5-tuple associated with the allocation.
     async fn packet_handler(&mut self) {
+        let mut relays = vec![(self.relay_addr, Arc::clone(&self.relay_socket))];
+        if let Some((addr, socket)) = &self.additional_relay {
+            relays.push((*addr, Arc::clone(socket)));
+        }
+
+        for (relay_addr, relay_socket) in relays {
+            self.relay_loop(relay_addr, relay_socket);
+        }
+    }
+
+    // relay_loop relays the datagrams that peers send to relay_socket.
+    fn relay_loop(&mut self, relay_addr: SocketAddr, relay_socket: Arc<dyn Conn + Send + Sync>) {
         let five_tuple = self.five_tuple;
-        let relay_addr = self.relay_addr;
-        let relay_socket = Arc::clone(&self.relay_socket);
         let turn_socket = Arc::clone(&self.turn_socket);
         let allocations = self.allocations.clone();
         let channel_bindings = Arc::clone(&self.channel_bindings);
         let permissions = Arc::clone(&self.permissions);
+        let bandwidth_limiters = self.bandwidth_limiters.clone();
+        let counters = Arc::clone(&self.counters);
+        let user_counters = self.user_counters.clone();
         let (drop_tx, drop_rx) = oneshot::channel::<u32>();
-        self.drop_tx = Some(drop_tx);
+        self.drop_tx.push(drop_tx);
 
         tokio::spawn(async move {
             let mut buffer = vec![0u8; RTP_MTU];
@@ -374,6 +512,20 @@ impl Allocation {
                     cb_number
                 };
 
+                let permitted = cb_number.is_some() || {
+                    let ps = permissions.lock().await;
+                    ps.get(&addr2ipfingerprint(&src_addr)).is_some()
+                };
+
+                if permitted && !allow_relay(&bandwidth_limiters, n) {
+                    log::debug!(
+                        "dropped {} bytes from {}, bandwidth limit exceeded",
+                        n,
+                        src_addr
+                    );
+                    continue;
+                }
+
                 if let Some(number) = cb_number {
                     let mut channel_data = ChannelData {
                         data: buffer[..n].to_vec(),
@@ -391,14 +543,11 @@ impl Allocation {
                             src_addr,
                             err
                         );
+                    } else {
+                        record_from_peer(&counters, &user_counters, n);
                     }
                 } else {
-                    let exist = {
-                        let ps = permissions.lock().await;
-                        ps.get(&addr2ipfingerprint(&src_addr)).is_some()
-                    };
-
-                    if exist {
+                    if permitted {
                         let msg = {
                             let peer_address_attr = PeerAddress {
                                 ip: src_addr.ip(),
@@ -438,6 +587,8 @@ impl Allocation {
                                     src_addr,
                                     err
                                 );
+                            } else {
+                                record_from_peer(&counters, &user_counters, n);
                             }
                         }
                     } else {
@@ -0,0 +1,68 @@
+#[cfg(test)]
+mod quota_test;
+
+use util::sync::Mutex as SyncMutex;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// AllocationQuota limits the allocations each username may hold. A server shares
+// one quota between the managers of all its listeners.
+pub struct AllocationQuota {
+    max_per_user: usize,
+    users: SyncMutex<HashMap<String, usize>>,
+}
+
+impl AllocationQuota {
+    // new creates a quota of max_per_user allocations per username.
+    pub fn new(max_per_user: usize) -> Self {
+        AllocationQuota {
+            max_per_user,
+            users: SyncMutex::new(HashMap::new()),
+        }
+    }
+
+    // max_per_user returns the allocations a username may hold.
+    pub fn max_per_user(&self) -> usize {
+        self.max_per_user
+    }
+
+    // count returns the allocations a username holds.
+    pub fn count(&self, username: &str) -> usize {
+        self.users.lock().get(username).copied().unwrap_or(0)
+    }
+
+    // acquire counts an allocation of username and returns a permit that gives it
+    // back when dropped, or returns None if the username has reached the quota.
+    pub fn acquire(self: &Arc<Self>, username: &str) -> Option<QuotaPermit> {
+        let mut users = self.users.lock();
+        let count = users.entry(username.to_owned()).or_insert(0);
+        if *count >= self.max_per_user {
+            return None;
+        }
+        *count += 1;
+
+        Some(QuotaPermit {
+            quota: Arc::clone(self),
+            username: username.to_owned(),
+        })
+    }
+}
+
+// QuotaPermit is an allocation counted by an AllocationQuota.
+pub struct QuotaPermit {
+    quota: Arc<AllocationQuota>,
+    username: String,
+}
+
+impl Drop for QuotaPermit {
+    fn drop(&mut self) {
+        let mut users = self.quota.users.lock();
+        if let Some(count) = users.get_mut(&self.username) {
+            *count -= 1;
+            if *count == 0 {
+                users.remove(&self.username);
+            }
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use super::*;
+
+#[test]
+fn test_allocation_quota() {
+    let quota = Arc::new(AllocationQuota::new(2));
+
+    let first = quota.acquire("alice");
+    let second = quota.acquire("alice");
+    assert!(first.is_some() && second.is_some());
+    assert!(quota.acquire("alice").is_none(), "should reach the quota");
+    assert!(
+        quota.acquire("bob").is_some(),
+        "the quota should be per username"
+    );
+    assert_eq!(quota.count("alice"), 2);
+
+    drop(first);
+    assert_eq!(quota.count("alice"), 1);
+    assert!(quota.acquire("alice").is_some());
+
+    drop(second);
+    assert_eq!(quota.count("alice"), 0);
+    assert!(quota.users.lock().is_empty());
+}
+
+#[test]
+fn test_allocation_quota_concurrent() {
+    let quota = Arc::new(AllocationQuota::new(3));
+
+    let permits: Vec<_> = (0..8)
+        .map(|_| {
+            let quota = Arc::clone(&quota);
+            std::thread::spawn(move || quota.acquire("alice"))
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .collect();
+
+    assert_eq!(permits.iter().filter(|p| p.is_some()).count(), 3);
+    assert_eq!(quota.count("alice"), 3);
+}
@@ -0,0 +1,150 @@
+#[cfg(test)]
+mod stats_test;
+
+use util::sync::Mutex as SyncMutex;
+
+use std::collections::HashMap;
+use std::ops::AddAssign;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// RelayStats are the datagrams relayed by an allocation, or by all allocations
+/// of a user. Only payloads are counted, without TURN, UDP and IP headers.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct RelayStats {
+    /// Bytes relayed from the client to peers.
+    pub bytes_to_peers: u64,
+    /// Datagrams relayed from the client to peers.
+    pub packets_to_peers: u64,
+    /// Bytes relayed from peers to the client.
+    pub bytes_from_peers: u64,
+    /// Datagrams relayed from peers to the client.
+    pub packets_from_peers: u64,
+}
+
+impl AddAssign for RelayStats {
+    fn add_assign(&mut self, other: Self) {
+        self.bytes_to_peers += other.bytes_to_peers;
+        self.packets_to_peers += other.packets_to_peers;
+        self.bytes_from_peers += other.bytes_from_peers;
+        self.packets_from_peers += other.packets_from_peers;
+    }
+}
+
+// RelayCounters count relayed datagrams. They are shared by the relay loops and
+// request handlers of an allocation.
+#[derive(Debug, Default)]
+pub struct RelayCounters {
+    bytes_to_peers: AtomicU64,
+    packets_to_peers: AtomicU64,
+    bytes_from_peers: AtomicU64,
+    packets_from_peers: AtomicU64,
+}
+
+impl RelayCounters {
+    // record_to_peer counts a datagram of n bytes relayed from the client to a peer.
+    pub fn record_to_peer(&self, n: usize) {
+        self.bytes_to_peers.fetch_add(n as u64, Ordering::Relaxed);
+        self.packets_to_peers.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // record_from_peer counts a datagram of n bytes relayed from a peer to the client.
+    pub fn record_from_peer(&self, n: usize) {
+        self.bytes_from_peers.fetch_add(n as u64, Ordering::Relaxed);
+        self.packets_from_peers.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // stats returns the current values of the counters.
+    pub fn stats(&self) -> RelayStats {
+        RelayStats {
+            bytes_to_peers: self.bytes_to_peers.load(Ordering::Relaxed),
+            packets_to_peers: self.packets_to_peers.load(Ordering::Relaxed),
+            bytes_from_peers: self.bytes_from_peers.load(Ordering::Relaxed),
+            packets_from_peers: self.packets_from_peers.load(Ordering::Relaxed),
+        }
+    }
+
+    // take returns the current values of the counters and resets them, without
+    // losing datagrams counted concurrently.
+    pub fn take(&self) -> RelayStats {
+        RelayStats {
+            bytes_to_peers: self.bytes_to_peers.swap(0, Ordering::Relaxed),
+            packets_to_peers: self.packets_to_peers.swap(0, Ordering::Relaxed),
+            bytes_from_peers: self.bytes_from_peers.swap(0, Ordering::Relaxed),
+            packets_from_peers: self.packets_from_peers.swap(0, Ordering::Relaxed),
+        }
+    }
+}
+
+// UserCounters count the datagrams relayed by the allocations of each user. The
+// counters of a user outlive its allocations, so that no datagram goes unbilled.
+#[derive(Debug, Default)]
+pub struct UserCounters {
+    users: SyncMutex<Users>,
+}
+
+#[derive(Debug, Default)]
+struct Users {
+    // counters are updated by the allocations and reset by take.
+    counters: HashMap<String, Arc<RelayCounters>>,
+    // taken sums what take returned for each user, so that totals never go
+    // backwards. Users are never forgotten here.
+    taken: HashMap<String, RelayStats>,
+}
+
+impl UserCounters {
+    pub fn new() -> Self {
+        UserCounters::default()
+    }
+
+    // counters returns the counters of username, which allocations of the user
+    // update.
+    pub fn counters(&self, username: &str) -> Arc<RelayCounters> {
+        let mut users = self.users.lock();
+        if let Some(counters) = users.counters.get(username) {
+            return Arc::clone(counters);
+        }
+        let counters = Arc::new(RelayCounters::default());
+        users
+            .counters
+            .insert(username.to_owned(), Arc::clone(&counters));
+        counters
+    }
+
+    // stats returns the datagrams relayed by each user since the last take.
+    pub fn stats(&self) -> HashMap<String, RelayStats> {
+        self.users
+            .lock()
+            .counters
+            .iter()
+            .map(|(username, counters)| (username.clone(), counters.stats()))
+            .collect()
+    }
+
+    // totals returns the datagrams relayed by each user since the counters were
+    // created. Unlike stats, they are not reset by take.
+    pub fn totals(&self) -> HashMap<String, RelayStats> {
+        let users = self.users.lock();
+        let mut totals = users.taken.clone();
+        for (username, counters) in &users.counters {
+            *totals.entry(username.clone()).or_default() += counters.stats();
+        }
+        totals
+    }
+
+    // take returns the datagrams relayed by each user since the last take, and
+    // forgets users without allocations.
+    pub fn take(&self) -> HashMap<String, RelayStats> {
+        let mut users = self.users.lock();
+        let Users { counters, taken } = &mut *users;
+        let stats: HashMap<String, RelayStats> = counters
+            .iter()
+            .map(|(username, counters)| (username.clone(), counters.take()))
+            .collect();
+        for (username, stats) in &stats {
+            *taken.entry(username.clone()).or_default() += *stats;
+        }
+        counters.retain(|_, counters| Arc::strong_count(counters) > 1);
+        stats
+    }
+}
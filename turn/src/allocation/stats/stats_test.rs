@@ -0,0 +1,59 @@
+use super::*;
+
+#[test]
+fn test_relay_counters() {
+    let counters = RelayCounters::default();
+    counters.record_to_peer(100);
+    counters.record_to_peer(50);
+    counters.record_from_peer(20);
+
+    let expected = RelayStats {
+        bytes_to_peers: 150,
+        packets_to_peers: 2,
+        bytes_from_peers: 20,
+        packets_from_peers: 1,
+    };
+    assert_eq!(counters.stats(), expected);
+    assert_eq!(counters.take(), expected);
+    assert_eq!(counters.stats(), RelayStats::default());
+}
+
+#[test]
+fn test_user_counters_take() {
+    let users = UserCounters::new();
+    let alice = users.counters("alice");
+    users.counters("bob").record_from_peer(10);
+    alice.record_to_peer(100);
+    users.counters("alice").record_to_peer(10);
+
+    let stats = users.take();
+    assert_eq!(stats["alice"].bytes_to_peers, 110);
+    assert_eq!(stats["alice"].packets_to_peers, 2);
+    assert_eq!(stats["bob"].bytes_from_peers, 10);
+
+    // bob has no allocation holding his counters anymore.
+    let stats = users.stats();
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats["alice"], RelayStats::default());
+
+    alice.record_to_peer(5);
+    assert_eq!(users.take()["alice"].bytes_to_peers, 5);
+}
+
+#[test]
+fn test_user_counters_totals() {
+    let users = UserCounters::new();
+    let alice = users.counters("alice");
+    alice.record_to_peer(100);
+    users.counters("bob").record_from_peer(10);
+
+    users.take();
+    alice.record_to_peer(5);
+
+    // take neither resets the totals nor forgets bob in them.
+    let totals = users.totals();
+    assert_eq!(totals["alice"].bytes_to_peers, 105);
+    assert_eq!(totals["alice"].packets_to_peers, 2);
+    assert_eq!(totals["bob"].bytes_from_peers, 10);
+    assert_eq!(users.stats()["alice"].bytes_to_peers, 5);
+}
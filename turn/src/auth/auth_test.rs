@@ -1,5 +1,8 @@
 use super::*;
 
+#[cfg(target_family = "unix")]
+use std::sync::Arc;
+
 #[test]
 fn test_lt_cred() -> Result<()> {
     let username = "1599491771";
@@ -42,7 +45,6 @@ async fn test_new_long_term_auth_handler() -> Result<()> {
 
     use std::net::IpAddr;
     use std::str::FromStr;
-    use std::sync::Arc;
     use tokio::net::UdpSocket;
     use util::vnet::net::*;
 
@@ -67,7 +69,7 @@ async fn test_new_long_term_auth_handler() -> Result<()> {
         }],
         realm: "webrtc.rs".to_owned(),
         auth_handler: Arc::new(LongTermAuthHandler::new(SHARED_SECRET.to_string())),
-        channel_bind_timeout: Duration::from_secs(0),
+        ..Default::default()
     })
     .await?;
 
@@ -82,6 +84,7 @@ async fn test_new_long_term_auth_handler() -> Result<()> {
         username,
         password,
         realm: "webrtc.rs".to_owned(),
+        oauth_credential: None,
         software: String::new(),
         rto_in_ms: 0,
         conn,
@@ -98,3 +101,149 @@ async fn test_new_long_term_auth_handler() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(target_family = "unix")]
+async fn allocate_with_auth_handler(
+    auth_handler: Arc<dyn AuthHandler + Send + Sync>,
+    password_algorithms: Vec<PasswordAlgorithm>,
+    username_anonymity: bool,
+    username: String,
+    password: String,
+) -> Result<()> {
+    use crate::client::*;
+    use crate::relay::relay_static::*;
+    use crate::server::{config::*, *};
+
+    use std::net::IpAddr;
+    use std::str::FromStr;
+    use tokio::net::UdpSocket;
+    use util::vnet::net::*;
+
+    let conn = Arc::new(UdpSocket::bind("0.0.0.0:0").await?);
+    let server_port = conn.local_addr()?.port();
+
+    let server = Server::new(ServerConfig {
+        conn_configs: vec![ConnConfig {
+            conn,
+            relay_addr_generator: Box::new(RelayAddressGeneratorStatic {
+                relay_address: IpAddr::from_str("127.0.0.1")?,
+                address: "0.0.0.0".to_owned(),
+                net: Arc::new(Net::new(None)),
+            }),
+        }],
+        realm: "webrtc.rs".to_owned(),
+        auth_handler,
+        password_algorithms,
+        username_anonymity,
+        ..Default::default()
+    })
+    .await?;
+
+    let conn = Arc::new(UdpSocket::bind("0.0.0.0:0").await?);
+
+    let client = Client::new(ClientConfig {
+        stun_serv_addr: format!("0.0.0.0:{server_port}"),
+        turn_serv_addr: format!("0.0.0.0:{server_port}"),
+        username,
+        password,
+        realm: "webrtc.rs".to_owned(),
+        oauth_credential: None,
+        software: String::new(),
+        rto_in_ms: 0,
+        conn,
+        vnet: None,
+    })
+    .await?;
+
+    client.listen().await?;
+
+    let result = client.allocate().await.map(|_| ());
+
+    client.close().await?;
+    server.close().await?;
+
+    result
+}
+
+#[cfg(target_family = "unix")]
+#[tokio::test]
+async fn test_long_term_auth_handler_password_algorithms() -> Result<()> {
+    const SHARED_SECRET: &str = "HELLO_WORLD";
+
+    for password_algorithms in [
+        vec![PASSWORD_ALGORITHM_SHA256, PASSWORD_ALGORITHM_MD5],
+        vec![PASSWORD_ALGORITHM_MD5],
+    ] {
+        let (username, password) =
+            generate_long_term_credentials(SHARED_SECRET, Duration::from_secs(60))?;
+        allocate_with_auth_handler(
+            Arc::new(LongTermAuthHandler::new(SHARED_SECRET.to_string())),
+            password_algorithms,
+            false,
+            username,
+            password,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(target_family = "unix")]
+struct UserHashAuthHandler;
+
+#[cfg(target_family = "unix")]
+impl AuthHandler for UserHashAuthHandler {
+    fn auth_handle(&self, username: &str, realm: &str, _src_addr: SocketAddr) -> Result<Vec<u8>> {
+        if username != "user" {
+            return Err(Error::ErrNoSuchUser);
+        }
+        Ok(generate_auth_key(username, realm, "pass"))
+    }
+
+    fn auth_handle_with_algorithm(
+        &self,
+        username: &str,
+        realm: &str,
+        _src_addr: SocketAddr,
+        algorithm: PasswordAlgorithm,
+    ) -> Result<Vec<u8>> {
+        if username != "user" {
+            return Err(Error::ErrNoSuchUser);
+        }
+        Ok(algorithm.long_term_key(username, realm, "pass")?)
+    }
+
+    fn username_from_userhash(&self, userhash: &[u8], realm: &str) -> Result<String> {
+        if stun::userhash::UserHash::new("user", realm).0 == userhash {
+            Ok("user".to_owned())
+        } else {
+            Err(Error::ErrNoSuchUser)
+        }
+    }
+}
+
+#[cfg(target_family = "unix")]
+#[tokio::test]
+async fn test_auth_handler_username_anonymity() -> Result<()> {
+    allocate_with_auth_handler(
+        Arc::new(UserHashAuthHandler {}),
+        vec![PASSWORD_ALGORITHM_SHA256],
+        true,
+        "user".to_owned(),
+        "pass".to_owned(),
+    )
+    .await?;
+
+    let result = allocate_with_auth_handler(
+        Arc::new(UserHashAuthHandler {}),
+        vec![PASSWORD_ALGORITHM_SHA256],
+        true,
+        "user".to_owned(),
+        "wrong".to_owned(),
+    )
+    .await;
+    assert!(result.is_err(), "wrong password should fail");
+
+    Ok(())
+}
@@ -1,6 +1,8 @@
 #[cfg(test)]
 mod auth_test;
 
+pub mod oauth;
+
 use crate::error::*;
 
 use std::net::SocketAddr;
@@ -8,9 +10,75 @@ use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use md5::{Digest, Md5};
 use ring::hmac;
+use stun::integrity::*;
+use stun::message::*;
+use stun::password_algorithm::*;
 
 pub trait AuthHandler {
     fn auth_handle(&self, username: &str, realm: &str, src_addr: SocketAddr) -> Result<Vec<u8>>;
+
+    // auth_handle_with_algorithm returns the key derived with the RFC 8489 password
+    // algorithm the client chose. The default only supports MD5, the algorithm of
+    // the keys returned by auth_handle.
+    fn auth_handle_with_algorithm(
+        &self,
+        username: &str,
+        realm: &str,
+        src_addr: SocketAddr,
+        algorithm: PasswordAlgorithm,
+    ) -> Result<Vec<u8>> {
+        if algorithm == PASSWORD_ALGORITHM_MD5 {
+            self.auth_handle(username, realm, src_addr)
+        } else {
+            Err(stun::Error::ErrUnsupportedPasswordAlgorithm.into())
+        }
+    }
+
+    // username_from_userhash returns the username whose USERHASH in realm is
+    // userhash. It is needed if the server offers username anonymity; the default
+    // knows no user.
+    fn username_from_userhash(&self, _userhash: &[u8], _realm: &str) -> Result<String> {
+        Err(Error::ErrNoSuchUser)
+    }
+}
+
+// NoAuthHandler knows no user, so every authenticated request is rejected. It is
+// the auth_handler of ServerConfig::default; only unauthenticated requests like
+// Binding are served with it.
+pub struct NoAuthHandler;
+
+impl AuthHandler for NoAuthHandler {
+    fn auth_handle(&self, _username: &str, _realm: &str, _src_addr: SocketAddr) -> Result<Vec<u8>> {
+        Err(Error::ErrNoSuchUser)
+    }
+}
+
+// Integrity is the integrity attribute of long-term authenticated messages:
+// MESSAGE-INTEGRITY, or MESSAGE-INTEGRITY-SHA256 if both ends support RFC 8489.
+#[derive(Clone)]
+pub enum Integrity {
+    Sha1(MessageIntegrity),
+    Sha256(MessageIntegritySha256),
+}
+
+impl Setter for Integrity {
+    fn add_to(&self, m: &mut Message) -> std::result::Result<(), stun::Error> {
+        match self {
+            Integrity::Sha1(mi) => mi.add_to(m),
+            Integrity::Sha256(mi) => mi.add_to(m),
+        }
+    }
+}
+
+impl Integrity {
+    // check checks the integrity attribute of m.
+    pub fn check(&self, m: &mut Message) -> Result<()> {
+        match self {
+            Integrity::Sha1(mi) => mi.check(m)?,
+            Integrity::Sha256(mi) => mi.check(m)?,
+        }
+        Ok(())
+    }
 }
 
 // generate_long_term_credentials can be used to create credentials valid for [duration] time
@@ -65,6 +133,22 @@ impl AuthHandler for LongTermAuthHandler {
         let password = long_term_credentials(username, &self.shared_secret);
         Ok(generate_auth_key(username, realm, &password))
     }
+
+    fn auth_handle_with_algorithm(
+        &self,
+        username: &str,
+        realm: &str,
+        src_addr: SocketAddr,
+        algorithm: PasswordAlgorithm,
+    ) -> Result<Vec<u8>> {
+        let key = self.auth_handle(username, realm, src_addr)?;
+        if algorithm == PASSWORD_ALGORITHM_MD5 {
+            return Ok(key);
+        }
+
+        let password = long_term_credentials(username, &self.shared_secret);
+        Ok(algorithm.long_term_key(username, realm, &password)?)
+    }
 }
 
 impl LongTermAuthHandler {
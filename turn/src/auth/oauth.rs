@@ -0,0 +1,232 @@
+#[cfg(test)]
+mod oauth_test;
+
+use crate::error::*;
+
+use ring::aead;
+use util::sync::RwLock;
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// AES-GCM tokens carry a 12 byte nonce.
+const NONCE_LEN: usize = 12;
+
+// The fraction of a second in the timestamp of a token is in 1/64000 seconds.
+const TIMESTAMP_FRACTIONS: u64 = 64000;
+
+// MAX_CLOCK_SKEW tolerates authorization servers whose clock is ahead of ours.
+const MAX_CLOCK_SKEW: Duration = Duration::from_secs(5);
+
+/// OAuthAlgorithm is the AEAD algorithm that an AS-RS key encrypts access tokens
+/// with.
+///
+/// RFC 7635 Section 4.1
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OAuthAlgorithm {
+    A128Gcm,
+    A256Gcm,
+}
+
+impl OAuthAlgorithm {
+    fn aead(&self) -> &'static aead::Algorithm {
+        match self {
+            OAuthAlgorithm::A128Gcm => &aead::AES_128_GCM,
+            OAuthAlgorithm::A256Gcm => &aead::AES_256_GCM,
+        }
+    }
+}
+
+/// OAuthKey is the AS-RS key that the authorization server and the TURN server
+/// share to encrypt and decrypt access tokens.
+#[derive(Clone)]
+pub struct OAuthKey {
+    pub key: Vec<u8>,
+    pub algorithm: OAuthAlgorithm,
+}
+
+impl OAuthKey {
+    fn aead_key(&self) -> Result<aead::LessSafeKey> {
+        let key = aead::UnboundKey::new(self.algorithm.aead(), &self.key)
+            .map_err(|_| Error::Other("invalid AS-RS key".to_owned()))?;
+        Ok(aead::LessSafeKey::new(key))
+    }
+}
+
+/// Token is the content of a self-contained access token: the session key that
+/// protects the requests of the client with MESSAGE-INTEGRITY, and the time the
+/// token is valid.
+///
+/// RFC 7635 Section 6.2
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub mac_key: Vec<u8>,
+    pub timestamp: SystemTime,
+    pub lifetime: Duration,
+}
+
+impl Token {
+    /// encrypt encrypts the token with key for the STUN server server_name, as the
+    /// authorization server does. The result is the value of ACCESS-TOKEN.
+    pub fn encrypt(&self, key: &OAuthKey, server_name: &str) -> Result<Vec<u8>> {
+        let since_epoch = self.timestamp.duration_since(UNIX_EPOCH)?;
+        let timestamp = (since_epoch.as_secs() << 16)
+            | (since_epoch.subsec_nanos() as u64 * TIMESTAMP_FRACTIONS / 1_000_000_000);
+
+        let mut block = Vec::with_capacity(2 + self.mac_key.len() + 12 + 16);
+        block.extend_from_slice(&(self.mac_key.len() as u16).to_be_bytes());
+        block.extend_from_slice(&self.mac_key);
+        block.extend_from_slice(&timestamp.to_be_bytes());
+        block.extend_from_slice(&(self.lifetime.as_secs() as u32).to_be_bytes());
+
+        let nonce: [u8; NONCE_LEN] = rand::random();
+        key.aead_key()?
+            .seal_in_place_append_tag(
+                aead::Nonce::assume_unique_for_key(nonce),
+                aead::Aad::from(server_name.as_bytes()),
+                &mut block,
+            )
+            .map_err(|_| Error::ErrInvalidAccessToken)?;
+
+        let mut token = Vec::with_capacity(2 + NONCE_LEN + block.len());
+        token.extend_from_slice(&(NONCE_LEN as u16).to_be_bytes());
+        token.extend_from_slice(&nonce);
+        token.extend_from_slice(&block);
+        Ok(token)
+    }
+
+    /// decrypt decrypts and authenticates an access token that was encrypted with
+    /// key for the STUN server server_name.
+    pub fn decrypt(token: &[u8], key: &OAuthKey, server_name: &str) -> Result<Self> {
+        if token.len() < 2 {
+            return Err(Error::ErrInvalidAccessToken);
+        }
+        let nonce_len = u16::from_be_bytes([token[0], token[1]]) as usize;
+        if nonce_len != NONCE_LEN || token.len() < 2 + nonce_len {
+            return Err(Error::ErrInvalidAccessToken);
+        }
+        let nonce = aead::Nonce::try_assume_unique_for_key(&token[2..2 + nonce_len])
+            .map_err(|_| Error::ErrInvalidAccessToken)?;
+
+        let mut block = token[2 + nonce_len..].to_vec();
+        let block = key
+            .aead_key()?
+            .open_in_place(nonce, aead::Aad::from(server_name.as_bytes()), &mut block)
+            .map_err(|_| Error::ErrInvalidAccessToken)?;
+
+        if block.len() < 2 {
+            return Err(Error::ErrInvalidAccessToken);
+        }
+        let key_len = u16::from_be_bytes([block[0], block[1]]) as usize;
+        if block.len() != 2 + key_len + 12 {
+            return Err(Error::ErrInvalidAccessToken);
+        }
+        let mac_key = block[2..2 + key_len].to_vec();
+
+        let mut timestamp = [0u8; 8];
+        timestamp.copy_from_slice(&block[2 + key_len..2 + key_len + 8]);
+        let timestamp = u64::from_be_bytes(timestamp);
+        let fraction = timestamp & 0xFFFF;
+        if fraction >= TIMESTAMP_FRACTIONS {
+            return Err(Error::ErrInvalidAccessToken);
+        }
+        let timestamp = UNIX_EPOCH
+            + Duration::new(
+                timestamp >> 16,
+                (fraction * 1_000_000_000 / TIMESTAMP_FRACTIONS) as u32,
+            );
+
+        let mut lifetime = [0u8; 4];
+        lifetime.copy_from_slice(&block[2 + key_len + 8..]);
+        let lifetime = Duration::from_secs(u32::from_be_bytes(lifetime) as u64);
+
+        Ok(Token {
+            mac_key,
+            timestamp,
+            lifetime,
+        })
+    }
+
+    /// expires returns the time the token expires.
+    pub fn expires(&self) -> SystemTime {
+        self.timestamp + self.lifetime
+    }
+
+    /// is_valid_at returns true if the token is valid at now.
+    pub fn is_valid_at(&self, now: SystemTime) -> bool {
+        self.timestamp <= now + MAX_CLOCK_SKEW && now < self.expires()
+    }
+}
+
+/// OAuthKeyTable holds the AS-RS keys of a TURN server, by key id. Clients send
+/// the key id of their access token in USERNAME. Keys can be added and removed
+/// while the server runs, to roll them over.
+pub struct OAuthKeyTable {
+    server_name: String,
+    keys: RwLock<HashMap<String, OAuthKey>>,
+}
+
+impl OAuthKeyTable {
+    /// new creates an empty key table for the STUN server server_name, which
+    /// authorization servers bind the tokens to and clients learn from
+    /// THIRD-PARTY-AUTHORIZATION.
+    pub fn new(server_name: String) -> Self {
+        OAuthKeyTable {
+            server_name,
+            keys: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// server_name returns the STUN server name of the table.
+    pub fn server_name(&self) -> &str {
+        &self.server_name
+    }
+
+    /// insert adds or replaces the key with key id kid.
+    pub fn insert(&self, kid: String, key: OAuthKey) {
+        self.keys.write().insert(kid, key);
+    }
+
+    /// remove removes the key with key id kid.
+    pub fn remove(&self, kid: &str) -> Option<OAuthKey> {
+        self.keys.write().remove(kid)
+    }
+
+    /// validate decrypts the access token with the key kid and returns its
+    /// content if the token is valid now.
+    pub fn validate(&self, kid: &str, token: &[u8]) -> Result<Token> {
+        let key = self
+            .keys
+            .read()
+            .get(kid)
+            .cloned()
+            .ok_or(Error::ErrNoSuchAccessTokenKey)?;
+
+        let token = Token::decrypt(token, &key, &self.server_name)?;
+        if !token.is_valid_at(SystemTime::now()) {
+            return Err(Error::ErrAccessTokenExpired);
+        }
+        Ok(token)
+    }
+}
+
+/// OAuthCredential is the credential of a client that authenticates with an
+/// access token: the session key of the token and the token itself, as issued
+/// by the authorization server. The USERNAME of the client is the key id.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct OAuthCredential {
+    pub mac_key: Vec<u8>,
+    pub access_token: Vec<u8>,
+}
+
+impl OAuthCredential {
+    /// from_base64 creates a credential from the base64 encoded session key and
+    /// access token, like the RTCOAuthCredential of WebRTC.
+    pub fn from_base64(mac_key: &str, access_token: &str) -> Result<Self> {
+        Ok(OAuthCredential {
+            mac_key: base64::decode(mac_key).map_err(|err| Error::Other(err.to_string()))?,
+            access_token: base64::decode(access_token)
+                .map_err(|err| Error::Other(err.to_string()))?,
+        })
+    }
+}
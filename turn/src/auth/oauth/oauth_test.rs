@@ -0,0 +1,121 @@
+use super::*;
+
+fn new_token(lifetime: Duration) -> Token {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    Token {
+        mac_key: vec![7; 20],
+        timestamp: UNIX_EPOCH + Duration::from_secs(now.as_secs()),
+        lifetime,
+    }
+}
+
+#[test]
+fn test_token_encrypt_decrypt() -> Result<()> {
+    for (algorithm, key_len) in [(OAuthAlgorithm::A128Gcm, 16), (OAuthAlgorithm::A256Gcm, 32)] {
+        let key = OAuthKey {
+            key: vec![1; key_len],
+            algorithm,
+        };
+        let token = new_token(Duration::from_secs(3600));
+
+        let encrypted = token.encrypt(&key, "turn.example.org")?;
+        assert_eq!(
+            Token::decrypt(&encrypted, &key, "turn.example.org")?,
+            token,
+            "{algorithm:?}"
+        );
+
+        let other_key = OAuthKey {
+            key: vec![2; key.key.len()],
+            algorithm,
+        };
+        assert_eq!(
+            Token::decrypt(&encrypted, &other_key, "turn.example.org"),
+            Err(Error::ErrInvalidAccessToken),
+            "should not decrypt with another key"
+        );
+        assert_eq!(
+            Token::decrypt(&encrypted, &key, "turn.example.com"),
+            Err(Error::ErrInvalidAccessToken),
+            "should not decrypt for another server"
+        );
+        assert_eq!(
+            Token::decrypt(&encrypted[..encrypted.len() - 1], &key, "turn.example.org"),
+            Err(Error::ErrInvalidAccessToken),
+            "should not decrypt a truncated token"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_token_timestamp_fraction() -> Result<()> {
+    let key = OAuthKey {
+        key: vec![1; 16],
+        algorithm: OAuthAlgorithm::A128Gcm,
+    };
+    let token = Token {
+        mac_key: vec![7; 20],
+        timestamp: UNIX_EPOCH + Duration::from_millis(1_600_000_000_500),
+        lifetime: Duration::from_secs(60),
+    };
+
+    let decrypted = Token::decrypt(&token.encrypt(&key, "")?, &key, "")?;
+    assert_eq!(decrypted.timestamp, token.timestamp);
+
+    Ok(())
+}
+
+#[test]
+fn test_oauth_key_table_validate() -> Result<()> {
+    let table = OAuthKeyTable::new("turn.example.org".to_owned());
+    let key = OAuthKey {
+        key: vec![1; 16],
+        algorithm: OAuthAlgorithm::A128Gcm,
+    };
+    table.insert("kid".to_owned(), key.clone());
+
+    let token = new_token(Duration::from_secs(3600));
+    let encrypted = token.encrypt(&key, table.server_name())?;
+    assert_eq!(table.validate("kid", &encrypted)?, token);
+    assert_eq!(
+        table.validate("other", &encrypted),
+        Err(Error::ErrNoSuchAccessTokenKey)
+    );
+
+    let mut expired = new_token(Duration::from_secs(60));
+    expired.timestamp -= Duration::from_secs(120);
+    let encrypted = expired.encrypt(&key, table.server_name())?;
+    assert_eq!(
+        table.validate("kid", &encrypted),
+        Err(Error::ErrAccessTokenExpired)
+    );
+
+    let mut future = new_token(Duration::from_secs(60));
+    future.timestamp += Duration::from_secs(60);
+    let encrypted = future.encrypt(&key, table.server_name())?;
+    assert_eq!(
+        table.validate("kid", &encrypted),
+        Err(Error::ErrAccessTokenExpired)
+    );
+
+    table.remove("kid");
+    assert_eq!(
+        table.validate("kid", &encrypted),
+        Err(Error::ErrNoSuchAccessTokenKey)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_oauth_credential_from_base64() -> Result<()> {
+    let credential = OAuthCredential::from_base64("AQID", "BAUG")?;
+    assert_eq!(credential.mac_key, vec![1, 2, 3]);
+    assert_eq!(credential.access_token, vec![4, 5, 6]);
+
+    assert!(OAuthCredential::from_base64("!", "BAUG").is_err());
+
+    Ok(())
+}
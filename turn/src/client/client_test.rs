@@ -18,6 +18,7 @@ async fn create_listening_test_client(rto_in_ms: u16) -> Result<Client> {
         username: String::new(),
         password: String::new(),
         realm: String::new(),
+        oauth_credential: None,
         software: "TEST SOFTWARE".to_owned(),
         rto_in_ms,
         conn: Arc::new(conn),
@@ -39,6 +40,7 @@ async fn create_listening_test_client_with_stun_serv() -> Result<Client> {
         username: String::new(),
         password: String::new(),
         realm: String::new(),
+        oauth_credential: None,
         software: "TEST SOFTWARE".to_owned(),
         rto_in_ms: 0,
         conn: Arc::new(conn),
@@ -149,7 +151,8 @@ async fn test_client_nonce_expiration() -> Result<()> {
         }],
         realm: "webrtc.rs".to_owned(),
         auth_handler: Arc::new(TestAuthHandler {}),
-        channel_bind_timeout: Duration::from_secs(0),
+        peer_address_filter: peer_filter::PeerAddressFilter::allow_all(),
+        ..Default::default()
     })
     .await?;
 
@@ -161,6 +164,7 @@ async fn test_client_nonce_expiration() -> Result<()> {
         username: "foo".to_owned(),
         password: "pass".to_owned(),
         realm: String::new(),
+        oauth_credential: None,
         software: String::new(),
         rto_in_ms: 0,
         conn,
@@ -187,3 +191,75 @@ async fn test_client_nonce_expiration() -> Result<()> {
 
     Ok(())
 }
+
+// An unauthenticated 300 (Try Alternate) to the first Allocate must not redirect the client
+#[tokio::test]
+async fn test_client_ignores_unauthenticated_redirect() -> Result<()> {
+    let server = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let server_addr = server.local_addr()?;
+    let alternate = UdpSocket::bind("127.0.0.1:0").await?;
+    let alternate_addr = alternate.local_addr()?;
+
+    let server2 = Arc::clone(&server);
+    tokio::spawn(async move {
+        let mut buf = vec![0u8; 1500];
+        while let Ok((n, from)) = server2.recv_from(&mut buf).await {
+            let mut req = Message::new();
+            if req.unmarshal_binary(&buf[..n]).is_err() {
+                continue;
+            }
+
+            let mut res = Message::new();
+            res.build(&[
+                Box::new(req),
+                Box::new(MessageType::new(METHOD_ALLOCATE, CLASS_ERROR_RESPONSE)),
+                Box::new(ErrorCodeAttribute {
+                    code: CODE_TRY_ALTERNATE,
+                    reason: vec![],
+                }),
+            ])
+            .unwrap();
+            AlternateServer {
+                ip: alternate_addr.ip(),
+                port: alternate_addr.port(),
+            }
+            .add_to_as(&mut res, ATTR_ALTERNATE_SERVER)
+            .unwrap();
+            FINGERPRINT.add_to(&mut res).unwrap();
+            let _ = server2.send_to(&res.raw, from).await;
+        }
+    });
+
+    let conn = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let client = Client::new(ClientConfig {
+        stun_serv_addr: server_addr.to_string(),
+        turn_serv_addr: server_addr.to_string(),
+        username: "foo".to_owned(),
+        password: "pass".to_owned(),
+        realm: String::new(),
+        oauth_credential: None,
+        software: String::new(),
+        rto_in_ms: 0,
+        conn,
+        vnet: None,
+    })
+    .await?;
+    client.listen().await?;
+
+    assert!(
+        client.allocate().await.is_err(),
+        "the allocation should fail without a nonce"
+    );
+
+    let mut buf = vec![0u8; 1500];
+    let result =
+        tokio::time::timeout(Duration::from_millis(200), alternate.recv_from(&mut buf)).await;
+    assert!(
+        result.is_err(),
+        "the client should not contact the alternate server"
+    );
+
+    client.close().await?;
+
+    Ok(())
+}
@@ -7,9 +7,11 @@ pub mod permission;
 pub mod relay_conn;
 pub mod transaction;
 
+use crate::auth::oauth::OAuthCredential;
 use crate::error::*;
 use crate::proto::{
-    chandata::*, data::*, lifetime::*, peeraddr::*, relayaddr::*, reqtrans::*, PROTO_UDP,
+    addrerror::*, chandata::*, data::*, lifetime::*, peeraddr::*, relayaddr::*, reqfamily::*,
+    reqtrans::*, split_attributes, PROTO_UDP,
 };
 use binding::*;
 use relay_conn::*;
@@ -18,11 +20,11 @@ use transaction::*;
 use std::net::SocketAddr;
 use std::str::FromStr;
 use std::sync::Arc;
+use stun::addr::*;
 use stun::agent::*;
 use stun::attributes::*;
 use stun::error_code::*;
 use stun::fingerprint::*;
-use stun::integrity::*;
 use stun::message::*;
 use stun::textattrs::*;
 use stun::xoraddr::*;
@@ -34,6 +36,7 @@ use async_trait::async_trait;
 const DEFAULT_RTO_IN_MS: u16 = 200;
 const MAX_DATA_BUFFER_SIZE: usize = u16::MAX as usize; // message size limit for Chromium
 const MAX_READ_QUEUE_SIZE: usize = 1024;
+const MAX_REDIRECTS: usize = 3;
 
 //              interval [msec]
 // 0: 0 ms      +500
@@ -52,20 +55,53 @@ pub struct ClientConfig {
     pub username: String,
     pub password: String,
     pub realm: String,
+    // oauth_credential authenticates the client with an access token of a third
+    // party (RFC 7635) instead of the password. The username is its key id.
+    pub oauth_credential: Option<OAuthCredential>,
     pub software: String,
     pub rto_in_ms: u16,
     pub conn: Arc<dyn Conn + Send + Sync>,
     pub vnet: Option<Arc<Net>>,
 }
 
+/// AllocationFamily selects the address families of the relayed transport
+/// addresses that [`Client::allocate_with_family`] requests.
+///
+/// RFC 8656 Section 7.1
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum AllocationFamily {
+    /// Ipv4 requests an IPv4 relayed transport address, like servers that don't
+    /// support RFC 8656 allocate.
+    #[default]
+    Ipv4,
+    /// Ipv6 requests an IPv6 relayed transport address.
+    Ipv6,
+    /// DualStack requests an IPv4 and an IPv6 relayed transport address. The
+    /// server may only allocate the IPv4 one.
+    DualStack,
+}
+
+impl Setter for AllocationFamily {
+    // add_to adds the attribute that requests the families to an Allocate request.
+    fn add_to(&self, m: &mut Message) -> std::result::Result<(), stun::Error> {
+        match *self {
+            AllocationFamily::Ipv4 => Ok(()),
+            AllocationFamily::Ipv6 => REQUESTED_FAMILY_IPV6.add_to(m),
+            AllocationFamily::DualStack => {
+                REQUESTED_FAMILY_IPV6.add_to_as(m, ATTR_ADDITIONAL_ADDRESS_FAMILY)
+            }
+        }
+    }
+}
+
 struct ClientInternal {
     conn: Arc<dyn Conn + Send + Sync>,
     stun_serv_addr: String,
     turn_serv_addr: String,
     username: Username,
     password: String,
+    oauth_credential: Option<OAuthCredential>,
     realm: Realm,
-    integrity: MessageIntegrity,
     software: Software,
     tr_map: Arc<Mutex<TransactionMap>>,
     binding_mgr: Arc<Mutex<BindingManager>>,
@@ -192,6 +228,7 @@ impl ClientInternal {
             turn_serv_addr,
             username: Username::new(ATTR_USERNAME, config.username),
             password: config.password,
+            oauth_credential: config.oauth_credential,
             realm: Realm::new(ATTR_REALM, config.realm),
             software: Software::new(ATTR_SOFTWARE, config.software),
             tr_map: Arc::new(Mutex::new(TransactionMap::new())),
@@ -201,7 +238,6 @@ impl ClientInternal {
             } else {
                 DEFAULT_RTO_IN_MS
             },
-            integrity: MessageIntegrity::new_short_term_integrity(String::new()),
             read_ch_tx: Arc::new(Mutex::new(None)),
         })
     }
@@ -481,7 +517,7 @@ impl ClientInternal {
     }
 
     // Allocate sends a TURN allocation request to the given transport address
-    async fn allocate(&mut self) -> Result<RelayConnConfig> {
+    async fn allocate(&mut self, family: AllocationFamily) -> Result<RelayConnConfig> {
         {
             let read_ch_tx = self.read_ch_tx.lock().await;
             log::debug!("allocate check: read_ch_tx_opt = {}", read_ch_tx.is_some());
@@ -490,6 +526,20 @@ impl ClientInternal {
             }
         }
 
+        // Servers the client was redirected from, to detect redirect loops.
+        let mut tried = vec![];
+        loop {
+            match self.allocate_on_server(family).await? {
+                AllocateResponse::Allocated(config) => return Ok(*config),
+                AllocateResponse::TryAlternate(alternate) => {
+                    self.redirect(&mut tried, alternate)?
+                }
+            }
+        }
+    }
+
+    // allocate_on_server sends an Allocate request to the current TURN server.
+    async fn allocate_on_server(&mut self, family: AllocationFamily) -> Result<AllocateResponse> {
         let mut msg = Message::new();
         msg.build(&[
             Box::new(TransactionId::new()),
@@ -497,6 +547,7 @@ impl ClientInternal {
             Box::new(RequestedTransport {
                 protocol: PROTO_UDP,
             }),
+            Box::new(family),
             Box::new(FINGERPRINT),
         ])?;
 
@@ -506,15 +557,19 @@ impl ClientInternal {
             .await?;
         let res = tr_res.msg;
 
-        // Anonymous allocate failed, trying to authenticate.
+        // Anonymous allocate failed, trying to authenticate. A redirect is only followed
+        // once it is authenticated, anyone on the path could send an unauthenticated one.
         let nonce = Nonce::get_from_as(&res, ATTR_NONCE)?;
         self.realm = Realm::get_from_as(&res, ATTR_REALM)?;
 
-        self.integrity = MessageIntegrity::new_long_term_integrity(
-            self.username.text.clone(),
-            self.realm.text.clone(),
-            self.password.clone(),
-        );
+        let credentials = Credentials::new(
+            self.username.clone(),
+            self.realm.clone(),
+            &self.password,
+            self.oauth_credential.as_ref(),
+            &nonce,
+            &res,
+        )?;
 
         // Trying to authorize.
         msg.build(&[
@@ -523,10 +578,9 @@ impl ClientInternal {
             Box::new(RequestedTransport {
                 protocol: PROTO_UDP,
             }),
-            Box::new(self.username.clone()),
-            Box::new(self.realm.clone()),
+            Box::new(family),
             Box::new(nonce.clone()),
-            Box::new(self.integrity.clone()),
+            Box::new(credentials.clone()),
             Box::new(FINGERPRINT),
         ])?;
 
@@ -534,7 +588,15 @@ impl ClientInternal {
         let tr_res = self
             .perform_transaction(&msg, &self.turn_serv_addr.clone(), false)
             .await?;
-        let res = tr_res.msg;
+        let mut res = tr_res.msg;
+
+        if let Some(alternate) = alternate_server(&res) {
+            // Only a redirect authenticated like the request is followed.
+            if credentials.check(&mut res).is_ok() {
+                return Ok(AllocateResponse::TryAlternate(alternate));
+            }
+            log::warn!("ignoring unauthenticated redirect to {}", alternate);
+        }
 
         if res.typ.class == CLASS_ERROR_RESPONSE {
             let mut code = ErrorCodeAttribute::default();
@@ -546,10 +608,27 @@ impl ClientInternal {
             }
         }
 
-        // Getting relayed addresses from response.
-        let mut relayed = RelayedAddress::default();
-        relayed.get_from(&res)?;
-        let relayed_addr = SocketAddr::new(relayed.ip, relayed.port);
+        // Getting relayed addresses from response. A dual-stack allocation has two.
+        let mut relayed_addrs = vec![];
+        for single in split_attributes(&res, ATTR_XOR_RELAYED_ADDRESS) {
+            let mut relayed = RelayedAddress::default();
+            relayed.get_from(&single)?;
+            relayed_addrs.push(SocketAddr::new(relayed.ip, relayed.port));
+        }
+        if relayed_addrs.is_empty() {
+            return Err(stun::Error::ErrAttributeNotFound.into());
+        }
+        let relayed_addr = relayed_addrs[0];
+        let additional_relayed_addr = relayed_addrs.get(1).copied();
+        if family == AllocationFamily::DualStack && additional_relayed_addr.is_none() {
+            let mut address_error = AddressErrorCode::default();
+            if address_error.get_from(&res).is_ok() {
+                log::warn!(
+                    "server did not allocate a dual-stack relay: {}",
+                    address_error
+                );
+            }
+        }
 
         // Getting lifetime from response
         let mut lifetime = Lifetime::default();
@@ -562,17 +641,56 @@ impl ClientInternal {
             log::debug!("allocate: read_ch_tx_opt = {}", read_ch_tx_opt.is_some());
         }
 
-        Ok(RelayConnConfig {
+        Ok(AllocateResponse::Allocated(Box::new(RelayConnConfig {
             relayed_addr,
-            integrity: self.integrity.clone(),
+            additional_relayed_addr,
+            credentials,
             nonce,
             lifetime: lifetime.0,
             binding_mgr: Arc::clone(&self.binding_mgr),
             read_ch_rx: Arc::new(Mutex::new(read_ch_rx)),
-        })
+        })))
+    }
+
+    // redirect makes alternate the TURN server of the client, unless the client
+    // was already redirected from it or too many times.
+    fn redirect(&mut self, tried: &mut Vec<String>, alternate: SocketAddr) -> Result<()> {
+        tried.push(self.turn_serv_addr.clone());
+        let alternate = alternate.to_string();
+        if tried.contains(&alternate) {
+            return Err(Error::ErrAlternateServerLoop);
+        }
+        if tried.len() > MAX_REDIRECTS {
+            return Err(Error::ErrTooManyRedirects);
+        }
+
+        log::debug!("redirected from {} to {}", self.turn_serv_addr, alternate);
+        self.turn_serv_addr = alternate;
+        Ok(())
     }
 }
 
+// AllocateResponse is the outcome of an Allocate transaction with one server.
+enum AllocateResponse {
+    Allocated(Box<RelayConnConfig>),
+    TryAlternate(SocketAddr),
+}
+
+// alternate_server returns the ALTERNATE-SERVER of a 300 (Try Alternate) response.
+fn alternate_server(res: &Message) -> Option<SocketAddr> {
+    if res.typ.class != CLASS_ERROR_RESPONSE {
+        return None;
+    }
+    let mut code = ErrorCodeAttribute::default();
+    if code.get_from(res).is_err() || code.code != CODE_TRY_ALTERNATE {
+        return None;
+    }
+
+    let mut alternate = AlternateServer::default();
+    alternate.get_from_as(res, ATTR_ALTERNATE_SERVER).ok()?;
+    Some(SocketAddr::new(alternate.ip, alternate.port))
+}
+
 // Client is a STUN server client
 #[derive(Clone)]
 pub struct Client {
@@ -593,12 +711,29 @@ impl Client {
     }
 
     pub async fn allocate(&self) -> Result<impl Conn> {
+        let (relay_conn, _) = self.allocate_with_family(AllocationFamily::Ipv4).await?;
+        Ok(relay_conn)
+    }
+
+    /// allocate_with_family allocates relayed transport addresses of the family.
+    /// The local address of the returned Conn is the IPv4 relayed address, or the
+    /// IPv6 one if family is [`AllocationFamily::Ipv6`]. The second value is the
+    /// IPv6 relayed address of a dual-stack allocation, if the server allocated it.
+    /// The Conn relays to peers of both families then.
+    pub async fn allocate_with_family(
+        &self,
+        family: AllocationFamily,
+    ) -> Result<(impl Conn, Option<SocketAddr>)> {
         let config = {
             let mut ci = self.client_internal.lock().await;
-            ci.allocate().await?
+            ci.allocate(family).await?
         };
+        let additional_relayed_addr = config.additional_relayed_addr;
 
-        Ok(RelayConn::new(Arc::clone(&self.client_internal), config).await)
+        Ok((
+            RelayConn::new(Arc::clone(&self.client_internal), config).await,
+            additional_relayed_addr,
+        ))
     }
 
     pub async fn close(&self) -> Result<()> {
@@ -6,7 +6,9 @@ use super::binding::*;
 use super::periodic_timer::*;
 use super::permission::*;
 use super::transaction::*;
+use crate::auth::{oauth::OAuthCredential, Integrity};
 use crate::proto;
+use crate::proto::accesstoken::AccessToken;
 use crate::Error;
 
 use stun::agent::*;
@@ -15,7 +17,10 @@ use stun::error_code::*;
 use stun::fingerprint::*;
 use stun::integrity::*;
 use stun::message::*;
+use stun::password_algorithm::*;
+use stun::security_features::*;
 use stun::textattrs::*;
+use stun::userhash::*;
 
 use util::Conn;
 
@@ -50,10 +55,120 @@ pub trait RelayConnObserver {
     ) -> Result<TransactionResult, Error>;
 }
 
+// Credentials authenticate requests with the long-term credential mechanism, or
+// with an access token. They add USERNAME or USERHASH, REALM, PASSWORD-ALGORITHMS
+// and PASSWORD-ALGORITHM if negotiated, ACCESS-TOKEN, and the integrity attribute,
+// so only FINGERPRINT may follow them.
+#[derive(Clone)]
+pub(crate) struct Credentials {
+    username: Username,
+    userhash: Option<UserHash>,
+    realm: Realm,
+    password_algorithms: Option<(PasswordAlgorithms, PasswordAlgorithm)>,
+    access_token: Option<AccessToken>,
+    integrity: Integrity,
+}
+
+impl Credentials {
+    // new derives the credentials from the challenge of the server, using the
+    // features of RFC 8489 its nonce announces. With an OAuth credential, the
+    // username is the key id of the access token and the password is unused.
+    pub(crate) fn new(
+        username: Username,
+        realm: Realm,
+        password: &str,
+        oauth_credential: Option<&OAuthCredential>,
+        nonce: &Nonce,
+        challenge: &Message,
+    ) -> Result<Self, Error> {
+        let features = SecurityFeatures::from_nonce(&nonce.text).unwrap_or_default();
+
+        // Requests are protected with the session key of the access token.
+        // RFC 7635 Section 9
+        if let Some(oauth_credential) = oauth_credential {
+            let integrity = if features.password_algorithms {
+                Integrity::Sha256(MessageIntegritySha256(oauth_credential.mac_key.clone()))
+            } else {
+                Integrity::Sha1(MessageIntegrity(oauth_credential.mac_key.clone()))
+            };
+            return Ok(Credentials {
+                username,
+                userhash: None,
+                realm,
+                password_algorithms: None,
+                access_token: Some(AccessToken(oauth_credential.access_token.clone())),
+                integrity,
+            });
+        }
+
+        let userhash = if features.username_anonymity {
+            Some(UserHash::new(&username.text, &realm.text))
+        } else {
+            None
+        };
+
+        let (password_algorithms, integrity) = if features.password_algorithms {
+            let mut algorithms = PasswordAlgorithms::default();
+            algorithms.get_from(challenge)?;
+            let algorithm = algorithms
+                .preferred()
+                .ok_or(stun::Error::ErrUnsupportedPasswordAlgorithm)?;
+            let integrity = MessageIntegritySha256::new_long_term_integrity(
+                username.text.clone(),
+                realm.text.clone(),
+                password.to_owned(),
+                algorithm,
+            )?;
+            (Some((algorithms, algorithm)), Integrity::Sha256(integrity))
+        } else {
+            let integrity = MessageIntegrity::new_long_term_integrity(
+                username.text.clone(),
+                realm.text.clone(),
+                password.to_owned(),
+            );
+            (None, Integrity::Sha1(integrity))
+        };
+
+        Ok(Credentials {
+            username,
+            userhash,
+            realm,
+            password_algorithms,
+            access_token: None,
+            integrity,
+        })
+    }
+
+    // check checks the integrity of a response to a request with the credentials.
+    pub(crate) fn check(&self, m: &mut Message) -> Result<(), Error> {
+        self.integrity.check(m)
+    }
+}
+
+impl Setter for Credentials {
+    fn add_to(&self, m: &mut Message) -> Result<(), stun::Error> {
+        if let Some(userhash) = &self.userhash {
+            userhash.add_to(m)?;
+        } else {
+            self.username.add_to(m)?;
+        }
+        self.realm.add_to(m)?;
+        if let Some((algorithms, algorithm)) = &self.password_algorithms {
+            algorithms.add_to(m)?;
+            algorithm.add_to(m)?;
+        }
+        if let Some(access_token) = &self.access_token {
+            access_token.add_to(m)?;
+        }
+        self.integrity.add_to(m)
+    }
+}
+
 // RelayConnConfig is a set of configuration params use by NewUDPConn
 pub(crate) struct RelayConnConfig {
     pub(crate) relayed_addr: SocketAddr,
-    pub(crate) integrity: MessageIntegrity,
+    pub(crate) additional_relayed_addr: Option<SocketAddr>,
+    pub(crate) credentials: Credentials,
     pub(crate) nonce: Nonce,
     pub(crate) lifetime: Duration,
     pub(crate) binding_mgr: Arc<Mutex<BindingManager>>,
@@ -65,7 +180,7 @@ pub struct RelayConnInternal<T: 'static + RelayConnObserver + Send + Sync> {
     relayed_addr: SocketAddr,
     perm_map: PermissionMap,
     binding_mgr: Arc<Mutex<BindingManager>>,
-    integrity: MessageIntegrity,
+    credentials: Credentials,
     nonce: Nonce,
     lifetime: Duration,
 }
@@ -198,7 +313,7 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
             relayed_addr: config.relayed_addr,
             perm_map: PermissionMap::new(),
             binding_mgr: config.binding_mgr,
-            integrity: config.integrity,
+            credentials: config.credentials,
             nonce: config.nonce,
             lifetime: config.lifetime,
         }
@@ -254,7 +369,7 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
                     let binding_mgr = Arc::clone(&self.binding_mgr);
                     let rc_obs = Arc::clone(&self.obs);
                     let nonce = self.nonce.clone();
-                    let integrity = self.integrity.clone();
+                    let credentials = self.credentials.clone();
                     {
                         let mut bm = binding_mgr.lock().await;
                         if let Some(b) = bm.get_by_addr(&bind_addr) {
@@ -267,7 +382,7 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
                             bind_addr,
                             bind_number,
                             nonce,
-                            integrity,
+                            credentials,
                         )
                         .await;
 
@@ -318,7 +433,7 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
                 let binding_mgr = Arc::clone(&self.binding_mgr);
                 let rc_obs = Arc::clone(&self.obs);
                 let nonce = self.nonce.clone();
-                let integrity = self.integrity.clone();
+                let credentials = self.credentials.clone();
                 {
                     let mut bm = binding_mgr.lock().await;
                     if let Some(b) = bm.get_by_addr(&bind_addr) {
@@ -327,7 +442,7 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
                 }
                 tokio::spawn(async move {
                     let result =
-                        RelayConnInternal::bind(rc_obs, bind_addr, bind_number, nonce, integrity)
+                        RelayConnInternal::bind(rc_obs, bind_addr, bind_number, nonce, credentials)
                             .await;
 
                     {
@@ -390,7 +505,6 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
     async fn create_permissions(&mut self, addrs: &[SocketAddr]) -> Result<(), Error> {
         let res = {
             let msg = {
-                let obs = self.obs.lock().await;
                 let mut setters: Vec<Box<dyn Setter>> = vec![
                     Box::new(TransactionId::new()),
                     Box::new(MessageType::new(METHOD_CREATE_PERMISSION, CLASS_REQUEST)),
@@ -400,10 +514,8 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
                     setters.push(Box::new(socket_addr2peer_address(addr)));
                 }
 
-                setters.push(Box::new(obs.username()));
-                setters.push(Box::new(obs.realm()));
                 setters.push(Box::new(self.nonce.clone()));
-                setters.push(Box::new(self.integrity.clone()));
+                setters.push(Box::new(self.credentials.clone()));
                 setters.push(Box::new(FINGERPRINT));
 
                 let mut msg = Message::new();
@@ -469,10 +581,8 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
                 Box::new(TransactionId::new()),
                 Box::new(MessageType::new(METHOD_REFRESH, CLASS_REQUEST)),
                 Box::new(proto::lifetime::Lifetime(lifetime)),
-                Box::new(obs.username()),
-                Box::new(obs.realm()),
                 Box::new(self.nonce.clone()),
-                Box::new(self.integrity.clone()),
+                Box::new(self.credentials.clone()),
                 Box::new(FINGERPRINT),
             ])?;
 
@@ -537,7 +647,7 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
         bind_addr: SocketAddr,
         bind_number: u16,
         nonce: Nonce,
-        integrity: MessageIntegrity,
+        credentials: Credentials,
     ) -> Result<(), Error> {
         let (msg, turn_server_addr) = {
             let obs = rc_obs.lock().await;
@@ -547,10 +657,8 @@ impl<T: RelayConnObserver + Send + Sync> RelayConnInternal<T> {
                 Box::new(MessageType::new(METHOD_CHANNEL_BIND, CLASS_REQUEST)),
                 Box::new(socket_addr2peer_address(&bind_addr)),
                 Box::new(proto::channum::ChannelNumber(bind_number)),
-                Box::new(obs.username()),
-                Box::new(obs.realm()),
                 Box::new(nonce),
-                Box::new(integrity),
+                Box::new(credentials),
                 Box::new(FINGERPRINT),
             ];
 
@@ -44,13 +44,23 @@ async fn test_relay_conn() -> Result<()> {
         username: Username::new(ATTR_USERNAME, "username".to_owned()),
         realm: Realm::new(ATTR_REALM, "realm".to_owned()),
     };
+    let nonce = Nonce::new(ATTR_NONCE, "nonce".to_owned());
+    let credentials = Credentials::new(
+        obs.username(),
+        obs.realm(),
+        "password",
+        None,
+        &nonce,
+        &Message::new(),
+    )?;
 
     let (_read_ch_tx, read_ch_rx) = mpsc::channel(100);
 
     let config = RelayConnConfig {
         relayed_addr: SocketAddr::new(Ipv4Addr::new(0, 0, 0, 0).into(), 0),
-        integrity: MessageIntegrity::default(),
-        nonce: Nonce::new(ATTR_NONCE, "nonce".to_owned()),
+        additional_relayed_addr: None,
+        credentials,
+        nonce,
         lifetime: Duration::from_secs(0),
         binding_mgr: Arc::new(Mutex::new(BindingManager::new())),
         read_ch_rx: Arc::new(Mutex::new(read_ch_rx)),
@@ -70,10 +80,10 @@ async fn test_relay_conn() -> Result<()> {
     //let binding_mgr = Arc::clone(&rci.binding_mgr);
     let rc_obs = Arc::clone(&rci.obs);
     let nonce = rci.nonce.clone();
-    let integrity = rci.integrity.clone();
+    let credentials = rci.credentials.clone();
 
     if let Err(err) =
-        RelayConnInternal::bind(rc_obs, bind_addr, bind_number, nonce, integrity).await
+        RelayConnInternal::bind(rc_obs, bind_addr, bind_number, nonce, credentials).await
     {
         assert!(Error::ErrUnexpectedResponse != err);
     } else {
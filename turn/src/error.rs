@@ -120,6 +120,24 @@ pub enum Error {
     ErrDuplicatedNonce,
     #[error("no such user exists")]
     ErrNoSuchUser,
+    #[error("redirected to an alternate server that was already tried")]
+    ErrAlternateServerLoop,
+    #[error("too many redirects to alternate servers")]
+    ErrTooManyRedirects,
+    #[error("allocation quota of the user reached")]
+    ErrAllocationQuotaReached,
+    #[error("peer address is forbidden by the server policy")]
+    ErrPeerAddressForbidden,
+    #[error("PASSWORD-ALGORITHMS doesn't match the algorithms offered with the nonce")]
+    ErrPasswordAlgorithmsMismatch,
+    #[error("no access token key with this key id")]
+    ErrNoSuchAccessTokenKey,
+    #[error("invalid access token")]
+    ErrInvalidAccessToken,
+    #[error("access token expired")]
+    ErrAccessTokenExpired,
+    #[error("realm of the request doesn't match the server")]
+    ErrRealmMismatch,
     #[error("unexpected class")]
     ErrUnexpectedClass,
     #[error("unexpected method")]
@@ -142,6 +160,18 @@ pub enum Error {
     ErrNoDontFragmentSupport,
     #[error("Request must not contain RESERVATION-TOKEN and EVEN-PORT")]
     ErrRequestWithReservationTokenAndEvenPort,
+    #[error(
+        "Request must not contain both REQUESTED-ADDRESS-FAMILY and ADDITIONAL-ADDRESS-FAMILY"
+    )]
+    ErrRequestedAndAdditionalAddressFamily,
+    #[error("Request must not contain an address family and RESERVATION-TOKEN")]
+    ErrRequestWithReservationTokenAndAddressFamily,
+    #[error("ADDITIONAL-ADDRESS-FAMILY must be IPv6")]
+    ErrInvalidAdditionalAddressFamily,
+    #[error("address family not supported")]
+    ErrAddressFamilyNotSupported,
+    #[error("peer address family mismatch")]
+    ErrPeerAddressFamilyMismatch,
     #[error("no allocation found")]
     ErrNoAllocationFound,
     #[error("unable to handle send-indication, no permission added")]
@@ -0,0 +1,59 @@
+#[cfg(test)]
+mod accesstoken_test;
+
+use stun::attributes::*;
+use stun::message::*;
+
+// AccessToken represents ACCESS-TOKEN attribute.
+//
+// The ACCESS-TOKEN attribute contains a self-contained token that the
+// client obtained from an authorization server. The token holds the
+// session key that protects the request with MESSAGE-INTEGRITY, encrypted
+// with a key shared by the authorization server and the STUN server.
+//
+// RFC 7635 Section 6.2
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AccessToken(pub Vec<u8>);
+
+impl Setter for AccessToken {
+    // AddTo adds ACCESS-TOKEN to message.
+    fn add_to(&self, m: &mut Message) -> Result<(), stun::Error> {
+        m.add(ATTR_ACCESS_TOKEN, &self.0);
+        Ok(())
+    }
+}
+
+impl Getter for AccessToken {
+    // GetFrom decodes ACCESS-TOKEN from message.
+    fn get_from(&mut self, m: &Message) -> Result<(), stun::Error> {
+        self.0 = m.get(ATTR_ACCESS_TOKEN)?;
+        Ok(())
+    }
+}
+
+// ThirdPartyAuthorization represents THIRD-PARTY-AUTHORIZATION attribute.
+//
+// The server includes the attribute in a 401 (Unauthorized) response to tell
+// the client that it supports third-party authorization, with the STUN server
+// name that the client requests an access token for.
+//
+// RFC 7635 Section 6.1
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ThirdPartyAuthorization(pub String);
+
+impl Setter for ThirdPartyAuthorization {
+    // AddTo adds THIRD-PARTY-AUTHORIZATION to message.
+    fn add_to(&self, m: &mut Message) -> Result<(), stun::Error> {
+        m.add(ATTR_THIRD_PARTY_AUTHORIZATION, self.0.as_bytes());
+        Ok(())
+    }
+}
+
+impl Getter for ThirdPartyAuthorization {
+    // GetFrom decodes THIRD-PARTY-AUTHORIZATION from message.
+    fn get_from(&mut self, m: &Message) -> Result<(), stun::Error> {
+        let v = m.get(ATTR_THIRD_PARTY_AUTHORIZATION)?;
+        self.0 = String::from_utf8(v)?;
+        Ok(())
+    }
+}
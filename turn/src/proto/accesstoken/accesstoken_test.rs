@@ -0,0 +1,40 @@
+use super::*;
+
+#[test]
+fn test_access_token() -> Result<(), stun::Error> {
+    let mut m = Message::new();
+    let token = AccessToken(vec![0, 12, 1, 2, 3, 4]);
+    token.add_to(&mut m)?;
+    ThirdPartyAuthorization("turn.example.org".to_owned()).add_to(&mut m)?;
+    m.write_header();
+
+    //"GetFrom"
+    {
+        let mut decoded = Message::new();
+        decoded.write(&m.raw)?;
+        let mut tok = AccessToken::default();
+        tok.get_from(&decoded)?;
+        assert_eq!(tok, token, "Decoded {tok:?}, expected {token:?}");
+
+        let mut third_party = ThirdPartyAuthorization::default();
+        third_party.get_from(&decoded)?;
+        assert_eq!(third_party.0, "turn.example.org");
+
+        //"HandleErr"
+        {
+            let m = Message::new();
+            let mut handle = AccessToken::default();
+            if let Err(err) = handle.get_from(&m) {
+                assert_eq!(
+                    stun::Error::ErrAttributeNotFound,
+                    err,
+                    "{err} should be not found"
+                );
+            } else {
+                panic!("expected error, but got ok");
+            }
+        }
+    }
+
+    Ok(())
+}
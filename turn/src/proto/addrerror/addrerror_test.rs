@@ -0,0 +1,46 @@
+use super::*;
+
+#[test]
+fn test_address_error_code() -> Result<(), stun::Error> {
+    let mut m = Message::new();
+    let a = AddressErrorCode {
+        family: REQUESTED_FAMILY_IPV6,
+        code: CODE_ADDR_FAMILY_NOT_SUPPORTED,
+        reason: b"Address Family not Supported".to_vec(),
+    };
+    a.add_to(&mut m)?;
+    m.write_header();
+
+    //"GetFrom"
+    {
+        let mut decoded = Message::new();
+        decoded.write(&m.raw)?;
+        let mut got = AddressErrorCode::default();
+        got.get_from(&decoded)?;
+        assert_eq!(got, a, "Decoded {got}, expected {a}");
+        assert_eq!(got.to_string(), "IPv6: 440 Address Family not Supported");
+
+        //"HandleErr"
+        {
+            let mut m = Message::new();
+            let mut handle = AddressErrorCode::default();
+            if let Err(err) = handle.get_from(&m) {
+                assert_eq!(
+                    stun::Error::ErrAttributeNotFound,
+                    err,
+                    "{err} should be not found"
+                );
+            } else {
+                panic!("expected error, but got ok");
+            }
+            m.add(ATTR_ADDRESS_ERROR_CODE, &[2, 0, 4]);
+            assert_eq!(
+                handle.get_from(&m),
+                Err(stun::Error::ErrUnexpectedEof),
+                "should error on short value"
+            );
+        }
+    }
+
+    Ok(())
+}
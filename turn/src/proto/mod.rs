@@ -1,7 +1,9 @@
 #[cfg(test)]
 mod proto_test;
 
+pub mod accesstoken;
 pub mod addr;
+pub mod addrerror;
 pub mod chandata;
 pub mod channum;
 pub mod data;
@@ -16,6 +18,7 @@ pub mod rsrvtoken;
 
 use std::fmt;
 
+use stun::attributes::AttrType;
 use stun::message::*;
 
 // proto implements RFC 5766 Traversal Using Relays around NAT.
@@ -67,3 +70,20 @@ pub fn send_indication() -> MessageType {
 pub fn refresh_request() -> MessageType {
     MessageType::new(METHOD_REFRESH, CLASS_REQUEST)
 }
+
+// split_attributes returns a message for every attribute of type t in m, holding
+// only that attribute, so that repeated attributes like XOR-PEER-ADDRESS can be
+// decoded with their Getter, which reads the first attribute of a type.
+pub(crate) fn split_attributes(m: &Message, t: AttrType) -> Vec<Message> {
+    m.attributes
+        .0
+        .iter()
+        .filter(|attr| attr.typ == t)
+        .map(|attr| {
+            let mut single = Message::new();
+            single.transaction_id = m.transaction_id;
+            single.add(t, &attr.value);
+            single
+        })
+        .collect()
+}
@@ -6,16 +6,23 @@ use stun::checks::*;
 use stun::message::*;
 
 use std::fmt;
+use std::net::IpAddr;
 
 // Values for RequestedAddressFamily as defined in RFC 6156 Section 4.1.1.
 pub const REQUESTED_FAMILY_IPV4: RequestedAddressFamily = RequestedAddressFamily(0x01);
 pub const REQUESTED_FAMILY_IPV6: RequestedAddressFamily = RequestedAddressFamily(0x02);
 
 // RequestedAddressFamily represents the REQUESTED-ADDRESS-FAMILY Attribute as
-// defined in RFC 6156 Section 4.1.1.
-#[derive(Debug, Default, PartialEq, Eq)]
+// defined in RFC 6156 Section 4.1.1. It also represents the
+// ADDITIONAL-ADDRESS-FAMILY Attribute of RFC 8656 Section 18.11, which has the
+// same encoding.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
 pub struct RequestedAddressFamily(pub u8);
 
+// AdditionalAddressFamily represents the ADDITIONAL-ADDRESS-FAMILY Attribute.
+// Use add_to_as and get_from_as with ATTR_ADDITIONAL_ADDRESS_FAMILY.
+pub type AdditionalAddressFamily = RequestedAddressFamily;
+
 impl fmt::Display for RequestedAddressFamily {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let s = match *self {
@@ -29,28 +36,36 @@ impl fmt::Display for RequestedAddressFamily {
 
 const REQUESTED_FAMILY_SIZE: usize = 4;
 
-impl Setter for RequestedAddressFamily {
-    // AddTo adds REQUESTED-ADDRESS-FAMILY to message.
-    fn add_to(&self, m: &mut Message) -> Result<(), stun::Error> {
+impl RequestedAddressFamily {
+    // from_ip returns the family of ip.
+    pub fn from_ip(ip: &IpAddr) -> Self {
+        if ip.is_ipv4() {
+            REQUESTED_FAMILY_IPV4
+        } else {
+            REQUESTED_FAMILY_IPV6
+        }
+    }
+
+    // is_ipv4 returns true if the family is IPv4.
+    pub fn is_ipv4(&self) -> bool {
+        *self == REQUESTED_FAMILY_IPV4
+    }
+
+    // add_to_as adds the family to message as attribute t.
+    pub fn add_to_as(&self, m: &mut Message, t: AttrType) -> Result<(), stun::Error> {
         let mut v = vec![0; REQUESTED_FAMILY_SIZE];
         v[0] = self.0;
         // b[1:4] is RFFU = 0.
         // The RFFU field MUST be set to zero on transmission and MUST be
         // ignored on reception. It is reserved for future uses.
-        m.add(ATTR_REQUESTED_ADDRESS_FAMILY, &v);
+        m.add(t, &v);
         Ok(())
     }
-}
 
-impl Getter for RequestedAddressFamily {
-    // GetFrom decodes REQUESTED-ADDRESS-FAMILY from message.
-    fn get_from(&mut self, m: &Message) -> Result<(), stun::Error> {
-        let v = m.get(ATTR_REQUESTED_ADDRESS_FAMILY)?;
-        check_size(
-            ATTR_REQUESTED_ADDRESS_FAMILY,
-            v.len(),
-            REQUESTED_FAMILY_SIZE,
-        )?;
+    // get_from_as decodes the family from attribute t of message.
+    pub fn get_from_as(&mut self, m: &Message, t: AttrType) -> Result<(), stun::Error> {
+        let v = m.get(t)?;
+        check_size(t, v.len(), REQUESTED_FAMILY_SIZE)?;
 
         if v[0] != REQUESTED_FAMILY_IPV4.0 && v[0] != REQUESTED_FAMILY_IPV6.0 {
             return Err(stun::Error::Other("ErrInvalidRequestedFamilyValue".into()));
@@ -59,3 +74,17 @@ impl Getter for RequestedAddressFamily {
         Ok(())
     }
 }
+
+impl Setter for RequestedAddressFamily {
+    // AddTo adds REQUESTED-ADDRESS-FAMILY to message.
+    fn add_to(&self, m: &mut Message) -> Result<(), stun::Error> {
+        self.add_to_as(m, ATTR_REQUESTED_ADDRESS_FAMILY)
+    }
+}
+
+impl Getter for RequestedAddressFamily {
+    // GetFrom decodes REQUESTED-ADDRESS-FAMILY from message.
+    fn get_from(&mut self, m: &Message) -> Result<(), stun::Error> {
+        self.get_from_as(m, ATTR_REQUESTED_ADDRESS_FAMILY)
+    }
+}
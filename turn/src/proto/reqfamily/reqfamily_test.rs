@@ -75,3 +75,27 @@ fn test_requested_address_family_add_to() -> Result<(), stun::Error> {
 
     Ok(())
 }
+
+#[test]
+fn test_additional_address_family() -> Result<(), stun::Error> {
+    let mut m = Message::new();
+    REQUESTED_FAMILY_IPV6.add_to_as(&mut m, ATTR_ADDITIONAL_ADDRESS_FAMILY)?;
+    m.write_header();
+
+    let mut decoded = Message::new();
+    decoded.write(&m.raw)?;
+    assert!(
+        !decoded.contains(ATTR_REQUESTED_ADDRESS_FAMILY),
+        "should only add ADDITIONAL-ADDRESS-FAMILY"
+    );
+    let mut family = AdditionalAddressFamily::default();
+    family.get_from_as(&decoded, ATTR_ADDITIONAL_ADDRESS_FAMILY)?;
+    assert_eq!(family, REQUESTED_FAMILY_IPV6);
+    assert!(!family.is_ipv4());
+    assert_eq!(
+        RequestedAddressFamily::from_ip(&"::1".parse().unwrap()),
+        REQUESTED_FAMILY_IPV6
+    );
+
+    Ok(())
+}
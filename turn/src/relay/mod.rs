@@ -1,3 +1,4 @@
+pub mod relay_dual_stack;
 pub mod relay_none;
 pub mod relay_range;
 pub mod relay_static;
@@ -7,7 +8,7 @@ use crate::error::Result;
 use util::Conn;
 
 use async_trait::async_trait;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 
 // RelayAddressGenerator is used to generate a RelayAddress when creating an allocation.
@@ -17,6 +18,14 @@ pub trait RelayAddressGenerator {
     // validate confirms that the RelayAddressGenerator is properly initialized
     fn validate(&self) -> Result<()>;
 
+    // supports_family returns true if the generator can allocate relayed addresses
+    // of the IPv4 family, or of the IPv6 family if use_ipv4 is false. Allocate
+    // requests for other families are rejected with 440 (Address Family not
+    // Supported).
+    fn supports_family(&self, use_ipv4: bool) -> bool {
+        use_ipv4
+    }
+
     // Allocate a RelayAddress
     async fn allocate_conn(
         &self,
@@ -24,3 +33,12 @@ pub trait RelayAddressGenerator {
         requested_port: u16,
     ) -> Result<(Arc<dyn Conn + Send + Sync>, SocketAddr)>;
 }
+
+// address_with_port joins a listening address and a port, enclosing IPv6
+// addresses in brackets.
+pub(crate) fn address_with_port(address: &str, port: u16) -> String {
+    match address.parse::<IpAddr>() {
+        Ok(IpAddr::V6(ip)) => format!("[{ip}]:{port}"),
+        _ => format!("{address}:{port}"),
+    }
+}
@@ -0,0 +1,44 @@
+use super::*;
+
+use async_trait::async_trait;
+
+// RelayAddressGeneratorDualStack allocates IPv4 relayed addresses with one
+// generator and IPv6 relayed addresses with another, so that clients can
+// request either family, or both with ADDITIONAL-ADDRESS-FAMILY.
+pub struct RelayAddressGeneratorDualStack {
+    // ipv4 allocates the relayed addresses of the IPv4 family
+    pub ipv4: Box<dyn RelayAddressGenerator + Send + Sync>,
+
+    // ipv6 allocates the relayed addresses of the IPv6 family
+    pub ipv6: Box<dyn RelayAddressGenerator + Send + Sync>,
+}
+
+#[async_trait]
+impl RelayAddressGenerator for RelayAddressGeneratorDualStack {
+    // validate confirms that both generators are properly initialized
+    fn validate(&self) -> Result<()> {
+        self.ipv4.validate()?;
+        self.ipv6.validate()
+    }
+
+    fn supports_family(&self, use_ipv4: bool) -> bool {
+        if use_ipv4 {
+            self.ipv4.supports_family(true)
+        } else {
+            self.ipv6.supports_family(false)
+        }
+    }
+
+    // Allocate a PacketConn (UDP) RelayAddress with the generator of the family
+    async fn allocate_conn(
+        &self,
+        use_ipv4: bool,
+        requested_port: u16,
+    ) -> Result<(Arc<dyn Conn + Send + Sync>, SocketAddr)> {
+        if use_ipv4 {
+            self.ipv4.allocate_conn(true, requested_port).await
+        } else {
+            self.ipv6.allocate_conn(false, requested_port).await
+        }
+    }
+}
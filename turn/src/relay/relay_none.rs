@@ -2,6 +2,7 @@ use super::*;
 use crate::error::*;
 
 use async_trait::async_trait;
+use std::net::IpAddr;
 use util::vnet::net::*;
 
 // RelayAddressGeneratorNone returns the listener with no modifications
@@ -22,6 +23,15 @@ impl RelayAddressGenerator for RelayAddressGeneratorNone {
         }
     }
 
+    // supports_family returns true if the family matches address. Host names
+    // may resolve to both families.
+    fn supports_family(&self, use_ipv4: bool) -> bool {
+        match self.address.parse::<IpAddr>() {
+            Ok(ip) => ip.is_ipv4() == use_ipv4,
+            Err(_) => true,
+        }
+    }
+
     // Allocate a PacketConn (UDP) RelayAddress
     async fn allocate_conn(
         &self,
@@ -30,7 +40,7 @@ impl RelayAddressGenerator for RelayAddressGeneratorNone {
     ) -> Result<(Arc<dyn Conn + Send + Sync>, SocketAddr)> {
         let addr = self
             .net
-            .resolve_addr(use_ipv4, &format!("{}:{}", self.address, requested_port))
+            .resolve_addr(use_ipv4, &address_with_port(&self.address, requested_port))
             .await?;
         let conn = self.net.bind(addr).await?;
         let relay_addr = conn.local_addr()?;
@@ -42,6 +42,11 @@ impl RelayAddressGenerator for RelayAddressGeneratorRanges {
         }
     }
 
+    // supports_family returns true if the family matches relay_address.
+    fn supports_family(&self, use_ipv4: bool) -> bool {
+        self.relay_address.is_ipv4() == use_ipv4
+    }
+
     // Allocate a PacketConn (UDP) relay_address
     async fn allocate_conn(
         &self,
@@ -57,7 +62,7 @@ impl RelayAddressGenerator for RelayAddressGeneratorRanges {
         if requested_port != 0 {
             let addr = self
                 .net
-                .resolve_addr(use_ipv4, &format!("{}:{}", self.address, requested_port))
+                .resolve_addr(use_ipv4, &address_with_port(&self.address, requested_port))
                 .await?;
             let conn = self.net.bind(addr).await?;
             let mut relay_addr = conn.local_addr()?;
@@ -69,7 +74,7 @@ impl RelayAddressGenerator for RelayAddressGeneratorRanges {
             let port = self.min_port + rand::random::<u16>() % (self.max_port - self.min_port + 1);
             let addr = self
                 .net
-                .resolve_addr(use_ipv4, &format!("{}:{}", self.address, port))
+                .resolve_addr(use_ipv4, &address_with_port(&self.address, port))
                 .await?;
             let conn = match self.net.bind(addr).await {
                 Ok(conn) => conn,
@@ -28,6 +28,11 @@ impl RelayAddressGenerator for RelayAddressGeneratorStatic {
         }
     }
 
+    // supports_family returns true if the family matches relay_address.
+    fn supports_family(&self, use_ipv4: bool) -> bool {
+        self.relay_address.is_ipv4() == use_ipv4
+    }
+
     // Allocate a PacketConn (UDP) RelayAddress
     async fn allocate_conn(
         &self,
@@ -36,7 +41,7 @@ impl RelayAddressGenerator for RelayAddressGeneratorStatic {
     ) -> Result<(Arc<dyn Conn + Send + Sync>, SocketAddr)> {
         let addr = self
             .net
-            .resolve_addr(use_ipv4, &format!("{}:{}", self.address, requested_port))
+            .resolve_addr(use_ipv4, &address_with_port(&self.address, requested_port))
             .await?;
         let conn = self.net.bind(addr).await?;
         let mut relay_addr = conn.local_addr()?;
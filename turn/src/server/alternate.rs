@@ -0,0 +1,72 @@
+#[cfg(test)]
+mod alternate_test;
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// AlternateServerHandler decides whether an authenticated Allocate request is
+// redirected to another TURN server. If it returns an address, the server answers
+// with 300 (Try Alternate) and the address in ALTERNATE-SERVER, and the client
+// retries there. allocations is the number of allocations on the listener that
+// received the request.
+//
+// You can use LoadSheddingHandler, a closure, or provide your own, e.g. to drain a
+// node before a deploy.
+//
+// https://tools.ietf.org/html/rfc8489#section-10
+pub trait AlternateServerHandler {
+    fn alternate_server(
+        &self,
+        username: &str,
+        src_addr: SocketAddr,
+        allocations: usize,
+    ) -> Option<SocketAddr>;
+}
+
+impl<F> AlternateServerHandler for F
+where
+    F: Fn(&str, SocketAddr, usize) -> Option<SocketAddr>,
+{
+    fn alternate_server(
+        &self,
+        username: &str,
+        src_addr: SocketAddr,
+        allocations: usize,
+    ) -> Option<SocketAddr> {
+        self(username, src_addr, allocations)
+    }
+}
+
+// LoadSheddingHandler redirects Allocate requests once a listener has
+// max_allocations allocations, to each of the alternate servers in turn.
+pub struct LoadSheddingHandler {
+    max_allocations: usize,
+    alternates: Vec<SocketAddr>,
+    next: AtomicUsize,
+}
+
+impl LoadSheddingHandler {
+    pub fn new(max_allocations: usize, alternates: Vec<SocketAddr>) -> Self {
+        LoadSheddingHandler {
+            max_allocations,
+            alternates,
+            next: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl AlternateServerHandler for LoadSheddingHandler {
+    fn alternate_server(
+        &self,
+        _username: &str,
+        _src_addr: SocketAddr,
+        allocations: usize,
+    ) -> Option<SocketAddr> {
+        if allocations < self.max_allocations || self.alternates.is_empty() {
+            return None;
+        }
+
+        let next = self.next.fetch_add(1, Ordering::Relaxed);
+        Some(self.alternates[next % self.alternates.len()])
+    }
+}
@@ -0,0 +1,50 @@
+use super::*;
+
+use std::net::{IpAddr, Ipv4Addr};
+
+#[test]
+fn test_load_shedding_handler() {
+    let src_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 5000);
+    let alternates = vec![
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)), 3478),
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 3)), 3478),
+    ];
+    let handler = LoadSheddingHandler::new(2, alternates.clone());
+
+    assert_eq!(handler.alternate_server("user", src_addr, 0), None);
+    assert_eq!(handler.alternate_server("user", src_addr, 1), None);
+    assert_eq!(
+        handler.alternate_server("user", src_addr, 2),
+        Some(alternates[0])
+    );
+    assert_eq!(
+        handler.alternate_server("user", src_addr, 3),
+        Some(alternates[1])
+    );
+    assert_eq!(
+        handler.alternate_server("user", src_addr, 2),
+        Some(alternates[0])
+    );
+
+    let handler = LoadSheddingHandler::new(0, vec![]);
+    assert_eq!(handler.alternate_server("user", src_addr, 10), None);
+}
+
+#[test]
+fn test_alternate_server_handler_closure() {
+    let src_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 5000);
+    let alternate = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)), 3478);
+
+    let handler = move |username: &str, _: SocketAddr, _: usize| {
+        if username == "draining" {
+            Some(alternate)
+        } else {
+            None
+        }
+    };
+    assert_eq!(handler.alternate_server("user", src_addr, 0), None);
+    assert_eq!(
+        handler.alternate_server("draining", src_addr, 0),
+        Some(alternate)
+    );
+}
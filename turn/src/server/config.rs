@@ -1,7 +1,12 @@
+use super::alternate::AlternateServerHandler;
+use super::events::EventHandler;
+use super::peer_filter::PeerAddressFilter;
+use crate::auth::oauth::OAuthKeyTable;
 use crate::auth::*;
 use crate::error::*;
 use crate::relay::*;
 
+use stun::password_algorithm::PasswordAlgorithm;
 use util::Conn;
 
 use std::sync::Arc;
@@ -36,6 +41,71 @@ pub struct ServerConfig {
 
     // channel_bind_timeout sets the lifetime of channel binding. Defaults to 10 minutes.
     pub channel_bind_timeout: Duration,
+
+    // password_algorithms are offered to clients in PASSWORD-ALGORITHMS, in order of
+    // preference, as of RFC 8489. If empty, clients authenticate with MD5 keys only.
+    pub password_algorithms: Vec<PasswordAlgorithm>,
+
+    // username_anonymity allows clients to send USERHASH instead of USERNAME. The
+    // auth_handler must implement username_from_userhash.
+    pub username_anonymity: bool,
+
+    // alternate_server_handler can redirect Allocate requests to other TURN servers,
+    // e.g. to shed load. If None, every request is served.
+    pub alternate_server_handler: Option<Arc<dyn AlternateServerHandler + Send + Sync>>,
+
+    // max_allocations_per_user limits the allocations a username may hold across
+    // all listeners. Further Allocate requests fail with 486 (Allocation Quota Reached).
+    // 0 means unlimited.
+    pub max_allocations_per_user: usize,
+
+    // allocation_bandwidth_limit limits the bytes per second relayed by each
+    // allocation, in both directions together. Excess packets are dropped. 0 means
+    // unlimited.
+    pub allocation_bandwidth_limit: usize,
+
+    // total_bandwidth_limit limits the bytes per second relayed by all allocations
+    // of the server. 0 means unlimited.
+    pub total_bandwidth_limit: usize,
+
+    // peer_address_filter decides which peers clients may create permissions and
+    // channels for. Denied peers are rejected with 403 (Forbidden). The default
+    // denies private, loopback and link-local networks, so that clients can't reach
+    // the hosts behind the server. Use PeerAddressFilter::allow_all to relay to
+    // any peer.
+    pub peer_address_filter: PeerAddressFilter,
+
+    // oauth_key_table enables third-party authorization with access tokens as of
+    // RFC 7635, which are decrypted with its AS-RS keys. Clients without access
+    // token still authenticate with the auth_handler. If None, access tokens are
+    // not accepted.
+    pub oauth_key_table: Option<Arc<OAuthKeyTable>>,
+
+    // event_handler is notified of allocations, permissions, channel bindings and
+    // failed authentications, for monitoring and accounting.
+    pub event_handler: Option<Arc<dyn EventHandler + Send + Sync>>,
+}
+
+impl Default for ServerConfig {
+    // default is a server without listeners that knows no user. Fields left at
+    // their defaults turn the optional features off.
+    fn default() -> Self {
+        ServerConfig {
+            conn_configs: vec![],
+            realm: String::new(),
+            auth_handler: Arc::new(NoAuthHandler),
+            channel_bind_timeout: Duration::from_secs(0),
+            password_algorithms: vec![],
+            username_anonymity: false,
+            alternate_server_handler: None,
+            max_allocations_per_user: 0,
+            allocation_bandwidth_limit: 0,
+            total_bandwidth_limit: 0,
+            peer_address_filter: PeerAddressFilter::default(),
+            oauth_key_table: None,
+            event_handler: None,
+        }
+    }
 }
 
 impl ServerConfig {
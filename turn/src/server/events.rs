@@ -0,0 +1,70 @@
+use crate::allocation::five_tuple::FiveTuple;
+use crate::allocation::stats::RelayStats;
+
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+// Event is something that happened on the server, for monitoring and accounting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    // AllocationCreated is emitted when an Allocate request succeeded.
+    AllocationCreated {
+        five_tuple: FiveTuple,
+        username: String,
+        relay_addrs: Vec<SocketAddr>,
+        lifetime: Duration,
+    },
+    // AllocationRefreshed is emitted when a Refresh request extended the lifetime
+    // of an allocation.
+    AllocationRefreshed {
+        five_tuple: FiveTuple,
+        username: String,
+        lifetime: Duration,
+    },
+    // AllocationDeleted is emitted when an allocation was deleted by the client,
+    // expired, or was closed by the server. stats are its relayed datagrams.
+    AllocationDeleted {
+        five_tuple: FiveTuple,
+        username: String,
+        stats: RelayStats,
+    },
+    // PermissionAdded is emitted when an allocation got a permission for a new
+    // peer, by CreatePermission or ChannelBind.
+    PermissionAdded {
+        five_tuple: FiveTuple,
+        username: String,
+        peer: IpAddr,
+    },
+    // ChannelBound is emitted when a channel was bound to a peer.
+    ChannelBound {
+        five_tuple: FiveTuple,
+        username: String,
+        number: u16,
+        peer: SocketAddr,
+    },
+    // AuthFailed is emitted when a request was rejected because of its
+    // credentials: an unknown user, a wrong key or an invalid access token.
+    AuthFailed {
+        src_addr: SocketAddr,
+        username: String,
+        reason: String,
+    },
+}
+
+// EventHandler receives the events of a server. It is called on the tasks that
+// serve clients, so it must not block; forward events to a channel for slow
+// processing.
+//
+// You can use a closure, or provide your own.
+pub trait EventHandler {
+    fn on_event(&self, event: Event);
+}
+
+impl<F> EventHandler for F
+where
+    F: Fn(Event),
+{
+    fn on_event(&self, event: Event) {
+        self(event)
+    }
+}
@@ -1,17 +1,29 @@
 #[cfg(test)]
 mod server_test;
 
+pub mod alternate;
 pub mod config;
+pub mod events;
+pub mod peer_filter;
+#[cfg(feature = "metrics")]
+pub mod prometheus;
 pub mod request;
 
 use crate::{
-    allocation::{allocation_manager::*, five_tuple::FiveTuple, AllocationInfo},
-    auth::AuthHandler,
+    allocation::{
+        allocation_manager::*, bandwidth::BandwidthLimiter, five_tuple::FiveTuple, quota::*,
+        stats::*, AllocationInfo,
+    },
+    auth::{oauth::*, AuthHandler},
     error::*,
     proto::lifetime::DEFAULT_LIFETIME,
 };
+use alternate::AlternateServerHandler;
 use config::*;
+use events::EventHandler;
+use peer_filter::PeerAddressFilter;
 use request::*;
+use stun::password_algorithm::PasswordAlgorithm;
 
 use std::{collections::HashMap, sync::Arc};
 
@@ -31,7 +43,18 @@ pub struct Server {
     auth_handler: Arc<dyn AuthHandler + Send + Sync>,
     realm: String,
     channel_bind_timeout: Duration,
+    password_algorithms: Vec<PasswordAlgorithm>,
+    username_anonymity: bool,
+    alternate_server_handler: Option<Arc<dyn AlternateServerHandler + Send + Sync>>,
+    allocation_quota: Option<Arc<AllocationQuota>>,
+    allocation_bandwidth_limit: usize,
+    total_bandwidth_limiter: Option<Arc<BandwidthLimiter>>,
+    peer_address_filter: Arc<PeerAddressFilter>,
+    oauth_key_table: Option<Arc<OAuthKeyTable>>,
     pub(crate) nonces: Arc<Mutex<HashMap<String, Instant>>>,
+    access_token_sessions: Arc<Mutex<HashMap<FiveTuple, Token>>>,
+    user_counters: Arc<UserCounters>,
+    event_handler: Option<Arc<dyn EventHandler + Send + Sync>>,
     command_tx: Mutex<Option<broadcast::Sender<Command>>>,
 }
 
@@ -45,7 +68,30 @@ impl Server {
             auth_handler: config.auth_handler,
             realm: config.realm,
             channel_bind_timeout: config.channel_bind_timeout,
+            password_algorithms: config.password_algorithms,
+            username_anonymity: config.username_anonymity,
+            alternate_server_handler: config.alternate_server_handler,
+            allocation_quota: if config.max_allocations_per_user != 0 {
+                Some(Arc::new(AllocationQuota::new(
+                    config.max_allocations_per_user,
+                )))
+            } else {
+                None
+            },
+            allocation_bandwidth_limit: config.allocation_bandwidth_limit,
+            total_bandwidth_limiter: if config.total_bandwidth_limit != 0 {
+                Some(Arc::new(BandwidthLimiter::new(
+                    config.total_bandwidth_limit,
+                )))
+            } else {
+                None
+            },
+            peer_address_filter: Arc::new(config.peer_address_filter),
+            oauth_key_table: config.oauth_key_table,
             nonces: Arc::new(Mutex::new(HashMap::new())),
+            access_token_sessions: Arc::new(Mutex::new(HashMap::new())),
+            user_counters: Arc::new(UserCounters::new()),
+            event_handler: config.event_handler,
             command_tx: Mutex::new(Some(command_tx.clone())),
         };
 
@@ -58,10 +104,22 @@ impl Server {
             let auth_handler = Arc::clone(&s.auth_handler);
             let realm = s.realm.clone();
             let channel_bind_timeout = s.channel_bind_timeout;
+            let password_algorithms = s.password_algorithms.clone();
+            let username_anonymity = s.username_anonymity;
+            let alternate_server_handler = s.alternate_server_handler.clone();
+            let peer_address_filter = Arc::clone(&s.peer_address_filter);
+            let oauth_key_table = s.oauth_key_table.clone();
+            let access_token_sessions = Arc::clone(&s.access_token_sessions);
+            let event_handler = s.event_handler.clone();
             let handle_rx = command_tx.subscribe();
             let conn = p.conn;
             let allocation_manager = Arc::new(Manager::new(ManagerConfig {
                 relay_addr_generator: p.relay_addr_generator,
+                allocation_quota: s.allocation_quota.clone(),
+                allocation_bandwidth_limit: s.allocation_bandwidth_limit,
+                total_bandwidth_limiter: s.total_bandwidth_limiter.clone(),
+                user_counters: Some(Arc::clone(&s.user_counters)),
+                event_handler: s.event_handler.clone(),
             }));
 
             tokio::spawn(Server::read_loop(
@@ -71,6 +129,13 @@ impl Server {
                 auth_handler,
                 realm,
                 channel_bind_timeout,
+                password_algorithms,
+                username_anonymity,
+                alternate_server_handler,
+                peer_address_filter,
+                oauth_key_table,
+                access_token_sessions,
+                event_handler,
                 handle_rx,
             ));
         }
@@ -139,6 +204,32 @@ impl Server {
         }
     }
 
+    /// Get the datagrams relayed for each user since the server was created, or
+    /// since the last [`Server::take_user_stats`].
+    pub fn get_user_stats(&self) -> HashMap<String, RelayStats> {
+        self.user_counters.stats()
+    }
+
+    /// Get the datagrams relayed for each user and reset the counters, e.g. to
+    /// bill users periodically. Users without allocations are forgotten.
+    pub fn take_user_stats(&self) -> HashMap<String, RelayStats> {
+        self.user_counters.take()
+    }
+
+    /// Get the allocations and the relayed datagrams of all users in the
+    /// Prometheus text exposition format, to be served on a metrics endpoint.
+    /// The relayed datagrams are totals since the server was created, which
+    /// [`Server::take_user_stats`] does not reset.
+    #[cfg(feature = "metrics")]
+    pub async fn prometheus_metrics(&self) -> Result<String> {
+        let infos = self.get_allocations_info(None).await?;
+        Ok(prometheus::encode(
+            infos.len(),
+            &self.user_counters.totals(),
+        ))
+    }
+
+    #[allow(clippy::too_many_arguments)]
     async fn read_loop(
         conn: Arc<dyn Conn + Send + Sync>,
         allocation_manager: Arc<Manager>,
@@ -146,6 +237,13 @@ impl Server {
         auth_handler: Arc<dyn AuthHandler + Send + Sync>,
         realm: String,
         channel_bind_timeout: Duration,
+        password_algorithms: Vec<PasswordAlgorithm>,
+        username_anonymity: bool,
+        alternate_server_handler: Option<Arc<dyn AlternateServerHandler + Send + Sync>>,
+        peer_address_filter: Arc<PeerAddressFilter>,
+        oauth_key_table: Option<Arc<OAuthKeyTable>>,
+        access_token_sessions: Arc<Mutex<HashMap<FiveTuple, Token>>>,
+        event_handler: Option<Arc<dyn EventHandler + Send + Sync>>,
         mut handle_rx: broadcast::Receiver<Command>,
     ) {
         let mut buf = vec![0u8; INBOUND_MTU];
@@ -206,6 +304,13 @@ impl Server {
                 auth_handler: Arc::clone(&auth_handler),
                 realm: realm.clone(),
                 channel_bind_timeout,
+                password_algorithms: password_algorithms.clone(),
+                username_anonymity,
+                alternate_server_handler: alternate_server_handler.clone(),
+                peer_address_filter: Arc::clone(&peer_address_filter),
+                oauth_key_table: oauth_key_table.clone(),
+                access_token_sessions: Arc::clone(&access_token_sessions),
+                event_handler: event_handler.clone(),
             };
 
             if let Err(err) = r.handle_request().await {
@@ -0,0 +1,83 @@
+#[cfg(test)]
+mod peer_filter_test;
+
+use ipnet::IpNet;
+
+use std::net::IpAddr;
+
+// Networks that a public TURN server usually must not relay to: "this" network,
+// private, shared, loopback, link-local, multicast and reserved ranges of RFC 6890.
+const PRIVATE_NETWORKS: [&str; 14] = [
+    "0.0.0.0/8",
+    "10.0.0.0/8",
+    "100.64.0.0/10",
+    "127.0.0.0/8",
+    "169.254.0.0/16",
+    "172.16.0.0/12",
+    "192.168.0.0/16",
+    "224.0.0.0/4",
+    "240.0.0.0/4",
+    "::/128",
+    "::1/128",
+    "fc00::/7",
+    "fe80::/10",
+    "ff00::/8",
+];
+
+/// PeerAddressFilter decides which peer addresses clients may create permissions
+/// and channels for. A peer address is permitted unless it is in a denied network,
+/// and addresses in allowed networks are permitted even if they are denied, e.g.
+/// to allow a single media server in a denied private network.
+///
+/// The default filter is [`PeerAddressFilter::deny_private_networks`], so that a
+/// server doesn't relay to the hosts of the networks it is in. Servers that are
+/// meant to relay to such hosts opt out with [`PeerAddressFilter::allow_all`].
+#[derive(Debug, Clone)]
+pub struct PeerAddressFilter {
+    pub allow: Vec<IpNet>,
+    pub deny: Vec<IpNet>,
+}
+
+impl Default for PeerAddressFilter {
+    fn default() -> Self {
+        PeerAddressFilter::deny_private_networks()
+    }
+}
+
+impl PeerAddressFilter {
+    /// allow_all creates a filter that permits all peer addresses, including those
+    /// in private, loopback and link-local networks.
+    pub fn allow_all() -> Self {
+        PeerAddressFilter {
+            allow: vec![],
+            deny: vec![],
+        }
+    }
+
+    /// deny_private_networks creates a filter that denies private, loopback,
+    /// link-local, multicast and reserved networks, so that the server can't be
+    /// used to reach hosts behind it.
+    pub fn deny_private_networks() -> Self {
+        PeerAddressFilter {
+            allow: vec![],
+            deny: PRIVATE_NETWORKS
+                .iter()
+                .filter_map(|n| n.parse().ok())
+                .collect(),
+        }
+    }
+
+    /// is_permitted returns true if clients may relay to ip.
+    pub fn is_permitted(&self, ip: IpAddr) -> bool {
+        // IPv4-mapped IPv6 addresses reach IPv4 hosts.
+        let ip = match ip {
+            IpAddr::V6(ip6) => match ip6.to_ipv4_mapped() {
+                Some(ip4) => IpAddr::V4(ip4),
+                None => ip,
+            },
+            ip => ip,
+        };
+
+        !self.deny.iter().any(|n| n.contains(&ip)) || self.allow.iter().any(|n| n.contains(&ip))
+    }
+}
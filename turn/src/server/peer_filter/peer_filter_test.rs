@@ -0,0 +1,54 @@
+use super::*;
+
+#[test]
+fn test_peer_address_filter_default() {
+    let filter = PeerAddressFilter::default();
+
+    for ip in ["10.0.0.1", "127.0.0.1", "169.254.1.1", "fd00::1", "::1"] {
+        assert!(!filter.is_permitted(ip.parse().unwrap()), "{ip}");
+    }
+    assert!(filter.is_permitted("203.0.113.1".parse().unwrap()));
+}
+
+#[test]
+fn test_peer_address_filter_allow_all() {
+    let filter = PeerAddressFilter::allow_all();
+
+    for ip in ["10.0.0.1", "127.0.0.1", "203.0.113.1", "::1"] {
+        assert!(filter.is_permitted(ip.parse().unwrap()), "{ip}");
+    }
+}
+
+#[test]
+fn test_peer_address_filter_deny_private_networks() {
+    let filter = PeerAddressFilter::deny_private_networks();
+
+    for ip in [
+        "10.1.2.3",
+        "172.16.0.1",
+        "192.168.1.1",
+        "127.0.0.1",
+        "169.254.169.254",
+        "100.64.0.1",
+        "0.0.0.0",
+        "::1",
+        "fd00::1",
+        "fe80::1",
+        "::ffff:10.0.0.1",
+    ] {
+        assert!(!filter.is_permitted(ip.parse().unwrap()), "{ip}");
+    }
+
+    for ip in ["203.0.113.1", "8.8.8.8", "2001:db8::1", "::ffff:8.8.8.8"] {
+        assert!(filter.is_permitted(ip.parse().unwrap()), "{ip}");
+    }
+}
+
+#[test]
+fn test_peer_address_filter_allow() {
+    let mut filter = PeerAddressFilter::deny_private_networks();
+    filter.allow.push("10.0.0.5/32".parse().unwrap());
+
+    assert!(filter.is_permitted("10.0.0.5".parse().unwrap()));
+    assert!(!filter.is_permitted("10.0.0.6".parse().unwrap()));
+}
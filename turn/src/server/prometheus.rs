@@ -0,0 +1,81 @@
+#[cfg(test)]
+mod prometheus_test;
+
+use crate::allocation::stats::RelayStats;
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+// encode renders the number of allocations and the datagrams relayed for each
+// user in the Prometheus text exposition format. Users are sorted, so that the
+// output is stable between scrapes.
+pub(crate) fn encode(allocations: usize, users: &HashMap<String, RelayStats>) -> String {
+    let mut usernames: Vec<&String> = users.keys().collect();
+    usernames.sort();
+
+    let mut out = String::new();
+    let _ = writeln!(out, "# HELP turn_allocations Number of active allocations.");
+    let _ = writeln!(out, "# TYPE turn_allocations gauge");
+    let _ = writeln!(out, "turn_allocations {allocations}");
+
+    let _ = writeln!(
+        out,
+        "# HELP turn_user_relayed_bytes_total Payload bytes relayed for a user."
+    );
+    let _ = writeln!(out, "# TYPE turn_user_relayed_bytes_total counter");
+    for username in &usernames {
+        let stats = &users[*username];
+        write_sample(
+            &mut out,
+            "turn_user_relayed_bytes_total",
+            username,
+            stats.bytes_to_peers,
+            stats.bytes_from_peers,
+        );
+    }
+
+    let _ = writeln!(
+        out,
+        "# HELP turn_user_relayed_packets_total Datagrams relayed for a user."
+    );
+    let _ = writeln!(out, "# TYPE turn_user_relayed_packets_total counter");
+    for username in &usernames {
+        let stats = &users[*username];
+        write_sample(
+            &mut out,
+            "turn_user_relayed_packets_total",
+            username,
+            stats.packets_to_peers,
+            stats.packets_from_peers,
+        );
+    }
+
+    out
+}
+
+fn write_sample(out: &mut String, name: &str, username: &str, to_peers: u64, from_peers: u64) {
+    let username = escape_label_value(username);
+    let _ = writeln!(
+        out,
+        "{name}{{username=\"{username}\",direction=\"to_peers\"}} {to_peers}"
+    );
+    let _ = writeln!(
+        out,
+        "{name}{{username=\"{username}\",direction=\"from_peers\"}} {from_peers}"
+    );
+}
+
+// escape_label_value escapes backslashes, double quotes and line feeds, which
+// usernames chosen by clients may contain.
+fn escape_label_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
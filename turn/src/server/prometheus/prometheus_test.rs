@@ -0,0 +1,35 @@
+use super::*;
+
+#[test]
+fn test_encode() {
+    let mut users = HashMap::new();
+    users.insert(
+        "bob".to_owned(),
+        RelayStats {
+            bytes_to_peers: 100,
+            packets_to_peers: 2,
+            bytes_from_peers: 300,
+            packets_from_peers: 4,
+        },
+    );
+    users.insert("alice\"\\\n".to_owned(), RelayStats::default());
+
+    let expected = "\
+# HELP turn_allocations Number of active allocations.
+# TYPE turn_allocations gauge
+turn_allocations 3
+# HELP turn_user_relayed_bytes_total Payload bytes relayed for a user.
+# TYPE turn_user_relayed_bytes_total counter
+turn_user_relayed_bytes_total{username=\"alice\\\"\\\\\\n\",direction=\"to_peers\"} 0
+turn_user_relayed_bytes_total{username=\"alice\\\"\\\\\\n\",direction=\"from_peers\"} 0
+turn_user_relayed_bytes_total{username=\"bob\",direction=\"to_peers\"} 100
+turn_user_relayed_bytes_total{username=\"bob\",direction=\"from_peers\"} 300
+# HELP turn_user_relayed_packets_total Datagrams relayed for a user.
+# TYPE turn_user_relayed_packets_total counter
+turn_user_relayed_packets_total{username=\"alice\\\"\\\\\\n\",direction=\"to_peers\"} 0
+turn_user_relayed_packets_total{username=\"alice\\\"\\\\\\n\",direction=\"from_peers\"} 0
+turn_user_relayed_packets_total{username=\"bob\",direction=\"to_peers\"} 2
+turn_user_relayed_packets_total{username=\"bob\",direction=\"from_peers\"} 4
+";
+    assert_eq!(encode(3, &users), expected);
+}
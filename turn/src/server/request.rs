@@ -5,8 +5,11 @@ use crate::allocation::allocation_manager::*;
 use crate::allocation::channel_bind::ChannelBind;
 use crate::allocation::five_tuple::*;
 use crate::allocation::permission::Permission;
+use crate::auth::oauth::*;
 use crate::auth::*;
 use crate::error::*;
+use crate::proto::accesstoken::*;
+use crate::proto::addrerror::AddressErrorCode;
 use crate::proto::chandata::ChannelData;
 use crate::proto::channum::ChannelNumber;
 use crate::proto::data::Data;
@@ -14,18 +17,26 @@ use crate::proto::evenport::EvenPort;
 use crate::proto::lifetime::*;
 use crate::proto::peeraddr::PeerAddress;
 use crate::proto::relayaddr::RelayedAddress;
+use crate::proto::reqfamily::*;
 use crate::proto::reqtrans::RequestedTransport;
 use crate::proto::rsrvtoken::ReservationToken;
 use crate::proto::*;
+use crate::server::alternate::AlternateServerHandler;
+use crate::server::events::{Event, EventHandler};
+use crate::server::peer_filter::PeerAddressFilter;
 
+use stun::addr::*;
 use stun::agent::*;
 use stun::attributes::*;
 use stun::error_code::*;
 use stun::fingerprint::*;
 use stun::integrity::*;
 use stun::message::*;
+use stun::password_algorithm::*;
+use stun::security_features::*;
 use stun::textattrs::*;
 use stun::uattrs::*;
+use stun::userhash::*;
 use stun::xoraddr::*;
 
 use util::Conn;
@@ -60,6 +71,13 @@ pub struct Request {
     pub auth_handler: Arc<dyn AuthHandler + Send + Sync>,
     pub realm: String,
     pub channel_bind_timeout: Duration,
+    pub password_algorithms: Vec<PasswordAlgorithm>,
+    pub username_anonymity: bool,
+    pub alternate_server_handler: Option<Arc<dyn AlternateServerHandler + Send + Sync>>,
+    pub peer_address_filter: Arc<PeerAddressFilter>,
+    pub oauth_key_table: Option<Arc<OAuthKeyTable>>,
+    pub access_token_sessions: Arc<Mutex<HashMap<FiveTuple, Token>>>,
+    pub event_handler: Option<Arc<dyn EventHandler + Send + Sync>>,
 }
 
 impl Request {
@@ -78,6 +96,13 @@ impl Request {
             auth_handler,
             realm: String::new(),
             channel_bind_timeout: Duration::from_secs(0),
+            password_algorithms: vec![],
+            username_anonymity: false,
+            alternate_server_handler: None,
+            peer_address_filter: Arc::new(PeerAddressFilter::default()),
+            oauth_key_table: None,
+            access_token_sessions: Arc::new(Mutex::new(HashMap::new())),
+            event_handler: None,
         }
     }
 
@@ -142,8 +167,8 @@ impl Request {
         &mut self,
         m: &Message,
         calling_method: Method,
-    ) -> Result<Option<(Username, MessageIntegrity)>> {
-        if !m.contains(ATTR_MESSAGE_INTEGRITY) {
+    ) -> Result<Option<(Username, Integrity)>> {
+        if !m.contains(ATTR_MESSAGE_INTEGRITY) && !m.contains(ATTR_MESSAGE_INTEGRITY_SHA256) {
             self.respond_with_nonce(m, calling_method, CODE_UNAUTHORIZED)
                 .await?;
             return Ok(None);
@@ -166,28 +191,7 @@ impl Request {
             return Ok(None);
         }
 
-        let to_be_deleted = {
-            // Assert Nonce exists and is not expired
-            let mut nonces = self.nonces.lock().await;
-
-            let to_be_deleted = if let Some(nonce_creation_time) = nonces.get(&nonce_attr.text) {
-                Instant::now()
-                    .checked_duration_since(*nonce_creation_time)
-                    .unwrap_or_else(|| Duration::from_secs(0))
-                    >= NONCE_LIFETIME
-            } else {
-                true
-            };
-
-            if to_be_deleted {
-                nonces.remove(&nonce_attr.text);
-            }
-            to_be_deleted
-        };
-
-        if to_be_deleted {
-            self.respond_with_nonce(m, calling_method, CODE_STALE_NONCE)
-                .await?;
+        if !self.check_nonce(m, calling_method, &nonce_attr).await? {
             return Ok(None);
         }
 
@@ -195,18 +199,95 @@ impl Request {
             build_and_send_err(&self.conn, self.src_addr, bad_request_msg, err.into()).await?;
             return Ok(None);
         }
-        if let Err(err) = username_attr.get_from(m) {
+
+        // Clients authorized by a third party protect their requests with the
+        // session key of their access token. They send the token in Allocate and
+        // Refresh requests, and USERNAME holds the key id of the token.
+        // https://tools.ietf.org/html/rfc7635#section-9
+        if let Some(key_table) = self.oauth_key_table.clone() {
+            let five_tuple = FiveTuple {
+                src_addr: self.src_addr,
+                dst_addr: self.conn.local_addr()?,
+                protocol: PROTO_UDP,
+            };
+            let token = if m.contains(ATTR_ACCESS_TOKEN) {
+                if let Err(err) = username_attr.get_from(m) {
+                    build_and_send_err(&self.conn, self.src_addr, bad_request_msg, err.into())
+                        .await?;
+                    return Ok(None);
+                }
+                let mut access_token = AccessToken::default();
+                match access_token
+                    .get_from(m)
+                    .map_err(Error::from)
+                    .and_then(|_| key_table.validate(&username_attr.text, &access_token.0))
+                {
+                    Ok(token) => Some(token),
+                    Err(err) => {
+                        self.auth_failed(&username_attr.text, &err);
+                        self.respond_with_nonce(m, calling_method, CODE_UNAUTHORIZED)
+                            .await?;
+                        return Err(err);
+                    }
+                }
+            } else {
+                self.access_token_sessions
+                    .lock()
+                    .await
+                    .get(&five_tuple)
+                    .cloned()
+            };
+
+            if let Some(token) = token {
+                return self
+                    .authenticate_with_access_token(
+                        m,
+                        calling_method,
+                        five_tuple,
+                        token,
+                        realm_attr,
+                        bad_request_msg,
+                    )
+                    .await;
+            }
+        }
+
+        let algorithm = match self.password_algorithm(m) {
+            Ok(algorithm) => algorithm,
+            Err(err) => {
+                build_and_send_err(&self.conn, self.src_addr, bad_request_msg, err).await?;
+                return Ok(None);
+            }
+        };
+
+        if self.username_anonymity && !m.contains(ATTR_USERNAME) && m.contains(ATTR_USER_HASH) {
+            let mut userhash = UserHash::default();
+            let username = userhash.get_from(m).map_err(Error::from).and_then(|_| {
+                self.auth_handler
+                    .username_from_userhash(&userhash.0, &realm_attr.text)
+            });
+            match username {
+                Ok(username) => username_attr.text = username,
+                Err(err) => {
+                    self.auth_failed("", &err);
+                    build_and_send_err(&self.conn, self.src_addr, bad_request_msg, err).await?;
+                    return Ok(None);
+                }
+            }
+        } else if let Err(err) = username_attr.get_from(m) {
             build_and_send_err(&self.conn, self.src_addr, bad_request_msg, err.into()).await?;
             return Ok(None);
         }
 
-        let our_key = match self.auth_handler.auth_handle(
+        let our_key = match self.auth_handler.auth_handle_with_algorithm(
             &username_attr.to_string(),
             &realm_attr.to_string(),
             self.src_addr,
+            algorithm,
         ) {
             Ok(key) => key,
             Err(_) => {
+                self.auth_failed(&username_attr.text, &Error::ErrNoSuchUser);
                 build_and_send_err(
                     &self.conn,
                     self.src_addr,
@@ -218,22 +299,160 @@ impl Request {
             }
         };
 
-        let mi = MessageIntegrity(our_key);
+        // The response is protected like the request.
+        let mi = if m.contains(ATTR_MESSAGE_INTEGRITY_SHA256) {
+            Integrity::Sha256(MessageIntegritySha256(our_key))
+        } else {
+            Integrity::Sha1(MessageIntegrity(our_key))
+        };
         if let Err(err) = mi.check(&mut m.clone()) {
-            build_and_send_err(&self.conn, self.src_addr, bad_request_msg, err.into()).await?;
+            self.auth_failed(&username_attr.text, &err);
+            build_and_send_err(&self.conn, self.src_addr, bad_request_msg, err).await?;
             Ok(None)
         } else {
             Ok(Some((username_attr, mi)))
         }
     }
 
+    // auth_failed passes an AuthFailed event to the event handler, if any.
+    fn auth_failed(&self, username: &str, err: &Error) {
+        if let Some(handler) = &self.event_handler {
+            handler.on_event(Event::AuthFailed {
+                src_addr: self.src_addr,
+                username: username.to_owned(),
+                reason: err.to_string(),
+            });
+        }
+    }
+
+    // check_nonce returns true if the nonce of a request was issued by the server
+    // and hasn't expired. Otherwise it responds with 438 (Stale Nonce) and a new
+    // nonce, and forgets the old one.
+    async fn check_nonce(
+        &mut self,
+        m: &Message,
+        calling_method: Method,
+        nonce_attr: &Nonce,
+    ) -> Result<bool> {
+        let stale = {
+            let mut nonces = self.nonces.lock().await;
+
+            let stale = if let Some(nonce_creation_time) = nonces.get(&nonce_attr.text) {
+                Instant::now()
+                    .checked_duration_since(*nonce_creation_time)
+                    .unwrap_or_else(|| Duration::from_secs(0))
+                    >= NONCE_LIFETIME
+            } else {
+                true
+            };
+
+            if stale {
+                nonces.remove(&nonce_attr.text);
+            }
+            stale
+        };
+
+        if stale {
+            self.respond_with_nonce(m, calling_method, CODE_STALE_NONCE)
+                .await?;
+        }
+        Ok(!stale)
+    }
+
+    // authenticate_with_access_token checks the integrity of a request with the
+    // session key of an access token, and remembers the token for the following
+    // requests of the client. The nonce was checked like for long-term
+    // credentials; as the session key doesn't depend on the realm, the realm is
+    // compared with the one of the server.
+    async fn authenticate_with_access_token(
+        &mut self,
+        m: &Message,
+        calling_method: Method,
+        five_tuple: FiveTuple,
+        token: Token,
+        realm_attr: Realm,
+        bad_request_msg: Message,
+    ) -> Result<Option<(Username, Integrity)>> {
+        let now = SystemTime::now();
+        if !token.is_valid_at(now) {
+            self.access_token_sessions.lock().await.remove(&five_tuple);
+            self.respond_with_nonce(m, calling_method, CODE_UNAUTHORIZED)
+                .await?;
+            return Err(Error::ErrAccessTokenExpired);
+        }
+
+        let mut username_attr = Username::new(ATTR_USERNAME, String::new());
+        if let Err(err) = username_attr.get_from(m) {
+            build_and_send_err(&self.conn, self.src_addr, bad_request_msg, err.into()).await?;
+            return Ok(None);
+        }
+
+        if realm_attr.text != self.realm {
+            self.auth_failed(&username_attr.text, &Error::ErrRealmMismatch);
+            self.respond_with_nonce(m, calling_method, CODE_UNAUTHORIZED)
+                .await?;
+            return Ok(None);
+        }
+
+        let mi = if m.contains(ATTR_MESSAGE_INTEGRITY_SHA256) {
+            Integrity::Sha256(MessageIntegritySha256(token.mac_key.clone()))
+        } else {
+            Integrity::Sha1(MessageIntegrity(token.mac_key.clone()))
+        };
+        if let Err(err) = mi.check(&mut m.clone()) {
+            self.auth_failed(&username_attr.text, &err);
+            build_and_send_err(&self.conn, self.src_addr, bad_request_msg, err).await?;
+            return Ok(None);
+        }
+
+        if m.contains(ATTR_ACCESS_TOKEN) {
+            let mut sessions = self.access_token_sessions.lock().await;
+            sessions.retain(|_, token| token.is_valid_at(now));
+            sessions.insert(five_tuple, token);
+        }
+
+        Ok(Some((username_attr, mi)))
+    }
+
+    // password_algorithm returns the algorithm of the key the client used. The
+    // client must echo the PASSWORD-ALGORITHMS sent with the nonce, which protects
+    // their negotiation against bid-down attacks. Requests with neither attribute
+    // use MD5.
+    //
+    // RFC 8489 Section 9.2.4
+    fn password_algorithm(&self, m: &Message) -> Result<PasswordAlgorithm> {
+        if !m.contains(ATTR_PASSWORD_ALGORITHMS) && !m.contains(ATTR_PASSWORD_ALGORITHM) {
+            return Ok(PASSWORD_ALGORITHM_MD5);
+        }
+
+        let mut algorithms = PasswordAlgorithms::default();
+        algorithms.get_from(m)?;
+        let mut algorithm = PasswordAlgorithm::default();
+        algorithm.get_from(m)?;
+        if self.password_algorithms.is_empty()
+            || algorithms.0 != self.password_algorithms
+            || !algorithms.0.contains(&algorithm)
+        {
+            return Err(Error::ErrPasswordAlgorithmsMismatch);
+        }
+
+        Ok(algorithm)
+    }
+
     async fn respond_with_nonce(
         &mut self,
         m: &Message,
         calling_method: Method,
         response_code: ErrorCode,
     ) -> Result<()> {
-        let nonce = build_nonce()?;
+        let features = SecurityFeatures {
+            password_algorithms: !self.password_algorithms.is_empty(),
+            username_anonymity: self.username_anonymity,
+        };
+        let mut nonce = build_nonce()?;
+        if features != SecurityFeatures::default() {
+            nonce = features.nonce_cookie() + &nonce;
+        }
 
         {
             // Nonce has already been taken
@@ -244,18 +463,32 @@ impl Request {
             nonces.insert(nonce.clone(), Instant::now());
         }
 
-        let msg = build_msg(
-            m.transaction_id,
-            MessageType::new(calling_method, CLASS_ERROR_RESPONSE),
-            vec![
+        let msg = {
+            let mut attrs: Vec<Box<dyn Setter>> = vec![
                 Box::new(ErrorCodeAttribute {
                     code: response_code,
                     reason: vec![],
                 }),
                 Box::new(Nonce::new(ATTR_NONCE, nonce)),
                 Box::new(Realm::new(ATTR_REALM, self.realm.clone())),
-            ],
-        )?;
+            ];
+            if features.password_algorithms {
+                attrs.push(Box::new(PasswordAlgorithms(
+                    self.password_algorithms.clone(),
+                )));
+            }
+            if let Some(key_table) = &self.oauth_key_table {
+                attrs.push(Box::new(ThirdPartyAuthorization(
+                    key_table.server_name().to_owned(),
+                )));
+            }
+
+            build_msg(
+                m.transaction_id,
+                MessageType::new(calling_method, CLASS_ERROR_RESPONSE),
+                attrs,
+            )?
+        };
 
         build_and_send(&self.conn, self.src_addr, msg).await
     }
@@ -328,6 +561,33 @@ impl Request {
             .await;
         }
 
+        // The server may redirect the client to another server, e.g. to shed
+        // load. The response is authenticated, so that the client can trust it.
+        // https://tools.ietf.org/html/rfc8489#section-10
+        if let Some(handler) = &self.alternate_server_handler {
+            let allocations = self.allocation_manager.allocation_count().await;
+            if let Some(alternate) =
+                handler.alternate_server(&username.text, self.src_addr, allocations)
+            {
+                log::debug!("redirecting {} to {}", self.src_addr, alternate);
+                let mut msg = build_msg(
+                    m.transaction_id,
+                    MessageType::new(METHOD_ALLOCATE, CLASS_ERROR_RESPONSE),
+                    vec![Box::new(ErrorCodeAttribute {
+                        code: CODE_TRY_ALTERNATE,
+                        reason: vec![],
+                    })],
+                )?;
+                AlternateServer {
+                    ip: alternate.ip(),
+                    port: alternate.port(),
+                }
+                .add_to_as(&mut msg, ATTR_ALTERNATE_SERVER)?;
+                message_integrity.add_to(&mut msg)?;
+                return build_and_send(&self.conn, self.src_addr, msg).await;
+            }
+        }
+
         // 3. The server checks if the request contains a REQUESTED-TRANSPORT
         //    attribute.  If the REQUESTED-TRANSPORT attribute is not included
         //    or is malformed, the server rejects the request with a 400 (Bad
@@ -420,6 +680,42 @@ impl Request {
             }
         }
 
+        // The server checks the address families of the relayed transport
+        // addresses. Without REQUESTED-ADDRESS-FAMILY, an IPv4 address is
+        // allocated. ADDITIONAL-ADDRESS-FAMILY asks for an IPv6 address as well.
+        // https://tools.ietf.org/html/rfc8656#section-7.2
+        let (requested_family, additional_family) = match address_families(m) {
+            Ok(families) => families,
+            Err((code, err)) => {
+                let msg = build_msg(
+                    m.transaction_id,
+                    MessageType::new(METHOD_ALLOCATE, CLASS_ERROR_RESPONSE),
+                    vec![Box::new(ErrorCodeAttribute {
+                        code,
+                        reason: vec![],
+                    })],
+                )?;
+                return build_and_send_err(&self.conn, self.src_addr, msg, err).await;
+            }
+        };
+        if !self.allocation_manager.supports_family(requested_family) {
+            let msg = build_msg(
+                m.transaction_id,
+                MessageType::new(METHOD_ALLOCATE, CLASS_ERROR_RESPONSE),
+                vec![Box::new(ErrorCodeAttribute {
+                    code: CODE_ADDR_FAMILY_NOT_SUPPORTED,
+                    reason: vec![],
+                })],
+            )?;
+            return build_and_send_err(
+                &self.conn,
+                self.src_addr,
+                msg,
+                Error::ErrAddressFamilyNotSupported,
+            )
+            .await;
+        }
+
         // 6. The server checks if the request contains an EVEN-PORT attribute.
         //    If yes, then the server checks that it can satisfy the request
         //    (i.e., can allocate a relayed transport address as described
@@ -463,6 +759,7 @@ impl Request {
         //    server is free to define this allocation quota any way it wishes,
         //    but SHOULD define it based on the username used to authenticate
         //    the request, and not on the client's transport address.
+        //    The allocation manager enforces the quota per username.
 
         // 8. Also at any point, the server MAY choose to reject the request
         //    with a 300 (Try Alternate) error if it wishes to redirect the
@@ -477,26 +774,27 @@ impl Request {
                 requested_port,
                 lifetime_duration,
                 username,
+                requested_family,
+                additional_family,
             )
             .await
         {
             Ok(a) => a,
             Err(err) => {
-                let insufficent_capacity_msg = build_msg(
+                let code = if err == Error::ErrAllocationQuotaReached {
+                    CODE_ALLOC_QUOTA_REACHED
+                } else {
+                    CODE_INSUFFICIENT_CAPACITY
+                };
+                let msg = build_msg(
                     m.transaction_id,
                     MessageType::new(METHOD_ALLOCATE, CLASS_ERROR_RESPONSE),
                     vec![Box::new(ErrorCodeAttribute {
-                        code: CODE_INSUFFICIENT_CAPACITY,
+                        code,
                         reason: vec![],
                     })],
                 )?;
-                return build_and_send_err(
-                    &self.conn,
-                    self.src_addr,
-                    insufficent_capacity_msg,
-                    err,
-                )
-                .await;
+                return build_and_send_err(&self.conn, self.src_addr, msg, err).await;
             }
         };
 
@@ -510,9 +808,10 @@ impl Request {
         //     address was reserved).
         //   * An XOR-MAPPED-ADDRESS attribute containing the client's IP address
         //     and port (from the 5-tuple).
+        //   * An ADDRESS-ERROR-CODE attribute if the additional relayed transport
+        //     address could not be allocated.
 
         let (src_ip, src_port) = (self.src_addr.ip(), self.src_addr.port());
-        let relay_ip = a.relay_addr.ip();
         let relay_port = a.relay_addr.port();
 
         let msg = {
@@ -522,17 +821,32 @@ impl Request {
                     .await;
             }
 
-            let mut response_attrs: Vec<Box<dyn Setter>> = vec![
-                Box::new(RelayedAddress {
-                    ip: relay_ip,
-                    port: relay_port,
-                }),
-                Box::new(Lifetime(lifetime_duration)),
-                Box::new(XorMappedAddress {
-                    ip: src_ip,
-                    port: src_port,
-                }),
-            ];
+            let mut response_attrs: Vec<Box<dyn Setter>> = vec![];
+            for relay_addr in a.relay_addrs() {
+                response_attrs.push(Box::new(RelayedAddress {
+                    ip: relay_addr.ip(),
+                    port: relay_addr.port(),
+                }));
+            }
+            if let Some(family) = additional_family {
+                if a.additional_relay.is_none() {
+                    let code = if self.allocation_manager.supports_family(family) {
+                        CODE_INSUFFICIENT_CAPACITY
+                    } else {
+                        CODE_ADDR_FAMILY_NOT_SUPPORTED
+                    };
+                    response_attrs.push(Box::new(AddressErrorCode {
+                        family,
+                        code,
+                        reason: vec![],
+                    }));
+                }
+            }
+            response_attrs.push(Box::new(Lifetime(lifetime_duration)));
+            response_attrs.push(Box::new(XorMappedAddress {
+                ip: src_ip,
+                port: src_port,
+            }));
 
             if !reservation_token.is_empty() {
                 response_attrs.push(Box::new(ReservationToken(
@@ -569,6 +883,39 @@ impl Request {
             protocol: PROTO_UDP,
         };
 
+        // A Refresh request for a family the allocation has no relayed address
+        // of is rejected with 443 (Peer Address Family Mismatch).
+        // https://tools.ietf.org/html/rfc8656#section-7.5
+        let mut family = RequestedAddressFamily::default();
+        if family.get_from(m).is_ok() {
+            if let Some(a) = self.allocation_manager.get_allocation(&five_tuple).await {
+                if !a
+                    .relay_addrs()
+                    .iter()
+                    .any(|addr| RequestedAddressFamily::from_ip(&addr.ip()) == family)
+                {
+                    let msg = build_msg(
+                        m.transaction_id,
+                        MessageType::new(METHOD_REFRESH, CLASS_ERROR_RESPONSE),
+                        vec![
+                            Box::new(ErrorCodeAttribute {
+                                code: CODE_PEER_ADDR_FAMILY_MISMATCH,
+                                reason: vec![],
+                            }),
+                            Box::new(message_integrity),
+                        ],
+                    )?;
+                    return build_and_send_err(
+                        &self.conn,
+                        self.src_addr,
+                        msg,
+                        Error::ErrPeerAddressFamilyMismatch,
+                    )
+                    .await;
+                }
+            }
+        }
+
         if lifetime_duration != Duration::from_secs(0) {
             let a = self.allocation_manager.get_allocation(&five_tuple).await;
             if let Some(a) = a {
@@ -614,32 +961,70 @@ impl Request {
                 log::debug!("no MessageIntegrity");
                 return Ok(());
             };
-            let mut add_count = 0;
-
-            {
-                for attr in &m.attributes.0 {
-                    if attr.typ != ATTR_XOR_PEER_ADDRESS {
-                        continue;
-                    }
+            let mut peers = vec![];
+            for single in split_attributes(m, ATTR_XOR_PEER_ADDRESS) {
+                let mut peer_address = PeerAddress::default();
+                if peer_address.get_from(&single).is_err() {
+                    peers.clear();
+                    break;
+                }
+                peers.push(SocketAddr::new(peer_address.ip, peer_address.port));
+            }
 
-                    let mut peer_address = PeerAddress::default();
-                    if peer_address.get_from(m).is_err() {
-                        add_count = 0;
-                        break;
-                    }
+            // Permissions can only be installed for peers of the address family of
+            // a relayed transport address of the allocation.
+            // https://tools.ietf.org/html/rfc8656#section-9.2
+            if peers.iter().any(|peer| a.relay_socket_for(peer).is_none()) {
+                let msg = build_msg(
+                    m.transaction_id,
+                    MessageType::new(METHOD_CREATE_PERMISSION, CLASS_ERROR_RESPONSE),
+                    vec![
+                        Box::new(ErrorCodeAttribute {
+                            code: CODE_PEER_ADDR_FAMILY_MISMATCH,
+                            reason: vec![],
+                        }),
+                        Box::new(message_integrity),
+                    ],
+                )?;
+                return build_and_send_err(
+                    &self.conn,
+                    self.src_addr,
+                    msg,
+                    Error::ErrPeerAddressFamilyMismatch,
+                )
+                .await;
+            }
 
-                    log::debug!(
-                        "adding permission for {}",
-                        format!("{}:{}", peer_address.ip, peer_address.port)
-                    );
+            // If any peer address is forbidden, no permission is installed.
+            // https://tools.ietf.org/html/rfc8656#section-9.2
+            if peers
+                .iter()
+                .any(|peer| !self.peer_address_filter.is_permitted(peer.ip()))
+            {
+                let msg = build_msg(
+                    m.transaction_id,
+                    MessageType::new(METHOD_CREATE_PERMISSION, CLASS_ERROR_RESPONSE),
+                    vec![
+                        Box::new(ErrorCodeAttribute {
+                            code: CODE_FORBIDDEN,
+                            reason: vec![],
+                        }),
+                        Box::new(message_integrity),
+                    ],
+                )?;
+                return build_and_send_err(
+                    &self.conn,
+                    self.src_addr,
+                    msg,
+                    Error::ErrPeerAddressForbidden,
+                )
+                .await;
+            }
 
-                    a.add_permission(Permission::new(SocketAddr::new(
-                        peer_address.ip,
-                        peer_address.port,
-                    )))
-                    .await;
-                    add_count += 1;
-                }
+            let add_count = peers.len();
+            for peer in peers {
+                log::debug!("adding permission for {}", peer);
+                a.add_permission(Permission::new(peer)).await;
             }
 
             let mut resp_class = CLASS_SUCCESS_RESPONSE;
@@ -685,13 +1070,26 @@ impl Request {
                 return Err(Error::ErrNoPermission);
             }
 
-            let l = a.relay_socket.send_to(&data_attr.0, msg_dst).await?;
+            if !a.allow_relay(data_attr.0.len()) {
+                log::debug!(
+                    "dropped {} bytes to {}, bandwidth limit exceeded",
+                    data_attr.0.len(),
+                    msg_dst
+                );
+                return Ok(());
+            }
+
+            let relay_socket = a
+                .relay_socket_for(&msg_dst)
+                .ok_or(Error::ErrPeerAddressFamilyMismatch)?;
+            let l = relay_socket.send_to(&data_attr.0, msg_dst).await?;
             if l != data_attr.0.len() {
                 Err(Error::ErrShortWrite)
             } else {
                 #[cfg(feature = "metrics")]
                 a.relayed_bytes
                     .fetch_add(data_attr.0.len(), Ordering::AcqRel);
+                a.record_to_peer(l);
 
                 Ok(())
             }
@@ -741,6 +1139,49 @@ impl Request {
                     .await;
             }
 
+            let peer = SocketAddr::new(peer_addr.ip, peer_addr.port);
+            if a.relay_socket_for(&peer).is_none() {
+                let msg = build_msg(
+                    m.transaction_id,
+                    MessageType::new(METHOD_CHANNEL_BIND, CLASS_ERROR_RESPONSE),
+                    vec![
+                        Box::new(ErrorCodeAttribute {
+                            code: CODE_PEER_ADDR_FAMILY_MISMATCH,
+                            reason: vec![],
+                        }),
+                        Box::new(message_integrity),
+                    ],
+                )?;
+                return build_and_send_err(
+                    &self.conn,
+                    self.src_addr,
+                    msg,
+                    Error::ErrPeerAddressFamilyMismatch,
+                )
+                .await;
+            }
+
+            if !self.peer_address_filter.is_permitted(peer_addr.ip) {
+                let msg = build_msg(
+                    m.transaction_id,
+                    MessageType::new(METHOD_CHANNEL_BIND, CLASS_ERROR_RESPONSE),
+                    vec![
+                        Box::new(ErrorCodeAttribute {
+                            code: CODE_FORBIDDEN,
+                            reason: vec![],
+                        }),
+                        Box::new(message_integrity),
+                    ],
+                )?;
+                return build_and_send_err(
+                    &self.conn,
+                    self.src_addr,
+                    msg,
+                    Error::ErrPeerAddressForbidden,
+                )
+                .await;
+            }
+
             log::debug!(
                 "binding channel {} to {}",
                 channel,
@@ -784,12 +1225,25 @@ impl Request {
         if let Some(a) = a {
             let channel = a.get_channel_addr(&c.number).await;
             if let Some(peer) = channel {
-                let l = a.relay_socket.send_to(&c.data, peer).await?;
+                if !a.allow_relay(c.data.len()) {
+                    log::debug!(
+                        "dropped {} bytes to {}, bandwidth limit exceeded",
+                        c.data.len(),
+                        peer
+                    );
+                    return Ok(());
+                }
+
+                let relay_socket = a
+                    .relay_socket_for(&peer)
+                    .ok_or(Error::ErrPeerAddressFamilyMismatch)?;
+                let l = relay_socket.send_to(&c.data, peer).await?;
                 if l != c.data.len() {
                     Err(Error::ErrShortWrite)
                 } else {
                     #[cfg(feature = "metrics")]
                     a.relayed_bytes.fetch_add(c.data.len(), Ordering::AcqRel);
+                    a.record_to_peer(l);
 
                     Ok(())
                 }
@@ -885,3 +1339,57 @@ pub(crate) fn allocation_lifetime(m: &Message) -> Duration {
 
     lifetime_duration
 }
+
+// address_families returns the family requested with REQUESTED-ADDRESS-FAMILY,
+// IPv4 if absent, and the family requested with ADDITIONAL-ADDRESS-FAMILY. If the
+// attributes are invalid, it returns the code to reject the request with.
+// https://tools.ietf.org/html/rfc8656#section-7.2
+fn address_families(
+    m: &Message,
+) -> std::result::Result<(RequestedAddressFamily, Option<RequestedAddressFamily>), (ErrorCode, Error)>
+{
+    let has_requested = m.contains(ATTR_REQUESTED_ADDRESS_FAMILY);
+    let has_additional = m.contains(ATTR_ADDITIONAL_ADDRESS_FAMILY);
+    if has_requested && has_additional {
+        return Err((
+            CODE_BAD_REQUEST,
+            Error::ErrRequestedAndAdditionalAddressFamily,
+        ));
+    }
+    if (has_requested || has_additional) && m.contains(ATTR_RESERVATION_TOKEN) {
+        return Err((
+            CODE_BAD_REQUEST,
+            Error::ErrRequestWithReservationTokenAndAddressFamily,
+        ));
+    }
+
+    let mut requested = REQUESTED_FAMILY_IPV4;
+    if has_requested {
+        match requested.get_from(m) {
+            Ok(()) => {}
+            // An unknown family is not supported, a malformed attribute is a bad request.
+            Err(stun::Error::Other(_)) => {
+                return Err((
+                    CODE_ADDR_FAMILY_NOT_SUPPORTED,
+                    Error::ErrAddressFamilyNotSupported,
+                ))
+            }
+            Err(err) => return Err((CODE_BAD_REQUEST, err.into())),
+        }
+    }
+
+    let mut additional = None;
+    if has_additional {
+        let mut family = AdditionalAddressFamily::default();
+        if family
+            .get_from_as(m, ATTR_ADDITIONAL_ADDRESS_FAMILY)
+            .is_err()
+            || family != REQUESTED_FAMILY_IPV6
+        {
+            return Err((CODE_BAD_REQUEST, Error::ErrInvalidAdditionalAddressFamily));
+        }
+        additional = Some(family);
+    }
+
+    Ok((requested, additional))
+}
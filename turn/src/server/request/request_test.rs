@@ -67,6 +67,11 @@ async fn test_allocation_lifetime_deletion_zero_lifetime() -> Result<()> {
             address: "0.0.0.0".to_owned(),
             net: Arc::new(Net::new(None)),
         }),
+        allocation_quota: None,
+        allocation_bandwidth_limit: 0,
+        total_bandwidth_limiter: None,
+        user_counters: None,
+        event_handler: None,
     }));
 
     let socket = SocketAddr::new(IpAddr::from_str("127.0.0.1")?, 5000);
@@ -91,6 +96,8 @@ async fn test_allocation_lifetime_deletion_zero_lifetime() -> Result<()> {
             0,
             Duration::from_secs(3600),
             TextAttribute::new(ATTR_USERNAME, "user".into()),
+            REQUESTED_FAMILY_IPV4,
+            None,
         )
         .await?;
     assert!(r
@@ -115,3 +122,227 @@ async fn test_allocation_lifetime_deletion_zero_lifetime() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_authenticate_request_password_algorithms() -> Result<()> {
+    let l = Arc::new(UdpSocket::bind("0.0.0.0:0").await?);
+
+    let allocation_manager = Arc::new(Manager::new(ManagerConfig {
+        relay_addr_generator: Box::new(RelayAddressGeneratorNone {
+            address: "0.0.0.0".to_owned(),
+            net: Arc::new(Net::new(None)),
+        }),
+        allocation_quota: None,
+        allocation_bandwidth_limit: 0,
+        total_bandwidth_limiter: None,
+        user_counters: None,
+        event_handler: None,
+    }));
+
+    let socket = SocketAddr::new(IpAddr::from_str("127.0.0.1")?, 5000);
+
+    let mut r = Request::new(l, socket, allocation_manager, Arc::new(TestAuthHandler {}));
+    r.password_algorithms = vec![PASSWORD_ALGORITHM_SHA256, PASSWORD_ALGORITHM_MD5];
+
+    {
+        let mut nonces = r.nonces.lock().await;
+        nonces.insert(STATIC_KEY.to_owned(), Instant::now());
+    }
+
+    let request = |algorithms: Option<Vec<PasswordAlgorithm>>| -> Result<Message> {
+        let mut setters: Vec<Box<dyn Setter>> = vec![
+            Box::new(TransactionId::new()),
+            Box::new(MessageType::new(METHOD_ALLOCATE, CLASS_REQUEST)),
+            Box::new(Nonce::new(ATTR_NONCE, STATIC_KEY.to_owned())),
+            Box::new(Realm::new(ATTR_REALM, STATIC_KEY.to_owned())),
+            Box::new(Username::new(ATTR_USERNAME, STATIC_KEY.to_owned())),
+        ];
+        if let Some(algorithms) = algorithms {
+            setters.push(Box::new(PasswordAlgorithms(algorithms)));
+            setters.push(Box::new(PASSWORD_ALGORITHM_MD5));
+        }
+        setters.push(Box::new(MessageIntegritySha256(
+            STATIC_KEY.as_bytes().to_vec(),
+        )));
+
+        let mut m = Message::new();
+        m.build(&setters)?;
+        Ok(m)
+    };
+
+    // The client echoes the offered algorithms.
+    let m = request(Some(r.password_algorithms.clone()))?;
+    let result = r.authenticate_request(&m, METHOD_ALLOCATE).await?;
+    assert!(matches!(result, Some((_, Integrity::Sha256(_)))));
+
+    // An attacker removed SHA-256 from the offer.
+    let m = request(Some(vec![PASSWORD_ALGORITHM_MD5]))?;
+    let result = r.authenticate_request(&m, METHOD_ALLOCATE).await;
+    assert!(
+        matches!(result, Err(Error::ErrPasswordAlgorithmsMismatch)),
+        "expected bid-down to be detected"
+    );
+
+    // A client that doesn't support RFC 8489 authenticates with an MD5 key.
+    let m = request(None)?;
+    let result = r.authenticate_request(&m, METHOD_ALLOCATE).await?;
+    assert!(result.is_some());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_authenticate_with_access_token_nonce() -> Result<()> {
+    let l = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let client = UdpSocket::bind("127.0.0.1:0").await?;
+
+    let allocation_manager = Arc::new(Manager::new(ManagerConfig {
+        relay_addr_generator: Box::new(RelayAddressGeneratorNone {
+            address: "0.0.0.0".to_owned(),
+            net: Arc::new(Net::new(None)),
+        }),
+        allocation_quota: None,
+        allocation_bandwidth_limit: 0,
+        total_bandwidth_limiter: None,
+        user_counters: None,
+        event_handler: None,
+    }));
+
+    let key = OAuthKey {
+        key: vec![7; 16],
+        algorithm: OAuthAlgorithm::A128Gcm,
+    };
+    let key_table = Arc::new(OAuthKeyTable::new("turn.webrtc.rs".to_owned()));
+    key_table.insert("kid".to_owned(), key.clone());
+    let token = Token {
+        mac_key: vec![9; 20],
+        timestamp: SystemTime::now(),
+        lifetime: Duration::from_secs(300),
+    };
+    let access_token = token.encrypt(&key, "turn.webrtc.rs")?;
+
+    let mut r = Request::new(
+        l,
+        client.local_addr()?,
+        allocation_manager,
+        Arc::new(TestAuthHandler {}),
+    );
+    r.realm = "webrtc.rs".to_owned();
+    r.oauth_key_table = Some(key_table);
+
+    let request = |nonce: &str, realm: &str| -> Result<Message> {
+        let setters: Vec<Box<dyn Setter>> = vec![
+            Box::new(TransactionId::new()),
+            Box::new(MessageType::new(METHOD_ALLOCATE, CLASS_REQUEST)),
+            Box::new(Nonce::new(ATTR_NONCE, nonce.to_owned())),
+            Box::new(Realm::new(ATTR_REALM, realm.to_owned())),
+            Box::new(Username::new(ATTR_USERNAME, "kid".to_owned())),
+            Box::new(AccessToken(access_token.clone())),
+            Box::new(MessageIntegrity(token.mac_key.clone())),
+        ];
+        let mut m = Message::new();
+        m.build(&setters)?;
+        Ok(m)
+    };
+    let mut buf = vec![0u8; 1500];
+    let mut response_code = || -> Result<ErrorCode> {
+        let n = client.try_recv(&mut buf)?;
+        let mut res = Message::new();
+        res.raw = buf[..n].to_vec();
+        res.decode()?;
+        let mut code = ErrorCodeAttribute::default();
+        code.get_from(&res)?;
+        Ok(code.code)
+    };
+
+    r.nonces
+        .lock()
+        .await
+        .insert("fresh".to_owned(), Instant::now());
+    let m = request("fresh", "webrtc.rs")?;
+    let result = r.authenticate_request(&m, METHOD_ALLOCATE).await?;
+    assert!(matches!(result, Some((_, Integrity::Sha1(_)))));
+
+    // A captured request is replayed after its nonce expired.
+    {
+        let mut nonces = r.nonces.lock().await;
+        nonces.remove("fresh");
+        if let Some(expired) = Instant::now().checked_sub(NONCE_LIFETIME) {
+            nonces.insert("fresh".to_owned(), expired);
+        }
+    }
+    let result = r.authenticate_request(&m, METHOD_ALLOCATE).await?;
+    assert!(result.is_none(), "a stale nonce should be rejected");
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(response_code()?, CODE_STALE_NONCE);
+    assert!(!r.nonces.lock().await.contains_key("fresh"));
+
+    // The session key doesn't depend on the realm, so it is compared.
+    r.nonces
+        .lock()
+        .await
+        .insert("other".to_owned(), Instant::now());
+    let m = request("other", "webrtc.org")?;
+    let result = r.authenticate_request(&m, METHOD_ALLOCATE).await?;
+    assert!(result.is_none(), "a foreign realm should be rejected");
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(response_code()?, CODE_UNAUTHORIZED);
+
+    Ok(())
+}
+
+#[test]
+fn test_address_families() -> Result<()> {
+    let families = |setters: Vec<Box<dyn Setter>>| -> Result<_> {
+        let mut m = Message::new();
+        m.build(&setters)?;
+        Ok(address_families(&m).map_err(|(code, _)| code))
+    };
+
+    assert_eq!(families(vec![])?, Ok((REQUESTED_FAMILY_IPV4, None)));
+    assert_eq!(
+        families(vec![Box::new(REQUESTED_FAMILY_IPV6)])?,
+        Ok((REQUESTED_FAMILY_IPV6, None))
+    );
+
+    let mut m = Message::new();
+    REQUESTED_FAMILY_IPV6.add_to_as(&mut m, ATTR_ADDITIONAL_ADDRESS_FAMILY)?;
+    assert_eq!(
+        address_families(&m).map_err(|(code, _)| code),
+        Ok((REQUESTED_FAMILY_IPV4, Some(REQUESTED_FAMILY_IPV6)))
+    );
+
+    // Both attributes.
+    REQUESTED_FAMILY_IPV6.add_to(&mut m)?;
+    assert_eq!(
+        address_families(&m).map_err(|(code, _)| code),
+        Err(CODE_BAD_REQUEST)
+    );
+
+    // ADDITIONAL-ADDRESS-FAMILY must be IPv6.
+    let mut m = Message::new();
+    REQUESTED_FAMILY_IPV4.add_to_as(&mut m, ATTR_ADDITIONAL_ADDRESS_FAMILY)?;
+    assert_eq!(
+        address_families(&m).map_err(|(code, _)| code),
+        Err(CODE_BAD_REQUEST)
+    );
+
+    // Unknown families are not supported.
+    let mut m = Message::new();
+    m.add(ATTR_REQUESTED_ADDRESS_FAMILY, &[0x03, 0, 0, 0]);
+    assert_eq!(
+        address_families(&m).map_err(|(code, _)| code),
+        Err(CODE_ADDR_FAMILY_NOT_SUPPORTED)
+    );
+
+    // Families can't be requested with RESERVATION-TOKEN.
+    assert_eq!(
+        families(vec![
+            Box::new(REQUESTED_FAMILY_IPV6),
+            Box::new(ReservationToken(vec![0; 8])),
+        ])?,
+        Err(CODE_BAD_REQUEST)
+    );
+
+    Ok(())
+}
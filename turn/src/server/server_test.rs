@@ -1,13 +1,17 @@
 use super::config::*;
+use super::events::*;
 use super::*;
 use crate::auth::generate_auth_key;
+use crate::auth::oauth::*;
 use crate::client::*;
 use crate::error::*;
 use crate::relay::relay_static::*;
+use crate::relay::{relay_dual_stack::*, RelayAddressGenerator};
 
 use crate::relay::relay_none::RelayAddressGeneratorNone;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::str::FromStr;
+use stun::error_code::*;
 use tokio::net::UdpSocket;
 use tokio::sync::mpsc;
 use util::{vnet::router::Nic, vnet::*};
@@ -57,7 +61,7 @@ async fn test_server_simple() -> Result<()> {
         }],
         realm: "webrtc.rs".to_owned(),
         auth_handler: Arc::new(TestAuthHandler::new()),
-        channel_bind_timeout: Duration::from_secs(0),
+        ..Default::default()
     })
     .await?;
 
@@ -74,6 +78,7 @@ async fn test_server_simple() -> Result<()> {
         username: String::new(),
         password: String::new(),
         realm: String::new(),
+        oauth_credential: None,
         software: String::new(),
         rto_in_ms: 0,
         conn,
@@ -191,7 +196,7 @@ async fn build_vnet() -> Result<VNet> {
         }],
         realm: "webrtc.rs".to_owned(),
         auth_handler: Arc::new(TestAuthHandler::new()),
-        channel_bind_timeout: Duration::from_secs(0),
+        ..Default::default()
     })
     .await?;
 
@@ -227,6 +232,7 @@ async fn test_server_vnet_send_binding_request() -> Result<()> {
         username: String::new(),
         password: String::new(),
         realm: String::new(),
+        oauth_credential: None,
         software: String::new(),
         rto_in_ms: 0,
         conn: lconn,
@@ -264,6 +270,7 @@ async fn test_server_vnet_echo_via_relay() -> Result<()> {
         username: "user".to_owned(),
         password: "pass".to_owned(),
         realm: String::new(),
+        oauth_credential: None,
         software: String::new(),
         rto_in_ms: 0,
         conn: lconn,
@@ -332,3 +339,588 @@ async fn test_server_vnet_echo_via_relay() -> Result<()> {
 
     Ok(())
 }
+
+async fn new_redirecting_server(
+    conn: Arc<UdpSocket>,
+    alternate: Option<SocketAddr>,
+) -> Result<Server> {
+    let alternate_server_handler: Option<Arc<dyn AlternateServerHandler + Send + Sync>> = alternate
+        .map(|alternate| {
+            Arc::new(move |_: &str, _: SocketAddr, _: usize| Some(alternate)) as Arc<_>
+        });
+
+    Server::new(ServerConfig {
+        conn_configs: vec![ConnConfig {
+            conn,
+            relay_addr_generator: Box::new(RelayAddressGeneratorStatic {
+                relay_address: IpAddr::from_str("127.0.0.1")?,
+                address: "0.0.0.0".to_owned(),
+                net: Arc::new(net::Net::new(None)),
+            }),
+        }],
+        realm: "webrtc.rs".to_owned(),
+        auth_handler: Arc::new(TestAuthHandler::new()),
+        alternate_server_handler,
+        ..Default::default()
+    })
+    .await
+}
+
+async fn new_redirected_client(turn_serv_addr: SocketAddr) -> Result<Client> {
+    let conn = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let client = Client::new(ClientConfig {
+        stun_serv_addr: turn_serv_addr.to_string(),
+        turn_serv_addr: turn_serv_addr.to_string(),
+        username: "user".to_owned(),
+        password: "pass".to_owned(),
+        realm: String::new(),
+        oauth_credential: None,
+        software: String::new(),
+        rto_in_ms: 0,
+        conn,
+        vnet: None,
+    })
+    .await?;
+    client.listen().await?;
+    Ok(client)
+}
+
+#[tokio::test]
+async fn test_server_alternate_server() -> Result<()> {
+    let conn_a = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let conn_b = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let addr_a = conn_a.local_addr()?;
+    let addr_b = conn_b.local_addr()?;
+
+    let server_a = new_redirecting_server(conn_a, Some(addr_b)).await?;
+    let server_b = new_redirecting_server(conn_b, None).await?;
+
+    let client = new_redirected_client(addr_a).await?;
+    let relay_conn = client.allocate().await?;
+
+    assert!(server_a.get_allocations_info(None).await?.is_empty());
+    let infos = server_b.get_allocations_info(None).await?;
+    assert_eq!(
+        infos.len(),
+        1,
+        "allocation should be on the alternate server"
+    );
+    assert_eq!(
+        infos.values().next().map(|info| info.username.as_str()),
+        Some("user")
+    );
+
+    relay_conn.close().await?;
+    client.close().await?;
+    server_a.close().await?;
+    server_b.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_alternate_server_loop() -> Result<()> {
+    let conn_a = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let conn_b = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let addr_a = conn_a.local_addr()?;
+    let addr_b = conn_b.local_addr()?;
+
+    let server_a = new_redirecting_server(conn_a, Some(addr_b)).await?;
+    let server_b = new_redirecting_server(conn_b, Some(addr_a)).await?;
+
+    let client = new_redirected_client(addr_a).await?;
+    let result = client.allocate().await;
+    assert!(
+        matches!(result, Err(Error::ErrAlternateServerLoop)),
+        "redirect loop should be detected"
+    );
+
+    client.close().await?;
+    server_a.close().await?;
+    server_b.close().await?;
+
+    Ok(())
+}
+
+async fn new_policy_server(
+    conns: Vec<Arc<UdpSocket>>,
+    max_allocations_per_user: usize,
+    peer_address_filter: PeerAddressFilter,
+) -> Result<Server> {
+    let mut conn_configs = vec![];
+    for conn in conns {
+        conn_configs.push(ConnConfig {
+            conn,
+            relay_addr_generator: Box::new(RelayAddressGeneratorStatic {
+                relay_address: IpAddr::from_str("127.0.0.1")?,
+                address: "0.0.0.0".to_owned(),
+                net: Arc::new(net::Net::new(None)),
+            }),
+        });
+    }
+
+    Server::new(ServerConfig {
+        conn_configs,
+        realm: "webrtc.rs".to_owned(),
+        auth_handler: Arc::new(TestAuthHandler::new()),
+        max_allocations_per_user,
+        peer_address_filter,
+        ..Default::default()
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_server_allocation_quota() -> Result<()> {
+    let conn = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let server_addr = conn.local_addr()?;
+    let server = new_policy_server(vec![conn], 1, PeerAddressFilter::default()).await?;
+
+    let client1 = new_redirected_client(server_addr).await?;
+    let relay_conn = client1.allocate().await?;
+
+    let client2 = new_redirected_client(server_addr).await?;
+    match client2.allocate().await {
+        Err(Error::Other(err)) => assert!(
+            err.contains(&CODE_ALLOC_QUOTA_REACHED.0.to_string()),
+            "unexpected error: {err}"
+        ),
+        Err(err) => panic!("unexpected error: {err}"),
+        Ok(_) => panic!("second allocation of the user should be rejected"),
+    }
+    assert_eq!(server.get_allocations_info(None).await?.len(), 1);
+
+    relay_conn.close().await?;
+    client1.close().await?;
+    client2.close().await?;
+    server.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_allocation_quota_across_listeners() -> Result<()> {
+    let conn_a = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let conn_b = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let addr_a = conn_a.local_addr()?;
+    let addr_b = conn_b.local_addr()?;
+    let server = new_policy_server(vec![conn_a, conn_b], 1, PeerAddressFilter::default()).await?;
+
+    let client1 = new_redirected_client(addr_a).await?;
+    let relay_conn = client1.allocate().await?;
+
+    let client2 = new_redirected_client(addr_b).await?;
+    match client2.allocate().await {
+        Err(Error::Other(err)) => assert!(
+            err.contains(&CODE_ALLOC_QUOTA_REACHED.0.to_string()),
+            "unexpected error: {err}"
+        ),
+        Err(err) => panic!("unexpected error: {err}"),
+        Ok(_) => panic!("the quota should hold across listeners"),
+    }
+
+    // Closing the allocation gives its quota back
+    relay_conn.close().await?;
+    client1.close().await?;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let relay_conn = client2.allocate().await?;
+
+    relay_conn.close().await?;
+    client2.close().await?;
+    server.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_peer_address_filter() -> Result<()> {
+    let peer = UdpSocket::bind("127.0.0.1:0").await?;
+    let peer_addr = peer.local_addr()?;
+
+    let conn = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let server_addr = conn.local_addr()?;
+    let server =
+        new_policy_server(vec![conn], 0, PeerAddressFilter::deny_private_networks()).await?;
+
+    let client = new_redirected_client(server_addr).await?;
+    let relay_conn = client.allocate().await?;
+
+    let result = relay_conn.send_to(b"hello", peer_addr).await;
+    match result {
+        Err(err) => assert!(
+            err.to_string().contains(&CODE_FORBIDDEN.0.to_string()),
+            "unexpected error: {err}"
+        ),
+        Ok(_) => panic!("relaying to a loopback peer should be forbidden"),
+    }
+
+    relay_conn.close().await?;
+    client.close().await?;
+    server.close().await?;
+
+    Ok(())
+}
+
+fn new_static_generator(ip: &str) -> Result<Box<dyn RelayAddressGenerator + Send + Sync>> {
+    Ok(Box::new(RelayAddressGeneratorStatic {
+        relay_address: IpAddr::from_str(ip)?,
+        address: ip.to_owned(),
+        net: Arc::new(net::Net::new(None)),
+    }))
+}
+
+async fn new_family_server(
+    conn: Arc<UdpSocket>,
+    relay_addr_generator: Box<dyn RelayAddressGenerator + Send + Sync>,
+) -> Result<Server> {
+    Server::new(ServerConfig {
+        conn_configs: vec![ConnConfig {
+            conn,
+            relay_addr_generator,
+        }],
+        realm: "webrtc.rs".to_owned(),
+        auth_handler: Arc::new(TestAuthHandler::new()),
+        peer_address_filter: PeerAddressFilter::allow_all(),
+        ..Default::default()
+    })
+    .await
+}
+
+// relay_round_trip sends through relay_conn to a new peer at ip, and back.
+async fn relay_round_trip(relay_conn: &impl Conn, relay_addr: SocketAddr, ip: &str) -> Result<()> {
+    let peer = UdpSocket::bind(SocketAddr::new(IpAddr::from_str(ip)?, 0)).await?;
+    let peer_addr = peer.local_addr()?;
+
+    relay_conn.send_to(b"ping", peer_addr).await?;
+    let mut buf = vec![0u8; 1500];
+    let (n, from) = tokio::time::timeout(Duration::from_secs(5), peer.recv_from(&mut buf))
+        .await
+        .map_err(|_| Error::Other("peer timed out".to_owned()))??;
+    assert_eq!(&buf[..n], b"ping");
+    assert_eq!(
+        from, relay_addr,
+        "should be relayed from the relayed address"
+    );
+
+    peer.send_to(b"pong", from).await?;
+    let (n, from) = tokio::time::timeout(Duration::from_secs(5), relay_conn.recv_from(&mut buf))
+        .await
+        .map_err(|_| Error::Other("client timed out".to_owned()))??;
+    assert_eq!(&buf[..n], b"pong");
+    assert_eq!(from, peer_addr);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_allocate_ipv6() -> Result<()> {
+    let conn = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let server_addr = conn.local_addr()?;
+    let server = new_family_server(conn, new_static_generator("::1")?).await?;
+
+    let client = new_redirected_client(server_addr).await?;
+    let (relay_conn, additional) = client.allocate_with_family(AllocationFamily::Ipv6).await?;
+    let relay_addr = relay_conn.local_addr()?;
+    assert!(
+        relay_addr.is_ipv6(),
+        "relayed address {relay_addr} should be IPv6"
+    );
+    assert!(additional.is_none());
+
+    relay_round_trip(&relay_conn, relay_addr, "::1").await?;
+
+    // An IPv6 allocation can't relay to IPv4 peers.
+    let result = relay_conn.send_to(b"ping", "127.0.0.1:9".parse()?).await;
+    match result {
+        Err(err) => assert!(
+            err.to_string()
+                .contains(&CODE_PEER_ADDR_FAMILY_MISMATCH.0.to_string()),
+            "unexpected error: {err}"
+        ),
+        Ok(_) => panic!("relaying to an IPv4 peer should fail"),
+    }
+
+    relay_conn.close().await?;
+    client.close().await?;
+    server.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_allocate_dual_stack() -> Result<()> {
+    let conn = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let server_addr = conn.local_addr()?;
+    let server = new_family_server(
+        conn,
+        Box::new(RelayAddressGeneratorDualStack {
+            ipv4: new_static_generator("127.0.0.1")?,
+            ipv6: new_static_generator("::1")?,
+        }),
+    )
+    .await?;
+
+    let client = new_redirected_client(server_addr).await?;
+    let (relay_conn, additional) = client
+        .allocate_with_family(AllocationFamily::DualStack)
+        .await?;
+    let relay_addr = relay_conn.local_addr()?;
+    assert!(
+        relay_addr.is_ipv4(),
+        "relayed address {relay_addr} should be IPv4"
+    );
+    let additional = additional.expect("should allocate an IPv6 relayed address");
+    assert!(
+        additional.is_ipv6(),
+        "additional address {additional} should be IPv6"
+    );
+
+    relay_round_trip(&relay_conn, relay_addr, "127.0.0.1").await?;
+    relay_round_trip(&relay_conn, additional, "::1").await?;
+
+    relay_conn.close().await?;
+    client.close().await?;
+    server.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_address_family_not_supported() -> Result<()> {
+    let conn = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let server_addr = conn.local_addr()?;
+    let server = new_family_server(conn, new_static_generator("127.0.0.1")?).await?;
+
+    let client = new_redirected_client(server_addr).await?;
+    match client.allocate_with_family(AllocationFamily::Ipv6).await {
+        Err(Error::Other(err)) => assert!(
+            err.contains(&CODE_ADDR_FAMILY_NOT_SUPPORTED.0.to_string()),
+            "unexpected error: {err}"
+        ),
+        Err(err) => panic!("unexpected error: {err}"),
+        Ok(_) => panic!("IPv6 allocation should be rejected"),
+    }
+    client.close().await?;
+
+    // A dual-stack request gets the IPv4 relayed address only.
+    let client = new_redirected_client(server_addr).await?;
+    let (relay_conn, additional) = client
+        .allocate_with_family(AllocationFamily::DualStack)
+        .await?;
+    assert!(relay_conn.local_addr()?.is_ipv4());
+    assert!(additional.is_none());
+
+    relay_conn.close().await?;
+    client.close().await?;
+    server.close().await?;
+
+    Ok(())
+}
+
+async fn new_oauth_server(conn: Arc<UdpSocket>, key_table: Arc<OAuthKeyTable>) -> Result<Server> {
+    Server::new(ServerConfig {
+        conn_configs: vec![ConnConfig {
+            conn,
+            relay_addr_generator: new_static_generator("127.0.0.1")?,
+        }],
+        realm: "webrtc.rs".to_owned(),
+        auth_handler: Arc::new(TestAuthHandler::new()),
+        oauth_key_table: Some(key_table),
+        peer_address_filter: PeerAddressFilter::allow_all(),
+        ..Default::default()
+    })
+    .await
+}
+
+async fn new_oauth_client(
+    turn_serv_addr: SocketAddr,
+    kid: &str,
+    oauth_credential: OAuthCredential,
+) -> Result<Client> {
+    let conn = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let client = Client::new(ClientConfig {
+        stun_serv_addr: turn_serv_addr.to_string(),
+        turn_serv_addr: turn_serv_addr.to_string(),
+        username: kid.to_owned(),
+        password: String::new(),
+        realm: String::new(),
+        oauth_credential: Some(oauth_credential),
+        software: String::new(),
+        rto_in_ms: 0,
+        conn,
+        vnet: None,
+    })
+    .await?;
+    client.listen().await?;
+    Ok(client)
+}
+
+// new_access_token issues an access token valid for lifetime, like an
+// authorization server.
+fn new_access_token(
+    key: &OAuthKey,
+    server_name: &str,
+    lifetime: Duration,
+) -> Result<OAuthCredential> {
+    let token = Token {
+        mac_key: rand::random::<[u8; 20]>().to_vec(),
+        timestamp: std::time::SystemTime::now(),
+        lifetime,
+    };
+    Ok(OAuthCredential {
+        access_token: token.encrypt(key, server_name)?,
+        mac_key: token.mac_key,
+    })
+}
+
+#[tokio::test]
+async fn test_server_oauth() -> Result<()> {
+    let key = OAuthKey {
+        key: rand::random::<[u8; 16]>().to_vec(),
+        algorithm: OAuthAlgorithm::A128Gcm,
+    };
+    let key_table = Arc::new(OAuthKeyTable::new("turn.webrtc.rs".to_owned()));
+    key_table.insert("kid".to_owned(), key.clone());
+
+    let conn = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let server_addr = conn.local_addr()?;
+    let server = new_oauth_server(conn, key_table).await?;
+
+    let credential = new_access_token(&key, "turn.webrtc.rs", Duration::from_secs(300))?;
+    let client = new_oauth_client(server_addr, "kid", credential).await?;
+    let relay_conn = client.allocate().await?;
+    let relay_addr = relay_conn.local_addr()?;
+    relay_round_trip(&relay_conn, relay_addr, "127.0.0.1").await?;
+    relay_conn.close().await?;
+    client.close().await?;
+
+    // Clients without access token still use long-term credentials.
+    let client = new_redirected_client(server_addr).await?;
+    let relay_conn = client.allocate().await?;
+    relay_conn.close().await?;
+    client.close().await?;
+
+    for (kid, credential) in [
+        (
+            "other",
+            new_access_token(&key, "turn.webrtc.rs", Duration::from_secs(300))?,
+        ),
+        (
+            "kid",
+            new_access_token(&key, "turn.webrtc.org", Duration::from_secs(300))?,
+        ),
+        (
+            "kid",
+            new_access_token(&key, "turn.webrtc.rs", Duration::from_secs(0))?,
+        ),
+    ] {
+        let client = new_oauth_client(server_addr, kid, credential).await?;
+        match client.allocate().await {
+            Err(Error::Other(err)) => assert!(
+                err.contains(&CODE_UNAUTHORIZED.0.to_string()),
+                "unexpected error: {err}"
+            ),
+            Err(err) => panic!("unexpected error: {err}"),
+            Ok(_) => panic!("allocation with an invalid access token should be rejected"),
+        }
+        client.close().await?;
+    }
+
+    server.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_events_and_user_stats() -> Result<()> {
+    let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+    let event_handler: Arc<dyn EventHandler + Send + Sync> = Arc::new(move |event: Event| {
+        let _ = event_tx.send(event);
+    });
+
+    let conn = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let server_addr = conn.local_addr()?;
+    let server = Server::new(ServerConfig {
+        conn_configs: vec![ConnConfig {
+            conn,
+            relay_addr_generator: new_static_generator("127.0.0.1")?,
+        }],
+        realm: "webrtc.rs".to_owned(),
+        auth_handler: Arc::new(TestAuthHandler::new()),
+        event_handler: Some(event_handler),
+        peer_address_filter: PeerAddressFilter::allow_all(),
+        ..Default::default()
+    })
+    .await?;
+
+    let client = new_redirected_client(server_addr).await?;
+    let relay_conn = client.allocate().await?;
+    let relay_addr = relay_conn.local_addr()?;
+    relay_round_trip(&relay_conn, relay_addr, "127.0.0.1").await?;
+
+    let expected = RelayStats {
+        bytes_to_peers: 4,
+        packets_to_peers: 1,
+        bytes_from_peers: 4,
+        packets_from_peers: 1,
+    };
+    let infos = server.get_allocations_info(None).await?;
+    assert_eq!(infos.values().next().map(|info| info.stats), Some(expected));
+    assert_eq!(server.get_user_stats().get("user"), Some(&expected));
+
+    relay_conn.close().await?;
+    client.close().await?;
+
+    // Stats outlive the allocation until they are taken.
+    assert_eq!(server.take_user_stats().get("user"), Some(&expected));
+    assert_eq!(
+        server
+            .get_user_stats()
+            .get("user")
+            .copied()
+            .unwrap_or_default(),
+        RelayStats::default(),
+        "taking should reset the stats"
+    );
+
+    // A wrong password is reported as an authentication failure.
+    let conn = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let client = Client::new(ClientConfig {
+        stun_serv_addr: server_addr.to_string(),
+        turn_serv_addr: server_addr.to_string(),
+        username: "user".to_owned(),
+        password: "wrong".to_owned(),
+        realm: String::new(),
+        oauth_credential: None,
+        software: String::new(),
+        rto_in_ms: 0,
+        conn,
+        vnet: None,
+    })
+    .await?;
+    client.listen().await?;
+    assert!(client.allocate().await.is_err());
+    client.close().await?;
+
+    server.close().await?;
+
+    let mut events = vec![];
+    while let Ok(event) = event_rx.try_recv() {
+        events.push(event);
+    }
+
+    assert!(
+        matches!(&events[0], Event::AllocationCreated { username, relay_addrs, .. }
+            if username == "user" && relay_addrs == &vec![relay_addr]),
+        "unexpected event: {:?}",
+        events[0]
+    );
+    assert!(events.iter().any(|event| matches!(event,
+        Event::PermissionAdded { peer, .. } if peer.is_loopback())));
+    assert!(events.iter().any(|event| matches!(event,
+        Event::AllocationDeleted { stats, .. } if *stats == expected)));
+    assert!(events.iter().any(|event| matches!(event,
+        Event::AuthFailed { username, .. } if username == "user")));
+
+    Ok(())
+}
@@ -404,6 +404,19 @@ impl MediaEngine {
         }
     }
 
+    /// is_header_extension_registered returns whether a header extension was registered
+    /// for a kind of media, whether or not it was negotiated yet.
+    pub(crate) fn is_header_extension_registered(&self, uri: &str, typ: RTPCodecType) -> bool {
+        self.header_extensions.iter().any(|ext| {
+            ext.uri == uri
+                && match typ {
+                    RTPCodecType::Audio => ext.is_audio,
+                    RTPCodecType::Video => ext.is_video,
+                    _ => false,
+                }
+        })
+    }
+
     /// get_header_extension_id returns the negotiated ID for a header extension.
     /// If the Header Extension isn't enabled ok will be false
     pub(crate) async fn get_header_extension_id(
@@ -5,8 +5,11 @@ use crate::dtls_transport::dtls_role::DTLSRole;
 use crate::ice_transport::ice_candidate_type::RTCIceCandidateType;
 use dtls::extension::extension_use_srtp::SrtpProtectionProfile;
 use ice::agent::agent_config::{InterfaceFilterFn, IpFilterFn};
+use ice::agent::agent_selection_policy::PairSelectionPolicy;
 use ice::mdns::MulticastDnsMode;
+use ice::network_monitor::ContinualGatheringPolicy;
 use ice::network_type::NetworkType;
+use ice::proxy::ProxyDialer;
 use ice::udp_network::UDPNetwork;
 
 use crate::error::{Error, Result};
@@ -42,6 +45,9 @@ pub struct Candidates {
     pub nat_1to1_ip_candidate_type: RTCIceCandidateType,
     pub multicast_dns_mode: MulticastDnsMode,
     pub multicast_dns_host_name: String,
+    pub continual_gathering_policy: ContinualGatheringPolicy,
+    pub ice_renomination: bool,
+    pub pair_selection_policy: Option<Arc<dyn PairSelectionPolicy + Send + Sync>>,
     pub username_fragment: String,
     pub password: String,
 }
@@ -71,7 +77,7 @@ pub struct SettingEngine {
     pub(crate) vnet: Option<Arc<Net>>,
     //BufferFactory                             :func(packetType packetio.BufferPacketType, ssrc uint32) io.ReadWriteCloser,
     //iceTCPMux                                 :ice.TCPMux,?
-    //iceProxyDialer                            :proxy.Dialer,?
+    pub(crate) ice_proxy_dialer: Option<Arc<dyn ProxyDialer + Send + Sync>>,
     pub(crate) udp_network: UDPNetwork,
     pub(crate) disable_media_engine_copy: bool,
     pub(crate) srtp_protection_profiles: Vec<SrtpProtectionProfile>,
@@ -235,6 +241,26 @@ impl SettingEngine {
         self.candidates.multicast_dns_host_name = host_name;
     }
 
+    /// set_ice_continual_gathering_policy controls if ice keeps gathering candidates when the
+    /// network interfaces change. With GatherContinually, gathering never completes, so it can
+    /// only be used with trickle ICE.
+    pub fn set_ice_continual_gathering_policy(&mut self, policy: ContinualGatheringPolicy) {
+        self.candidates.continual_gathering_policy = policy;
+    }
+
+    /// set_ice_renomination enables ICE renomination and advertises it with
+    /// `a=ice-options:renomination`. When controlling, the agent moves traffic to a better
+    /// candidate pair when one succeeds later, as decided by `policy`, or by the default
+    /// policy if it is None. Renomination is only used when the remote peer advertises it too.
+    pub fn set_ice_renomination(
+        &mut self,
+        enabled: bool,
+        policy: Option<Arc<dyn PairSelectionPolicy + Send + Sync>>,
+    ) {
+        self.candidates.ice_renomination = enabled;
+        self.candidates.pair_selection_policy = policy;
+    }
+
     /// set_ice_credentials sets a staic uFrag/uPwd to be used by ice
     /// This is useful if you want to do signalless WebRTC session, or having a reproducible environment with static credentials
     pub fn set_ice_credentials(&mut self, username_fragment: String, password: String) {
@@ -293,10 +319,12 @@ impl SettingEngine {
     //    self.iceTCPMux = tcpMux
     //}
 
-    // SetICEProxyDialer sets the proxy dialer interface based on golang.org/x/net/proxy.
-    //pub fn SetICEProxyDialer(&mut self, d proxy.Dialer) {
-    //    self.iceProxyDialer = d
-    //}
+    /// set_ice_proxy_dialer sets the dialer through which TURN servers with `transport=tcp`
+    /// are connected, for networks that only allow traffic through a proxy. `Socks5Dialer`
+    /// and `HttpConnectDialer` are provided by the ice crate.
+    pub fn set_ice_proxy_dialer(&mut self, d: Arc<dyn ProxyDialer + Send + Sync>) {
+        self.ice_proxy_dialer = Some(d);
+    }
 
     /// disable_media_engine_copy stops the MediaEngine from being copied. This allows a user to modify
     /// the MediaEngine after the PeerConnection has been constructed. This is useful if you wish to
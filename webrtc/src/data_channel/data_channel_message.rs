@@ -4,7 +4,7 @@ use bytes::Bytes;
 /// data channel. IsString will be set to true if the incoming
 /// message is of the string type. Otherwise the message is of
 /// a binary type.
-#[derive(Default, Debug, Clone)]
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct DataChannelMessage {
     pub is_string: bool,
     pub data: Bytes,
@@ -0,0 +1,201 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::{Sink, Stream};
+use tokio::sync::{mpsc, Notify};
+use util::sync::Mutex as SyncMutex;
+
+use super::data_channel_message::DataChannelMessage;
+use super::RTCDataChannel;
+use crate::error::{Error, Result};
+
+/// Number of received messages buffered by [`RTCDataChannelStream`] before
+/// the read loop of the underlying data channel is paused.
+const MESSAGE_QUEUE_SIZE: usize = 64;
+
+type PendingFut = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+type MessageSender = Arc<SyncMutex<Option<mpsc::Sender<DataChannelMessage>>>>;
+
+/// A message-oriented wrapper around [`RTCDataChannel`], which implements
+/// [`Stream`] of incoming [`DataChannelMessage`]s and [`Sink`] of outgoing ones.
+///
+/// The stream takes over the `on_message`, `on_close` and `on_buffered_amount_low`
+/// handlers of the data channel. It ends once the data channel is closed, either by
+/// the remote peer or by closing the sink.
+///
+/// The sink only becomes ready when the buffered amount of the data channel is at
+/// or below `buffered_amount_low_threshold`, so a slow remote peer exerts backpressure
+/// on the sender. The threshold is 0 by default, which means every message waits
+/// for the previous one to be acknowledged; raise it with
+/// [`RTCDataChannel::set_buffered_amount_low_threshold`] to allow more data in flight.
+///
+/// Detached data channels never invoke `on_message`; use
+/// [`RTCDataChannel::detach_poll`] to get a byte-stream wrapper for them instead.
+pub struct RTCDataChannelStream {
+    data_channel: Arc<RTCDataChannel>,
+    message_tx: MessageSender,
+    message_rx: mpsc::Receiver<DataChannelMessage>,
+    buffered_amount_low: Arc<Notify>,
+
+    ready_fut: Option<PendingFut>,
+    send_fut: Option<PendingFut>,
+    close_fut: Option<PendingFut>,
+}
+
+impl RTCDataChannelStream {
+    /// Constructs a new `RTCDataChannelStream`.
+    pub async fn new(data_channel: Arc<RTCDataChannel>) -> Self {
+        let (message_tx, message_rx) = mpsc::channel(MESSAGE_QUEUE_SIZE);
+        let message_tx: MessageSender = Arc::new(SyncMutex::new(Some(message_tx)));
+
+        let message_tx2 = Arc::clone(&message_tx);
+        data_channel.on_message(Box::new(move |msg: DataChannelMessage| {
+            let message_tx3 = message_tx2.lock().clone();
+            Box::pin(async move {
+                if let Some(message_tx) = message_tx3 {
+                    // A full queue suspends the read loop, which in turn lets the SCTP
+                    // receive window fill up and slows the remote sender down.
+                    let _ = message_tx.send(msg).await;
+                }
+            })
+        }));
+        let message_tx2 = Arc::clone(&message_tx);
+        data_channel.on_close(Box::new(move || {
+            message_tx2.lock().take();
+            Box::pin(async {})
+        }));
+
+        let buffered_amount_low = Arc::new(Notify::new());
+        let buffered_amount_low2 = Arc::clone(&buffered_amount_low);
+        data_channel
+            .on_buffered_amount_low(Box::new(move || {
+                buffered_amount_low2.notify_one();
+                Box::pin(async {})
+            }))
+            .await;
+
+        Self {
+            data_channel,
+            message_tx,
+            message_rx,
+            buffered_amount_low,
+            ready_fut: None,
+            send_fut: None,
+            close_fut: None,
+        }
+    }
+
+    /// Get back the inner data_channel.
+    pub fn into_inner(self) -> Arc<RTCDataChannel> {
+        self.data_channel
+    }
+
+    /// Obtain a clone of the inner data_channel.
+    pub fn clone_inner(&self) -> Arc<RTCDataChannel> {
+        Arc::clone(&self.data_channel)
+    }
+
+    fn poll_send(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        match self.send_fut.as_mut() {
+            Some(fut) => {
+                let result = futures::ready!(fut.as_mut().poll(cx));
+                self.send_fut = None;
+                Poll::Ready(result)
+            }
+            None => Poll::Ready(Ok(())),
+        }
+    }
+}
+
+impl Stream for RTCDataChannelStream {
+    type Item = DataChannelMessage;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.message_rx.poll_recv(cx)
+    }
+}
+
+impl Sink<DataChannelMessage> for RTCDataChannelStream {
+    type Error = Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        futures::ready!(self.poll_send(cx))?;
+
+        let fut = match self.ready_fut.as_mut() {
+            Some(fut) => fut,
+            None => {
+                let data_channel = Arc::clone(&self.data_channel);
+                let buffered_amount_low = Arc::clone(&self.buffered_amount_low);
+                self.ready_fut.insert(Box::pin(async move {
+                    loop {
+                        if data_channel.buffered_amount().await
+                            <= data_channel.buffered_amount_low_threshold().await
+                        {
+                            return Ok(());
+                        }
+                        // `notify_one` stores a permit, so a notification that races with
+                        // the check above is not lost.
+                        buffered_amount_low.notified().await;
+                    }
+                }))
+            }
+        };
+
+        let result = futures::ready!(fut.as_mut().poll(cx));
+        self.ready_fut = None;
+        Poll::Ready(result)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, msg: DataChannelMessage) -> Result<()> {
+        let data_channel = Arc::clone(&self.data_channel);
+        self.send_fut = Some(Box::pin(async move {
+            if msg.is_string {
+                let s = String::from_utf8(msg.data.to_vec())?;
+                data_channel.send_text(s).await?;
+            } else {
+                data_channel.send(&msg.data).await?;
+            }
+            Ok(())
+        }));
+        Ok(())
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.poll_send(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        futures::ready!(self.poll_send(cx))?;
+
+        let fut = match self.close_fut.as_mut() {
+            Some(fut) => fut,
+            None => {
+                let data_channel = Arc::clone(&self.data_channel);
+                let message_tx = Arc::clone(&self.message_tx);
+                self.close_fut.insert(Box::pin(async move {
+                    let result = data_channel.close().await;
+                    // A locally closed data channel stops its read loop without
+                    // invoking `on_close`, so end the stream here.
+                    message_tx.lock().take();
+                    result
+                }))
+            }
+        };
+
+        let result = futures::ready!(fut.as_mut().poll(cx));
+        self.close_fut = None;
+        Poll::Ready(result)
+    }
+}
+
+impl fmt::Debug for RTCDataChannelStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RTCDataChannelStream")
+            .field("label", &self.data_channel.label())
+            .field("id", &self.data_channel.id())
+            .finish()
+    }
+}
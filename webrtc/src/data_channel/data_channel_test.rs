@@ -1203,6 +1203,93 @@ async fn test_eof_no_detach() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_data_channel_stream_and_sink() -> Result<()> {
+    use data_channel_stream::RTCDataChannelStream;
+    use futures::{SinkExt, StreamExt};
+
+    let label: &str = "test-channel";
+
+    let api = APIBuilder::new().build();
+
+    // Set up two peer connections.
+    let mut pca = api.new_peer_connection(RTCConfiguration::default()).await?;
+    let mut pcb = api.new_peer_connection(RTCConfiguration::default()).await?;
+
+    // pcb echoes every message back through its own stream.
+    pcb.on_data_channel(Box::new(move |dc: Arc<RTCDataChannel>| {
+        if dc.label() != label {
+            return Box::pin(async {});
+        }
+        Box::pin(async move {
+            let mut stream = RTCDataChannelStream::new(dc).await;
+            tokio::spawn(async move {
+                while let Some(msg) = stream.next().await {
+                    if stream.send(msg).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        })
+    }));
+
+    let dca = pca.create_data_channel(label, None).await?;
+    dca.set_buffered_amount_low_threshold(1024).await;
+
+    let (open_tx, mut open_rx) = mpsc::channel::<()>(1);
+    dca.on_open(Box::new(move || {
+        Box::pin(async move {
+            let _ = open_tx.send(()).await;
+        })
+    }));
+    let mut stream = RTCDataChannelStream::new(Arc::clone(&dca)).await;
+
+    signal_pair(&mut pca, &mut pcb).await?;
+
+    let _ = open_rx.recv().await;
+
+    let messages = vec![
+        DataChannelMessage {
+            is_string: true,
+            data: Bytes::from_static(b"ping"),
+        },
+        DataChannelMessage {
+            is_string: false,
+            data: Bytes::from_static(&[0, 1, 2, 3]),
+        },
+        DataChannelMessage {
+            is_string: true,
+            data: Bytes::from_static(b"pong"),
+        },
+    ];
+
+    for msg in &messages {
+        stream.feed(msg.clone()).await?;
+    }
+    stream.flush().await?;
+
+    for expected in &messages {
+        let msg = tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("timed out waiting for echo")
+            .expect("stream ended unexpectedly");
+        assert_eq!(&msg, expected, "echoed message mismatch");
+    }
+
+    stream.close().await?;
+    let next = tokio::time::timeout(Duration::from_secs(5), stream.next())
+        .await
+        .expect("timed out waiting for the stream to end");
+    assert!(
+        next.is_none(),
+        "stream should end once the channel is closed"
+    );
+
+    close_pair_now(&pca, &pcb).await;
+
+    Ok(())
+}
+
 // Assert that a Session Description that doesn't follow
 // draft-ietf-mmusic-sctp-sdp is still accepted
 #[tokio::test]
@@ -5,6 +5,7 @@ pub mod data_channel_init;
 pub mod data_channel_message;
 pub mod data_channel_parameters;
 pub mod data_channel_state;
+pub mod data_channel_stream;
 
 use data_channel_message::*;
 use data_channel_parameters::*;
@@ -21,6 +22,7 @@ use std::{
     time::SystemTime,
 };
 
+use data::data_channel::PollDataChannel;
 use data::message::message_channel_open::ChannelType;
 use sctp::stream::OnBufferedAmountLowFn;
 use tokio::sync::{Mutex, Notify};
@@ -179,17 +181,6 @@ impl RTCDataChannel {
 
             let dc = data::data_channel::DataChannel::dial(&association, self.id(), cfg).await?;
 
-            // buffered_amount_low_threshold and on_buffered_amount_low might be set earlier
-            dc.set_buffered_amount_low_threshold(
-                self.buffered_amount_low_threshold.load(Ordering::SeqCst),
-            );
-            {
-                let mut on_buffered_amount_low = self.on_buffered_amount_low.lock().await;
-                if let Some(f) = on_buffered_amount_low.take() {
-                    dc.on_buffered_amount_low(f);
-                }
-            }
-
             self.handle_open(Arc::new(dc)).await;
 
             Ok(())
@@ -262,6 +253,17 @@ impl RTCDataChannel {
     }
 
     pub(crate) async fn handle_open(&self, dc: Arc<data::data_channel::DataChannel>) {
+        // buffered_amount_low_threshold and on_buffered_amount_low might be set earlier
+        dc.set_buffered_amount_low_threshold(
+            self.buffered_amount_low_threshold.load(Ordering::SeqCst),
+        );
+        {
+            let mut on_buffered_amount_low = self.on_buffered_amount_low.lock().await;
+            if let Some(f) = on_buffered_amount_low.take() {
+                dc.on_buffered_amount_low(f);
+            }
+        }
+
         {
             let mut data_channel = self.data_channel.lock().await;
             *data_channel = Some(Arc::clone(&dc));
@@ -418,6 +420,14 @@ impl RTCDataChannel {
         }
     }
 
+    /// detach_poll detaches the underlying datachannel like detach does, and
+    /// wraps it into a PollDataChannel, which implements AsyncRead and AsyncWrite.
+    /// This makes it possible to use the datachannel as a byte stream, e.g.
+    /// together with tokio_util codecs.
+    pub async fn detach_poll(&self) -> Result<PollDataChannel> {
+        Ok(PollDataChannel::new(self.detach().await?))
+    }
+
     /// Close Closes the DataChannel. It may be called regardless of whether
     /// the DataChannel object was created by this peer or the remote peer.
     pub async fn close(&self) -> Result<()> {
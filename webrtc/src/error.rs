@@ -334,6 +334,28 @@ pub enum Error {
     ErrRTPSenderDTLSTransportNil,
     #[error("Send has already been called")]
     ErrRTPSenderSendAlreadyCalled,
+    #[error("RTPSender has been stopped")]
+    ErrRTPSenderStopped,
+    #[error("encoding RID must not be empty")]
+    ErrRTPSenderRidNil,
+    #[error("an encoding with this RID already exists")]
+    ErrRTPSenderRIDCollision,
+    #[error("no encoding found for RID")]
+    ErrRTPSenderNoEncodingForRid,
+    #[error("RTPSender has no base encoding with a RID")]
+    ErrRTPSenderNoBaseEncoding,
+    #[error("encoding track must have the same id, stream id and kind as the base encoding")]
+    ErrRTPSenderBaseEncodingMismatch,
+    #[error("set_parameters called without a preceding get_parameters")]
+    ErrRTPSenderNoParameters,
+    #[error("transaction_id doesn't match the last get_parameters call")]
+    ErrRTPSenderTransactionIdMismatch,
+    #[error("set_parameters can't modify read-only parameters")]
+    ErrRTPSenderReadOnlyParameterModified,
+    #[error("scale_resolution_down_by must not be less than 1.0")]
+    ErrRTPSenderScaleResolutionDownByRange,
+    #[error("max_framerate must not be negative")]
+    ErrRTPSenderMaxFramerateRange,
     #[error("errRTPSenderTrackNil")]
     ErrRTPTransceiverCannotChangeMid,
     #[error("invalid state change in RTPTransceiver.setSending")]
@@ -404,6 +426,8 @@ pub enum Error {
     Rtcp(#[from] rtcp::Error),
     #[error("{0}")]
     Rtp(#[from] rtp::Error),
+    #[error("{0}")]
+    Media(#[from] media::Error),
 
     #[error("utf-8 error: {0}")]
     Utf8(#[from] FromUtf8Error),
@@ -128,10 +128,13 @@ impl RTCIceGatherer {
                 .candidates
                 .multicast_dns_host_name
                 .clone(),
+            continual_gathering_policy: self.setting_engine.candidates.continual_gathering_policy,
+            renomination: self.setting_engine.candidates.ice_renomination,
+            pair_selection_policy: self.setting_engine.candidates.pair_selection_policy.clone(),
             local_ufrag: self.setting_engine.candidates.username_fragment.clone(),
             local_pwd: self.setting_engine.candidates.password.clone(),
             //TODO: TCPMux:                 self.setting_engine.iceTCPMux,
-            //TODO: ProxyDialer:            self.setting_engine.iceProxyDialer,
+            proxy_dialer: self.setting_engine.ice_proxy_dialer.clone(),
             ..Default::default()
         };
 
@@ -233,6 +236,7 @@ impl RTCIceGatherer {
             username_fragment: frag,
             password: pwd,
             ice_lite: false,
+            renomination: self.setting_engine.candidates.ice_renomination,
         })
     }
 
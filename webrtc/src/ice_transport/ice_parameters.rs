@@ -7,4 +7,30 @@ pub struct RTCIceParameters {
     pub username_fragment: String,
     pub password: String,
     pub ice_lite: bool,
+    /// The agent supports ICE renomination, advertised with `a=ice-options:renomination`.
+    /// It is used when both agents support it.
+    #[serde(default)]
+    pub renomination: bool,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ice_parameters_deserialize_without_renomination() {
+        let parameters = serde_json::from_str::<RTCIceParameters>(
+            r#"{"username_fragment":"ufrag","password":"pwd","ice_lite":false}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            parameters,
+            RTCIceParameters {
+                username_fragment: "ufrag".to_owned(),
+                password: "pwd".to_owned(),
+                ice_lite: false,
+                renomination: false,
+            }
+        );
+    }
 }
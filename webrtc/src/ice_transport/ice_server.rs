@@ -1,6 +1,21 @@
 use crate::error::{Error, Result};
 use crate::ice_transport::ice_credential_type::RTCIceCredentialType;
 
+/// OAuthCredential represents OAuth credential information which is used by
+/// the STUN/TURN client to connect to an ICE server as defined in
+/// <https://tools.ietf.org/html/rfc7635>. Note that the kid parameter is not
+/// located in OAuthCredential, but in ICEServer's username member.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct RTCOAuthCredential {
+    /// mac_key is the base64 encoded session key, which protects the
+    /// requests to the TURN server with MESSAGE-INTEGRITY.
+    pub mac_key: String,
+
+    /// access_token is the base64 encoded self-contained token, which is
+    /// opaque to the application.
+    pub access_token: String,
+}
+
 /// ICEServer describes a single STUN and TURN server that can be used by
 /// the ICEAgent to establish a connection with a peer.
 #[derive(Default, Debug, Clone)]
@@ -9,6 +24,9 @@ pub struct RTCIceServer {
     pub username: String,
     pub credential: String,
     pub credential_type: RTCIceCredentialType,
+    /// oauth_credential is the credential of TURN servers with credential_type
+    /// Oauth, instead of credential.
+    pub oauth_credential: Option<RTCOAuthCredential>,
 }
 
 impl RTCIceServer {
@@ -29,7 +47,12 @@ impl RTCIceServer {
             if url.scheme == ice::url::SchemeType::Turn || url.scheme == ice::url::SchemeType::Turns
             {
                 // https://www.w3.org/TR/webrtc/#set-the-configuration (step #11.3.2)
-                if self.username.is_empty() || self.credential.is_empty() {
+                let has_credential = if self.credential_type == RTCIceCredentialType::Oauth {
+                    self.oauth_credential.is_some()
+                } else {
+                    !self.credential.is_empty()
+                };
+                if self.username.is_empty() || !has_credential {
                     return Err(Error::ErrNoTurnCredentials);
                 }
                 url.username = self.username.clone();
@@ -41,11 +64,15 @@ impl RTCIceServer {
                     }
                     RTCIceCredentialType::Oauth => {
                         // https://www.w3.org/TR/webrtc/#set-the-configuration (step #11.3.4)
-                        /*if _, ok: = s.Credential.(OAuthCredential); !ok {
-                                return nil,
-                                &rtcerr.InvalidAccessError{Err: ErrTurnCredentials
-                            }
-                        }*/
+                        if let Some(oauth_credential) = &self.oauth_credential {
+                            url.oauth_credential = Some(
+                                turn::auth::oauth::OAuthCredential::from_base64(
+                                    &oauth_credential.mac_key,
+                                    &oauth_credential.access_token,
+                                )
+                                .map_err(|_| Error::ErrTurnCredentials)?,
+                            );
+                        }
                     }
                     _ => return Err(Error::ErrTurnCredentials),
                 };
@@ -71,6 +98,7 @@ mod test {
                     username: "unittest".to_owned(),
                     credential: "placeholder".to_owned(),
                     credential_type: RTCIceCredentialType::Password,
+                    oauth_credential: None,
                 },
                 true,
             ),
@@ -80,18 +108,23 @@ mod test {
                     username: "unittest".to_owned(),
                     credential: "placeholder".to_owned(),
                     credential_type: RTCIceCredentialType::Password,
+                    oauth_credential: None,
                 },
                 true,
             ),
-            /*TODO:(ICEServer{
-                URLs:     []string{"turn:192.158.29.39?transport=udp"},
-                Username: "unittest".to_owned(),
-                Credential: OAuthCredential{
-                    MACKey:      "WmtzanB3ZW9peFhtdm42NzUzNG0=",
-                    AccessToken: "AAwg3kPHWPfvk9bDFL936wYvkoctMADzQ5VhNDgeMR3+ZlZ35byg972fW8QjpEl7bx91YLBPFsIhsxloWcXPhA==",
+            (
+                RTCIceServer {
+                    urls: vec!["turn:192.158.29.39?transport=udp".to_owned()],
+                    username: "unittest".to_owned(),
+                    credential: String::new(),
+                    credential_type: RTCIceCredentialType::Oauth,
+                    oauth_credential: Some(RTCOAuthCredential {
+                        mac_key: "WmtzanB3ZW9peFhtdm42NzUzNG0=".to_owned(),
+                        access_token: "AAwg3kPHWPfvk9bDFL936wYvkoctMADzQ5VhNDgeMR3+ZlZ35byg972fW8QjpEl7bx91YLBPFsIhsxloWcXPhA==".to_owned(),
+                    }),
                 },
-                CredentialType: ICECredentialTypeOauth,
-            }, true),*/
+                true,
+            ),
         ];
 
         for (ice_server, expected_validate) in tests {
@@ -116,6 +149,7 @@ mod test {
                     username: "unittest".to_owned(),
                     credential: String::new(),
                     credential_type: RTCIceCredentialType::Password,
+                    oauth_credential: None,
                 },
                 Error::ErrNoTurnCredentials,
             ),
@@ -125,6 +159,7 @@ mod test {
                     username: "unittest".to_owned(),
                     credential: String::new(),
                     credential_type: RTCIceCredentialType::Oauth,
+                    oauth_credential: None,
                 },
                 Error::ErrNoTurnCredentials,
             ),
@@ -134,9 +169,23 @@ mod test {
                     username: "unittest".to_owned(),
                     credential: String::new(),
                     credential_type: RTCIceCredentialType::Unspecified,
+                    oauth_credential: None,
                 },
                 Error::ErrNoTurnCredentials,
             ),
+            (
+                RTCIceServer {
+                    urls: vec!["turn:192.158.29.39?transport=udp".to_owned()],
+                    username: "unittest".to_owned(),
+                    credential: String::new(),
+                    credential_type: RTCIceCredentialType::Oauth,
+                    oauth_credential: Some(RTCOAuthCredential {
+                        mac_key: "not base64!".to_owned(),
+                        access_token: String::new(),
+                    }),
+                },
+                Error::ErrTurnCredentials,
+            ),
         ];
 
         for (ice_server, expected_err) in tests {
@@ -156,6 +205,7 @@ mod test {
                 username: "unittest".to_owned(),
                 credential: String::new(),
                 credential_type: RTCIceCredentialType::Oauth,
+                oauth_credential: None,
             },
             ice::Error::ErrStunQuery,
         )];
@@ -148,6 +148,10 @@ impl RTCIceTransport {
                 RTCIceRole::Controlled
             };
 
+            agent.set_renomination(
+                self.gatherer.setting_engine.candidates.ice_renomination && params.renomination,
+            );
+
             let (cancel_tx, cancel_rx) = mpsc::channel(1);
             {
                 let mut internal = self.internal.lock().await;
@@ -24,6 +24,7 @@ pub mod error;
 pub mod ice_transport;
 pub mod mux;
 pub mod peer_connection;
+pub mod record;
 pub mod rtp_transceiver;
 pub mod sctp_transport;
 pub mod stats;
@@ -40,6 +41,8 @@ pub(crate) const UNSPECIFIED_STR: &str = "Unspecified";
 pub(crate) const RECEIVE_MTU: usize = 1460;
 
 pub(crate) const SDP_ATTRIBUTE_RID: &str = "rid";
+pub(crate) const SDP_ATTRIBUTE_ICE_OPTIONS: &str = "ice-options";
+pub(crate) const ICE_OPTION_RENOMINATION: &str = "renomination";
 pub(crate) const GENERATED_CERTIFICATE_ORIGIN: &str = "WebRTC";
 pub(crate) const SDES_REPAIR_RTP_STREAM_ID_URI: &str =
     "urn:ietf:params:rtp-hdrext:sdes:repaired-rtp-stream-id";
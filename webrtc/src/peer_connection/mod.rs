@@ -73,7 +73,7 @@ use ::sdp::description::session::*;
 use ::sdp::util::ConnectionRole;
 use arc_swap::ArcSwapOption;
 use async_trait::async_trait;
-use interceptor::{stats, Attributes, Interceptor, RTCPWriter};
+use interceptor::{stats, sync, Attributes, Interceptor, RTCPWriter};
 use peer_connection_internal::*;
 use rand::{thread_rng, Rng};
 use rcgen::KeyPair;
@@ -221,18 +221,29 @@ impl RTCPeerConnection {
     pub(crate) async fn new(api: &API, mut configuration: RTCConfiguration) -> Result<Self> {
         RTCPeerConnection::init_configuration(&mut configuration)?;
 
-        let (interceptor, stats_interceptor): (Arc<dyn Interceptor + Send + Sync>, _) = {
+        let (interceptor, stats_interceptor, sync_interceptor): (
+            Arc<dyn Interceptor + Send + Sync>,
+            _,
+            _,
+        ) = {
             let mut chain = api.interceptor_registry.build_chain("")?;
             let stats_interceptor = stats::make_stats_interceptor("");
             chain.add(stats_interceptor.clone());
+            let sync_interceptor = Arc::new(sync::SyncInterceptor::new());
+            chain.add(sync_interceptor.clone());
 
-            (Arc::new(chain), stats_interceptor)
+            (Arc::new(chain), stats_interceptor, sync_interceptor)
         };
 
         let weak_interceptor = Arc::downgrade(&interceptor);
-        let (internal, configuration) =
-            PeerConnectionInternal::new(api, weak_interceptor, stats_interceptor, configuration)
-                .await?;
+        let (internal, configuration) = PeerConnectionInternal::new(
+            api,
+            weak_interceptor,
+            stats_interceptor,
+            sync_interceptor,
+            configuration,
+        )
+        .await?;
         let internal_rtcp_writer = Arc::clone(&internal) as Arc<dyn RTCPWriter + Send + Sync>;
         let interceptor_rtcp_writer = interceptor.bind_rtcp_writer(internal_rtcp_writer).await;
 
@@ -1377,6 +1388,8 @@ impl RTCPeerConnection {
                                 Arc::clone(&self.internal.media_engine),
                                 Arc::clone(&self.interceptor),
                             ));
+                            receiver
+                                .set_sync_interceptor(Arc::clone(&self.internal.sync_interceptor));
 
                             let sender = Arc::new(
                                 RTCRtpSender::new(
@@ -1545,6 +1558,13 @@ impl RTCPeerConnection {
 
             //log::trace!("start_transports: parsed={:?}", parsed);
 
+            let remote_ice_parameters = RTCIceParameters {
+                username_fragment: remote_ufrag,
+                password: remote_pwd,
+                ice_lite: remote_is_lite,
+                renomination: has_ice_option_renomination(parsed),
+            };
+
             let pci = Arc::clone(&self.internal);
             let dtls_role = DTLSRole::from(parsed);
             let remote_desc = Arc::new(desc);
@@ -1554,8 +1574,7 @@ impl RTCPeerConnection {
                     move || {
                         let pc = Arc::clone(&pci);
                         let rd = Arc::clone(&remote_desc);
-                        let ru = remote_ufrag.clone();
-                        let rp = remote_pwd.clone();
+                        let rp = remote_ice_parameters.clone();
                         let fp = fingerprint.clone();
                         let fp_hash = fingerprint_hash.clone();
                         Box::pin(async move {
@@ -1564,7 +1583,7 @@ impl RTCPeerConnection {
                                 ice_role,
                                 dtls_role,
                             );
-                            pc.start_transports(ice_role, dtls_role, ru, rp, fp, fp_hash)
+                            pc.start_transports(ice_role, dtls_role, rp, fp, fp_hash)
                                 .await;
 
                             if we_offer {
@@ -1587,7 +1606,7 @@ impl RTCPeerConnection {
         for transceiver in &*current_transceivers {
             let sender = transceiver.sender();
             if sender.is_negotiated() && !sender.has_sent() {
-                sender.send(&sender.get_parameters().await).await?;
+                sender.send(&sender.current_parameters().await).await?;
             }
         }
 
@@ -1699,7 +1718,7 @@ impl RTCPeerConnection {
 
         let transceiver = self
             .internal
-            .new_transceiver_from_track(RTCRtpTransceiverDirection::Sendrecv, track)
+            .new_transceiver_from_track(RTCRtpTransceiverDirection::Sendrecv, vec![], track)
             .await?;
         self.internal
             .add_rtp_transceiver(Arc::clone(&transceiver))
@@ -1758,6 +1777,12 @@ impl RTCPeerConnection {
     }
 
     /// add_transceiver_from_track Create a new RtpTransceiver(SendRecv or SendOnly) and add it to the set of transceivers.
+    ///
+    /// To send simulcast, pass one `send_encodings` entry per layer, each with its own RID, and
+    /// a track created with that RID (e.g. [`TrackLocalStaticSample::new_with_rid`]). Tracks for
+    /// the other layers are attached with [`RTCRtpSender::add_encoding`].
+    ///
+    /// [`TrackLocalStaticSample::new_with_rid`]: crate::track::track_local::track_local_static_sample::TrackLocalStaticSample::new_with_rid
     pub async fn add_transceiver_from_track(
         &self,
         track: Arc<dyn TrackLocal + Send + Sync>,
@@ -1767,13 +1792,13 @@ impl RTCPeerConnection {
             return Err(Error::ErrConnectionClosed);
         }
 
-        let direction = init
-            .map(|init| init.direction)
-            .unwrap_or(RTCRtpTransceiverDirection::Sendrecv);
+        let (direction, send_encodings) = init
+            .map(|init| (init.direction, init.send_encodings))
+            .unwrap_or((RTCRtpTransceiverDirection::Sendrecv, vec![]));
 
         let t = self
             .internal
-            .new_transceiver_from_track(direction, track)
+            .new_transceiver_from_track(direction, send_encodings, track)
             .await?;
 
         self.internal.add_rtp_transceiver(Arc::clone(&t)).await;
@@ -2,7 +2,7 @@ use bytes::Bytes;
 use tokio::time::Instant;
 
 use super::*;
-use crate::rtp_transceiver::create_stream_info;
+use crate::rtp_transceiver::{create_stream_info, RTCRtpEncodingParameters};
 use crate::stats::stats_collector::StatsCollector;
 use crate::stats::{
     InboundRTPStats, OutboundRTPStats, RTCStatsType, RemoteInboundRTPStats, RemoteOutboundRTPStats,
@@ -65,6 +65,7 @@ pub(crate) struct PeerConnectionInternal {
     pub(crate) media_engine: Arc<MediaEngine>,
     pub(super) interceptor: Weak<dyn Interceptor + Send + Sync>,
     stats_interceptor: Arc<stats::StatsInterceptor>,
+    pub(super) sync_interceptor: Arc<sync::SyncInterceptor>,
 }
 
 impl PeerConnectionInternal {
@@ -72,6 +73,7 @@ impl PeerConnectionInternal {
         api: &API,
         interceptor: Weak<dyn Interceptor + Send + Sync>,
         stats_interceptor: Arc<stats::StatsInterceptor>,
+        sync_interceptor: Arc<sync::SyncInterceptor>,
         mut configuration: RTCConfiguration,
     ) -> Result<(Arc<Self>, RTCConfiguration)> {
         let mut pc = PeerConnectionInternal {
@@ -109,6 +111,7 @@ impl PeerConnectionInternal {
             },
             interceptor,
             stats_interceptor,
+            sync_interceptor,
             on_peer_connection_state_change_handler: Arc::new(ArcSwapOption::empty()),
             pending_remote_description: Arc::new(Default::default()),
         };
@@ -216,6 +219,7 @@ impl PeerConnectionInternal {
                     Arc::clone(&self.media_engine),
                     interceptor,
                 ));
+                receiver.set_sync_interceptor(Arc::clone(&self.sync_interceptor));
                 t.set_receiver(receiver);
             }
         }
@@ -436,9 +440,9 @@ impl PeerConnectionInternal {
             return Err(Error::ErrConnectionClosed);
         }
 
-        let direction = init
-            .map(|value| value.direction)
-            .unwrap_or(RTCRtpTransceiverDirection::Sendrecv);
+        let (direction, send_encodings) = init
+            .map(|value| (value.direction, value.send_encodings))
+            .unwrap_or((RTCRtpTransceiverDirection::Sendrecv, vec![]));
 
         if direction == RTCRtpTransceiverDirection::Unspecified {
             return Err(Error::ErrPeerConnAddTransceiverFromKindSupport);
@@ -455,6 +459,7 @@ impl PeerConnectionInternal {
             Arc::clone(&self.media_engine),
             Arc::clone(&interceptor),
         ));
+        receiver.set_sync_interceptor(Arc::clone(&self.sync_interceptor));
 
         let sender = Arc::new(
             RTCRtpSender::new(
@@ -467,6 +472,7 @@ impl PeerConnectionInternal {
             )
             .await,
         );
+        sender.set_send_encodings(kind, send_encodings).await?;
 
         let t = RTCRtpTransceiver::new(
             receiver,
@@ -487,6 +493,7 @@ impl PeerConnectionInternal {
     pub(super) async fn new_transceiver_from_track(
         &self,
         direction: RTCRtpTransceiverDirection,
+        send_encodings: Vec<RTCRtpEncodingParameters>,
         track: Arc<dyn TrackLocal + Send + Sync>,
     ) -> Result<Arc<RTCRtpTransceiver>> {
         let interceptor = self
@@ -505,6 +512,7 @@ impl PeerConnectionInternal {
             Arc::clone(&self.media_engine),
             Arc::clone(&interceptor),
         ));
+        r.set_sync_interceptor(Arc::clone(&self.sync_interceptor));
 
         let s = Arc::new(
             RTCRtpSender::new(
@@ -517,6 +525,7 @@ impl PeerConnectionInternal {
             )
             .await,
         );
+        s.set_send_encodings(track.kind(), send_encodings).await?;
 
         Ok(RTCRtpTransceiver::new(
             r,
@@ -596,22 +605,14 @@ impl PeerConnectionInternal {
         self: &Arc<Self>,
         ice_role: RTCIceRole,
         dtls_role: DTLSRole,
-        remote_ufrag: String,
-        remote_pwd: String,
+        remote_ice_parameters: RTCIceParameters,
         fingerprint: String,
         fingerprint_hash: String,
     ) {
         // Start the ice transport
         if let Err(err) = self
             .ice_transport
-            .start(
-                &RTCIceParameters {
-                    username_fragment: remote_ufrag,
-                    password: remote_pwd,
-                    ice_lite: false,
-                },
-                Some(ice_role),
-            )
+            .start(&remote_ice_parameters, Some(ice_role))
             .await
         {
             log::warn!("Failed to start manager ice: {}", err);
@@ -696,6 +697,7 @@ impl PeerConnectionInternal {
         let params = PopulateSdpParams {
             media_description_fingerprint: self.setting_engine.sdp_media_level_fingerprints,
             is_icelite: self.setting_engine.candidates.ice_lite,
+            ice_renomination: self.setting_engine.candidates.ice_renomination,
             connection_role: DEFAULT_DTLS_ROLE_OFFER.to_connection_role(),
             ice_gathering_state: self.ice_gathering_state(),
         };
@@ -812,6 +814,7 @@ impl PeerConnectionInternal {
         let params = PopulateSdpParams {
             media_description_fingerprint: self.setting_engine.sdp_media_level_fingerprints,
             is_icelite: self.setting_engine.candidates.ice_lite,
+            ice_renomination: self.setting_engine.candidates.ice_renomination,
             connection_role,
             ice_gathering_state: self.ice_gathering_state(),
         };
@@ -1350,13 +1353,16 @@ impl PeerConnectionInternal {
                 RTPCodecType::Video => "video",
             };
 
-            track_infos.push(TrackInfo {
-                track_id,
-                ssrc: sender.ssrc,
-                mid: mid.clone(),
-                rid: None,
-                kind,
-            });
+            let encodings = sender.track_encodings.lock().clone();
+            for encoding in encodings {
+                track_infos.push(TrackInfo {
+                    track_id: track_id.clone(),
+                    ssrc: encoding.parameters.ssrc,
+                    mid: mid.clone(),
+                    rid: Some(encoding.parameters.rid).filter(|rid| !rid.is_empty()),
+                    kind,
+                });
+            }
         }
 
         let stream_stats = self
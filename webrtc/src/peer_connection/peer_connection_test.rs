@@ -374,3 +374,51 @@ async fn test_get_stats() -> Result<()> {
 
     Ok(())
 }
+
+async fn negotiated_renomination(offer: bool, answer: bool) -> Result<(bool, bool)> {
+    let new_pc = |enabled: bool| async move {
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        let mut s = SettingEngine::default();
+        s.set_ice_renomination(enabled, None);
+        APIBuilder::new()
+            .with_media_engine(m)
+            .with_setting_engine(s)
+            .build()
+            .new_peer_connection(RTCConfiguration::default())
+            .await
+    };
+    let mut pc_offer = new_pc(offer).await?;
+    let mut pc_answer = new_pc(answer).await?;
+
+    let wg = WaitGroup::new();
+    until_connection_state(&mut pc_offer, &wg, RTCPeerConnectionState::Connected).await;
+    until_connection_state(&mut pc_answer, &wg, RTCPeerConnectionState::Connected).await;
+
+    signal_pair(&mut pc_offer, &mut pc_answer).await?;
+    wg.wait().await;
+
+    let mut negotiated = vec![];
+    for pc in [&pc_offer, &pc_answer] {
+        let agent = pc
+            .internal
+            .ice_transport
+            .gatherer
+            .get_agent()
+            .await
+            .expect("ICE agent should have been created");
+        negotiated.push(agent.renomination());
+    }
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+
+    Ok((negotiated[0], negotiated[1]))
+}
+
+#[tokio::test]
+async fn test_ice_renomination_negotiation() -> Result<()> {
+    assert_eq!(negotiated_renomination(true, true).await?, (true, true));
+    assert_eq!(negotiated_renomination(true, false).await?, (false, false));
+
+    Ok(())
+}
@@ -19,9 +19,12 @@ pub mod sdp_type;
 pub mod session_description;
 
 use crate::peer_connection::MEDIA_SECTION_APPLICATION;
-use crate::SDP_ATTRIBUTE_RID;
+use crate::{ICE_OPTION_RENOMINATION, SDP_ATTRIBUTE_ICE_OPTIONS, SDP_ATTRIBUTE_RID};
 use ice::candidate::candidate_base::unmarshal_candidate;
 use ice::candidate::Candidate;
+use sdp::attribute::{
+    Fingerprint, Rid, RidDirection, Simulcast, SimulcastRid, SsrcGroup, TypedAttribute,
+};
 use sdp::description::common::{Address, ConnectionInformation};
 use sdp::description::media::{MediaDescription, MediaName, RangedPort};
 use sdp::description::session::*;
@@ -99,27 +102,19 @@ pub(crate) fn track_details_from_sdp(
             match attr.key.as_str() {
                 ATTR_KEY_SSRCGROUP => {
                     if let Some(value) = &attr.value {
-                        let split: Vec<&str> = value.split(' ').collect();
-                        if split[0] == SEMANTIC_TOKEN_FLOW_IDENTIFICATION {
+                        let group = match SsrcGroup::unmarshal(value) {
+                            Ok(group) => group,
+                            Err(err) => {
+                                log::warn!("Failed to parse SSRC group: {}", err);
+                                continue;
+                            }
+                        };
+                        if group.semantics == SEMANTIC_TOKEN_FLOW_IDENTIFICATION {
                             // Add rtx ssrcs to blacklist, to avoid adding them as tracks
                             // Essentially lines like `a=ssrc-group:FID 2231627014 632943048` are processed by this section
                             // as this declares that the second SSRC (632943048) is a rtx repair flow (RFC4588) for the first
                             // (2231627014) as specified in RFC5576
-                            if split.len() == 3 {
-                                let base_ssrc = match split[1].parse::<u32>() {
-                                    Ok(ssrc) => ssrc,
-                                    Err(err) => {
-                                        log::warn!("Failed to parse SSRC: {}", err);
-                                        continue;
-                                    }
-                                };
-                                let rtx_repair_flow = match split[2].parse::<u32>() {
-                                    Ok(n) => n,
-                                    Err(err) => {
-                                        log::warn!("Failed to parse SSRC: {}", err);
-                                        continue;
-                                    }
-                                };
+                            if let [base_ssrc, rtx_repair_flow] = group.ssrcs[..] {
                                 rtx_repair_flows.insert(rtx_repair_flow, base_ssrc);
                                 // Remove if rtx was added as track before
                                 filter_track_with_ssrc(
@@ -530,12 +525,34 @@ pub(crate) async fn add_transceiver_sdp(
     for mt in transceivers {
         let sender = mt.sender();
         if let Some(track) = sender.track().await {
-            media = media.with_media_source(
-                sender.ssrc,
-                track.stream_id().to_owned(), /* cname */
-                track.stream_id().to_owned(), /* streamLabel */
-                track.id().to_owned(),
-            );
+            let encodings = sender.track_encodings.lock().clone();
+            if encodings.len() == 1 {
+                media = media.with_media_source(
+                    encodings[0].parameters.ssrc,
+                    track.stream_id().to_owned(), /* cname */
+                    track.stream_id().to_owned(), /* streamLabel */
+                    track.id().to_owned(),
+                );
+            } else {
+                // Simulcast layers are identified by their RTP stream id rather than
+                // by a=ssrc lines, so the remote can tell the layers apart.
+                let mut send_rids = vec![];
+                for encoding in &encodings {
+                    media = media.with_typed_attribute(TypedAttribute::Rid(Rid {
+                        id: encoding.parameters.rid.clone(),
+                        direction: RidDirection::Send,
+                        ..Default::default()
+                    }));
+                    send_rids.push(vec![SimulcastRid {
+                        id: encoding.parameters.rid.clone(),
+                        paused: !encoding.parameters.active,
+                    }]);
+                }
+                media = media.with_typed_attribute(TypedAttribute::Simulcast(Simulcast {
+                    send: send_rids,
+                    recv: vec![],
+                }));
+            }
 
             // Send msid based on the configured track if we haven't already
             // sent on this sender. If we have sent we must keep the msid line consistent, this
@@ -638,6 +655,7 @@ pub(crate) struct MediaSection {
 pub(crate) struct PopulateSdpParams {
     pub(crate) media_description_fingerprint: bool,
     pub(crate) is_icelite: bool,
+    pub(crate) ice_renomination: bool,
     pub(crate) connection_role: ConnectionRole,
     pub(crate) ice_gathering_state: RTCIceGatheringState,
 }
@@ -725,6 +743,13 @@ pub(crate) async fn populate_sdp(
         d = d.with_value_attribute(ATTR_KEY_ICELITE.to_owned(), ATTR_KEY_ICELITE.to_owned());
     }
 
+    if params.ice_renomination {
+        d = d.with_value_attribute(
+            SDP_ATTRIBUTE_ICE_OPTIONS.to_owned(),
+            ICE_OPTION_RENOMINATION.to_owned(),
+        );
+    }
+
     Ok(d.with_value_attribute(ATTR_KEY_GROUP.to_owned(), bundle_value))
 }
 
@@ -770,12 +795,26 @@ pub(crate) fn extract_fingerprint(desc: &SessionDescription) -> Result<(String,
         }
     }
 
-    let parts: Vec<&str> = fingerprints[0].split(' ').collect();
-    if parts.len() != 2 {
-        return Err(Error::ErrSessionDescriptionInvalidFingerprint);
-    }
+    let fingerprint = Fingerprint::unmarshal(&fingerprints[0])
+        .map_err(|_| Error::ErrSessionDescriptionInvalidFingerprint)?;
+
+    Ok((fingerprint.value, fingerprint.algorithm))
+}
+
+/// Returns true if the description advertises ICE renomination with `a=ice-options`, at the
+/// session level or in a media section.
+pub(crate) fn has_ice_option_renomination(desc: &SessionDescription) -> bool {
+    let has_renomination = |value: &str| {
+        value
+            .split_whitespace()
+            .any(|option| option == ICE_OPTION_RENOMINATION)
+    };
 
-    Ok((parts[1].to_owned(), parts[0].to_owned()))
+    desc.attributes
+        .iter()
+        .chain(desc.media_descriptions.iter().flat_map(|m| &m.attributes))
+        .filter(|a| a.key == SDP_ATTRIBUTE_ICE_OPTIONS)
+        .any(|a| a.value.as_deref().map_or(false, has_renomination))
 }
 
 pub(crate) async fn extract_ice_details(
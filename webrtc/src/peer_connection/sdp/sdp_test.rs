@@ -520,6 +520,64 @@ fn test_have_application_media_section() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_populate_sdp_ice_renomination() -> Result<()> {
+    let engine = Arc::new(MediaEngine::default());
+    for ice_renomination in [false, true] {
+        let params = PopulateSdpParams {
+            media_description_fingerprint: false,
+            is_icelite: false,
+            ice_renomination,
+            connection_role: ConnectionRole::Active,
+            ice_gathering_state: RTCIceGatheringState::New,
+        };
+
+        let s = populate_sdp(
+            SessionDescription::default(),
+            &[],
+            &engine,
+            &[],
+            &RTCIceParameters::default(),
+            &[],
+            params,
+        )
+        .await?;
+
+        assert_eq!(
+            s.attribute(SDP_ATTRIBUTE_ICE_OPTIONS).map(String::as_str),
+            ice_renomination.then_some(ICE_OPTION_RENOMINATION)
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_has_ice_option_renomination() -> Result<()> {
+    let tests = vec![
+        ("v=0\r\no=- 0 0 IN IP4 0.0.0.0\r\ns=-\r\nt=0 0\r\n", false),
+        (
+            "v=0\r\no=- 0 0 IN IP4 0.0.0.0\r\ns=-\r\nt=0 0\r\na=ice-options:trickle renomination\r\n",
+            true,
+        ),
+        (
+            "v=0\r\no=- 0 0 IN IP4 0.0.0.0\r\ns=-\r\nt=0 0\r\na=ice-options:trickle\r\nm=audio 9 UDP/TLS/RTP/SAVPF 111\r\na=ice-options:renomination\r\n",
+            true,
+        ),
+        (
+            "v=0\r\no=- 0 0 IN IP4 0.0.0.0\r\ns=-\r\nt=0 0\r\na=ice-options:trickle\r\nm=audio 9 UDP/TLS/RTP/SAVPF 111\r\na=ice-options:renominations\r\n",
+            false,
+        ),
+    ];
+
+    for (sdp, expected) in tests {
+        let s = SessionDescription::unmarshal(&mut std::io::Cursor::new(sdp.as_bytes()))?;
+        assert_eq!(has_ice_option_renomination(&s), expected, "{sdp}");
+    }
+
+    Ok(())
+}
+
 async fn fingerprint_test(
     certificate: &RTCCertificate,
     engine: &Arc<MediaEngine>,
@@ -534,6 +592,7 @@ async fn fingerprint_test(
     let params = PopulateSdpParams {
         media_description_fingerprint: sdpmedia_description_fingerprints,
         is_icelite: false,
+        ice_renomination: false,
         connection_role: ConnectionRole::Active,
         ice_gathering_state: RTCIceGatheringState::New,
     };
@@ -714,6 +773,7 @@ async fn test_populate_sdp() -> Result<()> {
         let params = PopulateSdpParams {
             media_description_fingerprint: se.sdp_media_level_fingerprints,
             is_icelite: se.candidates.ice_lite,
+            ice_renomination: se.candidates.ice_renomination,
             connection_role: DEFAULT_DTLS_ROLE_OFFER.to_connection_role(),
             ice_gathering_state: RTCIceGatheringState::Complete,
         };
@@ -808,6 +868,7 @@ async fn test_populate_sdp() -> Result<()> {
         let params = PopulateSdpParams {
             media_description_fingerprint: se.sdp_media_level_fingerprints,
             is_icelite: se.candidates.ice_lite,
+            ice_renomination: se.candidates.ice_renomination,
             connection_role: DEFAULT_DTLS_ROLE_OFFER.to_connection_role(),
             ice_gathering_state: RTCIceGatheringState::Complete,
         };
@@ -934,6 +995,7 @@ async fn test_populate_sdp_reject() -> Result<()> {
     let params = PopulateSdpParams {
         media_description_fingerprint: se.sdp_media_level_fingerprints,
         is_icelite: se.candidates.ice_lite,
+        ice_renomination: se.candidates.ice_renomination,
         connection_role: DEFAULT_DTLS_ROLE_OFFER.to_connection_role(),
         ice_gathering_state: RTCIceGatheringState::Complete,
     };
@@ -0,0 +1,392 @@
+#[cfg(test)]
+mod record_test;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use interceptor::stream_info::StreamInfo;
+use interceptor::{
+    Attributes, Error, Interceptor, InterceptorBuilder, RTCPReader, RTCPWriter, RTPReader,
+    RTPWriter,
+};
+use media::io::pcap_reader::{PcapFormat, PcapPacket};
+use media::io::pcap_writer::PcapWriter;
+use media::io::rtpdump_reader::{RtpDumpHeader, RtpDumpPacket};
+use media::io::rtpdump_writer::RtpDumpWriter;
+use media::io::Writer;
+use std::io::Write;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use util::sync::Mutex;
+use util::Marshal;
+
+/// the number of packets that can wait for the writer, newer packets are dropped when it is full
+const RECORD_QUEUE_SIZE: usize = 1024;
+
+type IResult<T> = std::result::Result<T, interceptor::Error>;
+type FnTimeGen = Arc<dyn Fn() -> SystemTime + Sync + 'static + Send>;
+type FnMakeWriter = Arc<dyn Fn(&str) -> std::io::Result<Box<dyn Write + Send>> + Sync + Send>;
+
+/// RecordFormat is the file format of a recording
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RecordFormat {
+    /// the rtpdump format of rtptools, which can be replayed with rtpplay
+    RtpDump,
+    /// a pcap capture of UDP datagrams, which can be opened in Wireshark
+    Pcap,
+    /// a pcapng capture of UDP datagrams
+    PcapNg,
+}
+
+/// RecordBuilder can be used to configure Record Interceptor.
+pub struct RecordBuilder {
+    format: RecordFormat,
+    make_writer: FnMakeWriter,
+    local_addr: SocketAddr,
+    remote_addr: SocketAddr,
+    now: Option<FnTimeGen>,
+}
+
+impl RecordBuilder {
+    /// new returns a builder of interceptors that record the RTP and RTCP packets of every
+    /// PeerConnection to the writer that make_writer returns for its id, for example a file
+    /// in a buffered writer. make_writer is called when the PeerConnection is created, and the
+    /// writer is written on a blocking thread of the tokio runtime.
+    pub fn new<F>(format: RecordFormat, make_writer: F) -> RecordBuilder
+    where
+        F: Fn(&str) -> std::io::Result<Box<dyn Write + Send>> + Sync + Send + 'static,
+    {
+        RecordBuilder {
+            format,
+            make_writer: Arc::new(make_writer),
+            local_addr: SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(192, 0, 2, 1), 5000)),
+            remote_addr: SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(192, 0, 2, 2), 5000)),
+            now: None,
+        }
+    }
+
+    /// with_local_addr sets the address of the outgoing packets in a pcap capture,
+    /// 192.0.2.1:5000 by default.
+    pub fn with_local_addr(mut self, local_addr: SocketAddr) -> RecordBuilder {
+        self.local_addr = local_addr;
+        self
+    }
+
+    /// with_remote_addr sets the address of the incoming packets in a pcap capture, and the
+    /// source of an rtpdump file, 192.0.2.2:5000 by default.
+    pub fn with_remote_addr(mut self, remote_addr: SocketAddr) -> RecordBuilder {
+        self.remote_addr = remote_addr;
+        self
+    }
+
+    /// with_now_fn sets an alternative for the time.Now function.
+    pub fn with_now_fn(mut self, now: FnTimeGen) -> RecordBuilder {
+        self.now = Some(now);
+        self
+    }
+}
+
+impl InterceptorBuilder for RecordBuilder {
+    fn build(&self, id: &str) -> IResult<Arc<dyn Interceptor + Send + Sync>> {
+        let writer = (self.make_writer)(id).map_err(|err| Error::Other(err.to_string()))?;
+        let start = if let Some(now) = &self.now {
+            now()
+        } else {
+            SystemTime::now()
+        };
+
+        let sink = match self.format {
+            RecordFormat::RtpDump => {
+                let source = match self.remote_addr {
+                    SocketAddr::V4(addr) => addr,
+                    SocketAddr::V6(addr) => SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, addr.port()),
+                };
+                RtpDumpWriter::new(writer, &RtpDumpHeader { start, source })
+                    .map(RecordSink::RtpDump)
+            }
+            RecordFormat::Pcap => PcapWriter::new(writer, PcapFormat::Pcap).map(RecordSink::Pcap),
+            RecordFormat::PcapNg => {
+                PcapWriter::new(writer, PcapFormat::PcapNg).map(RecordSink::Pcap)
+            }
+        }
+        .map_err(|err| Error::Other(err.to_string()))?;
+
+        let (packet_tx, packet_rx) = mpsc::channel(RECORD_QUEUE_SIZE);
+        let writer = RecordWriter {
+            sink,
+            start,
+            local_addr: self.local_addr,
+            remote_addr: self.remote_addr,
+        };
+        let writer_task = tokio::task::spawn_blocking(move || writer.run(packet_rx));
+
+        Ok(Arc::new(RecordInterceptor {
+            internal: Arc::new(RecordInternal {
+                packet_tx: Mutex::new(Some(packet_tx)),
+                writer_task: Mutex::new(Some(writer_task)),
+                now: self.now.clone(),
+            }),
+        }))
+    }
+}
+
+enum RecordSink {
+    RtpDump(RtpDumpWriter<Box<dyn Write + Send>>),
+    Pcap(PcapWriter<Box<dyn Write + Send>>),
+}
+
+/// RecordedPacket is a marshaled packet on its way to the writer
+struct RecordedPacket {
+    /// the packet is sent, rather than received
+    outgoing: bool,
+    is_rtcp: bool,
+    timestamp: SystemTime,
+    data: Bytes,
+}
+
+/// RecordWriter writes the recorded packets to the sink, off the async tasks of the
+/// PeerConnection
+struct RecordWriter {
+    sink: RecordSink,
+    start: SystemTime,
+    local_addr: SocketAddr,
+    remote_addr: SocketAddr,
+}
+
+impl RecordWriter {
+    /// run writes the packets until the interceptor is closed, and then closes the sink
+    fn run(
+        mut self,
+        mut packet_rx: mpsc::Receiver<RecordedPacket>,
+    ) -> std::result::Result<(), media::Error> {
+        while let Some(packet) = packet_rx.blocking_recv() {
+            if let Err(err) = self.write(packet) {
+                log::warn!("failed to record packet: {}", err);
+            }
+        }
+
+        match &mut self.sink {
+            RecordSink::RtpDump(writer) => writer.close(),
+            RecordSink::Pcap(writer) => writer.close(),
+        }
+    }
+
+    fn write(&mut self, packet: RecordedPacket) -> std::result::Result<(), media::Error> {
+        match &mut self.sink {
+            RecordSink::RtpDump(writer) => writer.write_packet(&RtpDumpPacket {
+                offset: packet
+                    .timestamp
+                    .duration_since(self.start)
+                    .unwrap_or_default(),
+                is_rtcp: packet.is_rtcp,
+                data: packet.data,
+            }),
+            RecordSink::Pcap(writer) => {
+                let (source, destination) = if packet.outgoing {
+                    (self.local_addr, self.remote_addr)
+                } else {
+                    (self.remote_addr, self.local_addr)
+                };
+                writer.write_packet(&PcapPacket {
+                    timestamp: packet.timestamp,
+                    source,
+                    destination,
+                    data: packet.data,
+                })
+            }
+        }
+    }
+}
+
+struct RecordInternal {
+    /// None once the interceptor is closed
+    packet_tx: Mutex<Option<mpsc::Sender<RecordedPacket>>>,
+    writer_task: Mutex<Option<JoinHandle<std::result::Result<(), media::Error>>>>,
+    now: Option<FnTimeGen>,
+}
+
+impl RecordInternal {
+    /// record queues a marshaled packet for the writer, which is sent when outgoing is true.
+    /// The packet is dropped if the writer falls behind.
+    fn record(&self, outgoing: bool, is_rtcp: bool, data: Bytes) {
+        let timestamp = if let Some(now) = &self.now {
+            now()
+        } else {
+            SystemTime::now()
+        };
+
+        let packet_tx = self.packet_tx.lock();
+        // The packets after close are not recorded
+        if let Some(packet_tx) = &*packet_tx {
+            if packet_tx
+                .try_send(RecordedPacket {
+                    outgoing,
+                    is_rtcp,
+                    timestamp,
+                    data,
+                })
+                .is_err()
+            {
+                log::warn!("recording is too slow, dropped a packet");
+            }
+        }
+    }
+}
+
+/// RecordInterceptor records the RTP and RTCP packets of all local and remote streams of a
+/// PeerConnection. Interceptors see the packets before SRTP encryption and after decryption,
+/// so the recording is unencrypted and can be analyzed or replayed.
+pub struct RecordInterceptor {
+    internal: Arc<RecordInternal>,
+}
+
+#[async_trait]
+impl Interceptor for RecordInterceptor {
+    /// bind_rtcp_reader lets you modify any incoming RTCP packets. It is called once per sender/receiver, however this might
+    /// change in the future. The returned method will be called once per packet batch.
+    async fn bind_rtcp_reader(
+        &self,
+        reader: Arc<dyn RTCPReader + Send + Sync>,
+    ) -> Arc<dyn RTCPReader + Send + Sync> {
+        Arc::new(RecordRTCPReader {
+            internal: Arc::clone(&self.internal),
+            parent_rtcp_reader: reader,
+        })
+    }
+
+    /// bind_rtcp_writer lets you modify any outgoing RTCP packets. It is called once per PeerConnection. The returned method
+    /// will be called once per packet batch.
+    async fn bind_rtcp_writer(
+        &self,
+        writer: Arc<dyn RTCPWriter + Send + Sync>,
+    ) -> Arc<dyn RTCPWriter + Send + Sync> {
+        Arc::new(RecordRTCPWriter {
+            internal: Arc::clone(&self.internal),
+            next_rtcp_writer: writer,
+        })
+    }
+
+    /// bind_local_stream lets you modify any outgoing RTP packets. It is called once for per LocalStream. The returned method
+    /// will be called once per rtp packet.
+    async fn bind_local_stream(
+        &self,
+        _info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync> {
+        Arc::new(RecordRTPWriter {
+            internal: Arc::clone(&self.internal),
+            next_rtp_writer: writer,
+        })
+    }
+
+    /// unbind_local_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_local_stream(&self, _info: &StreamInfo) {}
+
+    /// bind_remote_stream lets you modify any incoming RTP packets. It is called once for per RemoteStream. The returned method
+    /// will be called once per rtp packet.
+    async fn bind_remote_stream(
+        &self,
+        _info: &StreamInfo,
+        reader: Arc<dyn RTPReader + Send + Sync>,
+    ) -> Arc<dyn RTPReader + Send + Sync> {
+        Arc::new(RecordRTPReader {
+            internal: Arc::clone(&self.internal),
+            parent_rtp_reader: reader,
+        })
+    }
+
+    /// unbind_remote_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_remote_stream(&self, _info: &StreamInfo) {}
+
+    /// close closes the Interceptor, and waits until the queued packets are written and the
+    /// recording is flushed.
+    async fn close(&self) -> IResult<()> {
+        self.internal.packet_tx.lock().take();
+        let writer_task = self.internal.writer_task.lock().take();
+        if let Some(writer_task) = writer_task {
+            writer_task
+                .await
+                .map_err(|err| Error::Other(err.to_string()))?
+                .map_err(|err| Error::Other(err.to_string()))?;
+        }
+
+        Ok(())
+    }
+}
+
+struct RecordRTPWriter {
+    internal: Arc<RecordInternal>,
+    next_rtp_writer: Arc<dyn RTPWriter + Send + Sync>,
+}
+
+#[async_trait]
+impl RTPWriter for RecordRTPWriter {
+    /// write a rtp packet
+    async fn write(&self, pkt: &rtp::packet::Packet, attributes: &Attributes) -> IResult<usize> {
+        match pkt.marshal() {
+            Ok(data) => self.internal.record(true, false, data),
+            Err(err) => log::warn!("failed to record rtp packet: {}", err),
+        }
+
+        self.next_rtp_writer.write(pkt, attributes).await
+    }
+}
+
+struct RecordRTPReader {
+    internal: Arc<RecordInternal>,
+    parent_rtp_reader: Arc<dyn RTPReader + Send + Sync>,
+}
+
+#[async_trait]
+impl RTPReader for RecordRTPReader {
+    /// read a rtp packet
+    async fn read(&self, buf: &mut [u8], attributes: &Attributes) -> IResult<(usize, Attributes)> {
+        let (n, attr) = self.parent_rtp_reader.read(buf, attributes).await?;
+        self.internal
+            .record(false, false, Bytes::copy_from_slice(&buf[..n]));
+
+        Ok((n, attr))
+    }
+}
+
+struct RecordRTCPWriter {
+    internal: Arc<RecordInternal>,
+    next_rtcp_writer: Arc<dyn RTCPWriter + Send + Sync>,
+}
+
+#[async_trait]
+impl RTCPWriter for RecordRTCPWriter {
+    /// write a batch of rtcp packets
+    async fn write(
+        &self,
+        pkts: &[Box<dyn rtcp::packet::Packet + Send + Sync>],
+        attributes: &Attributes,
+    ) -> IResult<usize> {
+        // A batch is sent as one compound packet
+        match rtcp::packet::marshal(pkts) {
+            Ok(data) => self.internal.record(true, true, data),
+            Err(err) => log::warn!("failed to record rtcp packets: {}", err),
+        }
+
+        self.next_rtcp_writer.write(pkts, attributes).await
+    }
+}
+
+struct RecordRTCPReader {
+    internal: Arc<RecordInternal>,
+    parent_rtcp_reader: Arc<dyn RTCPReader + Send + Sync>,
+}
+
+#[async_trait]
+impl RTCPReader for RecordRTCPReader {
+    /// read a batch of rtcp packets
+    async fn read(&self, buf: &mut [u8], attributes: &Attributes) -> IResult<(usize, Attributes)> {
+        let (n, attr) = self.parent_rtcp_reader.read(buf, attributes).await?;
+        self.internal
+            .record(false, true, Bytes::copy_from_slice(&buf[..n]));
+
+        Ok((n, attr))
+    }
+}
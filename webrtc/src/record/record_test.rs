@@ -0,0 +1,261 @@
+use super::*;
+use crate::error::Result;
+use interceptor::mock::mock_stream::MockStream;
+use interceptor::mock::mock_time::MockTime;
+
+use media::io::pcap_reader::PcapReader;
+use media::io::rtpdump_reader::RtpDumpReader;
+use rtcp::payload_feedbacks::picture_loss_indication::PictureLossIndication;
+use std::io::Cursor;
+use std::time::Duration;
+
+/// SharedBuffer is a writer whose data can be read while the interceptor owns it
+#[derive(Default, Clone)]
+struct SharedBuffer(Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn rtp_packet(sequence_number: u16) -> rtp::packet::Packet {
+    rtp::packet::Packet {
+        header: rtp::header::Header {
+            version: 2,
+            payload_type: 96,
+            sequence_number,
+            ssrc: 123456,
+            ..Default::default()
+        },
+        payload: Bytes::from_static(&[0x10, 0x01]),
+    }
+}
+
+fn pli() -> Vec<Box<dyn rtcp::packet::Packet + Send + Sync>> {
+    vec![Box::new(PictureLossIndication {
+        sender_ssrc: 1,
+        media_ssrc: 123456,
+    })]
+}
+
+/// exchange writes and reads an RTP packet and an RTCP batch in both directions,
+/// advancing the time by 10ms after every packet
+async fn exchange(stream: &MockStream, mt: &MockTime) -> Result<()> {
+    stream.write_rtp(&rtp_packet(1)).await?;
+    mt.set_now(mt.now() + Duration::from_millis(10));
+
+    stream.receive_rtp(rtp_packet(2)).await;
+    stream.read_rtp().await.unwrap()?;
+    mt.set_now(mt.now() + Duration::from_millis(10));
+
+    stream.write_rtcp(&pli()).await?;
+    mt.set_now(mt.now() + Duration::from_millis(10));
+
+    stream.receive_rtcp(pli()).await;
+    stream.read_rtcp().await.unwrap()?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_record_interceptor_pcap() -> Result<()> {
+    let mt = Arc::new(MockTime::default());
+    let time_gen = {
+        let mt = Arc::clone(&mt);
+        Arc::new(move || mt.now())
+    };
+    let buffer = SharedBuffer::default();
+    let ids = Arc::new(std::sync::Mutex::new(vec![]));
+
+    let builder = {
+        let buffer = buffer.clone();
+        let ids = Arc::clone(&ids);
+        RecordBuilder::new(RecordFormat::PcapNg, move |id| {
+            ids.lock().unwrap().push(id.to_owned());
+            Ok(Box::new(buffer.clone()))
+        })
+    }
+    .with_local_addr("10.0.0.1:4000".parse().unwrap())
+    .with_remote_addr("10.0.0.2:6000".parse().unwrap())
+    .with_now_fn(time_gen);
+    let icpr = builder.build("pc-1")?;
+    assert_eq!(*ids.lock().unwrap(), vec!["pc-1".to_owned()]);
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 123456,
+            clock_rate: 90000,
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+    exchange(&stream, &mt).await?;
+    stream.close().await?;
+
+    let data = buffer.0.lock().unwrap().clone();
+    let mut reader = PcapReader::new(Cursor::new(data))?;
+    let local: SocketAddr = "10.0.0.1:4000".parse().unwrap();
+    let remote: SocketAddr = "10.0.0.2:6000".parse().unwrap();
+    let expected = [
+        (local, remote, rtp_packet(1).marshal()?),
+        (remote, local, rtp_packet(2).marshal()?),
+        (local, remote, rtcp::packet::marshal(&pli())?),
+        (remote, local, rtcp::packet::marshal(&pli())?),
+    ];
+    let mut timestamp = None;
+    for (source, destination, data) in expected {
+        let packet = reader.parse_next_packet()?;
+        assert_eq!(packet.source, source);
+        assert_eq!(packet.destination, destination);
+        assert_eq!(packet.data, data);
+        if let Some(timestamp) = timestamp {
+            assert_eq!(
+                packet.timestamp.duration_since(timestamp).unwrap(),
+                Duration::from_millis(10)
+            );
+        }
+        timestamp = Some(packet.timestamp);
+    }
+    assert_eq!(reader.parse_next_packet(), Err(media::Error::ErrIoEOF));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_record_interceptor_rtpdump() -> Result<()> {
+    let mt = Arc::new(MockTime::default());
+    let time_gen = {
+        let mt = Arc::clone(&mt);
+        Arc::new(move || mt.now())
+    };
+    let buffer = SharedBuffer::default();
+
+    let icpr = {
+        let buffer = buffer.clone();
+        RecordBuilder::new(RecordFormat::RtpDump, move |_| Ok(Box::new(buffer.clone())))
+    }
+    .with_now_fn(time_gen)
+    .build("")?;
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 123456,
+            clock_rate: 90000,
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+    mt.set_now(mt.now() + Duration::from_millis(5));
+    exchange(&stream, &mt).await?;
+    stream.close().await?;
+
+    let data = buffer.0.lock().unwrap().clone();
+    let (mut reader, header) = RtpDumpReader::new(Cursor::new(data))?;
+    assert_eq!(header.source, "192.0.2.2:5000".parse().unwrap());
+
+    let expected = [
+        (5, false, rtp_packet(1).marshal()?),
+        (15, false, rtp_packet(2).marshal()?),
+        (25, true, rtcp::packet::marshal(&pli())?),
+        (35, true, rtcp::packet::marshal(&pli())?),
+    ];
+    for (offset, is_rtcp, data) in expected {
+        let packet = reader.parse_next_packet()?;
+        assert_eq!(packet.offset, Duration::from_millis(offset));
+        assert_eq!(packet.is_rtcp, is_rtcp);
+        assert_eq!(packet.data, data);
+    }
+    assert_eq!(reader.parse_next_packet(), Err(media::Error::ErrIoEOF));
+
+    Ok(())
+}
+
+#[test]
+fn test_record_builder_writer_error() {
+    let builder = RecordBuilder::new(RecordFormat::Pcap, |_| {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            "denied",
+        ))
+    });
+    assert!(matches!(builder.build(""), Err(Error::Other(_))));
+}
+
+/// GatedBuffer is a SharedBuffer whose writes block while its gate is closed
+#[derive(Default, Clone)]
+struct GatedBuffer {
+    buffer: SharedBuffer,
+    gate: Arc<(std::sync::Mutex<bool>, std::sync::Condvar)>,
+}
+
+impl GatedBuffer {
+    fn set_closed(&self, closed: bool) {
+        *self.gate.0.lock().unwrap() = closed;
+        self.gate.1.notify_all();
+    }
+}
+
+impl Write for GatedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut closed = self.gate.0.lock().unwrap();
+        while *closed {
+            closed = self.gate.1.wait(closed).unwrap();
+        }
+        self.buffer.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_record_interceptor_blocked_writer() -> Result<()> {
+    let mt = Arc::new(MockTime::default());
+    let time_gen = {
+        let mt = Arc::clone(&mt);
+        Arc::new(move || mt.now())
+    };
+    let buffer = GatedBuffer::default();
+
+    let icpr = {
+        let buffer = buffer.clone();
+        RecordBuilder::new(RecordFormat::Pcap, move |_| Ok(Box::new(buffer.clone())))
+    }
+    .with_now_fn(time_gen)
+    .build("")?;
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 123456,
+            clock_rate: 90000,
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    // The packets are queued while the writer blocks
+    buffer.set_closed(true);
+    tokio::time::timeout(Duration::from_secs(1), exchange(&stream, &mt))
+        .await
+        .expect("recording should not block the stream")?;
+    buffer.set_closed(false);
+    stream.close().await?;
+
+    let data = buffer.buffer.0.lock().unwrap().clone();
+    let mut reader = PcapReader::new(Cursor::new(data))?;
+    for _ in 0..4 {
+        reader.parse_next_packet()?;
+    }
+    assert_eq!(reader.parse_next_packet(), Err(media::Error::ErrIoEOF));
+
+    Ok(())
+}
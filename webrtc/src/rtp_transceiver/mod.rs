@@ -4,6 +4,8 @@ mod rtp_transceiver_test;
 use crate::api::media_engine::MediaEngine;
 use crate::error::{Error, Result};
 use crate::rtp_transceiver::rtp_codec::*;
+use crate::rtp_transceiver::rtp_degradation_preference::RTCDegradationPreference;
+use crate::rtp_transceiver::rtp_priority::RTCPriorityType;
 use crate::rtp_transceiver::rtp_receiver::{RTCRtpReceiver, RTPReceiverInternal};
 use crate::rtp_transceiver::rtp_sender::RTCRtpSender;
 use crate::rtp_transceiver::rtp_transceiver_direction::RTCRtpTransceiverDirection;
@@ -26,6 +28,8 @@ use util::Unmarshal;
 
 pub(crate) mod fmtp;
 pub mod rtp_codec;
+pub mod rtp_degradation_preference;
+pub mod rtp_priority;
 pub mod rtp_receiver;
 pub mod rtp_sender;
 pub mod rtp_transceiver_direction;
@@ -106,10 +110,52 @@ pub struct RTCRtpCodingParameters {
 /// <http://draft.ortc.org/#dom-rtcrtpdecodingparameters>
 pub type RTCRtpDecodingParameters = RTCRtpCodingParameters;
 
-/// RTPEncodingParameters provides information relating to both encoding and decoding.
-/// This is a subset of the RFC since Pion WebRTC doesn't implement encoding itself
-/// <http://draft.ortc.org/#dom-rtcrtpencodingparameters>
-pub type RTCRtpEncodingParameters = RTCRtpCodingParameters;
+/// RTPEncodingParameters provides information relating to the encoding of a single
+/// RTP stream, e.g. one simulcast layer.
+/// <https://w3c.github.io/webrtc-pc/#dom-rtcrtpencodingparameters>
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RTCRtpEncodingParameters {
+    pub rid: String,
+    pub ssrc: SSRC,
+    pub payload_type: PayloadType,
+    pub rtx: RTCRtpRtxParameters,
+
+    /// active indicates that the encoding is being sent. Inactive encodings send no RTP.
+    pub active: bool,
+    /// max_bitrate is the maximum bitrate of the encoding in bits per second.
+    ///
+    /// It is advisory: tracks are sent as they are written, the application or an
+    /// interceptor, which sees it in `StreamInfo::encoding`, has to keep to it.
+    pub max_bitrate: Option<u64>,
+    /// max_framerate is the maximum number of frames per second of the encoding.
+    pub max_framerate: Option<f64>,
+    /// priority is the share of the available bitrate the encoding gets relative to
+    /// the other encodings sent over the same transport.
+    pub priority: RTCPriorityType,
+    /// scale_resolution_down_by is the factor the resolution of the encoding is
+    /// scaled down by, e.g. 2.0 for a quarter of the pixels of the track.
+    ///
+    /// It is advisory: the media of a track is encoded by the application, which has
+    /// to scale it.
+    pub scale_resolution_down_by: Option<f64>,
+}
+
+impl Default for RTCRtpEncodingParameters {
+    fn default() -> Self {
+        RTCRtpEncodingParameters {
+            rid: String::new(),
+            ssrc: 0,
+            payload_type: 0,
+            rtx: RTCRtpRtxParameters::default(),
+            active: true,
+            max_bitrate: None,
+            max_framerate: None,
+            priority: RTCPriorityType::default(),
+            scale_resolution_down_by: None,
+        }
+    }
+}
 
 /// RTPReceiveParameters contains the RTP stack settings used by receivers
 #[derive(Debug)]
@@ -117,16 +163,23 @@ pub struct RTCRtpReceiveParameters {
     pub encodings: Vec<RTCRtpDecodingParameters>,
 }
 
-/// RTPSendParameters contains the RTP stack settings used by receivers
-#[derive(Debug)]
+/// RTPSendParameters contains the RTP stack settings used by senders
+/// <https://w3c.github.io/webrtc-pc/#dom-rtcrtpsendparameters>
+#[derive(Debug, Clone)]
 pub struct RTCRtpSendParameters {
     pub rtp_parameters: RTCRtpParameters,
     pub encodings: Vec<RTCRtpEncodingParameters>,
+    /// transaction_id identifies the get_parameters call these parameters were
+    /// returned by, set_parameters only accepts the parameters of the last one.
+    pub transaction_id: String,
+    pub degradation_preference: RTCDegradationPreference,
 }
 
 /// RTPTransceiverInit dictionary is used when calling the WebRTC function addTransceiver() to provide configuration options for the new transceiver.
 pub struct RTCRtpTransceiverInit {
     pub direction: RTCRtpTransceiverDirection,
+    /// send_encodings configures the encodings of the sender, one per simulcast layer.
+    /// With more than one encoding, every encoding needs a unique RID.
     pub send_encodings: Vec<RTCRtpEncodingParameters>,
     // Streams       []*Track
 }
@@ -166,6 +219,7 @@ pub(crate) fn create_stream_info(
         channels: codec.channels,
         sdp_fmtp_line: codec.sdp_fmtp_line,
         rtcp_feedback: feedbacks,
+        ..Default::default()
     }
 }
 
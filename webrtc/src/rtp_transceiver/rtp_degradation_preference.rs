@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// RTCDegradationPreference indicates how an encoder trades resolution against
+/// framerate when the bitrate available to a video track is constrained.
+/// <https://w3c.github.io/mst-content-hint/#dom-rtcdegradationpreference>
+#[derive(Default, Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+pub enum RTCDegradationPreference {
+    Unspecified,
+
+    /// MaintainFramerate degrades the resolution to keep the framerate.
+    #[serde(rename = "maintain-framerate")]
+    MaintainFramerate,
+
+    /// MaintainResolution degrades the framerate to keep the resolution.
+    #[serde(rename = "maintain-resolution")]
+    MaintainResolution,
+
+    /// Balanced degrades both the framerate and the resolution.
+    #[default]
+    #[serde(rename = "balanced")]
+    Balanced,
+}
+
+const DEGRADATION_PREFERENCE_MAINTAIN_FRAMERATE_STR: &str = "maintain-framerate";
+const DEGRADATION_PREFERENCE_MAINTAIN_RESOLUTION_STR: &str = "maintain-resolution";
+const DEGRADATION_PREFERENCE_BALANCED_STR: &str = "balanced";
+
+impl From<&str> for RTCDegradationPreference {
+    fn from(raw: &str) -> Self {
+        match raw {
+            DEGRADATION_PREFERENCE_MAINTAIN_FRAMERATE_STR => {
+                RTCDegradationPreference::MaintainFramerate
+            }
+            DEGRADATION_PREFERENCE_MAINTAIN_RESOLUTION_STR => {
+                RTCDegradationPreference::MaintainResolution
+            }
+            DEGRADATION_PREFERENCE_BALANCED_STR => RTCDegradationPreference::Balanced,
+            _ => RTCDegradationPreference::Unspecified,
+        }
+    }
+}
+
+impl fmt::Display for RTCDegradationPreference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match *self {
+            RTCDegradationPreference::MaintainFramerate => {
+                DEGRADATION_PREFERENCE_MAINTAIN_FRAMERATE_STR
+            }
+            RTCDegradationPreference::MaintainResolution => {
+                DEGRADATION_PREFERENCE_MAINTAIN_RESOLUTION_STR
+            }
+            RTCDegradationPreference::Balanced => DEGRADATION_PREFERENCE_BALANCED_STR,
+            RTCDegradationPreference::Unspecified => crate::UNSPECIFIED_STR,
+        };
+        write!(f, "{s}")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_degradation_preference_string() {
+        let tests = vec![
+            (RTCDegradationPreference::Unspecified, "Unspecified"),
+            (
+                RTCDegradationPreference::MaintainFramerate,
+                "maintain-framerate",
+            ),
+            (
+                RTCDegradationPreference::MaintainResolution,
+                "maintain-resolution",
+            ),
+            (RTCDegradationPreference::Balanced, "balanced"),
+        ];
+
+        for (preference, expected_string) in tests {
+            assert_eq!(preference.to_string(), expected_string);
+            assert_eq!(RTCDegradationPreference::from(expected_string), preference);
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// RTCPriorityType indicates the priority of an encoding relative to the other
+/// encodings and data channels sent over the same transport.
+/// <https://w3c.github.io/webrtc-priority/#rtc-priority-type>
+#[derive(Default, Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+pub enum RTCPriorityType {
+    Unspecified,
+
+    #[serde(rename = "very-low")]
+    VeryLow,
+
+    #[default]
+    #[serde(rename = "low")]
+    Low,
+
+    #[serde(rename = "medium")]
+    Medium,
+
+    #[serde(rename = "high")]
+    High,
+}
+
+const PRIORITY_TYPE_VERY_LOW_STR: &str = "very-low";
+const PRIORITY_TYPE_LOW_STR: &str = "low";
+const PRIORITY_TYPE_MEDIUM_STR: &str = "medium";
+const PRIORITY_TYPE_HIGH_STR: &str = "high";
+
+impl From<&str> for RTCPriorityType {
+    fn from(raw: &str) -> Self {
+        match raw {
+            PRIORITY_TYPE_VERY_LOW_STR => RTCPriorityType::VeryLow,
+            PRIORITY_TYPE_LOW_STR => RTCPriorityType::Low,
+            PRIORITY_TYPE_MEDIUM_STR => RTCPriorityType::Medium,
+            PRIORITY_TYPE_HIGH_STR => RTCPriorityType::High,
+            _ => RTCPriorityType::Unspecified,
+        }
+    }
+}
+
+impl fmt::Display for RTCPriorityType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match *self {
+            RTCPriorityType::VeryLow => PRIORITY_TYPE_VERY_LOW_STR,
+            RTCPriorityType::Low => PRIORITY_TYPE_LOW_STR,
+            RTCPriorityType::Medium => PRIORITY_TYPE_MEDIUM_STR,
+            RTCPriorityType::High => PRIORITY_TYPE_HIGH_STR,
+            RTCPriorityType::Unspecified => crate::UNSPECIFIED_STR,
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl RTCPriorityType {
+    /// bitrate_priority returns the share of the available bitrate of this priority
+    /// relative to the default one, which is `low`.
+    /// <https://w3c.github.io/webrtc-priority/#rtc-priority-type>
+    pub fn bitrate_priority(&self) -> f64 {
+        match *self {
+            RTCPriorityType::VeryLow => 0.5,
+            RTCPriorityType::Medium => 2.0,
+            RTCPriorityType::High => 4.0,
+            RTCPriorityType::Low | RTCPriorityType::Unspecified => 1.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_new_priority_type() {
+        let tests = vec![
+            ("Unspecified", RTCPriorityType::Unspecified),
+            ("very-low", RTCPriorityType::VeryLow),
+            ("low", RTCPriorityType::Low),
+            ("medium", RTCPriorityType::Medium),
+            ("high", RTCPriorityType::High),
+        ];
+
+        for (priority_string, expected_priority) in tests {
+            assert_eq!(RTCPriorityType::from(priority_string), expected_priority);
+        }
+    }
+
+    #[test]
+    fn test_priority_type_string() {
+        let tests = vec![
+            (RTCPriorityType::Unspecified, "Unspecified"),
+            (RTCPriorityType::VeryLow, "very-low"),
+            (RTCPriorityType::Low, "low"),
+            (RTCPriorityType::Medium, "medium"),
+            (RTCPriorityType::High, "high"),
+        ];
+
+        for (priority, expected_string) in tests {
+            assert_eq!(priority.to_string(), expected_string);
+        }
+    }
+}
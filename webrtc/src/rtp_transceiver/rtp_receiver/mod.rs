@@ -18,6 +18,7 @@ use crate::track::{TrackStream, TrackStreams};
 
 use arc_swap::ArcSwapOption;
 use interceptor::stream_info::RTPHeaderExtension;
+use interceptor::sync::SyncInterceptor;
 use interceptor::{Attributes, Interceptor};
 use log::trace;
 use std::fmt;
@@ -153,6 +154,7 @@ pub struct RTPReceiverInternal {
     tracks: RwLock<Vec<TrackStreams>>,
 
     transceiver_codecs: ArcSwapOption<Mutex<Vec<RTCRtpCodecParameters>>>,
+    sync_interceptor: ArcSwapOption<SyncInterceptor>,
 
     transport: Arc<RTCDtlsTransport>,
     media_engine: Arc<MediaEngine>,
@@ -160,6 +162,12 @@ pub struct RTPReceiverInternal {
 }
 
 impl RTPReceiverInternal {
+    /// sync_interceptor returns the interceptor that maps the RTP timestamps of the remote
+    /// streams of the PeerConnection to the NTP time of the sender
+    pub(crate) fn sync_interceptor(&self) -> Option<Arc<SyncInterceptor>> {
+        self.sync_interceptor.load_full()
+    }
+
     /// read reads incoming RTCP for this RTPReceiver
     async fn read(&self, b: &mut [u8]) -> Result<(usize, Attributes)> {
         let mut state_watch_rx = self.state_tx.subscribe();
@@ -428,6 +436,7 @@ impl RTCRtpReceiver {
                 state_rx,
 
                 transceiver_codecs: ArcSwapOption::new(None),
+                sync_interceptor: ArcSwapOption::new(None),
             }),
         }
     }
@@ -443,6 +452,10 @@ impl RTCRtpReceiver {
         self.internal.transceiver_codecs.store(codecs);
     }
 
+    pub(crate) fn set_sync_interceptor(&self, sync_interceptor: Arc<SyncInterceptor>) {
+        self.internal.sync_interceptor.store(Some(sync_interceptor));
+    }
+
     /// transport returns the currently-configured *DTLSTransport or nil
     /// if one has not yet been configured
     pub fn transport(&self) -> Arc<RTCDtlsTransport> {
@@ -3,13 +3,16 @@ mod rtp_sender_test;
 
 use crate::api::media_engine::MediaEngine;
 use crate::dtls_transport::RTCDtlsTransport;
-use crate::error::{Error, Result};
-use crate::rtp_transceiver::rtp_codec::{RTCRtpCodecParameters, RTPCodecType};
+use crate::error::{flatten_errs, Error, Result};
+use crate::rtp_transceiver::rtp_codec::{
+    RTCRtpCodecParameters, RTCRtpHeaderExtensionCapability, RTPCodecType,
+};
+use crate::rtp_transceiver::rtp_degradation_preference::RTCDegradationPreference;
 use crate::rtp_transceiver::rtp_transceiver_direction::RTCRtpTransceiverDirection;
 use crate::rtp_transceiver::srtp_writer_future::SrtpWriterFuture;
 use crate::rtp_transceiver::{
     create_stream_info, PayloadType, RTCRtpEncodingParameters, RTCRtpSendParameters,
-    RTCRtpTransceiver, SSRC,
+    RTCRtpTransceiver,
 };
 use crate::track::track_local::{
     InterceptorToTrackLocalWriter, TrackLocal, TrackLocalContext, TrackLocalWriter,
@@ -29,47 +32,39 @@ pub(crate) struct RTPSenderInternal {
     pub(crate) send_called_rx: Mutex<mpsc::Receiver<()>>,
     pub(crate) stop_called_rx: Arc<Notify>,
     pub(crate) stop_called_signal: Arc<AtomicBool>,
-    pub(crate) rtcp_interceptor: Mutex<Option<Arc<dyn RTCPReader + Send + Sync>>>,
 }
 
 impl RTPSenderInternal {
-    /// read reads incoming RTCP for this RTPReceiver
-    async fn read(&self, b: &mut [u8]) -> Result<(usize, Attributes)> {
-        let mut send_called_rx = self.send_called_rx.lock().await;
-
+    /// read reads incoming RTCP of one encoding of this RTPSender
+    async fn read(
+        &self,
+        rtcp_interceptor: &Arc<dyn RTCPReader + Send + Sync>,
+        b: &mut [u8],
+    ) -> Result<(usize, Attributes)> {
+        let a = Attributes::new();
         tokio::select! {
-            _ = send_called_rx.recv() =>{
-                let rtcp_interceptor = {
-                    let rtcp_interceptor = self.rtcp_interceptor.lock().await;
-                    rtcp_interceptor.clone()
-                };
-                if let Some(rtcp_interceptor) = rtcp_interceptor{
-                    let a = Attributes::new();
-                    tokio::select! {
-                        _ = self.stop_called_rx.notified() => {
-                            Err(Error::ErrClosedPipe)
-                        }
-                        result = rtcp_interceptor.read(b, &a) => {
-                            Ok(result?)
-                        }
-                    }
-                }else{
-                    Err(Error::ErrInterceptorNotBind)
-                }
-            }
-            _ = self.stop_called_rx.notified() =>{
+            _ = self.stop_called_rx.notified() => {
                 Err(Error::ErrClosedPipe)
             }
+            result = async {
+                // The lock is only held until send is called, so that the encodings
+                // of a simulcast sender are read concurrently.
+                self.send_called_rx.lock().await.recv().await;
+                rtcp_interceptor.read(b, &a).await
+            } => {
+                Ok(result?)
+            }
         }
     }
 
     /// read_rtcp is a convenience method that wraps Read and unmarshals for you.
     async fn read_rtcp(
         &self,
+        rtcp_interceptor: &Arc<dyn RTCPReader + Send + Sync>,
         receive_mtu: usize,
     ) -> Result<(Vec<Box<dyn rtcp::packet::Packet + Send + Sync>>, Attributes)> {
         let mut b = vec![0u8; receive_mtu];
-        let (n, attributes) = self.read(&mut b).await?;
+        let (n, attributes) = self.read(rtcp_interceptor, &mut b).await?;
 
         let mut buf = &b[..n];
         let pkts = rtcp::packet::unmarshal(&mut buf)?;
@@ -78,20 +73,37 @@ impl RTPSenderInternal {
     }
 }
 
-/// RTPSender allows an application to control how a given Track is encoded and transmitted to a remote peer
-pub struct RTCRtpSender {
-    pub(crate) track: Mutex<Option<Arc<dyn TrackLocal + Send + Sync>>>,
+/// TrackEncoding is a single encoding of a RTPSender, e.g. one simulcast layer.
+/// Every encoding has its own track, SSRC and write path to the transport.
+#[derive(Clone)]
+pub(crate) struct TrackEncoding {
+    pub(crate) track: Option<Arc<dyn TrackLocal + Send + Sync>>,
+    pub(crate) parameters: RTCRtpEncodingParameters,
 
     pub(crate) srtp_stream: Arc<SrtpWriterFuture>,
-    pub(crate) stream_info: Mutex<StreamInfo>,
-    seq_trans: Arc<SequenceTransformer>,
+    pub(crate) rtcp_interceptor: Arc<dyn RTCPReader + Send + Sync>,
+    pub(crate) seq_trans: Arc<SequenceTransformer>,
 
-    pub(crate) context: Mutex<TrackLocalContext>,
+    /// paused is set while either the RTPSender is paused or the encoding is inactive.
+    pub(crate) paused: Arc<AtomicBool>,
+
+    pub(crate) stream_info: StreamInfo,
+    pub(crate) context: TrackLocalContext,
+}
+
+impl TrackEncoding {
+    fn rid(&self) -> Option<&str> {
+        Some(self.parameters.rid.as_str()).filter(|rid| !rid.is_empty())
+    }
+}
+
+/// RTPSender allows an application to control how a given Track is encoded and transmitted to a remote peer
+pub struct RTCRtpSender {
+    pub(crate) track_encodings: SyncMutex<Vec<TrackEncoding>>,
 
     pub(crate) transport: Arc<RTCDtlsTransport>,
 
     pub(crate) payload_type: PayloadType,
-    pub(crate) ssrc: SSRC,
     receive_mtu: usize,
 
     /// a transceiver sender since we can just check the
@@ -116,6 +128,11 @@ pub struct RTCRtpSender {
     stop_called_signal: Arc<AtomicBool>,
 
     pub(crate) paused: Arc<AtomicBool>,
+    seq_trans_enabled: AtomicBool,
+
+    degradation_preference: SyncMutex<RTCDegradationPreference>,
+    /// The parameters returned by the last get_parameters call, until they are set.
+    last_returned_parameters: SyncMutex<Option<RTCRtpSendParameters>>,
 
     internal: Arc<RTPSenderInternal>,
 }
@@ -144,50 +161,24 @@ impl RTCRtpSender {
         let (send_called_tx, send_called_rx) = mpsc::channel(1);
         let stop_called_tx = Arc::new(Notify::new());
         let stop_called_rx = stop_called_tx.clone();
-        let ssrc = rand::random::<u32>();
         let stop_called_signal = Arc::new(AtomicBool::new(false));
 
         let internal = Arc::new(RTPSenderInternal {
             send_called_rx: Mutex::new(send_called_rx),
             stop_called_rx,
             stop_called_signal: Arc::clone(&stop_called_signal),
-            rtcp_interceptor: Mutex::new(None),
-        });
-
-        let seq_trans = Arc::new(SequenceTransformer::new());
-        let srtp_stream = Arc::new(SrtpWriterFuture {
-            closed: AtomicBool::new(false),
-            ssrc,
-            rtp_sender: Arc::downgrade(&internal),
-            rtp_transport: Arc::clone(&transport),
-            rtcp_read_stream: Mutex::new(None),
-            rtp_write_session: Mutex::new(None),
-            seq_trans: Arc::clone(&seq_trans),
         });
 
-        let srtp_rtcp_reader = Arc::clone(&srtp_stream) as Arc<dyn RTCPReader + Send + Sync>;
-        let rtcp_interceptor = interceptor.bind_rtcp_reader(srtp_rtcp_reader).await;
-        {
-            let mut internal_rtcp_interceptor = internal.rtcp_interceptor.lock().await;
-            *internal_rtcp_interceptor = Some(rtcp_interceptor);
-        }
-
         let stream_ids = track
             .as_ref()
             .map(|track| vec![track.stream_id().to_string()])
             .unwrap_or_default();
-        Self {
-            track: Mutex::new(track),
-
-            srtp_stream,
-            stream_info: Mutex::new(StreamInfo::default()),
-            seq_trans,
+        let sender = Self {
+            track_encodings: SyncMutex::new(vec![]),
 
-            context: Mutex::new(TrackLocalContext::default()),
             transport,
 
             payload_type: 0,
-            ssrc,
             receive_mtu,
 
             negotiated: AtomicBool::new(false),
@@ -206,9 +197,210 @@ impl RTCRtpSender {
             stop_called_signal,
 
             paused: Arc::new(AtomicBool::new(start_paused)),
+            seq_trans_enabled: AtomicBool::new(false),
+
+            degradation_preference: SyncMutex::new(RTCDegradationPreference::default()),
+            last_returned_parameters: SyncMutex::new(None),
 
             internal,
+        };
+
+        let parameters = RTCRtpEncodingParameters {
+            rid: track
+                .as_ref()
+                .and_then(|track| track.rid())
+                .unwrap_or_default()
+                .to_owned(),
+            ..Default::default()
+        };
+        let encoding = sender.new_track_encoding(track, parameters).await;
+        sender.track_encodings.lock().push(encoding);
+
+        sender
+    }
+
+    /// new_track_encoding creates the write path of a single encoding. A random SSRC is
+    /// chosen if the parameters don't specify one.
+    async fn new_track_encoding(
+        &self,
+        track: Option<Arc<dyn TrackLocal + Send + Sync>>,
+        mut parameters: RTCRtpEncodingParameters,
+    ) -> TrackEncoding {
+        if parameters.ssrc == 0 {
+            parameters.ssrc = rand::random::<u32>();
+        }
+
+        let seq_trans = Arc::new(SequenceTransformer::new());
+        if self.seq_trans_enabled.load(Ordering::SeqCst) {
+            // A new encoding hasn't sent any data yet, so this can't fail.
+            let _ = seq_trans.enable();
+        }
+
+        let srtp_stream = Arc::new(SrtpWriterFuture {
+            closed: AtomicBool::new(false),
+            ssrc: parameters.ssrc,
+            rtp_sender: Arc::downgrade(&self.internal),
+            rtp_transport: Arc::clone(&self.transport),
+            rtcp_read_stream: Mutex::new(None),
+            rtp_write_session: Mutex::new(None),
+            seq_trans: Arc::clone(&seq_trans),
+        });
+
+        let srtp_rtcp_reader = Arc::clone(&srtp_stream) as Arc<dyn RTCPReader + Send + Sync>;
+        let rtcp_interceptor = self.interceptor.bind_rtcp_reader(srtp_rtcp_reader).await;
+
+        let paused = self.paused.load(Ordering::SeqCst) || !parameters.active;
+
+        TrackEncoding {
+            track,
+            parameters,
+            srtp_stream,
+            rtcp_interceptor,
+            seq_trans,
+            paused: Arc::new(AtomicBool::new(paused)),
+            stream_info: StreamInfo::default(),
+            context: TrackLocalContext::default(),
+        }
+    }
+
+    /// set_send_encodings replaces the encodings of this RTPSender before it has started
+    /// sending. Tracks that are already attached are kept on the encoding with the same RID,
+    /// or on the first encoding if they have no RID.
+    pub(crate) async fn set_send_encodings(
+        &self,
+        kind: RTPCodecType,
+        send_encodings: Vec<RTCRtpEncodingParameters>,
+    ) -> Result<()> {
+        if send_encodings.is_empty() {
+            return Ok(());
+        }
+        if self.has_sent() {
+            return Err(Error::ErrRTPSenderSendAlreadyCalled);
+        }
+
+        if send_encodings.len() > 1 {
+            self.check_simulcast_header_extensions(kind)?;
+            for (i, parameters) in send_encodings.iter().enumerate() {
+                if parameters.rid.is_empty() {
+                    return Err(Error::ErrRTPSenderRidNil);
+                }
+                if send_encodings[..i].iter().any(|p| p.rid == parameters.rid) {
+                    return Err(Error::ErrRTPSenderRIDCollision);
+                }
+            }
+        }
+
+        let mut tracks: Vec<Arc<dyn TrackLocal + Send + Sync>> = self
+            .track_encodings
+            .lock()
+            .iter()
+            .filter_map(|encoding| encoding.track.clone())
+            .collect();
+
+        let mut encodings = Vec::with_capacity(send_encodings.len());
+        for (i, parameters) in send_encodings.into_iter().enumerate() {
+            let track = tracks
+                .iter()
+                .position(|track| match track.rid() {
+                    Some(rid) => rid == parameters.rid,
+                    None => i == 0,
+                })
+                .map(|pos| tracks.remove(pos));
+            encodings.push(self.new_track_encoding(track, parameters).await);
+        }
+        if !tracks.is_empty() {
+            return Err(Error::ErrRTPSenderNoEncodingForRid);
+        }
+
+        *self.track_encodings.lock() = encodings;
+
+        Ok(())
+    }
+
+    /// add_encoding adds an encoding to the RTPSender, which sends `track` as an
+    /// additional simulcast layer. The track must have a RID and match the id, stream id
+    /// and kind of the track of the first encoding. If an encoding with the RID of the track
+    /// was configured with `send_encodings`, the track is attached to it.
+    ///
+    /// Encodings must be added before the RTPSender starts sending, and the MID and RID
+    /// header extensions must be registered with the MediaEngine, which the receiver needs
+    /// to tell the layers apart.
+    pub async fn add_encoding(&self, track: Arc<dyn TrackLocal + Send + Sync>) -> Result<()> {
+        let rid = match track.rid() {
+            Some(rid) if !rid.is_empty() => rid.to_owned(),
+            _ => return Err(Error::ErrRTPSenderRidNil),
+        };
+
+        if self.has_stopped().await {
+            return Err(Error::ErrRTPSenderStopped);
+        }
+        if self.has_sent() {
+            return Err(Error::ErrRTPSenderSendAlreadyCalled);
+        }
+        self.check_simulcast_header_extensions(track.kind())?;
+
+        {
+            let mut encodings = self.track_encodings.lock();
+            let base_track = encodings
+                .iter()
+                .find_map(|encoding| encoding.track.clone())
+                .ok_or(Error::ErrRTPSenderNoBaseEncoding)?;
+            if base_track.rid().is_none() {
+                return Err(Error::ErrRTPSenderNoBaseEncoding);
+            }
+            if base_track.id() != track.id()
+                || base_track.stream_id() != track.stream_id()
+                || base_track.kind() != track.kind()
+            {
+                return Err(Error::ErrRTPSenderBaseEncodingMismatch);
+            }
+
+            // An encoding configured with send_encodings only needs its track
+            if let Some(existing) = encodings.iter_mut().find(|e| e.parameters.rid == rid) {
+                if existing.track.is_some() {
+                    return Err(Error::ErrRTPSenderRIDCollision);
+                }
+                existing.track = Some(track);
+                return Ok(());
+            }
+        }
+
+        let encoding = self
+            .new_track_encoding(
+                Some(Arc::clone(&track)),
+                RTCRtpEncodingParameters {
+                    rid: rid.clone(),
+                    ..Default::default()
+                },
+            )
+            .await;
+
+        let mut encodings = self.track_encodings.lock();
+        if encodings.iter().any(|e| e.parameters.rid == rid) {
+            return Err(Error::ErrRTPSenderRIDCollision);
+        }
+        encodings.push(encoding);
+
+        Ok(())
+    }
+
+    /// check_simulcast_header_extensions checks that the MID and RID header extensions are
+    /// registered, without them the remote peer can't tell the simulcast layers apart.
+    fn check_simulcast_header_extensions(&self, kind: RTPCodecType) -> Result<()> {
+        if !self
+            .media_engine
+            .is_header_extension_registered(::sdp::extmap::SDES_MID_URI, kind)
+        {
+            return Err(Error::ErrPeerConnSimulcastMidRTPExtensionRequired);
         }
+        if !self
+            .media_engine
+            .is_header_extension_registered(::sdp::extmap::SDES_RTP_STREAM_ID_URI, kind)
+        {
+            return Err(Error::ErrPeerConnSimulcastStreamIDRTPExtensionRequired);
+        }
+
+        Ok(())
     }
 
     pub(crate) fn is_negotiated(&self) -> bool {
@@ -229,6 +421,13 @@ impl RTCRtpSender {
 
     pub(crate) fn set_paused(&self, paused: bool) {
         self.paused.store(paused, Ordering::SeqCst);
+
+        let encodings = self.track_encodings.lock();
+        for encoding in encodings.iter() {
+            encoding
+                .paused
+                .store(paused || !encoding.parameters.active, Ordering::SeqCst);
+        }
     }
 
     /// transport returns the currently-configured DTLSTransport
@@ -238,27 +437,81 @@ impl RTCRtpSender {
     }
 
     /// get_parameters describes the current configuration for the encoding and
-    /// transmission of media on the sender's track.
+    /// transmission of media on the sender's track. The returned parameters carry a
+    /// new transaction id, which set_parameters requires to apply them.
     pub async fn get_parameters(&self) -> RTCRtpSendParameters {
-        let kind = {
-            let track = self.track.lock().await;
-            if let Some(t) = &*track {
-                t.kind()
-            } else {
-                RTPCodecType::default()
-            }
+        let mut parameters = self.current_parameters().await;
+        parameters.transaction_id = generate_crypto_random_string(
+            16,
+            b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789",
+        );
+
+        *self.last_returned_parameters.lock() = Some(parameters.clone());
+
+        parameters
+    }
+
+    /// set_parameters changes the parameters of the encodings of the sender while it
+    /// is sending, without renegotiation. `parameters` must be the ones returned by
+    /// the last get_parameters call, modified only in `degradation_preference` and
+    /// the `active`, `max_bitrate`, `max_framerate`, `priority` and
+    /// `scale_resolution_down_by` of the encodings.
+    ///
+    /// Deactivating an encoding stops sending RTP on its SSRC. The new limits are
+    /// shared with the interceptors through `StreamInfo::encoding`.
+    pub async fn set_parameters(&self, parameters: RTCRtpSendParameters) -> Result<()> {
+        if self.stop_called_signal.load(Ordering::SeqCst) {
+            return Err(Error::ErrRTPSenderStopped);
+        }
+
+        let mut last_returned_parameters = self.last_returned_parameters.lock();
+        let last = match &*last_returned_parameters {
+            Some(last) => last,
+            None => return Err(Error::ErrRTPSenderNoParameters),
         };
+        validate_parameters(last, &parameters)?;
+        *last_returned_parameters = None;
+        drop(last_returned_parameters);
+
+        *self.degradation_preference.lock() = parameters.degradation_preference;
+
+        let paused = self.paused.load(Ordering::SeqCst);
+        let mut encodings = self.track_encodings.lock();
+        for (encoding, p) in encodings.iter_mut().zip(parameters.encodings) {
+            encoding.parameters.active = p.active;
+            encoding.parameters.max_bitrate = p.max_bitrate;
+            encoding.parameters.max_framerate = p.max_framerate;
+            encoding.parameters.priority = p.priority;
+            encoding.parameters.scale_resolution_down_by = p.scale_resolution_down_by;
+
+            encoding
+                .paused
+                .store(paused || !encoding.parameters.active, Ordering::SeqCst);
+        }
+
+        Ok(())
+    }
+
+    /// current_parameters returns the parameters of the sender without a transaction id.
+    pub(crate) async fn current_parameters(&self) -> RTCRtpSendParameters {
+        let kind = self.kind();
 
         let mut send_parameters = {
             RTCRtpSendParameters {
                 rtp_parameters: self
                     .media_engine
                     .get_rtp_parameters_by_kind(kind, RTCRtpTransceiverDirection::Sendonly),
-                encodings: vec![RTCRtpEncodingParameters {
-                    ssrc: self.ssrc,
-                    payload_type: self.payload_type,
-                    ..Default::default()
-                }],
+                encodings: self
+                    .track_encodings
+                    .lock()
+                    .iter()
+                    .map(|encoding| RTCRtpEncodingParameters {
+                        payload_type: self.payload_type,
+                        ..encoding.parameters.clone()
+                    })
+                    .collect(),
+                transaction_id: String::new(),
+                degradation_preference: *self.degradation_preference.lock(),
             }
         };
 
@@ -279,15 +532,30 @@ impl RTCRtpSender {
         send_parameters
     }
 
-    /// track returns the RTCRtpTransceiver track, or nil
+    /// track returns the RTCRtpTransceiver track, or nil. With multiple encodings this
+    /// is the track of the first encoding that has one.
     pub async fn track(&self) -> Option<Arc<dyn TrackLocal + Send + Sync>> {
-        let track = self.track.lock().await;
-        track.clone()
+        self.track_encodings
+            .lock()
+            .iter()
+            .find_map(|encoding| encoding.track.clone())
+    }
+
+    /// kind returns the kind of the track of this RTPSender.
+    fn kind(&self) -> RTPCodecType {
+        self.track_encodings
+            .lock()
+            .iter()
+            .find_map(|encoding| encoding.track.as_ref().map(|t| t.kind()))
+            .unwrap_or_default()
     }
 
     /// replace_track replaces the track currently being used as the sender's source with a new TrackLocal.
     /// The new track must be of the same media kind (audio, video, etc) and switching the track should not
     /// require negotiation.
+    ///
+    /// With multiple encodings, a track replaces the track of the encoding with the same RID, or
+    /// the track of the first encoding if it has no RID. Passing `None` removes all tracks.
     pub async fn replace_track(
         &self,
         track: Option<Arc<dyn TrackLocal + Send + Sync>>,
@@ -307,30 +575,57 @@ impl RTCRtpSender {
             }
         }
 
+        let indices: Vec<usize> = {
+            let encodings = self.track_encodings.lock();
+            match &track {
+                Some(t) => {
+                    let index = match t.rid() {
+                        Some(rid) if encodings.len() > 1 => encodings
+                            .iter()
+                            .position(|e| e.parameters.rid == rid)
+                            .ok_or(Error::ErrRTPSenderNoEncodingForRid)?,
+                        _ => 0,
+                    };
+                    vec![index]
+                }
+                None => (0..encodings.len()).collect(),
+            }
+        };
+
+        for index in indices {
+            self.replace_encoding_track(index, track.clone()).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn replace_encoding_track(
+        &self,
+        index: usize,
+        track: Option<Arc<dyn TrackLocal + Send + Sync>>,
+    ) -> Result<()> {
+        let encoding = match self.track_encodings.lock().get(index) {
+            Some(encoding) => encoding.clone(),
+            None => return Ok(()),
+        };
+
         if self.has_sent() {
-            let t = {
-                let t = self.track.lock().await;
-                t.clone()
-            };
-            if let Some(t) = t {
-                let context = self.context.lock().await;
-                t.unbind(&context).await?;
+            if let Some(t) = &encoding.track {
+                t.unbind(&encoding.context).await?;
             }
         }
 
         if !self.has_sent() || track.is_none() {
-            let mut t = self.track.lock().await;
-            *t = track;
+            if let Some(e) = self.track_encodings.lock().get_mut(index) {
+                e.track = track;
+            }
             return Ok(());
         }
 
-        let context = {
-            let context = self.context.lock().await;
-            context.clone()
-        };
+        let context = encoding.context;
 
         let result = if let Some(t) = &track {
-            self.seq_trans.reset_offset();
+            encoding.seq_trans.reset_offset();
 
             let new_context = TrackLocalContext {
                 id: context.id.clone(),
@@ -339,7 +634,7 @@ impl RTCRtpSender {
                     .get_rtp_parameters_by_kind(t.kind(), RTCRtpTransceiverDirection::Sendonly),
                 ssrc: context.ssrc,
                 write_stream: context.write_stream.clone(),
-                paused: self.paused.clone(),
+                paused: Arc::clone(&encoding.paused),
             };
 
             t.bind(&new_context).await
@@ -350,23 +645,21 @@ impl RTCRtpSender {
         match result {
             Err(err) => {
                 // Re-bind the original track
-                let track = self.track.lock().await;
-                if let Some(t) = &*track {
+                if let Some(t) = &encoding.track {
                     t.bind(&context).await?;
                 }
 
                 Err(err)
             }
             Ok(codec) => {
-                // Codec has changed
-                if self.payload_type != codec.payload_type {
-                    let mut context = self.context.lock().await;
-                    context.params.codecs = vec![codec];
-                }
+                let mut encodings = self.track_encodings.lock();
+                if let Some(e) = encodings.get_mut(index) {
+                    // Codec has changed
+                    if self.payload_type != codec.payload_type {
+                        e.context.params.codecs = vec![codec];
+                    }
 
-                {
-                    let mut t = self.track.lock().await;
-                    *t = track;
+                    e.track = track;
                 }
 
                 Ok(())
@@ -374,33 +667,74 @@ impl RTCRtpSender {
         }
     }
 
+    /// negotiated_header_extension_id returns the id of the header extension with the
+    /// given uri if it is negotiated for kind.
+    async fn negotiated_header_extension_id(&self, uri: &str, kind: RTPCodecType) -> Option<u8> {
+        let (id, audio_supported, video_supported) = self
+            .media_engine
+            .get_header_extension_id(RTCRtpHeaderExtensionCapability {
+                uri: uri.to_owned(),
+            })
+            .await;
+        match kind {
+            RTPCodecType::Audio if audio_supported => Some(id as u8),
+            RTPCodecType::Video if video_supported => Some(id as u8),
+            _ => None,
+        }
+    }
+
     /// send Attempts to set the parameters controlling the sending of media.
     pub async fn send(&self, parameters: &RTCRtpSendParameters) -> Result<()> {
         if self.has_sent() {
             return Err(Error::ErrRTPSenderSendAlreadyCalled);
         }
 
-        let write_stream = Arc::new(InterceptorToTrackLocalWriter::new(self.paused.clone()));
-        let (context, stream_info) = {
-            let track = self.track.lock().await;
+        let kind = self.kind();
+        let encodings = self.track_encodings.lock().clone();
+        let mid = self
+            .rtp_transceiver
+            .lock()
+            .as_ref()
+            .and_then(|t| t.upgrade())
+            .and_then(|t| t.mid());
+        let mid_extension = self
+            .negotiated_header_extension_id(::sdp::extmap::SDES_MID_URI, kind)
+            .await;
+        let rtp_stream_id_extension = self
+            .negotiated_header_extension_id(::sdp::extmap::SDES_RTP_STREAM_ID_URI, kind)
+            .await;
+
+        let mut bound = Vec::with_capacity(encodings.len());
+        for (i, encoding) in encodings.into_iter().enumerate() {
+            let ssrc = parameters
+                .encodings
+                .get(i)
+                .map_or(encoding.parameters.ssrc, |p| p.ssrc);
+
+            let mut write_stream = InterceptorToTrackLocalWriter::new(Arc::clone(&encoding.paused));
+            if let (Some(rid), Some(rid_id)) = (encoding.rid(), rtp_stream_id_extension) {
+                // Tag every packet with its MID and RID, so the remote peer can tell the
+                // simulcast layers apart without signaled SSRCs.
+                if let (Some(mid), Some(mid_id)) = (&mid, mid_extension) {
+                    write_stream = write_stream.with_header_extension(mid_id, mid.clone());
+                }
+                write_stream = write_stream.with_header_extension(rid_id, rid.to_owned());
+            }
+            let write_stream = Arc::new(write_stream);
+
             let mut context = TrackLocalContext {
                 id: self.id.clone(),
-                params: self.media_engine.get_rtp_parameters_by_kind(
-                    if let Some(t) = &*track {
-                        t.kind()
-                    } else {
-                        RTPCodecType::default()
-                    },
-                    RTCRtpTransceiverDirection::Sendonly,
-                ),
-                ssrc: parameters.encodings[0].ssrc,
+                params: self
+                    .media_engine
+                    .get_rtp_parameters_by_kind(kind, RTCRtpTransceiverDirection::Sendonly),
+                ssrc,
                 write_stream: Some(
                     Arc::clone(&write_stream) as Arc<dyn TrackLocalWriter + Send + Sync>
                 ),
-                paused: self.paused.clone(),
+                paused: Arc::clone(&encoding.paused),
             };
 
-            let codec = if let Some(t) = &*track {
+            let codec = if let Some(t) = &encoding.track {
                 t.bind(&context).await?
             } else {
                 RTCRtpCodecParameters::default()
@@ -410,32 +744,32 @@ impl RTCRtpSender {
             context.params.codecs = vec![codec];
             let stream_info = create_stream_info(
                 self.id.clone(),
-                parameters.encodings[0].ssrc,
+                ssrc,
                 payload_type,
                 capability,
                 &parameters.rtp_parameters.header_extensions,
             );
 
-            (context, stream_info)
-        };
+            let srtp_rtp_writer =
+                Arc::clone(&encoding.srtp_stream) as Arc<dyn RTPWriter + Send + Sync>;
+            let rtp_interceptor = self
+                .interceptor
+                .bind_local_stream(&stream_info, srtp_rtp_writer)
+                .await;
+            {
+                let mut interceptor_rtp_writer = write_stream.interceptor_rtp_writer.lock().await;
+                *interceptor_rtp_writer = Some(rtp_interceptor);
+            }
 
-        let srtp_rtp_writer = Arc::clone(&self.srtp_stream) as Arc<dyn RTPWriter + Send + Sync>;
-        let rtp_interceptor = self
-            .interceptor
-            .bind_local_stream(&stream_info, srtp_rtp_writer)
-            .await;
-        {
-            let mut interceptor_rtp_writer = write_stream.interceptor_rtp_writer.lock().await;
-            *interceptor_rtp_writer = Some(rtp_interceptor);
+            bound.push((context, stream_info));
         }
 
         {
-            let mut ctx = self.context.lock().await;
-            *ctx = context;
-        }
-        {
-            let mut si = self.stream_info.lock().await;
-            *si = stream_info;
+            let mut encodings = self.track_encodings.lock();
+            for (encoding, (context, stream_info)) in encodings.iter_mut().zip(bound) {
+                encoding.context = context;
+                encoding.stream_info = stream_info;
+            }
         }
 
         {
@@ -460,24 +794,65 @@ impl RTCRtpSender {
 
         self.replace_track(None).await?;
 
-        {
-            let stream_info = self.stream_info.lock().await;
-            self.interceptor.unbind_local_stream(&stream_info).await;
+        let encodings = self.track_encodings.lock().clone();
+        let mut errs = vec![];
+        for encoding in encodings {
+            self.interceptor
+                .unbind_local_stream(&encoding.stream_info)
+                .await;
+
+            if let Err(err) = encoding.srtp_stream.close().await {
+                errs.push(err);
+            }
         }
 
-        self.srtp_stream.close().await
+        flatten_errs(errs)
     }
 
-    /// read reads incoming RTCP for this RTPReceiver
+    /// read reads incoming RTCP for this RTPSender. With multiple encodings, this
+    /// reads the RTCP of the first encoding; use read_simulcast for the others.
     pub async fn read(&self, b: &mut [u8]) -> Result<(usize, Attributes)> {
-        self.internal.read(b).await
+        let rtcp_interceptor = self.rtcp_interceptor(None)?;
+        self.internal.read(&rtcp_interceptor, b).await
     }
 
     /// read_rtcp is a convenience method that wraps Read and unmarshals for you.
     pub async fn read_rtcp(
         &self,
     ) -> Result<(Vec<Box<dyn rtcp::packet::Packet + Send + Sync>>, Attributes)> {
-        self.internal.read_rtcp(self.receive_mtu).await
+        let rtcp_interceptor = self.rtcp_interceptor(None)?;
+        self.internal
+            .read_rtcp(&rtcp_interceptor, self.receive_mtu)
+            .await
+    }
+
+    /// read_simulcast reads incoming RTCP for the encoding with the given RID.
+    pub async fn read_simulcast(&self, b: &mut [u8], rid: &str) -> Result<(usize, Attributes)> {
+        let rtcp_interceptor = self.rtcp_interceptor(Some(rid))?;
+        self.internal.read(&rtcp_interceptor, b).await
+    }
+
+    /// read_simulcast_rtcp is a convenience method that wraps read_simulcast and unmarshals for you.
+    pub async fn read_simulcast_rtcp(
+        &self,
+        rid: &str,
+    ) -> Result<(Vec<Box<dyn rtcp::packet::Packet + Send + Sync>>, Attributes)> {
+        let rtcp_interceptor = self.rtcp_interceptor(Some(rid))?;
+        self.internal
+            .read_rtcp(&rtcp_interceptor, self.receive_mtu)
+            .await
+    }
+
+    fn rtcp_interceptor(&self, rid: Option<&str>) -> Result<Arc<dyn RTCPReader + Send + Sync>> {
+        let encodings = self.track_encodings.lock();
+        let encoding = match rid {
+            Some(rid) => encodings.iter().find(|e| e.parameters.rid == rid),
+            None => encodings.first(),
+        };
+
+        encoding
+            .map(|e| Arc::clone(&e.rtcp_interceptor))
+            .ok_or(Error::ErrRTPSenderNoEncodingForRid)
     }
 
     /// Enables overriding outgoing `RTP` packets' `sequence number`s.
@@ -489,7 +864,20 @@ impl RTCRtpSender {
     /// Errors if this [`RTCRtpSender`] has started to send data or sequence
     /// transforming has been already enabled.
     pub fn enable_seq_transformer(&self) -> Result<()> {
-        self.seq_trans.enable()
+        let encodings = self.track_encodings.lock();
+        if self.seq_trans_enabled.load(Ordering::SeqCst) {
+            return Err(Error::ErrRTPSenderSeqTransEnabled);
+        }
+        if encodings.iter().any(|e| e.seq_trans.data_sent()) {
+            return Err(Error::ErrRTPSenderDataSent);
+        }
+
+        for encoding in encodings.iter() {
+            encoding.seq_trans.enable()?;
+        }
+        self.seq_trans_enabled.store(true, Ordering::SeqCst);
+
+        Ok(())
     }
 
     /// has_sent tells if data has been ever sent for this instance
@@ -539,3 +927,48 @@ impl RTCRtpSender {
         lock.clone()
     }
 }
+
+/// validate_parameters checks that parameters only differ from the last returned
+/// ones in the members set_parameters may change, and that those are in range.
+/// <https://w3c.github.io/webrtc-pc/#dom-rtcrtpsender-setparameters>
+fn validate_parameters(
+    last: &RTCRtpSendParameters,
+    parameters: &RTCRtpSendParameters,
+) -> Result<()> {
+    if parameters.transaction_id != last.transaction_id {
+        return Err(Error::ErrRTPSenderTransactionIdMismatch);
+    }
+
+    if parameters.encodings.len() != last.encodings.len()
+        || parameters.rtp_parameters.codecs != last.rtp_parameters.codecs
+        || parameters.rtp_parameters.header_extensions != last.rtp_parameters.header_extensions
+    {
+        return Err(Error::ErrRTPSenderReadOnlyParameterModified);
+    }
+
+    for (encoding, last) in parameters.encodings.iter().zip(&last.encodings) {
+        if encoding.rid != last.rid
+            || encoding.ssrc != last.ssrc
+            || encoding.payload_type != last.payload_type
+            || encoding.rtx.ssrc != last.rtx.ssrc
+        {
+            return Err(Error::ErrRTPSenderReadOnlyParameterModified);
+        }
+
+        if encoding
+            .scale_resolution_down_by
+            .map_or(false, |scale| scale < 1.0)
+        {
+            return Err(Error::ErrRTPSenderScaleResolutionDownByRange);
+        }
+
+        if encoding
+            .max_framerate
+            .map_or(false, |framerate| framerate < 0.0)
+        {
+            return Err(Error::ErrRTPSenderMaxFramerateRange);
+        }
+    }
+
+    Ok(())
+}
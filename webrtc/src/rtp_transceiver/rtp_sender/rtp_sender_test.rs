@@ -3,12 +3,16 @@ use crate::api::media_engine::{MIME_TYPE_H264, MIME_TYPE_OPUS, MIME_TYPE_VP8, MI
 use crate::api::setting_engine::SettingEngine;
 use crate::api::APIBuilder;
 use crate::error::Result;
+use crate::peer_connection::configuration::RTCConfiguration;
 use crate::peer_connection::peer_connection_state::RTCPeerConnectionState;
 use crate::peer_connection::peer_connection_test::{
     close_pair_now, create_vnet_pair, new_pair, send_video_until_done, signal_pair,
     until_connection_state,
 };
 use crate::rtp_transceiver::rtp_codec::RTCRtpCodecCapability;
+use crate::rtp_transceiver::rtp_degradation_preference::RTCDegradationPreference;
+use crate::rtp_transceiver::rtp_priority::RTCPriorityType;
+use crate::rtp_transceiver::RTCRtpTransceiverInit;
 use crate::track::track_local::track_local_static_sample::TrackLocalStaticSample;
 use bytes::Bytes;
 use std::sync::atomic::AtomicU64;
@@ -137,12 +141,119 @@ async fn test_rtp_sender_get_parameters() -> Result<()> {
     let parameters = sender.get_parameters().await;
     assert_ne!(0, parameters.rtp_parameters.codecs.len());
     assert_eq!(1, parameters.encodings.len());
-    assert_eq!(sender.ssrc, parameters.encodings[0].ssrc);
+    assert_eq!(
+        sender.track_encodings.lock()[0].parameters.ssrc,
+        parameters.encodings[0].ssrc
+    );
 
     close_pair_now(&offerer, &answerer).await;
     Ok(())
 }
 
+#[tokio::test]
+async fn test_rtp_sender_set_parameters() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (mut sender, mut receiver) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+    let rtp_sender = sender
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    let parameters = rtp_sender.get_parameters().await;
+    assert!(!parameters.transaction_id.is_empty());
+
+    let mut invalid = parameters.clone();
+    invalid.transaction_id = "stale".to_owned();
+    assert_eq!(
+        rtp_sender.set_parameters(invalid).await,
+        Err(Error::ErrRTPSenderTransactionIdMismatch)
+    );
+
+    let mut invalid = parameters.clone();
+    invalid.encodings[0].ssrc += 1;
+    assert_eq!(
+        rtp_sender.set_parameters(invalid).await,
+        Err(Error::ErrRTPSenderReadOnlyParameterModified)
+    );
+
+    let mut invalid = parameters.clone();
+    invalid.encodings[0].scale_resolution_down_by = Some(0.5);
+    assert_eq!(
+        rtp_sender.set_parameters(invalid).await,
+        Err(Error::ErrRTPSenderScaleResolutionDownByRange)
+    );
+
+    // Failed calls keep the parameters of the last get_parameters call valid.
+    let mut parameters = parameters;
+    parameters.encodings[0].active = false;
+    parameters.encodings[0].max_bitrate = Some(500_000);
+    parameters.encodings[0].priority = RTCPriorityType::High;
+    parameters.degradation_preference = RTCDegradationPreference::MaintainResolution;
+    rtp_sender.set_parameters(parameters.clone()).await?;
+    assert_eq!(
+        rtp_sender.set_parameters(parameters).await,
+        Err(Error::ErrRTPSenderNoParameters)
+    );
+
+    let parameters = rtp_sender.get_parameters().await;
+    assert!(!parameters.encodings[0].active);
+    assert_eq!(parameters.encodings[0].max_bitrate, Some(500_000));
+    assert_eq!(
+        parameters.degradation_preference,
+        RTCDegradationPreference::MaintainResolution
+    );
+    {
+        let encodings = rtp_sender.track_encodings.lock();
+        assert!(encodings[0].paused.load(Ordering::SeqCst));
+        assert_eq!(encodings[0].parameters.priority, RTCPriorityType::High);
+    }
+
+    let (seen_track_tx, mut seen_track_rx) = mpsc::channel::<()>(1);
+    receiver.on_track(Box::new(move |_, _, _| {
+        let seen_track_tx2 = seen_track_tx.clone();
+        Box::pin(async move {
+            let _ = seen_track_tx2.send(()).await;
+        })
+    }));
+
+    signal_pair(&mut sender, &mut receiver).await?;
+
+    let (done_tx, done_rx) = mpsc::channel::<()>(1);
+    tokio::spawn(async move {
+        send_video_until_done(done_rx, vec![track], Bytes::from_static(&[0xAA]), None).await;
+    });
+
+    // An inactive encoding sends no RTP, so the remote never sees the track.
+    assert!(
+        tokio::time::timeout(Duration::from_secs(1), seen_track_rx.recv())
+            .await
+            .is_err()
+    );
+
+    let mut parameters = rtp_sender.get_parameters().await;
+    parameters.encodings[0].active = true;
+    rtp_sender.set_parameters(parameters).await?;
+
+    tokio::time::timeout(Duration::from_secs(10), seen_track_rx.recv())
+        .await
+        .expect("timed out waiting for the reactivated encoding");
+    drop(done_tx);
+
+    close_pair_now(&sender, &receiver).await;
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_rtp_sender_set_read_deadline() -> Result<()> {
     let (mut sender, mut receiver, wan) = create_vnet_pair().await?;
@@ -332,3 +443,172 @@ async fn test_rtp_sender_replace_track_invalid_codec_change() -> Result<()> {
     close_pair_now(&sender, &receiver).await;
     Ok(())
 }
+
+#[tokio::test]
+async fn test_rtp_sender_simulcast() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    for extension in [
+        ::sdp::extmap::SDES_MID_URI,
+        ::sdp::extmap::SDES_RTP_STREAM_ID_URI,
+    ] {
+        m.register_header_extension(
+            RTCRtpHeaderExtensionCapability {
+                uri: extension.to_owned(),
+            },
+            RTPCodecType::Video,
+            None,
+        )?;
+    }
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (mut sender, mut receiver) = new_pair(&api).await?;
+
+    let rids = ["q", "h", "f"];
+    let tracks: Vec<Arc<TrackLocalStaticSample>> = rids
+        .iter()
+        .map(|rid| {
+            Arc::new(TrackLocalStaticSample::new_with_rid(
+                RTCRtpCodecCapability {
+                    mime_type: MIME_TYPE_VP8.to_owned(),
+                    ..Default::default()
+                },
+                "video".to_owned(),
+                rid.to_string(),
+                "webrtc-rs".to_owned(),
+            ))
+        })
+        .collect();
+
+    let transceiver = sender
+        .add_transceiver_from_track(
+            Arc::clone(&tracks[0]) as Arc<dyn TrackLocal + Send + Sync>,
+            Some(RTCRtpTransceiverInit {
+                direction: RTCRtpTransceiverDirection::Sendonly,
+                send_encodings: rids
+                    .iter()
+                    .map(|rid| RTCRtpEncodingParameters {
+                        rid: rid.to_string(),
+                        ..Default::default()
+                    })
+                    .collect(),
+            }),
+        )
+        .await?;
+    let rtp_sender = transceiver.sender();
+    for track in &tracks[1..] {
+        rtp_sender
+            .add_encoding(Arc::clone(track) as Arc<dyn TrackLocal + Send + Sync>)
+            .await?;
+    }
+    assert_eq!(
+        rtp_sender
+            .add_encoding(Arc::clone(&tracks[1]) as Arc<dyn TrackLocal + Send + Sync>)
+            .await,
+        Err(Error::ErrRTPSenderRIDCollision)
+    );
+
+    let parameters = rtp_sender.get_parameters().await;
+    assert_eq!(parameters.encodings.len(), 3);
+    assert_ne!(parameters.encodings[0].ssrc, parameters.encodings[1].ssrc);
+    assert_ne!(parameters.encodings[1].ssrc, parameters.encodings[2].ssrc);
+
+    let offer = sender.create_offer(None).await?;
+    assert!(offer.sdp.contains("a=rid:q send"), "{}", offer.sdp);
+    assert!(
+        offer.sdp.contains("a=simulcast:send q;h;f"),
+        "{}",
+        offer.sdp
+    );
+
+    let (seen_rid_tx, mut seen_rid_rx) = mpsc::channel::<String>(3);
+    receiver.on_track(Box::new(move |track, _, _| {
+        let seen_rid_tx2 = seen_rid_tx.clone();
+        Box::pin(async move {
+            let _ = seen_rid_tx2.send(track.rid().to_owned()).await;
+        })
+    }));
+
+    signal_pair(&mut sender, &mut receiver).await?;
+
+    let (done_tx, done_rx) = mpsc::channel::<()>(1);
+    tokio::spawn(async move {
+        send_video_until_done(done_rx, tracks, Bytes::from_static(&[0xAA]), None).await;
+    });
+
+    let mut seen_rids = vec![];
+    while seen_rids.len() < rids.len() {
+        let rid = tokio::time::timeout(Duration::from_secs(10), seen_rid_rx.recv())
+            .await
+            .expect("timed out waiting for simulcast layers")
+            .expect("on_track handler dropped");
+        seen_rids.push(rid);
+    }
+    seen_rids.sort();
+    assert_eq!(seen_rids, vec!["f", "h", "q"]);
+    drop(done_tx);
+
+    close_pair_now(&sender, &receiver).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rtp_sender_simulcast_requires_header_extensions() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let pc = api.new_peer_connection(RTCConfiguration::default()).await?;
+
+    let tracks: Vec<Arc<TrackLocalStaticSample>> = ["q", "h"]
+        .iter()
+        .map(|rid| {
+            Arc::new(TrackLocalStaticSample::new_with_rid(
+                RTCRtpCodecCapability {
+                    mime_type: MIME_TYPE_VP8.to_owned(),
+                    ..Default::default()
+                },
+                "video".to_owned(),
+                rid.to_string(),
+                "webrtc-rs".to_owned(),
+            ))
+        })
+        .collect();
+
+    let result = pc
+        .add_transceiver_from_track(
+            Arc::clone(&tracks[0]) as Arc<dyn TrackLocal + Send + Sync>,
+            Some(RTCRtpTransceiverInit {
+                direction: RTCRtpTransceiverDirection::Sendonly,
+                send_encodings: ["q", "h"]
+                    .iter()
+                    .map(|rid| RTCRtpEncodingParameters {
+                        rid: rid.to_string(),
+                        ..Default::default()
+                    })
+                    .collect(),
+            }),
+        )
+        .await;
+    assert_eq!(
+        result.err(),
+        Some(Error::ErrPeerConnSimulcastMidRTPExtensionRequired)
+    );
+
+    let transceiver = pc
+        .add_transceiver_from_track(
+            Arc::clone(&tracks[0]) as Arc<dyn TrackLocal + Send + Sync>,
+            None,
+        )
+        .await?;
+    assert_eq!(
+        transceiver
+            .sender()
+            .add_encoding(Arc::clone(&tracks[1]) as Arc<dyn TrackLocal + Send + Sync>)
+            .await,
+        Err(Error::ErrPeerConnSimulcastMidRTPExtensionRequired)
+    );
+
+    pc.close().await?;
+    Ok(())
+}
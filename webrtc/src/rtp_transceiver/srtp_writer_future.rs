@@ -65,6 +65,12 @@ impl SequenceTransformer {
             .ok_or(Error::ErrRTPSenderDataSent)
     }
 
+    /// Reports whether this [`SequenceTransformer`] has already rewritten the
+    /// `sequence number` of a sent packet.
+    pub(crate) fn data_sent(&self) -> bool {
+        self.0.lock().data_sent
+    }
+
     /// Indicates [`SequenceTransformer`] about necessity of recalculating
     /// `offset`.
     pub(crate) fn reset_offset(&self) {
@@ -9,6 +9,7 @@ use crate::rtp_transceiver::rtp_codec::*;
 use crate::rtp_transceiver::*;
 
 use async_trait::async_trait;
+use bytes::Bytes;
 use interceptor::{Attributes, RTPWriter};
 use std::any::Any;
 use std::fmt;
@@ -90,6 +91,12 @@ pub trait TrackLocal {
     /// stream_id is the group this track belongs too. This must be unique
     fn stream_id(&self) -> &str;
 
+    /// rid is the RTP stream identifier of this track, which sets the simulcast layer the
+    /// track is sent as. Tracks without a RID are sent without simulcast.
+    fn rid(&self) -> Option<&str> {
+        None
+    }
+
     /// kind controls if this TrackLocal is audio or video
     fn kind(&self) -> RTPCodecType;
 
@@ -118,6 +125,8 @@ impl TrackBinding {
 pub(crate) struct InterceptorToTrackLocalWriter {
     pub(crate) interceptor_rtp_writer: Mutex<Option<Arc<dyn RTPWriter + Send + Sync>>>,
     sender_paused: Arc<AtomicBool>,
+    /// Header extensions, by negotiated id, written to every packet, e.g. MID and RID.
+    header_extensions: Vec<(u8, Bytes)>,
 }
 
 impl InterceptorToTrackLocalWriter {
@@ -125,9 +134,17 @@ impl InterceptorToTrackLocalWriter {
         InterceptorToTrackLocalWriter {
             interceptor_rtp_writer: Mutex::new(None),
             sender_paused: paused,
+            header_extensions: vec![],
         }
     }
 
+    /// with_header_extension makes the writer set the header extension with the
+    /// given id to value on every packet.
+    pub(crate) fn with_header_extension(mut self, id: u8, value: String) -> Self {
+        self.header_extensions.push((id, Bytes::from(value)));
+        self
+    }
+
     fn is_sender_paused(&self) -> bool {
         self.sender_paused.load(Ordering::SeqCst)
     }
@@ -149,7 +166,15 @@ impl TrackLocalWriter for InterceptorToTrackLocalWriter {
         let interceptor_rtp_writer = self.interceptor_rtp_writer.lock().await;
         if let Some(writer) = &*interceptor_rtp_writer {
             let a = Attributes::new();
-            Ok(writer.write(pkt, &a).await?)
+            if self.header_extensions.is_empty() {
+                Ok(writer.write(pkt, &a).await?)
+            } else {
+                let mut pkt = pkt.clone();
+                for (id, value) in &self.header_extensions {
+                    pkt.header.set_extension(*id, value.clone())?;
+                }
+                Ok(writer.write(&pkt, &a).await?)
+            }
         } else {
             Ok(0)
         }
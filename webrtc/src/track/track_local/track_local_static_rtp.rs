@@ -4,7 +4,9 @@ use super::*;
 
 use crate::error::flatten_errs;
 use bytes::BytesMut;
+use std::time::Duration;
 use tokio::sync::Mutex;
+use tokio::time::Instant;
 use util::{Marshal, MarshalSize};
 
 /// TrackLocalStaticRTP  is a TrackLocal that has a pre-set codec and accepts RTP Packets.
@@ -14,6 +16,7 @@ pub struct TrackLocalStaticRTP {
     pub(crate) bindings: Mutex<Vec<Arc<TrackBinding>>>,
     codec: RTCRtpCodecCapability,
     id: String,
+    rid: Option<String>,
     stream_id: String,
 }
 
@@ -24,10 +27,24 @@ impl TrackLocalStaticRTP {
             codec,
             bindings: Mutex::new(vec![]),
             id,
+            rid: None,
             stream_id,
         }
     }
 
+    /// returns a TrackLocalStaticRTP that is sent as the simulcast layer with the given RID.
+    pub fn new_with_rid(
+        codec: RTCRtpCodecCapability,
+        id: String,
+        rid: String,
+        stream_id: String,
+    ) -> Self {
+        TrackLocalStaticRTP {
+            rid: Some(rid),
+            ..Self::new(codec, id, stream_id)
+        }
+    }
+
     /// codec gets the Codec of the track
     pub fn codec(&self) -> RTCRtpCodecCapability {
         self.codec.clone()
@@ -131,6 +148,24 @@ impl TrackLocalStaticRTP {
         flatten_errs(write_errs)?;
         Ok(n)
     }
+
+    /// replay_rtp writes the packets of a captured stream, for example the packets of one SSRC
+    /// read from an rtpdump or pcap file, with their original timing. Every packet is written
+    /// at its offset from the start of the replay. The sequence numbers and timestamps of the
+    /// packets are kept, so the losses and reordering of the capture are reproduced.
+    pub async fn replay_rtp<I>(&self, packets: I) -> Result<usize>
+    where
+        I: IntoIterator<Item = (Duration, rtp::packet::Packet)>,
+    {
+        let start = Instant::now();
+        let mut n = 0;
+        for (offset, packet) in packets {
+            tokio::time::sleep_until(start + offset).await;
+            n += self.write_rtp(&packet).await?;
+        }
+
+        Ok(n)
+    }
 }
 
 #[async_trait]
@@ -195,6 +230,11 @@ impl TrackLocal for TrackLocalStaticRTP {
         self.stream_id.as_str()
     }
 
+    /// rid is the RTP stream identifier of the simulcast layer this track is sent as
+    fn rid(&self) -> Option<&str> {
+        self.rid.as_deref()
+    }
+
     /// kind controls if this TrackLocal is audio or video
     fn kind(&self) -> RTPCodecType {
         if self.codec.mime_type.starts_with("audio/") {
@@ -26,8 +26,20 @@ pub struct TrackLocalStaticSample {
 impl TrackLocalStaticSample {
     /// returns a TrackLocalStaticSample
     pub fn new(codec: RTCRtpCodecCapability, id: String, stream_id: String) -> Self {
-        let rtp_track = TrackLocalStaticRTP::new(codec, id, stream_id);
+        Self::from_rtp_track(TrackLocalStaticRTP::new(codec, id, stream_id))
+    }
+
+    /// returns a TrackLocalStaticSample that is sent as the simulcast layer with the given RID
+    pub fn new_with_rid(
+        codec: RTCRtpCodecCapability,
+        id: String,
+        rid: String,
+        stream_id: String,
+    ) -> Self {
+        Self::from_rtp_track(TrackLocalStaticRTP::new_with_rid(codec, id, rid, stream_id))
+    }
 
+    fn from_rtp_track(rtp_track: TrackLocalStaticRTP) -> Self {
         TrackLocalStaticSample {
             rtp_track,
             internal: Mutex::new(TrackLocalStaticSampleInternal {
@@ -226,6 +238,11 @@ impl TrackLocal for TrackLocalStaticSample {
         self.rtp_track.stream_id()
     }
 
+    /// rid is the RTP stream identifier of the simulcast layer this track is sent as
+    fn rid(&self) -> Option<&str> {
+        self.rtp_track.rid()
+    }
+
     /// kind controls if this TrackLocal is audio or video
     fn kind(&self) -> RTPCodecType {
         self.rtp_track.kind()
@@ -2,11 +2,14 @@ use super::{track_local_static_rtp::*, track_local_static_sample::*, *};
 use crate::api::media_engine::{MediaEngine, MIME_TYPE_VP8};
 use crate::api::APIBuilder;
 use crate::peer_connection::configuration::RTCConfiguration;
+use crate::peer_connection::peer_connection_state::RTCPeerConnectionState;
 use crate::peer_connection::peer_connection_test::*;
 
 use bytes::Bytes;
 use std::sync::Arc;
 use tokio::sync::{mpsc, Mutex};
+use tokio::time::{Duration, Instant};
+use waitgroup::WaitGroup;
 
 // If a remote doesn't support a Codec used by a `TrackLocalStatic`
 // an error should be returned to the user
@@ -403,6 +406,92 @@ async fn test_track_local_static_binding_non_blocking() -> Result<()> {
     Ok(())
 }
 
+// Assert that a replayed capture keeps the order and the timing of its packets
+#[tokio::test]
+async fn test_track_local_static_replay_rtp() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (mut pc_offer, mut pc_answer) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticRTP::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+    pc_offer
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    let (received_tx, mut received_rx) = mpsc::channel::<(u16, Instant)>(8);
+    pc_answer.on_track(Box::new(move |track, _, _| {
+        let received_tx = received_tx.clone();
+        Box::pin(async move {
+            while let Ok((packet, _)) = track.read_rtp().await {
+                if received_tx
+                    .send((packet.header.sequence_number, Instant::now()))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        })
+    }));
+
+    let wg = WaitGroup::new();
+    until_connection_state(&mut pc_offer, &wg, RTCPeerConnectionState::Connected).await;
+    signal_pair(&mut pc_offer, &mut pc_answer).await?;
+    wg.wait().await;
+
+    let sequence_numbers = [10u16, 11, 13, 12];
+    let packets = sequence_numbers
+        .iter()
+        .enumerate()
+        .map(|(i, &sequence_number)| {
+            (
+                Duration::from_millis(50 * i as u64),
+                rtp::packet::Packet {
+                    header: rtp::header::Header {
+                        version: 2,
+                        sequence_number,
+                        timestamp: 3000 * i as u32,
+                        ..Default::default()
+                    },
+                    payload: Bytes::from_static(&[0x10, 0x00, 0x00]),
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+    track.replay_rtp(packets).await?;
+
+    let mut received = vec![];
+    while received.len() < sequence_numbers.len() {
+        match tokio::time::timeout(Duration::from_secs(5), received_rx.recv()).await {
+            Ok(Some(packet)) => received.push(packet),
+            _ => panic!("replayed packets were not received"),
+        }
+    }
+    assert_eq!(
+        received.iter().map(|(seq, _)| *seq).collect::<Vec<_>>(),
+        sequence_numbers
+    );
+    let spread = received[3].1.duration_since(received[0].1);
+    assert!(
+        spread >= Duration::from_millis(100),
+        "replay took {:?}",
+        spread
+    );
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+
+    Ok(())
+}
+
 /*
 //TODO: func BenchmarkTrackLocalWrite(b *testing.B) {
     offerPC, answerPC, err := newPair()
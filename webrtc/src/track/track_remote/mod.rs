@@ -1,3 +1,8 @@
+#[cfg(test)]
+mod track_remote_test;
+
+pub mod track_remote_jitter_buffer;
+
 use crate::api::media_engine::MediaEngine;
 use crate::error::{Error, Result};
 use crate::rtp_transceiver::rtp_codec::{RTCRtpCodecParameters, RTCRtpParameters, RTPCodecType};
@@ -8,12 +13,14 @@ use crate::rtp_transceiver::rtp_receiver::RTPReceiverInternal;
 use crate::track::RTP_PAYLOAD_TYPE_BITMASK;
 use arc_swap::ArcSwapOption;
 use bytes::{Bytes, BytesMut};
+use interceptor::sync::{SyncInterceptor, SyncOffset};
 use interceptor::{Attributes, Interceptor};
 use std::collections::VecDeque;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::atomic::{AtomicU32, AtomicU8, AtomicUsize, Ordering};
 use std::sync::{Arc, Weak};
+use std::time::SystemTime;
 use tokio::sync::Mutex;
 use util::sync::Mutex as SyncMutex;
 
@@ -212,6 +219,43 @@ impl TrackRemote {
             .store(Some(Arc::new(Mutex::new(Box::new(handler)))));
     }
 
+    /// capture_time returns the time on the clock of the remote peer at which the media with
+    /// an RTP timestamp of the track was captured, for example the `packet_timestamp` of a
+    /// sample. It is mapped with the last RTCP sender report of the track, so it is None
+    /// until the RTCP of the receiver has been read and a sender report has arrived.
+    pub fn capture_time(&self, rtp_timestamp: u32) -> Option<SystemTime> {
+        self.sync_interceptor()?
+            .capture_time(self.ssrc(), rtp_timestamp)
+    }
+
+    /// cname returns the canonical name of the source of the track, from the RTCP source
+    /// descriptions of the remote peer
+    pub fn cname(&self) -> Option<String> {
+        self.sync_interceptor()?.cname(self.ssrc())
+    }
+
+    /// sync_offset returns the extra playout delay of this track and another track of the
+    /// same source, for example the audio and the video of a camera, that keeps them in lip
+    /// sync. The tracks are of the same source when they have the same CNAME, or the same
+    /// stream id when a CNAME is unknown. It assumes both tracks are played with the same
+    /// jitter buffer delay, the difference of their delays has to be added otherwise.
+    pub fn sync_offset(&self, other: &TrackRemote) -> Option<SyncOffset> {
+        let same_source = match (self.cname(), other.cname()) {
+            (Some(cname), Some(other_cname)) => cname == other_cname,
+            _ => self.stream_id() == other.stream_id(),
+        };
+        if !same_source {
+            return None;
+        }
+
+        self.sync_interceptor()?
+            .sync_offset(self.ssrc(), other.ssrc())
+    }
+
+    fn sync_interceptor(&self) -> Option<Arc<SyncInterceptor>> {
+        self.receiver.as_ref()?.upgrade()?.sync_interceptor()
+    }
+
     /// Reads data from the track.
     ///
     /// **Cancel Safety:** This method is not cancel safe. Dropping the resulting [`Future`] before
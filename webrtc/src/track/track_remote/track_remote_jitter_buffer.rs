@@ -0,0 +1,119 @@
+use super::TrackRemote;
+use crate::error::{Error, Result};
+
+use media::io::jitter_buffer::{JitterBuffer, JitterBufferStats};
+use media::Sample;
+use rtp::packetizer::Depacketizer;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use util::sync::Mutex as SyncMutex;
+
+struct JitterBufferInternal<T: Depacketizer> {
+    buffer: SyncMutex<JitterBuffer<T>>,
+    /// set when the reading of the track ended, with its error until it is returned
+    closed: SyncMutex<Option<Option<Error>>>,
+    notify: Notify,
+}
+
+/// TrackRemoteJitterBuffer reads the packets of a [`TrackRemote`] into a [`JitterBuffer`]
+/// and hands out its frames at their playout time. The packets are read in the background
+/// from the moment it is created, so the arrival times are accurate however the frames are
+/// consumed. Lost packets are waited for when the buffer is created
+/// [`with_nack`](JitterBuffer::with_nack) and the NACK generator interceptor is registered.
+pub struct TrackRemoteJitterBuffer<T: Depacketizer + Send + 'static> {
+    internal: Arc<JitterBufferInternal<T>>,
+    read_loop: JoinHandle<()>,
+}
+
+impl<T: Depacketizer + Send + 'static> TrackRemoteJitterBuffer<T> {
+    /// new starts reading the packets of the track into the jitter buffer
+    pub fn new(track: Arc<TrackRemote>, buffer: JitterBuffer<T>) -> Self {
+        let internal = Arc::new(JitterBufferInternal {
+            buffer: SyncMutex::new(buffer),
+            closed: SyncMutex::new(None),
+            notify: Notify::new(),
+        });
+
+        let internal2 = Arc::clone(&internal);
+        let read_loop = tokio::spawn(async move {
+            loop {
+                match track.read_rtp().await {
+                    Ok((packet, _)) => {
+                        let mut buffer = internal2.buffer.lock();
+                        buffer.push(packet, Instant::now().into_std());
+                    }
+                    Err(err) => {
+                        let mut closed = internal2.closed.lock();
+                        *closed = Some(Some(err));
+                        break;
+                    }
+                }
+                internal2.notify.notify_one();
+            }
+            internal2.notify.notify_one();
+        });
+
+        TrackRemoteJitterBuffer {
+            internal,
+            read_loop,
+        }
+    }
+
+    /// read_sample returns the next frame at its playout time. After the track is closed
+    /// the buffered frames are returned, then the error that ended the reading.
+    ///
+    /// **Cancel Safety:** This method is cancel safe, no frame is lost when the resulting
+    /// future is dropped.
+    pub async fn read_sample(&self) -> Result<Sample> {
+        loop {
+            let next_playout = {
+                let mut buffer = self.internal.buffer.lock();
+                if let Some(sample) = buffer.pop(Instant::now().into_std()) {
+                    return Ok(sample);
+                }
+                buffer.next_playout()
+            };
+
+            match next_playout {
+                Some(next_playout) => {
+                    // A new packet may change the next playout time.
+                    let _ = tokio::time::timeout_at(
+                        Instant::from_std(next_playout),
+                        self.internal.notify.notified(),
+                    )
+                    .await;
+                }
+                None => {
+                    {
+                        let mut closed = self.internal.closed.lock();
+                        if let Some(err) = closed.as_mut() {
+                            return Err(err.take().unwrap_or(Error::ErrClosedPipe));
+                        }
+                    }
+                    self.internal.notify.notified().await;
+                }
+            }
+        }
+    }
+
+    /// set_rtt updates the round trip time that the jitter buffer waits for retransmissions
+    pub fn set_rtt(&self, rtt: Duration) {
+        let mut buffer = self.internal.buffer.lock();
+        buffer.set_rtt(rtt);
+    }
+
+    /// stats returns the statistics of the jitter buffer
+    pub fn stats(&self) -> JitterBufferStats {
+        let buffer = self.internal.buffer.lock();
+        buffer.stats()
+    }
+}
+
+impl<T: Depacketizer + Send + 'static> Drop for TrackRemoteJitterBuffer<T> {
+    fn drop(&mut self) {
+        self.read_loop.abort();
+    }
+}
@@ -0,0 +1,220 @@
+use super::track_remote_jitter_buffer::*;
+use crate::api::interceptor_registry::register_default_interceptors;
+use crate::api::media_engine::{MediaEngine, MIME_TYPE_VP8};
+use crate::api::APIBuilder;
+use crate::error::Result;
+use crate::peer_connection::peer_connection_state::RTCPeerConnectionState;
+use crate::peer_connection::peer_connection_test::*;
+use crate::rtp_transceiver::rtp_codec::RTCRtpCodecCapability;
+use crate::track::track_local::track_local_static_rtp::TrackLocalStaticRTP;
+use crate::track::track_local::{TrackLocal, TrackLocalWriter};
+
+use bytes::Bytes;
+use interceptor::registry::Registry;
+use media::io::jitter_buffer::JitterBuffer;
+use rtp::codecs::vp8::Vp8Packet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::sync::mpsc;
+use tokio::time::{Duration, Instant};
+use waitgroup::WaitGroup;
+
+#[tokio::test]
+async fn test_track_remote_jitter_buffer() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (mut pc_offer, mut pc_answer) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticRTP::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+    pc_offer
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    let (samples_tx, mut samples_rx) = mpsc::channel(8);
+    pc_answer.on_track(Box::new(move |track, _, _| {
+        let samples_tx = samples_tx.clone();
+        Box::pin(async move {
+            let buffer = JitterBuffer::new(Vp8Packet::default(), 90000)
+                .unwrap()
+                .with_min_delay(Duration::from_millis(50));
+            let jitter_buffer = TrackRemoteJitterBuffer::new(track, buffer);
+            while let Ok(sample) = jitter_buffer.read_sample().await {
+                let stats = jitter_buffer.stats();
+                if samples_tx
+                    .send((sample, stats, Instant::now()))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        })
+    }));
+
+    let wg = WaitGroup::new();
+    until_connection_state(&mut pc_offer, &wg, RTCPeerConnectionState::Connected).await;
+    signal_pair(&mut pc_offer, &mut pc_answer).await?;
+    wg.wait().await;
+
+    // One single packet frame every 20ms
+    let done = Arc::new(AtomicBool::new(false));
+    let sender = {
+        let done = Arc::clone(&done);
+        tokio::spawn(async move {
+            let mut i = 0u8;
+            while !done.load(Ordering::SeqCst) {
+                let packet = rtp::packet::Packet {
+                    header: rtp::header::Header {
+                        version: 2,
+                        sequence_number: i as u16,
+                        timestamp: i as u32 * 1800,
+                        marker: true,
+                        ..Default::default()
+                    },
+                    payload: Bytes::from(vec![0x10, i, i, i]),
+                };
+                let _ = track.write_rtp(&packet).await;
+                i = i.wrapping_add(1);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+    };
+
+    let mut received = vec![];
+    while received.len() < 5 {
+        match tokio::time::timeout(Duration::from_secs(5), samples_rx.recv()).await {
+            Ok(Some(sample)) => received.push(sample),
+            _ => panic!("no samples were received"),
+        }
+    }
+    done.store(true, Ordering::SeqCst);
+    sender.await.unwrap();
+
+    for window in received.windows(2) {
+        let (previous, _, previous_time) = &window[0];
+        let (sample, stats, time) = &window[1];
+        assert_eq!(sample.data[0], previous.data[0].wrapping_add(1));
+        assert_eq!(
+            sample.packet_timestamp,
+            sample.data[0] as u32 * 1800,
+            "timestamp of frame {}",
+            sample.data[0]
+        );
+        assert!(stats.jitter_buffer_target_delay >= Duration::from_millis(50));
+        assert!(
+            time.duration_since(*previous_time) < Duration::from_millis(100),
+            "frames are played at their rate"
+        );
+    }
+    let (_, stats, _) = &received[4];
+    assert_eq!(stats.concealed_frames, 0);
+    assert!(stats.jitter_buffer_emitted_count >= 5);
+    assert!(stats.jitter_buffer_delay >= Duration::from_millis(5 * 40));
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_track_remote_capture_time() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let registry = register_default_interceptors(Registry::new(), &mut m)?;
+    let api = APIBuilder::new()
+        .with_media_engine(m)
+        .with_interceptor_registry(registry)
+        .build();
+
+    let (mut pc_offer, mut pc_answer) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticRTP::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+    pc_offer
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    let (captures_tx, mut captures_rx) = mpsc::channel(1);
+    pc_answer.on_track(Box::new(move |track, receiver, _| {
+        let captures_tx = captures_tx.clone();
+        // The sender reports are only handled while the RTCP is read
+        tokio::spawn(async move { while receiver.read_rtcp().await.is_ok() {} });
+        Box::pin(async move {
+            while let Ok((packet, _)) = track.read_rtp().await {
+                if let Some(capture_time) = track.capture_time(packet.header.timestamp) {
+                    let offset = track.sync_offset(&track);
+                    let _ = captures_tx
+                        .send((capture_time, SystemTime::now(), offset))
+                        .await;
+                    break;
+                }
+            }
+        })
+    }));
+
+    let wg = WaitGroup::new();
+    until_connection_state(&mut pc_offer, &wg, RTCPeerConnectionState::Connected).await;
+    signal_pair(&mut pc_offer, &mut pc_answer).await?;
+    wg.wait().await;
+
+    let done = Arc::new(AtomicBool::new(false));
+    let sender = {
+        let done = Arc::clone(&done);
+        tokio::spawn(async move {
+            let mut i = 0u32;
+            while !done.load(Ordering::SeqCst) {
+                let packet = rtp::packet::Packet {
+                    header: rtp::header::Header {
+                        version: 2,
+                        sequence_number: i as u16,
+                        timestamp: 1_000_000 + i * 1800,
+                        marker: true,
+                        ..Default::default()
+                    },
+                    payload: Bytes::from(vec![0x10, 0, 0, 0]),
+                };
+                let _ = track.write_rtp(&packet).await;
+                i += 1;
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+    };
+
+    let (capture_time, received_time, offset) =
+        match tokio::time::timeout(Duration::from_secs(10), captures_rx.recv()).await {
+            Ok(Some(capture)) => capture,
+            _ => panic!("no sender report was received"),
+        };
+    done.store(true, Ordering::SeqCst);
+    sender.await.unwrap();
+
+    // Both peers share the clock, the media is received shortly after its capture
+    let delay = received_time
+        .duration_since(capture_time)
+        .unwrap_or_else(|err| err.duration());
+    assert!(delay < Duration::from_millis(500), "delay of {:?}", delay);
+
+    let offset = offset.expect("a track is in sync with itself");
+    assert_eq!(offset.delay, Duration::ZERO);
+    assert_eq!(offset.other_delay, Duration::ZERO);
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+
+    Ok(())
+}